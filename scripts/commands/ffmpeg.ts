@@ -1,3 +1,5 @@
+import { join } from "node:path";
+
 import { type FfmpegProfile, profileBuildScript } from "../ffmpeg-profile.ts";
 import { type CommandContext, runCommand } from "../runtime.ts";
 import { runPrepare } from "../standalone.ts";
@@ -25,3 +27,34 @@ export async function runFfmpegBuildCommand(
   const script = profileBuildScript(options.profile);
   return runCommand(context, "bash", [script]);
 }
+
+/** Release tag whose `manifest.json` asset the app's stable update channel fetches. */
+const FFMPEG_CHANNEL_TAG = "ffmpeg-channel";
+
+export async function runFfmpegChannelCommand(context: CommandContext): Promise<number> {
+  // The app pins the same file at build time, so publish it only alongside a release that ships it.
+  const manifest = join(context.srcTauriDir, "ffmpeg-channel", "manifest.json");
+  const exists = await runCommand(context, "gh", ["release", "view", FFMPEG_CHANNEL_TAG], {
+    check: false,
+    stdio: "pipe",
+  });
+  if (exists !== 0) {
+    await runCommand(context, "gh", [
+      "release",
+      "create",
+      FFMPEG_CHANNEL_TAG,
+      "--title",
+      "FFmpeg update channel",
+      "--notes",
+      "Manifest of the stable FFmpeg update channel.",
+      "--latest=false",
+    ]);
+  }
+  return runCommand(context, "gh", [
+    "release",
+    "upload",
+    FFMPEG_CHANNEL_TAG,
+    manifest,
+    "--clobber",
+  ]);
+}
@@ -3,7 +3,11 @@ import { Command } from "commander";
 import { runBuildCommand } from "./commands/build.ts";
 import { runCleanBundleCommand } from "./commands/clean.ts";
 import { runDevCommand } from "./commands/dev.ts";
-import { runFfmpegBuildCommand, runFfmpegPrepareCommand } from "./commands/ffmpeg.ts";
+import {
+  runFfmpegBuildCommand,
+  runFfmpegChannelCommand,
+  runFfmpegPrepareCommand,
+} from "./commands/ffmpeg.ts";
 import { runIconCompileCommand } from "./commands/icon.ts";
 import { runTestMatrixCommand, runTestSuiteCommand } from "./commands/test.ts";
 import { type FfmpegProfile } from "./ffmpeg-profile.ts";
@@ -162,6 +166,13 @@ function createProgram(): Command {
             runFfmpegBuildCommand(ctx, { profile: opts.profile })
           )
         )
+    )
+    .addCommand(
+      new Command("channel")
+        .description("Publish the pinned FFmpeg update channel manifest")
+        .addOption(dryRunOption())
+        .addOption(verboseOption())
+        .action(withContextAction<CommonCliOptions>((ctx) => runFfmpegChannelCommand(ctx)))
     );
 
   program
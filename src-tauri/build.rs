@@ -12,8 +12,58 @@ fn main() {
             "get_video_metadata",
             "get_build_variant",
             "get_pending_opened_files",
-            "move_compressed_file",
-            "cleanup_temp_file",
+            "get_tunes",
+            "run_self_test",
+            "check_ffmpeg_integrity",
+            "repair_bundled_ffmpeg",
+            "check_ffmpeg_update",
+            "install_ffmpeg_update",
+            "rollback_ffmpeg_update",
+            "get_encoder_health",
+            "repair_file",
+            "generate_hover_preview",
+            "export_contact_sheet",
+            "get_webhook_config",
+            "set_webhook_config",
+            "get_locale",
+            "set_locale",
+            "compose_picture_in_picture",
+            "analyze_silence",
+            "analyze_dead_frames",
+            "suggest_options",
+            "resolve_batch_jobs",
+            "run_batch",
+            "analyze_batch",
+            "get_last_batch_manifest",
+            "compute_output_checksum",
+            "queue_add",
+            "queue_remove",
+            "queue_reorder",
+            "queue_list",
+            "queue_start",
+            "commit_temp_output",
+            "discard_temp_output",
+            "commit_batch_outputs",
+            "suggest_quality",
+            "get_video_thumbnail",
+            "generate_storyboard",
+            "get_progress_throttle",
+            "set_progress_throttle",
+            "get_live_stderr",
+            "get_speed_preset_tunings",
+            "quick_compress",
+            "list_presets",
+            "save_preset",
+            "delete_preset",
+            "export_presets",
+            "import_presets",
+            "add_watch_folder",
+            "remove_watch_folder",
+            "list_watch_folders",
+            "get_estimate_sampling",
+            "set_estimate_sampling",
+            "ffmpeg_cancel_job",
+            "ffmpeg_cancel_request",
         ]),
     ))
     .expect("failed to run tauri build");
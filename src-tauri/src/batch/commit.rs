@@ -0,0 +1,320 @@
+//! All-or-nothing delivery of a set of outputs (e.g. the temp results of a batch).
+//!
+//! Each output is first moved next to its destination under a hidden staging name, then every
+//! staged file is renamed over its destination, keeping any file it replaces as a backup until
+//! the whole set is in place. The first failure undoes both steps in reverse order: overwritten
+//! destinations are restored, the outputs go back where they came from and folders made for
+//! them are removed. The final renames stay within one folder, so only a crash between two of
+//! them can leave a partial set.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::collision_key;
+use crate::commands::move_file;
+use crate::error::AppError;
+use crate::ffmpeg::ensure_not_job_source;
+use crate::source_guard::ensure_not_source;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCommitItem {
+    /// Finished output, usually a temp file.
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    /// Input the output was made from; never overwritten.
+    #[serde(default)]
+    pub original_path: Option<PathBuf>,
+}
+
+/// One output on its way to `dest`.
+struct Delivery {
+    source: PathBuf,
+    staged: PathBuf,
+    dest: PathBuf,
+    /// Previous file at `dest`, moved aside while the set is published.
+    backup: Option<PathBuf>,
+    published: bool,
+}
+
+/// Hidden file next to `dest`, e.g. `.clip.mp4.tiny-vid-staging`.
+fn sibling(dest: &Path, tag: &str) -> PathBuf {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dest.with_file_name(format!(".{}.tiny-vid-{}", name, tag))
+}
+
+fn validate(items: &[BatchCommitItem]) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for item in items {
+        if let Some(original) = &item.original_path {
+            ensure_not_source(original, &item.dest)?;
+        }
+        ensure_not_job_source(&item.source)?;
+        ensure_not_job_source(&item.dest)?;
+        if !item.source.is_file() {
+            return Err(AppError::from(format!(
+                "Output not found: {}",
+                item.source.display()
+            )));
+        }
+        if item.dest.file_name().is_none() || item.dest.is_dir() {
+            return Err(AppError::from(format!(
+                "Not a file destination: {}",
+                item.dest.display()
+            )));
+        }
+        if !seen.insert(collision_key(&item.dest)) {
+            return Err(AppError::from(format!(
+                "Two outputs share the destination {}",
+                item.dest.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Creates the missing folders above `dest`, outermost first, adding each one to `created`.
+fn create_parent(dest: &Path, created: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let Some(dir) = dest.parent() else {
+        return Ok(());
+    };
+    let missing: Vec<&Path> = dir
+        .ancestors()
+        .take_while(|d| !d.as_os_str().is_empty() && !d.exists())
+        .collect();
+    for dir in missing.into_iter().rev() {
+        match fs::create_dir(dir) {
+            Ok(()) => created.push(dir.to_path_buf()),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+fn stage(item: &BatchCommitItem, created: &mut Vec<PathBuf>) -> Result<Delivery, AppError> {
+    create_parent(&item.dest, created)?;
+    let staged = sibling(&item.dest, "staging");
+    move_file(&item.source, &staged)?;
+    Ok(Delivery {
+        source: item.source.clone(),
+        staged,
+        dest: item.dest.clone(),
+        backup: None,
+        published: false,
+    })
+}
+
+fn publish(delivery: &mut Delivery) -> Result<(), AppError> {
+    if delivery.dest.exists() {
+        let backup = sibling(&delivery.dest, "backup");
+        fs::rename(&delivery.dest, &backup)?;
+        delivery.backup = Some(backup);
+    }
+    fs::rename(&delivery.staged, &delivery.dest)?;
+    delivery.published = true;
+    Ok(())
+}
+
+/// Undoes `deliveries` last to first, then removes the `created` folders in reverse order, so
+/// the deepest go first. Failures are logged; the rest are still rolled back.
+fn roll_back(deliveries: Vec<Delivery>, created: Vec<PathBuf>) {
+    for delivery in deliveries.into_iter().rev() {
+        let undo = || -> Result<(), AppError> {
+            if delivery.published {
+                fs::rename(&delivery.dest, &delivery.staged)?;
+            }
+            if let Some(backup) = &delivery.backup {
+                fs::rename(backup, &delivery.dest)?;
+            }
+            move_file(&delivery.staged, &delivery.source)
+        };
+        if let Err(e) = undo() {
+            log::error!(
+                target: "tiny_vid::batch",
+                "roll_back: could not restore {} (staged at {}): {}",
+                delivery.source.display(),
+                delivery.staged.display(),
+                e
+            );
+        }
+    }
+    for dir in created.into_iter().rev() {
+        if let Err(e) = fs::remove_dir(&dir) {
+            log::warn!(
+                target: "tiny_vid::batch",
+                "roll_back: could not remove folder {}: {}",
+                dir.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Moves every item to its destination, or none of them. Returns the destinations in order.
+pub(crate) fn commit_outputs(items: &[BatchCommitItem]) -> Result<Vec<PathBuf>, AppError> {
+    validate(items)?;
+    let mut deliveries = Vec::with_capacity(items.len());
+    let mut created = Vec::new();
+    for item in items {
+        match stage(item, &mut created) {
+            Ok(delivery) => deliveries.push(delivery),
+            Err(e) => {
+                log::warn!(
+                    target: "tiny_vid::batch",
+                    "commit_outputs: staging {} failed, rolling back: {}",
+                    item.dest.display(),
+                    e
+                );
+                roll_back(deliveries, created);
+                return Err(e);
+            }
+        }
+    }
+    for i in 0..deliveries.len() {
+        if let Err(e) = publish(&mut deliveries[i]) {
+            log::warn!(
+                target: "tiny_vid::batch",
+                "commit_outputs: publishing {} failed, rolling back: {}",
+                deliveries[i].dest.display(),
+                e
+            );
+            roll_back(deliveries, created);
+            return Err(e);
+        }
+    }
+    for backup in deliveries.iter().filter_map(|d| d.backup.as_ref()) {
+        let _ = fs::remove_file(backup);
+    }
+    log::info!(
+        target: "tiny_vid::batch",
+        "commit_outputs: {} output(s) delivered",
+        deliveries.len()
+    );
+    Ok(deliveries.into_iter().map(|d| d.dest).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(source: PathBuf, dest: PathBuf) -> BatchCommitItem {
+        BatchCommitItem {
+            source,
+            dest,
+            original_path: None,
+        }
+    }
+
+    fn output(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn delivers_every_output_and_replaces_existing_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let a = output(temp.path(), "a.mp4", b"a");
+        let b = output(temp.path(), "b.mp4", b"b");
+        output(out.path(), "b.mp4", b"old");
+
+        let items = [
+            item(a.clone(), out.path().join("nested/a.mp4")),
+            item(b.clone(), out.path().join("b.mp4")),
+        ];
+        let written = commit_outputs(&items).unwrap();
+
+        assert_eq!(written.len(), 2);
+        assert_eq!(fs::read(out.path().join("nested/a.mp4")).unwrap(), b"a");
+        assert_eq!(fs::read(out.path().join("b.mp4")).unwrap(), b"b");
+        assert!(!a.exists() && !b.exists());
+        let names: Vec<_> = fs::read_dir(out.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            names.iter().all(|n| !n.contains("tiny-vid-")),
+            "{:?}",
+            names
+        );
+    }
+
+    #[test]
+    fn failed_staging_returns_outputs_and_writes_nothing() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let a = output(temp.path(), "a.mp4", b"a");
+        let b = output(temp.path(), "b.mp4", b"b");
+        // A file where the second destination's folder should be.
+        output(out.path(), "blocked", b"");
+
+        let items = [
+            item(a.clone(), out.path().join("new/deeper/a.mp4")),
+            item(b.clone(), out.path().join("blocked/b.mp4")),
+        ];
+        assert!(commit_outputs(&items).is_err());
+
+        assert_eq!(fs::read(&a).unwrap(), b"a");
+        assert_eq!(fs::read(&b).unwrap(), b"b");
+        let names: Vec<_> = fs::read_dir(out.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["blocked"]);
+    }
+
+    #[test]
+    fn rollback_restores_overwritten_destinations() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let a = output(temp.path(), "a.mp4", b"a");
+        let dest = output(out.path(), "a.mp4", b"old");
+        let mut created = Vec::new();
+        let mut delivery = stage(&item(a.clone(), dest.clone()), &mut created).unwrap();
+        publish(&mut delivery).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"a");
+        assert!(created.is_empty());
+
+        roll_back(vec![delivery], created);
+
+        assert_eq!(fs::read(&dest).unwrap(), b"old");
+        assert_eq!(fs::read(&a).unwrap(), b"a");
+        assert_eq!(fs::read_dir(out.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn refuses_job_sources_without_original_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = output(temp.path(), "clip.mov", b"source");
+        let a = output(temp.path(), "a.mp4", b"a");
+        crate::ffmpeg::record_job_sources(&[source.as_path()]);
+
+        assert!(commit_outputs(&[item(a.clone(), source.clone())]).is_err());
+        let moved = temp.path().join("moved.mov");
+        assert!(commit_outputs(&[item(source.clone(), moved.clone())]).is_err());
+        assert_eq!(fs::read(&source).unwrap(), b"source");
+        assert!(a.exists() && !moved.exists());
+    }
+
+    #[test]
+    fn rejects_missing_outputs_and_shared_destinations() {
+        let temp = tempfile::tempdir().unwrap();
+        let a = output(temp.path(), "a.mp4", b"a");
+        let b = output(temp.path(), "b.mp4", b"b");
+        let dest = temp.path().join("out.mp4");
+
+        let shared = [item(a.clone(), dest.clone()), item(b.clone(), dest.clone())];
+        assert!(commit_outputs(&shared).is_err());
+        let missing = [item(temp.path().join("gone.mp4"), dest.clone())];
+        assert!(commit_outputs(&missing).is_err());
+        assert!(a.exists() && b.exists() && !dest.exists());
+    }
+}
@@ -0,0 +1,351 @@
+//! Post-batch manifest: one row per file (sizes, ratio, codec, duration, verify status) for
+//! teams that need an audit trail of what was compressed.
+//!
+//! The manifest of the last batch is kept in memory for `get_last_batch_manifest`; writing it
+//! to disk as JSON or CSV is optional.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::{BatchJobResult, BatchOutputLayout};
+use crate::error::AppError;
+use crate::ffmpeg::verify::verify_video;
+use crate::ffmpeg::{FfmpegContext, path_to_string};
+
+const MANIFEST_FILE_STEM: &str = "tiny-vid-manifest";
+
+static LAST_MANIFEST: Mutex<Option<BatchManifest>> = Mutex::new(None);
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ManifestFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Manifest file settings for `run_batch`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchManifestConfig {
+    #[serde(default)]
+    pub format: ManifestFormat,
+    /// Default: `tiny-vid-manifest.<ext>` in the layout's output root.
+    pub path: Option<PathBuf>,
+    /// Decode every output to check it before it is listed. Default false.
+    #[serde(default)]
+    pub verify: bool,
+}
+
+impl BatchManifestConfig {
+    /// Where the manifest is written. Errors before any encoding when there is nowhere to put it.
+    pub(crate) fn resolve_path(
+        &self,
+        layout: Option<&BatchOutputLayout>,
+    ) -> Result<PathBuf, AppError> {
+        if let Some(path) = &self.path {
+            return Ok(path.clone());
+        }
+        layout
+            .map(|l| {
+                l.output_root.join(format!(
+                    "{}.{}",
+                    MANIFEST_FILE_STEM,
+                    self.format.extension()
+                ))
+            })
+            .ok_or_else(|| AppError::from("Batch manifest needs a path or an output root"))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ManifestStatus {
+    Encoded,
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum VerifyStatus {
+    NotRun,
+    Passed,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub input_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    pub status: ManifestStatus,
+    pub input_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_bytes: Option<u64>,
+    /// Output size / input size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+    pub codec: String,
+    /// Source duration in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encode_seconds: Option<f64>,
+    pub verify: VerifyStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Source size and modification time unchanged by the job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_untouched: Option<bool>,
+    /// Encode error, skip reason or verify error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchManifest {
+    /// Unix seconds.
+    pub created_at: u64,
+    pub entries: Vec<ManifestEntry>,
+    pub total_input_bytes: u64,
+    pub total_output_bytes: u64,
+    /// Set once the manifest has been written to disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+fn entry_for(result: &BatchJobResult) -> ManifestEntry {
+    let size = |p: &str| fs::metadata(p).map(|m| m.len()).ok();
+    let input_bytes = size(&result.job.input_path).unwrap_or(0);
+    let output_bytes = result.written_path.as_deref().and_then(size);
+    let (status, note) = match (&result.job.skipped, &result.error) {
+        (Some(reason), _) => (ManifestStatus::Skipped, Some(reason.describe())),
+        (None, Some(error)) => (ManifestStatus::Failed, Some(error.clone())),
+        (None, None) => (ManifestStatus::Encoded, None),
+    };
+    ManifestEntry {
+        input_path: result.job.input_path.clone(),
+        output_path: result.written_path.clone(),
+        status,
+        input_bytes,
+        output_bytes,
+        ratio: output_bytes
+            .filter(|_| input_bytes > 0)
+            .map(|out| out as f64 / input_bytes as f64),
+        codec: result.job.options.effective_codec().to_string(),
+        duration_secs: result.job.options.duration_secs,
+        encode_seconds: result.encode_seconds,
+        verify: VerifyStatus::NotRun,
+        sha256: result.checksum.as_ref().map(|c| c.sha256.clone()),
+        source_untouched: result.source_untouched,
+        note,
+    }
+}
+
+/// Builds the manifest for `results`, decoding each output under `verify` when it is set.
+/// Blocking.
+pub(crate) fn build_manifest(
+    results: &[BatchJobResult],
+    verify: Option<&FfmpegContext>,
+) -> BatchManifest {
+    let entries: Vec<ManifestEntry> = results
+        .iter()
+        .map(|result| {
+            let mut entry = entry_for(result);
+            if let Some(ctx) = verify
+                && entry.status == ManifestStatus::Encoded
+                && let Some(output) = &entry.output_path
+            {
+                match verify_video(ctx, Path::new(output), Some(&entry.codec)) {
+                    Ok(()) => entry.verify = VerifyStatus::Passed,
+                    Err(e) => {
+                        entry.verify = VerifyStatus::Failed;
+                        entry.note = Some(e);
+                    }
+                }
+            }
+            entry
+        })
+        .collect();
+    BatchManifest {
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        total_input_bytes: entries.iter().map(|e| e.input_bytes).sum(),
+        total_output_bytes: entries.iter().filter_map(|e| e.output_bytes).sum(),
+        entries,
+        path: None,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(manifest: &BatchManifest) -> String {
+    let mut out = String::from(
+        "input_path,output_path,status,input_bytes,output_bytes,ratio,codec,duration_secs,encode_seconds,verify,sha256,source_untouched,note\n",
+    );
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    for e in &manifest.entries {
+        let row = [
+            e.input_path.clone(),
+            opt(e.output_path.clone()),
+            format!("{:?}", e.status).to_lowercase(),
+            e.input_bytes.to_string(),
+            opt(e.output_bytes.map(|b| b.to_string())),
+            opt(e.ratio.map(|r| format!("{:.4}", r))),
+            e.codec.clone(),
+            opt(e.duration_secs.map(|d| format!("{:.3}", d))),
+            opt(e.encode_seconds.map(|d| format!("{:.3}", d))),
+            match e.verify {
+                VerifyStatus::NotRun => String::new(),
+                VerifyStatus::Passed => "passed".to_string(),
+                VerifyStatus::Failed => "failed".to_string(),
+            },
+            opt(e.sha256.clone()),
+            opt(e.source_untouched.map(|u| u.to_string())),
+            opt(e.note.clone()),
+        ];
+        let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes `manifest` to `path` in `format` and records the path on it.
+pub(crate) fn write_manifest(
+    manifest: &mut BatchManifest,
+    path: &Path,
+    format: ManifestFormat,
+) -> Result<(), AppError> {
+    let data = match format {
+        ManifestFormat::Json => {
+            serde_json::to_vec_pretty(manifest).map_err(|e| AppError::from(e.to_string()))?
+        }
+        ManifestFormat::Csv => to_csv(manifest).into_bytes(),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, data)?;
+    manifest.path = Some(path_to_string(path));
+    log::info!(
+        target: "tiny_vid::batch",
+        "write_manifest: {} entries -> {}",
+        manifest.entries.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+pub(crate) fn set_last_manifest(manifest: BatchManifest) {
+    *LAST_MANIFEST.lock() = Some(manifest);
+}
+
+pub fn last_manifest() -> Option<BatchManifest> {
+    LAST_MANIFEST.lock().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(input: &str, status: ManifestStatus) -> ManifestEntry {
+        ManifestEntry {
+            input_path: input.to_string(),
+            output_path: Some("/out/a.mp4".to_string()),
+            status,
+            input_bytes: 2000,
+            output_bytes: Some(500),
+            ratio: Some(0.25),
+            codec: "libx264".to_string(),
+            duration_secs: Some(12.5),
+            encode_seconds: Some(3.0),
+            verify: VerifyStatus::Passed,
+            sha256: None,
+            source_untouched: Some(true),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn csv_escapes_fields_and_writes_one_row_per_entry() {
+        let mut failed = entry("/in/b, \"final\".mov", ManifestStatus::Failed);
+        failed.output_path = None;
+        failed.verify = VerifyStatus::NotRun;
+        failed.note = Some("Encoder failed".to_string());
+        let manifest = BatchManifest {
+            created_at: 0,
+            entries: vec![entry("/in/a.mov", ManifestStatus::Encoded), failed],
+            total_input_bytes: 4000,
+            total_output_bytes: 500,
+            path: None,
+        };
+        let csv = to_csv(&manifest);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "/in/a.mov,/out/a.mp4,encoded,2000,500,0.2500,libx264,12.500,3.000,passed,,true,"
+        );
+        assert!(lines[2].starts_with("\"/in/b, \"\"final\"\".mov\",,failed,"));
+        assert!(lines[2].ends_with(",,true,Encoder failed"));
+    }
+
+    #[test]
+    fn manifest_path_defaults_to_output_root() {
+        let layout = BatchOutputLayout {
+            output_root: PathBuf::from("/out"),
+            source_root: None,
+            overwrite: false,
+        };
+        let config = BatchManifestConfig {
+            format: ManifestFormat::Csv,
+            ..BatchManifestConfig::default()
+        };
+        assert_eq!(
+            config.resolve_path(Some(&layout)).unwrap(),
+            PathBuf::from("/out/tiny-vid-manifest.csv")
+        );
+        assert!(config.resolve_path(None).is_err());
+    }
+
+    #[test]
+    fn write_manifest_records_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports/manifest.json");
+        let mut manifest = BatchManifest {
+            created_at: 1,
+            entries: vec![entry("/in/a.mov", ManifestStatus::Encoded)],
+            total_input_bytes: 2000,
+            total_output_bytes: 500,
+            path: None,
+        };
+        write_manifest(&mut manifest, &path, ManifestFormat::Json).unwrap();
+        assert_eq!(manifest.path, Some(path_to_string(&path)));
+        let written: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["entries"][0]["ratio"], 0.25);
+    }
+}
@@ -0,0 +1,1095 @@
+//! Batch transcodes: one base option set (the preset) applied to many files, each with optional
+//! per-file overrides (e.g. one file needs its audio removed).
+//!
+//! Effective options are layered `base <- probed metadata <- overrides`: metadata fields such as
+//! duration and stream counts describe each file, so they replace whatever the base carried, and
+//! an override can still replace anything. The merged options are echoed back per job.
+//!
+//! Skip rules are checked against the same probe before anything is encoded. A dry run
+//! (`analyze_batch_jobs`) predicts each output size with the sampled estimator instead.
+//!
+//! With a `BatchOutputLayout`, destinations mirror the input tree under an output root and
+//! name collisions are numbered in job order, so a batch never needs per-file save dialogs.
+//! Each run leaves a manifest (see `manifest`). Outputs left in temp can be delivered as one
+//! set with `commit::commit_outputs`.
+
+pub mod commit;
+pub mod manifest;
+
+use manifest::BatchManifestConfig;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use tauri::Emitter;
+
+use crate::checksum::{ChecksumOptions, OutputChecksum, checksum_output};
+use crate::error::AppError;
+use crate::events::{JobKind, JobStarted, Versioned};
+use crate::ffmpeg::compression::{CompressionMetrics, LargerOutputWarning};
+use crate::ffmpeg::ffprobe::{VideoMetadata, get_video_metadata_in};
+use crate::ffmpeg::quality::VmafScore;
+use crate::ffmpeg::{
+    CpuMeter, FfmpegContext, FfmpegProgressPayload, JobCancellation, JobEnergy, LiveStderr,
+    ProgressSink, ProgressStep, SizeEstimate, TempJob, TranscodeOptions, path_to_string,
+    register_transcode_temp,
+};
+use crate::size_format::SizeSummary;
+use crate::source_guard::{SourceFingerprint, ensure_not_source};
+
+/// One input file and the option fields that differ from the batch base (camelCase keys,
+/// same shape as `TranscodeOptions`).
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchFile {
+    pub input_path: PathBuf,
+    #[serde(default)]
+    pub overrides: Map<String, Value>,
+    /// Final destination. Takes precedence over the batch output layout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<PathBuf>,
+}
+
+/// Batch-wide settings shared by resolve, dry run and run. Everything is optional.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSettings {
+    #[serde(default)]
+    pub rules: BatchSkipRules,
+    pub layout: Option<BatchOutputLayout>,
+    /// Run only: write the manifest to disk.
+    pub manifest: Option<BatchManifestConfig>,
+    /// Run only: hash every written output.
+    pub checksum: Option<ChecksumOptions>,
+}
+
+/// Where batch outputs go when files do not name their own `output_path`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOutputLayout {
+    /// Outputs land here, in the same relative folders as under `source_root`.
+    pub output_root: PathBuf,
+    /// Root of the input tree. Default: the deepest folder containing every input.
+    pub source_root: Option<PathBuf>,
+    /// Reuse names of files already on disk instead of numbering around them. Default false.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Deepest folder containing every path in `paths`.
+pub(crate) fn common_parent<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut parents = paths.into_iter().filter_map(Path::parent);
+    let mut common = parents.next()?.to_path_buf();
+    for parent in parents {
+        while !parent.starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common)
+}
+
+/// Case-insensitive, so `A.mp4` and `a.mp4` collide on every filesystem.
+fn collision_key(path: &Path) -> String {
+    path_to_string(path).to_lowercase()
+}
+
+/// `path`, or the first free `stem (N).ext` next to it.
+pub(crate) fn unique_path(path: PathBuf, taken: &HashSet<String>, avoid_existing: bool) -> PathBuf {
+    let is_taken = |p: &Path| taken.contains(&collision_key(p)) || (avoid_existing && p.exists());
+    if !is_taken(&path) {
+        return path;
+    }
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut n = 1;
+    loop {
+        let candidate = dir.join(format!("{} ({}).{}", stem, n, ext));
+        if !is_taken(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+impl BatchOutputLayout {
+    /// Destination for `input` before collision handling: same relative folder under
+    /// `output_root`, same stem, extension of the output format. Inputs outside `source_root`
+    /// go straight into `output_root`.
+    fn mirrored_path(&self, source_root: &Path, input: &Path, ext: &str) -> PathBuf {
+        let relative_dir = input
+            .strip_prefix(source_root)
+            .ok()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        let stem = input
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "output".to_string());
+        self.output_root
+            .join(relative_dir)
+            .join(format!("{}.{}", stem, ext))
+    }
+
+    /// Fills `output_path` of jobs that have none, skipping jobs already skipped. Inputs,
+    /// explicit outputs and destinations in `taken` are never reused; with `avoid_existing`,
+    /// neither are files on disk. Every destination of `jobs` is added to `taken`.
+    fn assign(
+        &self,
+        jobs: &mut [ResolvedBatchJob],
+        avoid_existing: bool,
+        taken: &mut HashSet<String>,
+    ) {
+        let source_root = self
+            .source_root
+            .clone()
+            .or_else(|| common_parent(jobs.iter().map(|j| Path::new(&j.input_path))))
+            .unwrap_or_default();
+        taken.extend(
+            jobs.iter()
+                .flat_map(|j| std::iter::once(&j.input_path).chain(j.output_path.as_ref()))
+                .map(|p| collision_key(Path::new(p))),
+        );
+        for job in jobs
+            .iter_mut()
+            .filter(|j| j.output_path.is_none() && j.skipped.is_none())
+        {
+            let ext = job.options.effective_output_format();
+            let mirrored = self.mirrored_path(&source_root, Path::new(&job.input_path), &ext);
+            let path = unique_path(mirrored, taken, avoid_existing);
+            taken.insert(collision_key(&path));
+            job.output_path = Some(path_to_string(&path));
+        }
+    }
+}
+
+/// Files matching any rule are reported as skipped and not encoded.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSkipRules {
+    /// Skip inputs already at or under this size.
+    pub max_size_mb: Option<f64>,
+    /// Skip inputs whose video codec is one of these (`hevc`, `h265`, `av1`, ...).
+    #[serde(default)]
+    pub skip_codecs: Vec<String>,
+    /// Skip files whose destination already exists.
+    #[serde(default)]
+    pub skip_existing_outputs: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum SkipReason {
+    #[serde(rename_all = "camelCase")]
+    UnderSize {
+        size_mb: f64,
+        max_size_mb: f64,
+    },
+    Codec {
+        codec: String,
+    },
+    OutputExists {
+        path: String,
+    },
+}
+
+impl SkipReason {
+    /// Short human-readable reason, e.g. for the batch manifest.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::UnderSize {
+                size_mb,
+                max_size_mb,
+            } => format!("{:.1} MB, at or under {} MB", size_mb, max_size_mb),
+            Self::Codec { codec } => format!("already {}", codec),
+            Self::OutputExists { path } => format!("output exists: {}", path),
+        }
+    }
+}
+
+/// ffprobe codec name for common user-facing aliases.
+fn normalize_codec(name: &str) -> String {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "h265" | "h.265" | "x265" | "libx265" | "hevc" => "hevc".to_string(),
+        "h264" | "h.264" | "x264" | "libx264" | "avc" => "h264".to_string(),
+        "libsvtav1" | "libaom-av1" | "av1" => "av1".to_string(),
+        "libvpx-vp9" | "vp9" => "vp9".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl BatchSkipRules {
+    /// First source rule (size, codec) the probed file matches, if any.
+    pub(crate) fn evaluate_source(&self, meta: &VideoMetadata) -> Option<SkipReason> {
+        let size_mb = meta.size as f64 / (1024.0 * 1024.0);
+        if let Some(max_size_mb) = self.max_size_mb
+            && meta.size > 0
+            && size_mb <= max_size_mb
+        {
+            return Some(SkipReason::UnderSize {
+                size_mb,
+                max_size_mb,
+            });
+        }
+        if let Some(codec) = meta.codec_name.as_deref().map(normalize_codec)
+            && self.skip_codecs.iter().any(|c| normalize_codec(c) == codec)
+        {
+            return Some(SkipReason::Codec { codec });
+        }
+        None
+    }
+
+    /// `OutputExists` when the rule is on and `output` is already on disk.
+    pub(crate) fn evaluate_output(&self, output: Option<&str>) -> Option<SkipReason> {
+        let output = output.filter(|p| self.skip_existing_outputs && Path::new(p).exists())?;
+        Some(SkipReason::OutputExists {
+            path: output.to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedBatchJob {
+    pub index: usize,
+    pub input_path: String,
+    /// Effective options for this file.
+    pub options: TranscodeOptions,
+    /// Option keys taken from the file's overrides rather than the base.
+    pub overridden: Vec<String>,
+    /// Final destination (explicit or from the output layout). None: output stays in temp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<SkipReason>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchJobResult {
+    #[serde(flatten)]
+    pub job: ResolvedBatchJob,
+    /// Where the encoded file ended up: the job's destination, or the temp file behind
+    /// `commit_token` when it has none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub written_path: Option<String>,
+    /// Token for `commit_temp_output` / `discard_temp_output` when the output was left in temp.
+    /// Such outputs can also be delivered together with `commit_batch_outputs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Wall time of the encode; None for skipped jobs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encode_seconds: Option<f64>,
+    /// Hash of `written_path`, when the batch ran with a checksum step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<OutputChecksum>,
+    /// Source size and modification time unchanged by the job. None for skipped jobs or when
+    /// the source could not be read beforehand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_untouched: Option<bool>,
+    /// Source against written size, formatted. None unless the job succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_summary: Option<SizeSummary>,
+    /// Compression ratio and bits per pixel of the source and the written file. None unless
+    /// the job succeeded and the source could be probed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionMetrics>,
+    /// Set when the written file is not smaller than the source (see `larger_output` in the
+    /// job's options).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub larger_output: Option<LargerOutputWarning>,
+    /// Set with `vmaf_check` in the job's options when the written file could be scored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vmaf: Option<VmafScore>,
+    /// CPU time and energy estimate of the encode; None for skipped jobs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy: Option<JobEnergy>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReportEntry {
+    #[serde(flatten)]
+    pub job: ResolvedBatchJob,
+    pub input_size: u64,
+    /// None for skipped files and when estimation failed (see `error`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SizeEstimate>,
+    /// Input against the estimate's best guess, formatted. Set with `estimate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_summary: Option<SizeSummary>,
+    /// Compression ratio and bits per pixel against the estimate's best guess.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionMetrics>,
+    /// Set when the estimate's best guess is not smaller than the input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub larger_output: Option<LargerOutputWarning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Dry-run summary. Totals cover the files that would be encoded and have an estimate.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub entries: Vec<BatchReportEntry>,
+    /// All inputs, skipped ones included.
+    pub total_input_bytes: u64,
+    pub encoded_input_bytes: u64,
+    pub predicted_output_bytes: u64,
+    pub predicted_low_bytes: u64,
+    pub predicted_high_bytes: u64,
+    pub skipped_count: usize,
+    pub failed_count: usize,
+    /// `encoded_input_bytes` against `predicted_output_bytes`, formatted. None when nothing
+    /// has an estimate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_summary: Option<SizeSummary>,
+}
+
+impl BatchReport {
+    fn push(&mut self, entry: BatchReportEntry) {
+        self.total_input_bytes += entry.input_size;
+        if entry.job.skipped.is_some() {
+            self.skipped_count += 1;
+        } else if let Some(estimate) = &entry.estimate {
+            self.encoded_input_bytes += entry.input_size;
+            self.predicted_output_bytes += estimate.best_size;
+            self.predicted_low_bytes += estimate.low_size;
+            self.predicted_high_bytes += estimate.high_size;
+        } else {
+            self.failed_count += 1;
+        }
+        self.entries.push(entry);
+    }
+}
+
+/// Set fields of `options` as a JSON object (unset fields omitted).
+fn to_object(options: &TranscodeOptions) -> Result<Map<String, Value>, AppError> {
+    match serde_json::to_value(options).map_err(|e| AppError::from(e.to_string()))? {
+        Value::Object(map) => Ok(map.into_iter().filter(|(_, v)| !v.is_null()).collect()),
+        _ => Err(AppError::from(
+            "Transcode options did not serialize to an object",
+        )),
+    }
+}
+
+/// Per-file fields that come from probing rather than from the preset.
+fn metadata_fields(meta: &VideoMetadata, base: &TranscodeOptions) -> Map<String, Value> {
+    let mut fields = json!({
+        "durationSecs": meta.duration,
+        "audioStreamCount": meta.audio_stream_count,
+        "subtitleStreamCount": meta.subtitle_stream_count,
+        "audioCodecName": meta.audio_codec_name,
+        "audioChannels": meta.audio_channels,
+        "sourceAudioBitRate": meta.audio_bit_rate,
+        "videoStreamCount": meta.video_stream_count,
+        "videoStreamIndex": meta.main_video_stream_index,
+        "sourceFps": meta.fps,
+    });
+    if base.content_type.is_none() && meta.looks_like_screen_recording() {
+        fields["contentType"] = json!("screenRecording");
+    }
+    match fields {
+        Value::Object(map) => map.into_iter().filter(|(_, v)| !v.is_null()).collect(),
+        _ => Map::new(),
+    }
+}
+
+/// Layers `layers` over `base`; a `null` in a layer clears the field.
+pub(crate) fn merge_options(
+    base: &TranscodeOptions,
+    layers: &[&Map<String, Value>],
+) -> Result<TranscodeOptions, AppError> {
+    let mut merged = to_object(base)?;
+    for layer in layers {
+        for (key, value) in layer.iter() {
+            if value.is_null() {
+                merged.remove(key);
+            } else {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    serde_json::from_value(Value::Object(merged))
+        .map_err(|e| AppError::from(format!("Invalid batch option override: {}", e)))
+}
+
+fn resolve_job(
+    index: usize,
+    base: &TranscodeOptions,
+    file: &BatchFile,
+    meta: &VideoMetadata,
+    rules: &BatchSkipRules,
+) -> Result<ResolvedBatchJob, AppError> {
+    let fields = metadata_fields(meta, base);
+    let options = merge_options(base, &[&fields, &file.overrides])?;
+    let mut overridden: Vec<String> = file.overrides.keys().cloned().collect();
+    overridden.sort();
+    Ok(ResolvedBatchJob {
+        index,
+        input_path: path_to_string(&file.input_path),
+        options,
+        overridden,
+        output_path: file.output_path.as_deref().map(path_to_string),
+        skipped: rules.evaluate_source(meta),
+    })
+}
+
+/// Probes every file with `ffmpeg`, merges its effective options, plans destinations with the
+/// layout and applies the skip rules. Blocking. Fails on the first file that cannot be probed or
+/// whose overrides do not parse, naming the file.
+pub fn resolve_batch_jobs(
+    ffmpeg: &FfmpegContext,
+    base: &TranscodeOptions,
+    files: &[BatchFile],
+    settings: &BatchSettings,
+) -> Result<Vec<ResolvedBatchJob>, AppError> {
+    resolve_batch_jobs_avoiding(ffmpeg, base, files, settings, &mut HashSet::new())
+}
+
+/// [`resolve_batch_jobs`] that never plans a destination in `taken` and adds the ones it plans.
+/// The queue resolves one file at a time and passes the same set for the whole run, so two
+/// items never land on one destination.
+pub(crate) fn resolve_batch_jobs_avoiding(
+    ffmpeg: &FfmpegContext,
+    base: &TranscodeOptions,
+    files: &[BatchFile],
+    settings: &BatchSettings,
+    taken: &mut HashSet<String>,
+) -> Result<Vec<ResolvedBatchJob>, AppError> {
+    let rules = &settings.rules;
+    let mut jobs = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            let meta = get_video_metadata_in(ffmpeg, &file.input_path, false)?;
+            resolve_job(index, base, file, &meta, rules)
+                .map_err(|e| AppError::from(format!("{}: {}", file.input_path.display(), e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    plan_outputs(&mut jobs, rules, settings.layout.as_ref(), taken);
+    Ok(jobs)
+}
+
+/// Assigns layout destinations, then applies the existing-output rule. With that rule on, the
+/// natural name is kept so an earlier run's output is found and skipped.
+fn plan_outputs(
+    jobs: &mut [ResolvedBatchJob],
+    rules: &BatchSkipRules,
+    layout: Option<&BatchOutputLayout>,
+    taken: &mut HashSet<String>,
+) {
+    if let Some(layout) = layout {
+        layout.assign(
+            jobs,
+            !layout.overwrite && !rules.skip_existing_outputs,
+            taken,
+        );
+    }
+    for job in jobs.iter_mut().filter(|j| j.skipped.is_none()) {
+        job.skipped = rules.evaluate_output(job.output_path.as_deref());
+    }
+}
+
+async fn transcode_job(
+    ffmpeg: &FfmpegContext,
+    job: &ResolvedBatchJob,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<(PathBuf, Option<LargerOutputWarning>), AppError> {
+    crate::ffmpeg::record_job_sources(&[Path::new(&job.input_path)]);
+    let ext = job.options.effective_output_format();
+    let output = ffmpeg.create_temp(&format!("batch-{}-output.{}", job.index, ext), None)?;
+    let output_str = path_to_string(&output);
+    let mut larger_output = None;
+    let result = match crate::commands::run_transcode(
+        ffmpeg,
+        &job.input_path,
+        &output_str,
+        &job.options,
+        app,
+        window_label,
+        &ProgressSink::new(app, window_label, None),
+    )
+    .await
+    {
+        Ok(()) => {
+            match crate::commands::apply_larger_output_policy(
+                ffmpeg,
+                &job.input_path,
+                &output,
+                &job.options,
+            )
+            .await
+            {
+                Ok(warning) => {
+                    larger_output = warning;
+                    crate::commands::embed_cover_art(
+                        ffmpeg,
+                        &job.input_path,
+                        &output,
+                        &job.options,
+                        app,
+                        window_label,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let result = result.and_then(|()| match &job.output_path {
+        Some(dest) => {
+            let dest = Path::new(dest);
+            ensure_not_source(Path::new(&job.input_path), dest)?;
+            if let Some(dir) = dest.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            crate::commands::move_file(&output, dest)?;
+            Ok(dest.to_path_buf())
+        }
+        None => Ok(output.clone()),
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&output);
+    }
+    result.map(|path| (path, larger_output))
+}
+
+/// Predicts output sizes for `jobs` without encoding them. Emits `ffmpeg-progress` (step
+/// `batch_analyze`) after each file. Estimation failures are reported per file.
+pub(crate) async fn analyze_batch_jobs(
+    jobs: Vec<ResolvedBatchJob>,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<BatchReport, AppError> {
+    let ffmpeg = FfmpegContext::discover()?;
+    let total = jobs.len();
+    let mut report = BatchReport::default();
+    for (i, job) in jobs.into_iter().enumerate() {
+        let input = Path::new(&job.input_path);
+        let input_size = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+        let (estimate, error) = if job.skipped.is_some() {
+            (None, None)
+        } else {
+            let duration = job.options.duration_secs.unwrap_or(0.0);
+            match crate::preview::estimate_size_quiet(&ffmpeg, input, duration, &job.options).await
+            {
+                Ok(estimate) => (Some(estimate), None),
+                Err(AppError::Aborted) => return Err(AppError::aborted()),
+                Err(e) => {
+                    log::warn!(
+                        target: "tiny_vid::batch",
+                        "analyze_batch_jobs: estimate failed for {}: {}",
+                        job.input_path,
+                        e
+                    );
+                    (None, Some(e.to_string()))
+                }
+            }
+        };
+        let size_summary = estimate
+            .as_ref()
+            .map(|e| SizeSummary::new(input_size, e.best_size));
+        let compression = match &estimate {
+            Some(e) => compression_metrics(&ffmpeg, &job, e.best_size).await,
+            None => None,
+        };
+        let larger_output = estimate
+            .as_ref()
+            .and_then(|e| LargerOutputWarning::check(input_size, e.best_size, &job.options));
+        report.push(BatchReportEntry {
+            job,
+            input_size,
+            estimate,
+            size_summary,
+            compression,
+            larger_output,
+            error,
+        });
+        let payload = FfmpegProgressPayload::new(
+            (i + 1) as f64 / total as f64,
+            Some(ProgressStep::new("batch_analyze", i, total)),
+        );
+        let _ = app.emit_to(window_label, "ffmpeg-progress", Versioned::new(payload));
+    }
+    report.size_summary = (report.encoded_input_bytes > 0)
+        .then(|| SizeSummary::new(report.encoded_input_bytes, report.predicted_output_bytes));
+    log::info!(
+        target: "tiny_vid::batch",
+        "analyze_batch_jobs: {} file(s), {} -> ~{} bytes, {} skipped, {} failed",
+        total,
+        report.encoded_input_bytes,
+        report.predicted_output_bytes,
+        report.skipped_count,
+        report.failed_count
+    );
+    Ok(report)
+}
+
+/// Hashes a written output off the async runtime. Failures are logged, not fatal to the job.
+async fn checksum_written(path: &Path, options: ChecksumOptions) -> Option<OutputChecksum> {
+    let path = path.to_path_buf();
+    let outcome =
+        tauri::async_runtime::spawn_blocking(move || checksum_output(&path, options)).await;
+    match outcome {
+        Ok(Ok(checksum)) => Some(checksum),
+        Ok(Err(e)) => {
+            log::warn!(target: "tiny_vid::batch", "checksum_written: {}", e);
+            None
+        }
+        Err(e) => {
+            log::warn!(target: "tiny_vid::batch", "checksum_written: task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Compression metrics of `job` producing `output_bytes`. None (logged) when the source
+/// cannot be probed.
+async fn compression_metrics(
+    ffmpeg: &FfmpegContext,
+    job: &ResolvedBatchJob,
+    output_bytes: u64,
+) -> Option<CompressionMetrics> {
+    let input = Path::new(&job.input_path);
+    match crate::preview::get_video_metadata_async(ffmpeg, input).await {
+        Ok(meta) => Some(CompressionMetrics::new(&meta, &job.options, output_bytes)),
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::batch",
+                "compression_metrics: could not probe {}: {}",
+                job.input_path,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Runs one job, emitting `batch-job-started` (unless skipped) and `batch-job-complete`.
+/// Returns the result and whether the run was cancelled (`ffmpeg_terminate`). With `checksum`,
+/// the written output is hashed after it is moved into place.
+pub(crate) async fn run_batch_job(
+    ffmpeg: &FfmpegContext,
+    job: ResolvedBatchJob,
+    checksum: Option<ChecksumOptions>,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> (BatchJobResult, bool) {
+    if let Some(reason) = &job.skipped {
+        log::info!(
+            target: "tiny_vid::batch",
+            "run_batch_job: job {} skipped: {:?}",
+            job.index,
+            reason
+        );
+        let result = BatchJobResult {
+            job,
+            written_path: None,
+            commit_token: None,
+            error: None,
+            encode_seconds: None,
+            checksum: None,
+            source_untouched: None,
+            size_summary: None,
+            compression: None,
+            larger_output: None,
+            vmaf: None,
+            energy: None,
+        };
+        let _ = app.emit_to(window_label, "batch-job-complete", Versioned::new(&result));
+        return (result, false);
+    }
+    log::info!(
+        target: "tiny_vid::batch",
+        "run_batch_job: job {} input={} overrides={:?}",
+        job.index,
+        job.input_path,
+        job.overridden
+    );
+    let _ = app.emit_to(window_label, "batch-job-started", Versioned::new(&job));
+    let temp_job = TempJob::start();
+    let job_id = temp_job.id();
+    let cancellation = JobCancellation::register(job_id);
+    let _live_stderr = LiveStderr::register(job_id);
+    let cpu_meter = CpuMeter::register(job_id);
+    let ffmpeg = &ffmpeg
+        .clone()
+        .with_job_id(job_id)
+        .with_cancellation(cancellation.token().clone());
+    let _ = app.emit_to(
+        window_label,
+        "ffmpeg-job-started",
+        Versioned::new(JobStarted {
+            job_id,
+            kind: JobKind::Batch,
+        }),
+    );
+    let fingerprint = SourceFingerprint::capture(Path::new(&job.input_path));
+    crate::webhook::job_started(job_id, &job.input_path);
+    let started = std::time::Instant::now();
+    let outcome = transcode_job(ffmpeg, &job, app, window_label).await;
+    let written = outcome.as_ref().map(|(output, _)| path_to_string(output));
+    crate::webhook::job_finished(job_id, written.as_deref().map_err(|e| *e));
+    let source_untouched = fingerprint.map(|f| f.is_unchanged(Path::new(&job.input_path)));
+    if source_untouched == Some(false) {
+        log::error!(
+            target: "tiny_vid::batch",
+            "run_batch_job: source of job {} changed during the job: {}",
+            job.index,
+            job.input_path
+        );
+    }
+    let bytes = outcome.as_ref().ok().map(|(output, _)| {
+        let size = |p: &Path| fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+        (size(Path::new(&job.input_path)), size(output))
+    });
+    let encode_seconds = started.elapsed().as_secs_f64();
+    let energy = JobEnergy::new(
+        job.options.effective_codec(),
+        cpu_meter.cpu_seconds(),
+        encode_seconds,
+    );
+    let compression = match bytes {
+        Some((_, output_bytes)) => compression_metrics(ffmpeg, &job, output_bytes).await,
+        None => None,
+    };
+    let vmaf = match &outcome {
+        Ok((output, _)) => {
+            crate::commands::score_vmaf(ffmpeg, &job.input_path, output, &job.options).await
+        }
+        Err(_) => None,
+    };
+    // A job cancelled on its own fails like any other; only terminating everything stops the run.
+    let aborted = matches!(outcome, Err(AppError::Aborted)) && !cancellation.token().is_cancelled();
+    let result = match outcome {
+        Ok((output, larger_output)) => BatchJobResult {
+            // Outputs without a destination stay in temp until committed or discarded.
+            commit_token: job.output_path.is_none().then(|| {
+                register_transcode_temp(&temp_job, output.clone(), &[Path::new(&job.input_path)])
+            }),
+            job,
+            written_path: Some(path_to_string(&output)),
+            error: None,
+            encode_seconds: Some(encode_seconds),
+            checksum: match checksum {
+                Some(options) => checksum_written(&output, options).await,
+                None => None,
+            },
+            source_untouched,
+            size_summary: bytes.map(|(input, output)| SizeSummary::new(input, output)),
+            compression,
+            larger_output,
+            vmaf,
+            energy: Some(energy),
+        },
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::batch",
+                "run_batch_job: job {} failed: {}",
+                job.index,
+                e
+            );
+            BatchJobResult {
+                job,
+                written_path: None,
+                commit_token: None,
+                error: Some(e.to_string()),
+                encode_seconds: Some(encode_seconds),
+                checksum: None,
+                source_untouched,
+                size_summary: None,
+                compression: None,
+                larger_output: None,
+                vmaf: None,
+                energy: Some(energy),
+            }
+        }
+    };
+    let _ = app.emit_to(window_label, "batch-job-complete", Versioned::new(&result));
+    (result, aborted)
+}
+
+/// Runs `jobs` one after another with `run_batch_job`. A failed job does not stop the batch;
+/// cancelling does.
+pub(crate) async fn run_batch_jobs(
+    jobs: Vec<ResolvedBatchJob>,
+    checksum: Option<ChecksumOptions>,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<Vec<BatchJobResult>, AppError> {
+    let ffmpeg = FfmpegContext::discover()?;
+    let mut results = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let (result, aborted) = run_batch_job(&ffmpeg, job, checksum, app, window_label).await;
+        results.push(result);
+        if aborted {
+            return Err(AppError::aborted());
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> VideoMetadata {
+        VideoMetadata {
+            duration: 42.0,
+            audio_stream_count: 2,
+            subtitle_stream_count: 1,
+            audio_codec_name: Some("aac".to_string()),
+            audio_channels: Some(6),
+            video_stream_count: 1,
+            fps: 59.94,
+            ..VideoMetadata::default()
+        }
+    }
+
+    fn file(overrides: Value) -> BatchFile {
+        BatchFile {
+            input_path: PathBuf::from("/videos/a.mov"),
+            overrides: match overrides {
+                Value::Object(map) => map,
+                _ => Map::new(),
+            },
+            output_path: None,
+        }
+    }
+
+    fn resolve(base: &TranscodeOptions, overrides: Value) -> Result<ResolvedBatchJob, AppError> {
+        resolve_job(
+            0,
+            base,
+            &file(overrides),
+            &meta(),
+            &BatchSkipRules::default(),
+        )
+    }
+
+    #[test]
+    fn overrides_win_over_base_and_base_fills_the_rest() {
+        let base = TranscodeOptions {
+            quality: Some(60),
+            ..TranscodeOptions::default()
+        };
+        let job = resolve(&base, json!({ "removeAudio": true })).unwrap();
+        assert_eq!(job.options.remove_audio, Some(true));
+        assert_eq!(job.options.quality, Some(60));
+        assert_eq!(job.options.codec.as_deref(), Some("libx264"));
+        assert_eq!(job.overridden, vec!["removeAudio".to_string()]);
+    }
+
+    #[test]
+    fn metadata_replaces_base_values_but_not_overrides() {
+        let base = TranscodeOptions {
+            duration_secs: Some(10.0),
+            audio_stream_count: Some(1),
+            ..TranscodeOptions::default()
+        };
+        let job = resolve(&base, json!({ "audioStreamCount": 1 })).unwrap();
+        assert_eq!(job.options.duration_secs, Some(42.0));
+        assert_eq!(job.options.audio_channels, Some(6));
+        assert_eq!(job.options.source_fps, Some(59.94));
+        assert_eq!(job.options.audio_stream_count, Some(1));
+    }
+
+    #[test]
+    fn null_override_clears_field_and_bad_override_is_rejected() {
+        let base = TranscodeOptions {
+            tune: Some("film".to_string()),
+            ..TranscodeOptions::default()
+        };
+        let job = resolve(&base, json!({ "tune": null })).unwrap();
+        assert_eq!(job.options.tune, None);
+        assert!(job.skipped.is_none());
+        assert!(resolve(&base, json!({ "quality": "high" })).is_err());
+    }
+
+    #[test]
+    fn report_totals_count_only_estimated_files() {
+        let job = resolve(&TranscodeOptions::default(), json!({})).unwrap();
+        let estimate = SizeEstimate {
+            best_size: 100,
+            low_size: 80,
+            high_size: 120,
+            confidence: crate::ffmpeg::EstimateConfidence::High,
+            method: "sampled_bitrate".to_string(),
+            sample_count: 3,
+            sample_seconds_total: 4.5,
+        };
+        let mut skipped = job.clone();
+        skipped.skipped = Some(SkipReason::Codec {
+            codec: "hevc".to_string(),
+        });
+        let mut report = BatchReport::default();
+        for (job, estimate) in [(job.clone(), Some(estimate)), (job, None), (skipped, None)] {
+            report.push(BatchReportEntry {
+                job,
+                input_size: 1000,
+                estimate,
+                size_summary: None,
+                compression: None,
+                larger_output: None,
+                error: None,
+            });
+        }
+        assert_eq!(report.total_input_bytes, 3000);
+        assert_eq!(report.encoded_input_bytes, 1000);
+        assert_eq!(
+            (
+                report.predicted_low_bytes,
+                report.predicted_output_bytes,
+                report.predicted_high_bytes
+            ),
+            (80, 100, 120)
+        );
+        assert_eq!((report.skipped_count, report.failed_count), (1, 1));
+    }
+
+    #[test]
+    fn skip_rules_match_size_codec_and_existing_output() {
+        let mut meta = meta();
+        meta.size = 8 * 1024 * 1024;
+        meta.codec_name = Some("hevc".to_string());
+
+        let rules = BatchSkipRules {
+            max_size_mb: Some(10.0),
+            ..BatchSkipRules::default()
+        };
+        assert!(matches!(
+            rules.evaluate_source(&meta),
+            Some(SkipReason::UnderSize { .. })
+        ));
+
+        let rules = BatchSkipRules {
+            skip_codecs: vec!["H.265".to_string()],
+            ..BatchSkipRules::default()
+        };
+        assert_eq!(
+            rules.evaluate_source(&meta),
+            Some(SkipReason::Codec {
+                codec: "hevc".to_string()
+            })
+        );
+
+        let existing = tempfile::NamedTempFile::new().unwrap();
+        let rules = BatchSkipRules {
+            skip_existing_outputs: true,
+            ..BatchSkipRules::default()
+        };
+        assert_eq!(rules.evaluate_source(&meta), None);
+        assert_eq!(rules.evaluate_output(Some("/missing/out.mp4")), None);
+        assert!(matches!(
+            rules.evaluate_output(Some(&path_to_string(existing.path()))),
+            Some(SkipReason::OutputExists { .. })
+        ));
+    }
+
+    fn job_for(input: &str) -> ResolvedBatchJob {
+        let mut job = resolve(&TranscodeOptions::default(), json!({})).unwrap();
+        job.input_path = input.to_string();
+        job
+    }
+
+    #[test]
+    fn layout_mirrors_tree_and_numbers_collisions_in_job_order() {
+        let mut jobs = vec![
+            job_for("/in/trip/day1/a.mov"),
+            job_for("/in/trip/day1/A.mkv"),
+            job_for("/in/trip/b.mp4"),
+            job_for("/in/trip/day2/c.mov"),
+        ];
+        jobs[3].output_path = Some("/custom/c.mp4".to_string());
+        let layout = BatchOutputLayout {
+            output_root: PathBuf::from("/out"),
+            source_root: None,
+            overwrite: false,
+        };
+        layout.assign(&mut jobs, false, &mut HashSet::new());
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output_path.as_deref()).collect();
+        assert_eq!(
+            outputs,
+            vec![
+                Some("/out/day1/a.mp4"),
+                Some("/out/day1/A (1).mp4"),
+                Some("/out/b.mp4"),
+                Some("/custom/c.mp4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_never_overwrites_inputs_or_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clip.mp4");
+        fs::write(&input, b"x").unwrap();
+        fs::write(dir.path().join("other.mp4"), b"x").unwrap();
+        let mut jobs = vec![
+            job_for(&path_to_string(&input)),
+            job_for(&path_to_string(&dir.path().join("other.mov"))),
+        ];
+        let layout = BatchOutputLayout {
+            output_root: dir.path().to_path_buf(),
+            source_root: Some(dir.path().to_path_buf()),
+            overwrite: false,
+        };
+        layout.assign(&mut jobs, true, &mut HashSet::new());
+        assert_eq!(
+            jobs[0].output_path,
+            Some(path_to_string(&dir.path().join("clip (1).mp4")))
+        );
+        assert_eq!(
+            jobs[1].output_path,
+            Some(path_to_string(&dir.path().join("other (1).mp4")))
+        );
+    }
+
+    #[test]
+    fn outputs_planned_one_job_at_a_time_share_the_taken_set() {
+        let layout = BatchOutputLayout {
+            output_root: PathBuf::from("/out"),
+            source_root: Some(PathBuf::from("/in")),
+            overwrite: true,
+        };
+        let rules = BatchSkipRules {
+            skip_existing_outputs: true,
+            ..BatchSkipRules::default()
+        };
+        let mut taken = HashSet::new();
+        let mut first = [job_for("/in/clip.mov")];
+        let mut second = [job_for("/in/clip.mkv")];
+        plan_outputs(&mut first, &rules, Some(&layout), &mut taken);
+        plan_outputs(&mut second, &rules, Some(&layout), &mut taken);
+        assert_eq!(first[0].output_path.as_deref(), Some("/out/clip.mp4"));
+        assert_eq!(second[0].output_path.as_deref(), Some("/out/clip (1).mp4"));
+        assert!(first[0].skipped.is_none() && second[0].skipped.is_none());
+    }
+
+    #[test]
+    fn common_parent_finds_deepest_shared_folder() {
+        let paths = [Path::new("/a/b/c/x.mp4"), Path::new("/a/b/y.mp4")];
+        assert_eq!(common_parent(paths), Some(PathBuf::from("/a/b")));
+        assert_eq!(
+            common_parent([Path::new("/a/x.mp4")]),
+            Some(PathBuf::from("/a"))
+        );
+    }
+}
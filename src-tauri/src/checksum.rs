@@ -0,0 +1,98 @@
+//! SHA-256 of committed outputs for archival and upload-integrity checks, optionally written
+//! next to the file as a `.sha256` sidecar in `sha256sum` format.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::ffmpeg::integrity::sha256_file;
+use crate::ffmpeg::path_to_string;
+
+/// Checksum step settings for `run_batch`.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumOptions {
+    /// Also write `<output>.sha256`. Default false.
+    #[serde(default)]
+    pub sidecar: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputChecksum {
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidecar_path: Option<String>,
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// `sha256sum`-compatible line: `<hash>  <file name>`, so `sha256sum -c` works in the folder.
+fn sidecar_line(sha256: &str, path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{}  {}\n", sha256, name)
+}
+
+/// Hashes `path` and, with `options.sidecar`, writes the sidecar. Blocking.
+pub fn checksum_output(path: &Path, options: ChecksumOptions) -> Result<OutputChecksum, AppError> {
+    let sha256 = sha256_file(path)?;
+    let sidecar = if options.sidecar {
+        let sidecar = sidecar_path(path);
+        fs::write(&sidecar, sidecar_line(&sha256, path))?;
+        Some(path_to_string(&sidecar))
+    } else {
+        None
+    };
+    log::info!(
+        target: "tiny_vid::checksum",
+        "checksum_output: {} sha256={}",
+        path.display(),
+        sha256
+    );
+    Ok(OutputChecksum {
+        sha256,
+        sidecar_path: sidecar,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_uses_sha256sum_format_next_to_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.mp4");
+        fs::write(&path, b"abc").unwrap();
+        let checksum = checksum_output(&path, ChecksumOptions { sidecar: true }).unwrap();
+        assert_eq!(
+            checksum.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let sidecar = dir.path().join("clip.mp4.sha256");
+        assert_eq!(checksum.sidecar_path, Some(path_to_string(&sidecar)));
+        assert_eq!(
+            fs::read_to_string(sidecar).unwrap(),
+            format!("{}  clip.mp4\n", checksum.sha256)
+        );
+    }
+
+    #[test]
+    fn no_sidecar_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.mp4");
+        fs::write(&path, b"abc").unwrap();
+        let checksum = checksum_output(&path, ChecksumOptions::default()).unwrap();
+        assert_eq!(checksum.sidecar_path, None);
+        assert!(!dir.path().join("clip.mp4.sha256").exists());
+    }
+}
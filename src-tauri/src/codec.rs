@@ -1,15 +1,21 @@
 //! Codec metadata and build variant for FFmpeg.
 
+use std::path::Path;
+
 use crate::error::AppError;
 use serde::{Deserialize, Serialize};
 
+/// DRM render node VA-API encoders open. Without it the `*_vaapi` encoders are hidden.
+pub const VAAPI_RENDER_NODE: &str = "/dev/dri/renderD128";
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CodecInfo {
     pub value: String,
     pub name: String,
     pub formats: Vec<String>,
-    pub supports_tune: bool,
+    /// Valid `-tune` values for this encoder. Empty when the encoder has no tune option.
+    pub tunes: Vec<String>,
     pub preset_type: String,
 }
 
@@ -24,20 +30,20 @@ struct CodecRow {
     value: &'static str,
     name: &'static str,
     formats: &'static [&'static str],
-    supports_tune: bool,
+    tunes: &'static [&'static str],
     preset_type: &'static str,
 }
 
 macro_rules! codec_table {
     (
-        $( [$value:expr, $name:expr, $formats:expr, $tune:expr, $preset:expr] ),* $(,)?
+        $( [$value:expr, $name:expr, $formats:expr, $tunes:expr, $preset:expr] ),* $(,)?
     ) => {
         const CODEC_TABLE: &[CodecRow] = &[
             $( CodecRow {
                 value: $value,
                 name: $name,
                 formats: $formats,
-                supports_tune: $tune,
+                tunes: $tunes,
                 preset_type: $preset,
             } ),*
         ];
@@ -47,51 +53,157 @@ macro_rules! codec_table {
     };
 }
 
+const X264_TUNES: &[&str] = &[
+    "film",
+    "animation",
+    "grain",
+    "stillimage",
+    "fastdecode",
+    "zerolatency",
+    "psnr",
+    "ssim",
+];
+const X265_TUNES: &[&str] = &[
+    "animation",
+    "grain",
+    "fastdecode",
+    "zerolatency",
+    "psnr",
+    "ssim",
+];
+
+/// Frame-type settings changed on top of a speed-critical x264/x265 preset. The presets keep
+/// more B-frames and lookahead than their speed suggests (x265 ultrafast still has 3 B-frames);
+/// these give up a little compression for encode time, which is what picking them asks for
+/// and what preview and estimate encodes need. Only values that differ from the preset's own
+/// are set (x264 ultrafast already has no B-frames or lookahead, so it gets none).
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedPresetTuning {
+    pub preset: &'static str,
+    /// Max consecutive B-frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bframes: Option<u32>,
+    /// Frames the rate control looks ahead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rc_lookahead: Option<u32>,
+}
+
+/// x264 superfast keeps 3 B-frames.
+const X264_SPEED_TUNING: &[SpeedPresetTuning] = &[SpeedPresetTuning {
+    preset: "superfast",
+    bframes: Some(1),
+    rc_lookahead: None,
+}];
+/// x265 ultrafast and superfast keep 3 B-frames; superfast looks 10 frames ahead.
+const X265_SPEED_TUNING: &[SpeedPresetTuning] = &[
+    SpeedPresetTuning {
+        preset: "ultrafast",
+        bframes: Some(0),
+        rc_lookahead: None,
+    },
+    SpeedPresetTuning {
+        preset: "superfast",
+        bframes: Some(2),
+        rc_lookahead: Some(5),
+    },
+];
+
 codec_table!(
     [
         "libx264",
         "H.264 (Widest support)",
         &["mp4", "mkv"],
-        true,
+        X264_TUNES,
         "x264"
     ],
     [
         "libx265",
         "H.265 (Smaller files)",
         &["mp4", "mkv"],
-        false,
+        X265_TUNES,
         "x265"
     ],
     [
         "libsvtav1",
         "AV1 (Smallest files)",
         &["mp4", "webm", "mkv"],
-        false,
+        &[],
         "av1"
     ],
     [
         "libvpx-vp9",
         "VP9 (Browser-friendly WebM)",
         &["webm", "mkv"],
-        false,
+        &[],
         "vp9"
     ],
     [
         "h264_videotoolbox",
         "H.264 (VideoToolbox)",
         &["mp4", "mkv"],
-        false,
+        &[],
         "vt"
     ],
     [
         "hevc_videotoolbox",
         "H.265 (VideoToolbox)",
         &["mp4", "mkv"],
-        false,
+        &[],
         "vt"
     ],
+    [
+        "h264_nvenc",
+        "H.264 (NVIDIA NVENC)",
+        &["mp4", "mkv"],
+        &[],
+        "nvenc"
+    ],
+    [
+        "hevc_nvenc",
+        "H.265 (NVIDIA NVENC)",
+        &["mp4", "mkv"],
+        &[],
+        "nvenc"
+    ],
+    [
+        "h264_qsv",
+        "H.264 (Intel Quick Sync)",
+        &["mp4", "mkv"],
+        &[],
+        "qsv"
+    ],
+    [
+        "hevc_qsv",
+        "H.265 (Intel Quick Sync)",
+        &["mp4", "mkv"],
+        &[],
+        "qsv"
+    ],
+    [
+        "h264_vaapi",
+        "H.264 (VA-API)",
+        &["mp4", "mkv"],
+        &[],
+        "vaapi"
+    ],
+    [
+        "hevc_vaapi",
+        "H.265 (VA-API)",
+        &["mp4", "mkv"],
+        &[],
+        "vaapi"
+    ],
+    ["h264_amf", "H.264 (AMD AMF)", &["mp4", "mkv"], &[], "amf"],
+    ["hevc_amf", "H.265 (AMD AMF)", &["mp4", "mkv"], &[], "amf"],
+    // FFmpeg's palette encoder: GIF output has no video codec to pick, so it is an entry of
+    // its own, with no presets or tunes.
+    [GIF_CODEC, "GIF (Animated)", &["gif"], &[], "gif"],
 );
 
+/// Encoder behind GIF output. `TranscodeOptions::effective_codec` reports it for every GIF.
+pub const GIF_CODEC: &str = "gif";
+
 /// Return CodecInfo for a known codec string. Panics on unknown codec.
 pub fn get_codec_info(codec: &str) -> CodecInfo {
     let row = CODEC_TABLE
@@ -102,11 +214,45 @@ pub fn get_codec_info(codec: &str) -> CodecInfo {
         value: row.value.to_string(),
         name: row.name.to_string(),
         formats: row.formats.iter().copied().map(str::to_string).collect(),
-        supports_tune: row.supports_tune,
+        tunes: row.tunes.iter().copied().map(str::to_string).collect(),
         preset_type: row.preset_type.to_string(),
     }
 }
 
+/// Valid tune values for a codec. Empty for unknown codecs and encoders without `-tune`.
+pub fn get_tunes(codec: &str) -> &'static [&'static str] {
+    CODEC_TABLE
+        .iter()
+        .find(|r| r.value == codec)
+        .map(|r| r.tunes)
+        .unwrap_or(&[])
+}
+
+/// Presets of `codec` that get frame-type tuning, with the values. Empty for encoders other
+/// than libx264 and libx265.
+pub fn get_speed_preset_tunings(codec: &str) -> &'static [SpeedPresetTuning] {
+    match codec {
+        "libx264" => X264_SPEED_TUNING,
+        "libx265" => X265_SPEED_TUNING,
+        _ => &[],
+    }
+}
+
+/// Tuning added to `preset` for `codec`, when it is a speed-critical one.
+pub fn speed_preset_tuning(codec: &str, preset: &str) -> Option<SpeedPresetTuning> {
+    get_speed_preset_tunings(codec)
+        .iter()
+        .find(|t| t.preset == preset)
+        .copied()
+}
+
+/// Hardware encoders depend on the GPU/driver at runtime and need a probe before use.
+pub fn is_hardware_codec(codec: &str) -> bool {
+    ["_videotoolbox", "_nvenc", "_qsv", "_vaapi", "_amf"]
+        .iter()
+        .any(|suffix| codec.ends_with(suffix))
+}
+
 const NON_VT: &[&str] = &["libx264", "libx265", "libsvtav1", "libvpx-vp9"];
 const VT: &[&str] = &["h264_videotoolbox", "hevc_videotoolbox"];
 
@@ -124,10 +270,24 @@ pub fn filter_codecs_for_display(available: &[String]) -> Vec<String> {
     }
 }
 
+/// Drops the VA-API encoders when there is no render node for them to open.
+fn filter_vaapi_codecs(codecs: Vec<String>, render_node_present: bool) -> Vec<String> {
+    if render_node_present {
+        return codecs;
+    }
+    codecs
+        .into_iter()
+        .filter(|c| !c.ends_with("_vaapi"))
+        .collect()
+}
+
 pub fn get_build_variant(available: Vec<String>) -> Result<BuildVariantResult, AppError> {
-    let codecs = filter_codecs_for_display(&available);
+    let codecs = filter_vaapi_codecs(
+        filter_codecs_for_display(&available),
+        Path::new(VAAPI_RENDER_NODE).exists(),
+    );
 
-    if codecs.is_empty() {
+    if codecs.iter().all(|c| c == GIF_CODEC) {
         return Err(AppError::from(
             "No supported video codecs found in FFmpeg. Please ensure FFmpeg is properly installed with codec support.",
         ));
@@ -146,7 +306,11 @@ pub fn get_build_variant(available: Vec<String>) -> Result<BuildVariantResult, A
 
 #[cfg(test)]
 mod tests {
-    use super::{CODEC_TABLE, SUPPORTED_CODEC_NAMES, filter_codecs_for_display, get_codec_info};
+    use super::{
+        CODEC_TABLE, SUPPORTED_CODEC_NAMES, filter_codecs_for_display, filter_vaapi_codecs,
+        get_build_variant, get_codec_info, get_speed_preset_tunings, get_tunes, is_hardware_codec,
+        speed_preset_tuning,
+    };
 
     #[test]
     fn codec_info_has_correct_metadata() {
@@ -154,7 +318,7 @@ mod tests {
         assert_eq!(info.value, "libx264");
         assert_eq!(info.name, "H.264 (Widest support)");
         assert_eq!(info.formats, vec!["mp4", "mkv"]);
-        assert!(info.supports_tune);
+        assert!(info.tunes.contains(&"film".to_string()));
         assert_eq!(info.preset_type, "x264");
     }
 
@@ -167,6 +331,15 @@ mod tests {
             "libvpx-vp9",
             "h264_videotoolbox",
             "hevc_videotoolbox",
+            "h264_nvenc",
+            "hevc_nvenc",
+            "h264_qsv",
+            "hevc_qsv",
+            "h264_vaapi",
+            "hevc_vaapi",
+            "h264_amf",
+            "hevc_amf",
+            "gif",
         ] {
             let info = get_codec_info(codec);
             assert!(!info.value.is_empty());
@@ -179,6 +352,10 @@ mod tests {
         let x264 = get_codec_info("libx264");
         assert_eq!(x264.formats, vec!["mp4", "mkv"]);
 
+        let gif = get_codec_info("gif");
+        assert_eq!(gif.formats, vec!["gif"]);
+        assert!(gif.tunes.is_empty());
+
         let av1 = get_codec_info("libsvtav1");
         assert_eq!(av1.formats, vec!["mp4", "webm", "mkv"]);
 
@@ -192,6 +369,74 @@ mod tests {
         assert_eq!(get_codec_info("libx265").preset_type, "x265");
         assert_eq!(get_codec_info("libsvtav1").preset_type, "av1");
         assert_eq!(get_codec_info("h264_videotoolbox").preset_type, "vt");
+        assert_eq!(get_codec_info("hevc_nvenc").preset_type, "nvenc");
+        assert_eq!(get_codec_info("h264_qsv").preset_type, "qsv");
+        assert_eq!(get_codec_info("hevc_vaapi").preset_type, "vaapi");
+        assert_eq!(get_codec_info("h264_amf").preset_type, "amf");
+    }
+
+    #[test]
+    fn get_tunes_per_codec() {
+        assert!(get_tunes("libx264").contains(&"stillimage"));
+        assert!(get_tunes("libx265").contains(&"psnr"));
+        assert!(!get_tunes("libx265").contains(&"film"));
+        assert!(get_tunes("libsvtav1").is_empty());
+        assert!(get_tunes("h264_videotoolbox").is_empty());
+        assert!(get_tunes("unknown").is_empty());
+    }
+
+    #[test]
+    fn speed_tuning_only_for_the_fastest_software_presets() {
+        let x265 = speed_preset_tuning("libx265", "ultrafast").unwrap();
+        assert_eq!(x265.bframes, Some(0));
+        assert!(speed_preset_tuning("libx264", "superfast").is_some());
+        assert_eq!(speed_preset_tuning("libx264", "ultrafast"), None);
+        assert_eq!(speed_preset_tuning("libx264", "fast"), None);
+        assert_eq!(speed_preset_tuning("h264_nvenc", "ultrafast"), None);
+    }
+
+    #[test]
+    fn speed_tuning_changes_what_the_preset_sets() {
+        // (codec, preset, bframes, rc-lookahead) as the encoders' presets set them.
+        let builtin = [
+            ("libx264", "ultrafast", 0, 0),
+            ("libx264", "superfast", 3, 0),
+            ("libx265", "ultrafast", 3, 5),
+            ("libx265", "superfast", 3, 10),
+        ];
+        for codec in ["libx264", "libx265"] {
+            for tuning in get_speed_preset_tunings(codec) {
+                let &(_, _, bframes, lookahead) = builtin
+                    .iter()
+                    .find(|(c, p, _, _)| *c == codec && *p == tuning.preset)
+                    .unwrap();
+                assert!(
+                    tuning.bframes.is_some() || tuning.rc_lookahead.is_some(),
+                    "{} {}",
+                    codec,
+                    tuning.preset
+                );
+                assert_ne!(tuning.bframes, Some(bframes), "{} {}", codec, tuning.preset);
+                assert_ne!(
+                    tuning.rc_lookahead,
+                    Some(lookahead),
+                    "{} {}",
+                    codec,
+                    tuning.preset
+                );
+                // x265 wants more lookahead than consecutive B-frames.
+                if let (Some(b), Some(l)) = (tuning.bframes, tuning.rc_lookahead) {
+                    assert!(l > b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hardware_codecs_detected_by_suffix() {
+        assert!(is_hardware_codec("h264_videotoolbox"));
+        assert!(is_hardware_codec("hevc_nvenc"));
+        assert!(!is_hardware_codec("libx264"));
     }
 
     #[test]
@@ -205,6 +450,23 @@ mod tests {
         assert_eq!(filtered, vec!["libx264"]);
     }
 
+    #[test]
+    fn vaapi_codecs_need_a_render_node() {
+        let available = vec!["libx264".to_string(), "h264_vaapi".to_string()];
+        assert_eq!(
+            filter_vaapi_codecs(available.clone(), true),
+            vec!["libx264", "h264_vaapi"]
+        );
+        assert_eq!(filter_vaapi_codecs(available, false), vec!["libx264"]);
+    }
+
+    #[test]
+    fn gif_alone_is_not_a_supported_build() {
+        assert!(get_build_variant(vec!["gif".to_string()]).is_err());
+        let variant = get_build_variant(vec!["libx264".to_string(), "gif".to_string()]).unwrap();
+        assert_eq!(variant.codecs.len(), 2);
+    }
+
     #[test]
     fn codec_table_matches_supported_codec_names() {
         let table_names: Vec<&str> = CODEC_TABLE.iter().map(|r| r.value).collect();
@@ -3,17 +3,53 @@
 use std::fs;
 use std::io;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::AppState;
-use crate::codec::BuildVariantResult;
+use crate::batch::commit::BatchCommitItem;
+use crate::batch::manifest::{BatchManifest, BatchManifestConfig};
+use crate::batch::{BatchFile, BatchJobResult, BatchReport, BatchSettings, ResolvedBatchJob};
+use crate::checksum::{ChecksumOptions, OutputChecksum};
+use crate::codec::{BuildVariantResult, SpeedPresetTuning};
 use crate::error::AppError;
-use crate::ffmpeg::ffprobe::{VideoMetadata as FfprobeVideoMetadata, get_video_metadata_impl};
+use crate::events::{JobKind, JobStarted, OpenFiles, Versioned};
+use crate::ffmpeg::compose::PipLayout;
+use crate::ffmpeg::compression::LargerOutputWarning;
+use crate::ffmpeg::contact_sheet::ContactSheetResult;
+use crate::ffmpeg::dead_frames::DeadFrameAnalysis;
+use crate::ffmpeg::ffprobe::{VideoMetadata as FfprobeVideoMetadata, get_video_metadata_in};
+use crate::ffmpeg::hw_probe::EncoderHealth;
+use crate::ffmpeg::integrity::IntegrityReport;
+use crate::ffmpeg::quality::{VmafScore, score_export_vmaf};
+use crate::ffmpeg::quality_search;
+use crate::ffmpeg::repair::RepairReport;
+use crate::ffmpeg::self_test::SelfTestReport;
+use crate::ffmpeg::silence::SilenceAnalysis;
+use crate::ffmpeg::storyboard::Storyboard;
+use crate::ffmpeg::suggest::{
+    GenerationLossAdvisory, QualitySuggestion, SuggestedOptions, generation_loss_advisory,
+};
+use crate::ffmpeg::updater::{UpdateChannel, UpdateCheck, UpdateInstallResult};
 use crate::ffmpeg::{
-    TempFileManager, TranscodeOptions, build_ffmpeg_command, build_first_frame_args,
-    cleanup_transcode_temp, format_args_for_display_multiline, path_to_string, set_transcode_temp,
-    terminate_all_ffmpeg,
+    CpuMeter, EstimateSampling, FfmpegContext, FfmpegProgressPayload, HOVER_PREVIEW_SECONDS,
+    JobCancellation, JobEnergy, LargerOutputPolicy, LiveStderr, ProgressChannel, ProgressSink,
+    ProgressStep, ProgressThrottle, SalvageReport, TempJob, TranscodeOptions,
+    build_cover_image_args, build_embed_cover_args, build_ffmpeg_command, build_first_frame_args,
+    build_hover_preview_args, build_pip_compose_args, build_remux_args, build_thumbnail_args,
+    commit_transcode_temp, discard_transcode_temp, ensure_not_job_source, estimated_audio_kbps,
+    file_signature, format_args_for_display_multiline, get_cached_hover_preview, path_to_string,
+    register_transcode_temp, run_two_pass, set_cached_hover_preview, terminate_all_ffmpeg,
+    transcode_temp_for_token, transcode_temp_job,
+};
+use crate::i18n::Locale;
+use crate::presets::NamedPreset;
+use crate::preview::{
+    PreviewProgressCtx, PreviewWithEstimateResult, run_preview_core, run_preview_with_estimate_core,
 };
-use crate::preview::{PreviewWithEstimateResult, run_preview_core, run_preview_with_estimate_core};
+use crate::queue::QueueItem;
+use crate::quick::PlatformPreset;
+use crate::watch::{WatchFolder, WatchFolderConfig};
+use crate::webhook::WebhookConfig;
 use tauri::{Emitter, Manager};
 
 fn is_cross_device_rename_error(e: &io::Error) -> bool {
@@ -50,6 +86,8 @@ pub(crate) struct VideoMetadataResult {
     duration: f64,
     width: u32,
     height: u32,
+    /// Clockwise rotation of the source's display matrix; width/height are already upright.
+    rotation: u32,
     size: u64,
     size_mb: f64,
     fps: f64,
@@ -75,16 +113,28 @@ pub(crate) struct VideoMetadataResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     audio_channels: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    audio_bit_rate: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     encoder: Option<String>,
+    video_stream_count: u32,
+    main_video_stream_index: u32,
+    /// Encoder tag names a screen recorder; the UI can default to the screen recording content type.
+    likely_screen_recording: bool,
+    /// Set when re-encoding would likely cost quality for little size (see
+    /// `suggest::generation_loss_advisory`); judged against `target_codec` when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_loss: Option<GenerationLossAdvisory>,
 }
 
 impl From<FfprobeVideoMetadata> for VideoMetadataResult {
     fn from(meta: FfprobeVideoMetadata) -> Self {
+        let likely_screen_recording = meta.looks_like_screen_recording();
         let fps = (meta.fps * 100.0).round() / 100.0;
         Self {
             duration: meta.duration,
             width: meta.width,
             height: meta.height,
+            rotation: meta.rotation,
             size: meta.size,
             size_mb: meta.size as f64 / 1024.0 / 1024.0,
             fps,
@@ -100,99 +150,638 @@ impl From<FfprobeVideoMetadata> for VideoMetadataResult {
             audio_codec_name: meta.audio_codec_name,
             encoder: meta.encoder,
             audio_channels: meta.audio_channels,
+            audio_bit_rate: meta.audio_bit_rate,
+            video_stream_count: meta.video_stream_count,
+            main_video_stream_index: meta.main_video_stream_index,
+            likely_screen_recording,
+            generation_loss: None,
         }
     }
 }
 
+/// A finished output waiting in temp for the user to pick a destination. Save it with
+/// `commit_temp_output` or drop it with `discard_temp_output`; either way, it is deleted after
+/// an hour or at exit if neither happens.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TempOutput {
+    path: String,
+    commit_token: String,
+    /// Set when the transcode is not smaller than its input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    larger_output: Option<LargerOutputWarning>,
+    /// Set with `vmaf_check` when the output could be scored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vmaf: Option<VmafScore>,
+    /// CPU time and energy estimate of a transcode (see `ffmpeg::energy`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    energy: Option<JobEnergy>,
+}
+
+/// Single-pass transcode, or two passes (`transcode_pass1`/`transcode_pass2` progress steps,
+/// each covering half of the bar) when targeting a size with a two-pass capable codec, or a
+/// quality search with `quality_search`. Progress goes to `progress`; errors are emitted to
+/// the window.
+pub(crate) async fn run_transcode(
+    ffmpeg: &FfmpegContext,
+    input: &str,
+    output: &str,
+    options: &TranscodeOptions,
+    app: &tauri::AppHandle,
+    window_label: &str,
+    progress: &ProgressSink,
+) -> Result<(), AppError> {
+    // Shorter than the source when trimmed or when keep_segments cuts silence.
+    let duration_secs = options.output_duration_secs();
+    crate::source_guard::ensure_not_source(
+        std::path::Path::new(input),
+        std::path::Path::new(output),
+    )?;
+    if options.effective_quality_search() {
+        return run_quality_search_transcode(
+            ffmpeg,
+            input,
+            output,
+            options,
+            app,
+            window_label,
+            progress,
+        )
+        .await;
+    }
+    if !options.uses_two_pass() {
+        let args = build_ffmpeg_command(input, output, options, None, None, None)?;
+        let emit_progress = crate::preview::make_progress_emitter(progress.clone(), "transcode");
+        let job_id = ffmpeg.job_id();
+        let progress_callback: Arc<dyn Fn(f64) + Send + Sync> = Arc::new(move |p: f64| {
+            emit_progress(p);
+            crate::webhook::job_progress(job_id, p);
+        });
+        return crate::preview::run_ffmpeg_step(
+            ffmpeg,
+            args,
+            Some((app, window_label)),
+            duration_secs,
+            Some(progress_callback),
+        )
+        .await;
+    }
+
+    run_two_pass(ffmpeg, input, output, options, |pass_index, args| {
+        let step = ["transcode_pass1", "transcode_pass2"][pass_index];
+        let sink = progress.clone();
+        let job_id = ffmpeg.job_id();
+        let progress_callback: Arc<dyn Fn(f64) + Send + Sync> = Arc::new(move |p: f64| {
+            let overall = (pass_index as f64 + p) / 2.0;
+            sink.send(FfmpegProgressPayload::new(
+                overall,
+                Some(ProgressStep::new(step, pass_index, 2)),
+            ));
+            crate::webhook::job_progress(job_id, overall);
+        });
+        crate::preview::run_ffmpeg_step(
+            ffmpeg,
+            args,
+            Some((app, window_label)),
+            duration_secs,
+            Some(progress_callback),
+        )
+    })
+    .await
+}
+
+/// Progress callback mapping one phase's 0-1 onto `[start, start + span]` of the bar.
+fn phase_progress(
+    sink: &ProgressSink,
+    job_id: Option<u64>,
+    step: ProgressStep,
+    start: f64,
+    span: f64,
+) -> Arc<dyn Fn(f64) + Send + Sync> {
+    let sink = sink.clone();
+    Arc::new(move |p: f64| {
+        let overall = start + p * span;
+        sink.send(FfmpegProgressPayload::new(overall, Some(step.clone())));
+        crate::webhook::job_progress(job_id, overall);
+    })
+}
+
+/// Target size by quality search: sample encodes (`quality_search` step), the export at the
+/// picked quality (`transcode`), and one more encode to a separate file (`transcode_retry`)
+/// when it misses the target by more than `quality_search::RETRY_TOLERANCE`. Of the export and
+/// the retry, `output` ends up holding the one `quality_search::pick_encode` keeps.
+async fn run_quality_search_transcode(
+    ffmpeg: &FfmpegContext,
+    input: &str,
+    output: &str,
+    options: &TranscodeOptions,
+    app: &tauri::AppHandle,
+    window_label: &str,
+    progress: &ProgressSink,
+) -> Result<(), AppError> {
+    let plan = quality_search::plan(options)?;
+    let sample = ffmpeg.create_temp(
+        &format!(
+            "quality-search-sample.{}",
+            options.effective_output_format()
+        ),
+        None,
+    )?;
+    let sample_str = path_to_string(&sample);
+    let mut points = Vec::with_capacity(quality_search::SAMPLE_QUALITIES.len());
+    let sampled: Result<(), AppError> = async {
+        for quality in quality_search::SAMPLE_QUALITIES {
+            let (mut bytes, mut secs) = (0u64, 0.0);
+            for (window_index, &window) in plan.windows.iter().enumerate() {
+                let sample_opts = quality_search::sample_options(options, quality, window);
+                let args =
+                    build_ffmpeg_command(input, &sample_str, &sample_opts, None, None, None)?;
+                let (start, span) =
+                    quality_search::sample_progress(points.len(), window_index, plan.windows.len());
+                let callback = phase_progress(
+                    progress,
+                    ffmpeg.job_id(),
+                    ProgressStep::new("quality_search", 0, quality_search::PROGRESS_STEPS),
+                    start,
+                    span,
+                );
+                crate::preview::run_ffmpeg_step(
+                    ffmpeg,
+                    args,
+                    Some((app, window_label)),
+                    Some(window.1),
+                    Some(callback),
+                )
+                .await?;
+                bytes += fs::metadata(&sample)?.len();
+                secs += window.1;
+            }
+            points.push((quality, bytes as f64 * 8.0 / secs / 1000.0));
+        }
+        Ok(())
+    }
+    .await;
+    let _ = fs::remove_file(&sample);
+    sampled?;
+
+    let quality = quality_search::pick_quality(&points, plan.budget_kbps);
+    log::info!(
+        target: "tiny_vid::commands",
+        "run_quality_search_transcode: samples {:?} kbps, budget {:.0} kbps -> quality {}",
+        points,
+        plan.budget_kbps,
+        quality
+    );
+    let args = build_ffmpeg_command(
+        input,
+        output,
+        &quality_search::encode_options(options, quality),
+        None,
+        None,
+        None,
+    )?;
+    let (start, span) = quality_search::export_progress();
+    let callback = phase_progress(
+        progress,
+        ffmpeg.job_id(),
+        ProgressStep::new("transcode", 1, quality_search::PROGRESS_STEPS),
+        start,
+        span,
+    );
+    crate::preview::run_ffmpeg_step(
+        ffmpeg,
+        args,
+        Some((app, window_label)),
+        Some(plan.output_secs),
+        Some(callback),
+    )
+    .await?;
+
+    let actual = fs::metadata(output)?.len();
+    if !quality_search::misses_target(actual, plan.target_bytes) {
+        // No retry: the half of the bar kept for it is done too.
+        phase_progress(
+            progress,
+            ffmpeg.job_id(),
+            ProgressStep::new("transcode", 1, quality_search::PROGRESS_STEPS),
+            1.0,
+            0.0,
+        )(1.0);
+        return Ok(());
+    }
+    let actual_kbps =
+        quality_search::export_video_kbps(actual, plan.output_secs, estimated_audio_kbps(options));
+    let retry = quality_search::retry_quality(&points, quality, actual_kbps, plan.budget_kbps);
+    log::warn!(
+        target: "tiny_vid::commands",
+        "run_quality_search_transcode: {} bytes misses target {} bytes, retrying at quality {}",
+        actual,
+        plan.target_bytes,
+        retry
+    );
+    let retry_output = ffmpeg.create_temp(
+        &format!("quality-search-retry.{}", options.effective_output_format()),
+        None,
+    )?;
+    let encoded: Result<u64, AppError> = async {
+        let args = build_ffmpeg_command(
+            input,
+            &path_to_string(&retry_output),
+            &quality_search::encode_options(options, retry),
+            None,
+            None,
+            None,
+        )?;
+        let (start, span) = quality_search::retry_progress();
+        let callback = phase_progress(
+            progress,
+            ffmpeg.job_id(),
+            ProgressStep::new("transcode_retry", 2, quality_search::PROGRESS_STEPS),
+            start,
+            span,
+        );
+        crate::preview::run_ffmpeg_step(
+            ffmpeg,
+            args,
+            Some((app, window_label)),
+            Some(plan.output_secs),
+            Some(callback),
+        )
+        .await?;
+        Ok(fs::metadata(&retry_output)?.len())
+    }
+    .await;
+    let kept = encoded.and_then(|retried| {
+        let kept = quality_search::pick_encode(actual, retried, plan.target_bytes);
+        log::info!(
+            target: "tiny_vid::commands",
+            "run_quality_search_transcode: retry {} bytes, export {} bytes, target {} bytes -> {:?}",
+            retried,
+            actual,
+            plan.target_bytes,
+            kept
+        );
+        match kept {
+            Some(quality_search::KeptEncode::Retry) => move_file(&retry_output, std::path::Path::new(output)),
+            Some(quality_search::KeptEncode::Export) => Ok(()),
+            None => Err(AppError::from(format!(
+                "Could not fit the video under the target size ({} and {} bytes, target {} bytes)",
+                actual, retried, plan.target_bytes
+            ))),
+        }
+    });
+    let _ = fs::remove_file(&retry_output);
+    kept
+}
+
+/// Applies `options.larger_output` once `output` is encoded from `input`. Returns the warning
+/// to report when the output is not smaller, or fails under `refuse`. A failed remux keeps
+/// the encode.
+pub(crate) async fn apply_larger_output_policy(
+    ffmpeg: &FfmpegContext,
+    input: &str,
+    output: &std::path::Path,
+    options: &TranscodeOptions,
+) -> Result<Option<LargerOutputWarning>, AppError> {
+    let size = |p: &std::path::Path| fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+    let Some(mut warning) =
+        LargerOutputWarning::check(size(std::path::Path::new(input)), size(output), options)
+    else {
+        return Ok(None);
+    };
+    let policy = options.effective_larger_output();
+    log::warn!(
+        target: "tiny_vid::commands",
+        "apply_larger_output_policy: output {} bytes >= input {} bytes, policy {:?}",
+        warning.output_bytes,
+        warning.input_bytes,
+        policy
+    );
+    match policy {
+        LargerOutputPolicy::Warn => {}
+        LargerOutputPolicy::Refuse => {
+            return Err(AppError::from(format!(
+                "The output ({} bytes) is not smaller than the input ({} bytes)",
+                warning.output_bytes, warning.input_bytes
+            )));
+        }
+        LargerOutputPolicy::Remux => {
+            let format = options.effective_output_format();
+            let remuxed = ffmpeg.create_temp(&format!("remux-output.{}", format), None)?;
+            let Some(args) = build_remux_args(input, &path_to_string(&remuxed), options) else {
+                let _ = fs::remove_file(&remuxed);
+                return Ok(Some(warning));
+            };
+            let result = crate::preview::run_ffmpeg_step(ffmpeg, args, None, None, None)
+                .await
+                .and_then(|()| fs::rename(&remuxed, output).map_err(AppError::from));
+            match result {
+                Ok(()) => warning.remuxed = true,
+                Err(AppError::Aborted) => {
+                    let _ = fs::remove_file(&remuxed);
+                    return Err(AppError::Aborted);
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&remuxed);
+                    log::warn!(
+                        target: "tiny_vid::commands",
+                        "apply_larger_output_policy: remux failed, keeping the encode: {}",
+                        e
+                    );
+                }
+            }
+        }
+    }
+    Ok(Some(warning))
+}
+
+/// Scores `output` against `input` when `options.vmaf_check` is set. Failures are logged and
+/// leave the export as it is.
+pub(crate) async fn score_vmaf(
+    ffmpeg: &FfmpegContext,
+    input: &str,
+    output: &std::path::Path,
+    options: &TranscodeOptions,
+) -> Option<VmafScore> {
+    if !options.effective_vmaf_check() {
+        return None;
+    }
+    let result = tauri::async_runtime::spawn_blocking({
+        let (ffmpeg, input, output, options) = (
+            ffmpeg.clone(),
+            PathBuf::from(input),
+            output.to_path_buf(),
+            options.clone(),
+        );
+        move || score_export_vmaf(&ffmpeg, &input, &output, &options)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))
+    .and_then(|r| r);
+    result.unwrap_or_else(|e| {
+        log::warn!(
+            target: "tiny_vid::commands",
+            "score_vmaf: could not score {}: {}",
+            output.display(),
+            e
+        );
+        None
+    })
+}
+
+/// Remuxes `output` in place with cover art (chosen frame or image) when requested.
+/// No-op for containers without cover art support.
+pub(crate) async fn embed_cover_art(
+    ffmpeg: &FfmpegContext,
+    input: &str,
+    output: &std::path::Path,
+    options: &TranscodeOptions,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<(), AppError> {
+    let Some((source, at_secs)) = options.cover_art_source(input) else {
+        return Ok(());
+    };
+    let format = options.effective_output_format();
+    let cover = ffmpeg.create_temp("cover.jpg", None)?;
+    let muxed = ffmpeg.create_temp(&format!("cover-output.{}", format), None)?;
+    let Some(embed_args) = build_embed_cover_args(
+        &path_to_string(output),
+        &path_to_string(&cover),
+        &path_to_string(&muxed),
+        &format,
+    ) else {
+        log::warn!(
+            target: "tiny_vid::commands",
+            "embed_cover_art: {} does not support cover art, skipping",
+            format
+        );
+        let _ = fs::remove_file(&cover);
+        let _ = fs::remove_file(&muxed);
+        return Ok(());
+    };
+    let cover_args = build_cover_image_args(source, &path_to_string(&cover), at_secs);
+    let emit = Some((app, window_label));
+    let result =
+        match crate::preview::run_ffmpeg_step(ffmpeg, cover_args, emit, None, None).await {
+            Ok(()) => crate::preview::run_ffmpeg_step(ffmpeg, embed_args, emit, None, None).await,
+            Err(e) => Err(e),
+        }
+        .and_then(|()| fs::rename(&muxed, output).map_err(AppError::from));
+    let _ = fs::remove_file(&cover);
+    if result.is_err() {
+        let _ = fs::remove_file(&muxed);
+    }
+    result
+}
+
+/// Probes the output of an `ignore_errors` transcode and reports how much of the source survived.
+async fn emit_salvage_report(
+    ffmpeg: &FfmpegContext,
+    app: &tauri::AppHandle,
+    window_label: &str,
+    output_path: &std::path::Path,
+    source_secs: f64,
+) {
+    let probed = crate::preview::get_video_metadata_async(ffmpeg, output_path).await;
+    match probed {
+        Ok(meta) => {
+            let report = SalvageReport::new(source_secs, meta.duration);
+            log::info!(
+                target: "tiny_vid::commands",
+                "ffmpeg_transcode_to_temp: salvaged {:.2}s of {:.2}s",
+                report.salvaged_seconds,
+                report.source_seconds
+            );
+            let _ = app.emit_to(
+                window_label,
+                "ffmpeg-salvage-report",
+                Versioned::new(report),
+            );
+        }
+        Err(e) => log::warn!(
+            target: "tiny_vid::commands",
+            "ffmpeg_transcode_to_temp: could not probe salvaged output: {}",
+            e
+        ),
+    }
+}
+
+/// With `on_progress`, progress is sent to that channel instead of ffmpeg-progress events.
+/// `request_id` (chosen by the caller) cancels it through `ffmpeg_cancel_request`.
 #[tauri::command(rename_all = "camelCase")]
 pub async fn ffmpeg_transcode_to_temp(
     input_path: PathBuf,
     options: TranscodeOptions,
+    request_id: Option<String>,
+    on_progress: Option<ProgressChannel>,
     app: tauri::AppHandle,
     window: tauri::Window,
-) -> Result<String, AppError> {
+) -> Result<TempOutput, AppError> {
     log::info!(
         target: "tiny_vid::commands",
         "ffmpeg_transcode_to_temp: input={}",
         input_path.display()
     );
-    cleanup_transcode_temp();
+    let job = TempJob::start();
+    let cancellation = JobCancellation::register_request(job.id(), request_id);
+    let ffmpeg = FfmpegContext::discover()?
+        .with_job_id(job.id())
+        .with_cancellation(cancellation.token().clone());
+    let _live_stderr = LiveStderr::register(job.id());
+    let cpu_meter = CpuMeter::register(job.id());
 
     let ext = options.effective_output_format();
     let suffix = format!("transcode-output.{}", ext);
 
-    let temp = TempFileManager;
-    let output_path = temp.create(&suffix, None).map_err(AppError::from)?;
+    let output_path = job
+        .create(&ffmpeg.temp_dir(), &suffix, None)
+        .map_err(AppError::from)?;
     let output_str = path_to_string(&output_path);
 
-    set_transcode_temp(Some(output_path.clone()));
+    let commit_token = register_transcode_temp(&job, output_path.clone(), &[input_path.as_path()]);
 
-    let args = build_ffmpeg_command(
-        &path_to_string(&input_path),
-        &output_str,
-        &options,
-        None,
-        None,
-        None,
-    )?;
-    let duration_secs = options.duration_secs;
+    // Shorter than the source when keep_segments cuts silence.
+    let duration_secs = options.output_duration_secs();
     let window_label = window.label().to_string();
-    let progress_callback =
-        crate::preview::make_progress_emitter(app.clone(), window_label.clone(), "transcode");
+    let progress = ProgressSink::new(&app, &window_label, on_progress);
+    let _ = app.emit_to(
+        &window_label,
+        "ffmpeg-job-started",
+        Versioned::new(JobStarted {
+            job_id: job.id(),
+            kind: JobKind::Transcode,
+        }),
+    );
 
-    match crate::preview::run_ffmpeg_step(
-        args,
-        Some((&app, &window_label)),
-        duration_secs,
-        Some(progress_callback),
+    let input_str = path_to_string(&input_path);
+    crate::webhook::job_started(job.id(), &input_str);
+    let started = std::time::Instant::now();
+    let mut larger_output = None;
+    let result = match run_transcode(
+        &ffmpeg,
+        &input_str,
+        &output_str,
+        &options,
+        &app,
+        &window_label,
+        &progress,
     )
     .await
     {
+        Ok(()) => {
+            match apply_larger_output_policy(&ffmpeg, &input_str, &output_path, &options).await {
+                Ok(warning) => {
+                    larger_output = warning;
+                    embed_cover_art(
+                        &ffmpeg,
+                        &input_str,
+                        &output_path,
+                        &options,
+                        &app,
+                        &window_label,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    crate::webhook::job_finished(job.id(), result.as_ref().map(|()| output_str.as_str()));
+    let energy = JobEnergy::new(
+        options.effective_codec(),
+        cpu_meter.cpu_seconds(),
+        started.elapsed().as_secs_f64(),
+    );
+    match result {
         Ok(()) => {
             log::info!(
                 target: "tiny_vid::commands",
                 "ffmpeg_transcode_to_temp: complete -> {}",
                 output_str
             );
-            let _ = app.emit_to(&window_label, "ffmpeg-complete", ());
-            Ok(output_str)
+            if options.effective_ignore_errors()
+                && let Some(source_secs) = duration_secs.filter(|d| *d > 0.0)
+            {
+                emit_salvage_report(&ffmpeg, &app, &window_label, &output_path, source_secs).await;
+            }
+            let vmaf = score_vmaf(&ffmpeg, &input_str, &output_path, &options).await;
+            let _ = app.emit_to(&window_label, "ffmpeg-complete", crate::events::complete());
+            Ok(TempOutput {
+                path: output_str,
+                commit_token,
+                larger_output,
+                vmaf,
+                energy: Some(energy),
+            })
         }
         Err(e) => {
-            cleanup_transcode_temp();
+            discard_transcode_temp(job.id());
             Err(e)
         }
     }
 }
 
+/// With `on_progress`, progress is sent to that channel instead of ffmpeg-progress events.
+/// `request_id` (chosen by the caller) cancels it through `ffmpeg_cancel_request`.
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn ffmpeg_preview(
     input_path: PathBuf,
     options: TranscodeOptions,
     preview_start_seconds: Option<f64>,
     include_estimate: bool,
+    request_id: Option<String>,
+    on_progress: Option<ProgressChannel>,
     app: tauri::AppHandle,
     window: tauri::Window,
 ) -> Result<PreviewWithEstimateResult, AppError> {
+    let job_id = TempJob::start().id();
+    let cancellation = JobCancellation::register_request(job_id, request_id);
+    let ffmpeg = FfmpegContext::discover()?
+        .with_job_id(job_id)
+        .with_cancellation(cancellation.token().clone());
+    let _live_stderr = LiveStderr::register(job_id);
+    let _ = app.emit_to(
+        window.label(),
+        "ffmpeg-job-started",
+        Versioned::new(JobStarted {
+            job_id,
+            kind: JobKind::Preview,
+        }),
+    );
     let emit = Some((app, window.label().to_string()));
     if include_estimate {
-        let result =
-            run_preview_with_estimate_core(&input_path, &options, preview_start_seconds, emit)
-                .await?;
+        let mut result = run_preview_with_estimate_core(
+            &ffmpeg,
+            &input_path,
+            &options,
+            preview_start_seconds,
+            emit,
+            on_progress,
+        )
+        .await?;
+        result.job_id = Some(job_id);
         Ok(result)
     } else {
         let result = run_preview_core(
+            &ffmpeg,
             &input_path,
             &options,
             preview_start_seconds,
             emit,
-            None,
-            None,
+            on_progress.map(PreviewProgressCtx::for_channel),
             None,
         )
         .await?;
         Ok(PreviewWithEstimateResult {
             preview: result,
             estimate: None,
+            size_summary: None,
+            compression: None,
+            larger_output: None,
+            job_id: Some(job_id),
         })
     }
 }
@@ -220,14 +809,23 @@ pub fn preview_media_bytes(path: PathBuf) -> Result<Vec<u8>, AppError> {
 }
 
 #[tauri::command(rename_all = "camelCase")]
-pub fn get_video_metadata(path: PathBuf) -> Result<VideoMetadataResult, AppError> {
+pub fn get_video_metadata(
+    path: PathBuf,
+    quick: Option<bool>,
+    target_codec: Option<String>,
+) -> Result<VideoMetadataResult, AppError> {
     log::debug!(
         target: "tiny_vid::commands",
         "get_video_metadata: path={}",
         path.display()
     );
-    let meta = get_video_metadata_impl(&path)?;
-    Ok(meta.into())
+    let ffmpeg = FfmpegContext::discover()?;
+    let meta = get_video_metadata_in(&ffmpeg, &path, quick.unwrap_or(false))?;
+    let generation_loss = generation_loss_advisory(&meta, target_codec.as_deref());
+    Ok(VideoMetadataResult {
+        generation_loss,
+        ..meta.into()
+    })
 }
 
 #[tauri::command(rename_all = "camelCase")]
@@ -248,6 +846,18 @@ pub fn ffmpeg_terminate() {
     terminate_all_ffmpeg();
 }
 
+/// Cancels one running job (id from ffmpeg-job-started), leaving other jobs running.
+#[tauri::command(rename_all = "camelCase")]
+pub fn ffmpeg_cancel_job(job_id: u64) -> Result<(), AppError> {
+    crate::ffmpeg::cancel_job(job_id)
+}
+
+/// Cancels the preview or transcode started with `request_id`, even if it has not started yet.
+#[tauri::command(rename_all = "camelCase")]
+pub fn ffmpeg_cancel_request(request_id: String) {
+    crate::ffmpeg::cancel_request(&request_id);
+}
+
 #[tauri::command(rename_all = "camelCase")]
 pub fn get_pending_opened_files(state: tauri::State<'_, AppState>) -> Vec<String> {
     let mut files = state.pending_opened_files.lock();
@@ -268,26 +878,20 @@ pub fn buffer_opened_files(app: &tauri::AppHandle, files: Vec<PathBuf>) {
         let mut pending = state.pending_opened_files.lock();
         pending.extend(files);
     }
-    let _ = app.emit("open-file", paths);
+    let _ = app.emit("open-file", Versioned::new(OpenFiles { paths }));
 }
 
-#[tauri::command(rename_all = "camelCase")]
-pub fn move_compressed_file(source: PathBuf, dest: PathBuf) -> Result<(), AppError> {
-    log::info!(
-        target: "tiny_vid::commands",
-        "move_compressed_file: {} -> {}",
-        source.display(),
-        dest.display()
-    );
-    match fs::rename(&source, &dest) {
+/// Renames `source` to `dest`, copying across devices.
+pub(crate) fn move_file(source: &std::path::Path, dest: &std::path::Path) -> Result<(), AppError> {
+    match fs::rename(source, dest) {
         Ok(()) => {
-            log::debug!(target: "tiny_vid::commands", "move_compressed_file: complete");
+            log::debug!(target: "tiny_vid::commands", "move_file: complete");
             Ok(())
         }
         Err(e) => {
             if is_cross_device_rename_error(&e) {
-                fs::copy(&source, &dest)?;
-                fs::remove_file(&source)?;
+                fs::copy(source, dest)?;
+                fs::remove_file(source)?;
                 return Ok(());
             }
             Err(e.into())
@@ -295,48 +899,900 @@ pub fn move_compressed_file(source: PathBuf, dest: PathBuf) -> Result<(), AppErr
     }
 }
 
+/// Moves the output behind `commit_token` to `dest`. When the move fails the output stays
+/// registered, so the same token can be retried with another destination.
 #[tauri::command(rename_all = "camelCase")]
-pub fn cleanup_temp_file(path: PathBuf) -> Result<(), AppError> {
+pub fn commit_temp_output(
+    commit_token: String,
+    dest: PathBuf,
+    original_path: Option<PathBuf>,
+) -> Result<String, AppError> {
+    let (job_id, source) = transcode_temp_for_token(&commit_token)
+        .ok_or_else(|| AppError::from("The output has expired or was already saved"))?;
     log::info!(
         target: "tiny_vid::commands",
-        "cleanup_temp_file: path={}",
-        path.display()
+        "commit_temp_output: job {} {} -> {}",
+        job_id,
+        source.display(),
+        dest.display()
     );
-    let _ = fs::remove_file(&path);
-    cleanup_transcode_temp();
+    if let Some(original) = &original_path {
+        crate::source_guard::ensure_not_source(original, &dest)?;
+    }
+    ensure_not_job_source(&dest)?;
+    move_file(&source, &dest)?;
+    commit_transcode_temp(job_id);
+    Ok(path_to_string(&dest))
+}
+
+/// Deletes the output behind `commit_token`. Unknown or expired tokens are ignored.
+#[tauri::command(rename_all = "camelCase")]
+pub fn discard_temp_output(commit_token: String) -> Result<(), AppError> {
+    if let Some((job_id, _)) = transcode_temp_for_token(&commit_token) {
+        log::info!(
+            target: "tiny_vid::commands",
+            "discard_temp_output: job {}",
+            job_id
+        );
+        discard_transcode_temp(job_id);
+    }
     Ok(())
 }
 
+/// Quick mode: probes, encodes and saves `input_path` for `platform_preset` in one call,
+/// without preview or estimate (see `quick`). Returns where the output was saved.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn quick_compress(
+    input_path: PathBuf,
+    platform_preset: PlatformPreset,
+    on_progress: Option<ProgressChannel>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<String, AppError> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "quick_compress: input={}, platform={:?}",
+        input_path.display(),
+        platform_preset
+    );
+    let ffmpeg = FfmpegContext::discover()?;
+    let (meta, codec) = tauri::async_runtime::spawn_blocking({
+        let input_path = input_path.clone();
+        move || -> Result<_, AppError> {
+            let meta = get_video_metadata_in(&ffmpeg, &input_path, false)?;
+            let codec = crate::preview::resolve_preview_original_codec(ffmpeg.ffmpeg_path(), None);
+            Ok((meta, codec))
+        }
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    let options = crate::quick::quick_options(&meta, platform_preset, codec)?;
+    let dest = crate::quick::output_path(&input_path, platform_preset);
+    let output =
+        ffmpeg_transcode_to_temp(input_path.clone(), options, None, on_progress, app, window)
+            .await?;
+    commit_temp_output(output.commit_token, dest, Some(input_path))
+}
+
 #[tauri::command(rename_all = "camelCase")]
 pub async fn extract_first_frame(
     input_path: PathBuf,
     quality: u32,
     scale: f64,
-) -> Result<String, AppError> {
+) -> Result<TempOutput, AppError> {
     log::info!(
         target: "tiny_vid::commands",
         "extract_first_frame: input={}",
         input_path.display()
     );
 
-    let temp = TempFileManager;
-    let output_path = temp.create("frame.jpg", None).map_err(AppError::from)?;
+    let ffmpeg = FfmpegContext::discover()?;
+    let job = TempJob::start();
+    let output_path = job
+        .create(&ffmpeg.temp_dir(), "frame.jpg", None)
+        .map_err(AppError::from)?;
     let output_str = path_to_string(&output_path);
+    let commit_token = register_transcode_temp(&job, output_path.clone(), &[input_path.as_path()]);
 
     let args = build_first_frame_args(&path_to_string(&input_path), &output_str, quality, scale);
 
-    crate::preview::run_ffmpeg_step(args, None, None, None).await?;
+    if let Err(e) = crate::preview::run_ffmpeg_step(&ffmpeg, args, None, None, None).await {
+        discard_transcode_temp(job.id());
+        return Err(e);
+    }
 
     log::info!(
         target: "tiny_vid::commands",
         "extract_first_frame: complete -> {}",
         output_str
     );
-    Ok(output_str)
+    Ok(TempOutput {
+        path: output_str,
+        commit_token,
+        larger_output: None,
+        vmaf: None,
+        energy: None,
+    })
+}
+
+/// Default bounding square (px) of `get_video_thumbnail`.
+const THUMBNAIL_DEFAULT_MAX_SIZE: u32 = 640;
+
+/// Extracts the frame at `timestamp` seconds (clamped to the video) as a JPEG or PNG poster
+/// in temp and returns its path. `max_size` bounds the longer side (default 640px); `format`
+/// is "jpg" (default) or "png".
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_video_thumbnail(
+    path: PathBuf,
+    timestamp: f64,
+    max_size: Option<u32>,
+    format: Option<String>,
+) -> Result<String, AppError> {
+    let format = match format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("jpg") | Some("jpeg") => "jpg",
+        Some("png") => "png",
+        Some(other) => {
+            return Err(AppError::from(format!(
+                "Unsupported thumbnail format: {}",
+                other
+            )));
+        }
+    };
+    let max_size = max_size
+        .filter(|s| *s > 0)
+        .unwrap_or(THUMBNAIL_DEFAULT_MAX_SIZE);
+    log::info!(
+        target: "tiny_vid::commands",
+        "get_video_thumbnail: input={}, timestamp={}, max_size={}",
+        path.display(),
+        timestamp,
+        max_size
+    );
+
+    let ffmpeg = FfmpegContext::discover()?;
+    let meta = tauri::async_runtime::spawn_blocking({
+        let (ffmpeg, path) = (ffmpeg.clone(), path.clone());
+        move || get_video_metadata_in(&ffmpeg, &path, true)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    // Seeking to (or past) the very end decodes no frame; stay a little before it.
+    let at_secs = if meta.duration > 0.0 {
+        timestamp.min((meta.duration - 0.1).max(0.0))
+    } else {
+        timestamp
+    };
+
+    let output_path = ffmpeg.create_temp(&format!("thumbnail.{}", format), None)?;
+    let args = build_thumbnail_args(
+        &path_to_string(&path),
+        &path_to_string(&output_path),
+        at_secs,
+        max_size,
+        format,
+    );
+    if let Err(e) = crate::preview::run_ffmpeg_step(&ffmpeg, args, None, None, None).await {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
+    }
+    Ok(path_to_string(&output_path))
+}
+
+/// Renders (or returns the cached) 1s, 10fps, 320px looping clip for file-list hover previews.
+/// `format` is "mp4" (default) or "webp" (requires an FFmpeg build with libwebp).
+#[tauri::command(rename_all = "camelCase")]
+pub async fn generate_hover_preview(
+    input_path: PathBuf,
+    format: Option<String>,
+) -> Result<String, AppError> {
+    let format = format.unwrap_or_else(|| "mp4".to_string()).to_lowercase();
+    let input_str = path_to_string(&input_path);
+    let signature = file_signature(&input_path);
+    if let Some(cached) = get_cached_hover_preview(&input_str, &format, signature.as_ref()) {
+        return Ok(path_to_string(&cached));
+    }
+    log::info!(
+        target: "tiny_vid::commands",
+        "generate_hover_preview: input={}, format={}",
+        input_path.display(),
+        format
+    );
+
+    let ffmpeg = FfmpegContext::discover()?;
+    let (meta, available) = tauri::async_runtime::spawn_blocking({
+        let (ffmpeg, input_path) = (ffmpeg.clone(), input_path.clone());
+        move || -> Result<_, AppError> {
+            Ok((
+                get_video_metadata_in(&ffmpeg, &input_path, true)?,
+                crate::ffmpeg::discovery::get_available_codecs()?,
+            ))
+        }
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    let encoder = match format.as_str() {
+        "webp" => "libwebp_anim",
+        "mp4" => ["libx264", "h264_videotoolbox"]
+            .into_iter()
+            .find(|c| available.iter().any(|a| a == c))
+            .ok_or_else(|| AppError::from("No H.264 encoder available for hover preview"))?,
+        other => {
+            return Err(AppError::from(format!(
+                "Unsupported hover preview format: {}",
+                other
+            )));
+        }
+    };
+    // Skip intros/black leaders: start 10% in, but keep the clip inside the video.
+    let start_secs = (meta.duration * 0.1)
+        .min(meta.duration - HOVER_PREVIEW_SECONDS)
+        .max(0.0);
+
+    let output_path = ffmpeg.create_temp(&format!("hover-preview.{}", format), None)?;
+    let args = build_hover_preview_args(
+        &input_str,
+        &path_to_string(&output_path),
+        start_secs,
+        encoder,
+    );
+    if let Err(e) = crate::preview::run_ffmpeg_step(&ffmpeg, args, None, None, None).await {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
+    }
+    set_cached_hover_preview(&input_str, &format, signature.as_ref(), output_path.clone());
+    Ok(path_to_string(&output_path))
+}
+
+/// Encodes `overlay_path` picture-in-picture over `main_path` into a temp file (same temp
+/// lifecycle as `ffmpeg_transcode_to_temp`). Progress is reported as the `compose` step, to
+/// `on_progress` when given.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn compose_picture_in_picture(
+    main_path: PathBuf,
+    overlay_path: PathBuf,
+    layout: PipLayout,
+    options: TranscodeOptions,
+    on_progress: Option<ProgressChannel>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<TempOutput, AppError> {
+    layout.validate()?;
+    log::info!(
+        target: "tiny_vid::commands",
+        "compose_picture_in_picture: main={}, overlay={}, position={:?}",
+        main_path.display(),
+        overlay_path.display(),
+        layout.position
+    );
+    let job = TempJob::start();
+    let cancellation = JobCancellation::register(job.id());
+    let ffmpeg = FfmpegContext::discover()?
+        .with_job_id(job.id())
+        .with_cancellation(cancellation.token().clone());
+    let meta = tauri::async_runtime::spawn_blocking({
+        let (ffmpeg, main_path) = (ffmpeg.clone(), main_path.clone());
+        move || get_video_metadata_in(&ffmpeg, &main_path, true)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    let options = TranscodeOptions {
+        duration_secs: options.duration_secs.or(Some(meta.duration)),
+        ..options
+    };
+    let _live_stderr = LiveStderr::register(job.id());
+    let output_path = job.create(
+        &ffmpeg.temp_dir(),
+        &format!("compose-output.{}", options.effective_output_format()),
+        None,
+    )?;
+    let output_str = path_to_string(&output_path);
+    let commit_token = register_transcode_temp(
+        &job,
+        output_path.clone(),
+        &[main_path.as_path(), overlay_path.as_path()],
+    );
+
+    let args = build_pip_compose_args(
+        &path_to_string(&main_path),
+        &path_to_string(&overlay_path),
+        &output_str,
+        &options,
+        &layout,
+        meta.width,
+    )?;
+    let window_label = window.label().to_string();
+    let _ = app.emit_to(
+        &window_label,
+        "ffmpeg-job-started",
+        Versioned::new(JobStarted {
+            job_id: job.id(),
+            kind: JobKind::Compose,
+        }),
+    );
+    let emit_progress = crate::preview::make_progress_emitter(
+        ProgressSink::new(&app, &window_label, on_progress),
+        "compose",
+    );
+    let job_id = job.id();
+    let progress_callback: Arc<dyn Fn(f64) + Send + Sync> = Arc::new(move |p: f64| {
+        emit_progress(p);
+        crate::webhook::job_progress(Some(job_id), p);
+    });
+    crate::webhook::job_started(job_id, &path_to_string(&main_path));
+    let result = crate::preview::run_ffmpeg_step(
+        &ffmpeg,
+        args,
+        Some((&app, &window_label)),
+        options.duration_secs,
+        Some(progress_callback),
+    )
+    .await;
+    crate::webhook::job_finished(job_id, result.as_ref().map(|()| output_str.as_str()));
+    match result {
+        Ok(()) => {
+            log::info!(
+                target: "tiny_vid::commands",
+                "compose_picture_in_picture: complete -> {}",
+                output_str
+            );
+            let _ = app.emit_to(&window_label, "ffmpeg-complete", crate::events::complete());
+            Ok(TempOutput {
+                path: output_str,
+                commit_token,
+                larger_output: None,
+                vmaf: None,
+                energy: None,
+            })
+        }
+        Err(e) => {
+            discard_transcode_temp(job.id());
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_build_variant() -> Result<BuildVariantResult, AppError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let available = crate::ffmpeg::discovery::get_available_codecs()?;
+        let ffmpeg = crate::ffmpeg::discovery::get_ffmpeg_path()?;
+        let working = crate::ffmpeg::hw_probe::filter_working_encoders(ffmpeg, available);
+        crate::codec::get_build_variant(working)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Probe results for hardware encoders. `refresh` re-runs the probes instead of using the cache.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_encoder_health(refresh: Option<bool>) -> Result<Vec<EncoderHealth>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let available = crate::ffmpeg::discovery::get_available_codecs()?;
+        let ffmpeg = crate::ffmpeg::discovery::get_ffmpeg_path()?;
+        Ok(crate::ffmpeg::hw_probe::probe_hardware_encoders(
+            ffmpeg,
+            &available,
+            refresh.unwrap_or(false),
+        ))
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Diagnoses the installation: ffmpeg/ffprobe spawn, per-codec test encode, temp dir writability.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_self_test() -> Result<SelfTestReport, AppError> {
+    tauri::async_runtime::spawn_blocking(crate::ffmpeg::self_test::run_self_test)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// Renders a `cols` x `rows` contact sheet, `width` px wide, with timestamps burned in.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_contact_sheet(
+    path: PathBuf,
+    cols: u32,
+    rows: u32,
+    width: u32,
+) -> Result<ContactSheetResult, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::contact_sheet::export_contact_sheet(&ctx, &path, cols, rows, width)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Renders (or returns the cached) seek bar storyboard: a sprite of `frames` tiles (default
+/// 100), `tile_width` px wide (default 160), and its JSON index.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn generate_storyboard(
+    path: PathBuf,
+    frames: Option<u32>,
+    tile_width: Option<u32>,
+) -> Result<Storyboard, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::storyboard::generate_storyboard(&ctx, &path, frames, tile_width)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Detects silent ranges in the first audio stream and the ranges a jump-cut export keeps.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn analyze_silence(
+    path: PathBuf,
+    noise_db: Option<f64>,
+    min_silence_secs: Option<f64>,
+    padding_secs: Option<f64>,
+) -> Result<SilenceAnalysis, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::silence::analyze_silence(
+            &ctx,
+            &path,
+            noise_db,
+            min_silence_secs,
+            padding_secs,
+        )
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Finds black/frozen frames at the start and end; the suggested trim is a single keep segment.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn analyze_dead_frames(path: PathBuf) -> Result<DeadFrameAnalysis, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::dead_frames::analyze_dead_frames(&ctx, &path)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Samples motion and noise and suggests content-aware options (screencast, animation, film).
+#[tauri::command(rename_all = "camelCase")]
+pub async fn suggest_options(path: PathBuf) -> Result<SuggestedOptions, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::suggest::suggest_options(&ctx, &path)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Suggests a quality for `codec` from the source's bitrate and resolution, lowered for lean
+/// sources so the output does not grow; flags when even that is predicted to.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn suggest_quality(path: PathBuf, codec: String) -> Result<QualitySuggestion, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::suggest::suggest_quality(&ctx, &path, &codec)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Merges the batch base options with each file's metadata and overrides, plans destinations
+/// and evaluates skip rules, without encoding.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn resolve_batch_jobs(
+    base: TranscodeOptions,
+    files: Vec<BatchFile>,
+    settings: Option<BatchSettings>,
+) -> Result<Vec<ResolvedBatchJob>, AppError> {
+    let settings = settings.unwrap_or_default();
+    let ffmpeg = FfmpegContext::discover()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::batch::resolve_batch_jobs(&ffmpeg, &base, &files, &settings)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Dry run: probes every file and predicts output sizes with the estimator, without encoding.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn analyze_batch(
+    base: TranscodeOptions,
+    files: Vec<BatchFile>,
+    settings: Option<BatchSettings>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<BatchReport, AppError> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "analyze_batch: {} file(s)",
+        files.len()
+    );
+    let settings = settings.unwrap_or_default();
+    let ffmpeg = FfmpegContext::discover()?;
+    let jobs = tauri::async_runtime::spawn_blocking(move || {
+        crate::batch::resolve_batch_jobs(&ffmpeg, &base, &files, &settings)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    let window_label = window.label().to_string();
+    crate::batch::analyze_batch_jobs(jobs, &app, &window_label).await
+}
+
+/// Transcodes every file with its effective options, one at a time. Outputs with a destination
+/// (explicit or from the layout) are moved there; the rest stay in temp. Files matching the skip
+/// rules are skipped. Afterwards the batch manifest is built and, when configured, written to disk.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_batch(
+    base: TranscodeOptions,
+    files: Vec<BatchFile>,
+    settings: Option<BatchSettings>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<Vec<BatchJobResult>, AppError> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "run_batch: {} file(s)",
+        files.len()
+    );
+    let settings = settings.unwrap_or_default();
+    let manifest_target = manifest_target(&settings)?;
+    let checksum = settings.checksum;
+    let ffmpeg = FfmpegContext::discover()?;
+    let jobs = tauri::async_runtime::spawn_blocking(move || {
+        crate::batch::resolve_batch_jobs(&ffmpeg, &base, &files, &settings)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    let window_label = window.label().to_string();
+    let results = crate::batch::run_batch_jobs(jobs, checksum, &app, &window_label).await?;
+    record_manifest(&results, manifest_target).await?;
+    Ok(results)
+}
+
+/// Moves batch outputs left in temp to their destinations as one set: either every file is
+/// delivered or none is, with overwritten destinations restored. Only outputs still pending
+/// (see `BatchJobResult::commit_token`) are accepted. Returns the destinations.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn commit_batch_outputs(items: Vec<BatchCommitItem>) -> Result<Vec<String>, AppError> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "commit_batch_outputs: {} output(s)",
+        items.len()
+    );
+    let written = tauri::async_runtime::spawn_blocking(move || {
+        if let Some(item) = items
+            .iter()
+            .find(|item| transcode_temp_job(&item.source).is_none())
+        {
+            return Err(AppError::from(format!(
+                "Not a pending output: {}",
+                item.source.display()
+            )));
+        }
+        let written = crate::batch::commit::commit_outputs(&items)?;
+        for item in &items {
+            if let Some(job_id) = transcode_temp_job(&item.source) {
+                commit_transcode_temp(job_id);
+            }
+        }
+        Ok::<_, AppError>(written)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    Ok(written.iter().map(path_to_string).collect())
+}
+
+/// Manifest settings and destination. Resolved before encoding so a missing path fails early.
+fn manifest_target(
+    settings: &BatchSettings,
+) -> Result<Option<(BatchManifestConfig, PathBuf)>, AppError> {
+    settings
+        .manifest
+        .clone()
+        .map(|config| {
+            let path = config.resolve_path(settings.layout.as_ref())?;
+            Ok((config, path))
+        })
+        .transpose()
+}
+
+/// Builds the manifest for a finished run, writes it when configured and keeps it for
+/// `get_last_batch_manifest`. A write failure is logged, not returned.
+async fn record_manifest(
+    results: &[BatchJobResult],
+    target: Option<(BatchManifestConfig, PathBuf)>,
+) -> Result<(), AppError> {
+    let for_manifest = results.to_vec();
+    let built = tauri::async_runtime::spawn_blocking(move || {
+        let verify = target.as_ref().is_some_and(|(c, _)| c.verify);
+        let ctx = verify.then(FfmpegContext::discover).transpose()?;
+        let mut built = crate::batch::manifest::build_manifest(&for_manifest, ctx.as_ref());
+        if let Some((config, path)) = target
+            && let Err(e) = crate::batch::manifest::write_manifest(&mut built, &path, config.format)
+        {
+            log::warn!(
+                target: "tiny_vid::commands",
+                "record_manifest: could not write manifest {}: {}",
+                path.display(),
+                e
+            );
+        }
+        Ok::<_, AppError>(built)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))??;
+    crate::batch::manifest::set_last_manifest(built);
+    Ok(())
+}
+
+/// Appends files to the compression queue. Returns the whole queue.
+#[tauri::command(rename_all = "camelCase")]
+pub fn queue_add(files: Vec<BatchFile>, app: tauri::AppHandle) -> Vec<QueueItem> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "queue_add: {} file(s)",
+        files.len()
+    );
+    let items = crate::queue::add(files);
+    crate::queue::emit_updated(&app, items.clone());
+    items
+}
+
+/// Removes a queue item that is not currently being compressed.
+#[tauri::command(rename_all = "camelCase")]
+pub fn queue_remove(id: u64, app: tauri::AppHandle) -> Result<Vec<QueueItem>, AppError> {
+    let items = crate::queue::remove(id)?;
+    crate::queue::emit_updated(&app, items.clone());
+    Ok(items)
+}
+
+/// Reorders the queue; `ids` must list every item once.
+#[tauri::command(rename_all = "camelCase")]
+pub fn queue_reorder(ids: Vec<u64>, app: tauri::AppHandle) -> Result<Vec<QueueItem>, AppError> {
+    let items = crate::queue::reorder(&ids)?;
+    crate::queue::emit_updated(&app, items.clone());
+    Ok(items)
+}
+
+#[tauri::command]
+pub fn queue_list() -> Vec<QueueItem> {
+    crate::queue::list()
+}
+
+/// Compresses pending queue items one at a time with `base` and the batch `settings`, until the
+/// queue is empty. Items added meanwhile are included. Cancel with `ffmpeg_terminate`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn queue_start(
+    base: TranscodeOptions,
+    settings: Option<BatchSettings>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<Vec<BatchJobResult>, AppError> {
+    let settings = settings.unwrap_or_default();
+    let manifest_target = manifest_target(&settings)?;
+    let window_label = window.label().to_string();
+    let results = crate::queue::run_queue(base, settings, &app, &window_label).await?;
+    record_manifest(&results, manifest_target).await?;
+    Ok(results)
+}
+
+/// SHA-256 of a committed output, optionally written to `<path>.sha256`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn compute_output_checksum(
+    path: PathBuf,
+    sidecar: Option<bool>,
+) -> Result<OutputChecksum, AppError> {
+    let options = ChecksumOptions {
+        sidecar: sidecar.unwrap_or(false),
+    };
+    tauri::async_runtime::spawn_blocking(move || crate::checksum::checksum_output(&path, options))
+        .await
+        .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Manifest of the last completed `run_batch`, including its file path when one was written.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_last_batch_manifest() -> Option<BatchManifest> {
+    crate::batch::manifest::last_manifest()
+}
+
+/// Tries stream-copy fixes on a file that will not open; the repaired copy lands in the temp dir.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn repair_file(path: PathBuf) -> Result<RepairReport, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = FfmpegContext::discover()?;
+        crate::ffmpeg::repair::repair_file(&ctx, &path)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Verifies the bundled ffmpeg/ffprobe against the recorded baseline.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_ffmpeg_integrity() -> Result<IntegrityReport, AppError> {
+    tauri::async_runtime::spawn_blocking(crate::ffmpeg::integrity::check_bundled_integrity)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Restores corrupted bundled binaries from the verified backup.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn repair_bundled_ffmpeg() -> Result<IntegrityReport, AppError> {
+    log::info!(target: "tiny_vid::commands", "repair_bundled_ffmpeg: starting");
+    tauri::async_runtime::spawn_blocking(crate::ffmpeg::integrity::repair_bundled_ffmpeg)
+        .await
+        .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Checks `channel` for a newer FFmpeg build (standalone variant only).
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_ffmpeg_update(channel: UpdateChannel) -> Result<UpdateCheck, AppError> {
+    tauri::async_runtime::spawn_blocking(move || crate::ffmpeg::updater::check_for_update(channel))
+        .await
+        .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Downloads `version` as listed in `channel`'s manifest, verifies it against the manifest's
+/// checksum, and stages it; swapped in for the next launch.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn install_ffmpeg_update(
+    channel: UpdateChannel,
+    version: String,
+) -> Result<UpdateInstallResult, AppError> {
+    log::info!(
+        target: "tiny_vid::commands",
+        "install_ffmpeg_update: channel={:?}, version={}",
+        channel,
+        version
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::ffmpeg::updater::install_update(channel, &version)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Restores the previously installed FFmpeg (or the bundled one) on next launch.
+#[tauri::command(rename_all = "camelCase")]
+pub fn rollback_ffmpeg_update() -> Result<(), AppError> {
+    crate::ffmpeg::updater::rollback_update()
+}
+
+/// Valid tune values for `codec`. Empty when the encoder does not accept `-tune`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_tunes(codec: String) -> Vec<String> {
+    crate::codec::get_tunes(&codec)
+        .iter()
+        .copied()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Presets of `codec` that also pin B-frames, refs and lookahead, with the values used.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_speed_preset_tunings(codec: String) -> Vec<SpeedPresetTuning> {
+    crate::codec::get_speed_preset_tunings(&codec).to_vec()
+}
+
+/// Current job webhook, if any.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_webhook_config() -> Option<WebhookConfig> {
+    crate::webhook::config()
+}
+
+/// Sets the job webhook (POSTs job-started/progress/complete/error JSON); `None` disables it.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_webhook_config(config: Option<WebhookConfig>) -> Result<(), AppError> {
+    crate::webhook::set_config(config)
+}
+
+/// Last `lines` stderr lines of a running job (id from ffmpeg-job-started).
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_live_stderr(job_id: u64, lines: usize) -> Result<Vec<String>, AppError> {
+    crate::ffmpeg::get_live_stderr(job_id, lines)
+}
+
+/// Rate limits for progress events (ffmpeg-progress and progress channels).
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_progress_throttle() -> ProgressThrottle {
+    crate::ffmpeg::progress_throttle()
+}
+
+/// Sets the progress rate limits for runs started from now on. Returns the applied limits.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_progress_throttle(throttle: ProgressThrottle) -> Result<ProgressThrottle, AppError> {
+    crate::ffmpeg::set_progress_throttle(throttle)
+}
+
+/// Current locale for Rust-produced strings (error summaries, progress step labels).
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_locale() -> Locale {
+    crate::i18n::locale()
+}
+
+/// Selects the locale by BCP 47 tag (`de`, `es-MX`, ...); unsupported languages are an error.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_locale(locale: String) -> Result<Locale, AppError> {
+    crate::i18n::set_locale(&locale)
+}
+
+fn presets_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::from(e.to_string()))
+}
+
+/// Saved named presets, in the order they were first saved.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_presets(app: tauri::AppHandle) -> Result<Vec<NamedPreset>, AppError> {
+    crate::presets::list_presets(&presets_dir(&app)?)
+}
+
+/// Saves `options` under `name`, replacing a preset of that name. Returns all presets.
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_preset(
+    name: String,
+    options: TranscodeOptions,
+    app: tauri::AppHandle,
+) -> Result<Vec<NamedPreset>, AppError> {
+    crate::presets::save_preset(&presets_dir(&app)?, &name, options)
+}
+
+/// Deletes the preset called `name`. Returns the remaining presets.
+#[tauri::command(rename_all = "camelCase")]
+pub fn delete_preset(name: String, app: tauri::AppHandle) -> Result<Vec<NamedPreset>, AppError> {
+    crate::presets::delete_preset(&presets_dir(&app)?, &name)
+}
+
+/// Writes the presets called `names` (all when omitted) to a `.tinyvidpreset` file at `path`.
+/// Returns the written path.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_presets(
+    names: Option<Vec<String>>,
+    path: PathBuf,
+    app: tauri::AppHandle,
+) -> Result<String, AppError> {
+    let written = crate::presets::export_presets(&presets_dir(&app)?, names.as_deref(), &path)?;
+    Ok(path_to_string(&written))
+}
+
+/// Adds the presets of a `.tinyvidpreset` file, replacing same-named ones. Returns all presets.
+#[tauri::command(rename_all = "camelCase")]
+pub fn import_presets(path: PathBuf, app: tauri::AppHandle) -> Result<Vec<NamedPreset>, AppError> {
+    crate::presets::import_presets(&presets_dir(&app)?, &path)
+}
+
+/// Starts compressing videos that appear in `config.folder` with the saved preset
+/// `config.preset`. Events go to the calling window. Returns all watch folders.
+#[tauri::command(rename_all = "camelCase")]
+pub fn add_watch_folder(
+    config: WatchFolderConfig,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<Vec<WatchFolder>, AppError> {
+    let preset = crate::presets::get_preset(&presets_dir(&app)?, &config.preset)?;
+    crate::watch::add(config, preset.options, &app, window.label())
+}
+
+/// Stops a watch folder. Returns the remaining ones.
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_watch_folder(id: u64) -> Result<Vec<WatchFolder>, AppError> {
+    crate::watch::remove(id)
+}
+
+#[tauri::command]
+pub fn list_watch_folders() -> Vec<WatchFolder> {
+    crate::watch::list()
+}
+
+/// App-wide sampling of size estimates (advanced settings).
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_estimate_sampling() -> EstimateSampling {
+    crate::ffmpeg::estimate_sampling()
 }
 
+/// Sets the sampling used by estimates without their own `estimateSampling`. Returns what was
+/// applied.
 #[tauri::command(rename_all = "camelCase")]
-pub fn get_build_variant() -> Result<BuildVariantResult, AppError> {
-    let available = crate::ffmpeg::discovery::get_available_codecs()?;
-    crate::codec::get_build_variant(available)
+pub fn set_estimate_sampling(sampling: EstimateSampling) -> Result<EstimateSampling, AppError> {
+    crate::ffmpeg::set_estimate_sampling(sampling)
 }
@@ -138,29 +138,6 @@ fn ffmpeg_terminate_does_not_panic() {
     let _ = tauri::test::get_ipc_response(&window, invoke_request("ffmpeg_terminate", body));
 }
 
-#[test]
-fn move_compressed_file_renames() {
-    let app = create_test_app();
-    let window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
-        .build()
-        .expect("failed to create window");
-
-    let dir = tempfile::tempdir().unwrap();
-    let source = dir.path().join("source.mp4");
-    let dest = dir.path().join("dest.mp4");
-    fs::write(&source, b"video data").unwrap();
-
-    let body = InvokeBody::from(serde_json::json!({
-        "source": source.to_string_lossy(),
-        "dest": dest.to_string_lossy()
-    }));
-    let res = tauri::test::get_ipc_response(&window, invoke_request("move_compressed_file", body));
-    assert!(res.is_ok(), "move_compressed_file failed: {:?}", res.err());
-    assert!(!source.exists());
-    assert!(dest.exists());
-    assert_eq!(fs::read(&dest).unwrap(), b"video data");
-}
-
 #[test]
 fn get_video_metadata_nonexistent_returns_error() {
     let app = create_test_app();
@@ -237,18 +214,105 @@ fn get_pending_opened_files_returns_and_clears_buffered_paths() {
 }
 
 #[test]
-fn cleanup_temp_file_removes_file() {
+fn commit_temp_output_moves_once_per_token() {
     let app = create_test_app();
     let window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
         .build()
         .expect("failed to create window");
 
+    let job = crate::ffmpeg::TempJob::start();
+    let temp = job
+        .create(&std::env::temp_dir(), "commit-token.mp4", Some(b"output"))
+        .unwrap();
+    let token = crate::ffmpeg::register_transcode_temp(&job, temp.clone(), &[]);
     let dir = tempfile::tempdir().unwrap();
-    let path = dir.path().join("temp.mp4");
-    fs::write(&path, b"temp").unwrap();
+    let dest = dir.path().join("saved.mp4");
+    let body = InvokeBody::from(serde_json::json!({
+        "commitToken": token,
+        "dest": dest.to_string_lossy(),
+    }));
 
-    let body = InvokeBody::from(serde_json::json!({ "path": path.to_string_lossy() }));
-    let res = tauri::test::get_ipc_response(&window, invoke_request("cleanup_temp_file", body));
+    let res = tauri::test::get_ipc_response(&window, invoke_request("commit_temp_output", body));
+    assert!(res.is_ok(), "commit_temp_output failed: {:?}", res.err());
+    assert_eq!(fs::read(&dest).unwrap(), b"output");
+    assert!(!temp.exists());
+
+    let body = InvokeBody::from(serde_json::json!({
+        "commitToken": token,
+        "dest": dir.path().join("again.mp4").to_string_lossy(),
+    }));
+    let res = tauri::test::get_ipc_response(&window, invoke_request("commit_temp_output", body));
+    assert!(res.is_err(), "a committed token must not be reusable");
+}
+
+#[test]
+fn discard_temp_output_removes_file_and_is_idempotent() {
+    let app = create_test_app();
+    let window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
+        .build()
+        .expect("failed to create window");
+
+    let job = crate::ffmpeg::TempJob::start();
+    let temp = job
+        .create(&std::env::temp_dir(), "discard-token.mp4", Some(b"output"))
+        .unwrap();
+    let token = crate::ffmpeg::register_transcode_temp(&job, temp.clone(), &[]);
+
+    for _ in 0..2 {
+        let body = InvokeBody::from(serde_json::json!({ "commitToken": token }));
+        let res =
+            tauri::test::get_ipc_response(&window, invoke_request("discard_temp_output", body));
+        assert!(res.is_ok());
+    }
+    assert!(!temp.exists());
+}
+
+#[test]
+fn temp_output_commands_never_touch_a_job_source_without_original_path() {
+    let app = create_test_app();
+    let window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
+        .build()
+        .expect("failed to create window");
+
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("clip.mov");
+    fs::write(&source, b"source").unwrap();
+    let job = crate::ffmpeg::TempJob::start();
+    let temp = job
+        .create(&std::env::temp_dir(), "source-guard.mp4", Some(b"output"))
+        .unwrap();
+    let token = crate::ffmpeg::register_transcode_temp(&job, temp.clone(), &[source.as_path()]);
+
+    let refused = [
+        (
+            "commit_temp_output",
+            serde_json::json!({ "commitToken": token, "dest": source.to_string_lossy() }),
+        ),
+        (
+            "commit_batch_outputs",
+            serde_json::json!({ "items": [{
+                "source": temp.to_string_lossy(),
+                "dest": source.to_string_lossy(),
+            }] }),
+        ),
+        (
+            "commit_batch_outputs",
+            serde_json::json!({ "items": [{
+                "source": source.to_string_lossy(),
+                "dest": dir.path().join("moved.mov").to_string_lossy(),
+            }] }),
+        ),
+    ];
+    for (command, body) in refused {
+        let res =
+            tauri::test::get_ipc_response(&window, invoke_request(command, InvokeBody::from(body)));
+        assert!(res.is_err(), "{} must refuse the source", command);
+        assert_eq!(fs::read(&source).unwrap(), b"source", "{}", command);
+    }
+
+    let body = InvokeBody::from(serde_json::json!({ "commitToken": token }));
+    let res = tauri::test::get_ipc_response(&window, invoke_request("discard_temp_output", body));
     assert!(res.is_ok());
-    assert!(!path.exists());
+    assert!(!temp.exists());
+    assert_eq!(fs::read(&source).unwrap(), b"source");
 }
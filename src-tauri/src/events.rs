@@ -0,0 +1,240 @@
+//! Versioning for emitted event payloads (progress, errors, completion, job reports).
+//!
+//! Struct payloads go out as `Versioned<T>`: the payload's own fields plus `eventVersion`, so
+//! frontends and sidecar clients can tell which shape they received and upgrade independently
+//! of the core. Bump `EVENT_VERSION` when a payload changes shape. Deserializing accepts the
+//! current and the previous version; payloads from before versioning carry no `eventVersion`
+//! and read as version 1.
+//!
+//! Version 1 shapes that differ from version 2 are upgraded while reading: ffmpeg-progress sent
+//! `step` as a bare stage id (see `progress_step`), open-file was a bare list of paths (see
+//! `read_open_files`) and ffmpeg-complete was `null` (see `read_complete`).
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::ffmpeg::ProgressStep;
+
+/// Version of the payloads this build emits.
+pub const EVENT_VERSION: u32 = 2;
+/// Oldest version still accepted when deserializing.
+pub const MIN_SUPPORTED_EVENT_VERSION: u32 = EVENT_VERSION - 1;
+
+/// Payload of an event, tagged with the version of its shape.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Versioned<T> {
+    #[serde(default = "unversioned", deserialize_with = "supported_version")]
+    pub event_version: u32,
+    #[serde(flatten)]
+    pub payload: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(payload: T) -> Self {
+        Self {
+            event_version: EVENT_VERSION,
+            payload,
+        }
+    }
+}
+
+/// Payload of ffmpeg-complete, which carries nothing but its version.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Complete {}
+
+/// `Versioned<Complete>`, ready to emit.
+pub fn complete() -> Versioned<Complete> {
+    Versioned::new(Complete {})
+}
+
+/// What started a job announced with ffmpeg-job-started.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    Transcode,
+    Preview,
+    Batch,
+    Compose,
+}
+
+/// Payload of ffmpeg-job-started: the id to pass to `get_live_stderr` or `ffmpeg_cancel_job`
+/// while the job runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStarted {
+    pub job_id: u64,
+    pub kind: JobKind,
+}
+
+/// Payload of open-file: files the OS asked the app to open, first one first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenFiles {
+    pub paths: Vec<String>,
+}
+
+/// Reads an open-file payload. Version 1 emitted the paths as a bare array.
+pub fn read_open_files(value: serde_json::Value) -> serde_json::Result<Versioned<OpenFiles>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Wire {
+        V1(Vec<String>),
+        Current(Versioned<OpenFiles>),
+    }
+    Ok(match serde_json::from_value(value)? {
+        Wire::V1(paths) => Versioned {
+            event_version: 1,
+            payload: OpenFiles { paths },
+        },
+        Wire::Current(files) => files,
+    })
+}
+
+/// Reads an ffmpeg-complete payload. Version 1 emitted `null`.
+pub fn read_complete(value: serde_json::Value) -> serde_json::Result<Versioned<Complete>> {
+    if value.is_null() {
+        return Ok(Versioned {
+            event_version: 1,
+            payload: Complete {},
+        });
+    }
+    serde_json::from_value(value)
+}
+
+/// Reads `FfmpegProgressPayload::step`. Version 1 sent only the stage id, with no position;
+/// it reads as the only stage of its job.
+pub(crate) fn progress_step<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ProgressStep>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Wire {
+        V1(String),
+        Current(ProgressStep),
+    }
+    Ok(match Option::<Wire>::deserialize(deserializer)? {
+        Some(Wire::V1(stage)) => Some(ProgressStep::new(&stage, 0, 1)),
+        Some(Wire::Current(step)) => Some(step),
+        None => None,
+    })
+}
+
+fn unversioned() -> u32 {
+    1
+}
+
+fn supported_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    if (MIN_SUPPORTED_EVENT_VERSION..=EVENT_VERSION).contains(&version) {
+        Ok(version)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "unsupported event version {} (supported: {}-{})",
+            version, MIN_SUPPORTED_EVENT_VERSION, EVENT_VERSION
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Progress {
+        progress: f64,
+    }
+
+    #[test]
+    fn payload_fields_are_flattened_next_to_the_version() {
+        let value = serde_json::to_value(Versioned::new(Progress { progress: 0.5 })).unwrap();
+        assert_eq!(
+            value,
+            json!({ "eventVersion": EVENT_VERSION, "progress": 0.5 })
+        );
+        assert_eq!(
+            serde_json::to_value(complete()).unwrap(),
+            json!({ "eventVersion": EVENT_VERSION })
+        );
+        let files = Versioned::new(OpenFiles {
+            paths: vec!["/a.mp4".to_string()],
+        });
+        assert_eq!(
+            serde_json::to_value(files).unwrap(),
+            json!({ "eventVersion": EVENT_VERSION, "paths": ["/a.mp4"] })
+        );
+    }
+
+    #[test]
+    fn reads_current_previous_and_unversioned_payloads() {
+        let current: Versioned<Progress> =
+            serde_json::from_value(json!({ "eventVersion": EVENT_VERSION, "progress": 1.0 }))
+                .unwrap();
+        assert_eq!(current.event_version, EVENT_VERSION);
+        let previous: Versioned<Progress> = serde_json::from_value(
+            json!({ "eventVersion": MIN_SUPPORTED_EVENT_VERSION, "progress": 1.0 }),
+        )
+        .unwrap();
+        assert_eq!(previous.event_version, MIN_SUPPORTED_EVENT_VERSION);
+        let unversioned: Versioned<Progress> =
+            serde_json::from_value(json!({ "progress": 0.25 })).unwrap();
+        assert_eq!(unversioned.event_version, 1);
+        assert_eq!(unversioned.payload, Progress { progress: 0.25 });
+    }
+
+    #[test]
+    fn upgrades_version_1_progress_payloads() {
+        use crate::ffmpeg::FfmpegProgressPayload;
+
+        let v1: Versioned<FfmpegProgressPayload> =
+            serde_json::from_value(json!({ "progress": 0.5, "step": "generating_preview" }))
+                .unwrap();
+        assert_eq!(v1.event_version, 1);
+        assert_eq!(v1.payload.progress, 0.5);
+        assert_eq!(
+            v1.payload.step,
+            Some(ProgressStep::new("generating_preview", 0, 1))
+        );
+        let no_step: Versioned<FfmpegProgressPayload> =
+            serde_json::from_value(json!({ "progress": 0.1 })).unwrap();
+        assert_eq!(no_step.payload.step, None);
+
+        let step = ProgressStep::new("transcode_pass2", 1, 2);
+        let current = Versioned::new(FfmpegProgressPayload::new(0.75, Some(step.clone())));
+        let round_trip: Versioned<FfmpegProgressPayload> =
+            serde_json::from_value(serde_json::to_value(&current).unwrap()).unwrap();
+        assert_eq!(round_trip.payload.step, Some(step));
+    }
+
+    #[test]
+    fn upgrades_version_1_open_file_and_complete_payloads() {
+        let v1 = read_open_files(json!(["/a.mp4", "/b.mov"])).unwrap();
+        assert_eq!(v1.event_version, 1);
+        assert_eq!(v1.payload.paths, vec!["/a.mp4", "/b.mov"]);
+        let current = Versioned::new(OpenFiles {
+            paths: vec!["/a.mp4".to_string()],
+        });
+        assert_eq!(
+            read_open_files(serde_json::to_value(&current).unwrap()).unwrap(),
+            current
+        );
+
+        assert_eq!(read_complete(json!(null)).unwrap().event_version, 1);
+        assert_eq!(
+            read_complete(serde_json::to_value(complete()).unwrap()).unwrap(),
+            complete()
+        );
+    }
+
+    #[test]
+    fn rejects_versions_outside_the_supported_range() {
+        let newer = serde_json::from_value::<Versioned<Progress>>(
+            json!({ "eventVersion": EVENT_VERSION + 1, "progress": 1.0 }),
+        );
+        assert!(newer.is_err());
+        let older = serde_json::from_value::<Versioned<Progress>>(
+            json!({ "eventVersion": 0, "progress": 1.0 }),
+        );
+        assert!(older.is_err());
+    }
+}
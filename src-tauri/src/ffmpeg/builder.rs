@@ -1,9 +1,15 @@
-//! Build FFmpeg CLI args from TranscodeOptions. Maps quality/preset per codec (x264, x265, VP9, AV1, VideoToolbox).
+//! Build FFmpeg CLI args from TranscodeOptions. Maps quality/preset per codec (x264, x265, VP9, AV1, VideoToolbox, NVENC, QSV, VA-API, AMF).
 
 use std::collections::HashMap;
 use std::sync::LazyLock;
 
-use super::{RateControlMode, TranscodeOptions, compute_target_video_bitrate_kbps};
+use super::compose::PipLayout;
+use super::extra_args::{validate_output_args, validate_video_args};
+use super::{
+    BackgroundAudioMode, RateControlMode, RoiPriority, TranscodeOptions,
+    compute_target_video_bitrate_kbps,
+};
+use crate::codec::{VAAPI_RENDER_NODE, speed_preset_tuning};
 use crate::error::AppError;
 
 /// Codec variant for FFmpeg argument construction. Each variant handles its own quality, preset, and tags.
@@ -15,12 +21,36 @@ enum CodecKind {
     SvtAv1,
     VideoToolboxH264,
     VideoToolboxHevc,
+    NvencH264,
+    NvencHevc,
+    QsvH264,
+    QsvHevc,
+    VaapiH264,
+    VaapiHevc,
+    AmfH264,
+    AmfHevc,
 }
 
 impl CodecKind {
     fn from_codec_str(codec: &str) -> Self {
         let lower = codec.to_lowercase();
-        if lower.contains("hevc_videotoolbox") {
+        if lower.contains("hevc_amf") {
+            CodecKind::AmfHevc
+        } else if lower.contains("h264_amf") {
+            CodecKind::AmfH264
+        } else if lower.contains("hevc_vaapi") {
+            CodecKind::VaapiHevc
+        } else if lower.contains("h264_vaapi") {
+            CodecKind::VaapiH264
+        } else if lower.contains("hevc_qsv") {
+            CodecKind::QsvHevc
+        } else if lower.contains("h264_qsv") {
+            CodecKind::QsvH264
+        } else if lower.contains("hevc_nvenc") {
+            CodecKind::NvencHevc
+        } else if lower.contains("h264_nvenc") {
+            CodecKind::NvencH264
+        } else if lower.contains("hevc_videotoolbox") {
             CodecKind::VideoToolboxHevc
         } else if lower.contains("h264_videotoolbox") {
             CodecKind::VideoToolboxH264
@@ -45,11 +75,39 @@ impl CodecKind {
             CodecKind::SvtAv1 => "libsvtav1",
             CodecKind::VideoToolboxH264 => "h264_videotoolbox",
             CodecKind::VideoToolboxHevc => "hevc_videotoolbox",
+            CodecKind::NvencH264 => "h264_nvenc",
+            CodecKind::NvencHevc => "hevc_nvenc",
+            CodecKind::QsvH264 => "h264_qsv",
+            CodecKind::QsvHevc => "hevc_qsv",
+            CodecKind::VaapiH264 => "h264_vaapi",
+            CodecKind::VaapiHevc => "hevc_vaapi",
+            CodecKind::AmfH264 => "h264_amf",
+            CodecKind::AmfHevc => "hevc_amf",
         }
     }
 
-    fn supports_tune(&self) -> bool {
-        matches!(self, CodecKind::X264)
+    fn supports_tune(&self, tune: &str) -> bool {
+        crate::codec::get_tunes(self.ffmpeg_name()).contains(&tune)
+    }
+
+    /// Speed args that depend on the pass of a two-pass encode (`pass`), VP9 only: the first
+    /// pass only gathers stats and runs fast, the second stays at the preset's speed or slower.
+    fn build_speed_args(&self, preset: &str, pass: Option<u8>) -> Vec<String> {
+        if !matches!(self, CodecKind::VP9) {
+            return Vec::new();
+        }
+        let (deadline, cpu_used) = VP9_CPU_USED_MAP.get(preset).copied().unwrap_or(("good", 2));
+        let (deadline, cpu_used) = match pass {
+            Some(1) => ("good", VP9_FIRST_PASS_CPU_USED),
+            Some(_) => (deadline, cpu_used.min(VP9_SECOND_PASS_MAX_CPU_USED)),
+            None => (deadline, cpu_used),
+        };
+        vec![
+            "-deadline".to_string(),
+            deadline.to_string(),
+            "-cpu-used".to_string(),
+            cpu_used.to_string(),
+        ]
     }
 
     /// Build codec-specific args: preset/speed, quality/crf, tags, etc.
@@ -66,12 +124,6 @@ impl CodecKind {
 
         match self {
             CodecKind::VP9 => {
-                let (deadline, cpu_used) = VP9_CPU_USED_MAP
-                    .get(preset)
-                    .copied()
-                    .unwrap_or(("good", "2"));
-                args.extend(["-deadline".to_string(), deadline.to_string()]);
-                args.extend(["-cpu-used".to_string(), cpu_used.to_string()]);
                 args.extend(["-row-mt".to_string(), "1".to_string()]);
                 if matches!(rate_control_mode, RateControlMode::Quality) {
                     args.extend(["-b:v".to_string(), "0".to_string()]);
@@ -97,18 +149,156 @@ impl CodecKind {
                     args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
                 }
             }
+            CodecKind::NvencH264 | CodecKind::NvencHevc => {
+                let preset_val = NVENC_PRESET_MAP.get(preset).unwrap_or(&"p4");
+                args.extend(["-preset".to_string(), preset_val.to_string()]);
+                args.extend(["-rc".to_string(), "vbr".to_string()]);
+                if matches!(rate_control_mode, RateControlMode::TargetSize) {
+                    if let Some(bitrate) = target_bitrate_kbps {
+                        args.extend(["-b:v".to_string(), format!("{}k", bitrate)]);
+                    }
+                } else {
+                    // -cq is on the CRF scale; -b:v 0 lets it run unconstrained.
+                    let cq = match self {
+                        CodecKind::NvencHevc => map_linear_crf(quality, 28, 51),
+                        _ => map_linear_crf(quality, 23, 51),
+                    };
+                    args.extend([
+                        "-cq".to_string(),
+                        cq.to_string(),
+                        "-b:v".to_string(),
+                        "0".to_string(),
+                    ]);
+                }
+                if let Some(max_br) = max_bitrate {
+                    args.extend([
+                        "-maxrate".to_string(),
+                        format!("{}k", max_br),
+                        "-bufsize".to_string(),
+                        format!("{}k", max_br * 2),
+                    ]);
+                }
+                if matches!(self, CodecKind::NvencHevc) {
+                    args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+                }
+            }
+            CodecKind::QsvH264 | CodecKind::QsvHevc => {
+                let preset_val = QSV_PRESET_MAP.get(preset).unwrap_or(&"medium");
+                args.extend(["-preset".to_string(), preset_val.to_string()]);
+                if matches!(rate_control_mode, RateControlMode::TargetSize) {
+                    if let Some(bitrate) = target_bitrate_kbps {
+                        args.extend(["-b:v".to_string(), format!("{}k", bitrate)]);
+                    }
+                } else {
+                    // ICQ: -global_quality is on the CRF scale (1-51, lower is better).
+                    let global_quality = match self {
+                        CodecKind::QsvHevc => map_linear_crf(quality, 28, 51),
+                        _ => map_linear_crf(quality, 23, 51),
+                    };
+                    args.extend(["-global_quality".to_string(), global_quality.to_string()]);
+                }
+                if let Some(max_br) = max_bitrate {
+                    args.extend([
+                        "-maxrate".to_string(),
+                        format!("{}k", max_br),
+                        "-bufsize".to_string(),
+                        format!("{}k", max_br * 2),
+                    ]);
+                }
+                if matches!(self, CodecKind::QsvHevc) {
+                    args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+                }
+            }
+            CodecKind::VaapiH264 | CodecKind::VaapiHevc => {
+                // No preset: VA-API speed/quality trade-offs are driver specific.
+                if matches!(rate_control_mode, RateControlMode::TargetSize) {
+                    if let Some(bitrate) = target_bitrate_kbps {
+                        args.extend(["-b:v".to_string(), format!("{}k", bitrate)]);
+                    }
+                } else {
+                    // Constant QP on the CRF scale; the driver picks CQP when no bitrate is set.
+                    let qp = match self {
+                        CodecKind::VaapiHevc => map_linear_crf(quality, 28, 51),
+                        _ => map_linear_crf(quality, 23, 51),
+                    };
+                    args.extend(["-qp".to_string(), qp.to_string()]);
+                }
+                if let Some(max_br) = max_bitrate {
+                    args.extend([
+                        "-maxrate".to_string(),
+                        format!("{}k", max_br),
+                        "-bufsize".to_string(),
+                        format!("{}k", max_br * 2),
+                    ]);
+                }
+                if matches!(self, CodecKind::VaapiHevc) {
+                    args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+                }
+            }
+            CodecKind::AmfH264 | CodecKind::AmfHevc => {
+                let quality_val = AMF_QUALITY_MAP.get(preset).unwrap_or(&"balanced");
+                args.extend([
+                    "-usage".to_string(),
+                    "transcoding".to_string(),
+                    "-quality".to_string(),
+                    quality_val.to_string(),
+                ]);
+                if matches!(rate_control_mode, RateControlMode::TargetSize) {
+                    if let Some(bitrate) = target_bitrate_kbps {
+                        args.extend([
+                            "-rc".to_string(),
+                            "vbr_peak".to_string(),
+                            "-b:v".to_string(),
+                            format!("{}k", bitrate),
+                        ]);
+                    }
+                } else {
+                    // Constant QP on the CRF scale, same value for I, P (and B) frames.
+                    let qp = match self {
+                        CodecKind::AmfHevc => map_linear_crf(quality, 28, 51),
+                        _ => map_linear_crf(quality, 23, 51),
+                    };
+                    args.extend(["-rc".to_string(), "cqp".to_string()]);
+                    let frame_types: &[&str] = match self {
+                        CodecKind::AmfHevc => &["-qp_i", "-qp_p"],
+                        _ => &["-qp_i", "-qp_p", "-qp_b"],
+                    };
+                    for flag in frame_types {
+                        args.extend([flag.to_string(), qp.to_string()]);
+                    }
+                }
+                if let Some(max_br) = max_bitrate {
+                    args.extend([
+                        "-maxrate".to_string(),
+                        format!("{}k", max_br),
+                        "-bufsize".to_string(),
+                        format!("{}k", max_br * 2),
+                    ]);
+                }
+                if matches!(self, CodecKind::AmfHevc) {
+                    args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+                }
+            }
             CodecKind::X264 | CodecKind::X265 => {
                 args.extend(["-preset".to_string(), preset.to_string()]);
+                if matches!(self, CodecKind::X264)
+                    && let Some(tuning) = speed_preset_tuning(self.ffmpeg_name(), preset)
+                {
+                    if let Some(bframes) = tuning.bframes {
+                        args.extend(["-bf".to_string(), bframes.to_string()]);
+                    }
+                    if let Some(lookahead) = tuning.rc_lookahead {
+                        args.extend(["-rc-lookahead".to_string(), lookahead.to_string()]);
+                    }
+                }
                 if matches!(self, CodecKind::X265) {
                     args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
                 }
             }
         }
 
-        if self.supports_tune()
-            && let Some(tune_val) = tune
-            && !tune_val.is_empty()
-            && tune_val != "none"
+        if let Some(tune_val) = tune
+            && self.supports_tune(tune_val)
         {
             args.extend(["-tune".to_string(), tune_val.to_string()]);
         }
@@ -145,6 +335,177 @@ impl CodecKind {
 
         args
     }
+
+    /// Adaptive quantization mode and strength for x264/x265. `RoiPriority::Text` defaults to
+    /// the edge-aware AQ modes (x264 3, x265 4) with a slightly higher strength.
+    fn aq_settings(&self, options: &TranscodeOptions) -> (Option<u32>, Option<f64>) {
+        let text = options.roi_priority == Some(RoiPriority::Text);
+        let (max_mode, text_mode) = match self {
+            CodecKind::X264 => (3, 3),
+            CodecKind::X265 => (4, 4),
+            _ => return (None, None),
+        };
+        let mode = options
+            .aq_mode
+            .or(text.then_some(text_mode))
+            .map(|m| m.min(max_mode));
+        let strength = options
+            .effective_aq_strength()
+            .or(text.then_some(TEXT_AQ_STRENGTH));
+        (mode, strength)
+    }
+
+    /// Adaptive quantization args for x264. x265 takes the same settings as `-x265-params`
+    /// keys (see [`Self::build_x265_params`]).
+    fn build_aq_args(&self, options: &TranscodeOptions) -> Vec<String> {
+        if !matches!(self, CodecKind::X264) {
+            return Vec::new();
+        }
+        let (mode, strength) = self.aq_settings(options);
+        let mut args = Vec::new();
+        if let Some(mode) = mode {
+            args.extend(["-aq-mode".to_string(), mode.to_string()]);
+        }
+        if let Some(strength) = strength {
+            args.extend(["-aq-strength".to_string(), strength.to_string()]);
+        }
+        args
+    }
+
+    /// The one `-x265-params` value for x265, which reads no other: speed preset tuning,
+    /// adaptive quantization, the user's `-x265-params` from `extra_video_args` and reproducible
+    /// thread pinning, merged so each key appears once (a later part replaces an earlier value,
+    /// so the user's keys win over the app's but not over the pinning). Empty for other codecs.
+    fn build_x265_params(
+        &self,
+        options: &TranscodeOptions,
+        extra_video_args: &[String],
+    ) -> Vec<String> {
+        if !matches!(self, CodecKind::X265) {
+            return Vec::new();
+        }
+        let mut params: Vec<(String, Option<String>)> = Vec::new();
+        let mut set = |key: &str, value: Option<String>| match params
+            .iter_mut()
+            .find(|(k, _)| k.as_str() == key)
+        {
+            Some(existing) => existing.1 = value,
+            None => params.push((key.to_string(), value)),
+        };
+        if let Some(t) = speed_preset_tuning(self.ffmpeg_name(), options.effective_preset()) {
+            if let Some(bframes) = t.bframes {
+                set("bframes", Some(bframes.to_string()));
+            }
+            if let Some(lookahead) = t.rc_lookahead {
+                set("rc-lookahead", Some(lookahead.to_string()));
+            }
+        }
+        let (mode, strength) = self.aq_settings(options);
+        if let Some(mode) = mode {
+            set("aq-mode", Some(mode.to_string()));
+        }
+        if let Some(strength) = strength {
+            set("aq-strength", Some(strength.to_string()));
+        }
+        for param in extra_video_args
+            .chunks(2)
+            .filter(|pair| pair[0] == "-x265-params")
+            .filter_map(|pair| pair.get(1))
+            .flat_map(|value| value.split(':'))
+            .filter(|param| !param.trim().is_empty())
+        {
+            match param.split_once('=') {
+                Some((key, value)) => set(key.trim(), Some(value.to_string())),
+                None => set(param.trim(), None),
+            }
+        }
+        if options.effective_reproducible() {
+            set("pools", Some(REPRODUCIBLE_ENCODER_THREADS.to_string()));
+            set("frame-threads", Some("1".to_string()));
+        }
+        if params.is_empty() {
+            return Vec::new();
+        }
+        let value: Vec<String> = params
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{}={}", key, value),
+                None => key.clone(),
+            })
+            .collect();
+        vec!["-x265-params".to_string(), value.join(":")]
+    }
+
+    /// Global args that create the hardware device the encoder runs on. QSV gets an explicit
+    /// device (D3D11 on Windows, VA-API on Linux via `hw_any`) that frames are uploaded to;
+    /// VA-API opens the first render node.
+    fn hw_device_args(&self) -> Vec<String> {
+        match self {
+            CodecKind::QsvH264 | CodecKind::QsvHevc => [
+                "-init_hw_device",
+                "qsv=hw:hw_any",
+                "-filter_hw_device",
+                "hw",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            CodecKind::VaapiH264 | CodecKind::VaapiHevc => {
+                vec!["-vaapi_device".to_string(), VAAPI_RENDER_NODE.to_string()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Last video filter, moving software frames onto the device from `hw_device_args`.
+    fn hw_upload_filter(&self) -> Option<&'static str> {
+        match self {
+            CodecKind::QsvH264 | CodecKind::QsvHevc => {
+                Some("format=nv12,hwupload=extra_hw_frames=64")
+            }
+            CodecKind::VaapiH264 | CodecKind::VaapiHevc => Some("format=nv12,hwupload"),
+            _ => None,
+        }
+    }
+
+    fn supports_gop_size(&self) -> bool {
+        !matches!(
+            self,
+            CodecKind::VideoToolboxH264 | CodecKind::VideoToolboxHevc
+        )
+    }
+
+    /// `addroi` filter lowering the quantizer in the middle of the frame, for encoders that
+    /// read region-of-interest side data.
+    fn roi_filter(&self, options: &TranscodeOptions) -> Option<&'static str> {
+        (matches!(self, CodecKind::X264 | CodecKind::X265)
+            && options.roi_priority == Some(RoiPriority::Center))
+        .then_some("addroi=x=iw/4:y=ih/4:w=iw/2:h=ih/2:qoffset=-1/5")
+    }
+}
+
+const TEXT_AQ_STRENGTH: f64 = 1.2;
+/// Default tune for screen recordings; only x264 has it, other encoders skip it.
+const SCREEN_RECORDING_TUNE: &str = "stillimage";
+/// Keyframe interval for low-motion screen content.
+const SCREEN_RECORDING_KEYINT_SECS: f64 = 10.0;
+/// Encoder threads for reproducible output, independent of the machine and the thread limit.
+const REPRODUCIBLE_ENCODER_THREADS: u32 = 4;
+
+/// Output args for `reproducible`: a fixed encoder thread count, since the encoders' choices
+/// depend on it and it otherwise follows the machine's cores, plus bitexact encoding and
+/// muxing so no version strings or creation times end up in the file.
+fn reproducible_args() -> Vec<String> {
+    vec![
+        "-threads:v".to_string(),
+        REPRODUCIBLE_ENCODER_THREADS.to_string(),
+        "-flags:v".to_string(),
+        "+bitexact".to_string(),
+        "-flags:a".to_string(),
+        "+bitexact".to_string(),
+        "-fflags".to_string(),
+        "+bitexact".to_string(),
+    ]
 }
 
 pub fn supports_two_pass_codec(codec: &str) -> bool {
@@ -169,22 +530,78 @@ static SVTAV1_PRESET_MAP: LazyLock<HashMap<&'static str, &'static str>> = LazyLo
     .collect()
 });
 
+/// QSV -preset: veryfast-veryslow. x264 names map to themselves; faster-than-veryfast clamps.
+static QSV_PRESET_MAP: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    [
+        ("ultrafast", "veryfast"),
+        ("superfast", "veryfast"),
+        ("veryfast", "veryfast"),
+        ("faster", "faster"),
+        ("fast", "fast"),
+        ("medium", "medium"),
+        ("slow", "slow"),
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// Device setup args for hardware encoders that need one (QSV, VA-API), for commands built outside
+/// `build_ffmpeg_command` such as the encoder probe.
+pub(crate) fn hw_encoder_args(codec: &str) -> (Vec<String>, Option<&'static str>) {
+    let kind = CodecKind::from_codec_str(codec);
+    (kind.hw_device_args(), kind.hw_upload_filter())
+}
+
+/// AMF -quality: speed, balanced or quality. Maps x264-style preset names.
+static AMF_QUALITY_MAP: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    [
+        ("ultrafast", "speed"),
+        ("superfast", "speed"),
+        ("veryfast", "speed"),
+        ("faster", "balanced"),
+        ("fast", "balanced"),
+        ("medium", "balanced"),
+        ("slow", "quality"),
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// NVENC -preset: p1-p7 (p1 = fastest). Maps x264-style preset names.
+static NVENC_PRESET_MAP: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    [
+        ("ultrafast", "p1"),
+        ("superfast", "p2"),
+        ("veryfast", "p3"),
+        ("faster", "p3"),
+        ("fast", "p4"),
+        ("medium", "p5"),
+        ("slow", "p6"),
+    ]
+    .into_iter()
+    .collect()
+});
+
 /// libvpx-vp9 -cpu-used: 0-5 (0=slowest/best, 5=fastest). Maps x264-style preset names.
 /// -deadline good with cpu-used. For "slow" we use deadline best.
-static VP9_CPU_USED_MAP: LazyLock<HashMap<&'static str, (&'static str, &'static str)>> =
+static VP9_CPU_USED_MAP: LazyLock<HashMap<&'static str, (&'static str, u8)>> =
     LazyLock::new(|| {
         [
-            ("ultrafast", ("good", "4")),
-            ("superfast", ("good", "4")),
-            ("veryfast", ("good", "3")),
-            ("faster", ("good", "3")),
-            ("fast", ("good", "2")),
-            ("medium", ("good", "1")),
-            ("slow", ("best", "0")),
+            ("ultrafast", ("good", 4)),
+            ("superfast", ("good", 4)),
+            ("veryfast", ("good", 3)),
+            ("faster", ("good", 3)),
+            ("fast", ("good", 2)),
+            ("medium", ("good", 1)),
+            ("slow", ("best", 0)),
         ]
         .into_iter()
         .collect()
     });
+/// VP9 -cpu-used for the stats-only first pass of a two-pass encode.
+const VP9_FIRST_PASS_CPU_USED: u8 = 4;
+/// Fastest VP9 -cpu-used for the second pass; faster settings waste the first pass's stats.
+const VP9_SECOND_PASS_MAX_CPU_USED: u8 = 2;
 
 fn map_linear_crf(quality: u32, high_crf: i32, low_crf: i32) -> i32 {
     let q = quality.min(100) as f64 / 100.0;
@@ -292,86 +709,594 @@ fn ffmpeg_base_args() -> Vec<String> {
     ]
 }
 
-/// Build args for segment extraction (-ss -t -i -c copy or -map 0:v -c:v copy -an).
-pub fn build_extract_args(
+/// Build args encoding only the preview audio track (`preview_audio_track`, the first track
+/// the export keeps) of `[start, start + duration)` the way the export would (same codec,
+/// bitrate, downmix, passthrough), into a Matroska audio file.
+pub fn build_audio_preview_args(
     input_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
     start_secs: f64,
     duration_secs: f64,
-    output_path: &str,
-    strip_audio: bool,
 ) -> Vec<String> {
+    let config = get_output_config(
+        &options.effective_output_format(),
+        options.effective_codec(),
+    );
+    let downmix = options.effective_downmix_to_stereo();
+    let track = options.preview_audio_track().unwrap_or(0);
+    // Stream details describe the first track, so only it can be copied as is.
+    let passthrough = track == 0
+        && config.can_passthrough_audio(
+            options.audio_codec_name.as_deref(),
+            options.audio_channels,
+            downmix,
+        );
     let mut args = ffmpeg_base_args();
     args.extend([
+        "-y".to_string(),
         "-ss".to_string(),
         start_secs.to_string(),
         "-t".to_string(),
         duration_secs.to_string(),
-        "-progress".to_string(),
-        "pipe:1".to_string(),
         "-i".to_string(),
         input_path.to_string(),
+        "-map".to_string(),
+        format!("0:a:{}", track),
+        "-vn".to_string(),
     ]);
-    if strip_audio {
-        args.extend([
-            "-map".to_string(),
-            "0:v".to_string(),
-            "-c:v".to_string(),
-            "copy".to_string(),
-            "-an".to_string(),
-        ]);
+    if passthrough {
+        args.extend(["-c:a".to_string(), "copy".to_string()]);
     } else {
         args.extend([
-            "-c".to_string(),
-            "copy".to_string(),
+            "-c:a".to_string(),
+            config.audio_codec.to_string(),
+            "-b:a".to_string(),
+            format!("{}k", options.effective_audio_bitrate()),
         ]);
+        if config.requires_stereo_downmix || downmix {
+            args.extend(["-ac".to_string(), "2".to_string()]);
+        }
     }
     args.extend([
-        "-avoid_negative_ts".to_string(),
-        "make_zero".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
+        "-f".to_string(),
+        "matroska".to_string(),
         output_path.to_string(),
     ]);
     args
 }
 
-/// Build FFmpeg transcode command.
-struct BuildOverrides<'a> {
-    force_remove_audio: Option<bool>,
-    rate_control_mode: Option<RateControlMode>,
-    target_bitrate_kbps: Option<u32>,
-    pass: Option<u8>,
-    passlogfile: Option<&'a str>,
-    force_null_output: bool,
+/// Build args rendering one frame as a JPEG for cover art: the frame at `at_secs` of a video,
+/// or a still image when `at_secs` is None. Long side capped at 1280px.
+pub fn build_cover_image_args(
+    input_path: &str,
+    output_path: &str,
+    at_secs: Option<f64>,
+) -> Vec<String> {
+    let mut args = ffmpeg_base_args();
+    args.push("-y".to_string());
+    if let Some(at) = at_secs {
+        args.extend(["-ss".to_string(), at.to_string()]);
+    }
+    args.extend([
+        "-i".to_string(),
+        input_path.to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-vf".to_string(),
+        "scale='min(1280,iw)':'min(1280,ih)':force_original_aspect_ratio=decrease".to_string(),
+        "-q:v".to_string(),
+        "2".to_string(),
+        "-f".to_string(),
+        "mjpeg".to_string(),
+        output_path.to_string(),
+    ]);
+    args
 }
 
-fn build_ffmpeg_command_internal(
+/// Build args rendering the frame at `at_secs` as a poster thumbnail, scaled down (never up) to
+/// fit a `max_size` square. `format` is "png" or JPEG otherwise.
+pub fn build_thumbnail_args(
     input_path: &str,
     output_path: &str,
-    options: &TranscodeOptions,
-    output_duration_secs: Option<f64>,
-    format_override: Option<&str>,
-    start_offset_secs: Option<f64>,
-) -> Result<Vec<String>, AppError> {
-    build_ffmpeg_command_with_overrides(
-        input_path,
-        output_path,
-        options,
-        output_duration_secs,
-        format_override,
-        start_offset_secs,
-        BuildOverrides {
-            force_remove_audio: None,
-            rate_control_mode: None,
-            target_bitrate_kbps: None,
-            pass: None,
-            passlogfile: None,
-            force_null_output: false,
-        },
-    )
+    at_secs: f64,
+    max_size: u32,
+    format: &str,
+) -> Vec<String> {
+    let (codec, muxer) = match format {
+        "png" => ("png", "image2"),
+        _ => ("mjpeg", "mjpeg"),
+    };
+    let mut args = ffmpeg_base_args();
+    args.extend([
+        "-y".to_string(),
+        "-ss".to_string(),
+        at_secs.max(0.0).to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-vf".to_string(),
+        format!(
+            "scale='min({max_size},iw)':'min({max_size},ih)':force_original_aspect_ratio=decrease"
+        ),
+        "-c:v".to_string(),
+        codec.to_string(),
+    ]);
+    if codec == "mjpeg" {
+        args.extend(["-q:v".to_string(), "3".to_string()]);
+    }
+    args.extend(["-f".to_string(), muxer.to_string(), output_path.to_string()]);
+    args
 }
 
-fn build_ffmpeg_command_with_overrides(
+/// Build args remuxing `video_path` with `cover_path` (JPEG) as cover art. MP4 gets an
+/// attached_pic video stream; MKV gets a `cover.jpg` attachment. None for formats without
+/// cover art support (WebM).
+pub fn build_embed_cover_args(
+    video_path: &str,
+    cover_path: &str,
+    output_path: &str,
+    output_format: &str,
+) -> Option<Vec<String>> {
+    let mut args = ffmpeg_base_args();
+    args.extend(["-y".to_string(), "-i".to_string(), video_path.to_string()]);
+    match output_format.to_lowercase().as_str() {
+        "mp4" => args.extend([
+            "-i".to_string(),
+            cover_path.to_string(),
+            "-map".to_string(),
+            "0".to_string(),
+            "-map".to_string(),
+            "1".to_string(),
+            "-c".to_string(),
+            "copy".to_string(),
+            "-disposition:v:1".to_string(),
+            "attached_pic".to_string(),
+            "-movflags".to_string(),
+            "+faststart".to_string(),
+        ]),
+        "mkv" => args.extend([
+            "-map".to_string(),
+            "0".to_string(),
+            "-c".to_string(),
+            "copy".to_string(),
+            "-attach".to_string(),
+            cover_path.to_string(),
+            "-metadata:s:t".to_string(),
+            "mimetype=image/jpeg".to_string(),
+            "-metadata:s:t".to_string(),
+            "filename=cover.jpg".to_string(),
+        ]),
+        _ => return None,
+    }
+    args.push(output_path.to_string());
+    Some(args)
+}
+
+/// Length, frame rate and width of hover previews.
+pub const HOVER_PREVIEW_SECONDS: f64 = 1.0;
+const HOVER_PREVIEW_FPS: u32 = 10;
+const HOVER_PREVIEW_WIDTH: u32 = 320;
+
+/// Build args for a tiny silent looping clip used as a file-list hover preview.
+/// `encoder` is `libwebp_anim` (animated WebP) or an H.264 encoder (MP4).
+pub fn build_hover_preview_args(
+    input_path: &str,
+    output_path: &str,
+    start_secs: f64,
+    encoder: &str,
+) -> Vec<String> {
+    let mut args = ffmpeg_base_args();
+    args.extend([
+        "-y".to_string(),
+        "-ss".to_string(),
+        start_secs.to_string(),
+        "-t".to_string(),
+        HOVER_PREVIEW_SECONDS.to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-vf".to_string(),
+        format!("fps={HOVER_PREVIEW_FPS},scale={HOVER_PREVIEW_WIDTH}:-2"),
+        "-c:v".to_string(),
+        encoder.to_string(),
+    ]);
+    if encoder == "libwebp_anim" {
+        args.extend([
+            "-loop".to_string(),
+            "0".to_string(),
+            "-q:v".to_string(),
+            "60".to_string(),
+            "-f".to_string(),
+            "webp".to_string(),
+        ]);
+    } else {
+        if encoder == "libx264" {
+            args.extend([
+                "-preset".to_string(),
+                "veryfast".to_string(),
+                "-crf".to_string(),
+                "30".to_string(),
+            ]);
+        }
+        args.extend([
+            "-pix_fmt".to_string(),
+            "yuv420p".to_string(),
+            "-movflags".to_string(),
+            "+faststart".to_string(),
+            "-f".to_string(),
+            "mp4".to_string(),
+        ]);
+    }
+    args.push(output_path.to_string());
+    args
+}
+
+/// Build args composing `overlay_path` picture-in-picture over `main_path` (`main_width` px wide),
+/// encoded single-pass with the video/audio settings of `options`. Audio comes from the input
+/// chosen by `layout`; additional audio and subtitle streams are dropped.
+pub fn build_pip_compose_args(
+    main_path: &str,
+    overlay_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
+    layout: &PipLayout,
+    main_width: u32,
+) -> Result<Vec<String>, AppError> {
+    let output_format = options.effective_output_format();
+    let codec_str = options.effective_codec().to_string();
+    let codec_kind = CodecKind::from_codec_str(&codec_str);
+    let rate_control_mode = options.effective_rate_control_mode();
+    let target_bitrate_kbps = match rate_control_mode {
+        RateControlMode::TargetSize => Some(compute_target_video_bitrate_kbps(options)?),
+        RateControlMode::Quality => None,
+    };
+    let max_bitrate = target_bitrate_kbps
+        .is_none()
+        .then_some(options.max_bitrate)
+        .flatten();
+    let config = get_output_config(&output_format, &codec_str);
+
+    let mut args = ffmpeg_base_args();
+    args.extend([
+        "-progress".to_string(),
+        "pipe:1".to_string(),
+        "-i".to_string(),
+        main_path.to_string(),
+        "-i".to_string(),
+        overlay_path.to_string(),
+        "-filter_complex".to_string(),
+        layout.filter_graph(main_width, options.effective_scale()),
+        "-map".to_string(),
+        "[v]".to_string(),
+    ]);
+    if options.effective_remove_audio() {
+        args.push("-an".to_string());
+    } else {
+        args.extend([
+            "-map".to_string(),
+            format!("{}:a:0?", layout.audio_input()),
+            "-c:a".to_string(),
+            config.audio_codec.to_string(),
+            "-b:a".to_string(),
+            format!("{}k", options.effective_audio_bitrate()),
+        ]);
+        if config.requires_stereo_downmix || options.effective_downmix_to_stereo() {
+            args.extend(["-ac".to_string(), "2".to_string()]);
+        }
+    }
+    args.extend(["-c:v".to_string(), codec_kind.ffmpeg_name().to_string()]);
+    args.extend(codec_kind.build_speed_args(options.effective_preset(), None));
+    args.extend(codec_kind.build_codec_args(
+        rate_control_mode,
+        options.effective_quality(),
+        options.effective_preset(),
+        options.effective_tune(),
+        max_bitrate,
+        target_bitrate_kbps,
+    ));
+    args.extend(codec_kind.build_aq_args(options));
+    args.extend(codec_kind.build_x265_params(options, &[]));
+    args.extend(["-r".to_string(), options.effective_fps().to_string()]);
+    if config.use_movflags_faststart {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args.push(output_path.to_string());
+    Ok(args)
+}
+
+/// `select`/`aselect` expression true inside any of `segments`.
+fn keep_segments_expr(segments: &[(f64, f64)]) -> String {
+    segments
+        .iter()
+        .map(|(start, end)| format!("between(t,{},{})", start, end))
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// `-filter_complex` graph producing `[a]` from the background audio (input 1) and, when mixing,
+/// the first selected source track (`preview_audio_track`). Ducking compresses the music keyed
+/// on that track.
+fn background_audio_filter(options: &TranscodeOptions, mode: BackgroundAudioMode) -> String {
+    let gain = format!(
+        "[1:a:0]volume={}dB",
+        options.effective_background_audio_gain_db(mode)
+    );
+    let track = options.preview_audio_track().unwrap_or(0);
+    match mode {
+        BackgroundAudioMode::Replace => format!("{gain}[a]"),
+        BackgroundAudioMode::Mix if options.effective_background_audio_ducking() => format!(
+            "{gain}[bg];[0:a:{track}]asplit=2[src][key];\
+             [bg][key]sidechaincompress=threshold=0.05:ratio=8:attack=20:release=400[ducked];\
+             [src][ducked]amix=inputs=2:duration=first:normalize=0[a]"
+        ),
+        BackgroundAudioMode::Mix => {
+            format!("{gain}[bg];[0:a:{track}][bg]amix=inputs=2:duration=first:normalize=0[a]")
+        }
+    }
+}
+
+/// Frame rate and width caps for GIF exports. GIF frames are compressed on their own, so every
+/// extra frame or pixel costs close to its full size.
+pub(crate) const GIF_MAX_FPS: f64 = 15.0;
+const GIF_MAX_WIDTH: u32 = 640;
+/// From this quality on, GIFs use error-diffusion dithering (smoother, larger) instead of an
+/// ordered Bayer pattern.
+const GIF_DIFFUSION_MIN_QUALITY: u32 = 60;
+
+/// Palette size for `quality` (0-100): 32 colors at 0 up to the full 256 at 100.
+fn gif_palette_colors(quality: u32) -> u32 {
+    32 + quality.min(100) * (256 - 32) / 100
+}
+
+/// Animated GIF in one FFmpeg run that passes over the frames twice: `palettegen` builds the
+/// best palette for the whole clip, then `paletteuse` maps every frame onto it. Trim, cut,
+/// crop, rotation and scale apply as for video exports; audio and subtitles are dropped.
+fn build_gif_command(
+    input_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
+    output_duration_secs: Option<f64>,
+    start_offset_secs: Option<f64>,
+) -> Result<Vec<String>, AppError> {
+    // Estimate samples seek to their own window via start_offset_secs.
+    let (keep_segments, trim) = match start_offset_secs {
+        Some(_) => (None, None),
+        None => (options.effective_keep_segments(), options.effective_trim()),
+    };
+    if trim.is_some() && keep_segments.is_some() {
+        return Err(AppError::from(
+            "Trimming cannot be combined with cutting silence",
+        ));
+    }
+
+    let mut args = ffmpeg_base_args();
+    args.extend(["-progress".to_string(), "pipe:1".to_string()]);
+    if let Some(ss) = start_offset_secs.filter(|&s| s > 0.0) {
+        args.extend(["-ss".to_string(), ss.to_string()]);
+    }
+    if let Some((start, end)) = trim {
+        if start > 0.0 {
+            args.extend(["-ss".to_string(), start.to_string()]);
+        }
+        if let Some(end) = end {
+            args.extend(["-to".to_string(), end.to_string()]);
+        }
+    }
+    if options.effective_ignore_errors() {
+        args.extend(corrupt_input_args());
+    }
+    args.extend(["-i".to_string(), input_path.to_string()]);
+
+    let mut filters = Vec::new();
+    if let Some(segments) = &keep_segments {
+        filters.push(format!(
+            "select='{}',setpts=N/FRAME_RATE/TB",
+            keep_segments_expr(segments)
+        ));
+    }
+    filters.push(format!("fps={}", options.effective_fps()));
+    if let Some((w, h, x, y)) = options.effective_crop() {
+        filters.push(format!("crop={}:{}:{}:{}", w, h, x, y));
+    }
+    if let Some(orientation) = options.orientation_filter() {
+        filters.push(orientation);
+    }
+    let scale = options.effective_scale();
+    if scale < 1.0 {
+        filters.push(format!("scale=round(iw*{}/2)*2:-2", scale));
+    }
+    filters.push(format!(
+        "scale='min({},iw)':-2:flags=lanczos",
+        GIF_MAX_WIDTH
+    ));
+    let quality = options.effective_quality();
+    let dither = if quality >= GIF_DIFFUSION_MIN_QUALITY {
+        "sierra2_4a"
+    } else {
+        "bayer:bayer_scale=3"
+    };
+    let graph = format!(
+        "[0:v:{}]{},split[frames][sample];\
+         [sample]palettegen=max_colors={}:stats_mode=diff[palette];\
+         [frames][palette]paletteuse=dither={}:diff_mode=rectangle",
+        options.effective_video_stream_index(),
+        filters.join(","),
+        gif_palette_colors(quality),
+        dither
+    );
+    args.extend([
+        "-filter_complex".to_string(),
+        graph,
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-loop".to_string(),
+        "0".to_string(),
+    ]);
+    if let Some(dur) = output_duration_secs.filter(|&d| d > 0.0) {
+        args.extend(["-t".to_string(), dur.to_string()]);
+    }
+    args.push(output_path.to_string());
+    Ok(args)
+}
+
+/// Input options for partially corrupted sources: keep decoding past bitstream errors,
+/// drop corrupt packets, and regenerate missing timestamps.
+fn corrupt_input_args() -> Vec<String> {
+    vec![
+        "-err_detect".to_string(),
+        "ignore_err".to_string(),
+        "-fflags".to_string(),
+        "+genpts+discardcorrupt".to_string(),
+    ]
+}
+
+/// Build args for segment extraction (-ss -t -i -c copy or -map 0:v -c:v copy -an).
+pub fn build_extract_args(
+    input_path: &str,
+    start_secs: f64,
+    duration_secs: f64,
+    output_path: &str,
+    strip_audio: bool,
+    video_stream: u32,
+) -> Vec<String> {
+    let mut args = ffmpeg_base_args();
+    args.extend([
+        "-ss".to_string(),
+        start_secs.to_string(),
+        "-t".to_string(),
+        duration_secs.to_string(),
+        "-progress".to_string(),
+        "pipe:1".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+    ]);
+    if strip_audio {
+        args.extend([
+            "-map".to_string(),
+            format!("0:v:{}", video_stream),
+            "-c:v".to_string(),
+            "copy".to_string(),
+            "-an".to_string(),
+        ]);
+    } else {
+        args.extend(["-c".to_string(), "copy".to_string()]);
+    }
+    args.extend([
+        "-avoid_negative_ts".to_string(),
+        "make_zero".to_string(),
+        "-movflags".to_string(),
+        "+faststart".to_string(),
+        output_path.to_string(),
+    ]);
+    args
+}
+
+/// False when the options change the picture or timeline in ways a stream copy cannot (crop,
+/// rotation, flips, cut segments, background audio, GIF).
+pub fn supports_remux(options: &TranscodeOptions) -> bool {
+    !(options.is_gif()
+        || options.effective_crop().is_some()
+        || options.orientation_filter().is_some()
+        || options.effective_keep_segments().is_some()
+        || options.background_audio().is_some())
+}
+
+/// Build args stream-copying the source into the export container: the fallback for encodes
+/// that come out larger than their input. Keeps the trim (cut at keyframes), the video stream,
+/// the audio choice and metadata; size settings (quality, scale, fps) no longer apply. None
+/// unless `supports_remux`.
+pub fn build_remux_args(
+    input_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
+) -> Option<Vec<String>> {
+    if !supports_remux(options) {
+        return None;
+    }
+    let mut args = ffmpeg_base_args();
+    args.extend(["-progress".to_string(), "pipe:1".to_string()]);
+    if let Some((start, end)) = options.effective_trim() {
+        if start > 0.0 {
+            args.extend(["-ss".to_string(), start.to_string()]);
+        }
+        if let Some(end) = end {
+            args.extend(["-to".to_string(), end.to_string()]);
+        }
+    }
+    args.extend([
+        "-i".to_string(),
+        input_path.to_string(),
+        "-map".to_string(),
+        format!("0:v:{}", options.effective_video_stream_index()),
+    ]);
+    if !options.effective_remove_audio() {
+        match options.effective_audio_stream_indices() {
+            Some(tracks) => {
+                for track in tracks {
+                    args.extend(["-map".to_string(), format!("0:a:{}", track)]);
+                }
+            }
+            None => args.extend(["-map".to_string(), "0:a:0?".to_string()]),
+        }
+    }
+    args.extend(["-c".to_string(), "copy".to_string()]);
+    if options.effective_preserve_metadata() {
+        args.extend(["-map_metadata".to_string(), "0".to_string()]);
+    }
+    args.extend(["-avoid_negative_ts".to_string(), "make_zero".to_string()]);
+    let config = get_output_config(
+        &options.effective_output_format(),
+        options.effective_codec(),
+    );
+    if config.use_movflags_faststart {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args.push(output_path.to_string());
+    Some(args)
+}
+
+/// Build FFmpeg transcode command.
+struct BuildOverrides<'a> {
+    force_remove_audio: Option<bool>,
+    rate_control_mode: Option<RateControlMode>,
+    target_bitrate_kbps: Option<u32>,
+    pass: Option<u8>,
+    passlogfile: Option<&'a str>,
+    force_null_output: bool,
+}
+
+fn build_ffmpeg_command_internal(
+    input_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
+    output_duration_secs: Option<f64>,
+    format_override: Option<&str>,
+    start_offset_secs: Option<f64>,
+) -> Result<Vec<String>, AppError> {
+    build_ffmpeg_command_with_overrides(
+        input_path,
+        output_path,
+        options,
+        output_duration_secs,
+        format_override,
+        start_offset_secs,
+        BuildOverrides {
+            force_remove_audio: None,
+            rate_control_mode: None,
+            target_bitrate_kbps: None,
+            pass: None,
+            passlogfile: None,
+            force_null_output: false,
+        },
+    )
+}
+
+fn build_ffmpeg_command_with_overrides(
     input_path: &str,
     output_path: &str,
     options: &TranscodeOptions,
@@ -383,6 +1308,20 @@ fn build_ffmpeg_command_with_overrides(
     let output_format = format_override
         .map(str::to_lowercase)
         .unwrap_or_else(|| options.effective_output_format());
+    if output_format == "gif" {
+        return build_gif_command(
+            input_path,
+            output_path,
+            options,
+            output_duration_secs,
+            start_offset_secs,
+        );
+    }
+
+    let extra_video_args = options.effective_extra_video_args();
+    let extra_output_args = options.effective_extra_output_args();
+    validate_video_args(extra_video_args)?;
+    validate_output_args(extra_output_args)?;
 
     let codec_str = options.effective_codec().to_string();
     let codec_kind = CodecKind::from_codec_str(&codec_str);
@@ -410,7 +1349,11 @@ fn build_ffmpeg_command_with_overrides(
         .force_remove_audio
         .unwrap_or_else(|| options.effective_remove_audio());
     let preset = options.effective_preset();
-    let tune = options.effective_tune();
+    let screen_recording = options.is_screen_recording();
+    let tune = match options.tune {
+        None if screen_recording => Some(SCREEN_RECORDING_TUNE),
+        _ => options.effective_tune(),
+    };
 
     log::debug!(
         target: "tiny_vid::ffmpeg::builder",
@@ -424,18 +1367,60 @@ fn build_ffmpeg_command_with_overrides(
     let config = get_output_config(&output_format, &codec_str);
     // Preview uses format_override (e.g. "mp4"); always single audio, no subtitles. Export honors preserve.
     let is_preview = format_override.is_some();
-    let preserve_multi = !is_preview
-        && config.supports_multiple_audio
-        && options.effective_preserve_additional_audio_streams()
-        && options.effective_audio_stream_count() > 1;
+    let background_audio = if is_preview || overrides.force_remove_audio == Some(true) {
+        None
+    } else {
+        options.background_audio()
+    };
+    // Estimate samples seek to their own window via start_offset_secs.
+    let keep_segments = if is_preview || start_offset_secs.is_some() {
+        None
+    } else {
+        options.effective_keep_segments()
+    };
+    if keep_segments.is_some() && background_audio.is_some() {
+        return Err(AppError::from(
+            "Cutting silence cannot be combined with background audio",
+        ));
+    }
+    let trim = if is_preview || start_offset_secs.is_some() {
+        None
+    } else {
+        options.effective_trim()
+    };
+    if trim.is_some() && keep_segments.is_some() {
+        return Err(AppError::from(
+            "Trimming cannot be combined with cutting silence",
+        ));
+    }
+    // Selected audio tracks, in output order. Containers with a single audio stream keep the
+    // first of them.
+    let audio_tracks = if is_preview || background_audio.is_some() || remove_audio {
+        None
+    } else {
+        options.effective_audio_stream_indices().map(|mut tracks| {
+            if !config.supports_multiple_audio {
+                tracks.truncate(1);
+            }
+            tracks
+        })
+    };
+    let selects_tracks = audio_tracks.as_deref().is_some_and(|t| t != [0]);
+    let preserve_multi = audio_tracks.as_ref().is_some_and(|t| t.len() > 1);
     let preserve_subtitles = !is_preview
+        && keep_segments.is_none()
         && options.effective_preserve_subtitles()
         && options.effective_subtitle_stream_count() > 0;
-    let use_explicit_mapping = preserve_multi || preserve_subtitles;
+    let use_explicit_mapping = selects_tracks
+        || preserve_subtitles
+        || options.selects_video_stream()
+        || background_audio.is_some();
 
     let audio_bitrate_k = format!("{}k", options.effective_audio_bitrate());
     let downmix = options.effective_downmix_to_stereo();
-    let passthrough = !preserve_multi
+    // Stream details describe the first track, so only it can be copied as is.
+    let passthrough = !selects_tracks
+        && keep_segments.is_none()
         && config.can_passthrough_audio(
             options.audio_codec_name.as_deref(),
             options.audio_channels,
@@ -444,20 +1429,46 @@ fn build_ffmpeg_command_with_overrides(
 
     let mut args = ffmpeg_base_args();
     args.extend(["-progress".to_string(), "pipe:1".to_string()]);
+    args.extend(codec_kind.hw_device_args());
     if let Some(ss) = start_offset_secs.filter(|&s| s > 0.0) {
         args.extend(["-ss".to_string(), ss.to_string()]);
     }
+    if let Some((start, end)) = trim {
+        if start > 0.0 {
+            args.extend(["-ss".to_string(), start.to_string()]);
+        }
+        if let Some(end) = end {
+            args.extend(["-to".to_string(), end.to_string()]);
+        }
+    }
+    if options.effective_ignore_errors() {
+        args.extend(corrupt_input_args());
+    }
     args.extend(["-i".to_string(), input_path.to_string()]);
+    if let Some((path, _)) = background_audio {
+        args.extend([
+            "-stream_loop".to_string(),
+            "-1".to_string(),
+            "-i".to_string(),
+            path.to_string(),
+        ]);
+    }
 
     if use_explicit_mapping {
         args.push("-map".to_string());
-        args.push("0:v".to_string());
-        if !remove_audio {
-            if preserve_multi {
-                let n = options.effective_audio_stream_count();
-                for i in 0..n {
+        args.push(format!("0:v:{}", options.effective_video_stream_index()));
+        if let Some((_, mode)) = background_audio {
+            args.extend([
+                "-filter_complex".to_string(),
+                background_audio_filter(options, mode),
+                "-map".to_string(),
+                "[a]".to_string(),
+            ]);
+        } else if !remove_audio {
+            if let Some(tracks) = audio_tracks.as_ref().filter(|_| selects_tracks) {
+                for track in tracks {
                     args.push("-map".to_string());
-                    args.push(format!("0:a:{}", i));
+                    args.push(format!("0:a:{}", track));
                 }
             } else {
                 args.push("-map".to_string());
@@ -472,10 +1483,22 @@ fn build_ffmpeg_command_with_overrides(
 
     args.extend(["-c:v".to_string(), codec_kind.ffmpeg_name().to_string()]);
 
-    if remove_audio {
+    if background_audio.is_some() {
+        // The looped input never ends; stop at the end of the video.
+        args.extend([
+            "-c:a".to_string(),
+            config.audio_codec.to_string(),
+            "-b:a".to_string(),
+            audio_bitrate_k.clone(),
+            "-shortest".to_string(),
+        ]);
+        if config.requires_stereo_downmix || downmix {
+            args.extend(["-ac".to_string(), "2".to_string()]);
+        }
+    } else if remove_audio {
         args.push("-an".to_string());
     } else if preserve_multi {
-        let n = options.effective_audio_stream_count();
+        let n = audio_tracks.as_ref().map_or(0, Vec::len);
         for i in 0..n {
             if passthrough {
                 args.extend([format!("-c:a:{}", i), "copy".to_string()]);
@@ -528,11 +1551,42 @@ fn build_ffmpeg_command_with_overrides(
         args.extend(["-c:s".to_string(), sub_codec.to_string()]);
     }
 
+    let mut video_filters = Vec::new();
+    if let Some(segments) = &keep_segments {
+        let expr = keep_segments_expr(segments);
+        video_filters.push(format!("select='{}',setpts=N/FRAME_RATE/TB", expr));
+        if !remove_audio {
+            args.extend([
+                "-af".to_string(),
+                format!("aselect='{}',asetpts=N/SR/TB", expr),
+            ]);
+        }
+    }
+    // Crop first so scale (and the preview cap) work from the cropped size.
+    if let Some((w, h, x, y)) = options.effective_crop() {
+        video_filters.push(format!("crop={}:{}:{}:{}", w, h, x, y));
+    }
+    if let Some(orientation) = options.orientation_filter() {
+        video_filters.push(orientation);
+    }
     if scale < 1.0 {
-        let scale_filter = format!("scale=round(iw*{}/2)*2:-2", scale);
-        args.extend(["-vf".to_string(), scale_filter]);
+        video_filters.push(format!("scale=round(iw*{}/2)*2:-2", scale));
+    }
+    if screen_recording {
+        // Captures are often full-range RGB; x264 would otherwise pick unplayable 4:4:4.
+        video_filters.push("scale=out_range=tv,format=yuv420p".to_string());
+    }
+    if let Some(roi) = codec_kind.roi_filter(options) {
+        video_filters.push(roi.to_string());
+    }
+    if let Some(upload) = codec_kind.hw_upload_filter() {
+        video_filters.push(upload.to_string());
+    }
+    if !video_filters.is_empty() {
+        args.extend(["-vf".to_string(), video_filters.join(",")]);
     }
 
+    args.extend(codec_kind.build_speed_args(preset, overrides.pass));
     args.extend(codec_kind.build_codec_args(
         rate_control_mode,
         quality,
@@ -541,6 +1595,21 @@ fn build_ffmpeg_command_with_overrides(
         max_bitrate,
         target_bitrate_kbps,
     ));
+    args.extend(codec_kind.build_aq_args(options));
+    args.extend(codec_kind.build_x265_params(options, extra_video_args));
+    if screen_recording && codec_kind.supports_gop_size() {
+        let keyint = (fps * SCREEN_RECORDING_KEYINT_SECS).round().max(1.0);
+        args.extend(["-g".to_string(), keyint.to_string()]);
+    }
+    // After the app's own video options, so the user's values override them. x265 got the
+    // user's `-x265-params` merged into its own; a second one would replace that.
+    args.extend(
+        extra_video_args
+            .chunks(2)
+            .filter(|pair| !(matches!(codec_kind, CodecKind::X265) && pair[0] == "-x265-params"))
+            .flatten()
+            .cloned(),
+    );
 
     args.extend(["-r".to_string(), fps.to_string()]);
     if config.use_movflags_faststart {
@@ -552,7 +1621,13 @@ fn build_ffmpeg_command_with_overrides(
     }
     if options.effective_preserve_metadata() {
         args.extend(["-map_metadata".to_string(), "0".to_string()]);
+    } else if options.effective_reproducible() {
+        args.extend(["-map_metadata".to_string(), "-1".to_string()]);
+    }
+    if options.effective_reproducible() {
+        args.extend(reproducible_args());
     }
+    args.extend(extra_output_args.iter().cloned());
     if let Some(pass) = overrides.pass {
         args.extend(["-pass".to_string(), pass.to_string()]);
         if let Some(passlogfile) = overrides.passlogfile {
@@ -586,6 +1661,67 @@ pub fn build_ffmpeg_command(
     )
 }
 
+/// Export command with every audio stream dropped, for sampling the video part of a size
+/// estimate. Target-size mode still budgets the video bitrate as if the audio were there.
+pub fn build_video_only_ffmpeg_command(
+    input_path: &str,
+    output_path: &str,
+    options: &TranscodeOptions,
+    output_duration_secs: Option<f64>,
+    start_offset_secs: Option<f64>,
+) -> Result<Vec<String>, AppError> {
+    build_ffmpeg_command_with_overrides(
+        input_path,
+        output_path,
+        options,
+        output_duration_secs,
+        None,
+        start_offset_secs,
+        BuildOverrides {
+            force_remove_audio: Some(true),
+            rate_control_mode: None,
+            target_bitrate_kbps: None,
+            pass: None,
+            passlogfile: None,
+            force_null_output: false,
+        },
+    )
+}
+
+/// Total audio bitrate (kbps, all mapped streams) of the export, mirroring the stream and
+/// passthrough choices of `build_ffmpeg_command`. Copied audio uses the source bit rate when
+/// it is known, else the configured bitrate.
+pub fn estimated_audio_kbps(options: &TranscodeOptions) -> f64 {
+    let configured = options.effective_audio_bitrate() as f64;
+    if options.background_audio().is_some() {
+        return configured;
+    }
+    if options.effective_remove_audio() || options.audio_stream_count == Some(0) {
+        return 0.0;
+    }
+    let config = get_output_config(
+        &options.effective_output_format(),
+        options.effective_codec(),
+    );
+    let tracks = options
+        .effective_audio_stream_indices()
+        .map_or(1, |tracks| tracks.len());
+    if config.supports_multiple_audio && tracks > 1 {
+        return configured * tracks as f64;
+    }
+    let passthrough = !options.selects_audio_tracks()
+        && options.effective_keep_segments().is_none()
+        && config.can_passthrough_audio(
+            options.audio_codec_name.as_deref(),
+            options.audio_channels,
+            options.effective_downmix_to_stereo(),
+        );
+    match options.source_audio_bit_rate {
+        Some(bits) if passthrough => bits as f64 / 1000.0,
+        _ => configured,
+    }
+}
+
 pub struct TwoPassCommands {
     pub pass1: Vec<String>,
     pub pass2: Vec<String>,
@@ -716,6 +1852,8 @@ pub fn build_first_frame_args(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ffmpeg::ContentType;
+    use crate::ffmpeg::compose::PipAudioSource;
 
     fn opts() -> TranscodeOptions {
         TranscodeOptions::default()
@@ -723,7 +1861,7 @@ mod tests {
 
     #[test]
     fn build_extract_args_includes_faststart_and_avoid_negative_ts() {
-        let args = build_extract_args("/in.mkv", 0.0, 3.0, "/out.mp4", false);
+        let args = build_extract_args("/in.mkv", 0.0, 3.0, "/out.mp4", false, 0);
         assert!(args.contains(&"-movflags".to_string()));
         assert!(args.contains(&"+faststart".to_string()));
         assert!(args.contains(&"-avoid_negative_ts".to_string()));
@@ -734,9 +1872,9 @@ mod tests {
 
     #[test]
     fn build_extract_args_strip_audio_uses_map_v_and_an() {
-        let args = build_extract_args("/in.mkv", 0.0, 3.0, "/out.mp4", true);
+        let args = build_extract_args("/in.mkv", 0.0, 3.0, "/out.mp4", true, 1);
         assert!(args.contains(&"-map".to_string()));
-        assert!(args.contains(&"0:v".to_string()));
+        assert!(args.contains(&"0:v:1".to_string()));
         assert!(args.contains(&"-c:v".to_string()));
         assert!(args.contains(&"-an".to_string()));
     }
@@ -776,6 +1914,136 @@ mod tests {
         assert!(!args.iter().any(|a| a == "-c:a"));
     }
 
+    #[test]
+    fn video_only_command_drops_audio_and_background_music() {
+        let mut o = opts();
+        o.background_audio_path = Some("/music.mp3".to_string());
+        let args = build_video_only_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None).unwrap();
+        assert!(args.contains(&"-an".to_string()));
+        assert!(!args.iter().any(|a| a == "/music.mp3" || a == "-c:a"));
+    }
+
+    #[test]
+    fn estimated_audio_kbps_follows_stream_and_passthrough_choices() {
+        let mut o = opts();
+        o.audio_bitrate = Some(96);
+        assert_eq!(estimated_audio_kbps(&o), 96.0);
+
+        o.audio_codec_name = Some("aac".to_string());
+        o.audio_channels = Some(2);
+        o.source_audio_bit_rate = Some(192_000);
+        assert_eq!(estimated_audio_kbps(&o), 192.0);
+
+        o.audio_stream_indices = Some(vec![0, 1, 2]);
+        o.audio_stream_count = Some(3);
+        assert_eq!(estimated_audio_kbps(&o), 288.0);
+
+        o.remove_audio = Some(true);
+        assert_eq!(estimated_audio_kbps(&o), 0.0);
+    }
+
+    #[test]
+    fn nvenc_maps_quality_to_cq_and_preset_to_p_levels() {
+        let mut o = opts();
+        o.codec = Some("hevc_nvenc".to_string());
+        o.quality = Some(100);
+        o.preset = Some("slow".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let value_of = |flag: &str| {
+            let i = args.iter().position(|a| a == flag).unwrap();
+            args[i + 1].clone()
+        };
+        assert_eq!(value_of("-c:v"), "hevc_nvenc");
+        assert_eq!(value_of("-preset"), "p6");
+        assert_eq!(value_of("-rc"), "vbr");
+        assert_eq!(value_of("-cq"), "28");
+        assert_eq!(value_of("-b:v"), "0");
+        assert_eq!(value_of("-tag:v"), "hvc1");
+        assert!(!args.iter().any(|a| a == "-crf"));
+
+        o.codec = Some("h264_nvenc".to_string());
+        o.quality = Some(0);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let cq = args.iter().position(|a| a == "-cq").unwrap();
+        assert_eq!(args[cq + 1], "51");
+        assert!(!args.iter().any(|a| a == "-tag:v"));
+    }
+
+    #[test]
+    fn qsv_inits_device_uploads_frames_and_maps_global_quality() {
+        let mut o = opts();
+        o.codec = Some("h264_qsv".to_string());
+        o.quality = Some(100);
+        o.preset = Some("ultrafast".to_string());
+        o.scale = Some(0.5);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let value_of = |flag: &str| {
+            let i = args.iter().position(|a| a == flag).unwrap();
+            args[i + 1].clone()
+        };
+        let init = args.iter().position(|a| a == "-init_hw_device").unwrap();
+        assert!(init < args.iter().position(|a| a == "-i").unwrap());
+        assert_eq!(value_of("-init_hw_device"), "qsv=hw:hw_any");
+        assert_eq!(value_of("-filter_hw_device"), "hw");
+        assert!(value_of("-vf").ends_with(",format=nv12,hwupload=extra_hw_frames=64"));
+        assert_eq!(value_of("-c:v"), "h264_qsv");
+        assert_eq!(value_of("-preset"), "veryfast");
+        assert_eq!(value_of("-global_quality"), "23");
+        assert!(!args.iter().any(|a| a == "-crf"));
+
+        o.codec = Some("libx264".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.iter().any(|a| a == "-init_hw_device"));
+    }
+
+    #[test]
+    fn vaapi_opens_render_node_uploads_frames_and_maps_qp() {
+        let mut o = opts();
+        o.codec = Some("hevc_vaapi".to_string());
+        o.quality = Some(0);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let value_of = |flag: &str| {
+            let i = args.iter().position(|a| a == flag).unwrap();
+            args[i + 1].clone()
+        };
+        let device = args.iter().position(|a| a == "-vaapi_device").unwrap();
+        assert!(device < args.iter().position(|a| a == "-i").unwrap());
+        assert_eq!(value_of("-vaapi_device"), "/dev/dri/renderD128");
+        assert!(value_of("-vf").ends_with("format=nv12,hwupload"));
+        assert_eq!(value_of("-c:v"), "hevc_vaapi");
+        assert_eq!(value_of("-qp"), "51");
+        assert_eq!(value_of("-tag:v"), "hvc1");
+        assert!(!args.iter().any(|a| a == "-preset" || a == "-crf"));
+    }
+
+    #[test]
+    fn amf_maps_presets_to_quality_and_quality_to_cqp() {
+        let mut o = opts();
+        o.codec = Some("h264_amf".to_string());
+        o.quality = Some(100);
+        o.preset = Some("slow".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let value_of = |flag: &str| {
+            let i = args.iter().position(|a| a == flag).unwrap();
+            args[i + 1].clone()
+        };
+        assert_eq!(value_of("-c:v"), "h264_amf");
+        assert_eq!(value_of("-usage"), "transcoding");
+        assert_eq!(value_of("-quality"), "quality");
+        assert_eq!(value_of("-rc"), "cqp");
+        assert_eq!(value_of("-qp_i"), "23");
+        assert_eq!(value_of("-qp_b"), "23");
+        assert!(!args.iter().any(|a| a == "-crf" || a == "-preset"));
+
+        o.codec = Some("hevc_amf".to_string());
+        o.preset = Some("ultrafast".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let quality = args.iter().position(|a| a == "-quality").unwrap();
+        assert_eq!(args[quality + 1], "speed");
+        assert!(!args.iter().any(|a| a == "-qp_b"));
+        assert!(args.iter().any(|a| a == "hvc1"));
+    }
+
     #[test]
     fn h264_quality_maps_to_crf() {
         let mut o = opts();
@@ -868,6 +2136,50 @@ mod tests {
         assert_eq!(args.get(tune_idx + 1).unwrap(), "film");
     }
 
+    #[test]
+    fn speed_presets_change_bframes_and_lookahead() {
+        let mut o = opts();
+        o.preset = Some("superfast".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let bf = args.iter().position(|a| a == "-bf").unwrap();
+        assert_eq!(args[bf..bf + 2], ["-bf", "1"]);
+        assert!(!args.contains(&"-refs".to_string()));
+        assert!(!args.contains(&"-rc-lookahead".to_string()));
+
+        // x264 ultrafast already has no B-frames or lookahead.
+        o.preset = Some("ultrafast".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.contains(&"-bf".to_string()));
+
+        o.codec = Some("libx265".to_string());
+        o.preset = Some("superfast".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let params = args.iter().position(|a| a == "-x265-params").unwrap();
+        assert_eq!(args[params + 1], "bframes=2:rc-lookahead=5");
+        assert!(!args.contains(&"-bf".to_string()));
+
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &opts(), None, None, None).unwrap();
+        assert!(!args.contains(&"-bf".to_string()));
+    }
+
+    #[test]
+    fn extra_args_follow_the_apps_own_and_are_validated() {
+        let mut o = opts();
+        o.extra_video_args = Some(vec!["-g".to_string(), "120".to_string()]);
+        o.extra_output_args = Some(vec!["-metadata".to_string(), "title=Clip".to_string()]);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let g = args.iter().position(|a| a == "-g").unwrap();
+        let codec_args = args.iter().position(|a| a == "-crf").unwrap();
+        assert!(g > codec_args && args[g + 1] == "120");
+        assert_eq!(
+            args[args.len() - 3..],
+            ["-metadata", "title=Clip", "/out.mp4"]
+        );
+
+        o.extra_output_args = Some(vec!["-i".to_string(), "/etc/passwd".to_string()]);
+        assert!(build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).is_err());
+    }
+
     #[test]
     fn tune_skipped_for_svtav1() {
         let mut o = opts();
@@ -877,6 +2189,25 @@ mod tests {
         assert!(!args.contains(&"-tune".to_string()));
     }
 
+    #[test]
+    fn tune_added_for_x265_when_valid() {
+        let mut o = opts();
+        o.codec = Some("libx265".to_string());
+        o.tune = Some("ssim".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let tune_idx = args.iter().position(|a| a == "-tune").unwrap();
+        assert_eq!(args.get(tune_idx + 1).unwrap(), "ssim");
+    }
+
+    #[test]
+    fn tune_skipped_when_not_valid_for_codec() {
+        let mut o = opts();
+        o.codec = Some("libx265".to_string());
+        o.tune = Some("film".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.contains(&"-tune".to_string()));
+    }
+
     #[test]
     fn svtav1_adds_pix_fmt_and_av01_tag() {
         let mut o = opts();
@@ -1129,15 +2460,15 @@ mod tests {
     }
 
     #[test]
-    fn preserve_additional_audio_streams_adds_map_and_per_track_codec() {
+    fn audio_stream_indices_add_map_and_per_track_codec() {
         let mut o = opts();
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1, 2]);
         o.audio_stream_count = Some(3);
         o.remove_audio = Some(false);
         o.output_format = Some("mp4".to_string());
         let args = build_ffmpeg_command("/in.mkv", "/out.mp4", &o, None, None, None).unwrap();
         assert!(args.contains(&"-map".to_string()));
-        assert!(args.contains(&"0:v".to_string()));
+        assert!(args.contains(&"0:v:0".to_string()));
         assert!(args.contains(&"0:a:0".to_string()));
         assert!(args.contains(&"0:a:1".to_string()));
         assert!(args.contains(&"0:a:2".to_string()));
@@ -1148,9 +2479,9 @@ mod tests {
     }
 
     #[test]
-    fn preserve_additional_audio_streams_ignored_for_preview() {
+    fn audio_stream_indices_ignored_for_preview() {
         let mut o = opts();
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1, 2]);
         o.audio_stream_count = Some(3);
         o.remove_audio = Some(false);
         let args =
@@ -1163,25 +2494,61 @@ mod tests {
 
     #[test]
     #[cfg(not(feature = "lgpl"))]
-    fn preserve_additional_audio_streams_ignored_for_webm() {
+    fn audio_stream_indices_keep_one_track_for_webm() {
+        let mut o = opts();
+        o.audio_stream_indices = Some(vec![0, 1, 2]);
+        o.audio_stream_count = Some(3);
+        o.remove_audio = Some(false);
+        o.output_format = Some("webm".to_string());
+        o.codec = Some("libvpx-vp9".to_string());
+        let args = build_ffmpeg_command("/in.mkv", "/out.webm", &o, None, None, None).unwrap();
+        assert!(
+            !args.contains(&"0:a:1".to_string()),
+            "WebM supports single audio only"
+        );
+    }
+
+    #[test]
+    fn audio_stream_indices_map_only_the_chosen_tracks_in_order() {
+        let mut o = opts();
+        o.audio_stream_indices = Some(vec![2, 0, 2, 5]);
+        o.audio_stream_count = Some(3);
+        o.remove_audio = Some(false);
+        o.output_format = Some("mkv".to_string());
+        let args = build_ffmpeg_command("/in.mkv", "/out.mkv", &o, None, None, None).unwrap();
+        let maps: Vec<_> = args
+            .windows(2)
+            .filter(|w| w[0] == "-map")
+            .map(|w| w[1].as_str())
+            .collect();
+        assert_eq!(maps, ["0:v:0", "0:a:2", "0:a:0"]);
+        assert!(args.contains(&"-c:a:1".to_string()));
+        assert!(!args.contains(&"-c:a:2".to_string()));
+
+        // A single non-first track is mapped on its own and never copied.
+        o.audio_stream_indices = Some(vec![1]);
+        o.audio_codec_name = Some("aac".to_string());
+        o.audio_channels = Some(2);
+        let args = build_ffmpeg_command("/in.mkv", "/out.mkv", &o, None, None, None).unwrap();
+        assert!(args.contains(&"0:a:1".to_string()));
+        assert!(!args.contains(&"copy".to_string()));
+    }
+
+    #[test]
+    fn empty_audio_stream_indices_drop_audio() {
         let mut o = opts();
-        o.preserve_additional_audio_streams = Some(true);
-        o.audio_stream_count = Some(3);
+        o.audio_stream_indices = Some(vec![]);
         o.remove_audio = Some(false);
-        o.output_format = Some("webm".to_string());
-        o.codec = Some("libvpx-vp9".to_string());
-        let args = build_ffmpeg_command("/in.mkv", "/out.webm", &o, None, None, None).unwrap();
-        assert!(
-            !args.contains(&"0:a:1".to_string()),
-            "WebM supports single audio only"
-        );
+        let args = build_ffmpeg_command("/in.mkv", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(args.contains(&"-an".to_string()));
+        assert_eq!(estimated_audio_kbps(&o), 0.0);
     }
 
     #[test]
     #[cfg(not(feature = "lgpl"))]
-    fn mkv_vp9_preserve_additional_audio_streams_downmixes_each_track() {
+    fn mkv_vp9_audio_stream_indices_downmix_each_track() {
         let mut o = opts();
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1]);
         o.audio_stream_count = Some(2);
         o.remove_audio = Some(false);
         o.output_format = Some("mkv".to_string());
@@ -1272,6 +2639,346 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multiple_video_streams_map_selected_stream_only() {
+        let mut o = opts();
+        o.video_stream_count = Some(2);
+        o.video_stream_index = Some(1);
+        o.remove_audio = Some(false);
+        let args = build_ffmpeg_command("/in.mov", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(args.windows(2).any(|w| w == ["-map", "0:v:1"]));
+        assert!(args.contains(&"0:a:0?".to_string()));
+        assert!(!args.contains(&"0:v:0".to_string()));
+    }
+
+    #[test]
+    fn keep_segments_select_video_and_audio_for_export_only() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.scale = Some(0.5);
+        o.preserve_subtitles = Some(true);
+        o.subtitle_stream_count = Some(1);
+        o.keep_segments = Some(vec![(1.0, 4.5), (6.0, 9.0)]);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert_eq!(
+            vf,
+            "select='between(t,1,4.5)+between(t,6,9)',setpts=N/FRAME_RATE/TB,scale=round(iw*0.5/2)*2:-2"
+        );
+        let af = &args[args.iter().position(|a| a == "-af").unwrap() + 1];
+        assert_eq!(
+            af,
+            "aselect='between(t,1,4.5)+between(t,6,9)',asetpts=N/SR/TB"
+        );
+        assert!(!args.contains(&"0:s?".to_string()));
+
+        let preview =
+            build_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(3.0), Some("mp4"), None).unwrap();
+        assert!(!preview.iter().any(|a| a.contains("select=")));
+        let sample =
+            build_video_only_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(1.0), Some(7.0))
+                .unwrap();
+        assert!(!sample.iter().any(|a| a.contains("select=")));
+
+        o.background_audio_path = Some("/music.mp3".to_string());
+        assert!(build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).is_err());
+    }
+
+    #[test]
+    fn crop_runs_before_scale() {
+        let mut o = opts();
+        o.scale = Some(0.5);
+        o.crop_width = Some(1280);
+        o.crop_height = Some(720);
+        o.crop_x = Some(320);
+        o.crop_y = Some(180);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert_eq!(vf, "crop=1280:720:320:180,scale=round(iw*0.5/2)*2:-2");
+
+        o.scale = Some(1.0);
+        let preview =
+            build_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(3.0), Some("mp4"), None).unwrap();
+        let vf = &preview[preview.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert_eq!(vf, "crop=1280:720:320:180");
+    }
+
+    #[test]
+    fn rotation_and_flips_follow_crop_and_precede_scale() {
+        let mut o = opts();
+        o.scale = Some(0.5);
+        o.crop_width = Some(640);
+        o.crop_height = Some(480);
+        o.rotation = Some(90);
+        o.flip_horizontal = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert_eq!(
+            vf,
+            "crop=640:480:0:0,transpose=clock,hflip,scale=round(iw*0.5/2)*2:-2"
+        );
+        assert!(!args.contains(&"-noautorotate".to_string()));
+
+        o.rotation = Some(180);
+        o.flip_horizontal = None;
+        o.flip_vertical = Some(true);
+        assert_eq!(o.orientation_filter().as_deref(), Some("hflip,vflip,vflip"));
+        o.rotation = Some(270);
+        o.flip_vertical = None;
+        assert_eq!(o.orientation_filter().as_deref(), Some("transpose=cclock"));
+        o.rotation = Some(45);
+        assert_eq!(o.orientation_filter(), None);
+    }
+
+    #[test]
+    fn gif_uses_a_palette_graph_with_capped_fps_and_width() {
+        let mut o = opts();
+        o.output_format = Some("gif".into());
+        o.fps = Some(30.0);
+        o.quality = Some(100);
+        o.remove_audio = Some(false);
+        o.rate_control_mode = Some(RateControlMode::TargetSize);
+        o.crop_width = Some(640);
+        o.crop_height = Some(360);
+        let args = build_ffmpeg_command("/in.mp4", "/out.gif", &o, None, None, None).unwrap();
+        let graph = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert_eq!(
+            graph,
+            "[0:v:0]fps=15,crop=640:360:0:0,scale='min(640,iw)':-2:flags=lanczos,\
+             split[frames][sample];[sample]palettegen=max_colors=256:stats_mode=diff[palette];\
+             [frames][palette]paletteuse=dither=sierra2_4a:diff_mode=rectangle"
+        );
+        assert!(args.contains(&"-an".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-loop", "0"]));
+        assert!(!args.contains(&"-c:v".to_string()));
+        assert!(!args.contains(&"-movflags".to_string()));
+
+        o.quality = Some(0);
+        o.trim_start_secs = Some(2.0);
+        let sample =
+            build_video_only_ffmpeg_command("/in.mp4", "/out.gif", &o, Some(1.5), Some(8.0))
+                .unwrap();
+        let graph = &sample[sample.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(graph.contains("max_colors=32:") && graph.contains("dither=bayer"));
+        assert!(sample.windows(2).any(|w| w == ["-ss", "8"]));
+        assert!(!sample.windows(2).any(|w| w == ["-ss", "2"]));
+        assert!(sample.windows(2).any(|w| w == ["-t", "1.5"]));
+        assert_eq!(estimated_audio_kbps(&o), 0.0);
+    }
+
+    #[test]
+    fn remux_copies_the_trimmed_streams_unless_the_picture_changes() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.trim_start_secs = Some(1.5);
+        o.audio_stream_count = Some(2);
+        o.audio_stream_indices = Some(vec![1]);
+        let args = build_remux_args("/in.mov", "/out.mp4", &o).unwrap();
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert!(args[..input].windows(2).any(|w| w == ["-ss", "1.5"]));
+        assert!(args.windows(2).any(|w| w == ["-map", "0:v:0"]));
+        assert!(args.windows(2).any(|w| w == ["-map", "0:a:1"]));
+        assert!(args.windows(2).any(|w| w == ["-c", "copy"]));
+        assert!(args.windows(2).any(|w| w == ["-movflags", "+faststart"]));
+
+        o.remove_audio = Some(true);
+        let silent = build_remux_args("/in.mov", "/out.mp4", &o).unwrap();
+        assert!(!silent.iter().any(|a| a.starts_with("0:a")));
+
+        o.crop_width = Some(100);
+        o.crop_height = Some(100);
+        assert_eq!(build_remux_args("/in.mov", "/out.mp4", &o), None);
+        o.crop_width = None;
+        o.rotation = Some(90);
+        assert_eq!(build_remux_args("/in.mov", "/out.mp4", &o), None);
+    }
+
+    #[test]
+    fn trim_seeks_input_for_export_only() {
+        let mut o = opts();
+        o.trim_start_secs = Some(2.5);
+        o.trim_end_secs = Some(10.0);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert!(args[..input].windows(2).any(|w| w == ["-ss", "2.5"]));
+        assert!(args[..input].windows(2).any(|w| w == ["-to", "10"]));
+
+        let preview =
+            build_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(3.0), Some("mp4"), None).unwrap();
+        assert!(!preview.contains(&"-to".to_string()));
+        let sample =
+            build_video_only_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(1.0), Some(4.0))
+                .unwrap();
+        assert!(!sample.contains(&"-to".to_string()));
+        assert!(sample.windows(2).any(|w| w == ["-ss", "4"]));
+
+        o.trim_start_secs = None;
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.contains(&"-ss".to_string()));
+        assert!(args.contains(&"-to".to_string()));
+
+        o.keep_segments = Some(vec![(1.0, 2.0)]);
+        assert!(build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).is_err());
+    }
+
+    #[test]
+    fn screen_recording_uses_stillimage_long_gop_and_keeps_60fps() {
+        let mut o = opts();
+        o.content_type = Some(ContentType::ScreenRecording);
+        o.source_fps = Some(59.94);
+        let args = build_ffmpeg_command("/in.mov", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(args.windows(2).any(|w| w == ["-tune", "stillimage"]));
+        assert!(args.windows(2).any(|w| w == ["-r", "59.94"]));
+        assert!(args.windows(2).any(|w| w == ["-g", "599"]));
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert!(vf.contains("scale=out_range=tv,format=yuv420p"));
+
+        o.tune = Some("none".to_string());
+        o.source_fps = Some(30.0);
+        let args = build_ffmpeg_command("/in.mov", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.contains(&"-tune".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-g", "300"]));
+    }
+
+    #[test]
+    fn roi_text_uses_edge_aware_aq_per_codec() {
+        let mut o = opts();
+        o.roi_priority = Some(RoiPriority::Text);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(args.windows(2).any(|w| w == ["-aq-mode", "3"]));
+        assert!(args.windows(2).any(|w| w == ["-aq-strength", "1.2"]));
+
+        o.codec = Some("libx265".to_string());
+        o.aq_strength = Some(0.8);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(
+            args.windows(2)
+                .any(|w| w == ["-x265-params", "aq-mode=4:aq-strength=0.8"])
+        );
+
+        o.codec = Some("libsvtav1".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(!args.iter().any(|a| a.contains("aq-")));
+    }
+
+    #[test]
+    fn roi_center_adds_addroi_filter_for_x264() {
+        let mut o = opts();
+        o.roi_priority = Some(RoiPriority::Center);
+        o.aq_mode = Some(9);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert!(vf.starts_with("addroi="));
+        assert!(args.windows(2).any(|w| w == ["-aq-mode", "3"]));
+        assert!(!args.contains(&"-aq-strength".to_string()));
+    }
+
+    #[test]
+    fn background_audio_replace_loops_music_and_stops_with_video() {
+        let mut o = opts();
+        o.background_audio_path = Some("/music.mp3".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert!(
+            args.windows(4)
+                .any(|w| w == ["-stream_loop", "-1", "-i", "/music.mp3"])
+        );
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert_eq!(args[fc_idx + 1], "[1:a:0]volume=0dB[a]");
+        assert!(args.windows(2).any(|w| w == ["-map", "[a]"]));
+        assert!(args.contains(&"-shortest".to_string()));
+        assert!(!args.contains(&"0:a:0?".to_string()));
+
+        let preview =
+            build_ffmpeg_command("/in.mp4", "/out.mp4", &o, Some(3.0), Some("mp4"), None).unwrap();
+        assert!(!preview.contains(&"/music.mp3".to_string()));
+    }
+
+    #[test]
+    fn background_audio_mix_ducks_under_source_audio() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.background_audio_path = Some("/music.mp3".to_string());
+        o.background_audio_mode = Some(BackgroundAudioMode::Mix);
+        o.background_audio_ducking = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        let graph = &args[fc_idx + 1];
+        assert!(graph.starts_with("[1:a:0]volume=-15dB[bg];[0:a:0]asplit=2[src][key];"));
+        assert!(graph.contains("[bg][key]sidechaincompress="));
+        assert!(graph.ends_with("amix=inputs=2:duration=first:normalize=0[a]"));
+
+        o.remove_audio = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert_eq!(args[fc_idx + 1], "[1:a:0]volume=0dB[a]");
+        assert!(!args.contains(&"-an".to_string()));
+    }
+
+    #[test]
+    fn background_audio_mix_keys_on_the_selected_track() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.audio_stream_count = Some(3);
+        o.audio_stream_indices = Some(vec![2, 0]);
+        o.background_audio_path = Some("/music.mp3".to_string());
+        o.background_audio_mode = Some(BackgroundAudioMode::Mix);
+        o.background_audio_ducking = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert!(args[fc_idx + 1].contains("[0:a:2]asplit=2[src][key];"));
+        assert!(!args[fc_idx + 1].contains("[0:a:0]"));
+
+        o.background_audio_ducking = Some(false);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert_eq!(
+            args[fc_idx + 1],
+            "[1:a:0]volume=-15dB[bg];[0:a:2][bg]amix=inputs=2:duration=first:normalize=0[a]"
+        );
+    }
+
+    #[test]
+    fn background_audio_mix_without_selected_tracks_replaces() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.audio_stream_count = Some(2);
+        o.audio_stream_indices = Some(Vec::new());
+        o.background_audio_path = Some("/music.mp3".to_string());
+        o.background_audio_mode = Some(BackgroundAudioMode::Mix);
+        assert_eq!(
+            o.background_audio(),
+            Some(("/music.mp3", BackgroundAudioMode::Replace))
+        );
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert_eq!(args[fc_idx + 1], "[1:a:0]volume=0dB[a]");
+    }
+
+    #[test]
+    fn pip_compose_overlays_second_input_and_maps_chosen_audio() {
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        let layout = PipLayout {
+            audio_source: PipAudioSource::Overlay,
+            ..PipLayout::default()
+        };
+        let args = build_pip_compose_args("/screen.mp4", "/cam.mp4", "/out.mp4", &o, &layout, 1920)
+            .unwrap();
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 2);
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        assert!(args[fc_idx + 1].starts_with("[1:v]scale=480:-2[pip];[0:v][pip]overlay="));
+        assert!(args.windows(2).any(|w| w == ["-map", "[v]"]));
+        assert!(args.windows(2).any(|w| w == ["-map", "1:a:0?"]));
+        assert!(args.contains(&"libx264".to_string()));
+        assert_eq!(args.last().unwrap(), "/out.mp4");
+
+        o.remove_audio = Some(true);
+        let args = build_pip_compose_args("/screen.mp4", "/cam.mp4", "/out.mp4", &o, &layout, 1920)
+            .unwrap();
+        assert!(args.contains(&"-an".to_string()));
+        assert!(!args.iter().any(|a| a.ends_with(":a:0?")));
+    }
+
     #[test]
     fn audio_passthrough_uses_copy() {
         let mut o = opts();
@@ -1324,6 +3031,185 @@ mod tests {
         assert_eq!(args.get(mm_idx + 1).unwrap(), "0");
     }
 
+    #[test]
+    fn reproducible_pins_threads_and_strips_metadata() {
+        let value_after = |args: &[String], flag: &str| {
+            let idx = args.iter().position(|a| a == flag)?;
+            Some(args[idx + 1].clone())
+        };
+        let mut o = opts();
+        o.reproducible = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert_eq!(value_after(&args, "-threads:v").as_deref(), Some("4"));
+        assert_eq!(value_after(&args, "-fflags").as_deref(), Some("+bitexact"));
+        assert_eq!(value_after(&args, "-flags:v").as_deref(), Some("+bitexact"));
+        assert_eq!(value_after(&args, "-map_metadata").as_deref(), Some("-1"));
+
+        o.preserve_metadata = Some(true);
+        o.codec = Some("libx265".to_string());
+        o.aq_mode = Some(2);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert_eq!(value_after(&args, "-map_metadata").as_deref(), Some("0"));
+        assert_eq!(
+            value_after(&args, "-x265-params").as_deref(),
+            Some("aq-mode=2:pools=4:frame-threads=1")
+        );
+        assert_eq!(args.iter().filter(|a| *a == "-x265-params").count(), 1);
+
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &opts(), None, None, None).unwrap();
+        assert!(
+            !args
+                .iter()
+                .any(|a| a.contains("bitexact") || a == "-threads:v")
+        );
+
+        o.preset = Some("superfast".to_string());
+        o.aq_strength = Some(0.8);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert_eq!(args.iter().filter(|a| *a == "-x265-params").count(), 1);
+        let params = value_after(&args, "-x265-params").unwrap();
+        assert_eq!(
+            params,
+            "bframes=2:rc-lookahead=5:aq-mode=2:aq-strength=0.8:pools=4:frame-threads=1"
+        );
+        let keys: Vec<_> = params
+            .split(':')
+            .filter_map(|p| p.split('=').next())
+            .collect();
+        let mut unique = keys.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(keys.len(), unique.len());
+
+        // The user's x265 keys merge into the one value; the pinning still wins.
+        o.extra_video_args = Some(vec![
+            "-x265-params".to_string(),
+            "bframes=4:no-sao:pools=16:deblock=-1,-1".to_string(),
+        ]);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        assert_eq!(args.iter().filter(|a| *a == "-x265-params").count(), 1);
+        assert_eq!(
+            value_after(&args, "-x265-params").as_deref(),
+            Some(
+                "bframes=4:rc-lookahead=5:aq-mode=2:aq-strength=0.8:no-sao:pools=4:\
+                 deblock=-1,-1:frame-threads=1"
+            )
+        );
+    }
+
+    #[test]
+    fn audio_preview_args_encode_first_audio_stream_like_export() {
+        let mut o = opts();
+        o.output_format = Some("webm".to_string());
+        o.codec = Some("libvpx-vp9".to_string());
+        o.audio_bitrate = Some(96);
+        let args = build_audio_preview_args("/in.mp4", "/out.mka", &o, 5.0, 3.0);
+        let map_idx = args.iter().position(|a| a == "-map").unwrap();
+        assert_eq!(args[map_idx + 1], "0:a:0");
+        let c_idx = args.iter().position(|a| a == "-c:a").unwrap();
+        assert_eq!(args[c_idx + 1], "libopus");
+        assert!(args.contains(&"96k".to_string()));
+        assert!(args.contains(&"-ac".to_string()));
+        assert!(args.contains(&"-vn".to_string()));
+        assert_eq!(args.last().unwrap(), "/out.mka");
+    }
+
+    #[test]
+    fn audio_preview_args_follow_the_export_track_selection() {
+        let mut o = opts();
+        o.audio_codec_name = Some("aac".to_string());
+        o.audio_channels = Some(2);
+        let args = build_audio_preview_args("/in.mkv", "/out.mka", &o, 0.0, 3.0);
+        assert!(args.windows(2).any(|w| w == ["-c:a", "copy"]));
+
+        o.audio_stream_indices = Some(vec![2, 0]);
+        o.audio_stream_count = Some(3);
+        let args = build_audio_preview_args("/in.mkv", "/out.mka", &o, 0.0, 3.0);
+        let map_idx = args.iter().position(|a| a == "-map").unwrap();
+        assert_eq!(args[map_idx + 1], "0:a:2");
+        assert!(!args.windows(2).any(|w| w == ["-c:a", "copy"]));
+    }
+
+    #[test]
+    fn cover_image_args_seek_only_for_video_frames() {
+        let args = build_cover_image_args("/in.mp4", "/cover.jpg", Some(12.5));
+        let ss_idx = args.iter().position(|a| a == "-ss").unwrap();
+        let i_idx = args.iter().position(|a| a == "-i").unwrap();
+        assert!(ss_idx < i_idx);
+        assert_eq!(args[ss_idx + 1], "12.5");
+        assert_eq!(args.last().unwrap(), "/cover.jpg");
+
+        let args = build_cover_image_args("/art.png", "/cover.jpg", None);
+        assert!(!args.contains(&"-ss".to_string()));
+    }
+
+    #[test]
+    fn thumbnail_args_seek_before_input_and_fit_the_box() {
+        let args = build_thumbnail_args("/in.mp4", "/thumb.png", 3.5, 320, "png");
+        let ss_idx = args.iter().position(|a| a == "-ss").unwrap();
+        let i_idx = args.iter().position(|a| a == "-i").unwrap();
+        assert!(ss_idx < i_idx);
+        assert_eq!(args[ss_idx + 1], "3.5");
+        assert!(args.contains(
+            &"scale='min(320,iw)':'min(320,ih)':force_original_aspect_ratio=decrease".to_string()
+        ));
+        let c_idx = args.iter().position(|a| a == "-c:v").unwrap();
+        assert_eq!(args[c_idx + 1], "png");
+        assert!(!args.contains(&"-q:v".to_string()));
+        assert_eq!(args.last().unwrap(), "/thumb.png");
+
+        let args = build_thumbnail_args("/in.mp4", "/thumb.jpg", -1.0, 640, "jpg");
+        assert_eq!(args[args.iter().position(|a| a == "-ss").unwrap() + 1], "0");
+        assert!(args.contains(&"mjpeg".to_string()));
+    }
+
+    #[test]
+    fn embed_cover_args_per_container() {
+        let mp4 = build_embed_cover_args("/v.mp4", "/c.jpg", "/o.mp4", "mp4").unwrap();
+        let d_idx = mp4.iter().position(|a| a == "-disposition:v:1").unwrap();
+        assert_eq!(mp4[d_idx + 1], "attached_pic");
+        assert_eq!(mp4.iter().filter(|a| *a == "-i").count(), 2);
+
+        let mkv = build_embed_cover_args("/v.mkv", "/c.jpg", "/o.mkv", "mkv").unwrap();
+        let a_idx = mkv.iter().position(|a| a == "-attach").unwrap();
+        assert_eq!(mkv[a_idx + 1], "/c.jpg");
+        assert_eq!(mkv.iter().filter(|a| *a == "-i").count(), 1);
+
+        assert!(build_embed_cover_args("/v.webm", "/c.jpg", "/o.webm", "webm").is_none());
+    }
+
+    #[test]
+    fn hover_preview_args_are_short_small_and_silent() {
+        let args = build_hover_preview_args("/in.mov", "/hover.mp4", 4.0, "libx264");
+        let t_idx = args.iter().position(|a| a == "-t").unwrap();
+        assert_eq!(args[t_idx + 1], "1");
+        assert!(args.contains(&"fps=10,scale=320:-2".to_string()));
+        assert!(args.contains(&"-an".to_string()));
+        assert!(args.contains(&"+faststart".to_string()));
+        assert_eq!(args.last().unwrap(), "/hover.mp4");
+
+        let args = build_hover_preview_args("/in.mov", "/hover.webp", 0.0, "libwebp_anim");
+        let f_idx = args.iter().position(|a| a == "-f").unwrap();
+        assert_eq!(args[f_idx + 1], "webp");
+        assert!(!args.contains(&"-movflags".to_string()));
+    }
+
+    #[test]
+    fn ignore_errors_adds_input_side_flags_before_input() {
+        let mut o = opts();
+        o.ignore_errors = Some(true);
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &o, None, None, None).unwrap();
+        let i_idx = args.iter().position(|a| a == "-i").unwrap();
+        let err_idx = args.iter().position(|a| a == "-err_detect").unwrap();
+        let fflags_idx = args.iter().position(|a| a == "-fflags").unwrap();
+        assert!(err_idx < i_idx && fflags_idx < i_idx);
+        assert_eq!(args[err_idx + 1], "ignore_err");
+        assert_eq!(args[fflags_idx + 1], "+genpts+discardcorrupt");
+
+        let args = build_ffmpeg_command("/in.mp4", "/out.mp4", &opts(), None, None, None).unwrap();
+        assert!(!args.contains(&"-err_detect".to_string()));
+    }
+
     #[test]
     fn target_size_uses_bitrate_not_crf() {
         let mut o = opts();
@@ -1362,6 +3248,62 @@ mod tests {
         assert!(commands.pass2.contains(&"2".to_string()));
     }
 
+    #[test]
+    fn vp9_two_pass_speeds_up_the_first_pass_only() {
+        let cpu_used = |args: &[String]| {
+            let idx = args.iter().position(|a| a == "-cpu-used").unwrap();
+            (args[idx - 1].clone(), args[idx + 1].clone())
+        };
+        let mut o = opts();
+        o.codec = Some("libvpx-vp9".to_string());
+        o.output_format = Some("webm".to_string());
+        o.rate_control_mode = Some(RateControlMode::TargetSize);
+        o.target_size_mb = Some(20.0);
+        o.duration_secs = Some(60.0);
+        for (preset, second) in [
+            ("ultrafast", ("good", "2")),
+            ("medium", ("good", "1")),
+            ("slow", ("best", "0")),
+        ] {
+            o.preset = Some(preset.to_string());
+            let commands = build_two_pass_ffmpeg_commands(
+                "/in.mp4",
+                "/out.webm",
+                &o,
+                None,
+                None,
+                None,
+                "/tmp/passlog",
+            )
+            .unwrap();
+            assert_eq!(
+                cpu_used(&commands.pass1),
+                ("good".to_string(), "4".to_string())
+            );
+            assert_eq!(
+                commands.pass1.iter().filter(|a| *a == "-deadline").count(),
+                1
+            );
+            assert_eq!(
+                cpu_used(&commands.pass2),
+                (second.0.to_string(), second.1.to_string()),
+                "preset {}",
+                preset
+            );
+            let passlog_idx = commands
+                .pass2
+                .iter()
+                .position(|a| a == "-passlogfile")
+                .unwrap();
+            assert_eq!(commands.pass2[passlog_idx + 1], "/tmp/passlog");
+        }
+
+        o.rate_control_mode = Some(RateControlMode::Quality);
+        o.preset = Some("ultrafast".to_string());
+        let args = build_ffmpeg_command("/in.mp4", "/out.webm", &o, None, None, None).unwrap();
+        assert_eq!(cpu_used(&args).1, "4");
+    }
+
     #[test]
     fn first_frame_basic_args() {
         let args = build_first_frame_args("/in.mp4", "/out.jpg", 75, 1.0);
@@ -1396,4 +3338,69 @@ mod tests {
         let vf_idx = args.iter().position(|a| a == "-vf").unwrap();
         assert_eq!(args.get(vf_idx + 1).unwrap(), "scale=round(iw*0.5/2)*2:-2");
     }
+
+    /// The source may only appear as the value of `-i`, never as an output or elsewhere.
+    fn assert_source_read_only(args: &[String], source: &str) {
+        for (i, arg) in args.iter().enumerate() {
+            if arg.contains(source) {
+                assert!(
+                    i > 0 && args[i - 1] == "-i" && arg == source,
+                    "source used outside -i at {}: {:?}",
+                    i,
+                    args
+                );
+            }
+        }
+        assert_ne!(args.last().map(String::as_str), Some(source));
+    }
+
+    #[test]
+    fn source_is_only_ever_an_ffmpeg_input() {
+        let src = "/videos/source.mov";
+        let mut o = opts();
+        o.remove_audio = Some(false);
+        o.preserve_metadata = Some(true);
+        o.preserve_subtitles = Some(true);
+        o.subtitle_stream_count = Some(1);
+        o.audio_stream_indices = Some(vec![0, 1]);
+        o.audio_stream_count = Some(2);
+        o.duration_secs = Some(60.0);
+        assert_source_read_only(
+            &build_ffmpeg_command(src, "/out.mp4", &o, None, None, None).unwrap(),
+            src,
+        );
+        o.rate_control_mode = Some(RateControlMode::TargetSize);
+        o.target_size_mb = Some(50.0);
+        let two_pass =
+            build_two_pass_ffmpeg_commands(src, "/out.mp4", &o, None, None, None, "/tmp/log")
+                .unwrap();
+        assert_source_read_only(&two_pass.pass1, src);
+        assert_source_read_only(&two_pass.pass2, src);
+        o.rate_control_mode = Some(RateControlMode::Quality);
+
+        o.keep_segments = Some(vec![(1.0, 2.0)]);
+        o.preserve_subtitles = Some(false);
+        assert_source_read_only(
+            &build_ffmpeg_command(src, "/out.mp4", &o, None, None, None).unwrap(),
+            src,
+        );
+        o.keep_segments = None;
+        o.background_audio_path = Some("/music.mp3".to_string());
+        assert_source_read_only(
+            &build_ffmpeg_command(src, "/out.mp4", &o, None, None, None).unwrap(),
+            src,
+        );
+
+        assert_source_read_only(&build_cover_image_args(src, "/cover.jpg", Some(2.0)), src);
+        assert_source_read_only(
+            &build_thumbnail_args(src, "/thumb.jpg", 2.0, 640, "jpg"),
+            src,
+        );
+        assert_source_read_only(&build_extract_args(src, 0.0, 3.0, "/seg.mp4", true, 0), src);
+        assert_source_read_only(
+            &build_pip_compose_args(src, "/cam.mp4", "/out.mp4", &o, &PipLayout::default(), 1920)
+                .unwrap(),
+            src,
+        );
+    }
 }
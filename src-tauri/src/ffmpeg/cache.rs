@@ -10,10 +10,16 @@ use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use std::time::UNIX_EPOCH;
 
+use super::storyboard::Storyboard;
 use super::{SizeEstimate, TranscodeOptions};
 use parking_lot::Mutex;
 
 const PREVIEW_CACHE_MAX_ENTRIES: usize = 16;
+/// Hover previews are tiny; keep enough for a typical file list.
+const HOVER_PREVIEW_CACHE_MAX_ENTRIES: usize = 64;
+const STORYBOARD_CACHE_MAX_ENTRIES: usize = 16;
+/// Estimates are small but otherwise unbounded; maintenance trims them to this many.
+const ESTIMATE_CACHE_MAX_ENTRIES: usize = 256;
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 pub struct FileSignature {
@@ -45,16 +51,19 @@ struct PreviewCacheKey {
     input_path: String,
     preview_duration: u32,
     preview_start_ms: u64,
+    video_stream: u32,
     options_key: String,
     file_signature: FileSignature,
 }
 
-/// Key for segment store: (input_path, preview_duration, preview_start_ms, file_signature).
+/// Key for segment store: (input_path, preview_duration, preview_start_ms, video_stream,
+/// file_signature). Segments hold only the selected video stream.
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 struct SegmentKey {
     input_path: String,
     preview_duration: u32,
     preview_start_ms: u64,
+    video_stream: u32,
     file_signature: FileSignature,
 }
 
@@ -67,6 +76,23 @@ struct EstimateKey {
     file_signature: FileSignature,
 }
 
+/// Key for hover preview cache: (input_path, format, file_signature).
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+struct HoverPreviewKey {
+    input_path: String,
+    format: String,
+    file_signature: FileSignature,
+}
+
+/// Key for storyboard cache: (input_path, frames, tile_width, file_signature).
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+struct StoryboardKey {
+    input_path: String,
+    frames: u32,
+    tile_width: u32,
+    file_signature: FileSignature,
+}
+
 /// Segment store entry with ref count. Segments are shared across transcodes with same (input, duration).
 struct SegmentEntry {
     segment_paths: Vec<PathBuf>,
@@ -84,8 +110,12 @@ struct PreviewCache {
     lru: VecDeque<(PreviewCacheKey, PreviewEntry)>,
     /// Segments keyed by (input, duration, preview_start_ms). Ref count = number of LRU entries using them.
     segments: HashMap<SegmentKey, SegmentEntry>,
-    /// Structured estimates keyed by (input, duration, options_key).
+    /// Video-only estimates keyed by (input, duration, options_key).
     estimates: HashMap<EstimateKey, SizeEstimate>,
+    /// Hover preview clips. Front = least recent.
+    hover_previews: VecDeque<(HoverPreviewKey, PathBuf)>,
+    /// Seek bar sprites and their indexes. Front = least recent.
+    storyboards: VecDeque<(StoryboardKey, Storyboard)>,
 }
 
 impl PreviewCache {
@@ -94,6 +124,8 @@ impl PreviewCache {
             lru: VecDeque::new(),
             segments: HashMap::new(),
             estimates: HashMap::new(),
+            hover_previews: VecDeque::new(),
+            storyboards: VecDeque::new(),
         }
     }
 
@@ -112,6 +144,7 @@ impl PreviewCache {
             input_path: key.input_path,
             preview_duration: key.preview_duration,
             preview_start_ms: key.preview_start_ms,
+            video_stream: key.video_stream,
             file_signature: key.file_signature,
         };
         if let Some(seg) = self.segments.get_mut(&seg_key) {
@@ -136,6 +169,7 @@ impl PreviewCache {
             input_path: key.input_path,
             preview_duration: key.preview_duration,
             preview_start_ms: key.preview_start_ms,
+            video_stream: key.video_stream,
             file_signature: key.file_signature,
         };
         if let Some(seg) = self.segments.get_mut(&seg_key) {
@@ -156,11 +190,13 @@ fn preview_cache() -> &'static Mutex<PreviewCache> {
     PREVIEW_CACHE.get_or_init(|| Mutex::new(PreviewCache::new()))
 }
 
-/// Get cached segments for (input, duration, preview_start_ms). Used to reuse extraction when only options change.
+/// Get cached segments for (input, duration, preview_start_ms, video_stream). Used to reuse
+/// extraction when only options change.
 pub fn get_cached_segments(
     input_path: &str,
     preview_duration: u32,
     preview_start_ms: u64,
+    video_stream: u32,
     file_signature: Option<&FileSignature>,
 ) -> Option<Vec<PathBuf>> {
     let file_signature = file_signature?.clone();
@@ -169,6 +205,7 @@ pub fn get_cached_segments(
         input_path: input_path.to_string(),
         preview_duration,
         preview_start_ms,
+        video_stream,
         file_signature,
     };
     let entry = guard.segments.get(&key)?;
@@ -195,6 +232,7 @@ pub fn get_cached_preview(
         input_path: input_path.to_string(),
         preview_duration,
         preview_start_ms,
+        video_stream: options.effective_video_stream_index(),
         options_key: options_key.clone(),
         file_signature,
     };
@@ -211,6 +249,7 @@ pub fn get_cached_preview(
         input_path: key.input_path,
         preview_duration: key.preview_duration,
         preview_start_ms: key.preview_start_ms,
+        video_stream: key.video_stream,
         file_signature: key.file_signature,
     };
     let Some(seg_entry) = guard.segments.get(&seg_key) else {
@@ -228,7 +267,8 @@ pub fn get_cached_preview(
     Some(result)
 }
 
-/// Get cached estimate for (input, duration, options).
+/// Get the cached video-only estimate for (input, duration, options). Keyed without audio
+/// settings in quality mode; the caller adds the audio part.
 pub fn get_cached_estimate(
     input_path: &str,
     preview_duration: u32,
@@ -236,7 +276,7 @@ pub fn get_cached_estimate(
     file_signature: Option<&FileSignature>,
 ) -> Option<SizeEstimate> {
     let file_signature = file_signature?.clone();
-    let options_key = options.options_cache_key_for_estimate();
+    let options_key = options.options_cache_key_for_video_estimate();
     let key = EstimateKey {
         input_path: input_path.to_string(),
         preview_duration,
@@ -247,7 +287,7 @@ pub fn get_cached_estimate(
     guard.estimates.get(&key).cloned()
 }
 
-/// Store cached estimate for (input, duration, options).
+/// Store the video-only estimate for (input, duration, options).
 pub fn set_cached_estimate(
     input_path: &str,
     preview_duration: u32,
@@ -258,7 +298,7 @@ pub fn set_cached_estimate(
     let Some(file_signature) = file_signature.cloned() else {
         return;
     };
-    let options_key = options.options_cache_key_for_estimate();
+    let options_key = options.options_cache_key_for_video_estimate();
     let key = EstimateKey {
         input_path: input_path.to_string(),
         preview_duration,
@@ -269,6 +309,118 @@ pub fn set_cached_estimate(
     guard.estimates.insert(key, estimate);
 }
 
+/// Get cached hover preview clip for (input, format).
+pub fn get_cached_hover_preview(
+    input_path: &str,
+    format: &str,
+    file_signature: Option<&FileSignature>,
+) -> Option<PathBuf> {
+    let key = HoverPreviewKey {
+        input_path: input_path.to_string(),
+        format: format.to_string(),
+        file_signature: file_signature?.clone(),
+    };
+    let mut guard = preview_cache().lock();
+    let idx = guard.hover_previews.iter().position(|(k, _)| k == &key)?;
+    let (k, path) = guard.hover_previews.remove(idx)?;
+    if !path.exists() {
+        return None;
+    }
+    guard.hover_previews.push_back((k, path.clone()));
+    Some(path)
+}
+
+/// Store hover preview clip for (input, format), evicting the least recent beyond the limit.
+pub fn set_cached_hover_preview(
+    input_path: &str,
+    format: &str,
+    file_signature: Option<&FileSignature>,
+    path: PathBuf,
+) {
+    let Some(file_signature) = file_signature.cloned() else {
+        return;
+    };
+    let key = HoverPreviewKey {
+        input_path: input_path.to_string(),
+        format: format.to_string(),
+        file_signature,
+    };
+    let mut guard = preview_cache().lock();
+    if let Some(idx) = guard.hover_previews.iter().position(|(k, _)| k == &key)
+        && let Some((_, old)) = guard.hover_previews.remove(idx)
+        && old != path
+    {
+        let _ = fs::remove_file(&old);
+    }
+    while guard.hover_previews.len() >= HOVER_PREVIEW_CACHE_MAX_ENTRIES {
+        if let Some((_, old)) = guard.hover_previews.pop_front() {
+            let _ = fs::remove_file(&old);
+        }
+    }
+    guard.hover_previews.push_back((key, path));
+}
+
+fn remove_storyboard_files(storyboard: &Storyboard) {
+    let _ = fs::remove_file(&storyboard.sprite_path);
+    let _ = fs::remove_file(&storyboard.index_path);
+}
+
+/// Get cached storyboard for (input, frames, tile_width) when both of its files still exist.
+pub fn get_cached_storyboard(
+    input_path: &str,
+    frames: u32,
+    tile_width: u32,
+    file_signature: Option<&FileSignature>,
+) -> Option<Storyboard> {
+    let key = StoryboardKey {
+        input_path: input_path.to_string(),
+        frames,
+        tile_width,
+        file_signature: file_signature?.clone(),
+    };
+    let mut guard = preview_cache().lock();
+    let idx = guard.storyboards.iter().position(|(k, _)| k == &key)?;
+    let (k, storyboard) = guard.storyboards.remove(idx)?;
+    if !Path::new(&storyboard.sprite_path).exists() || !Path::new(&storyboard.index_path).exists() {
+        remove_storyboard_files(&storyboard);
+        return None;
+    }
+    guard.storyboards.push_back((k, storyboard.clone()));
+    Some(storyboard)
+}
+
+/// Store storyboard for (input, frames, tile_width), evicting the least recent beyond the limit.
+pub fn set_cached_storyboard(
+    input_path: &str,
+    frames: u32,
+    tile_width: u32,
+    file_signature: Option<&FileSignature>,
+    storyboard: Storyboard,
+) {
+    let Some(file_signature) = file_signature.cloned() else {
+        return;
+    };
+    let key = StoryboardKey {
+        input_path: input_path.to_string(),
+        frames,
+        tile_width,
+        file_signature,
+    };
+    let mut guard = preview_cache().lock();
+    if let Some(idx) = guard.storyboards.iter().position(|(k, _)| k == &key)
+        && let Some((_, old)) = guard.storyboards.remove(idx)
+        && old.sprite_path != storyboard.sprite_path
+    {
+        remove_storyboard_files(&old);
+    }
+    while guard.storyboards.len() >= STORYBOARD_CACHE_MAX_ENTRIES {
+        if let Some((_, old)) = guard.storyboards.pop_front() {
+            remove_storyboard_files(&old);
+        }
+    }
+    guard.storyboards.push_back((key, storyboard));
+}
+
 /// Returns all cached paths (segments + outputs).
 /// Used by cleanup to preserve cached files.
 pub fn get_all_cached_paths() -> Vec<PathBuf> {
@@ -282,6 +434,21 @@ pub fn get_all_cached_paths() -> Vec<PathBuf> {
             paths.push(e.output_path.clone());
         }
     }
+    paths.extend(
+        guard
+            .hover_previews
+            .iter()
+            .filter(|(_, p)| p.exists())
+            .map(|(_, p)| p.clone()),
+    );
+    for (_, storyboard) in &guard.storyboards {
+        paths.extend(
+            [&storyboard.sprite_path, &storyboard.index_path]
+                .into_iter()
+                .map(PathBuf::from)
+                .filter(|p| p.exists()),
+        );
+    }
     paths
 }
 
@@ -300,10 +467,12 @@ pub fn set_cached_preview(
     };
     let input_path_owned = input_path.to_string();
     let options_key = options.options_cache_key_for_preview();
+    let video_stream = options.effective_video_stream_index();
     let key = PreviewCacheKey {
         input_path: input_path_owned.clone(),
         preview_duration,
         preview_start_ms,
+        video_stream,
         options_key: options_key.clone(),
         file_signature: file_signature.clone(),
     };
@@ -311,6 +480,7 @@ pub fn set_cached_preview(
         input_path: input_path_owned,
         preview_duration,
         preview_start_ms,
+        video_stream,
         file_signature,
     };
 
@@ -328,6 +498,7 @@ pub fn set_cached_preview(
             input_path: old_key.input_path,
             preview_duration: old_key.preview_duration,
             preview_start_ms: old_key.preview_start_ms,
+            video_stream: old_key.video_stream,
             file_signature: old_key.file_signature,
         };
         if let Some(seg) = guard.segments.get_mut(&old_seg_key) {
@@ -379,6 +550,90 @@ pub fn set_cached_preview(
     guard.lru.push_back((key, PreviewEntry { output_path }));
 }
 
+/// Drops entries whose files are gone and estimates for inputs that changed or disappeared,
+/// then trims every part of the cache to its limit. Returns how many entries were dropped.
+/// Run periodically by maintenance; lookups already skip dead entries, this frees them sooner.
+pub fn enforce_cache_quotas() -> usize {
+    let mut guard = preview_cache().lock();
+    let mut dropped = 0;
+
+    let entries: Vec<_> = guard.lru.drain(..).collect();
+    for (key, entry) in entries {
+        let seg_key = SegmentKey {
+            input_path: key.input_path.clone(),
+            preview_duration: key.preview_duration,
+            preview_start_ms: key.preview_start_ms,
+            video_stream: key.video_stream,
+            file_signature: key.file_signature.clone(),
+        };
+        let alive = entry.output_path.exists()
+            && guard
+                .segments
+                .get(&seg_key)
+                .is_some_and(|seg| seg.segment_paths.iter().all(|p| p.exists()));
+        if alive {
+            guard.lru.push_back((key, entry));
+        } else {
+            guard.drop_preview_entry(key, entry);
+            dropped += 1;
+        }
+    }
+    while guard.lru.len() > PREVIEW_CACHE_MAX_ENTRIES {
+        guard.evict_one();
+        dropped += 1;
+    }
+
+    let before = guard.estimates.len();
+    guard.estimates.retain(|key, _| {
+        file_signature(Path::new(&key.input_path)).as_ref() == Some(&key.file_signature)
+    });
+    // Estimates keep no recency order, so any beyond the limit may go.
+    let excess: Vec<_> = guard
+        .estimates
+        .keys()
+        .skip(ESTIMATE_CACHE_MAX_ENTRIES)
+        .cloned()
+        .collect();
+    for key in excess {
+        guard.estimates.remove(&key);
+    }
+    dropped += before - guard.estimates.len();
+
+    let before = guard.hover_previews.len();
+    guard.hover_previews.retain(|(_, path)| path.exists());
+    while guard.hover_previews.len() > HOVER_PREVIEW_CACHE_MAX_ENTRIES {
+        if let Some((_, old)) = guard.hover_previews.pop_front() {
+            let _ = fs::remove_file(&old);
+        }
+    }
+    dropped += before - guard.hover_previews.len();
+
+    let before = guard.storyboards.len();
+    guard.storyboards.retain(|(_, storyboard)| {
+        let whole = Path::new(&storyboard.sprite_path).exists()
+            && Path::new(&storyboard.index_path).exists();
+        if !whole {
+            remove_storyboard_files(storyboard);
+        }
+        whole
+    });
+    while guard.storyboards.len() > STORYBOARD_CACHE_MAX_ENTRIES {
+        if let Some((_, old)) = guard.storyboards.pop_front() {
+            remove_storyboard_files(&old);
+        }
+    }
+    dropped += before - guard.storyboards.len();
+
+    if dropped > 0 {
+        log::debug!(
+            target: "tiny_vid::ffmpeg::cache",
+            "enforce_cache_quotas: dropped {} entries",
+            dropped
+        );
+    }
+    dropped
+}
+
 /// Remove all cached files and clear the cache. Call on app exit.
 pub fn cleanup_preview_transcode_cache() {
     let mut guard = preview_cache().lock();
@@ -401,6 +656,12 @@ pub fn cleanup_preview_transcode_cache() {
         }
     }
     guard.estimates.clear();
+    for (_, path) in guard.hover_previews.drain(..) {
+        let _ = fs::remove_file(&path);
+    }
+    for (_, storyboard) in guard.storyboards.drain(..) {
+        remove_storyboard_files(&storyboard);
+    }
 }
 
 #[cfg(test)]
@@ -652,4 +913,122 @@ mod tests {
         cleanup_preview_transcode_cache();
         let _ = fs::remove_file(&input);
     }
+
+    #[test]
+    #[serial]
+    fn hover_preview_cache_round_trips_and_is_preserved_by_cleanup_scan() {
+        cleanup_preview_transcode_cache();
+
+        let input = std::env::temp_dir().join("hover_cache_test_input.mp4");
+        let _ = fs::write(&input, b"fake");
+        let input_str = input.to_string_lossy().to_string();
+        let sig = file_signature(&input).unwrap();
+        let clip = TempFileManager
+            .create("hover-preview.mp4", Some(b"clip"))
+            .unwrap();
+
+        assert_eq!(
+            get_cached_hover_preview(&input_str, "mp4", Some(&sig)),
+            None
+        );
+        set_cached_hover_preview(&input_str, "mp4", Some(&sig), clip.clone());
+        assert_eq!(
+            get_cached_hover_preview(&input_str, "mp4", Some(&sig)),
+            Some(clip.clone())
+        );
+        assert_eq!(
+            get_cached_hover_preview(&input_str, "webp", Some(&sig)),
+            None
+        );
+        assert!(get_all_cached_paths().contains(&clip));
+
+        cleanup_preview_transcode_cache();
+        assert!(!clip.exists(), "cleanup should remove hover previews");
+        let _ = fs::remove_file(&input);
+    }
+
+    #[test]
+    #[serial]
+    fn storyboard_cache_round_trips_and_drops_missing_files() {
+        use crate::ffmpeg::storyboard::StoryboardIndex;
+
+        cleanup_preview_transcode_cache();
+
+        let input = std::env::temp_dir().join("storyboard_cache_test_input.mp4");
+        let _ = fs::write(&input, b"fake");
+        let input_str = input.to_string_lossy().to_string();
+        let sig = file_signature(&input).unwrap();
+        let sprite = TempFileManager
+            .create("storyboard.jpg", Some(b"sprite"))
+            .unwrap();
+        let index = TempFileManager
+            .create("storyboard.json", Some(b"{}"))
+            .unwrap();
+        let storyboard = Storyboard {
+            sprite_path: sprite.to_string_lossy().to_string(),
+            index_path: index.to_string_lossy().to_string(),
+            index: StoryboardIndex {
+                columns: 1,
+                rows: 1,
+                tile_width: 160,
+                tile_height: 90,
+                interval_secs: 1.0,
+                duration_secs: 1.0,
+                tiles: Vec::new(),
+            },
+        };
+
+        set_cached_storyboard(&input_str, 1, 160, Some(&sig), storyboard.clone());
+        assert_eq!(
+            get_cached_storyboard(&input_str, 1, 160, Some(&sig)),
+            Some(storyboard)
+        );
+        assert_eq!(get_cached_storyboard(&input_str, 2, 160, Some(&sig)), None);
+        let cached = get_all_cached_paths();
+        assert!(cached.contains(&sprite) && cached.contains(&index));
+
+        fs::remove_file(&index).unwrap();
+        assert_eq!(get_cached_storyboard(&input_str, 1, 160, Some(&sig)), None);
+        assert!(!sprite.exists(), "a partial storyboard should be deleted");
+
+        cleanup_preview_transcode_cache();
+        let _ = fs::remove_file(&input);
+    }
+
+    #[test]
+    #[serial]
+    fn quotas_drop_dead_entries_and_stale_estimates() {
+        cleanup_preview_transcode_cache();
+
+        let input = std::env::temp_dir().join("quota_test_input.mp4");
+        let _ = fs::write(&input, b"fake");
+        let input_str = input.to_string_lossy().to_string();
+        let sig = file_signature(&input).unwrap();
+        let clip = TempFileManager.create("hover.mp4", Some(b"clip")).unwrap();
+        set_cached_hover_preview(&input_str, "mp4", Some(&sig), clip.clone());
+        let options = TranscodeOptions::default();
+        let estimate = SizeEstimate {
+            best_size: 1,
+            low_size: 1,
+            high_size: 1,
+            confidence: crate::ffmpeg::EstimateConfidence::High,
+            method: "sampled_bitrate".into(),
+            sample_count: 3,
+            sample_seconds_total: 4.5,
+        };
+        set_cached_estimate(&input_str, 3, &options, estimate, Some(&sig));
+        assert_eq!(enforce_cache_quotas(), 0);
+
+        fs::remove_file(&clip).unwrap();
+        fs::write(&input, b"edited").unwrap();
+        assert_eq!(enforce_cache_quotas(), 2);
+        assert_eq!(
+            get_cached_estimate(&input_str, 3, &options, Some(&sig)),
+            None
+        );
+        assert!(get_all_cached_paths().is_empty());
+
+        cleanup_preview_transcode_cache();
+        let _ = fs::remove_file(&input);
+    }
 }
@@ -0,0 +1,320 @@
+//! Per-job cancellation of FFmpeg processes.
+//!
+//! A `CancellationToken` travels in the [`FfmpegContext`](super::FfmpegContext); the runner
+//! registers each spawned child with it, so cancelling one token kills only that job's
+//! processes and makes its remaining steps fail fast with `Aborted`. `terminate_all_ffmpeg`
+//! still kills every running job (app exit, window close).
+//!
+//! Jobs the frontend can see (those announced with ffmpeg-job-started) hold a
+//! [`JobCancellation`], which makes their token reachable by job id through [`cancel_job`].
+//! Cancelling a preview that way leaves a long export running.
+//!
+//! The frontend cannot know a job id before the job-started event arrives, so jobs it starts
+//! can also carry a request id it chose itself; [`cancel_request`] cancels by that id, and a
+//! request cancelled before its job registered starts out cancelled.
+
+use std::collections::{HashMap, VecDeque};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+
+use crate::error::AppError;
+
+/// Every token that has had a child registered; pruned as tokens are dropped.
+static LIVE_TOKENS: Mutex<Vec<Weak<TokenInner>>> = Mutex::new(Vec::new());
+static NEXT_RUN_ID: AtomicU64 = AtomicU64::new(0);
+/// Bumped by every `kill_all_registered`; async runs watch it since their children are not
+/// registered (they are killed by dropping the tokio child instead).
+static TERMINATE_GENERATION: AtomicU64 = AtomicU64::new(0);
+static JOB_TOKENS: Mutex<Option<HashMap<u64, CancellationToken>>> = Mutex::new(None);
+static REQUESTS: Mutex<Option<Requests>> = Mutex::new(None);
+/// Early cancels remembered; older ones are forgotten.
+const MAX_EARLY_CANCELS: usize = 64;
+
+#[derive(Default)]
+struct Requests {
+    /// Request id -> job id of registered jobs.
+    jobs: HashMap<String, u64>,
+    /// Requests cancelled before their job registered.
+    early_cancels: VecDeque<String>,
+}
+
+#[derive(Default)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    children: Mutex<HashMap<u64, Child>>,
+}
+
+impl TokenInner {
+    fn kill_children(&self) {
+        let children: Vec<Child> = self.children.lock().drain().map(|(_, c)| c).collect();
+        for mut child in children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Cheap to clone; clones share state. Once cancelled, a token stays cancelled.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the token cancelled and kills its running FFmpeg processes.
+    pub fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::SeqCst) {
+            log::info!(
+                target: "tiny_vid::ffmpeg::cancel",
+                "cancel: terminating {} FFmpeg process(es)",
+                self.inner.children.lock().len()
+            );
+        }
+        self.inner.kill_children();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Tracks `child` for cancellation. If the token is already cancelled, kills it instead.
+    pub(crate) fn register(&self, mut child: Child) -> Result<u64, AppError> {
+        if self.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(AppError::aborted());
+        }
+        {
+            let mut live = LIVE_TOKENS.lock();
+            live.retain(|t| t.strong_count() > 0);
+            if !live.iter().any(|t| t.as_ptr() == Arc::as_ptr(&self.inner)) {
+                live.push(Arc::downgrade(&self.inner));
+            }
+        }
+        let id = NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed);
+        self.inner.children.lock().insert(id, child);
+        // cancel() may have drained the map between the check above and the insert.
+        if self.is_cancelled() {
+            self.inner.kill_children();
+            return Err(AppError::aborted());
+        }
+        Ok(id)
+    }
+
+    /// Takes back a registered child to wait on it; `None` when it was killed by cancellation.
+    pub(crate) fn take(&self, id: u64) -> Option<Child> {
+        self.inner.children.lock().remove(&id)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Makes the job's token cancellable by id; dropping it forgets the job.
+#[derive(Debug)]
+pub struct JobCancellation {
+    job_id: u64,
+    request_id: Option<String>,
+    token: CancellationToken,
+}
+
+impl JobCancellation {
+    pub fn register(job_id: u64) -> Self {
+        let token = CancellationToken::new();
+        JOB_TOKENS
+            .lock()
+            .get_or_insert_with(HashMap::new)
+            .insert(job_id, token.clone());
+        Self {
+            job_id,
+            request_id: None,
+            token,
+        }
+    }
+
+    /// Like `register`, also cancellable through `cancel_request(request_id)`.
+    pub fn register_request(job_id: u64, request_id: Option<String>) -> Self {
+        let mut job = Self::register(job_id);
+        let Some(request_id) = request_id else {
+            return job;
+        };
+        let mut guard = REQUESTS.lock();
+        let requests = guard.get_or_insert_with(Requests::default);
+        if let Some(pos) = requests.early_cancels.iter().position(|r| *r == request_id) {
+            requests.early_cancels.remove(pos);
+            job.token.cancel();
+        }
+        requests.jobs.insert(request_id.clone(), job_id);
+        job.request_id = Some(request_id);
+        job
+    }
+
+    /// The token to run the job's steps with.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for JobCancellation {
+    fn drop(&mut self) {
+        if let Some(jobs) = JOB_TOKENS.lock().as_mut() {
+            jobs.remove(&self.job_id);
+        }
+        if let Some(request_id) = &self.request_id
+            && let Some(requests) = REQUESTS.lock().as_mut()
+            && requests.jobs.get(request_id) == Some(&self.job_id)
+        {
+            requests.jobs.remove(request_id);
+        }
+    }
+}
+
+/// Cancels one running job; other jobs keep going.
+pub fn cancel_job(job_id: u64) -> Result<(), AppError> {
+    let token = JOB_TOKENS
+        .lock()
+        .as_ref()
+        .and_then(|jobs| jobs.get(&job_id).cloned())
+        .ok_or_else(|| AppError::from(format!("No running job {}", job_id)))?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::cancel",
+        "cancel_job: cancelling job {}",
+        job_id
+    );
+    token.cancel();
+    Ok(())
+}
+
+/// Cancels the job started for `request_id`, or the job once it registers. Cancelling a
+/// finished request does nothing.
+pub fn cancel_request(request_id: &str) {
+    let job_id = {
+        let mut guard = REQUESTS.lock();
+        let requests = guard.get_or_insert_with(Requests::default);
+        match requests.jobs.get(request_id) {
+            Some(&job_id) => job_id,
+            None => {
+                if requests.early_cancels.len() == MAX_EARLY_CANCELS {
+                    requests.early_cancels.pop_front();
+                }
+                requests.early_cancels.push_back(request_id.to_string());
+                return;
+            }
+        }
+    };
+    // The job may have finished since; that is not an error here.
+    let _ = cancel_job(job_id);
+}
+
+/// Kills the FFmpeg processes of every token without marking the tokens cancelled.
+pub(crate) fn kill_all_registered() {
+    TERMINATE_GENERATION.fetch_add(1, Ordering::SeqCst);
+    let live: Vec<Arc<TokenInner>> = {
+        let mut guard = LIVE_TOKENS.lock();
+        guard.retain(|t| t.strong_count() > 0);
+        guard.iter().filter_map(Weak::upgrade).collect()
+    };
+    for token in live {
+        token.kill_children();
+    }
+}
+
+pub(crate) fn terminate_generation() -> u64 {
+    TERMINATE_GENERATION.load(Ordering::SeqCst)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn sleeper() -> Child {
+        Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("spawn sleep")
+    }
+
+    #[test]
+    fn cancel_kills_only_that_tokens_children() {
+        let a = CancellationToken::new();
+        let b = CancellationToken::new();
+        let id_a = a.register(sleeper()).unwrap();
+        let id_b = b.register(sleeper()).unwrap();
+
+        a.cancel();
+        assert!(a.is_cancelled());
+        assert!(a.take(id_a).is_none());
+        assert!(!b.is_cancelled());
+        let mut child_b = b.take(id_b).expect("b's child still registered");
+        let _ = child_b.kill();
+        let _ = child_b.wait();
+    }
+
+    #[test]
+    fn register_after_cancel_is_aborted() {
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(matches!(token.register(sleeper()), Err(AppError::Aborted)));
+    }
+
+    #[test]
+    fn clones_share_state() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, CancellationToken::new());
+    }
+
+    #[test]
+    fn cancel_job_reaches_only_registered_jobs() {
+        let job_id = u64::MAX - 13;
+        assert!(cancel_job(job_id).is_err());
+        let job = JobCancellation::register(job_id);
+        let other = JobCancellation::register(job_id - 1);
+        let id = job.token().register(sleeper()).unwrap();
+
+        cancel_job(job_id).unwrap();
+        assert!(job.token().is_cancelled());
+        assert!(job.token().take(id).is_none());
+        assert!(!other.token().is_cancelled());
+        drop(job);
+        assert!(cancel_job(job_id).is_err());
+    }
+
+    #[test]
+    fn cancel_request_reaches_jobs_registered_before_or_after() {
+        let running = JobCancellation::register_request(u64::MAX - 20, Some("running".into()));
+        cancel_request("running");
+        assert!(running.token().is_cancelled());
+
+        cancel_request("not-yet");
+        let late = JobCancellation::register_request(u64::MAX - 21, Some("not-yet".into()));
+        assert!(late.token().is_cancelled());
+
+        let next = JobCancellation::register_request(u64::MAX - 22, Some("not-yet".into()));
+        assert!(!next.token().is_cancelled());
+    }
+}
@@ -0,0 +1,162 @@
+//! Picture-in-picture composition: a second input (e.g. a webcam recording) overlaid in a corner
+//! of the main input (e.g. a screen capture), encoded with the regular transcode options.
+//!
+//! The overlay is scaled to a fraction of the main video's width and drops out when it ends
+//! (`eof_action=pass`); output length follows the main input.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+pub const DEFAULT_PIP_SIZE: f64 = 0.25;
+const MIN_PIP_SIZE: f64 = 0.1;
+const MAX_PIP_SIZE: f64 = 0.5;
+pub const DEFAULT_PIP_MARGIN: u32 = 24;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PipPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Which input's first audio stream ends up in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipAudioSource {
+    #[default]
+    Main,
+    Overlay,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipLayout {
+    #[serde(default)]
+    pub position: PipPosition,
+    /// Overlay width as a fraction of the main video width (0.1-0.5). Default 0.25.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    /// Distance from the frame edges in main-video pixels. Default 24.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin: Option<u32>,
+    #[serde(default)]
+    pub audio_source: PipAudioSource,
+}
+
+impl PipLayout {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(size) = self.size
+            && !(MIN_PIP_SIZE..=MAX_PIP_SIZE).contains(&size)
+        {
+            return Err(AppError::from(format!(
+                "Picture-in-picture size must be between {} and {}",
+                MIN_PIP_SIZE, MAX_PIP_SIZE
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn effective_size(&self) -> f64 {
+        self.size.unwrap_or(DEFAULT_PIP_SIZE)
+    }
+
+    pub fn effective_margin(&self) -> u32 {
+        self.margin.unwrap_or(DEFAULT_PIP_MARGIN)
+    }
+
+    /// Overlay width in pixels for a main video `main_width` wide (even, at least 2).
+    pub(crate) fn overlay_width(&self, main_width: u32) -> u32 {
+        ((main_width as f64 * self.effective_size()).round() as u32).max(2) & !1
+    }
+
+    /// `overlay` filter x/y expressions for the configured corner.
+    fn overlay_position(&self) -> (String, String) {
+        let m = self.effective_margin();
+        let (left, top) = (m.to_string(), m.to_string());
+        let (right, bottom) = (format!("W-w-{}", m), format!("H-h-{}", m));
+        match self.position {
+            PipPosition::TopLeft => (left, top),
+            PipPosition::TopRight => (right, top),
+            PipPosition::BottomLeft => (left, bottom),
+            PipPosition::BottomRight => (right, bottom),
+        }
+    }
+
+    /// `-filter_complex` graph overlaying input 1 on input 0, output label `[v]`.
+    /// `scale` < 1 downscales the composed frame like a regular transcode.
+    pub(crate) fn filter_graph(&self, main_width: u32, scale: f64) -> String {
+        let (x, y) = self.overlay_position();
+        let mut graph = format!(
+            "[1:v]scale={}:-2[pip];[0:v][pip]overlay=x={}:y={}:eof_action=pass",
+            self.overlay_width(main_width),
+            x,
+            y
+        );
+        if scale < 1.0 {
+            graph.push_str(&format!(",scale=round(iw*{}/2)*2:-2", scale));
+        }
+        graph.push_str("[v]");
+        graph
+    }
+
+    /// Input index whose audio is mapped.
+    pub(crate) fn audio_input(&self) -> u32 {
+        match self.audio_source {
+            PipAudioSource::Main => 0,
+            PipAudioSource::Overlay => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_graph_places_overlay_in_each_corner() {
+        let mut layout = PipLayout::default();
+        assert_eq!(
+            layout.filter_graph(1920, 1.0),
+            "[1:v]scale=480:-2[pip];[0:v][pip]overlay=x=W-w-24:y=H-h-24:eof_action=pass[v]"
+        );
+        layout.position = PipPosition::TopLeft;
+        layout.margin = Some(0);
+        assert!(layout.filter_graph(1920, 1.0).contains("overlay=x=0:y=0:"));
+        layout.position = PipPosition::TopRight;
+        assert!(
+            layout
+                .filter_graph(1920, 1.0)
+                .contains("overlay=x=W-w-0:y=0:")
+        );
+    }
+
+    #[test]
+    fn filter_graph_scales_composed_frame() {
+        let graph = PipLayout::default().filter_graph(1280, 0.5);
+        assert!(graph.ends_with("eof_action=pass,scale=round(iw*0.5/2)*2:-2[v]"));
+    }
+
+    #[test]
+    fn overlay_width_is_even() {
+        let layout = PipLayout {
+            size: Some(0.33),
+            ..PipLayout::default()
+        };
+        assert_eq!(layout.overlay_width(1366), 450);
+        assert_eq!(layout.overlay_width(0), 2);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_size() {
+        let mut layout = PipLayout::default();
+        assert!(layout.validate().is_ok());
+        layout.size = Some(0.8);
+        assert!(layout.validate().is_err());
+        layout.size = Some(0.5);
+        assert!(layout.validate().is_ok());
+    }
+}
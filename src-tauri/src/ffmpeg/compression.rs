@@ -0,0 +1,205 @@
+//! Codec-independent compression figures: size ratio and bits per pixel (bpp).
+//!
+//! bpp is the video bitrate spread over every pixel shown, `bitrate / (width * height * fps)`.
+//! It lets advanced users sanity-check a setting across codecs and resolutions: a 1080p30
+//! H.264 encode around 0.1 bpp looks fine, while 0.02 usually shows blocking. Audio is taken
+//! out of both sides so the figure describes the picture only.
+//!
+//! A ratio at or below 1 means "compression" grew the file; `LargerOutputWarning` reports that,
+//! before encoding (from an estimate) or after.
+
+use serde::Serialize;
+
+use super::ffprobe::VideoMetadata;
+use super::{TranscodeOptions, estimated_audio_kbps, supports_remux};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressionMetrics {
+    /// Input size over output size, e.g. 4.0 for an output a quarter of the input.
+    pub compression_ratio: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_bits_per_pixel: Option<f64>,
+    /// From the predicted size for estimates, from the written file for finished jobs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_bits_per_pixel: Option<f64>,
+}
+
+impl CompressionMetrics {
+    /// Metrics for turning the source described by `meta` into `output_bytes` with `options`.
+    pub fn new(meta: &VideoMetadata, options: &TranscodeOptions, output_bytes: u64) -> Self {
+        let compression_ratio = if output_bytes > 0 {
+            meta.size as f64 / output_bytes as f64
+        } else {
+            0.0
+        };
+        let (out_width, out_height) = output_frame_size(options, meta.width, meta.height);
+        let out_duration = options.output_duration_secs().unwrap_or(meta.duration);
+        let out_video_bps = total_bps(output_bytes, out_duration)
+            .map(|bps| bps - estimated_audio_kbps(options) * 1000.0);
+        Self {
+            compression_ratio,
+            source_bits_per_pixel: source_bits_per_pixel(meta),
+            output_bits_per_pixel: bits_per_pixel(
+                out_video_bps,
+                out_width,
+                out_height,
+                options.effective_fps(),
+            ),
+        }
+    }
+}
+
+fn total_bps(bytes: u64, duration: f64) -> Option<f64> {
+    (duration > 0.0 && bytes > 0).then(|| bytes as f64 * 8.0 / duration)
+}
+
+/// The stream's own bit rate when the container reports it, else the file's rate minus audio.
+fn source_video_bps(meta: &VideoMetadata) -> Option<f64> {
+    if let Some(bits) = meta.video_bit_rate.filter(|b| *b > 0) {
+        return Some(bits as f64);
+    }
+    let total = meta
+        .format_bit_rate
+        .filter(|b| *b > 0)
+        .map(|b| b as f64)
+        .or_else(|| total_bps(meta.size, meta.duration))?;
+    Some(total - meta.audio_bit_rate.unwrap_or(0) as f64)
+}
+
+/// The output is not smaller than its input.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LargerOutputWarning {
+    pub input_bytes: u64,
+    /// Written size, or the estimate's best guess before encoding.
+    pub output_bytes: u64,
+    /// The options allow replacing the encode with a stream copy of the source (larger output
+    /// policy `remux`).
+    pub remux_available: bool,
+    /// The encode was replaced with a stream copy.
+    pub remuxed: bool,
+}
+
+impl LargerOutputWarning {
+    /// Some when `output_bytes` is at least `input_bytes`.
+    pub fn check(input_bytes: u64, output_bytes: u64, options: &TranscodeOptions) -> Option<Self> {
+        (input_bytes > 0 && output_bytes >= input_bytes).then(|| Self {
+            input_bytes,
+            output_bytes,
+            remux_available: supports_remux(options),
+            remuxed: false,
+        })
+    }
+}
+
+/// bpp of the source's video stream, when its bit rate and frame size are known.
+pub(crate) fn source_bits_per_pixel(meta: &VideoMetadata) -> Option<f64> {
+    bits_per_pixel(source_video_bps(meta), meta.width, meta.height, meta.fps)
+}
+
+fn bits_per_pixel(video_bps: Option<f64>, width: u32, height: u32, fps: f64) -> Option<f64> {
+    let pixels_per_sec = width as f64 * height as f64 * fps;
+    let bps = video_bps.filter(|b| *b > 0.0)?;
+    (pixels_per_sec > 0.0 && pixels_per_sec.is_finite()).then(|| bps / pixels_per_sec)
+}
+
+/// Frame size after the crop, rotation and scale filters of `build_ffmpeg_command`.
+fn output_frame_size(options: &TranscodeOptions, width: u32, height: u32) -> (u32, u32) {
+    let (mut width, mut height) = options
+        .effective_crop()
+        .map_or((width, height), |(w, h, _, _)| (w, h));
+    if options.rotation.is_some_and(|r| r % 180 == 90) {
+        std::mem::swap(&mut width, &mut height);
+    }
+    let scale = options.effective_scale();
+    if scale < 1.0 && width > 0 {
+        let scaled_width = ((width as f64 * scale / 2.0).round() * 2.0) as u32;
+        let scaled_height =
+            ((height as f64 * scaled_width as f64 / width as f64 / 2.0).round() * 2.0) as u32;
+        (scaled_width, scaled_height)
+    } else {
+        (width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> VideoMetadata {
+        VideoMetadata {
+            duration: 10.0,
+            width: 1920,
+            height: 1080,
+            size: 25_000_000,
+            fps: 30.0,
+            video_bit_rate: Some(18_662_400),
+            audio_bit_rate: Some(128_000),
+            ..VideoMetadata::default()
+        }
+    }
+
+    #[test]
+    fn bits_per_pixel_follows_output_frame_size_and_fps() {
+        let options = TranscodeOptions {
+            scale: Some(0.5),
+            fps: Some(30.0),
+            remove_audio: Some(true),
+            duration_secs: Some(10.0),
+            ..TranscodeOptions::default()
+        };
+        // 960x540 at 30 fps for 10 s, 0.1 bpp.
+        let metrics = CompressionMetrics::new(&meta(), &options, 1_944_000);
+        assert!((metrics.compression_ratio - 25_000_000.0 / 1_944_000.0).abs() < 1e-9);
+        assert!((metrics.source_bits_per_pixel.unwrap() - 0.3).abs() < 1e-9);
+        assert!((metrics.output_bits_per_pixel.unwrap() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn crop_and_rotation_change_the_frame_size() {
+        let options = TranscodeOptions {
+            crop_width: Some(1000),
+            crop_height: Some(500),
+            rotation: Some(90),
+            scale: Some(0.5),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(output_frame_size(&options, 1920, 1080), (250, 500));
+        assert_eq!(
+            output_frame_size(&TranscodeOptions::default(), 1920, 1080),
+            (1920, 1080)
+        );
+    }
+
+    #[test]
+    fn larger_output_warning_only_when_the_output_does_not_shrink() {
+        let options = TranscodeOptions::default();
+        assert_eq!(LargerOutputWarning::check(1000, 999, &options), None);
+        assert_eq!(LargerOutputWarning::check(0, 10, &options), None);
+        let warning = LargerOutputWarning::check(1000, 1000, &options).unwrap();
+        assert!(warning.remux_available && !warning.remuxed);
+        let cropped = TranscodeOptions {
+            crop_width: Some(320),
+            crop_height: Some(240),
+            ..TranscodeOptions::default()
+        };
+        let warning = LargerOutputWarning::check(1000, 4000, &cropped).unwrap();
+        assert!(!warning.remux_available);
+    }
+
+    #[test]
+    fn missing_duration_or_empty_output_gives_no_bpp() {
+        let metrics = CompressionMetrics::new(
+            &VideoMetadata {
+                duration: 0.0,
+                ..meta()
+            },
+            &TranscodeOptions::default(),
+            0,
+        );
+        assert_eq!(metrics.compression_ratio, 0.0);
+        assert_eq!(metrics.output_bits_per_pixel, None);
+        assert!(metrics.source_bits_per_pixel.is_some());
+    }
+}
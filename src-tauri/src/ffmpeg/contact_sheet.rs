@@ -0,0 +1,232 @@
+//! Contact sheet: one tiled JPEG of evenly spaced frames with timestamps burned in, for
+//! documenting long recordings at a glance.
+//!
+//! Each tile is a separate fast-seek input (`-ss` before `-i`), so long files are not decoded
+//! end to end. Timestamps need the `drawtext` filter (libfreetype); builds without it, or where
+//! drawtext fails at runtime (no usable font), get a sheet without labels.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::ffprobe::get_video_metadata_in;
+use super::{FfmpegContext, path_to_string, run_ffmpeg_blocking};
+use crate::error::AppError;
+
+const MAX_GRID_SIDE: u32 = 12;
+const MIN_SHEET_WIDTH: u32 = 320;
+const MAX_SHEET_WIDTH: u32 = 7680;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactSheetResult {
+    pub path: String,
+    pub tiles: u32,
+    /// False when the FFmpeg build cannot draw text; tiles are then unlabeled.
+    pub timestamps_burned: bool,
+}
+
+/// Sample times at the centre of each of `count` equal slices of `duration`.
+fn tile_timestamps(duration: f64, count: u32) -> Vec<f64> {
+    (0..count)
+        .map(|i| duration * (i as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// `HH\:MM\:SS` with colons escaped for a drawtext `text` value.
+fn drawtext_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!(
+        "{:02}\\:{:02}\\:{:02}",
+        total / 3600,
+        (total / 60) % 60,
+        total % 60
+    )
+}
+
+/// Even tile height matching the source aspect ratio.
+pub(super) fn tile_height(tile_width: u32, src_width: u32, src_height: u32) -> u32 {
+    if src_width == 0 || src_height == 0 {
+        return (tile_width * 9 / 16).max(2) & !1;
+    }
+    let h = (tile_width as f64 * src_height as f64 / src_width as f64).round() as u32;
+    h.max(2) & !1
+}
+
+fn build_contact_sheet_args(
+    input_path: &str,
+    output_path: &str,
+    timestamps: &[f64],
+    cols: u32,
+    rows: u32,
+    tile_size: (u32, u32),
+    burn_timestamps: bool,
+) -> Vec<String> {
+    let (tile_w, tile_h) = tile_size;
+    let mut args = vec!["-nostdin".to_string(), "-y".to_string()];
+    for t in timestamps {
+        args.extend([
+            "-ss".to_string(),
+            format!("{:.3}", t),
+            "-i".to_string(),
+            input_path.to_string(),
+        ]);
+    }
+    let mut graph = String::new();
+    for (i, t) in timestamps.iter().enumerate() {
+        graph.push_str(&format!(
+            "[{i}:v]trim=end_frame=1,setpts=PTS-STARTPTS,\
+             scale={tile_w}:{tile_h}:force_original_aspect_ratio=decrease,\
+             pad={tile_w}:{tile_h}:(ow-iw)/2:(oh-ih)/2,setsar=1"
+        ));
+        if burn_timestamps {
+            graph.push_str(&format!(
+                ",drawtext=text='{}':x=w-tw-6:y=h-th-6:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4",
+                drawtext_timestamp(*t),
+                (tile_h / 10).clamp(10, 48)
+            ));
+        }
+        graph.push_str(&format!("[t{i}];"));
+    }
+    for i in 0..timestamps.len() {
+        graph.push_str(&format!("[t{i}]"));
+    }
+    graph.push_str(&format!(
+        "concat=n={}:v=1:a=0,tile={cols}x{rows}:padding=4:color=black[out]",
+        timestamps.len()
+    ));
+    args.extend([
+        "-filter_complex".to_string(),
+        graph,
+        "-map".to_string(),
+        "[out]".to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-q:v".to_string(),
+        "3".to_string(),
+        "-f".to_string(),
+        "mjpeg".to_string(),
+        output_path.to_string(),
+    ]);
+    args
+}
+
+/// Whether `ffmpeg -filters` lists `name`.
+pub(super) fn has_filter(ctx: &FfmpegContext, name: &str) -> bool {
+    let mut cmd = ctx.ffmpeg_command();
+    cmd.args(["-hide_banner", "-filters"]);
+    let Ok(output) = cmd.output() else {
+        return false;
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(name))
+}
+
+/// Renders a `cols` x `rows` contact sheet `width` px wide into the temp dir. Blocking.
+pub fn export_contact_sheet(
+    ctx: &FfmpegContext,
+    path: &Path,
+    cols: u32,
+    rows: u32,
+    width: u32,
+) -> Result<ContactSheetResult, AppError> {
+    if !(1..=MAX_GRID_SIDE).contains(&cols) || !(1..=MAX_GRID_SIDE).contains(&rows) {
+        return Err(AppError::from(format!(
+            "Contact sheet grid must be between 1x1 and {0}x{0}",
+            MAX_GRID_SIDE
+        )));
+    }
+    let width = width.clamp(MIN_SHEET_WIDTH, MAX_SHEET_WIDTH);
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    if meta.duration <= 0.0 {
+        return Err(AppError::from("Video duration is unknown"));
+    }
+    let tile_w = (width / cols).max(2) & !1;
+    let tile_h = tile_height(tile_w, meta.width, meta.height);
+    let timestamps = tile_timestamps(meta.duration, cols * rows);
+
+    let output = ctx.create_temp("contact-sheet.jpg", None)?;
+    let input_str = path_to_string(path);
+    let output_str = path_to_string(&output);
+    let build = |burn| {
+        build_contact_sheet_args(
+            &input_str,
+            &output_str,
+            &timestamps,
+            cols,
+            rows,
+            (tile_w, tile_h),
+            burn,
+        )
+    };
+
+    let mut timestamps_burned = has_filter(ctx, "drawtext");
+    let mut result =
+        run_ffmpeg_blocking(ctx, build(timestamps_burned), None, None, None, None, None);
+    if result.is_err() && timestamps_burned {
+        log::warn!(
+            target: "tiny_vid::ffmpeg::contact_sheet",
+            "export_contact_sheet: drawtext failed, retrying without timestamps: {:?}",
+            result
+        );
+        timestamps_burned = false;
+        result = run_ffmpeg_blocking(ctx, build(false), None, None, None, None, None);
+    }
+    if let Err(e) = result {
+        let _ = fs::remove_file(&output);
+        return Err(e);
+    }
+    log::info!(
+        target: "tiny_vid::ffmpeg::contact_sheet",
+        "export_contact_sheet: {}x{} tiles -> {}",
+        cols,
+        rows,
+        output_str
+    );
+    Ok(ContactSheetResult {
+        path: output_str,
+        tiles: cols * rows,
+        timestamps_burned,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_timestamps_sample_slice_centres() {
+        assert_eq!(tile_timestamps(40.0, 4), vec![5.0, 15.0, 25.0, 35.0]);
+    }
+
+    #[test]
+    fn drawtext_timestamp_escapes_colons() {
+        assert_eq!(drawtext_timestamp(3725.9), "01\\:02\\:05");
+        assert_eq!(drawtext_timestamp(-1.0), "00\\:00\\:00");
+    }
+
+    #[test]
+    fn tile_height_keeps_aspect_and_is_even() {
+        assert_eq!(tile_height(320, 1920, 1080), 180);
+        assert_eq!(tile_height(320, 1080, 1920) % 2, 0);
+        assert_eq!(tile_height(320, 0, 0), 180);
+    }
+
+    #[test]
+    fn contact_sheet_args_seek_each_tile_and_tile_grid() {
+        let ts = tile_timestamps(60.0, 4);
+        let args = build_contact_sheet_args("/in.mp4", "/sheet.jpg", &ts, 2, 2, (320, 180), true);
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 4);
+        assert_eq!(args.iter().filter(|a| *a == "-ss").count(), 4);
+        let fc_idx = args.iter().position(|a| a == "-filter_complex").unwrap();
+        let graph = &args[fc_idx + 1];
+        assert!(graph.contains("concat=n=4:v=1:a=0,tile=2x2"));
+        assert!(graph.contains("drawtext=text='00\\:00\\:07'"));
+        assert_eq!(args.last().unwrap(), "/sheet.jpg");
+
+        let args = build_contact_sheet_args("/in.mp4", "/sheet.jpg", &ts, 2, 2, (320, 180), false);
+        assert!(!args.iter().any(|a| a.contains("drawtext")));
+    }
+}
@@ -0,0 +1,270 @@
+//! Explicit FFmpeg runtime configuration passed to the runner and preview pipeline.
+//!
+//! Binaries, temp dir and process limits travel with each job instead of being read back from
+//! process-global state, so tests and embedders can point individual jobs at different FFmpeg
+//! builds or resource budgets without mutating env vars or OnceLocks. The context also carries
+//! the job's [`CancellationToken`].
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use super::TempFileManager;
+use super::cancel::CancellationToken;
+use super::discovery::{ffprobe_path_for, get_ffmpeg_path};
+use crate::error::AppError;
+
+#[cfg(windows)]
+const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x00004000;
+#[cfg(windows)]
+const IDLE_PRIORITY_CLASS: u32 = 0x00000040;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegContext {
+    ffmpeg_path: PathBuf,
+    ffprobe_path: Option<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    thread_limit: Option<u32>,
+    niceness: Option<i32>,
+    cancellation: CancellationToken,
+    job_id: Option<u64>,
+}
+
+impl FfmpegContext {
+    pub fn new(ffmpeg_path: impl Into<PathBuf>) -> Self {
+        Self {
+            ffmpeg_path: ffmpeg_path.into(),
+            ffprobe_path: None,
+            temp_dir: None,
+            thread_limit: None,
+            niceness: None,
+            cancellation: CancellationToken::new(),
+            job_id: None,
+        }
+    }
+
+    /// Context for the app-wide discovered FFmpeg (see [`get_ffmpeg_path`]) with default limits.
+    pub fn discover() -> Result<Self, AppError> {
+        Ok(Self::new(get_ffmpeg_path()?))
+    }
+
+    /// Use this ffprobe instead of the one next to ffmpeg.
+    pub fn with_ffprobe_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ffprobe_path = Some(path.into());
+        self
+    }
+
+    /// Create job temp files here instead of the system temp dir. Startup cleanup only sweeps
+    /// the system temp dir, so callers own cleanup of a custom dir.
+    pub fn with_temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Cap FFmpeg's `-threads` (0 keeps FFmpeg's automatic choice).
+    pub fn with_thread_limit(mut self, threads: u32) -> Self {
+        self.thread_limit = (threads > 0).then_some(threads);
+        self
+    }
+
+    /// Unix `nice` increment for spawned FFmpeg (1-19). On Windows any positive value lowers
+    /// the priority class (below normal, idle from 10).
+    pub fn with_niceness(mut self, niceness: i32) -> Self {
+        self.niceness = (niceness > 0).then_some(niceness.min(19));
+        self
+    }
+
+    /// Share `token` with this context; cancelling it aborts every step run with the context.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Mirror the stderr of every step run with this context into the job's live ring (see
+    /// [`LiveStderr`](super::LiveStderr)) and add up its CPU time (see
+    /// [`CpuMeter`](super::CpuMeter)).
+    pub fn with_job_id(mut self, job_id: u64) -> Self {
+        self.job_id = Some(job_id);
+        self
+    }
+
+    pub fn ffmpeg_path(&self) -> &Path {
+        &self.ffmpeg_path
+    }
+
+    /// Explicit ffprobe, else the one shipped next to ffmpeg (matching platform suffix first).
+    pub fn ffprobe_path(&self) -> Result<PathBuf, AppError> {
+        match &self.ffprobe_path {
+            Some(path) => Ok(path.clone()),
+            None => ffprobe_path_for(&self.ffmpeg_path),
+        }
+    }
+
+    pub fn temp_dir(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    pub fn thread_limit(&self) -> Option<u32> {
+        self.thread_limit
+    }
+
+    pub fn niceness(&self) -> Option<i32> {
+        self.niceness
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub fn job_id(&self) -> Option<u64> {
+        self.job_id
+    }
+
+    /// New temp file path in this context's temp dir (see [`TempFileManager::create`]).
+    pub fn create_temp(&self, suffix: &str, content: Option<&[u8]>) -> io::Result<PathBuf> {
+        TempFileManager.create_in(&self.temp_dir(), suffix, content)
+    }
+
+    /// Applies the thread limit: overrides every `-threads` value, and adds one as an output
+    /// option (after `-c:v`, else after the last input) when none follows the last `-i`. Before
+    /// `-i` it would only limit the decoder.
+    pub(crate) fn apply_thread_limit(&self, args: &mut Vec<String>) {
+        let Some(limit) = self.thread_limit else {
+            return;
+        };
+        let last_input = args.iter().rposition(|a| a == "-i");
+        let mut output_limited = false;
+        for i in 0..args.len().saturating_sub(1) {
+            if args[i] == "-threads" {
+                args[i + 1] = limit.to_string();
+                output_limited |= last_input.is_none_or(|input| i > input);
+            }
+        }
+        if output_limited {
+            return;
+        }
+        let after_inputs = last_input.map_or(0, |input| (input + 2).min(args.len()));
+        let at = args[after_inputs..]
+            .iter()
+            .position(|a| a == "-c:v")
+            .map_or(after_inputs, |codec| {
+                (after_inputs + codec + 2).min(args.len())
+            });
+        args.splice(at..at, ["-threads".to_string(), limit.to_string()]);
+    }
+
+    /// `Command` for ffmpeg with niceness applied. Unix wraps the call in `nice`, which execs
+    /// ffmpeg in place, so the child pid (used for termination) is still ffmpeg's.
+    pub(crate) fn ffmpeg_command(&self) -> Command {
+        #[cfg(unix)]
+        {
+            let Some(n) = self.niceness else {
+                return Command::new(&self.ffmpeg_path);
+            };
+            let mut cmd = Command::new("nice");
+            cmd.arg("-n").arg(n.to_string()).arg(&self.ffmpeg_path);
+            cmd
+        }
+        #[cfg(not(unix))]
+        {
+            let mut cmd = Command::new(&self.ffmpeg_path);
+            #[cfg(windows)]
+            {
+                let priority = match self.niceness {
+                    Some(n) if n >= 10 => IDLE_PRIORITY_CLASS,
+                    Some(_) => BELOW_NORMAL_PRIORITY_CLASS,
+                    None => 0,
+                };
+                cmd.creation_flags(0x08000000 | priority); // CREATE_NO_WINDOW
+            }
+            cmd
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn ffprobe_path_is_derived_from_context_ffmpeg() {
+        let dir = tempfile::tempdir().unwrap();
+        let ffmpeg = dir.path().join("ffmpeg-custom");
+        let ffprobe = dir.path().join("ffprobe-custom");
+        fs::write(&ffmpeg, b"").unwrap();
+        fs::write(&ffprobe, b"").unwrap();
+
+        let ctx = FfmpegContext::new(&ffmpeg);
+        assert_eq!(ctx.ffmpeg_path(), ffmpeg.as_path());
+        #[cfg(not(windows))]
+        assert_eq!(ctx.ffprobe_path().unwrap(), ffprobe);
+
+        let ctx = ctx.with_ffprobe_path("/opt/ffprobe");
+        assert_eq!(ctx.ffprobe_path().unwrap(), PathBuf::from("/opt/ffprobe"));
+    }
+
+    #[test]
+    fn thread_limit_overrides_or_adds_output_threads() {
+        let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let ctx = FfmpegContext::new("ffmpeg").with_thread_limit(2);
+        let mut args = strings(&[
+            "-i", "in.mp4", "-c:v", "libx264", "-threads", "0", "out.mp4",
+        ]);
+        ctx.apply_thread_limit(&mut args);
+        assert_eq!(args[5], "2");
+        assert_eq!(args.iter().filter(|a| *a == "-threads").count(), 1);
+
+        let mut args = strings(&[
+            "-threads", "0", "-i", "a.mp4", "-i", "b.png", "-c:v", "libx264", "-crf", "23",
+            "out.mp4",
+        ]);
+        ctx.apply_thread_limit(&mut args);
+        assert_eq!(args[1], "2");
+        let threads = args.iter().rposition(|a| a == "-threads").unwrap();
+        let last_input = args.iter().rposition(|a| a == "-i").unwrap();
+        assert!(threads > last_input, "{:?}", args);
+        assert_eq!(
+            args[threads - 2..threads + 2],
+            strings(&["-c:v", "libx264", "-threads", "2"])
+        );
+
+        let mut args = strings(&["-i", "in.mp4", "-f", "null", "-"]);
+        ctx.apply_thread_limit(&mut args);
+        assert_eq!(
+            args,
+            strings(&["-i", "in.mp4", "-threads", "2", "-f", "null", "-"])
+        );
+
+        let mut args = vec!["-threads".to_string(), "0".to_string()];
+        FfmpegContext::new("ffmpeg")
+            .with_thread_limit(0)
+            .apply_thread_limit(&mut args);
+        assert_eq!(args[1], "0");
+    }
+
+    #[test]
+    fn temp_files_go_to_context_temp_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = FfmpegContext::new("ffmpeg").with_temp_dir(dir.path());
+        let path = ctx.create_temp("ctx-test.mp4", None).unwrap();
+        assert_eq!(path.parent(), Some(dir.path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn niceness_wraps_ffmpeg_in_nice() {
+        let cmd = FfmpegContext::new("/usr/bin/ffmpeg")
+            .with_niceness(40)
+            .ffmpeg_command();
+        assert_eq!(cmd.get_program(), "nice");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["-n", "19", "/usr/bin/ffmpeg"]);
+
+        let cmd = FfmpegContext::new("/usr/bin/ffmpeg").ffmpeg_command();
+        assert_eq!(cmd.get_program(), "/usr/bin/ffmpeg");
+    }
+}
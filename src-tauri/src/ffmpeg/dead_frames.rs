@@ -0,0 +1,218 @@
+//! Leading/trailing dead frame detection: black screens and frozen frames at the start or end
+//! of screen recordings (countdown overlays, "recording stopped" stills).
+//!
+//! Only the first and last `WINDOW_SECS` are decoded, through `blackdetect` and `freezedetect`.
+//! The trimmed range is returned as a single keep segment for `TranscodeOptions::keep_segments`.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::ffprobe::get_video_metadata_in;
+use super::silence::value_after;
+use super::{FfmpegContext, path_to_string, run_ffmpeg_capture};
+use crate::error::AppError;
+
+/// Seconds analyzed at each end of the video.
+const WINDOW_SECS: f64 = 90.0;
+/// Intervals this close to each other or to a window edge count as touching.
+const EDGE_TOLERANCE_SECS: f64 = 0.05;
+const BLACK_MIN_SECS: f64 = 0.5;
+const FREEZE_MIN_SECS: f64 = 1.0;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadFrameAnalysis {
+    pub leading_seconds: f64,
+    pub trailing_seconds: f64,
+    pub source_seconds: f64,
+    /// The range without the dead head/tail; pass as `keepSegments`. None when nothing to trim.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_segments: Option<Vec<(f64, f64)>>,
+}
+
+/// Black and frozen intervals in one window's stderr, relative to the window start.
+/// An interval still open at the end of the output runs to `window_len`.
+fn parse_dead_intervals(stderr: &str, window_len: f64) -> Vec<(f64, f64)> {
+    let mut intervals = Vec::new();
+    let mut open_freeze: Option<f64> = None;
+    for line in stderr.lines() {
+        if line.contains("blackdetect") {
+            if let (Some(start), Some(end)) = (
+                value_after(line, "black_start:"),
+                value_after(line, "black_end:"),
+            ) {
+                intervals.push((start, end));
+            }
+        } else if line.contains("freezedetect") {
+            if let Some(start) = value_after(line, "freeze_start:") {
+                open_freeze = Some(start);
+            } else if let Some(end) = value_after(line, "freeze_end:")
+                && let Some(start) = open_freeze.take()
+            {
+                intervals.push((start, end));
+            }
+        }
+    }
+    if let Some(start) = open_freeze {
+        intervals.push((start, window_len));
+    }
+    intervals
+}
+
+/// Sorts and merges overlapping or touching intervals.
+fn merge_intervals(mut intervals: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + EDGE_TOLERANCE_SECS => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Dead seconds at the start of a window.
+fn leading_dead(intervals: &[(f64, f64)]) -> f64 {
+    intervals
+        .first()
+        .filter(|(start, _)| *start <= EDGE_TOLERANCE_SECS)
+        .map_or(0.0, |(_, end)| *end)
+}
+
+/// Dead seconds at the end of a window `window_len` long.
+fn trailing_dead(intervals: &[(f64, f64)], window_len: f64) -> f64 {
+    intervals
+        .last()
+        .filter(|(_, end)| *end >= window_len - EDGE_TOLERANCE_SECS)
+        .map_or(0.0, |(start, _)| (window_len - start).max(0.0))
+}
+
+fn build_detect_args(path: &str, start: f64, len: f64) -> Vec<String> {
+    let mut args = vec!["-nostdin".to_string(), "-hide_banner".to_string()];
+    if start > 0.0 {
+        args.extend(["-ss".to_string(), start.to_string()]);
+    }
+    args.extend([
+        "-t".to_string(),
+        len.to_string(),
+        "-i".to_string(),
+        path.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        format!(
+            "scale=320:-2,blackdetect=d={}:pix_th=0.10,freezedetect=n=-60dB:d={}",
+            BLACK_MIN_SECS, FREEZE_MIN_SECS
+        ),
+        "-an".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]);
+    args
+}
+
+fn detect_window(
+    ctx: &FfmpegContext,
+    path: &str,
+    start: f64,
+    len: f64,
+) -> Result<Vec<(f64, f64)>, AppError> {
+    let stderr = run_ffmpeg_capture(ctx, build_detect_args(path, start, len))?;
+    Ok(merge_intervals(parse_dead_intervals(&stderr, len)))
+}
+
+/// Finds black/frozen frames at the start and end of `path`. Blocking.
+pub fn analyze_dead_frames(
+    ctx: &FfmpegContext,
+    path: &Path,
+) -> Result<DeadFrameAnalysis, AppError> {
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    let duration = meta.duration;
+    if duration <= 0.0 {
+        return Err(AppError::from("Video duration is unknown"));
+    }
+    let input = path_to_string(path);
+
+    let (leading, trailing) = if duration <= WINDOW_SECS * 2.0 {
+        let intervals = detect_window(ctx, &input, 0.0, duration)?;
+        (
+            leading_dead(&intervals),
+            trailing_dead(&intervals, duration),
+        )
+    } else {
+        let head = detect_window(ctx, &input, 0.0, WINDOW_SECS)?;
+        let tail = detect_window(ctx, &input, duration - WINDOW_SECS, WINDOW_SECS)?;
+        (leading_dead(&head), trailing_dead(&tail, WINDOW_SECS))
+    };
+
+    let keep_start = leading.min(duration);
+    let keep_end = (duration - trailing).max(keep_start);
+    let keep_segments = if keep_end - keep_start <= EDGE_TOLERANCE_SECS {
+        log::warn!(
+            target: "tiny_vid::ffmpeg::dead_frames",
+            "analyze_dead_frames: whole video is black or frozen, nothing to keep"
+        );
+        None
+    } else {
+        (leading > 0.0 || trailing > 0.0).then(|| vec![(keep_start, keep_end)])
+    };
+    log::info!(
+        target: "tiny_vid::ffmpeg::dead_frames",
+        "analyze_dead_frames: leading={:.2}s, trailing={:.2}s of {:.2}s",
+        leading,
+        trailing,
+        duration
+    );
+    Ok(DeadFrameAnalysis {
+        leading_seconds: leading,
+        trailing_seconds: trailing,
+        source_seconds: duration,
+        keep_segments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DETECT_STDERR: &str = "\
+[blackdetect @ 0x600] black_start:0 black_end:2.5 black_duration:2.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_start: 2.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_duration: 1.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_end: 4
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_start: 20
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_duration: 4
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_end: 24
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_start: 55
+";
+
+    #[test]
+    fn parse_dead_intervals_reads_black_and_freeze_lines() {
+        assert_eq!(
+            parse_dead_intervals(DETECT_STDERR, 60.0),
+            vec![(0.0, 2.5), (2.5, 4.0), (20.0, 24.0), (55.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn leading_and_trailing_dead_use_merged_edge_intervals() {
+        let merged = merge_intervals(parse_dead_intervals(DETECT_STDERR, 60.0));
+        assert_eq!(merged, vec![(0.0, 4.0), (20.0, 24.0), (55.0, 60.0)]);
+        assert_eq!(leading_dead(&merged), 4.0);
+        assert_eq!(trailing_dead(&merged, 60.0), 5.0);
+        assert_eq!(leading_dead(&merged[1..]), 0.0);
+        assert_eq!(trailing_dead(&merged[..2], 60.0), 0.0);
+    }
+
+    #[test]
+    fn detect_args_seek_only_for_tail_window() {
+        let head = build_detect_args("/in.mp4", 0.0, 90.0);
+        assert!(!head.contains(&"-ss".to_string()));
+        let tail = build_detect_args("/in.mp4", 510.0, 90.0);
+        assert!(tail.windows(2).any(|w| w == ["-ss", "510"]));
+        assert!(tail.iter().any(|a| a.contains("blackdetect=d=0.5")));
+    }
+}
@@ -109,7 +109,8 @@ fn bundled_sidecar_base_names() -> [&'static str; 2] {
     [concat!("ffmpeg-", env!("TARGET")), "ffmpeg"]
 }
 
-fn resolve_bundled_ffmpeg_path() -> Option<PathBuf> {
+/// Bundled sidecar ffmpeg path, if present (macOS/Windows only). Ignores TINY_VID_USE_SYSTEM_FFMPEG.
+pub fn resolve_bundled_ffmpeg_path() -> Option<PathBuf> {
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     {
         for base_name in bundled_sidecar_base_names() {
@@ -126,16 +127,26 @@ fn resolve_bundled_ffmpeg_path() -> Option<PathBuf> {
 }
 
 /// Resolve FFmpeg path. Order:
-/// - bundled sidecar first (macOS/Windows), unless TINY_VID_USE_SYSTEM_FFMPEG is set
+/// - installed channel update, then bundled sidecar (macOS/Windows), unless TINY_VID_USE_SYSTEM_FFMPEG is set
 /// - then system paths
 /// - then PATH
-fn resolve_ffmpeg_path() -> Result<PathBuf, AppError> {
+pub fn resolve_ffmpeg_path() -> Result<PathBuf, AppError> {
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     {
         let prefer_system =
             std::env::var("TINY_VID_USE_SYSTEM_FFMPEG").is_ok_and(|v| !v.is_empty() && v != "0");
-        if !prefer_system && let Some(path) = resolve_bundled_ffmpeg_path() {
-            return Ok(path);
+        if !prefer_system {
+            if let Some(path) = super::updater::installed_ffmpeg_path() {
+                log::debug!(
+                    target: "tiny_vid::ffmpeg::discovery",
+                    "FFmpeg found as installed update: {}",
+                    path.display()
+                );
+                return Ok(path);
+            }
+            if let Some(path) = resolve_bundled_ffmpeg_path() {
+                return Ok(path);
+            }
         }
     }
 
@@ -172,10 +183,13 @@ fn resolve_ffmpeg_path() -> Result<PathBuf, AppError> {
 
 /// Get FFmpeg path. Cached for process lifetime.
 /// 1. FFMPEG_PATH env (when set and path exists) – for tests/CI or bundled binaries.
-/// 2. macOS/Windows bundled sidecar (ffmpeg-{TARGET} then ffmpeg).
+/// 2. macOS/Windows installed channel update, then bundled sidecar (ffmpeg-{TARGET} then ffmpeg).
 /// 3. Common installation paths (Homebrew, /usr/bin, etc.).
 /// 4. PATH (via which/where).
 pub fn get_ffmpeg_path() -> Result<&'static Path, AppError> {
+    if super::fake::enabled() {
+        return Ok(Path::new(super::fake::FAKE_FFMPEG_PATH));
+    }
     #[cfg(feature = "discovery-test-helpers")]
     {
         let guard = TEST_FFMPEG_CACHE.lock();
@@ -244,7 +258,14 @@ pub fn ffprobe_candidates(ffmpeg_path: &Path) -> Vec<PathBuf> {
 /// If ffmpeg has a platform suffix (e.g. ffmpeg-aarch64-apple-darwin), looks for
 /// ffprobe with the same suffix (ffprobe-aarch64-apple-darwin) first.
 pub fn get_ffprobe_path() -> Result<PathBuf, AppError> {
-    let ffmpeg = get_ffmpeg_path()?;
+    ffprobe_path_for(get_ffmpeg_path()?)
+}
+
+/// ffprobe next to a specific ffmpeg binary; see [`get_ffprobe_path`].
+pub fn ffprobe_path_for(ffmpeg: &Path) -> Result<PathBuf, AppError> {
+    if super::fake::enabled() {
+        return Ok(PathBuf::from(super::fake::FAKE_FFPROBE_PATH));
+    }
     let parent = ffmpeg
         .parent()
         .ok_or_else(|| AppError::from("FFmpeg path has no parent directory".to_string()))?;
@@ -267,7 +288,7 @@ pub fn get_ffprobe_path() -> Result<PathBuf, AppError> {
 
 /// Parse ffmpeg -encoders stdout and return supported video encoder names.
 /// Lines starting with " V" are video encoders; we filter to codecs we support.
-fn parse_encoder_output(stdout: &str) -> Vec<String> {
+pub(super) fn parse_encoder_output(stdout: &str) -> Vec<String> {
     let mut codecs = Vec::new();
     for line in stdout.lines() {
         if line.starts_with(" V")
@@ -282,7 +303,14 @@ fn parse_encoder_output(stdout: &str) -> Vec<String> {
 
 /// Runs `ffmpeg -encoders` and returns supported codec names.
 pub fn get_available_codecs() -> Result<Vec<String>, AppError> {
-    let ffmpeg_path = get_ffmpeg_path()?;
+    get_available_codecs_for(get_ffmpeg_path()?)
+}
+
+/// [`get_available_codecs`] for a specific FFmpeg binary.
+pub fn get_available_codecs_for(ffmpeg_path: &Path) -> Result<Vec<String>, AppError> {
+    if super::fake::enabled() {
+        return Ok(super::fake::encoders());
+    }
     log::debug!(
         target: "tiny_vid::ffmpeg::discovery",
         "Detecting available codecs from: {}",
@@ -373,11 +401,14 @@ Encoders:
  V..... libvpx-vp9           libvpx VP9
  V..... h264_videotoolbox    VideoToolbox H.264 Encoder
  V..... hevc_videotoolbox    VideoToolbox H.265 Encoder
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V....D hevc_nvenc           NVIDIA NVENC hevc encoder (codec hevc)
  V..... mpeg4                MPEG-4 part 2
  A..... aac                  AAC (Advanced Audio Coding)
 "#;
         let codecs = parse_encoder_output(sample_output);
-        assert_eq!(codecs.len(), 6);
+        assert_eq!(codecs.len(), 8);
+        assert!(codecs.contains(&"hevc_nvenc".to_string()));
         assert!(codecs.contains(&"libx264".to_string()));
         assert!(codecs.contains(&"h264_videotoolbox".to_string()));
         assert!(!codecs.contains(&"mpeg4".to_string()));
@@ -0,0 +1,167 @@
+//! CPU time and estimated energy per job.
+//!
+//! Runs of a metered job get `-benchmark`, so FFmpeg prints its own CPU time on exit
+//! (`bench: utime=12.3s stime=0.4s rtime=6.1s`). Like live stderr, a job opts in by holding a
+//! [`CpuMeter`] and running its steps with a context carrying its id; the runner adds the flag
+//! and each run's user and system time to the job. Other runs (previews, probes, thumbnails)
+//! go without it. Energy is a proxy, CPU seconds times `WATTS_PER_CORE`:
+//! good for comparing an overnight AV1 encode with a quick H.264 one, not a power reading.
+//! Hardware encoders do their work on a GPU or media engine FFmpeg cannot see, so their jobs
+//! report CPU time only.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::codec::is_hardware_codec;
+
+/// Package power per fully busy core of a typical desktop CPU under encode load (watts).
+const WATTS_PER_CORE: f64 = 12.0;
+
+static JOBS: Mutex<Option<HashMap<u64, f64>>> = Mutex::new(None);
+
+/// Keeps the job's CPU time counter alive; dropping it discards the count.
+#[derive(Debug)]
+pub struct CpuMeter {
+    job_id: u64,
+}
+
+impl CpuMeter {
+    pub fn register(job_id: u64) -> Self {
+        JOBS.lock()
+            .get_or_insert_with(HashMap::new)
+            .insert(job_id, 0.0);
+        Self { job_id }
+    }
+
+    /// CPU seconds of the job's FFmpeg runs so far.
+    pub fn cpu_seconds(&self) -> f64 {
+        JOBS.lock()
+            .as_ref()
+            .and_then(|jobs| jobs.get(&self.job_id).copied())
+            .unwrap_or(0.0)
+    }
+}
+
+impl Drop for CpuMeter {
+    fn drop(&mut self) {
+        if let Some(jobs) = JOBS.lock().as_mut() {
+            jobs.remove(&self.job_id);
+        }
+    }
+}
+
+/// Adds `-benchmark` to the args of a run for a job holding a [`CpuMeter`].
+pub(super) fn apply_benchmark(job_id: Option<u64>, args: &mut Vec<String>) {
+    let metered = job_id.is_some_and(|id| {
+        JOBS.lock()
+            .as_ref()
+            .is_some_and(|jobs| jobs.contains_key(&id))
+    });
+    if metered && !args.iter().any(|a| a == "-benchmark") {
+        args.insert(0, "-benchmark".to_string());
+    }
+}
+
+/// User plus system seconds from a `-benchmark` summary line.
+fn parse_bench_line(line: &str) -> Option<f64> {
+    let fields = line.trim().strip_prefix("bench:")?;
+    let secs = |key: &str| {
+        fields
+            .split_whitespace()
+            .find_map(|f| f.strip_prefix(key))
+            .and_then(|v| v.trim_end_matches('s').parse::<f64>().ok())
+    };
+    Some(secs("utime=")? + secs("stime=")?)
+}
+
+/// Adds the CPU time in `line`, when it is a benchmark summary, to a registered job.
+pub(super) fn record(job_id: u64, line: &str) {
+    let Some(secs) = parse_bench_line(line) else {
+        return;
+    };
+    if let Some(total) = JOBS.lock().as_mut().and_then(|jobs| jobs.get_mut(&job_id)) {
+        *total += secs;
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEnergy {
+    /// User plus system time of the job's FFmpeg runs.
+    pub cpu_seconds: f64,
+    /// Average busy cores over the job's wall time.
+    pub average_cores: f64,
+    /// Average CPU power proxy. None for hardware encoders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_watts: Option<f64>,
+    /// Energy proxy for the whole job. None for hardware encoders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_wh: Option<f64>,
+}
+
+impl JobEnergy {
+    pub fn new(codec: &str, cpu_seconds: f64, wall_seconds: f64) -> Self {
+        let average_cores = if wall_seconds > 0.0 {
+            cpu_seconds / wall_seconds
+        } else {
+            0.0
+        };
+        let software = !is_hardware_codec(codec);
+        Self {
+            cpu_seconds,
+            average_cores,
+            estimated_watts: software.then_some(average_cores * WATTS_PER_CORE),
+            estimated_wh: software.then_some(cpu_seconds * WATTS_PER_CORE / 3600.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_bench_lines_of_registered_jobs() {
+        assert_eq!(
+            parse_bench_line("bench: utime=12.500s stime=0.500s rtime=6.000s"),
+            Some(13.0)
+        );
+        assert_eq!(parse_bench_line("bench: maxrss=123456KiB"), None);
+        assert_eq!(parse_bench_line("frame=  10 fps=0.0"), None);
+
+        let job_id = u64::MAX - 11;
+        record(job_id, "bench: utime=1.000s stime=0.000s rtime=1.000s");
+        let mut args = vec!["-i".to_string(), "in.mp4".to_string()];
+        apply_benchmark(Some(job_id), &mut args);
+        apply_benchmark(None, &mut args);
+        assert!(!args.contains(&"-benchmark".to_string()));
+        let meter = CpuMeter::register(job_id);
+        apply_benchmark(Some(job_id), &mut args);
+        apply_benchmark(Some(job_id), &mut args);
+        assert_eq!(args, ["-benchmark", "-i", "in.mp4"]);
+        record(job_id, "bench: utime=3.000s stime=1.000s rtime=2.000s");
+        record(job_id, "bench: utime=2.000s stime=0.000s rtime=1.000s");
+        assert_eq!(meter.cpu_seconds(), 6.0);
+        drop(meter);
+        record(job_id, "bench: utime=3.000s stime=1.000s rtime=2.000s");
+        assert!(
+            JOBS.lock()
+                .as_ref()
+                .is_none_or(|jobs| !jobs.contains_key(&job_id))
+        );
+    }
+
+    #[test]
+    fn energy_is_estimated_for_software_encoders_only() {
+        let x264 = JobEnergy::new("libx264", 3600.0, 600.0);
+        assert_eq!(x264.average_cores, 6.0);
+        assert_eq!(x264.estimated_watts, Some(6.0 * WATTS_PER_CORE));
+        assert_eq!(x264.estimated_wh, Some(WATTS_PER_CORE));
+
+        let nvenc = JobEnergy::new("h264_nvenc", 30.0, 60.0);
+        assert_eq!(nvenc.average_cores, 0.5);
+        assert_eq!(nvenc.estimated_wh, None);
+    }
+}
@@ -6,6 +6,8 @@
 
 use serde::Serialize;
 
+use crate::i18n::t;
+
 /// Payload for ffmpeg-error event. Frontend shows summary; detail is expandable.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,7 +16,7 @@ pub struct FfmpegErrorPayload {
     pub detail: String,
 }
 
-/// Maps FFmpeg exit code to a short user-facing summary. Stderr is passed through as detail.
+/// Maps FFmpeg exit code to a short user-facing summary in the current locale. Stderr is passed through as detail.
 pub fn parse_ffmpeg_error(stderr: &str, exit_code: Option<i32>) -> FfmpegErrorPayload {
     let summary = match exit_code {
         Some(code) => match known_exit_code_summary(code) {
@@ -30,10 +32,10 @@ pub fn parse_ffmpeg_error(stderr: &str, exit_code: Option<i32>) -> FfmpegErrorPa
 /// Source-verified exit codes from ffmpeg.c.
 fn known_exit_code_summary(code: i32) -> Option<String> {
     match code {
-        -1 => Some("FFmpeg not found or failed to start.".into()),
-        1 => Some("FFmpeg failed.".into()),
-        69 => Some("Encoding rate limit exceeded.".into()),
-        123 | 255 => Some("Encoding was stopped.".into()),
+        -1 => Some(t("error.ffmpeg_not_started").into()),
+        1 => Some(t("error.ffmpeg_failed").into()),
+        69 => Some(t("error.rate_limit").into()),
+        123 | 255 => Some(t("error.stopped").into()),
         _ => None,
     }
 }
@@ -57,7 +59,7 @@ fn first_line_truncated(stderr: &str, max_len: usize) -> String {
 
 /// For unknown codes, use a short summary. Full stderr is in detail.
 fn summary_for_unknown_code(code: i32, _stderr: &str) -> String {
-    format!("FFmpeg failed (exit code {}).", code)
+    t("error.ffmpeg_failed_code").replace("{code}", &code.to_string())
 }
 
 fn fallback_summary(stderr: &str) -> String {
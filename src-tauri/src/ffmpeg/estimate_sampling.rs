@@ -0,0 +1,218 @@
+//! How the size estimate samples an export: how many windows, how long, where, and how many
+//! seconds at most.
+//!
+//! The app-wide setting applies to every estimate; `TranscodeOptions::estimate_sampling`
+//! replaces it for one call. Fewer, shorter samples make estimates faster on slow machines at
+//! the cost of accuracy; more samples narrow the range on footage that varies a lot.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Most sample windows, base and extra together.
+const MAX_SAMPLES: usize = 20;
+const MIN_SAMPLE_SECS: f64 = 0.25;
+const MAX_SAMPLE_SECS: f64 = 30.0;
+/// Where spread samples start and end, as fractions of the export.
+const FIRST_POSITION: f64 = 0.05;
+const LAST_POSITION: f64 = 0.95;
+
+static SAMPLING: Mutex<EstimateSampling> = Mutex::new(EstimateSampling::DEFAULT);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EstimateSampling {
+    /// Samples spread evenly from 5% to 95% of the export. Default 3. Ignored with `positions`.
+    pub sample_count: u32,
+    /// Length of each sample in seconds. Default 1.5.
+    pub sample_secs: f64,
+    /// Sample centres as fractions (0-1) of the export, instead of spreading `sample_count`.
+    pub positions: Option<Vec<f64>>,
+    /// Most seconds sampled in total. Footage that varies a lot gets extra samples halfway
+    /// between the base ones while they fit. Default 7.5.
+    pub max_sampled_secs: f64,
+}
+
+impl EstimateSampling {
+    const DEFAULT: Self = Self {
+        sample_count: 3,
+        sample_secs: 1.5,
+        positions: None,
+        max_sampled_secs: 7.5,
+    };
+
+    pub(crate) fn validate(&self) -> Result<(), AppError> {
+        let count = self.base_positions().len();
+        if count == 0 || count > MAX_SAMPLES {
+            return Err(AppError::from(format!(
+                "Estimate sampling needs between 1 and {} samples",
+                MAX_SAMPLES
+            )));
+        }
+        if self
+            .positions
+            .iter()
+            .flatten()
+            .any(|p| !(0.0..=1.0).contains(p))
+        {
+            return Err(AppError::from(
+                "Estimate sample positions must be between 0 and 1",
+            ));
+        }
+        if !(MIN_SAMPLE_SECS..=MAX_SAMPLE_SECS).contains(&self.sample_secs) {
+            return Err(AppError::from(format!(
+                "Estimate samples must be {}-{} seconds long",
+                MIN_SAMPLE_SECS, MAX_SAMPLE_SECS
+            )));
+        }
+        if !self.max_sampled_secs.is_finite()
+            || self.max_sampled_secs < count as f64 * self.sample_secs
+        {
+            return Err(AppError::from(
+                "Maximum sampled seconds must cover the base samples",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Centres of the samples always taken, in order.
+    pub(crate) fn base_positions(&self) -> Vec<f64> {
+        if let Some(positions) = &self.positions {
+            let mut positions = positions.clone();
+            positions.sort_by(f64::total_cmp);
+            positions.dedup();
+            return positions;
+        }
+        match self.sample_count {
+            0 => Vec::new(),
+            1 => vec![0.5],
+            n => {
+                let last = (n - 1) as f64;
+                (0..n)
+                    .map(|i| {
+                        let i = i as f64;
+                        (FIRST_POSITION * (last - i) + LAST_POSITION * i) / last
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Centres of the extra samples for footage that varies a lot: halfway between neighbours.
+    pub(crate) fn extra_positions(&self) -> Vec<f64> {
+        self.base_positions()
+            .windows(2)
+            .map(|pair| (pair[0] + pair[1]) / 2.0)
+            .take(MAX_SAMPLES.saturating_sub(self.base_positions().len()))
+            .collect()
+    }
+
+    /// Part of the estimate cache key: estimates sampled differently are cached apart.
+    pub(crate) fn cache_key(&self) -> String {
+        format!(
+            "{:?}:{}:{}",
+            self.base_positions(),
+            self.sample_secs,
+            self.max_sampled_secs
+        )
+    }
+}
+
+impl Default for EstimateSampling {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Sampling used by estimates that do not bring their own.
+pub fn estimate_sampling() -> EstimateSampling {
+    SAMPLING.lock().clone()
+}
+
+/// Validates and stores the app-wide sampling; returns what was applied.
+pub fn set_estimate_sampling(sampling: EstimateSampling) -> Result<EstimateSampling, AppError> {
+    sampling.validate()?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::estimate_sampling",
+        "set_estimate_sampling: {} sample(s) of {}s at {:?}, at most {}s",
+        sampling.base_positions().len(),
+        sampling.sample_secs,
+        sampling.base_positions(),
+        sampling.max_sampled_secs
+    );
+    *SAMPLING.lock() = sampling.clone();
+    Ok(sampling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_spreads_three_samples_with_two_extras() {
+        let sampling = EstimateSampling::default();
+        assert_eq!(sampling.base_positions(), [0.05, 0.5, 0.95]);
+        let extras = sampling.extra_positions();
+        assert_eq!(extras.len(), 2);
+        assert!((extras[0] - 0.275).abs() < 1e-9 && (extras[1] - 0.725).abs() < 1e-9);
+        assert!(sampling.validate().is_ok());
+
+        let single = EstimateSampling {
+            sample_count: 1,
+            ..EstimateSampling::default()
+        };
+        assert_eq!(single.base_positions(), [0.5]);
+        assert!(single.extra_positions().is_empty());
+    }
+
+    #[test]
+    fn positions_replace_the_spread_and_are_checked() {
+        let sampling = EstimateSampling {
+            positions: Some(vec![0.8, 0.2, 0.2]),
+            ..EstimateSampling::default()
+        };
+        assert_eq!(sampling.base_positions(), [0.2, 0.8]);
+        assert_ne!(
+            sampling.cache_key(),
+            EstimateSampling::default().cache_key()
+        );
+
+        let outside = EstimateSampling {
+            positions: Some(vec![0.5, 1.5]),
+            ..EstimateSampling::default()
+        };
+        assert!(outside.validate().is_err());
+        let none = EstimateSampling {
+            positions: Some(vec![]),
+            ..EstimateSampling::default()
+        };
+        assert!(none.validate().is_err());
+    }
+
+    #[test]
+    fn the_budget_must_cover_the_base_samples() {
+        let sampling = EstimateSampling {
+            sample_count: 6,
+            ..EstimateSampling::default()
+        };
+        assert!(sampling.validate().is_err());
+        assert!(
+            EstimateSampling {
+                max_sampled_secs: 9.0,
+                ..sampling
+            }
+            .validate()
+            .is_ok()
+        );
+        let tiny = EstimateSampling {
+            sample_secs: 0.1,
+            ..EstimateSampling::default()
+        };
+        assert!(tiny.validate().is_err());
+
+        let partial: EstimateSampling = serde_json::from_str(r#"{"sampleCount": 5}"#).unwrap();
+        assert_eq!(partial.sample_secs, 1.5);
+        assert_eq!(partial.base_positions().len(), 5);
+    }
+}
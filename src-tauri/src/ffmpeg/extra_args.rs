@@ -0,0 +1,424 @@
+//! User-supplied FFmpeg arguments (`extra_video_args` / `extra_output_args`).
+//!
+//! Only allowlisted flags are accepted, each followed by exactly one value, so extras can tune
+//! the encoder or the container but cannot add inputs, outputs, filters or stream mappings.
+//! Encoder parameter strings (`-x264-params` and friends) are checked key by key against a
+//! per-encoder allowlist, which leaves out options that read or write files of their own.
+
+use super::TranscodeOptions;
+use crate::error::AppError;
+
+/// Flags accepted in `extra_video_args`; each takes one value.
+const VIDEO_FLAGS: &[&str] = &[
+    "-g",
+    "-keyint_min",
+    "-bf",
+    "-b_strategy",
+    "-refs",
+    "-sc_threshold",
+    "-rc-lookahead",
+    "-qmin",
+    "-qmax",
+    "-qcomp",
+    "-bufsize",
+    "-minrate",
+    "-me_method",
+    "-me_range",
+    "-subq",
+    "-trellis",
+    "-profile:v",
+    "-level",
+    "-level:v",
+    "-pix_fmt",
+    "-color_primaries",
+    "-color_trc",
+    "-colorspace",
+    "-color_range",
+    "-x264opts",
+    "-x264-params",
+    "-x265-params",
+    "-svtav1-params",
+    "-tile-columns",
+    "-tile-rows",
+    "-frame-parallel",
+    "-row-mt",
+    "-lag-in-frames",
+    "-arnr-maxframes",
+    "-arnr-strength",
+    "-auto-alt-ref",
+    "-deadline",
+    "-cpu-used",
+    "-aq-mode",
+    "-tune-content",
+];
+
+/// Flags accepted in `extra_output_args`; each takes one value.
+const OUTPUT_FLAGS: &[&str] = &[
+    "-metadata",
+    "-metadata:s:v",
+    "-metadata:s:a",
+    "-movflags",
+    "-brand",
+    "-write_tmcd",
+    "-max_muxing_queue_size",
+    "-avoid_negative_ts",
+    "-max_interleave_delta",
+    "-muxdelay",
+    "-muxpreload",
+    "-cluster_size_limit",
+    "-cluster_time_limit",
+    "-disposition:v",
+    "-disposition:a",
+];
+
+/// x264 parameters accepted in `-x264opts` / `-x264-params`.
+const X264_PARAM_KEYS: &[&str] = &[
+    "ref",
+    "bframes",
+    "b-adapt",
+    "b-bias",
+    "b-pyramid",
+    "keyint",
+    "min-keyint",
+    "scenecut",
+    "no-scenecut",
+    "open-gop",
+    "rc-lookahead",
+    "mbtree",
+    "no-mbtree",
+    "me",
+    "merange",
+    "subme",
+    "trellis",
+    "partitions",
+    "direct",
+    "weightb",
+    "no-weightb",
+    "weightp",
+    "8x8dct",
+    "no-8x8dct",
+    "mixed-refs",
+    "no-mixed-refs",
+    "fast-pskip",
+    "no-fast-pskip",
+    "dct-decimate",
+    "no-dct-decimate",
+    "chroma-me",
+    "no-chroma-me",
+    "cabac",
+    "no-cabac",
+    "deblock",
+    "no-deblock",
+    "aq-mode",
+    "aq-strength",
+    "psy",
+    "no-psy",
+    "psy-rd",
+    "qcomp",
+    "qpmin",
+    "qpmax",
+    "qpstep",
+    "ipratio",
+    "pbratio",
+    "chroma-qp-offset",
+    "crf-max",
+    "vbv-maxrate",
+    "vbv-bufsize",
+    "vbv-init",
+    "nr",
+    "deadzone-inter",
+    "deadzone-intra",
+    "slices",
+    "slice-max-size",
+    "bluray-compat",
+    "colorprim",
+    "transfer",
+    "colormatrix",
+    "range",
+    "fullrange",
+    "overscan",
+    "videoformat",
+    "chromaloc",
+    "sar",
+    "aud",
+    "nal-hrd",
+    "filler",
+];
+
+/// x265 parameters accepted in `-x265-params`.
+const X265_PARAM_KEYS: &[&str] = &[
+    "ref",
+    "bframes",
+    "b-adapt",
+    "b-pyramid",
+    "no-b-pyramid",
+    "bframe-bias",
+    "keyint",
+    "min-keyint",
+    "scenecut",
+    "open-gop",
+    "no-open-gop",
+    "rc-lookahead",
+    "lookahead-slices",
+    "cutree",
+    "no-cutree",
+    "me",
+    "subme",
+    "merange",
+    "max-merge",
+    "ctu",
+    "min-cu-size",
+    "max-tu-size",
+    "tu-intra-depth",
+    "tu-inter-depth",
+    "limit-tu",
+    "rd",
+    "rdoq-level",
+    "psy-rd",
+    "psy-rdoq",
+    "rect",
+    "no-rect",
+    "amp",
+    "no-amp",
+    "early-skip",
+    "rskip",
+    "limit-refs",
+    "limit-modes",
+    "weightp",
+    "no-weightp",
+    "weightb",
+    "sao",
+    "no-sao",
+    "selective-sao",
+    "deblock",
+    "no-deblock",
+    "strong-intra-smoothing",
+    "no-strong-intra-smoothing",
+    "tskip",
+    "aq-mode",
+    "aq-strength",
+    "aq-motion",
+    "hevc-aq",
+    "qcomp",
+    "qpmin",
+    "qpmax",
+    "qpstep",
+    "ipratio",
+    "pbratio",
+    "cbqpoffs",
+    "crqpoffs",
+    "nr-intra",
+    "nr-inter",
+    "vbv-maxrate",
+    "vbv-bufsize",
+    "vbv-init",
+    "fades",
+    "pools",
+    "frame-threads",
+    "wpp",
+    "no-wpp",
+    "pmode",
+    "pme",
+    "colorprim",
+    "transfer",
+    "colormatrix",
+    "range",
+    "chromaloc",
+    "sar",
+    "hdr10",
+    "hdr10-opt",
+    "master-display",
+    "max-cll",
+    "repeat-headers",
+    "aud",
+    "hrd",
+    "info",
+    "no-info",
+    "log-level",
+    "level-idc",
+    "high-tier",
+];
+
+/// SVT-AV1 parameters accepted in `-svtav1-params`.
+const SVTAV1_PARAM_KEYS: &[&str] = &[
+    "tune",
+    "film-grain",
+    "film-grain-denoise",
+    "keyint",
+    "irefresh-type",
+    "scd",
+    "scm",
+    "lookahead",
+    "hierarchical-levels",
+    "enable-overlays",
+    "enable-tf",
+    "enable-qm",
+    "qm-min",
+    "qm-max",
+    "aq-mode",
+    "enable-variance-boost",
+    "variance-boost-strength",
+    "variance-octile",
+    "enable-cdef",
+    "enable-restoration",
+    "enable-dlf",
+    "sharpness",
+    "fast-decode",
+    "tile-rows",
+    "tile-columns",
+    "lp",
+    "pin",
+    "mbr",
+    "buf-sz",
+    "buf-initial-sz",
+    "buf-optimal-sz",
+    "bias-pct",
+    "minsection-pct",
+    "maxsection-pct",
+    "color-primaries",
+    "transfer-characteristics",
+    "matrix-coefficients",
+    "color-range",
+    "chroma-sample-position",
+    "mastering-display",
+    "content-light",
+];
+
+/// Flags whose value is a `key=value:key=value` encoder parameter string, with the keys each
+/// accepts. Keys that read or write files of their own (stats, zones, analysis, CSV logs, ...)
+/// are left out.
+const PARAM_STRING_FLAGS: &[(&str, &[&str])] = &[
+    ("-x264opts", X264_PARAM_KEYS),
+    ("-x264-params", X264_PARAM_KEYS),
+    ("-x265-params", X265_PARAM_KEYS),
+    ("-svtav1-params", SVTAV1_PARAM_KEYS),
+];
+
+/// Checks `args` against `allowed`: flag/value pairs, allowlisted flags, no value that looks
+/// like another option (negative numbers aside), only allowlisted encoder parameters.
+fn validate(args: &[String], allowed: &[&str]) -> Result<(), AppError> {
+    for pair in args.chunks(2) {
+        let flag = pair[0].as_str();
+        if !allowed.contains(&flag) {
+            return Err(AppError::from(format!(
+                "Extra FFmpeg argument {} is not allowed",
+                flag
+            )));
+        }
+        let Some(value) = pair.get(1) else {
+            return Err(AppError::from(format!(
+                "Extra FFmpeg argument {} needs a value",
+                flag
+            )));
+        };
+        if value.starts_with('-') && value.parse::<f64>().is_err() {
+            return Err(AppError::from(format!(
+                "Invalid value {} for extra FFmpeg argument {}",
+                value, flag
+            )));
+        }
+        // Parameters are `:`-separated; commas only separate the parts of one value
+        // (`deblock=-1,-1`).
+        if let Some((_, keys)) = PARAM_STRING_FLAGS.iter().find(|(f, _)| *f == flag)
+            && let Some(key) = value
+                .split(':')
+                .filter_map(|param| param.split('=').next())
+                .map(str::trim)
+                .find(|key| !key.is_empty() && !keys.contains(key))
+        {
+            return Err(AppError::from(format!(
+                "Encoder parameter {} is not allowed in {}",
+                key, flag
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Validates `extra_video_args`.
+pub(crate) fn validate_video_args(args: &[String]) -> Result<(), AppError> {
+    validate(args, VIDEO_FLAGS)
+}
+
+/// Validates `extra_output_args`.
+pub(crate) fn validate_output_args(args: &[String]) -> Result<(), AppError> {
+    validate(args, OUTPUT_FLAGS)
+}
+
+/// Validates both extra argument lists of `options`.
+pub(crate) fn validate_extra_args(options: &TranscodeOptions) -> Result<(), AppError> {
+    validate_video_args(options.effective_extra_video_args())?;
+    validate_output_args(options.effective_extra_output_args())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn allowlisted_pairs_pass() {
+        assert!(validate_video_args(&args(&["-g", "48", "-x264-params", "ref=4:me=umh"])).is_ok());
+        assert!(validate_video_args(&args(&["-sc_threshold", "-1"])).is_ok());
+        assert!(validate_output_args(&args(&["-metadata", "title=Clip"])).is_ok());
+        assert!(validate_video_args(&[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_flags_missing_values_and_option_values() {
+        assert!(validate_video_args(&args(&["-i", "/etc/passwd"])).is_err());
+        assert!(validate_video_args(&args(&["-vf", "scale=2:2"])).is_err());
+        assert!(validate_output_args(&args(&["-g", "48"])).is_err());
+        assert!(validate_video_args(&args(&["-g"])).is_err());
+        assert!(validate_output_args(&args(&["-movflags", "-y"])).is_err());
+    }
+
+    #[test]
+    fn rejects_file_naming_encoder_params() {
+        let err = validate_video_args(&args(&["-x265-params", "ref=3:csv=/tmp/x.csv"]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("csv"), "{}", err);
+        assert!(validate_video_args(&args(&["-x264-params", "stats=/tmp/s.log"])).is_err());
+        for key in [
+            "scaling-list",
+            "lambda-file",
+            "zonefile",
+            "dolby-vision-rpu",
+            "analysis-save",
+        ] {
+            let value = format!("ref=3:{}=/any/path", key);
+            assert!(
+                validate_video_args(&args(&["-x265-params", &value])).is_err(),
+                "{}",
+                key
+            );
+        }
+        for key in ["stat-file", "input-stat-file", "output-stat-file"] {
+            let value = format!("tune=0:{}=/any/path", key);
+            assert!(
+                validate_video_args(&args(&["-svtav1-params", &value])).is_err(),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn encoder_params_are_checked_against_their_own_encoder() {
+        assert!(
+            validate_video_args(&args(&["-x265-params", "bframes=4:rc-lookahead=20:no-sao"]))
+                .is_ok()
+        );
+        assert!(validate_video_args(&args(&["-svtav1-params", "tune=0:film-grain=8"])).is_ok());
+        assert!(validate_video_args(&args(&["-x264opts", "no-fast-pskip:ref=2"])).is_ok());
+        assert!(validate_video_args(&args(&["-x264-params", "deblock=-1,-1:ref=2"])).is_ok());
+        assert!(validate_video_args(&args(&["-x265-params", "deblock=-1,-1"])).is_ok());
+        // x265's `ctu` means nothing to x264.
+        assert!(validate_video_args(&args(&["-x264-params", "ctu=32"])).is_err());
+        assert!(validate_video_args(&args(&["-svtav1-params", "unknown-key=1"])).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Simulated FFmpeg for frontend and sidecar-client development, enabled with
+//! `TINY_VID_FAKE_FFMPEG=1` in debug builds. Release builds ignore the variable.
+//!
+//! Nothing is spawned. Discovery reports a stand-in binary and a fixed encoder list, every
+//! probe returns the same 30 s 1080p H.264/AAC metadata, and each run prints ffmpeg-style
+//! progress into the normal line handling over about two seconds, then writes a tiny
+//! placeholder where the real output would go (plus the pass log of a first pass). Progress
+//! throttling, cancellation and `terminate_all_ffmpeg` behave as with a real process.
+//! Commands that read ffmpeg's output directly (loudness, quality metrics, thumbnails on
+//! screen) fail or come back empty; the placeholders are not playable media.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use super::cancel::{CancellationToken, terminate_generation};
+use super::context::FfmpegContext;
+use super::ffprobe::VideoMetadata;
+use crate::error::AppError;
+
+/// Stand-in binary paths; never executed.
+pub(super) const FAKE_FFMPEG_PATH: &str = "tiny-vid-fake/ffmpeg";
+pub(super) const FAKE_FFPROBE_PATH: &str = "tiny-vid-fake/ffprobe";
+/// Written to every output path.
+pub(super) const PLACEHOLDER: &[u8] = b"tiny-vid placeholder output (TINY_VID_FAKE_FFMPEG)\n";
+/// Duration of every probed file, and of runs started without one.
+const MEDIA_DURATION_SECS: f64 = 30.0;
+/// Progress updates per run; a run takes `TICKS * TICK_INTERVAL`.
+const TICKS: u32 = 20;
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+static ENABLED: LazyLock<bool> = LazyLock::new(|| {
+    let enabled = cfg!(debug_assertions)
+        && std::env::var("TINY_VID_FAKE_FFMPEG").is_ok_and(|v| !v.is_empty() && v != "0");
+    if enabled {
+        log::warn!(
+            target: "tiny_vid::ffmpeg::fake",
+            "TINY_VID_FAKE_FFMPEG is set: FFmpeg runs are simulated"
+        );
+    }
+    enabled
+});
+
+/// Whether `TINY_VID_FAKE_FFMPEG` was set at startup of a debug build.
+pub(crate) fn enabled() -> bool {
+    *ENABLED
+}
+
+/// Encoders the simulated build offers.
+pub(super) fn encoders() -> Vec<String> {
+    #[cfg(feature = "lgpl")]
+    let codecs = ["h264_videotoolbox", "hevc_videotoolbox", "gif"];
+    #[cfg(not(feature = "lgpl"))]
+    let codecs = ["libx264", "libx265", "libsvtav1", "libvpx-vp9", "gif"];
+    codecs.iter().map(|c| c.to_string()).collect()
+}
+
+/// Metadata of a 30 s 1080p30 H.264 file with one stereo AAC track, sized like `path`.
+pub(super) fn video_metadata(path: &Path) -> VideoMetadata {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    VideoMetadata {
+        duration: MEDIA_DURATION_SECS,
+        audio_stream_count: 1,
+        width: 1920,
+        height: 1080,
+        size,
+        fps: 30.0,
+        codec_name: Some("h264".to_string()),
+        codec_long_name: Some("H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10".to_string()),
+        format_bit_rate: Some((size as f64 * 8.0 / MEDIA_DURATION_SECS) as u64),
+        format_name: Some("mov,mp4,m4a,3gp,3g2,mj2".to_string()),
+        nb_streams: Some(2),
+        audio_codec_name: Some("aac".to_string()),
+        audio_channels: Some(2),
+        audio_bit_rate: Some(128_000),
+        video_stream_count: 1,
+        ..VideoMetadata::default()
+    }
+}
+
+/// What ffmpeg would print by `tick`: the input duration first, then `-progress` lines.
+fn tick_lines(tick: u32, duration: f64) -> Vec<String> {
+    let mut lines = Vec::with_capacity(3);
+    if tick == 0 {
+        let secs = duration.max(0.0);
+        lines.push(format!(
+            "  Duration: {:02}:{:02}:{:05.2}, start: 0.000000, bitrate: N/A",
+            (secs / 3600.0) as u64,
+            (secs % 3600.0 / 60.0) as u64,
+            secs % 60.0
+        ));
+    }
+    let out_time_us = (duration * tick as f64 / TICKS as f64 * 1_000_000.0) as u64;
+    lines.push(format!("out_time_ms={}", out_time_us));
+    lines.push(if tick == TICKS {
+        "progress=end".to_string()
+    } else {
+        "progress=continue".to_string()
+    });
+    lines
+}
+
+/// Value following `flag` in `args`.
+fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let i = args.iter().position(|a| a == flag)?;
+    args.get(i + 1).map(String::as_str)
+}
+
+/// Files a real run leaves behind: the output (last argument, unless it is a pipe or null
+/// output) and the pass log of a first pass.
+fn output_files(args: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(output) = args.last()
+        && output != "-"
+        && !output.starts_with("pipe:")
+        && arg_after(args, "-f") != Some("null")
+    {
+        files.push(PathBuf::from(output));
+    }
+    if arg_after(args, "-pass") == Some("1")
+        && let Some(prefix) = arg_after(args, "-passlogfile")
+    {
+        files.push(PathBuf::from(format!("{}-0.log", prefix)));
+    }
+    files
+}
+
+fn finish(args: &[String]) -> Result<(), AppError> {
+    for file in output_files(args) {
+        fs::write(&file, PLACEHOLDER)?;
+    }
+    log::info!(
+        target: "tiny_vid::ffmpeg::fake",
+        "simulated run complete: output={:?}",
+        args.last()
+    );
+    Ok(())
+}
+
+fn interrupted(token: &CancellationToken, generation: u64) -> bool {
+    token.is_cancelled() || terminate_generation() != generation
+}
+
+/// Simulates one run, passing every line ffmpeg would print to `on_line`. Blocking.
+pub(super) fn run_blocking(
+    ctx: &FfmpegContext,
+    args: &[String],
+    duration_secs: Option<f64>,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), AppError> {
+    let token = ctx.cancellation();
+    let generation = terminate_generation();
+    let duration = duration_secs
+        .filter(|d| *d > 0.0)
+        .unwrap_or(MEDIA_DURATION_SECS);
+    for tick in 0..=TICKS {
+        if interrupted(token, generation) {
+            return Err(AppError::aborted());
+        }
+        for line in tick_lines(tick, duration) {
+            on_line(&line);
+        }
+        if tick < TICKS {
+            std::thread::sleep(TICK_INTERVAL);
+        }
+    }
+    finish(args)
+}
+
+/// Async counterpart of `run_blocking`.
+pub(super) async fn run_async(
+    ctx: &FfmpegContext,
+    args: &[String],
+    duration_secs: Option<f64>,
+    on_line: &mut (dyn FnMut(&str) + Send),
+) -> Result<(), AppError> {
+    let token = ctx.cancellation();
+    let generation = terminate_generation();
+    let duration = duration_secs
+        .filter(|d| *d > 0.0)
+        .unwrap_or(MEDIA_DURATION_SECS);
+    for tick in 0..=TICKS {
+        if interrupted(token, generation) {
+            return Err(AppError::aborted());
+        }
+        for line in tick_lines(tick, duration) {
+            on_line(&line);
+        }
+        if tick < TICKS {
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    }
+    finish(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::progress::parse_ffmpeg_progress;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn tick_lines_drive_progress_from_zero_to_done() {
+        let mut duration = None;
+        let mut seen = Vec::new();
+        for tick in 0..=TICKS {
+            for line in tick_lines(tick, 90.5) {
+                let (progress, d) = parse_ffmpeg_progress(&line, duration);
+                duration = d;
+                seen.extend(progress);
+            }
+        }
+        assert_eq!(duration, Some(90.5));
+        assert_eq!(seen.first(), Some(&0.0));
+        assert_eq!(seen.last(), Some(&1.0));
+        assert_eq!(seen.len(), TICKS as usize + 1);
+    }
+
+    #[test]
+    fn output_files_skip_pipes_and_null_output() {
+        assert_eq!(
+            output_files(&args(&["-i", "/in.mp4", "-c:v", "libx264", "/out.mp4"])),
+            [PathBuf::from("/out.mp4")]
+        );
+        assert!(output_files(&args(&["-i", "/in.mp4", "pipe:1"])).is_empty());
+        let first_pass = args(&[
+            "-i",
+            "/in.mp4",
+            "-pass",
+            "1",
+            "-passlogfile",
+            "/tmp/log",
+            "-f",
+            "null",
+            "-",
+        ]);
+        assert_eq!(output_files(&first_pass), [PathBuf::from("/tmp/log-0.log")]);
+    }
+
+    #[test]
+    fn finish_writes_placeholders() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.mp4");
+        finish(&args(&["-i", "/in.mp4", &output.to_string_lossy()])).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), PLACEHOLDER);
+    }
+
+    #[test]
+    fn cancelled_runs_abort_before_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.mp4");
+        let token = CancellationToken::new();
+        token.cancel();
+        let ctx = FfmpegContext::new(FAKE_FFMPEG_PATH).with_cancellation(token);
+        let result = run_blocking(
+            &ctx,
+            &args(&["-i", "/in.mp4", &output.to_string_lossy()]),
+            Some(1.0),
+            &mut |_| {},
+        );
+        assert!(matches!(result, Err(AppError::Aborted)));
+        assert!(!output.exists());
+    }
+}
@@ -4,8 +4,9 @@
 use crate::error::AppError;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
@@ -37,6 +38,8 @@ struct FfprobeStream {
     height: Option<u32>,
     r_frame_rate: Option<String>,
     #[serde(default)]
+    duration: Option<String>,
+    #[serde(default)]
     codec_name: Option<String>,
     #[serde(default)]
     codec_long_name: Option<String>,
@@ -46,6 +49,46 @@ struct FfprobeStream {
     channels: Option<u32>,
     #[serde(default)]
     tags: Option<HashMap<String, String>>,
+    #[serde(default)]
+    disposition: Option<HashMap<String, u8>>,
+    #[serde(default)]
+    side_data_list: Option<Vec<FfprobeSideData>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeSideData {
+    #[serde(default)]
+    rotation: Option<f64>,
+}
+
+impl FfprobeStream {
+    /// Cover art / thumbnails are stored as single-frame "video" streams.
+    fn is_attached_pic(&self) -> bool {
+        self.disposition
+            .as_ref()
+            .and_then(|d| d.get("attached_pic"))
+            .is_some_and(|&v| v == 1)
+    }
+
+    /// Clockwise degrees (0/90/180/270) players rotate the frames by, from the display matrix
+    /// (counter-clockwise) or the older `rotate` tag (clockwise).
+    fn display_rotation(&self) -> u32 {
+        let degrees = self
+            .side_data_list
+            .iter()
+            .flatten()
+            .find_map(|d| d.rotation)
+            .map(|ccw| -ccw)
+            .or_else(|| {
+                self.tags
+                    .as_ref()
+                    .and_then(|t| t.get("rotate"))
+                    .and_then(|r| r.trim().parse::<f64>().ok())
+            })
+            .filter(|d| d.is_finite())
+            .unwrap_or(0.0);
+        ((degrees / 90.0).round() as i64).rem_euclid(4) as u32 * 90
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,11 +110,40 @@ fn parse_frame_rate(s: &str) -> Option<f64> {
     Some(num / den)
 }
 
+/// Parses Matroska `DURATION` tags ("00:01:02.345000000") into seconds.
+fn parse_tag_duration(s: &str) -> Option<f64> {
+    let mut parts = s.trim().split(':');
+    let h: f64 = parts.next()?.parse().ok()?;
+    let m: f64 = parts.next()?.parse().ok()?;
+    let sec: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(h * 3600.0 + m * 60.0 + sec)
+}
+
+/// Duration from the video stream when the container has none (common in MKV/TS):
+/// stream `duration`, then the Matroska `DURATION` tag.
+fn stream_duration(stream: &FfprobeStream) -> Option<f64> {
+    stream
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .or_else(|| {
+            stream
+                .tags
+                .as_ref()
+                .and_then(|t| t.get("DURATION").or_else(|| t.get("duration")))
+                .and_then(|d| parse_tag_duration(d))
+        })
+        .filter(|d| d.is_finite() && *d > 0.0)
+}
+
 fn parse_bit_rate(s: &str) -> Option<u64> {
     s.trim().parse().ok()
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VideoMetadata {
     pub duration: f64,
@@ -79,8 +151,11 @@ pub struct VideoMetadata {
     /// Format start_time (seconds). Non-zero for stream-copied segments; re-encoded typically 0.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_time: Option<f64>,
+    /// Display size: FFmpeg rotates frames upright when decoding, so 90/270 swaps the coded size.
     pub width: u32,
     pub height: u32,
+    /// Clockwise rotation (0/90/180/270) from the source's display matrix.
+    pub rotation: u32,
     pub size: u64,
     pub fps: f64,
     pub codec_name: Option<String>,
@@ -96,8 +171,41 @@ pub struct VideoMetadata {
     pub audio_codec_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub audio_channels: Option<u32>,
+    /// First audio stream bit rate (bits/s).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_bit_rate: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encoder: Option<String>,
+    /// Video streams excluding cover art / thumbnails.
+    pub video_stream_count: u32,
+    /// `0:v:N` index of the main video stream (largest non-cover-art stream).
+    pub main_video_stream_index: u32,
+}
+
+/// Lowercase encoder-tag fragments written by common screen recorders.
+const SCREEN_RECORDER_TAGS: &[&str] = &[
+    "obs",
+    "sharex",
+    "bandicam",
+    "camtasia",
+    "screenflow",
+    "snagit",
+    "loom",
+    "kap",
+    "screen",
+];
+
+impl VideoMetadata {
+    /// True when the encoder tag names a known screen recorder (OBS, ShareX, Camtasia, ...).
+    pub fn looks_like_screen_recording(&self) -> bool {
+        let Some(encoder) = self.encoder.as_deref() else {
+            return false;
+        };
+        let encoder = encoder.to_lowercase();
+        encoder
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| SCREEN_RECORDER_TAGS.iter().any(|tag| word.starts_with(tag)))
+    }
 }
 
 /// Parse ffprobe JSON output into VideoMetadata.
@@ -106,10 +214,10 @@ pub fn parse_ffprobe_json(json: &str) -> Result<VideoMetadata, AppError> {
         .map_err(|e| AppError::from(format!("Failed to parse ffprobe JSON: {}", e)))?;
 
     let format = output.format.as_ref();
-    let duration = format
+    let format_duration = format
         .and_then(|f| f.duration.as_ref())
         .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.0);
+        .filter(|d| d.is_finite() && *d > 0.0);
     let start_time = format
         .and_then(|f| f.start_time.as_ref())
         .and_then(|s| s.parse::<f64>().ok())
@@ -119,13 +227,31 @@ pub fn parse_ffprobe_json(json: &str) -> Result<VideoMetadata, AppError> {
         .and_then(|s| s.parse::<u64>().ok())
         .unwrap_or(0);
 
-    let video_stream = output.streams.as_ref().and_then(|streams| {
-        streams
-            .iter()
-            .find(|s| s.codec_type.as_deref() == Some("video"))
-    });
-    let width = video_stream.and_then(|s| s.width).unwrap_or(0);
-    let height = video_stream.and_then(|s| s.height).unwrap_or(0);
+    // `0:v:N` counts every video-type stream, cover art included.
+    let video_streams: Vec<&FfprobeStream> = output
+        .streams
+        .iter()
+        .flatten()
+        .filter(|s| s.codec_type.as_deref() == Some("video"))
+        .collect();
+    let video_stream_count = video_streams
+        .iter()
+        .filter(|s| !s.is_attached_pic())
+        .count() as u32;
+    let (main_video_stream_index, video_stream) = main_video_stream(&video_streams)
+        .map(|(i, s)| (i as u32, Some(s)))
+        .unwrap_or((0, video_streams.first().copied()));
+    let duration = format_duration
+        .or_else(|| video_stream.and_then(stream_duration))
+        .unwrap_or(0.0);
+    let rotation = video_stream.map_or(0, FfprobeStream::display_rotation);
+    let coded_width = video_stream.and_then(|s| s.width).unwrap_or(0);
+    let coded_height = video_stream.and_then(|s| s.height).unwrap_or(0);
+    let (width, height) = if rotation % 180 == 90 {
+        (coded_height, coded_width)
+    } else {
+        (coded_width, coded_height)
+    };
     let fps = video_stream
         .and_then(|s| s.r_frame_rate.as_deref())
         .and_then(parse_frame_rate)
@@ -166,6 +292,9 @@ pub fn parse_ffprobe_json(json: &str) -> Result<VideoMetadata, AppError> {
     });
     let audio_codec_name = first_audio.and_then(|a| a.codec_name.clone());
     let audio_channels = first_audio.and_then(|a| a.channels);
+    let audio_bit_rate = first_audio
+        .and_then(|a| a.bit_rate.as_deref())
+        .and_then(parse_bit_rate);
 
     let encoder = video_stream
         .and_then(|s| s.tags.as_ref())
@@ -183,6 +312,7 @@ pub fn parse_ffprobe_json(json: &str) -> Result<VideoMetadata, AppError> {
         start_time,
         width,
         height,
+        rotation,
         size,
         fps,
         codec_name,
@@ -196,31 +326,108 @@ pub fn parse_ffprobe_json(json: &str) -> Result<VideoMetadata, AppError> {
         subtitle_stream_count,
         audio_codec_name,
         audio_channels,
+        audio_bit_rate,
         encoder,
+        video_stream_count,
+        main_video_stream_index,
     })
 }
 
+/// Largest-area video stream that is not cover art (first wins ties), with its `0:v:N` index.
+/// Skips e.g. GoPro/phone preview tracks and embedded MJPEG thumbnails.
+fn main_video_stream<'a>(
+    video_streams: &[&'a FfprobeStream],
+) -> Option<(usize, &'a FfprobeStream)> {
+    video_streams
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.is_attached_pic())
+        .fold(None, |best: Option<(usize, &FfprobeStream)>, (i, s)| {
+            let area = |st: &FfprobeStream| {
+                u64::from(st.width.unwrap_or(0)) * u64::from(st.height.unwrap_or(0))
+            };
+            match best {
+                Some((_, b)) if area(b) >= area(s) => best,
+                _ => Some((i, *s)),
+            }
+        })
+}
+
+/// Fields read by `parse_ffprobe_json`. Selecting them explicitly keeps ffprobe from
+/// serializing every stream/format property (side data, dispositions, all tags).
+const SHOW_ENTRIES: &str = "format=duration,start_time,size,bit_rate,format_name,format_long_name,nb_streams\
+:format_tags=encoder\
+:stream=codec_type,width,height,r_frame_rate,duration,codec_name,codec_long_name,bit_rate,channels\
+:stream_tags=encoder,DURATION,rotate\
+:stream_disposition=attached_pic\
+:stream_side_data=rotation";
+
+/// Probe limits for `quick` mode: stop analyzing after 1s of media / 5 MB of input.
+const QUICK_ANALYZE_DURATION_US: &str = "1000000";
+const QUICK_PROBE_SIZE_BYTES: &str = "5000000";
+
+fn build_ffprobe_args(path: &str, quick: bool) -> Vec<String> {
+    let mut args: Vec<String> = ["-v", "quiet", "-print_format", "json"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    if quick {
+        args.extend([
+            "-analyzeduration".to_string(),
+            QUICK_ANALYZE_DURATION_US.to_string(),
+            "-probesize".to_string(),
+            QUICK_PROBE_SIZE_BYTES.to_string(),
+        ]);
+    }
+    args.extend([
+        "-show_entries".to_string(),
+        SHOW_ENTRIES.to_string(),
+        path.to_string(),
+    ]);
+    args
+}
+
 /// Run ffprobe on a video file and return metadata.
 pub fn get_video_metadata_impl(path: &Path) -> Result<VideoMetadata, AppError> {
-    let ffprobe = get_ffprobe_path()?;
+    get_video_metadata_with_mode(path, false)
+}
+
+/// Like `get_video_metadata_impl`; `quick` caps analysis (`-analyzeduration`/`-probesize`) for
+/// lower latency on large or network files. Quick results may miss late-starting streams or
+/// report estimated durations/bitrates.
+pub fn get_video_metadata_with_mode(path: &Path, quick: bool) -> Result<VideoMetadata, AppError> {
+    get_video_metadata_with_ffprobe(&get_ffprobe_path()?, path, quick)
+}
+
+/// Like `get_video_metadata_with_mode`, probing with `ctx`'s ffprobe.
+pub fn get_video_metadata_in(
+    ctx: &super::FfmpegContext,
+    path: &Path,
+    quick: bool,
+) -> Result<VideoMetadata, AppError> {
+    get_video_metadata_with_ffprobe(&ctx.ffprobe_path()?, path, quick)
+}
+
+/// Like `get_video_metadata_with_mode`, using the given ffprobe binary (see `FfmpegContext`).
+pub fn get_video_metadata_with_ffprobe(
+    ffprobe: &Path,
+    path: &Path,
+    quick: bool,
+) -> Result<VideoMetadata, AppError> {
+    if super::fake::enabled() {
+        return Ok(super::fake::video_metadata(path));
+    }
     let path_str = path.to_string_lossy();
 
     log::debug!(
         target: "tiny_vid::ffmpeg::ffprobe",
-        "get_video_metadata: path={}",
-        path_str
+        "get_video_metadata: path={}, quick={}",
+        path_str,
+        quick
     );
 
-    let mut cmd = Command::new(&ffprobe);
-    cmd.args([
-        "-v",
-        "quiet",
-        "-print_format",
-        "json",
-        "-show_format",
-        "-show_streams",
-        &path_str,
-    ]);
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(build_ffprobe_args(&path_str, quick));
     #[cfg(windows)]
     cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     let output = cmd
@@ -235,13 +442,284 @@ pub fn get_video_metadata_impl(path: &Path) -> Result<VideoMetadata, AppError> {
     let json = String::from_utf8(output.stdout)
         .map_err(|_| AppError::from("ffprobe output was not valid UTF-8".to_string()))?;
 
-    parse_ffprobe_json(&json)
+    let mut meta = parse_ffprobe_json(&json)?;
+    if meta.duration <= 0.0
+        && !quick
+        && let Some(duration) = probe_duration_from_packets(ffprobe, &path_str)
+    {
+        log::debug!(
+            target: "tiny_vid::ffmpeg::ffprobe",
+            "get_video_metadata: no container duration, using last packet: {:.3}s",
+            duration
+        );
+        meta.duration = duration;
+    }
+    Ok(meta)
+}
+
+/// Probes piped data (e.g. the head of a stream later fed to `transcode_from_reader`) through
+/// ffprobe's stdin. A pipe has no reliable duration and cannot be re-read for the packet
+/// fallback, so a caller-supplied `duration_secs` replaces whatever the container reported.
+pub fn get_video_metadata_from_bytes(
+    ffprobe: &Path,
+    data: &[u8],
+    duration_secs: Option<f64>,
+) -> Result<VideoMetadata, AppError> {
+    log::debug!(
+        target: "tiny_vid::ffmpeg::ffprobe",
+        "get_video_metadata_from_bytes: {} bytes, duration={:?}",
+        data.len(),
+        duration_secs
+    );
+
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(build_ffprobe_args("pipe:0", false))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| AppError::from(format!("Failed to run ffprobe: {}", e)))?;
+    let output = std::thread::scope(|scope| {
+        if let Some(mut stdin) = child.stdin.take() {
+            // ffprobe may stop reading once it has seen enough; a broken pipe is expected then.
+            scope.spawn(move || {
+                let _ = stdin.write_all(data);
+            });
+        }
+        child.wait_with_output()
+    })
+    .map_err(|e| AppError::from(format!("Failed to run ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::from(format!("ffprobe failed: {}", stderr.trim())));
+    }
+    let json = String::from_utf8(output.stdout)
+        .map_err(|_| AppError::from("ffprobe output was not valid UTF-8".to_string()))?;
+
+    let mut meta = parse_ffprobe_json(&json)?;
+    if let Some(duration) = duration_secs.filter(|&d| d > 0.0) {
+        meta.duration = duration;
+    }
+    Ok(meta)
+}
+
+/// Parses `pts_time,duration_time` CSV lines; returns (first pts, end of the last packet).
+fn packet_span_from_csv(csv: &str) -> Option<(f64, f64)> {
+    let mut first: Option<f64> = None;
+    let mut end: Option<f64> = None;
+    for line in csv.lines() {
+        let mut fields = line.split(',');
+        let Some(pts) = fields.next().and_then(|v| v.trim().parse::<f64>().ok()) else {
+            continue;
+        };
+        let dur = fields
+            .next()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .unwrap_or(0.0);
+        first = Some(first.map_or(pts, |f: f64| f.min(pts)));
+        end = Some(end.map_or(pts + dur, |e: f64| e.max(pts + dur)));
+    }
+    Some((first?, end?))
+}
+
+/// Exact length of a video stream: frame count and first pts to end of the last frame.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoTiming {
+    pub frame_count: u64,
+    pub duration_seconds: f64,
+}
+
+/// Counts timestamped packets (one per frame for video) and measures their span.
+fn timing_from_packet_csv(csv: &str) -> Option<VideoTiming> {
+    let frame_count = csv
+        .lines()
+        .filter(|line| {
+            line.split(',')
+                .next()
+                .is_some_and(|v| v.trim().parse::<f64>().is_ok())
+        })
+        .count() as u64;
+    let duration_seconds = duration_from_packet_csv(csv)?;
+    Some(VideoTiming {
+        frame_count,
+        duration_seconds,
+    })
+}
+
+/// Frame count and duration of the first video stream, read from packet timestamps (demux only,
+/// no decode). Players drift apart when looping files whose container durations are rounded.
+pub fn probe_video_timing_with_ffprobe(ffprobe: &Path, path: &Path) -> Option<VideoTiming> {
+    timing_from_packet_csv(&read_packet_csv(ffprobe, &path.to_string_lossy(), "v:0")?)
+}
+
+/// Returns end of the last packet minus the first pts.
+fn duration_from_packet_csv(csv: &str) -> Option<f64> {
+    let (first, end) = packet_span_from_csv(csv)?;
+    let duration = end - first;
+    (duration.is_finite() && duration > 0.0).then_some(duration)
+}
+
+/// Last-resort duration: reads video packet timestamps (demux only, no decode).
+fn probe_duration_from_packets(ffprobe: &Path, path: &str) -> Option<f64> {
+    duration_from_packet_csv(&read_packet_csv(ffprobe, path, "v:0")?)
+}
+
+/// (first pts, end of last packet) for the stream matched by `stream_selector` (e.g. `a:0`).
+/// `None` when the stream is missing or has no timestamped packets. Demux only, no decode.
+pub(super) fn probe_packet_span(
+    ffprobe: &Path,
+    path: &str,
+    stream_selector: &str,
+) -> Option<(f64, f64)> {
+    packet_span_from_csv(&read_packet_csv(ffprobe, path, stream_selector)?)
+}
+
+fn read_packet_csv(ffprobe: &Path, path: &str, stream_selector: &str) -> Option<String> {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args([
+        "-v",
+        "error",
+        "-select_streams",
+        stream_selector,
+        "-show_entries",
+        "packet=pts_time,duration_time",
+        "-of",
+        "csv=p=0",
+        path,
+    ]);
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// First CSV field that parses as a timestamp (ffprobe prints `N/A` for frames without one).
+fn parse_first_frame_pts(csv: &str) -> Option<f64> {
+    csv.lines()
+        .filter_map(|line| line.split(',').next()?.trim().parse::<f64>().ok())
+        .find(|pts| pts.is_finite())
+}
+
+/// Presentation time (seconds) of the first decoded video frame. Unlike format `start_time`,
+/// this accounts for edit lists and B-frame reordering, i.e. what a player actually shows first.
+pub fn probe_first_frame_pts(path: &Path) -> Option<f64> {
+    probe_first_frame_pts_with_ffprobe(&get_ffprobe_path().ok()?, path)
+}
+
+/// Like `probe_first_frame_pts`, using the given ffprobe binary.
+pub fn probe_first_frame_pts_with_ffprobe(ffprobe: &Path, path: &Path) -> Option<f64> {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args([
+        "-v",
+        "error",
+        "-select_streams",
+        "v:0",
+        "-read_intervals",
+        "%+#1",
+        "-show_entries",
+        "frame=best_effort_timestamp_time",
+        "-of",
+        "csv=p=0",
+    ]);
+    cmd.arg(path);
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_first_frame_pts(&String::from_utf8_lossy(&output.stdout))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn ffprobe_args_select_entries_and_limit_probe_in_quick_mode() {
+        let full = build_ffprobe_args("/in.mp4", false);
+        assert!(full.contains(&"-show_entries".to_string()));
+        assert!(!full.contains(&"-show_streams".to_string()));
+        assert!(!full.contains(&"-probesize".to_string()));
+        assert_eq!(full.last().unwrap(), "/in.mp4");
+
+        let quick = build_ffprobe_args("/in.mp4", true);
+        let idx = quick.iter().position(|a| a == "-analyzeduration").unwrap();
+        assert_eq!(quick[idx + 1], QUICK_ANALYZE_DURATION_US);
+        assert!(quick.contains(&"-probesize".to_string()));
+        assert_eq!(quick.last().unwrap(), "/in.mp4");
+    }
+
+    #[test]
+    fn duration_falls_back_to_stream_then_mkv_tag() {
+        let json = r#"{
+            "format": { "format_name": "matroska,webm" },
+            "streams": [
+                { "codec_type": "video", "width": 640, "height": 360, "duration": "12.5" }
+            ]
+        }"#;
+        assert_eq!(parse_ffprobe_json(json).unwrap().duration, 12.5);
+
+        let json = r#"{
+            "format": { "duration": "0.000000" },
+            "streams": [
+                { "codec_type": "video", "tags": { "DURATION": "00:01:02.500000000" } }
+            ]
+        }"#;
+        assert_eq!(parse_ffprobe_json(json).unwrap().duration, 62.5);
+
+        let json = r#"{ "format": {}, "streams": [ { "codec_type": "video" } ] }"#;
+        assert_eq!(parse_ffprobe_json(json).unwrap().duration, 0.0);
+    }
+
+    #[test]
+    fn duration_from_packet_csv_uses_first_pts_and_last_end() {
+        let csv = "1.000000,0.040000\n1.040000,0.040000\nN/A,N/A\n3.960000,0.040000\n";
+        let d = duration_from_packet_csv(csv).unwrap();
+        assert!((d - 3.0).abs() < 1e-9, "{}", d);
+        assert_eq!(duration_from_packet_csv(""), None);
+        assert_eq!(packet_span_from_csv("0.5,0.1\n0.2,0.1\n"), Some((0.2, 0.6)));
+    }
+
+    #[test]
+    fn timing_counts_timestamped_packets() {
+        let csv = "0.000000,0.033367\n0.066733,0.033367\nN/A,N/A\n0.033367,0.033367\n";
+        let timing = timing_from_packet_csv(csv).unwrap();
+        assert_eq!(timing.frame_count, 3);
+        assert!((timing.duration_seconds - 0.1001).abs() < 1e-9);
+        assert_eq!(timing_from_packet_csv("N/A,N/A\n"), None);
+    }
+
+    #[test]
+    fn parse_first_frame_pts_skips_missing_timestamps() {
+        assert_eq!(parse_first_frame_pts("N/A\n0.083000\n"), Some(0.083));
+        assert_eq!(parse_first_frame_pts("0.000000,\n"), Some(0.0));
+        assert_eq!(parse_first_frame_pts(""), None);
+    }
+
+    #[test]
+    fn show_entries_covers_parsed_fields() {
+        for field in [
+            "duration",
+            "nb_streams",
+            "r_frame_rate",
+            "channels",
+            "encoder",
+            "attached_pic",
+            "rotation",
+        ] {
+            assert!(SHOW_ENTRIES.contains(field), "missing {}", field);
+        }
+    }
+
     #[test]
     fn parse_ffprobe_json_extracts_all_metadata() {
         let json = r#"{
@@ -280,6 +758,57 @@ mod tests {
         assert_eq!(meta.nb_streams, Some(2));
     }
 
+    #[test]
+    fn parse_ffprobe_json_picks_main_video_stream_over_thumbnails_and_previews() {
+        let json = r#"{
+            "format": { "duration": "10.0" },
+            "streams": [
+                {"codec_type": "video", "codec_name": "mjpeg", "width": 320, "height": 240,
+                 "disposition": {"attached_pic": 1}},
+                {"codec_type": "video", "codec_name": "h264", "width": 848, "height": 480,
+                 "disposition": {"attached_pic": 0}},
+                {"codec_type": "video", "codec_name": "hevc", "width": 3840, "height": 2160,
+                 "disposition": {"attached_pic": 0}}
+            ]
+        }"#;
+        let meta = parse_ffprobe_json(json).unwrap();
+        assert_eq!(meta.video_stream_count, 2);
+        assert_eq!(meta.main_video_stream_index, 2);
+        assert_eq!(meta.codec_name.as_deref(), Some("hevc"));
+        assert_eq!(meta.width, 3840);
+    }
+
+    #[test]
+    fn rotated_sources_report_display_size() {
+        let json = r#"{
+            "format": { "duration": "5.0" },
+            "streams": [
+                { "codec_type": "video", "width": 1920, "height": 1080,
+                  "side_data_list": [ { "side_data_type": "Display Matrix", "rotation": -90 } ] }
+            ]
+        }"#;
+        let meta = parse_ffprobe_json(json).unwrap();
+        assert_eq!((meta.width, meta.height, meta.rotation), (1080, 1920, 90));
+
+        let json = r#"{
+            "format": { "duration": "5.0" },
+            "streams": [
+                { "codec_type": "video", "width": 1920, "height": 1080, "tags": { "rotate": "180" } }
+            ]
+        }"#;
+        let meta = parse_ffprobe_json(json).unwrap();
+        assert_eq!((meta.width, meta.height, meta.rotation), (1920, 1080, 180));
+
+        let json = r#"{
+            "format": { "duration": "5.0" },
+            "streams": [
+                { "codec_type": "video", "width": 1920, "height": 1080,
+                  "side_data_list": [ { "rotation": 90 } ] }
+            ]
+        }"#;
+        assert_eq!(parse_ffprobe_json(json).unwrap().rotation, 270);
+    }
+
     #[test]
     fn parse_ffprobe_json_handles_missing_video_stream() {
         let json = r#"{
@@ -326,12 +855,13 @@ mod tests {
             "format": { "duration": "10.0", "size": "1000" },
             "streams": [
                 {"codec_type": "video", "width": 1920, "height": 1080, "r_frame_rate": "30/1"},
-                {"codec_type": "audio", "codec_name": "aac", "channels": 6}
+                {"codec_type": "audio", "codec_name": "aac", "channels": 6, "bit_rate": "384000"}
             ]
         }"#;
         let meta = parse_ffprobe_json(json).unwrap();
         assert_eq!(meta.audio_codec_name.as_deref(), Some("aac"));
         assert_eq!(meta.audio_channels, Some(6));
+        assert_eq!(meta.audio_bit_rate, Some(384_000));
     }
 
     #[test]
@@ -383,5 +913,21 @@ mod tests {
         }"#;
         let meta = parse_ffprobe_json(json).unwrap();
         assert_eq!(meta.encoder.as_deref(), Some("Lavf59.16.100"));
+        assert!(!meta.looks_like_screen_recording());
+    }
+
+    #[test]
+    fn screen_recorder_encoder_tags_are_detected() {
+        let json = r#"{
+            "format": {"duration": "10.0", "size": "1000"},
+            "streams": [{"codec_type": "video", "width": 2560, "height": 1440, "r_frame_rate": "60/1",
+                "tags": {"encoder": "OBS Studio (30.1.2)"}}]
+        }"#;
+        let mut meta = parse_ffprobe_json(json).unwrap();
+        assert!(meta.looks_like_screen_recording());
+        meta.encoder = Some("ScreenFlow".to_string());
+        assert!(meta.looks_like_screen_recording());
+        meta.encoder = Some("Lavc60.3.100 libx264 (Kodak)".to_string());
+        assert!(!meta.looks_like_screen_recording());
     }
 }
@@ -0,0 +1,243 @@
+//! Hardware encoder health probe.
+//!
+//! FFmpeg lists hardware encoders (VideoToolbox, NVENC, ...) whenever they were compiled in,
+//! even if the GPU/driver is missing or all encode sessions are taken. A 0.1s probe encode per
+//! hardware encoder catches that up front; results are cached for the process lifetime unless
+//! a refresh is requested. A session limit is transient (another encode holds the sessions), so
+//! that failure is only cached for `SESSION_LIMIT_TTL` and then probed again.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use super::builder::hw_encoder_args;
+use crate::codec::is_hardware_codec;
+
+/// How long a session limit failure is trusted before the encoder is probed again.
+const SESSION_LIMIT_TTL: Duration = Duration::from_secs(30);
+
+static HEALTH_CACHE: Mutex<Option<HashMap<String, (EncoderHealth, Instant)>>> = Mutex::new(None);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderFailure {
+    /// All hardware encode sessions are in use (e.g. NVENC consumer session limit).
+    SessionLimit,
+    /// Encoder is compiled in but the device/driver is not usable.
+    Unavailable,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EncoderHealth {
+    pub codec: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<EncoderFailure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+fn build_probe_args(codec: &str) -> Vec<String> {
+    let (device_args, upload_filter) = hw_encoder_args(codec);
+    let mut args: Vec<String> = ["-nostdin", "-hide_banner", "-loglevel", "error"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.extend(device_args);
+    args.extend(
+        [
+            "-f",
+            "lavfi",
+            "-i",
+            "testsrc=duration=0.1:size=256x256:rate=10",
+            "-frames:v",
+            "1",
+        ]
+        .iter()
+        .map(|s| s.to_string()),
+    );
+    match upload_filter {
+        Some(filter) => args.extend(["-vf".to_string(), filter.to_string()]),
+        None => args.extend(["-pix_fmt".to_string(), "yuv420p".to_string()]),
+    }
+    args.extend(
+        ["-c:v", codec, "-f", "null", "-"]
+            .iter()
+            .map(|s| s.to_string()),
+    );
+    args
+}
+
+fn classify_failure(stderr: &str) -> EncoderFailure {
+    let s = stderr.to_lowercase();
+    if s.contains("session limit")
+        || s.contains("incompatible client key")
+        || (s.contains("session") && s.contains("out of memory"))
+    {
+        EncoderFailure::SessionLimit
+    } else {
+        EncoderFailure::Unavailable
+    }
+}
+
+fn probe_encoder(ffmpeg: &Path, codec: &str) -> EncoderHealth {
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(build_probe_args(codec));
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let (ok, stderr) = match cmd.output() {
+        Ok(output) => (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ),
+        Err(e) => (false, e.to_string()),
+    };
+    if ok {
+        return EncoderHealth {
+            codec: codec.to_string(),
+            ok: true,
+            failure: None,
+            detail: None,
+        };
+    }
+    log::warn!(
+        target: "tiny_vid::ffmpeg::hw_probe",
+        "Hardware encoder {} failed probe: {}",
+        codec,
+        stderr
+    );
+    EncoderHealth {
+        codec: codec.to_string(),
+        ok: false,
+        failure: Some(classify_failure(&stderr)),
+        detail: (!stderr.is_empty()).then_some(stderr),
+    }
+}
+
+/// Whether a probe result `age` old can still be used.
+fn still_valid(health: &EncoderHealth, age: Duration) -> bool {
+    health.failure != Some(EncoderFailure::SessionLimit) || age < SESSION_LIMIT_TTL
+}
+
+/// Probes each hardware encoder in `available` (software encoders are skipped). Cached per
+/// codec, session limit failures briefly; `refresh` re-runs the probes. The cache is not locked
+/// while probing, so callers needing only cached results are never held up by a probe.
+pub fn probe_hardware_encoders(
+    ffmpeg: &Path,
+    available: &[String],
+    refresh: bool,
+) -> Vec<EncoderHealth> {
+    let cached: Vec<(&String, Option<EncoderHealth>)> = {
+        let mut guard = HEALTH_CACHE.lock();
+        let cache = guard.get_or_insert_with(HashMap::new);
+        if refresh {
+            cache.clear();
+        }
+        available
+            .iter()
+            .filter(|c| is_hardware_codec(c))
+            .map(|codec| {
+                let health = cache
+                    .get(codec)
+                    .filter(|(health, probed)| still_valid(health, probed.elapsed()))
+                    .map(|(health, _)| health.clone());
+                (codec, health)
+            })
+            .collect()
+    };
+    let mut probed = Vec::new();
+    let results = cached
+        .into_iter()
+        .map(|(codec, health)| {
+            health.unwrap_or_else(|| {
+                let health = probe_encoder(ffmpeg, codec);
+                probed.push(health.clone());
+                health
+            })
+        })
+        .collect();
+    if !probed.is_empty() {
+        let now = Instant::now();
+        let mut guard = HEALTH_CACHE.lock();
+        let cache = guard.get_or_insert_with(HashMap::new);
+        for health in probed {
+            cache.insert(health.codec.clone(), (health, now));
+        }
+    }
+    results
+}
+
+/// Drops hardware encoders that failed their probe. Software encoders pass through.
+pub fn filter_working_encoders(ffmpeg: &Path, available: Vec<String>) -> Vec<String> {
+    let health = probe_hardware_encoders(ffmpeg, &available, false);
+    available
+        .into_iter()
+        .filter(|codec| {
+            health
+                .iter()
+                .find(|h| &h.codec == codec)
+                .is_none_or(|h| h.ok)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_args_encode_single_frame_to_null() {
+        let args = build_probe_args("h264_videotoolbox");
+        let c_idx = args.iter().position(|a| a == "-c:v").unwrap();
+        assert_eq!(args[c_idx + 1], "h264_videotoolbox");
+        assert!(args.contains(&"testsrc=duration=0.1:size=256x256:rate=10".to_string()));
+        assert_eq!(&args[args.len() - 3..], ["-f", "null", "-"]);
+    }
+
+    #[test]
+    fn device_probes_initialize_device_and_upload() {
+        let args = build_probe_args("hevc_qsv");
+        assert!(args.contains(&"-init_hw_device".to_string()));
+        assert!(args.contains(&"format=nv12,hwupload=extra_hw_frames=64".to_string()));
+        assert!(!args.contains(&"-pix_fmt".to_string()));
+
+        let args = build_probe_args("h264_vaapi");
+        assert!(args.contains(&"-vaapi_device".to_string()));
+        assert!(args.contains(&"format=nv12,hwupload".to_string()));
+    }
+
+    #[test]
+    fn classify_failure_detects_session_limit() {
+        assert_eq!(
+            classify_failure("OpenEncodeSessionEx failed: out of memory (10): (no details)"),
+            EncoderFailure::SessionLimit
+        );
+        assert_eq!(
+            classify_failure("Error: cannot create compression session: -12908"),
+            EncoderFailure::Unavailable
+        );
+    }
+
+    #[test]
+    fn session_limit_failures_expire_quickly() {
+        let failed = |failure| EncoderHealth {
+            codec: "h264_nvenc".to_string(),
+            ok: false,
+            failure: Some(failure),
+            detail: None,
+        };
+        let busy = failed(EncoderFailure::SessionLimit);
+        assert!(still_valid(&busy, Duration::from_secs(1)));
+        assert!(!still_valid(&busy, SESSION_LIMIT_TTL));
+        let missing = failed(EncoderFailure::Unavailable);
+        assert!(still_valid(&missing, Duration::from_secs(24 * 60 * 60)));
+    }
+}
@@ -0,0 +1,445 @@
+//! Bundled FFmpeg integrity check and repair.
+//!
+//! Sidecar binaries are re-signed during bundling, so checksums cannot be pinned at build time.
+//! Instead, the first launch of each app version records a baseline (SHA-256 + size) after the
+//! binary runs `-version` successfully, and keeps a verified backup copy. Later launches compare
+//! against the baseline; a mismatch is reported as corrupted and can be repaired from the backup.
+//!
+//! An installed channel update (see `updater`) is checked against the digests recorded when it
+//! was staged. It has no backup: a corrupted update is rolled back rather than repaired.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::discovery::{ffprobe_candidates, resolve_bundled_ffmpeg_path};
+use super::updater::{InstalledBinary, binary_name, installed_update};
+use crate::error::AppError;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory holding the baseline manifest and backup copies. Set once at app startup.
+static INTEGRITY_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the directory used for the integrity baseline (e.g. `<app local data>/ffmpeg-integrity`).
+pub fn set_integrity_dir(dir: PathBuf) {
+    let _ = INTEGRITY_DIR.set(dir);
+}
+
+fn integrity_dir() -> Result<&'static Path, AppError> {
+    INTEGRITY_DIR
+        .get()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| AppError::from("FFmpeg integrity directory is not configured"))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    app_version: String,
+    binaries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    fn entry(&self, name: &str) -> Option<&ManifestEntry> {
+        self.binaries.iter().find(|e| e.name == name)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryStatus {
+    /// Matches the recorded baseline (or baseline was just recorded).
+    Ok,
+    /// Checksum differs from the baseline.
+    Corrupted,
+    /// Bundled binary is gone.
+    Missing,
+    /// No baseline could be recorded (binary failed to run on first check).
+    Unverified,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryIntegrity {
+    pub name: String,
+    pub path: String,
+    pub status: BinaryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_sha256: Option<String>,
+    /// A verified backup exists and can be restored over the bundled binary.
+    pub repairable: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    /// False when the app runs against a system FFmpeg (nothing to verify).
+    pub bundled: bool,
+    pub healthy: bool,
+    pub binaries: Vec<BinaryIntegrity>,
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+fn runs_version(binary: &Path) -> bool {
+    let mut cmd = Command::new(binary);
+    cmd.arg("-version");
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    cmd.output().is_ok_and(|o| o.status.success())
+}
+
+/// Bundled ffmpeg and its ffprobe (first existing candidate, else the plain name).
+fn bundled_binaries() -> Vec<PathBuf> {
+    let Some(ffmpeg) = resolve_bundled_ffmpeg_path() else {
+        return Vec::new();
+    };
+    let candidates = ffprobe_candidates(&ffmpeg);
+    let ffprobe = candidates
+        .iter()
+        .find(|p| p.exists())
+        .or(candidates.last())
+        .cloned();
+    std::iter::once(ffmpeg).chain(ffprobe).collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn read_manifest(dir: &Path) -> Option<Manifest> {
+    let data = fs::read(dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), AppError> {
+    let data = serde_json::to_vec_pretty(manifest).map_err(|e| AppError::from(e.to_string()))?;
+    fs::write(dir.join(MANIFEST_FILE), data)?;
+    Ok(())
+}
+
+fn backup_is_valid(dir: &Path, entry: &ManifestEntry) -> bool {
+    sha256_file(&dir.join(&entry.name)).is_ok_and(|h| h == entry.sha256)
+}
+
+/// Records a baseline for every binary that runs. Binaries that fail are left out (Unverified).
+fn record_baseline(dir: &Path, binaries: &[PathBuf]) -> Result<Manifest, AppError> {
+    fs::create_dir_all(dir)?;
+    let mut entries = Vec::new();
+    for path in binaries {
+        if !path.exists() || !runs_version(path) {
+            continue;
+        }
+        let name = file_name(path);
+        let sha256 = sha256_file(path)?;
+        fs::copy(path, dir.join(&name))?;
+        entries.push(ManifestEntry {
+            name,
+            size: fs::metadata(path)?.len(),
+            sha256,
+        });
+    }
+    let manifest = Manifest {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        binaries: entries,
+    };
+    write_manifest(dir, &manifest)?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::integrity",
+        "Recorded FFmpeg integrity baseline for {} binaries in {}",
+        manifest.binaries.len(),
+        dir.display()
+    );
+    Ok(manifest)
+}
+
+fn check_binary(dir: &Path, manifest: &Manifest, path: &Path) -> BinaryIntegrity {
+    let name = file_name(path);
+    let entry = manifest.entry(&name);
+    let repairable = entry.is_some_and(|e| backup_is_valid(dir, e));
+    let actual = if path.exists() {
+        sha256_file(path).ok()
+    } else {
+        None
+    };
+    BinaryIntegrity {
+        name,
+        path: path.display().to_string(),
+        status: compare(entry.map(|e| e.sha256.as_str()), actual.as_deref()),
+        expected_sha256: entry.map(|e| e.sha256.clone()),
+        actual_sha256: actual,
+        repairable,
+    }
+}
+
+fn compare(expected: Option<&str>, actual: Option<&str>) -> BinaryStatus {
+    match (expected, actual) {
+        (_, None) => BinaryStatus::Missing,
+        (None, Some(_)) => BinaryStatus::Unverified,
+        (Some(expected), Some(actual)) if expected == actual => BinaryStatus::Ok,
+        (Some(_), Some(_)) => BinaryStatus::Corrupted,
+    }
+}
+
+/// Checks ffmpeg/ffprobe of the installed update in `dir` against the digests recorded at
+/// install. Binaries without a recorded digest are Unverified, which fails the check.
+pub(crate) fn check_installed_binaries(
+    dir: &Path,
+    recorded: &[InstalledBinary],
+) -> Vec<BinaryIntegrity> {
+    ["ffmpeg", "ffprobe"]
+        .iter()
+        .map(|base| {
+            let name = binary_name(base);
+            let path = dir.join(&name);
+            let expected = recorded
+                .iter()
+                .find(|b| b.name == name)
+                .map(|b| b.sha256.clone());
+            let actual = if path.exists() {
+                sha256_file(&path).ok()
+            } else {
+                None
+            };
+            BinaryIntegrity {
+                name,
+                path: path.display().to_string(),
+                status: compare(expected.as_deref(), actual.as_deref()),
+                expected_sha256: expected,
+                actual_sha256: actual,
+                repairable: false,
+            }
+        })
+        .collect()
+}
+
+/// Adds the installed update's binaries, if any, to `report`.
+fn with_installed_update(mut report: IntegrityReport) -> IntegrityReport {
+    if let Some((dir, recorded)) = installed_update() {
+        report
+            .binaries
+            .extend(check_installed_binaries(&dir, &recorded));
+        report.healthy = report.binaries.iter().all(|b| b.status == BinaryStatus::Ok);
+    }
+    report
+}
+
+fn build_report(dir: &Path, manifest: &Manifest, binaries: &[PathBuf]) -> IntegrityReport {
+    let results: Vec<BinaryIntegrity> = binaries
+        .iter()
+        .map(|p| check_binary(dir, manifest, p))
+        .collect();
+    IntegrityReport {
+        bundled: true,
+        healthy: results.iter().all(|b| b.status == BinaryStatus::Ok),
+        binaries: results,
+    }
+}
+
+fn check_in_dir(dir: &Path, binaries: &[PathBuf]) -> Result<IntegrityReport, AppError> {
+    let manifest = match read_manifest(dir) {
+        Some(m) if m.app_version == env!("CARGO_PKG_VERSION") => m,
+        _ => record_baseline(dir, binaries)?,
+    };
+    Ok(build_report(dir, &manifest, binaries))
+}
+
+/// Verifies the bundled ffmpeg/ffprobe against the recorded baseline, and the installed update
+/// against its install-time digests. Records the baseline on first run of each app version.
+pub fn check_bundled_integrity() -> Result<IntegrityReport, AppError> {
+    let binaries = bundled_binaries();
+    let report = if binaries.is_empty() {
+        IntegrityReport {
+            bundled: false,
+            healthy: true,
+            binaries: Vec::new(),
+        }
+    } else {
+        check_in_dir(integrity_dir()?, &binaries)?
+    };
+    let report = with_installed_update(report);
+    if !report.healthy {
+        log::error!(
+            target: "tiny_vid::ffmpeg::integrity",
+            "FFmpeg failed integrity check: {:?}",
+            report
+                .binaries
+                .iter()
+                .map(|b| (b.name.as_str(), b.status))
+                .collect::<Vec<_>>()
+        );
+    }
+    Ok(report)
+}
+
+fn repair_in_dir(dir: &Path, binaries: &[PathBuf]) -> Result<IntegrityReport, AppError> {
+    let manifest = read_manifest(dir).ok_or_else(|| {
+        AppError::from("No FFmpeg integrity baseline recorded; reinstall the app")
+    })?;
+    for path in binaries {
+        let check = check_binary(dir, &manifest, path);
+        if !matches!(
+            check.status,
+            BinaryStatus::Corrupted | BinaryStatus::Missing
+        ) {
+            continue;
+        }
+        if !check.repairable {
+            return Err(AppError::from(format!(
+                "No valid backup for {}; reinstall the app",
+                check.name
+            )));
+        }
+        fs::copy(dir.join(&check.name), path).map_err(|e| {
+            AppError::from(format!(
+                "Failed to restore {} (reinstall the app if this persists): {}",
+                path.display(),
+                e
+            ))
+        })?;
+        log::info!(
+            target: "tiny_vid::ffmpeg::integrity",
+            "Restored {} from verified backup",
+            path.display()
+        );
+    }
+    Ok(build_report(dir, &manifest, binaries))
+}
+
+/// Restores corrupted or missing bundled binaries from the verified backup, then re-checks.
+pub fn repair_bundled_ffmpeg() -> Result<IntegrityReport, AppError> {
+    let binaries = bundled_binaries();
+    if binaries.is_empty() {
+        return Err(AppError::from("No bundled FFmpeg to repair"));
+    }
+    repair_in_dir(integrity_dir()?, &binaries).map(with_installed_update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Script that succeeds on `-version`, standing in for a bundled binary.
+    #[cfg(unix)]
+    fn fake_binary(dir: &Path, name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn sha256_file_matches_known_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc");
+        fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn first_check_records_baseline_then_detects_corruption() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let ffmpeg = fake_binary(bin_dir.path(), "ffmpeg");
+
+        let report = check_in_dir(store.path(), std::slice::from_ref(&ffmpeg)).unwrap();
+        assert!(report.healthy);
+        assert!(report.binaries[0].repairable);
+
+        fs::write(&ffmpeg, "garbage").unwrap();
+        let report = check_in_dir(store.path(), std::slice::from_ref(&ffmpeg)).unwrap();
+        assert!(!report.healthy);
+        assert_eq!(report.binaries[0].status, BinaryStatus::Corrupted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn repair_restores_from_backup() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let ffmpeg = fake_binary(bin_dir.path(), "ffmpeg");
+        check_in_dir(store.path(), std::slice::from_ref(&ffmpeg)).unwrap();
+
+        fs::remove_file(&ffmpeg).unwrap();
+        let report = repair_in_dir(store.path(), std::slice::from_ref(&ffmpeg)).unwrap();
+        assert!(report.healthy);
+        assert!(ffmpeg.exists());
+    }
+
+    #[test]
+    fn installed_binaries_are_checked_against_recorded_digests() {
+        let dir = tempfile::tempdir().unwrap();
+        let ffmpeg = dir.path().join(binary_name("ffmpeg"));
+        fs::write(&ffmpeg, b"abc").unwrap();
+        let recorded = [InstalledBinary {
+            name: binary_name("ffmpeg"),
+            sha256: sha256_file(&ffmpeg).unwrap(),
+        }];
+
+        let statuses = |recorded: &[InstalledBinary]| -> Vec<BinaryStatus> {
+            check_installed_binaries(dir.path(), recorded)
+                .iter()
+                .map(|b| b.status)
+                .collect()
+        };
+        assert_eq!(
+            statuses(&recorded),
+            [BinaryStatus::Ok, BinaryStatus::Missing]
+        );
+        assert_eq!(statuses(&[])[0], BinaryStatus::Unverified);
+
+        fs::write(&ffmpeg, b"tampered").unwrap();
+        let report = check_installed_binaries(dir.path(), &recorded);
+        assert_eq!(report[0].status, BinaryStatus::Corrupted);
+        assert!(!report[0].repairable);
+    }
+
+    #[test]
+    fn repair_without_baseline_errors() {
+        let store = tempfile::tempdir().unwrap();
+        let result = repair_in_dir(store.path(), &[store.path().join("ffmpeg")]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Recent FFmpeg stderr of running jobs, read on demand (e.g. by a "details" panel).
+//!
+//! A job opts in by holding a [`LiveStderr`] while it runs and running its steps with a
+//! context carrying its id ([`FfmpegContext::with_job_id`](super::FfmpegContext::with_job_id)).
+//! The runner then appends each stderr line to the job's ring of the last `MAX_LINES` lines.
+//! Nothing is streamed to the frontend; `get_live_stderr` copies the tail when asked. The ring
+//! goes away with the guard, so finished jobs read as unknown.
+
+use std::collections::{HashMap, VecDeque};
+
+use parking_lot::Mutex;
+
+use crate::error::AppError;
+
+/// Lines kept per job.
+const MAX_LINES: usize = 500;
+
+static JOBS: Mutex<Option<HashMap<u64, VecDeque<String>>>> = Mutex::new(None);
+
+/// Keeps the job's stderr ring alive; dropping it discards the lines.
+#[derive(Debug)]
+pub struct LiveStderr {
+    job_id: u64,
+}
+
+impl LiveStderr {
+    pub fn register(job_id: u64) -> Self {
+        JOBS.lock()
+            .get_or_insert_with(HashMap::new)
+            .insert(job_id, VecDeque::new());
+        Self { job_id }
+    }
+}
+
+impl Drop for LiveStderr {
+    fn drop(&mut self) {
+        if let Some(jobs) = JOBS.lock().as_mut() {
+            jobs.remove(&self.job_id);
+        }
+    }
+}
+
+/// Appends `line` to the job's ring, when the job is registered.
+pub(super) fn push(job_id: u64, line: &str) {
+    let mut jobs = JOBS.lock();
+    let Some(ring) = jobs.as_mut().and_then(|jobs| jobs.get_mut(&job_id)) else {
+        return;
+    };
+    if ring.len() == MAX_LINES {
+        ring.pop_front();
+    }
+    ring.push_back(line.to_string());
+}
+
+/// The last `lines` stderr lines of a running job, oldest first.
+pub fn get_live_stderr(job_id: u64, lines: usize) -> Result<Vec<String>, AppError> {
+    let jobs = JOBS.lock();
+    let ring = jobs
+        .as_ref()
+        .and_then(|jobs| jobs.get(&job_id))
+        .ok_or_else(|| AppError::from(format!("No running job {}", job_id)))?;
+    Ok(ring
+        .iter()
+        .skip(ring.len().saturating_sub(lines))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_tail_while_the_job_is_registered() {
+        let job_id = u64::MAX - 7;
+        push(job_id, "before registering");
+        let guard = LiveStderr::register(job_id);
+        for i in 0..MAX_LINES + 2 {
+            push(job_id, &format!("frame={}", i));
+        }
+        let tail = get_live_stderr(job_id, 2).unwrap();
+        assert_eq!(
+            tail,
+            [
+                format!("frame={}", MAX_LINES),
+                format!("frame={}", MAX_LINES + 1)
+            ]
+        );
+        let all = get_live_stderr(job_id, usize::MAX).unwrap();
+        assert_eq!(all.len(), MAX_LINES);
+        assert_eq!(all[0], "frame=2");
+
+        drop(guard);
+        assert!(get_live_stderr(job_id, 10).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! EBU R128 loudness measurement of preview audio.
+//!
+//! Previews are video-only, so the preview window's audio is encoded separately with the
+//! export's audio settings and measured with `ebur128`; a very low integrated loudness or a
+//! clipping true peak hints at over-compressed audio before the user commits to an export.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::{
+    FfmpegContext, TranscodeOptions, build_audio_preview_args, path_to_string, run_ffmpeg_blocking,
+};
+use crate::error::AppError;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessInfo {
+    /// Integrated loudness (LUFS).
+    pub integrated_lufs: f64,
+    /// True peak (dBTP).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub true_peak_dbtp: Option<f64>,
+    /// Loudness range (LU).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loudness_range_lu: Option<f64>,
+}
+
+/// Value after `label` on a summary line, e.g. `I:         -23.0 LUFS` -> -23.0.
+fn summary_value(line: &str, label: &str) -> Option<f64> {
+    line.trim()
+        .strip_prefix(label)?
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// Parses the `Summary:` block ebur128 prints to stderr at the end of the run.
+fn parse_ebur128_summary(stderr: &str) -> Option<LoudnessInfo> {
+    let summary = &stderr[stderr.rfind("Summary:")?..];
+    let mut integrated = None;
+    let mut peak = None;
+    let mut range = None;
+    for line in summary.lines() {
+        integrated = integrated.or_else(|| summary_value(line, "I:"));
+        peak = peak.or_else(|| summary_value(line, "Peak:"));
+        range = range.or_else(|| summary_value(line, "LRA:"));
+    }
+    Some(LoudnessInfo {
+        integrated_lufs: integrated?,
+        true_peak_dbtp: peak,
+        loudness_range_lu: range,
+    })
+}
+
+fn build_measure_args(path: &str) -> Vec<String> {
+    [
+        "-nostdin",
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        path,
+        "-map",
+        "0:a:0",
+        "-af",
+        "ebur128=peak=true",
+        "-f",
+        "null",
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Measures the first audio stream of `path`.
+pub fn measure_loudness(ctx: &FfmpegContext, path: &Path) -> Result<LoudnessInfo, AppError> {
+    let mut args = build_measure_args(&path_to_string(path));
+    ctx.apply_thread_limit(&mut args);
+    let mut cmd = ctx.ffmpeg_command();
+    cmd.args(args);
+    let output = cmd.output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(AppError::ffmpeg_failed(
+            output.status.code().unwrap_or(-1),
+            stderr.into_owned(),
+        ));
+    }
+    parse_ebur128_summary(&stderr)
+        .ok_or_else(|| AppError::from("No loudness summary in ffmpeg output"))
+}
+
+/// Encodes `[start, start + duration)` of the preview audio track (the first track the export
+/// keeps) as the export would, then measures it. Blocking.
+pub fn measure_preview_audio_loudness(
+    ctx: &FfmpegContext,
+    input_path: &Path,
+    options: &TranscodeOptions,
+    start_secs: f64,
+    duration_secs: f64,
+) -> Result<LoudnessInfo, AppError> {
+    let output = ctx.create_temp("preview-audio.mka", None)?;
+    let args = build_audio_preview_args(
+        &path_to_string(input_path),
+        &path_to_string(&output),
+        options,
+        start_secs,
+        duration_secs,
+    );
+    let result = run_ffmpeg_blocking(ctx, args, None, None, Some(duration_secs), None, None)
+        .and_then(|()| measure_loudness(ctx, &output));
+    let _ = fs::remove_file(&output);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EBUR128_STDERR: &str = "\
+[Parsed_ebur128_0 @ 0x600] t: 2.9     TARGET:-23 LUFS    M: -18.1 S:-120.7     I: -18.4 LUFS       LRA:   0.0 LU  FTPK: -3.2 dBFS  TPK: -3.1 dBFS
+[Parsed_ebur128_0 @ 0x600] Summary:
+
+  Integrated loudness:
+    I:         -18.6 LUFS
+    Threshold: -28.8 LUFS
+
+  Loudness range:
+    LRA:         1.3 LU
+    Threshold: -38.7 LUFS
+    LRA low:   -19.5 LUFS
+    LRA high:  -18.2 LUFS
+
+  True peak:
+    Peak:       -2.9 dBFS
+";
+
+    #[test]
+    fn parse_ebur128_summary_reads_final_block() {
+        let info = parse_ebur128_summary(EBUR128_STDERR).unwrap();
+        assert_eq!(info.integrated_lufs, -18.6);
+        assert_eq!(info.loudness_range_lu, Some(1.3));
+        assert_eq!(info.true_peak_dbtp, Some(-2.9));
+    }
+
+    #[test]
+    fn parse_ebur128_summary_requires_integrated_loudness() {
+        assert_eq!(parse_ebur128_summary("no summary here"), None);
+        assert_eq!(parse_ebur128_summary("Summary:\n  LRA: 1.0 LU\n"), None);
+    }
+
+    #[test]
+    fn measure_args_run_ebur128_on_first_audio_stream() {
+        let args = build_measure_args("/a.mka");
+        let af_idx = args.iter().position(|a| a == "-af").unwrap();
+        assert_eq!(args[af_idx + 1], "ebur128=peak=true");
+        assert!(args.contains(&"0:a:0".to_string()));
+    }
+}
@@ -0,0 +1,46 @@
+//! Periodic cleanup while the app runs.
+//!
+//! Startup removes temp files left by earlier runs and exit clears the caches, but a session
+//! that stays open for days would otherwise keep expired outputs, dead cache entries and
+//! orphaned temp files (from crashed FFmpeg runs, say) until it quits.
+
+use std::time::Duration;
+
+use super::cache::enforce_cache_quotas;
+use super::temp::{expire_pending_outputs, sweep_stale_temp_files};
+
+/// Time between maintenance passes.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Temp files untouched for this long, and not in use, are deleted while the app runs.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// One pass: expires uncommitted outputs past their TTL, trims the preview cache and deletes
+/// stale temp files. Blocking; does file I/O.
+pub fn run_maintenance() {
+    expire_pending_outputs();
+    let dropped = enforce_cache_quotas();
+    let removed = sweep_stale_temp_files(STALE_TEMP_AGE);
+    log::debug!(
+        target: "tiny_vid::ffmpeg::maintenance",
+        "run_maintenance: dropped {} cache entries, removed {} temp files",
+        dropped,
+        removed
+    );
+}
+
+/// Runs [`run_maintenance`] every [`MAINTENANCE_INTERVAL`] for as long as the app runs.
+pub async fn maintenance_loop() {
+    let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+    // The first tick is immediate; startup has just cleaned up.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = tauri::async_runtime::spawn_blocking(run_maintenance).await {
+            log::warn!(
+                target: "tiny_vid::ffmpeg::maintenance",
+                "maintenance_loop: pass failed: {}",
+                e
+            );
+        }
+    }
+}
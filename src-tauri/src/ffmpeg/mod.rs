@@ -1,37 +1,185 @@
 mod builder;
 mod cache;
+mod cancel;
+pub mod compose;
+pub mod compression;
+pub mod contact_sheet;
+mod context;
+pub mod dead_frames;
 pub mod discovery;
+mod energy;
 mod error;
+mod estimate_sampling;
+mod extra_args;
+mod fake;
 pub mod ffprobe;
+pub mod hw_probe;
+pub mod integrity;
+mod live_stderr;
+pub mod loudness;
+pub mod maintenance;
 mod progress;
+pub mod quality;
+pub mod quality_search;
+pub mod repair;
 mod runner;
+mod runner_async;
+pub mod self_test;
+pub mod silence;
+pub mod storyboard;
+pub mod suggest;
 mod temp;
-mod verify;
+pub mod updater;
+pub(crate) mod verify;
 
 pub use builder::{
-    build_extract_args, build_ffmpeg_command, build_first_frame_args,
-    build_two_pass_ffmpeg_commands, format_args_for_display_multiline,
-    is_preview_stream_copy_safe_codec, supports_two_pass_codec,
+    HOVER_PREVIEW_SECONDS, build_audio_preview_args, build_cover_image_args,
+    build_embed_cover_args, build_extract_args, build_ffmpeg_command, build_first_frame_args,
+    build_hover_preview_args, build_pip_compose_args, build_remux_args, build_thumbnail_args,
+    build_two_pass_ffmpeg_commands, build_video_only_ffmpeg_command, estimated_audio_kbps,
+    format_args_for_display_multiline, is_preview_stream_copy_safe_codec, supports_remux,
+    supports_two_pass_codec,
 };
 pub use error::{FfmpegErrorPayload, parse_ffmpeg_error};
+pub(crate) use extra_args::validate_extra_args;
+
+use crate::codec::GIF_CODEC;
+use crate::events::Versioned;
 
 /// Progress payload for ffmpeg-progress events.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FfmpegProgressPayload {
     pub progress: f64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub step: Option<String>,
+    /// Version 1 sent the bare stage id here; it reads as a single-stage `ProgressStep`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::events::progress_step"
+    )]
+    pub step: Option<ProgressStep>,
+    /// `step` translated for display (see `set_locale`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_label: Option<String>,
+}
+
+impl FfmpegProgressPayload {
+    pub fn new(progress: f64, step: Option<ProgressStep>) -> Self {
+        let step_label = step
+            .as_ref()
+            .and_then(|s| crate::i18n::step_label(&s.stage));
+        Self {
+            progress,
+            step,
+            step_label,
+        }
+    }
+}
+
+/// Stage of a multi-step job, so frontends (and screen readers) can announce "step 2 of 3".
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressStep {
+    /// Stable stage id (e.g. `preview_extract`, `transcode_pass2`).
+    pub stage: String,
+    /// 0-based position of this stage among `total`.
+    pub index: u32,
+    pub total: u32,
+    /// Translation key for the stage's display label (`step.<stage>`).
+    pub human_key: String,
+}
+
+impl ProgressStep {
+    pub fn new(stage: &str, index: usize, total: usize) -> Self {
+        Self {
+            stage: stage.to_string(),
+            index: index as u32,
+            total: total.max(index + 1) as u32,
+            human_key: format!("step.{}", stage),
+        }
+    }
+}
+
+/// Per-invocation progress channel. Commands that accept one send progress there instead of
+/// emitting ffmpeg-progress, so concurrent operations in one window stay apart.
+pub type ProgressChannel = tauri::ipc::Channel<Versioned<FfmpegProgressPayload>>;
+
+/// Where one operation's progress goes: ffmpeg-progress events to a window, or its own channel.
+#[derive(Clone)]
+pub enum ProgressSink {
+    Window {
+        app: tauri::AppHandle,
+        label: String,
+    },
+    Channel(ProgressChannel),
+}
+
+impl ProgressSink {
+    /// The invocation's channel when it passed one, otherwise the window.
+    pub fn new(app: &tauri::AppHandle, label: &str, channel: Option<ProgressChannel>) -> Self {
+        match channel {
+            Some(channel) => Self::Channel(channel),
+            None => Self::Window {
+                app: app.clone(),
+                label: label.to_string(),
+            },
+        }
+    }
+
+    pub fn send(&self, payload: FfmpegProgressPayload) {
+        use tauri::Emitter;
+        let payload = Versioned::new(payload);
+        match self {
+            Self::Window { app, label } => {
+                let _ = app.emit_to(label.as_str(), "ffmpeg-progress", payload);
+            }
+            Self::Channel(channel) => {
+                let _ = channel.send(payload);
+            }
+        }
+    }
+}
+
+/// Payload for ffmpeg-salvage-report events, emitted after an `ignore_errors` transcode.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SalvageReport {
+    pub source_seconds: f64,
+    pub salvaged_seconds: f64,
+    pub lost_seconds: f64,
+}
+
+impl SalvageReport {
+    pub fn new(source_seconds: f64, salvaged_seconds: f64) -> Self {
+        let salvaged_seconds = salvaged_seconds.clamp(0.0, source_seconds.max(0.0));
+        Self {
+            source_seconds,
+            salvaged_seconds,
+            lost_seconds: (source_seconds - salvaged_seconds).max(0.0),
+        }
+    }
 }
 pub use cache::{
     FileSignature, cleanup_preview_transcode_cache, file_signature, get_all_cached_paths,
-    get_cached_estimate, get_cached_preview, get_cached_segments, set_cached_estimate,
-    set_cached_preview,
+    get_cached_estimate, get_cached_hover_preview, get_cached_preview, get_cached_segments,
+    set_cached_estimate, set_cached_hover_preview, set_cached_preview,
+};
+pub use cancel::{CancellationToken, JobCancellation, cancel_job, cancel_request};
+pub use context::FfmpegContext;
+pub use energy::{CpuMeter, JobEnergy};
+pub use estimate_sampling::{EstimateSampling, estimate_sampling, set_estimate_sampling};
+pub use live_stderr::{LiveStderr, get_live_stderr};
+pub use progress::{ProgressThrottle, progress_throttle, set_progress_throttle};
+pub use runner::{run_ffmpeg_blocking, run_ffmpeg_capture, terminate_all_ffmpeg};
+pub(crate) use runner_async::run_two_pass;
+pub use runner_async::{
+    run_ffmpeg_async, transcode_async, transcode_from_reader, transcode_to_writer,
 };
-pub use runner::{run_ffmpeg_blocking, terminate_all_ffmpeg};
 pub use temp::{
-    TempFileManager, cleanup_old_temp_files, cleanup_previous_preview_paths,
-    cleanup_transcode_temp, set_transcode_temp, store_preview_paths_for_cleanup,
+    TempFileManager, TempJob, check_first_pass_log, cleanup_old_temp_files, cleanup_passlog_files,
+    cleanup_previous_preview_paths, cleanup_transcode_temp, commit_transcode_temp,
+    discard_transcode_temp, ensure_not_job_source, record_job_sources, register_transcode_temp,
+    store_preview_paths_for_cleanup, transcode_temp_for_token, transcode_temp_job,
 };
 #[cfg(any(test, feature = "integration-test-api"))]
 pub use verify::verify_video;
@@ -40,7 +188,7 @@ use serde::{Deserialize, Serialize};
 use crate::error::AppError;
 
 /// Version token for estimate cache key invalidation.
-pub const ESTIMATE_CACHE_VERSION: &str = "estimate-sampled-bitrate";
+pub const ESTIMATE_CACHE_VERSION: &str = "estimate-sampled-bitrate-2";
 
 /// Confidence bucket for size estimate range.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -71,7 +219,46 @@ pub enum RateControlMode {
     TargetSize,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Kind of source material; switches encoder defaults suited to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentType {
+    General,
+    ScreenRecording,
+}
+
+/// What x264/x265 should spend bits on: the middle of the frame (talking heads, gameplay) or
+/// sharp edges such as small text in screen recordings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RoiPriority {
+    Center,
+    Text,
+}
+
+/// How `background_audio_path` combines with the source audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackgroundAudioMode {
+    Replace,
+    Mix,
+}
+
+/// What an export does when the encoded file is not smaller than its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LargerOutputPolicy {
+    /// Keep the encode and report a warning.
+    #[default]
+    Warn,
+    /// Replace the encode with a stream copy of the source when the options allow one (see
+    /// `build_remux_args`); otherwise keep it and warn.
+    Remux,
+    /// Fail the export.
+    Refuse,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscodeOptions {
     pub codec: Option<String>,
@@ -85,11 +272,15 @@ pub struct TranscodeOptions {
     pub output_format: Option<String>,
     pub rate_control_mode: Option<RateControlMode>,
     pub target_size_mb: Option<f64>,
+    /// Export only, target size mode: hit the target by searching for a quality on sampled
+    /// encodes instead of a two-pass bitrate encode (see `quality_search`). Default false.
+    pub quality_search: Option<bool>,
     pub preview_duration: Option<u32>,
     pub duration_secs: Option<f64>,
-    /// Include all audio streams in output (transcoded to AAC/Opus). Default false.
-    pub preserve_additional_audio_streams: Option<bool>,
-    /// From metadata; used when preserve_additional_audio_streams. Default 1.
+    /// Audio tracks to keep, as 0-based indices among the input's audio streams, in output
+    /// order. None keeps the first track only; an empty list keeps none.
+    pub audio_stream_indices: Option<Vec<u32>>,
+    /// From metadata; indices in audio_stream_indices at or past it are dropped.
     pub audio_stream_count: Option<u32>,
     /// Copy input metadata (title, creation date, etc.) to output via -map_metadata 0. Default false.
     pub preserve_metadata: Option<bool>,
@@ -105,6 +296,85 @@ pub struct TranscodeOptions {
     pub audio_codec_name: Option<String>,
     /// From metadata; first audio stream channel count.
     pub audio_channels: Option<u32>,
+    /// From metadata; first audio stream bit rate (bits/s). Sizes passthrough audio in estimates.
+    pub source_audio_bit_rate: Option<u64>,
+    /// Tolerate decode errors in partially corrupted sources (interrupted downloads, bad SD
+    /// cards) by discarding broken packets instead of aborting. Default false.
+    pub ignore_errors: Option<bool>,
+    /// Image to embed as cover art (attached_pic) in MP4/MKV exports. Takes precedence over
+    /// cover_frame_seconds.
+    pub cover_image_path: Option<String>,
+    /// Source frame (seconds) to embed as cover art in MP4/MKV exports.
+    pub cover_frame_seconds: Option<f64>,
+    /// From metadata; real video streams (cover art excluded). When > 1 the main stream is
+    /// mapped explicitly instead of letting FFmpeg pick one.
+    pub video_stream_count: Option<u32>,
+    /// Video stream to encode (`-map 0:v:N`). Default 0, or the metadata's main stream.
+    pub video_stream_index: Option<u32>,
+    /// External audio file (e.g. music) that replaces or is mixed into the source audio.
+    /// Looped or cut to the video length. Export only; previews stay silent.
+    pub background_audio_path: Option<String>,
+    /// Default replace. Mix falls back to replace when the source has no audio or audio is removed.
+    pub background_audio_mode: Option<BackgroundAudioMode>,
+    /// Gain applied to the background audio in dB. Default 0 (replace) or -15 (mix).
+    pub background_audio_gain_db: Option<f64>,
+    /// Mix only: lower the background audio while the source audio is loud. Default false.
+    pub background_audio_ducking: Option<bool>,
+    /// Source ranges `[start, end]` (seconds) to keep, e.g. from silence analysis; everything
+    /// else is cut. Export only. Subtitles are dropped since their timings no longer match.
+    pub keep_segments: Option<Vec<(f64, f64)>>,
+    /// Crop rectangle in source pixels, applied before scaling. Width and height are needed;
+    /// x/y default to 0. Odd sizes are rounded down to even for 4:2:0 output.
+    pub crop_x: Option<u32>,
+    pub crop_y: Option<u32>,
+    pub crop_width: Option<u32>,
+    pub crop_height: Option<u32>,
+    /// Clockwise rotation (90/180/270) on top of the source's own orientation, which FFmpeg
+    /// already applies when decoding. Crop coordinates are in the upright source.
+    pub rotation: Option<u32>,
+    pub flip_horizontal: Option<bool>,
+    pub flip_vertical: Option<bool>,
+    /// Export only: source time (seconds) to start the output at. Input seek, so fast.
+    pub trim_start_secs: Option<f64>,
+    /// Export only: source time (seconds) to end the output at. Unset runs to the end.
+    pub trim_end_secs: Option<f64>,
+    /// x264 (0-3) / x265 (0-4) adaptive quantization mode. Encoder default when unset.
+    pub aq_mode: Option<u32>,
+    /// x264/x265 adaptive quantization strength (0.0-3.0). Encoder default when unset.
+    pub aq_strength: Option<f64>,
+    /// Region-of-interest hint for x264/x265. Explicit aq_mode/aq_strength take precedence.
+    pub roi_priority: Option<RoiPriority>,
+    /// Screen recordings get x264 `stillimage` (unless a tune is chosen), a 10 s keyframe
+    /// interval, limited-range 4:2:0 output and up to 60fps kept. Default general.
+    pub content_type: Option<ContentType>,
+    /// From metadata; source frame rate, used to keep 50-60fps screen recordings smooth.
+    pub source_fps: Option<f64>,
+    /// Preview only: cap (in lines of the shorter side, e.g. 1080) for the compressed preview
+    /// and estimate samples. The estimate is corrected back to full resolution.
+    pub preview_resolution_cap: Option<u32>,
+    /// Preview only: encoder for the original preview when it cannot be stream-copied. Unset
+    /// picks the fastest working H.264 encoder.
+    pub preview_codec: Option<String>,
+    /// Preview only: report SSIM and PSNR of the compressed preview against the original
+    /// preview. Costs one more decode of both. Default false.
+    pub preview_quality_metrics: Option<bool>,
+    /// Estimate only: sampling for this call instead of the app-wide `set_estimate_sampling`.
+    pub estimate_sampling: Option<EstimateSampling>,
+    /// Export only: handling of outputs that are not smaller than the source. Default warn.
+    pub larger_output: Option<LargerOutputPolicy>,
+    /// Bit-identical output for the same input and options: pinned encoder threads, bitexact
+    /// muxing (no encoder tag or creation time) and no source metadata unless preserved.
+    /// Software encoders only; hardware encoders give no such guarantee. Default false.
+    pub reproducible: Option<bool>,
+    /// Export only: score the finished output with VMAF on a few sampled windows (see
+    /// `quality::score_export_vmaf`). Needs an ffmpeg with libvmaf. Default false.
+    pub vmaf_check: Option<bool>,
+    /// Extra encoder flags as flag/value pairs (e.g. `-g 120`), placed after the app's own
+    /// video options so they win. Allowlisted flags only (see `extra_args`); not applied to GIF.
+    pub extra_video_args: Option<Vec<String>>,
+    /// Extra muxer flags as flag/value pairs (e.g. `-metadata title=Clip`). Allowlisted flags
+    /// only; not applied to GIF.
+    pub extra_output_args: Option<Vec<String>>,
 }
 
 impl Default for TranscodeOptions {
@@ -121,9 +391,10 @@ impl Default for TranscodeOptions {
             output_format: Some("mp4".to_string()),
             rate_control_mode: Some(RateControlMode::Quality),
             target_size_mb: None,
+            quality_search: None,
             preview_duration: Some(3),
             duration_secs: None,
-            preserve_additional_audio_streams: None,
+            audio_stream_indices: None,
             audio_stream_count: None,
             preserve_metadata: None,
             audio_bitrate: None,
@@ -132,12 +403,50 @@ impl Default for TranscodeOptions {
             subtitle_stream_count: None,
             audio_codec_name: None,
             audio_channels: None,
+            source_audio_bit_rate: None,
+            ignore_errors: None,
+            cover_image_path: None,
+            cover_frame_seconds: None,
+            video_stream_count: None,
+            video_stream_index: None,
+            background_audio_path: None,
+            background_audio_mode: None,
+            background_audio_gain_db: None,
+            background_audio_ducking: None,
+            keep_segments: None,
+            crop_x: None,
+            crop_y: None,
+            crop_width: None,
+            crop_height: None,
+            rotation: None,
+            flip_horizontal: None,
+            flip_vertical: None,
+            trim_start_secs: None,
+            trim_end_secs: None,
+            aq_mode: None,
+            aq_strength: None,
+            roi_priority: None,
+            content_type: None,
+            source_fps: None,
+            preview_resolution_cap: None,
+            preview_codec: None,
+            preview_quality_metrics: None,
+            estimate_sampling: None,
+            larger_output: None,
+            reproducible: None,
+            vmaf_check: None,
+            extra_video_args: None,
+            extra_output_args: None,
         }
     }
 }
 
 impl TranscodeOptions {
+    /// The encoder in use: [`GIF_CODEC`] for every GIF, whatever `codec` says.
     pub fn effective_codec(&self) -> &str {
+        if self.is_gif() {
+            return GIF_CODEC;
+        }
         self.codec.as_deref().unwrap_or("libx264")
     }
 
@@ -150,12 +459,26 @@ impl TranscodeOptions {
     }
 
     pub fn effective_fps(&self) -> f64 {
-        let fps = self.fps.unwrap_or(30.0);
+        let mut fps = self.fps.unwrap_or(30.0);
+        if self.is_screen_recording()
+            && let Some(source) = self.source_fps.filter(|f| f.is_finite() && *f >= 50.0)
+        {
+            fps = fps.max(source.min(60.0));
+        }
+        if self.is_gif() {
+            fps = fps.min(builder::GIF_MAX_FPS);
+        }
         (fps * 100.0).round() / 100.0
     }
 
+    /// True when audio is removed outright, no selected audio track is left, or the output is
+    /// a GIF.
     pub fn effective_remove_audio(&self) -> bool {
-        self.remove_audio.unwrap_or(false)
+        self.is_gif()
+            || self.remove_audio.unwrap_or(false)
+            || self
+                .effective_audio_stream_indices()
+                .is_some_and(|tracks| tracks.is_empty())
     }
 
     pub fn effective_preset(&self) -> &str {
@@ -168,37 +491,128 @@ impl TranscodeOptions {
             .filter(|t| !t.is_empty() && *t != "none")
     }
 
+    /// Output container. The GIF encoder only writes GIF, so it decides the format.
     pub fn effective_output_format(&self) -> String {
+        if self.codec.as_deref() == Some(GIF_CODEC) {
+            return GIF_CODEC.to_string();
+        }
         self.output_format
             .as_deref()
             .unwrap_or("mp4")
             .to_lowercase()
     }
 
+    /// GIF has no bitrate control, so it is always encoded for quality.
     pub fn effective_rate_control_mode(&self) -> RateControlMode {
+        if self.is_gif() {
+            return RateControlMode::Quality;
+        }
         self.rate_control_mode.unwrap_or(RateControlMode::Quality)
     }
 
+    pub fn effective_larger_output(&self) -> LargerOutputPolicy {
+        self.larger_output.unwrap_or_default()
+    }
+
+    /// Animated GIF output: palette-based, silent, and independent of `codec`. Paths that
+    /// depend on the codec (cache keys, two-pass, estimates) see [`GIF_CODEC`] instead.
+    pub fn is_gif(&self) -> bool {
+        self.effective_output_format() == "gif"
+    }
+
     pub fn effective_target_size_mb(&self) -> Option<f64> {
         self.target_size_mb
     }
 
+    pub fn effective_quality_search(&self) -> bool {
+        self.quality_search == Some(true)
+            && self.effective_rate_control_mode() == RateControlMode::TargetSize
+    }
+
+    /// Target-size encodes with a codec that takes a pass log run an analysis pass first.
+    pub fn uses_two_pass(&self) -> bool {
+        self.effective_rate_control_mode() == RateControlMode::TargetSize
+            && supports_two_pass_codec(self.effective_codec())
+    }
+
     pub fn effective_preview_duration(&self) -> u32 {
         self.preview_duration.unwrap_or(3)
     }
 
-    pub fn effective_preserve_additional_audio_streams(&self) -> bool {
-        self.preserve_additional_audio_streams.unwrap_or(false)
+    /// `audio_stream_indices` without repeats and without tracks the input does not have.
+    pub fn effective_audio_stream_indices(&self) -> Option<Vec<u32>> {
+        let indices = self.audio_stream_indices.as_ref()?;
+        let mut tracks = Vec::with_capacity(indices.len());
+        for &i in indices {
+            if self.audio_stream_count.is_none_or(|count| i < count) && !tracks.contains(&i) {
+                tracks.push(i);
+            }
+        }
+        Some(tracks)
+    }
+
+    /// Source audio track previews stand in for: the first one the export keeps. None when the
+    /// export keeps none (audio removed or an empty selection).
+    pub fn preview_audio_track(&self) -> Option<u32> {
+        if self.effective_remove_audio() {
+            return None;
+        }
+        match self.effective_audio_stream_indices() {
+            Some(tracks) => tracks.first().copied(),
+            None => Some(0),
+        }
     }
 
-    pub fn effective_audio_stream_count(&self) -> u32 {
-        self.audio_stream_count.unwrap_or(1).max(1)
+    /// True when the selection is anything but the default first track.
+    pub fn selects_audio_tracks(&self) -> bool {
+        self.effective_audio_stream_indices()
+            .is_some_and(|tracks| tracks != [0])
     }
 
     pub fn effective_preserve_metadata(&self) -> bool {
         self.preserve_metadata.unwrap_or(false)
     }
 
+    pub fn effective_ignore_errors(&self) -> bool {
+        self.ignore_errors.unwrap_or(false)
+    }
+
+    pub fn effective_reproducible(&self) -> bool {
+        self.reproducible.unwrap_or(false)
+    }
+
+    pub fn effective_extra_video_args(&self) -> &[String] {
+        self.extra_video_args.as_deref().unwrap_or_default()
+    }
+
+    pub fn effective_extra_output_args(&self) -> &[String] {
+        self.extra_output_args.as_deref().unwrap_or_default()
+    }
+
+    pub fn effective_estimate_sampling(&self) -> EstimateSampling {
+        self.estimate_sampling
+            .clone()
+            .unwrap_or_else(estimate_sampling)
+    }
+
+    pub fn effective_preview_quality_metrics(&self) -> bool {
+        self.preview_quality_metrics.unwrap_or(false)
+    }
+
+    pub fn effective_vmaf_check(&self) -> bool {
+        self.vmaf_check.unwrap_or(false)
+    }
+
+    /// Cover art source: `(path, seek)` where seek is None for a still image.
+    pub fn cover_art_source<'a>(&'a self, input_path: &'a str) -> Option<(&'a str, Option<f64>)> {
+        if let Some(image) = self.cover_image_path.as_deref().filter(|p| !p.is_empty()) {
+            return Some((image, None));
+        }
+        self.cover_frame_seconds
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(|s| (input_path, Some(s)))
+    }
+
     pub fn effective_audio_bitrate(&self) -> u32 {
         self.audio_bitrate.unwrap_or(128).clamp(64, 320)
     }
@@ -215,6 +629,143 @@ impl TranscodeOptions {
         self.subtitle_stream_count.unwrap_or(0)
     }
 
+    pub fn effective_video_stream_index(&self) -> u32 {
+        self.video_stream_index.unwrap_or(0)
+    }
+
+    /// True when the video stream must be mapped explicitly (a selector or several streams).
+    pub fn selects_video_stream(&self) -> bool {
+        self.video_stream_index.is_some() || self.video_stream_count.is_some_and(|n| n > 1)
+    }
+
+    /// Background audio path and the mode actually used, when one is set. Mixing needs a kept
+    /// source track (`preview_audio_track`); without one the music replaces the audio.
+    pub fn background_audio(&self) -> Option<(&str, BackgroundAudioMode)> {
+        if self.is_gif() {
+            return None;
+        }
+        let path = self
+            .background_audio_path
+            .as_deref()
+            .filter(|p| !p.is_empty())?;
+        let source_silent =
+            self.preview_audio_track().is_none() || self.audio_stream_count == Some(0);
+        let mode = match self.background_audio_mode {
+            Some(BackgroundAudioMode::Mix) if !source_silent => BackgroundAudioMode::Mix,
+            _ => BackgroundAudioMode::Replace,
+        };
+        Some((path, mode))
+    }
+
+    pub fn effective_background_audio_gain_db(&self, mode: BackgroundAudioMode) -> f64 {
+        self.background_audio_gain_db
+            .filter(|g| g.is_finite())
+            .unwrap_or(match mode {
+                BackgroundAudioMode::Replace => 0.0,
+                BackgroundAudioMode::Mix => -15.0,
+            })
+            .clamp(-60.0, 20.0)
+    }
+
+    pub fn effective_background_audio_ducking(&self) -> bool {
+        self.background_audio_ducking.unwrap_or(false)
+    }
+
+    /// Valid (finite, non-empty) keep ranges, or None when nothing is cut.
+    pub fn effective_keep_segments(&self) -> Option<Vec<(f64, f64)>> {
+        let segments: Vec<(f64, f64)> = self
+            .keep_segments
+            .as_deref()?
+            .iter()
+            .copied()
+            .filter(|(s, e)| s.is_finite() && e.is_finite() && *s >= 0.0 && e > s)
+            .collect();
+        (!segments.is_empty()).then_some(segments)
+    }
+
+    /// Crop as (width, height, x, y) with even width/height, or None when not cropping.
+    pub fn effective_crop(&self) -> Option<(u32, u32, u32, u32)> {
+        let width = self.crop_width? & !1;
+        let height = self.crop_height? & !1;
+        (width > 0 && height > 0).then(|| {
+            (
+                width,
+                height,
+                self.crop_x.unwrap_or(0),
+                self.crop_y.unwrap_or(0),
+            )
+        })
+    }
+
+    /// `transpose`/`hflip`/`vflip` filters for the requested rotation and flips, or None.
+    pub fn orientation_filter(&self) -> Option<String> {
+        let mut filters: Vec<&str> = match self.rotation.map(|r| r % 360) {
+            Some(90) => vec!["transpose=clock"],
+            Some(180) => vec!["hflip", "vflip"],
+            Some(270) => vec!["transpose=cclock"],
+            _ => Vec::new(),
+        };
+        if self.flip_horizontal.unwrap_or(false) {
+            filters.push("hflip");
+        }
+        if self.flip_vertical.unwrap_or(false) {
+            filters.push("vflip");
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+
+    /// Valid trim range as (start, optional end), or None when nothing is trimmed.
+    pub fn effective_trim(&self) -> Option<(f64, Option<f64>)> {
+        let start = self
+            .trim_start_secs
+            .filter(|s| s.is_finite() && *s > 0.0)
+            .unwrap_or(0.0);
+        let end = self.trim_end_secs.filter(|e| e.is_finite() && *e > start);
+        (start > 0.0 || end.is_some()).then_some((start, end))
+    }
+
+    /// Length of the export: the kept ranges when cutting, the trim range when trimming, else
+    /// `duration_secs`.
+    pub fn output_duration_secs(&self) -> Option<f64> {
+        if let Some(segments) = self.effective_keep_segments() {
+            return Some(segments.iter().map(|(s, e)| e - s).sum());
+        }
+        match self.effective_trim() {
+            Some((start, end)) => {
+                let end = match (end, self.duration_secs) {
+                    (Some(end), Some(duration)) => end.min(duration),
+                    (end, duration) => end.or(duration)?,
+                };
+                Some((end - start).max(0.0))
+            }
+            None => self.duration_secs,
+        }
+    }
+
+    pub fn is_screen_recording(&self) -> bool {
+        self.content_type == Some(ContentType::ScreenRecording)
+    }
+
+    pub fn effective_aq_strength(&self) -> Option<f64> {
+        self.aq_strength
+            .filter(|s| s.is_finite())
+            .map(|s| s.clamp(0.0, 3.0))
+    }
+
+    fn background_audio_cache_key(&self) -> String {
+        self.background_audio()
+            .map(|(path, mode)| {
+                format!(
+                    "{}:{:?}:{}:{}",
+                    path,
+                    mode,
+                    self.effective_background_audio_gain_db(mode),
+                    self.effective_background_audio_ducking()
+                )
+            })
+            .unwrap_or_default()
+    }
+
     /// Cache key for full transcode (excludes duration_secs).
     pub fn options_cache_key(&self) -> String {
         format!(
@@ -239,13 +790,47 @@ impl TranscodeOptions {
         )
     }
 
+    /// Cache key for the sampled video part of an estimate. In quality mode audio settings are
+    /// left out, so changing only audio reuses the cached video samples; in target-size mode the
+    /// audio budget decides the video bitrate, so they stay in.
+    pub fn options_cache_key_for_video_estimate(&self) -> String {
+        // Capped previews sample at a lower resolution and correct for it.
+        let cap = self.preview_resolution_cap.unwrap_or(0);
+        let sampling = self.effective_estimate_sampling().cache_key();
+        if self.effective_rate_control_mode() == RateControlMode::TargetSize {
+            return format!(
+                "{}|{}|{}",
+                self.options_cache_key_for_estimate(),
+                cap,
+                sampling
+            );
+        }
+        let key = Self {
+            remove_audio: Some(true),
+            audio_stream_indices: None,
+            audio_stream_count: None,
+            audio_bitrate: None,
+            downmix_to_stereo: None,
+            audio_codec_name: None,
+            audio_channels: None,
+            source_audio_bit_rate: None,
+            background_audio_path: None,
+            background_audio_mode: None,
+            background_audio_gain_db: None,
+            background_audio_ducking: None,
+            ..self.clone()
+        }
+        .options_cache_key_for_estimate();
+        format!("{}|{}|{}", key, cap, sampling)
+    }
+
     fn options_cache_key_common(&self) -> String {
         let rate_control_mode = match self.effective_rate_control_mode() {
             RateControlMode::Quality => "quality",
             RateControlMode::TargetSize => "targetSize",
         };
         format!(
-            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{:?}:{:?}:{:?}|{}|{}|{}|{}",
             self.effective_codec(),
             self.effective_quality(),
             self.max_bitrate
@@ -262,14 +847,48 @@ impl TranscodeOptions {
                 .map(|v| format!("{:.4}", v))
                 .as_deref()
                 .unwrap_or(""),
-            self.effective_preserve_additional_audio_streams(),
-            self.effective_audio_stream_count(),
+            self.effective_audio_stream_indices()
+                .map(|tracks| {
+                    tracks
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_default(),
+            self.audio_stream_count.unwrap_or(1),
             self.effective_preserve_metadata(),
             self.effective_audio_bitrate(),
             self.effective_downmix_to_stereo(),
             self.effective_preserve_subtitles(),
             self.effective_subtitle_stream_count(),
             self.audio_codec_name.as_deref().unwrap_or(""),
+            self.effective_ignore_errors(),
+            self.effective_video_stream_index(),
+            self.background_audio_cache_key(),
+            self.effective_keep_segments()
+                .map(|segments| {
+                    segments
+                        .iter()
+                        .map(|(s, e)| format!("{:.3}-{:.3}", s, e))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_default(),
+            self.effective_trim()
+                .map(|(start, end)| format!("{:.3}-{:?}", start, end))
+                .unwrap_or_default(),
+            self.effective_crop()
+                .map(|(w, h, x, y)| format!("{}x{}+{}+{}", w, h, x, y))
+                .unwrap_or_default(),
+            self.orientation_filter().unwrap_or_default(),
+            self.aq_mode,
+            self.effective_aq_strength(),
+            self.roi_priority,
+            self.is_screen_recording(),
+            self.effective_reproducible(),
+            self.effective_extra_video_args().join(" "),
+            self.effective_extra_output_args().join(" "),
         )
     }
 }
@@ -285,20 +904,22 @@ pub fn compute_target_video_bitrate_kbps(options: &TranscodeOptions) -> Result<u
         .filter(|v| v.is_finite() && *v > 0.0)
         .ok_or_else(|| AppError::from("Target size must be greater than zero"))?;
     let duration_secs = options
-        .duration_secs
+        .output_duration_secs()
         .filter(|v| v.is_finite() && *v > 0.0)
         .ok_or_else(|| AppError::from("Video duration is required for target size mode"))?;
 
-    let audio_streams = if options.effective_remove_audio() {
+    let audio_streams = if options.background_audio().is_some() {
+        1
+    } else if options.effective_remove_audio() {
         0
     } else {
         let count = options.audio_stream_count.unwrap_or(1);
         if count == 0 {
             0
-        } else if options.effective_preserve_additional_audio_streams() {
-            count
         } else {
-            1
+            options
+                .effective_audio_stream_indices()
+                .map_or(1, |tracks| tracks.len() as u32)
         }
     } as f64;
 
@@ -327,10 +948,24 @@ pub fn path_to_string(path: &(impl AsRef<std::path::Path> + ?Sized)) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        ESTIMATE_CACHE_VERSION, RateControlMode, TranscodeOptions,
-        compute_target_video_bitrate_kbps,
+        ESTIMATE_CACHE_VERSION, FfmpegProgressPayload, ProgressStep, RateControlMode,
+        SalvageReport, TranscodeOptions, compute_target_video_bitrate_kbps,
     };
 
+    #[test]
+    fn progress_step_serializes_structured_stage() {
+        let payload =
+            FfmpegProgressPayload::new(0.5, Some(ProgressStep::new("preview_extract", 0, 2)));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["step"]["stage"], "preview_extract");
+        assert_eq!(json["step"]["index"], 0);
+        assert_eq!(json["step"]["total"], 2);
+        assert_eq!(json["step"]["humanKey"], "step.preview_extract");
+
+        let json = serde_json::to_value(FfmpegProgressPayload::new(0.1, None)).unwrap();
+        assert!(json.get("step").is_none());
+    }
+
     #[test]
     fn estimate_cache_key_includes_output_format() {
         let mut opts_a = TranscodeOptions::default();
@@ -344,6 +979,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn crop_rounds_to_even_and_changes_cache_key() {
+        let mut o = TranscodeOptions {
+            crop_width: Some(641),
+            crop_height: Some(361),
+            crop_x: Some(10),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(o.effective_crop(), Some((640, 360, 10, 0)));
+        assert_ne!(
+            o.options_cache_key_for_estimate(),
+            TranscodeOptions::default().options_cache_key_for_estimate()
+        );
+        o.crop_height = Some(1);
+        assert_eq!(o.effective_crop(), None);
+        o.crop_height = None;
+        assert_eq!(o.effective_crop(), None);
+    }
+
+    #[test]
+    fn trim_shortens_output_duration() {
+        let mut o = TranscodeOptions {
+            duration_secs: Some(60.0),
+            trim_start_secs: Some(5.0),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(o.output_duration_secs(), Some(55.0));
+        o.trim_end_secs = Some(20.0);
+        assert_eq!(o.output_duration_secs(), Some(15.0));
+        o.trim_end_secs = Some(90.0);
+        assert_eq!(o.output_duration_secs(), Some(55.0));
+        o.trim_end_secs = Some(3.0);
+        assert_eq!(o.effective_trim(), Some((5.0, None)));
+        o.trim_start_secs = Some(0.0);
+        assert_eq!(o.effective_trim(), Some((0.0, Some(3.0))));
+        o.trim_end_secs = None;
+        assert_eq!(o.effective_trim(), None);
+        assert_eq!(o.output_duration_secs(), Some(60.0));
+    }
+
+    #[test]
+    fn preview_audio_track_is_the_first_exported_track() {
+        let mut o = TranscodeOptions::default();
+        assert_eq!(o.preview_audio_track(), Some(0));
+        o.audio_stream_count = Some(3);
+        o.audio_stream_indices = Some(vec![5, 2, 0]);
+        assert_eq!(o.preview_audio_track(), Some(2));
+        o.audio_stream_indices = Some(vec![]);
+        assert_eq!(o.preview_audio_track(), None);
+        o.audio_stream_indices = None;
+        o.remove_audio = Some(true);
+        assert_eq!(o.preview_audio_track(), None);
+    }
+
+    #[test]
+    fn video_estimate_key_ignores_audio_settings_in_quality_mode() {
+        let base = TranscodeOptions::default();
+        let audio_changed = TranscodeOptions {
+            audio_bitrate: Some(256),
+            downmix_to_stereo: Some(true),
+            remove_audio: Some(true),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(
+            base.options_cache_key_for_video_estimate(),
+            audio_changed.options_cache_key_for_video_estimate()
+        );
+        assert_ne!(
+            base.options_cache_key_for_estimate(),
+            audio_changed.options_cache_key_for_estimate()
+        );
+
+        let target = |audio_bitrate| TranscodeOptions {
+            rate_control_mode: Some(RateControlMode::TargetSize),
+            target_size_mb: Some(10.0),
+            audio_bitrate: Some(audio_bitrate),
+            ..TranscodeOptions::default()
+        };
+        assert_ne!(
+            target(128).options_cache_key_for_video_estimate(),
+            target(256).options_cache_key_for_video_estimate()
+        );
+
+        let capped = TranscodeOptions {
+            preview_resolution_cap: Some(1080),
+            ..TranscodeOptions::default()
+        };
+        assert_ne!(
+            base.options_cache_key_for_video_estimate(),
+            capped.options_cache_key_for_video_estimate()
+        );
+    }
+
     #[test]
     fn estimate_cache_key_is_versioned() {
         let opts = TranscodeOptions::default();
@@ -377,4 +1105,52 @@ mod tests {
         let result = compute_target_video_bitrate_kbps(&opts).unwrap();
         assert!(result >= 200);
     }
+
+    #[test]
+    fn uses_two_pass_only_for_target_size_with_pass_log_codecs() {
+        let mut opts = TranscodeOptions::default();
+        opts.rate_control_mode = Some(RateControlMode::Quality);
+        assert!(!opts.uses_two_pass());
+        opts.rate_control_mode = Some(RateControlMode::TargetSize);
+        assert!(opts.uses_two_pass());
+        opts.codec = Some("h264_videotoolbox".to_string());
+        assert!(!opts.uses_two_pass());
+    }
+
+    #[test]
+    fn gif_ignores_the_video_codec() {
+        let mut x264 = TranscodeOptions {
+            output_format: Some("gif".into()),
+            rate_control_mode: Some(RateControlMode::TargetSize),
+            ..TranscodeOptions::default()
+        };
+        let x265 = TranscodeOptions {
+            codec: Some("libx265".into()),
+            ..x264.clone()
+        };
+        assert_eq!(x264.effective_codec(), "gif");
+        assert!(!x264.uses_two_pass());
+        assert_eq!(x264.options_cache_key(), x265.options_cache_key());
+        assert_eq!(
+            x264.options_cache_key_for_estimate(),
+            x265.options_cache_key_for_estimate()
+        );
+
+        x264.output_format = None;
+        x264.codec = Some("gif".into());
+        assert!(x264.is_gif());
+        assert_eq!(x264.effective_output_format(), "gif");
+    }
+
+    #[test]
+    fn salvage_report_clamps_to_source_duration() {
+        let report = SalvageReport::new(60.0, 52.5);
+        assert_eq!(report.salvaged_seconds, 52.5);
+        assert_eq!(report.lost_seconds, 7.5);
+
+        // Regenerated timestamps can overshoot the container duration slightly.
+        let report = SalvageReport::new(60.0, 60.4);
+        assert_eq!(report.salvaged_seconds, 60.0);
+        assert_eq!(report.lost_seconds, 0.0);
+    }
 }
@@ -1,5 +1,9 @@
+use parking_lot::Mutex;
 use regex::Regex;
 use std::sync::LazyLock;
+use std::time::Duration;
+
+use crate::error::AppError;
 
 static DURATION_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"Duration: (\d+):(\d+):([\d.]+)").expect("invalid duration regex")
@@ -7,6 +11,85 @@ static DURATION_RE: LazyLock<Regex> = LazyLock::new(|| {
 static TIME_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"out_time_ms=(\d+)").expect("invalid time regex"));
 
+static THROTTLE: Mutex<ProgressThrottle> = Mutex::new(ProgressThrottle::DEFAULT);
+
+/// Rate limits for the progress events of one FFmpeg run. Very fast runs otherwise report a
+/// step for nearly every `-progress` line, flooding IPC and the webview. Completion always
+/// goes out.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressThrottle {
+    /// At most this many events per second; 0 lifts the limit.
+    pub max_events_per_sec: f64,
+    /// Smallest change in progress (0-1) since the last event worth another one.
+    pub min_delta: f64,
+}
+
+impl ProgressThrottle {
+    const DEFAULT: Self = Self {
+        max_events_per_sec: 8.0,
+        min_delta: 0.002,
+    };
+
+    fn validate(self) -> Result<Self, AppError> {
+        if !self.max_events_per_sec.is_finite() || self.max_events_per_sec < 0.0 {
+            return Err(AppError::from(
+                "maxEventsPerSec must be a non-negative number",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.min_delta) {
+            return Err(AppError::from("minDelta must be between 0 and 1"));
+        }
+        Ok(self)
+    }
+
+    fn min_interval(&self) -> Duration {
+        if self.max_events_per_sec > 0.0 {
+            Duration::from_secs_f64(1.0 / self.max_events_per_sec)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Whether `progress` is worth an event, `since_last` after the event that reported
+    /// `last_progress`.
+    pub(super) fn should_emit(
+        &self,
+        progress: f64,
+        last_progress: f64,
+        since_last: Duration,
+    ) -> bool {
+        if progress >= 1.0 {
+            return last_progress < 1.0;
+        }
+        since_last >= self.min_interval() && (progress - last_progress).abs() >= self.min_delta
+    }
+}
+
+impl Default for ProgressThrottle {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Limits applied to runs started from now on.
+pub fn progress_throttle() -> ProgressThrottle {
+    *THROTTLE.lock()
+}
+
+/// Validates and stores new limits; returns what was applied.
+pub fn set_progress_throttle(throttle: ProgressThrottle) -> Result<ProgressThrottle, AppError> {
+    let throttle = throttle.validate()?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::progress",
+        "set_progress_throttle: {}/s, min delta {}",
+        throttle.max_events_per_sec,
+        throttle.min_delta
+    );
+    *THROTTLE.lock() = throttle;
+    Ok(throttle)
+}
+
 /// Parse FFmpeg progress output. Returns (progress 0.0-1.0 or None, duration in seconds or None).
 pub fn parse_ffmpeg_progress(
     output: &str,
@@ -38,6 +121,41 @@ pub fn parse_ffmpeg_progress(
 mod tests {
     use super::*;
 
+    #[test]
+    fn throttle_limits_rate_and_delta_but_always_reports_completion() {
+        let throttle = ProgressThrottle {
+            max_events_per_sec: 10.0,
+            min_delta: 0.01,
+        };
+        let ms = Duration::from_millis;
+        assert!(throttle.should_emit(0.5, 0.4, ms(100)));
+        assert!(!throttle.should_emit(0.5, 0.4, ms(50)));
+        assert!(!throttle.should_emit(0.405, 0.4, ms(500)));
+        assert!(throttle.should_emit(1.0, 0.99, ms(0)));
+        assert!(!throttle.should_emit(1.0, 1.0, ms(500)));
+
+        let unlimited = ProgressThrottle {
+            max_events_per_sec: 0.0,
+            min_delta: 0.0,
+        };
+        assert!(unlimited.should_emit(0.401, 0.4, ms(0)));
+    }
+
+    #[test]
+    fn throttle_rejects_out_of_range_limits() {
+        let bad_rate = ProgressThrottle {
+            max_events_per_sec: -1.0,
+            ..ProgressThrottle::default()
+        };
+        assert!(bad_rate.validate().is_err());
+        let bad_delta = ProgressThrottle {
+            min_delta: 1.5,
+            ..ProgressThrottle::default()
+        };
+        assert!(bad_delta.validate().is_err());
+        assert!(ProgressThrottle::default().validate().is_ok());
+    }
+
     #[test]
     fn duration_parsed() {
         let (prog, dur) = parse_ffmpeg_progress("Duration: 0:1:30.5", None);
@@ -0,0 +1,447 @@
+//! Objective quality of an encode against its source.
+//!
+//! PSNR compares the two decoded pictures frame by frame with ffmpeg's `psnr` filter and
+//! reports the mean over the run; higher is closer, identical inputs read as infinity. Both
+//! inputs must have the same frame size.
+//!
+//! VMAF (0-100, `libvmaf`) tracks perceived quality much closer than PSNR: around 93 and up is
+//! hard to tell from the source, below 80 is visibly worse. Scoring a whole export would take
+//! about as long as encoding it, so `score_export_vmaf` scores a few short windows of the
+//! output against the same source time, with the export upscaled to the source frame size as
+//! VMAF expects.
+//!
+//! Previews get SSIM and PSNR instead (`measure_preview_quality`): both come out of a single
+//! decode of the two short preview files and need no optional library.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::contact_sheet::has_filter;
+use super::ffprobe::get_video_metadata_in;
+use super::{FfmpegContext, TranscodeOptions, path_to_string};
+use crate::error::AppError;
+
+/// Length of one VMAF window (seconds).
+const VMAF_WINDOW_SECS: f64 = 4.0;
+/// Where the windows sit, as a share of the output duration.
+const VMAF_WINDOW_POSITIONS: [f64; 3] = [0.2, 0.5, 0.8];
+/// Reported PSNR for identical pictures, which the filter gives as infinity (not valid JSON).
+const MAX_PSNR_DB: f64 = 100.0;
+
+/// SSIM and PSNR of a compressed preview against its original.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityMetrics {
+    /// 0-1 over all planes; 1 is identical.
+    pub ssim: f64,
+    /// Mean PSNR (dB), at most 100.
+    pub psnr_db: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VmafScore {
+    /// Mean over the windows.
+    pub score: f64,
+    /// Worst window; a low value next to a good mean points at one hard scene.
+    pub min_window_score: f64,
+    pub windows: u32,
+}
+
+/// Args comparing `distorted` against `reference`; the summary goes to stderr.
+fn build_psnr_args(reference: &str, distorted: &str) -> Vec<String> {
+    [
+        "-nostdin",
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        distorted,
+        "-i",
+        reference,
+        "-lavfi",
+        "[0:v:0][1:v:0]psnr",
+        "-f",
+        "null",
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Mean PSNR (dB) from the line the filter prints at the end, e.g.
+/// `PSNR y:40.1 u:45.3 v:45.5 average:41.5 min:38.9 max:44.0`.
+fn parse_psnr_average(stderr: &str) -> Option<f64> {
+    let line = stderr.lines().rev().find(|l| l.contains("PSNR "))?;
+    line.split_whitespace()
+        .find_map(|field| field.strip_prefix("average:"))?
+        .parse::<f64>()
+        .ok()
+        .filter(|v| !v.is_nan())
+}
+
+/// VMAF from the line libvmaf prints at the end, e.g. `VMAF score: 94.371206`.
+fn parse_vmaf_score(stderr: &str) -> Option<f64> {
+    let line = &stderr[stderr.rfind("VMAF score:")?..];
+    line["VMAF score:".len()..]
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// `(output start, duration)` windows over an output of `duration` seconds.
+fn vmaf_windows(duration: f64) -> Vec<(f64, f64)> {
+    if duration <= 0.0 {
+        return vec![];
+    }
+    if duration <= VMAF_WINDOW_SECS * VMAF_WINDOW_POSITIONS.len() as f64 {
+        return vec![(0.0, duration)];
+    }
+    VMAF_WINDOW_POSITIONS
+        .iter()
+        .map(|p| {
+            let start =
+                (duration * p - VMAF_WINDOW_SECS / 2.0).clamp(0.0, duration - VMAF_WINDOW_SECS);
+            (start, VMAF_WINDOW_SECS)
+        })
+        .collect()
+}
+
+/// SSIM over all planes from the line the filter prints at the end, e.g.
+/// `SSIM Y:0.987 (18.9) U:0.991 (20.5) V:0.990 (20.2) All:0.988 (19.3)`.
+fn parse_ssim_all(stderr: &str) -> Option<f64> {
+    let line = stderr.lines().rev().find(|l| l.contains("SSIM "))?;
+    line.split_whitespace()
+        .find_map(|field| field.strip_prefix("All:"))?
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// Filter graph head pairing input 0 (`distorted`, scaled to `width`x`height`) with input 1
+/// (the reference, resampled to `fps`) as `[dis]` and `[ref]`, both starting at 0.
+fn aligned_inputs((width, height): (u32, u32), fps: f64) -> String {
+    format!(
+        "[0:v:0]scale={width}:{height}:flags=bicubic,format=yuv420p,setpts=PTS-STARTPTS[dis];\
+         [1:v:0]fps={fps},format=yuv420p,setpts=PTS-STARTPTS[ref]"
+    )
+}
+
+/// Args measuring SSIM and PSNR of `distorted` against `reference` in one pass.
+fn build_ssim_psnr_args(
+    reference: &str,
+    distorted: &str,
+    reference_size: (u32, u32),
+    fps: f64,
+) -> Vec<String> {
+    vec![
+        "-nostdin".to_string(),
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-i".to_string(),
+        distorted.to_string(),
+        "-i".to_string(),
+        reference.to_string(),
+        "-lavfi".to_string(),
+        format!(
+            "{};[dis]split[dis1][dis2];[ref]split[ref1][ref2];[dis1][ref1]ssim;[dis2][ref2]psnr",
+            aligned_inputs(reference_size, fps)
+        ),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]
+}
+
+/// Args scoring `[output_start, +duration)` of `distorted` against the source from
+/// `source_start`. The source is resampled to the export's frame rate so frames pair up.
+fn build_vmaf_args(
+    reference: &str,
+    distorted: &str,
+    source_start: f64,
+    output_start: f64,
+    duration: f64,
+    reference_size: (u32, u32),
+    fps: f64,
+) -> Vec<String> {
+    vec![
+        "-nostdin".to_string(),
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-ss".to_string(),
+        format!("{:.3}", output_start),
+        "-t".to_string(),
+        format!("{:.3}", duration),
+        "-i".to_string(),
+        distorted.to_string(),
+        "-ss".to_string(),
+        format!("{:.3}", source_start),
+        "-t".to_string(),
+        format!("{:.3}", duration),
+        "-i".to_string(),
+        reference.to_string(),
+        "-lavfi".to_string(),
+        format!("{};[dis][ref]libvmaf", aligned_inputs(reference_size, fps)),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]
+}
+
+/// Why the export cannot be lined up with its source frame for frame, if it cannot.
+fn vmaf_unsupported_reason(options: &TranscodeOptions) -> Option<&'static str> {
+    if options.effective_keep_segments().is_some() {
+        Some("cut segments")
+    } else {
+        picture_change_reason(options)
+    }
+}
+
+/// Why an encode with `options` no longer shows the source's picture area, if it does not.
+fn picture_change_reason(options: &TranscodeOptions) -> Option<&'static str> {
+    if options.is_gif() {
+        Some("GIF output")
+    } else if options.effective_crop().is_some() {
+        Some("crop")
+    } else if options.rotation.is_some_and(|r| r % 360 != 0)
+        || options.flip_horizontal == Some(true)
+        || options.flip_vertical == Some(true)
+    {
+        Some("rotation or flip")
+    } else {
+        None
+    }
+}
+
+/// Runs a measuring ffmpeg invocation and returns its stderr, where the filters report.
+fn run_measurement(ctx: &FfmpegContext, mut args: Vec<String>) -> Result<String, AppError> {
+    ctx.apply_thread_limit(&mut args);
+    let mut cmd = ctx.ffmpeg_command();
+    cmd.args(args);
+    let output = cmd.output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if !output.status.success() {
+        return Err(AppError::ffmpeg_failed(
+            output.status.code().unwrap_or(-1),
+            stderr,
+        ));
+    }
+    Ok(stderr)
+}
+
+/// Scores the export at `output` against `input` on sampled windows. None when this ffmpeg has
+/// no libvmaf or the options change the picture in ways VMAF cannot compare (crop, rotation,
+/// cuts, GIF). Blocking.
+pub fn score_export_vmaf(
+    ctx: &FfmpegContext,
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<Option<VmafScore>, AppError> {
+    if let Some(reason) = vmaf_unsupported_reason(options) {
+        log::info!(
+            target: "tiny_vid::ffmpeg::quality",
+            "score_export_vmaf: skipped, not comparable with {}",
+            reason
+        );
+        return Ok(None);
+    }
+    if !has_filter(ctx, "libvmaf") {
+        log::info!(
+            target: "tiny_vid::ffmpeg::quality",
+            "score_export_vmaf: skipped, ffmpeg has no libvmaf"
+        );
+        return Ok(None);
+    }
+    let source = get_video_metadata_in(ctx, input, false)?;
+    let export = get_video_metadata_in(ctx, output, false)?;
+    let trim_start = options.effective_trim().map_or(0.0, |(start, _)| start);
+    let fps = options.effective_fps();
+    let (input_str, output_str) = (path_to_string(input), path_to_string(output));
+    let mut scores = Vec::new();
+    for (start, duration) in vmaf_windows(export.duration) {
+        let args = build_vmaf_args(
+            &input_str,
+            &output_str,
+            trim_start + start,
+            start,
+            duration,
+            (source.width, source.height),
+            fps,
+        );
+        let stderr = run_measurement(ctx, args)?;
+        scores.push(
+            parse_vmaf_score(&stderr)
+                .ok_or_else(|| AppError::from("No VMAF score in ffmpeg output"))?,
+        );
+    }
+    if scores.is_empty() {
+        return Ok(None);
+    }
+    let score = VmafScore {
+        score: scores.iter().sum::<f64>() / scores.len() as f64,
+        min_window_score: scores.iter().copied().fold(f64::INFINITY, f64::min),
+        windows: scores.len() as u32,
+    };
+    log::info!(
+        target: "tiny_vid::ffmpeg::quality",
+        "score_export_vmaf: {:.2} (min {:.2}) over {} window(s)",
+        score.score,
+        score.min_window_score,
+        score.windows
+    );
+    Ok(Some(score))
+}
+
+/// Mean PSNR of `distorted` against `reference` over their first video streams. Blocking.
+pub fn measure_psnr(
+    ctx: &FfmpegContext,
+    reference: &Path,
+    distorted: &Path,
+) -> Result<f64, AppError> {
+    let args = build_psnr_args(&path_to_string(reference), &path_to_string(distorted));
+    let stderr = run_measurement(ctx, args)?;
+    parse_psnr_average(&stderr).ok_or_else(|| AppError::from("No PSNR summary in ffmpeg output"))
+}
+
+/// SSIM and PSNR of the compressed preview against the original preview, which it was encoded
+/// from. The compressed side is scaled back to the original's size (for `scale` and the
+/// preview resolution cap) and the original resampled to the preview frame rate. None when
+/// `options` move the picture (crop, rotation, flips, GIF). Blocking.
+pub fn measure_preview_quality(
+    ctx: &FfmpegContext,
+    original: &Path,
+    compressed: &Path,
+    options: &TranscodeOptions,
+) -> Result<Option<QualityMetrics>, AppError> {
+    if let Some(reason) = picture_change_reason(options) {
+        log::info!(
+            target: "tiny_vid::ffmpeg::quality",
+            "measure_preview_quality: skipped, not comparable with {}",
+            reason
+        );
+        return Ok(None);
+    }
+    let reference = get_video_metadata_in(ctx, original, false)?;
+    let args = build_ssim_psnr_args(
+        &path_to_string(original),
+        &path_to_string(compressed),
+        (reference.width, reference.height),
+        options.effective_fps(),
+    );
+    let stderr = run_measurement(ctx, args)?;
+    let ssim = parse_ssim_all(&stderr)
+        .ok_or_else(|| AppError::from("No SSIM summary in ffmpeg output"))?;
+    let psnr = parse_psnr_average(&stderr)
+        .ok_or_else(|| AppError::from("No PSNR summary in ffmpeg output"))?;
+    Ok(Some(QualityMetrics {
+        ssim,
+        psnr_db: psnr.min(MAX_PSNR_DB),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_psnr_average_reads_summary_line() {
+        let stderr = "\
+Output #0, null, to 'pipe:':
+[Parsed_psnr_0 @ 0x600] PSNR y:40.12 u:45.31 v:45.52 average:41.48 min:38.90 max:44.02
+";
+        assert_eq!(parse_psnr_average(stderr), Some(41.48));
+        let identical =
+            "[Parsed_psnr_0 @ 0x600] PSNR y:inf u:inf v:inf average:inf min:inf max:inf";
+        assert_eq!(parse_psnr_average(identical), Some(f64::INFINITY));
+        assert_eq!(parse_psnr_average("Conversion failed!"), None);
+    }
+
+    #[test]
+    fn parse_vmaf_score_reads_final_line() {
+        let stderr = "\
+[libvmaf @ 0x600] VMAF score: 94.371206
+";
+        assert_eq!(parse_vmaf_score(stderr), Some(94.371206));
+        assert_eq!(parse_vmaf_score("[libvmaf @ 0x600] model loaded"), None);
+    }
+
+    #[test]
+    fn vmaf_windows_sample_long_outputs_and_cover_short_ones() {
+        assert_eq!(vmaf_windows(10.0), [(0.0, 10.0)]);
+        assert_eq!(vmaf_windows(100.0), [(18.0, 4.0), (48.0, 4.0), (78.0, 4.0)]);
+        assert!(vmaf_windows(0.0).is_empty());
+    }
+
+    #[test]
+    fn vmaf_args_align_source_and_export() {
+        let args = build_vmaf_args("/in.mov", "/out.mp4", 12.0, 2.0, 4.0, (1920, 1080), 30.0);
+        let seeks: Vec<_> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| *a == "-ss")
+            .map(|(i, _)| args[i + 1].as_str())
+            .collect();
+        assert_eq!(seeks, ["2.000", "12.000"]);
+        let lavfi = &args[args.iter().position(|a| a == "-lavfi").unwrap() + 1];
+        assert!(lavfi.starts_with("[0:v:0]scale=1920:1080:flags=bicubic"));
+        assert!(lavfi.contains("[1:v:0]fps=30,"));
+        assert!(lavfi.ends_with("[dis][ref]libvmaf"));
+    }
+
+    #[test]
+    fn vmaf_skips_options_that_move_pixels() {
+        assert_eq!(vmaf_unsupported_reason(&TranscodeOptions::default()), None);
+        let rotated = TranscodeOptions {
+            rotation: Some(90),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(vmaf_unsupported_reason(&rotated), Some("rotation or flip"));
+    }
+
+    #[test]
+    fn parse_ssim_all_reads_summary_line() {
+        let stderr = "\
+[Parsed_ssim_4 @ 0x600] SSIM Y:0.987 (18.9) U:0.991 (20.5) V:0.990 (20.2) All:0.988 (19.3)
+[Parsed_psnr_5 @ 0x600] PSNR y:40.12 u:45.31 v:45.52 average:41.48 min:38.90 max:44.02
+";
+        assert_eq!(parse_ssim_all(stderr), Some(0.988));
+        assert_eq!(parse_psnr_average(stderr), Some(41.48));
+        assert_eq!(parse_ssim_all("Conversion failed!"), None);
+    }
+
+    #[test]
+    fn ssim_psnr_args_measure_both_from_one_decode() {
+        let args = build_ssim_psnr_args("/orig.mp4", "/prev.mp4", (1280, 720), 30.0);
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 2);
+        let lavfi = &args[args.iter().position(|a| a == "-lavfi").unwrap() + 1];
+        assert!(lavfi.starts_with("[0:v:0]scale=1280:720:flags=bicubic"));
+        assert!(lavfi.ends_with("[dis1][ref1]ssim;[dis2][ref2]psnr"));
+    }
+
+    #[test]
+    fn preview_metrics_ignore_export_only_cuts() {
+        let cut = TranscodeOptions {
+            keep_segments: Some(vec![(0.0, 5.0)]),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(picture_change_reason(&cut), None);
+        assert_eq!(vmaf_unsupported_reason(&cut), Some("cut segments"));
+    }
+
+    #[test]
+    fn psnr_args_put_the_encode_first() {
+        let args = build_psnr_args("/ref.mkv", "/out.mp4");
+        let inputs: Vec<_> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| *a == "-i")
+            .map(|(i, _)| args[i + 1].as_str())
+            .collect();
+        assert_eq!(inputs, ["/out.mp4", "/ref.mkv"]);
+        assert_eq!(args.last().unwrap(), "-");
+    }
+}
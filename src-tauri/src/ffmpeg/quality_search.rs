@@ -0,0 +1,377 @@
+//! Target size by quality search, an alternative to the bitrate-driven two-pass encode.
+//!
+//! A few short windows of the source are encoded silently at several qualities and their
+//! video bitrates fitted to a size curve, `ln(kbps) = intercept + slope * quality` (CRF-style
+//! rate control changes size about exponentially with the quality setting). The export is
+//! encoded at the highest quality the curve puts within the video budget of
+//! `compute_target_video_bitrate_kbps`, so it keeps constant-quality rate control instead of
+//! spreading a fixed bitrate over easy and hard scenes. An export that misses the target by
+//! more than `RETRY_TOLERANCE` is encoded once more, with the quality moved along the curve's
+//! slope from the measured result. The retry goes to its own file; of the two encodes the one
+//! closest to the target without going over is kept (see `pick_encode`).
+
+use super::{RateControlMode, TranscodeOptions, compute_target_video_bitrate_kbps};
+use crate::error::AppError;
+
+/// Length of one sample window (seconds).
+const SAMPLE_SECS: f64 = 2.0;
+/// Where the windows sit, as a share of the encoded range.
+const SAMPLE_POSITIONS: [f64; 3] = [0.2, 0.5, 0.8];
+/// Qualities every window is encoded at; they span the useful part of the scale.
+pub(crate) const SAMPLE_QUALITIES: [u32; 3] = [35, 60, 85];
+/// Share of the target an export may miss by before it is encoded again.
+pub(crate) const RETRY_TOLERANCE: f64 = 0.10;
+/// Quality step of the re-encode when the samples gave no usable curve.
+const FALLBACK_RETRY_STEP: f64 = 10.0;
+/// Share of the progress bar taken by the sample encodes.
+const SAMPLES_PROGRESS_SHARE: f64 = 0.1;
+/// Share of the progress bar taken by the export; the retry, a full-length encode too, gets
+/// the same. An export that needs no retry completes the bar when it finishes.
+const EXPORT_PROGRESS_SHARE: f64 = (1.0 - SAMPLES_PROGRESS_SHARE) / 2.0;
+/// Progress steps of a search: the samples, the export and the retry (when needed).
+pub(crate) const PROGRESS_STEPS: usize = 3;
+
+/// What a search aims at and where it samples.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SearchPlan {
+    /// Video bitrate (kbps) that fills the target next to audio and container overhead.
+    pub budget_kbps: f64,
+    /// The target size in bytes.
+    pub target_bytes: u64,
+    /// Length of the export (seconds).
+    pub output_secs: f64,
+    /// `(source start, duration)` sample windows.
+    pub windows: Vec<(f64, f64)>,
+}
+
+/// Video bitrate against quality, fitted to the samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SizeCurve {
+    intercept: f64,
+    slope: f64,
+}
+
+impl SizeCurve {
+    /// Highest quality whose predicted bitrate stays within `kbps`.
+    fn quality_for(&self, kbps: f64) -> u32 {
+        ((kbps.ln() - self.intercept) / self.slope)
+            .floor()
+            .clamp(0.0, 100.0) as u32
+    }
+}
+
+/// Plans the search for `options`: the same checks as a two-pass target size encode, plus a
+/// known source duration to place the windows in.
+pub(crate) fn plan(options: &TranscodeOptions) -> Result<SearchPlan, AppError> {
+    let budget_kbps = compute_target_video_bitrate_kbps(options)? as f64;
+    let output_secs = options
+        .output_duration_secs()
+        .ok_or_else(|| AppError::from("Video duration is required for target size mode"))?;
+    let (start, end) = match options.effective_trim() {
+        Some((start, end)) => (start, end.or(options.duration_secs)),
+        None => (0.0, options.duration_secs),
+    };
+    let end = end
+        .filter(|e| e.is_finite() && *e > start)
+        .ok_or_else(|| AppError::from("Video duration is required for quality search"))?;
+    let target_mb = options.effective_target_size_mb().unwrap_or(0.0);
+    Ok(SearchPlan {
+        budget_kbps,
+        target_bytes: (target_mb * 1024.0 * 1024.0) as u64,
+        output_secs,
+        windows: sample_windows(start, end),
+    })
+}
+
+/// Windows over the source range `[start, end)`; one covering all of it when it is short.
+fn sample_windows(start: f64, end: f64) -> Vec<(f64, f64)> {
+    let length = end - start;
+    if length <= SAMPLE_SECS * SAMPLE_POSITIONS.len() as f64 {
+        return vec![(start, length)];
+    }
+    SAMPLE_POSITIONS
+        .iter()
+        .map(|p| {
+            let offset = (length * p - SAMPLE_SECS / 2.0).clamp(0.0, length - SAMPLE_SECS);
+            (start + offset, SAMPLE_SECS)
+        })
+        .collect()
+}
+
+/// `options` encoded for quality at `quality` instead of for a bitrate.
+pub(crate) fn encode_options(options: &TranscodeOptions, quality: u32) -> TranscodeOptions {
+    TranscodeOptions {
+        rate_control_mode: Some(RateControlMode::Quality),
+        quality: Some(quality),
+        ..options.clone()
+    }
+}
+
+/// Options for one silent, video-only sample of `window` at `quality`.
+pub(crate) fn sample_options(
+    options: &TranscodeOptions,
+    quality: u32,
+    (start, duration): (f64, f64),
+) -> TranscodeOptions {
+    TranscodeOptions {
+        remove_audio: Some(true),
+        background_audio_path: None,
+        preserve_subtitles: Some(false),
+        cover_image_path: None,
+        cover_frame_seconds: None,
+        keep_segments: None,
+        trim_start_secs: Some(start),
+        trim_end_secs: Some(start + duration),
+        vmaf_check: None,
+        ..encode_options(options, quality)
+    }
+}
+
+/// Least-squares fit of `ln(kbps)` over quality for `(quality, kbps)` points. None when the
+/// points do not grow with quality (e.g. an encoder ignoring it, or a max bitrate capping
+/// every sample).
+pub(crate) fn fit_size_curve(points: &[(u32, f64)]) -> Option<SizeCurve> {
+    let points: Vec<(f64, f64)> = points
+        .iter()
+        .filter(|(_, kbps)| kbps.is_finite() && *kbps > 0.0)
+        .map(|(q, kbps)| (*q as f64, kbps.ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_q = points.iter().map(|(q, _)| q).sum::<f64>() / n;
+    let mean_ln = points.iter().map(|(_, l)| l).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(q, l)| (q - mean_q) * (l - mean_ln))
+        .sum();
+    let variance: f64 = points.iter().map(|(q, _)| (q - mean_q).powi(2)).sum();
+    let slope = covariance / variance;
+    (slope.is_finite() && slope > 0.0).then_some(SizeCurve {
+        intercept: mean_ln - slope * mean_q,
+        slope,
+    })
+}
+
+/// Quality to encode the export at: from the fitted curve, else the best sample within
+/// `budget_kbps`, else the lowest sample.
+pub(crate) fn pick_quality(points: &[(u32, f64)], budget_kbps: f64) -> u32 {
+    if let Some(curve) = fit_size_curve(points) {
+        return curve.quality_for(budget_kbps);
+    }
+    points
+        .iter()
+        .filter(|(_, kbps)| *kbps <= budget_kbps)
+        .map(|(q, _)| *q)
+        .max()
+        .or_else(|| points.iter().map(|(q, _)| *q).min())
+        .unwrap_or(SAMPLE_QUALITIES[0])
+}
+
+/// Whether an export of `actual_bytes` is more than `RETRY_TOLERANCE` off `target_bytes`.
+pub(crate) fn misses_target(actual_bytes: u64, target_bytes: u64) -> bool {
+    target_bytes > 0
+        && (actual_bytes as f64 - target_bytes as f64).abs() / target_bytes as f64 > RETRY_TOLERANCE
+}
+
+/// Which encode a search keeps once a retry ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeptEncode {
+    Export,
+    Retry,
+}
+
+/// Picks between the export (`export_bytes`) and its retry (`retry_bytes`): the one within
+/// `target_bytes` and closest to it. None when both are over the target.
+pub(crate) fn pick_encode(
+    export_bytes: u64,
+    retry_bytes: u64,
+    target_bytes: u64,
+) -> Option<KeptEncode> {
+    match (export_bytes <= target_bytes, retry_bytes <= target_bytes) {
+        (true, true) if retry_bytes > export_bytes => Some(KeptEncode::Retry),
+        (true, _) => Some(KeptEncode::Export),
+        (false, true) => Some(KeptEncode::Retry),
+        (false, false) => None,
+    }
+}
+
+/// Video bitrate (kbps) of an export of `bytes` over `secs`, without its audio.
+pub(crate) fn export_video_kbps(bytes: u64, secs: f64, audio_kbps: f64) -> f64 {
+    (bytes as f64 * 8.0 / secs / 1000.0 - audio_kbps).max(1.0)
+}
+
+/// Quality for the re-encode of an export at `quality` that came out at `actual_kbps` of
+/// video. Moves along the sampled curve's slope from the measured point, and always at least
+/// one step towards the budget.
+pub(crate) fn retry_quality(
+    points: &[(u32, f64)],
+    quality: u32,
+    actual_kbps: f64,
+    budget_kbps: f64,
+) -> u32 {
+    let shift = match fit_size_curve(points) {
+        Some(curve) => (budget_kbps.ln() - actual_kbps.ln()) / curve.slope,
+        None => FALLBACK_RETRY_STEP.copysign(budget_kbps - actual_kbps),
+    };
+    let shift = if shift.abs() < 1.0 {
+        1f64.copysign(shift)
+    } else {
+        shift.round()
+    };
+    (quality as f64 + shift).clamp(0.0, 100.0) as u32
+}
+
+/// `(start, span)` on the progress bar of the sample encode of `window_index` (of `windows`)
+/// at the `quality_index`-th of `SAMPLE_QUALITIES`.
+pub(crate) fn sample_progress(
+    quality_index: usize,
+    window_index: usize,
+    windows: usize,
+) -> (f64, f64) {
+    let span = SAMPLES_PROGRESS_SHARE / (windows * SAMPLE_QUALITIES.len()) as f64;
+    (span * (quality_index * windows + window_index) as f64, span)
+}
+
+/// `(start, span)` on the progress bar of the export at the picked quality.
+pub(crate) fn export_progress() -> (f64, f64) {
+    (SAMPLES_PROGRESS_SHARE, EXPORT_PROGRESS_SHARE)
+}
+
+/// `(start, span)` on the progress bar of the retry encode; it continues after the export.
+pub(crate) fn retry_progress() -> (f64, f64) {
+    (
+        SAMPLES_PROGRESS_SHARE + EXPORT_PROGRESS_SHARE,
+        1.0 - SAMPLES_PROGRESS_SHARE - EXPORT_PROGRESS_SHARE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points on `kbps = 100 * 2^(quality / 25)`.
+    fn doubling_points() -> Vec<(u32, f64)> {
+        SAMPLE_QUALITIES
+            .iter()
+            .map(|&q| (q, 100.0 * 2f64.powf(q as f64 / 25.0)))
+            .collect()
+    }
+
+    #[test]
+    fn fitted_curve_inverts_the_samples() {
+        let curve = fit_size_curve(&doubling_points()).unwrap();
+        assert!((curve.slope - 2f64.ln() / 25.0).abs() < 1e-9);
+        assert_eq!(curve.quality_for(401.0), 50);
+        assert_eq!(curve.quality_for(399.0), 49);
+        assert_eq!(curve.quality_for(1.0), 0);
+        assert_eq!(curve.quality_for(1e9), 100);
+    }
+
+    #[test]
+    fn flat_samples_fall_back_to_the_best_one_within_budget() {
+        let capped = [(35, 800.0), (60, 800.0), (85, 800.0)];
+        assert_eq!(fit_size_curve(&capped), None);
+        assert_eq!(pick_quality(&capped, 1000.0), 85);
+        assert_eq!(pick_quality(&capped, 500.0), 35);
+        assert_eq!(pick_quality(&doubling_points(), 401.0), 50);
+    }
+
+    #[test]
+    fn windows_sample_long_ranges_and_cover_short_ones() {
+        assert_eq!(sample_windows(10.0, 14.0), [(10.0, 4.0)]);
+        assert_eq!(
+            sample_windows(0.0, 100.0),
+            [(19.0, 2.0), (49.0, 2.0), (79.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn plan_requires_a_target_and_samples_the_trimmed_range() {
+        let options = TranscodeOptions {
+            rate_control_mode: Some(RateControlMode::TargetSize),
+            target_size_mb: Some(25.0),
+            duration_secs: Some(120.0),
+            trim_start_secs: Some(20.0),
+            ..TranscodeOptions::default()
+        };
+        let plan = plan(&options).unwrap();
+        assert_eq!(plan.target_bytes, 25 * 1024 * 1024);
+        assert_eq!(plan.output_secs, 100.0);
+        assert_eq!(plan.windows[0], (39.0, 2.0));
+        assert!(plan.budget_kbps > 0.0);
+
+        let no_target = TranscodeOptions {
+            target_size_mb: None,
+            ..options
+        };
+        assert!(super::plan(&no_target).is_err());
+    }
+
+    #[test]
+    fn samples_are_silent_quality_encodes_of_the_window() {
+        let options = TranscodeOptions {
+            rate_control_mode: Some(RateControlMode::TargetSize),
+            keep_segments: Some(vec![(0.0, 5.0)]),
+            ..TranscodeOptions::default()
+        };
+        let sample = sample_options(&options, 60, (12.0, 2.0));
+        assert_eq!(
+            sample.effective_rate_control_mode(),
+            RateControlMode::Quality
+        );
+        assert_eq!(sample.quality, Some(60));
+        assert!(sample.effective_remove_audio());
+        assert_eq!(sample.effective_keep_segments(), None);
+        assert_eq!(sample.effective_trim(), Some((12.0, Some(14.0))));
+    }
+
+    #[test]
+    fn progress_moves_forward_across_windows_and_phases() {
+        let windows = SAMPLE_POSITIONS.len();
+        let mut ranges = Vec::new();
+        for quality_index in 0..SAMPLE_QUALITIES.len() {
+            for window_index in 0..windows {
+                ranges.push(sample_progress(quality_index, window_index, windows));
+            }
+        }
+        ranges.push(export_progress());
+        ranges.push(retry_progress());
+        for pair in ranges.windows(2) {
+            let ((start, span), (next_start, _)) = (pair[0], pair[1]);
+            assert!((start + span - next_start).abs() < 1e-9, "{:?}", pair);
+        }
+        assert_eq!(ranges[0].0, 0.0);
+        let (last_start, last_span) = ranges[ranges.len() - 1];
+        assert!((last_start + last_span - 1.0).abs() < 1e-9);
+        // The retry is a full-length encode and moves the bar as fast as the export.
+        assert!((export_progress().1 - retry_progress().1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn keeps_the_encode_closest_to_the_target_without_going_over() {
+        // An export well under the cap is kept over a retry that lands over it.
+        assert_eq!(pick_encode(750, 1_050, 1_000), Some(KeptEncode::Export));
+        assert_eq!(pick_encode(750, 950, 1_000), Some(KeptEncode::Retry));
+        assert_eq!(pick_encode(950, 750, 1_000), Some(KeptEncode::Export));
+        assert_eq!(pick_encode(1_200, 990, 1_000), Some(KeptEncode::Retry));
+        assert_eq!(pick_encode(1_000, 1_000, 1_000), Some(KeptEncode::Export));
+        assert_eq!(pick_encode(1_200, 1_150, 1_000), None);
+    }
+
+    #[test]
+    fn retry_only_past_tolerance_and_moves_towards_the_budget() {
+        assert!(!misses_target(1_090, 1_000));
+        assert!(misses_target(1_110, 1_000));
+        assert!(misses_target(850, 1_000));
+
+        let points = doubling_points();
+        // Twice the budget: one doubling (25 points) lower.
+        assert_eq!(retry_quality(&points, 60, 800.0, 400.0), 35);
+        assert_eq!(retry_quality(&points, 60, 390.0, 400.0), 61);
+        let flat = [(35, 800.0), (60, 800.0)];
+        assert_eq!(retry_quality(&flat, 60, 800.0, 400.0), 50);
+        assert_eq!(export_video_kbps(1_000_000, 10.0, 128.0), 672.0);
+    }
+}
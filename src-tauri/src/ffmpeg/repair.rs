@@ -0,0 +1,265 @@
+//! Best-effort repair of files that will not open ("my recording won't open").
+//!
+//! Tries the common stream-copy fixes in order of how little they change the file and stops at
+//! the first one whose output probes and decodes cleanly:
+//! 1. Regenerate timestamps in the original container.
+//! 2. Rebuild the MP4/MOV index by remuxing every readable packet into a fresh `moov`.
+//! 3. Remux into Matroska, which tolerates gaps and missing index data.
+//!
+//! A recording whose `moov` atom was never written at all cannot be rebuilt this way (that
+//! needs a healthy reference file from the same camera); such files are reported as not restored.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::ffprobe::get_video_metadata_in;
+use super::verify::verify_video;
+use super::{FfmpegContext, path_to_string, run_ffmpeg_blocking};
+use crate::error::AppError;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairStrategy {
+    RegeneratePts,
+    RebuildIndex,
+    RemuxMkv,
+}
+
+impl RepairStrategy {
+    const ALL: [RepairStrategy; 3] = [
+        RepairStrategy::RegeneratePts,
+        RepairStrategy::RebuildIndex,
+        RepairStrategy::RemuxMkv,
+    ];
+
+    /// Output container extension, or `None` when the strategy does not apply to the source.
+    fn output_extension(self, source_ext: &str) -> Option<&'static str> {
+        match self {
+            RepairStrategy::RegeneratePts => match source_ext {
+                "mp4" | "m4v" => Some("mp4"),
+                "mov" => Some("mov"),
+                "mkv" => Some("mkv"),
+                "webm" => Some("webm"),
+                "ts" | "mts" | "m2ts" => Some("ts"),
+                _ => None,
+            },
+            RepairStrategy::RebuildIndex => {
+                matches!(source_ext, "mp4" | "m4v" | "mov").then_some("mp4")
+            }
+            RepairStrategy::RemuxMkv => Some("mkv"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairAttempt {
+    pub strategy: RepairStrategy,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    /// True when the source already played or a repaired copy probes and decodes cleanly.
+    pub restored: bool,
+    /// False when the source was already playable and nothing was attempted.
+    pub needed_repair: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<RepairStrategy>,
+    /// Repaired copy in the temp dir; the source is never modified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    pub attempts: Vec<RepairAttempt>,
+}
+
+fn build_repair_args(strategy: RepairStrategy, input: &str, output: &str) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-nostdin",
+        "-y",
+        "-v",
+        "error",
+        "-err_detect",
+        "ignore_err",
+        "-fflags",
+        "+genpts+igndts+discardcorrupt",
+        "-i",
+        input,
+        "-map",
+        "0",
+        "-c",
+        "copy",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    match strategy {
+        RepairStrategy::RegeneratePts => {
+            args.extend(["-avoid_negative_ts".to_string(), "make_zero".to_string()]);
+        }
+        RepairStrategy::RebuildIndex => {
+            // Data streams (timecode, gyro) are often what is damaged; keep only A/V/subs.
+            args.extend([
+                "-dn".to_string(),
+                "-movflags".to_string(),
+                "+faststart".to_string(),
+            ]);
+        }
+        RepairStrategy::RemuxMkv => {
+            args.push("-dn".to_string());
+        }
+    }
+    args.push(output.to_string());
+    args
+}
+
+/// Playable = ffprobe finds a video stream with a duration and a full decode reports no errors.
+fn check_playable(ctx: &FfmpegContext, path: &Path) -> Result<(), String> {
+    let meta = get_video_metadata_in(ctx, path, false).map_err(|e| e.to_string())?;
+    if meta.duration <= 0.0 {
+        return Err("No usable duration after repair".to_string());
+    }
+    verify_video(ctx, path, None)
+}
+
+fn try_strategy(
+    ctx: &FfmpegContext,
+    input: &Path,
+    strategy: RepairStrategy,
+    ext: &str,
+) -> Result<String, String> {
+    let output = ctx
+        .create_temp(&format!("repair-output.{ext}"), None)
+        .map_err(|e| format!("Failed to create temp output: {e}"))?;
+    let output_str = path_to_string(&output);
+    let args = build_repair_args(strategy, &path_to_string(input), &output_str);
+    let result = match run_ffmpeg_blocking(ctx, args, None, None, None, None, None) {
+        Ok(()) => check_playable(ctx, &output),
+        Err(AppError::FfmpegFailed { stderr, .. }) => Err(stderr.trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match result {
+        Ok(()) => Ok(output_str),
+        Err(e) => {
+            let _ = fs::remove_file(&output);
+            Err(e)
+        }
+    }
+}
+
+/// Attempts to make `path` playable. Blocking; the source file is left untouched.
+pub fn repair_file(ctx: &FfmpegContext, path: &Path) -> Result<RepairReport, AppError> {
+    if !path.is_file() {
+        return Err(AppError::from(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+    log::info!(
+        target: "tiny_vid::ffmpeg::repair",
+        "repair_file: path={}",
+        path.display()
+    );
+    if check_playable(ctx, path).is_ok() {
+        return Ok(RepairReport {
+            restored: true,
+            needed_repair: false,
+            strategy: None,
+            output_path: None,
+            attempts: Vec::new(),
+        });
+    }
+
+    let source_ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut attempts = Vec::new();
+    for strategy in RepairStrategy::ALL {
+        let Some(ext) = strategy.output_extension(&source_ext) else {
+            continue;
+        };
+        match try_strategy(ctx, path, strategy, ext) {
+            Ok(output_path) => {
+                log::info!(
+                    target: "tiny_vid::ffmpeg::repair",
+                    "repair_file: restored via {:?} -> {}",
+                    strategy,
+                    output_path
+                );
+                attempts.push(RepairAttempt {
+                    strategy,
+                    ok: true,
+                    error: None,
+                });
+                return Ok(RepairReport {
+                    restored: true,
+                    needed_repair: true,
+                    strategy: Some(strategy),
+                    output_path: Some(output_path),
+                    attempts,
+                });
+            }
+            Err(e) => {
+                log::warn!(
+                    target: "tiny_vid::ffmpeg::repair",
+                    "repair_file: {:?} failed: {}",
+                    strategy,
+                    e
+                );
+                attempts.push(RepairAttempt {
+                    strategy,
+                    ok: false,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+    Ok(RepairReport {
+        restored: false,
+        needed_repair: true,
+        strategy: None,
+        output_path: None,
+        attempts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies_only_apply_to_matching_containers() {
+        assert_eq!(
+            RepairStrategy::RegeneratePts.output_extension("mov"),
+            Some("mov")
+        );
+        assert_eq!(RepairStrategy::RegeneratePts.output_extension("avi"), None);
+        assert_eq!(RepairStrategy::RebuildIndex.output_extension("mkv"), None);
+        assert_eq!(
+            RepairStrategy::RebuildIndex.output_extension("mov"),
+            Some("mp4")
+        );
+        assert_eq!(
+            RepairStrategy::RemuxMkv.output_extension("avi"),
+            Some("mkv")
+        );
+    }
+
+    #[test]
+    fn repair_args_stream_copy_with_regenerated_timestamps() {
+        let args = build_repair_args(RepairStrategy::RebuildIndex, "/in.mp4", "/out.mp4");
+        let i_idx = args.iter().position(|a| a == "-i").unwrap();
+        let fflags_idx = args.iter().position(|a| a == "-fflags").unwrap();
+        assert!(fflags_idx < i_idx);
+        assert!(args[fflags_idx + 1].contains("+genpts"));
+        let c_idx = args.iter().position(|a| a == "-c").unwrap();
+        assert_eq!(args[c_idx + 1], "copy");
+        assert!(args.contains(&"+faststart".to_string()));
+        assert_eq!(args.last().unwrap(), "/out.mp4");
+    }
+}
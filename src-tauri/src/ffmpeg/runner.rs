@@ -5,10 +5,7 @@
 //! thread to read the progress stream while the main thread waits for completion.
 
 use std::io::{BufRead, BufReader};
-use std::process::{Child, Command, Stdio};
-
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
+use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
@@ -18,24 +15,22 @@ use parking_lot::Mutex;
 use tauri::Emitter;
 
 use super::FfmpegProgressPayload;
-use super::discovery::get_ffmpeg_path;
-use super::progress::parse_ffmpeg_progress;
+use super::cancel::kill_all_registered;
+use super::context::FfmpegContext;
+use super::progress::{ProgressThrottle, parse_ffmpeg_progress, progress_throttle};
+use super::{energy, live_stderr};
 use crate::error::AppError;
+use crate::events::Versioned;
 
 /// Sentinel for "duration not yet known". AtomicU64 cannot hold Option<f64>,
 /// so we encode duration as f64 bits; u64::MAX means "not yet known".
 const NONE_DURATION_BITS: u64 = u64::MAX;
 
-/// Minimum interval between progress emits to reduce IPC and React re-renders.
-const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(150);
 /// Keep only the last N bytes of stderr to avoid unbounded memory growth.
 const MAX_STDERR_BYTES: usize = 64 * 1024;
 
-/// Single active FFmpeg process. Only one transcode/preview at a time.
-static ACTIVE_FFMPEG_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
-
 /// Configuration for FFmpeg output stream reading (stdout or stderr).
-struct ReadStreamConfig {
+pub(super) struct ReadStreamConfig {
     collect_stderr: Option<Arc<Mutex<Vec<u8>>>>,
     duration: Arc<AtomicU64>,
     app: Option<tauri::AppHandle>,
@@ -43,6 +38,179 @@ struct ReadStreamConfig {
     progress_collector: Option<Arc<Mutex<Vec<f64>>>>,
     /// When set, called instead of emitting ffmpeg-progress (used for preview aggregate progress).
     progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    /// Job whose live stderr ring and CPU meter receive the lines.
+    job: Option<u64>,
+    /// Cap on collected stderr bytes; older output is dropped first.
+    stderr_limit: usize,
+}
+
+impl ReadStreamConfig {
+    /// Also feed every line to `job_id`'s live stderr ring and CPU meter, when set.
+    pub(super) fn with_job(mut self, job_id: Option<u64>) -> Self {
+        self.job = job_id;
+        self
+    }
+
+    /// Keep all of stderr instead of the last `MAX_STDERR_BYTES`, for callers that parse it.
+    fn with_full_stderr(mut self) -> Self {
+        self.stderr_limit = usize::MAX;
+        self
+    }
+}
+
+/// Per-stream line state shared by the blocking and async runners: collects stderr, parses
+/// progress and emits it, throttled.
+pub(super) struct LineHandler {
+    config: ReadStreamConfig,
+    current_duration: Option<f64>,
+    /// Taken when the run starts; later `set_progress_throttle` calls apply to later runs.
+    throttle: ProgressThrottle,
+    /// None until the first event, which the rate limit never holds back.
+    last_emit: Option<Instant>,
+    last_progress: f64,
+}
+
+impl LineHandler {
+    pub(super) fn new(config: ReadStreamConfig) -> Self {
+        let bits = config.duration.load(Ordering::Relaxed);
+        let current_duration = if bits == NONE_DURATION_BITS {
+            None
+        } else {
+            Some(f64::from_bits(bits))
+        };
+        Self {
+            config,
+            current_duration,
+            throttle: progress_throttle(),
+            last_emit: None,
+            last_progress: 0.0,
+        }
+    }
+
+    pub(super) fn handle_line(&mut self, line: &str) {
+        let config = &self.config;
+        if let Some(ref buf) = config.collect_stderr {
+            let mut guard = buf.lock();
+            guard.extend_from_slice(line.as_bytes());
+            guard.push(b'\n');
+            if guard.len() > config.stderr_limit {
+                let excess = guard.len() - config.stderr_limit;
+                guard.drain(..excess);
+            }
+        }
+        if let Some(job_id) = config.job {
+            live_stderr::push(job_id, line);
+            energy::record(job_id, line);
+        }
+        let (progress, d) = parse_ffmpeg_progress(line, self.current_duration);
+        if let Some(new_dur) = d {
+            self.current_duration = Some(new_dur);
+            config.duration.store(new_dur.to_bits(), Ordering::Relaxed);
+        }
+        if let Some(p) = progress {
+            if let Some(ref collector) = config.progress_collector {
+                let mut guard = collector.lock();
+                guard.push(p);
+            }
+            let now = Instant::now();
+            let since_last = self
+                .last_emit
+                .map_or(Duration::MAX, |last| now.duration_since(last));
+            if self.throttle.should_emit(p, self.last_progress, since_last) {
+                self.last_emit = Some(now);
+                self.last_progress = p;
+                if let Some(ref cb) = config.progress_callback {
+                    cb(p);
+                } else if let Some(handle) = config.app.as_ref() {
+                    let payload = Versioned::new(FfmpegProgressPayload::new(p, None));
+                    let _ = if let Some(ref lbl) = config.window_label {
+                        handle.emit_to(lbl, "ffmpeg-progress", payload)
+                    } else {
+                        handle.emit("ffmpeg-progress", payload)
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Stdout/stderr configs for one run. When `progress_callback` is set it replaces app emits.
+pub(super) fn stream_configs(
+    duration_secs: Option<f64>,
+    app: Option<&tauri::AppHandle>,
+    window_label: Option<&str>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    progress_collector: Option<Arc<Mutex<Vec<f64>>>>,
+    stderr_buffer: &Arc<Mutex<Vec<u8>>>,
+) -> (ReadStreamConfig, ReadStreamConfig) {
+    let duration = Arc::new(AtomicU64::new(
+        duration_secs
+            .filter(|&d| d > 0.0)
+            .map(f64::to_bits)
+            .unwrap_or(NONE_DURATION_BITS),
+    ));
+    let (app_stdout, app_stderr, label) = if progress_callback.is_none() {
+        (app.cloned(), app.cloned(), window_label.map(String::from))
+    } else {
+        (None, None, None)
+    };
+    (
+        ReadStreamConfig {
+            collect_stderr: None,
+            duration: Arc::clone(&duration),
+            app: app_stdout,
+            window_label: label.clone(),
+            progress_collector,
+            progress_callback,
+            job: None,
+            stderr_limit: MAX_STDERR_BYTES,
+        },
+        ReadStreamConfig {
+            collect_stderr: Some(Arc::clone(stderr_buffer)),
+            duration,
+            app: app_stderr,
+            window_label: label,
+            progress_collector: None,
+            progress_callback: None,
+            job: None,
+            stderr_limit: MAX_STDERR_BYTES,
+        },
+    )
+}
+
+/// Maps the exit status to the collected stderr, or `FfmpegFailed` carrying it.
+pub(super) fn finish_run(
+    status: std::process::ExitStatus,
+    stderr_buffer: &Mutex<Vec<u8>>,
+) -> Result<String, AppError> {
+    let stderr_bytes = stderr_buffer.lock().clone();
+    let stderr_str = String::from_utf8_lossy(&stderr_bytes).to_string();
+
+    if status.success() {
+        log::info!(
+            target: "tiny_vid::ffmpeg::runner",
+            "FFmpeg completed successfully"
+        );
+        Ok(stderr_str)
+    } else {
+        let code = status.code().unwrap_or(-1);
+        let err_preview = stderr_str
+            .lines()
+            .rev()
+            .take(3)
+            .collect::<Vec<_>>()
+            .join("; ");
+        log::error!(
+            target: "tiny_vid::ffmpeg::runner",
+            "FFmpeg failed (code={}): {}",
+            code,
+            err_preview
+        );
+        Err(AppError::FfmpegFailed {
+            code,
+            stderr: stderr_str,
+        })
+    }
 }
 
 fn read_stream<R: std::io::Read + Send + 'static>(
@@ -50,70 +218,23 @@ fn read_stream<R: std::io::Read + Send + 'static>(
     config: ReadStreamConfig,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
-        let load_duration = || {
-            let bits = config.duration.load(Ordering::Relaxed);
-            if bits == NONE_DURATION_BITS {
-                None
-            } else {
-                Some(f64::from_bits(bits))
-            }
-        };
-        let mut current_duration = load_duration();
-        let mut last_emit = Instant::now();
-        let mut last_progress = 0.0_f64;
+        let mut handler = LineHandler::new(config);
         let mut stream_reader = BufReader::new(reader);
         let mut line_buf = Vec::with_capacity(256);
         while stream_reader.read_until(b'\n', &mut line_buf).unwrap_or(0) > 0 {
             let line = std::str::from_utf8(&line_buf)
                 .unwrap_or("")
                 .trim_end_matches(['\n', '\r']);
-            if let Some(ref buf) = config.collect_stderr {
-                let mut guard = buf.lock();
-                guard.extend_from_slice(line.as_bytes());
-                guard.push(b'\n');
-                if guard.len() > MAX_STDERR_BYTES {
-                    let excess = guard.len() - MAX_STDERR_BYTES;
-                    guard.drain(..excess);
-                }
-            }
-            let (progress, d) = parse_ffmpeg_progress(line, current_duration);
-            if let Some(new_dur) = d {
-                current_duration = Some(new_dur);
-                config.duration.store(new_dur.to_bits(), Ordering::Relaxed);
-            }
-            if let Some(p) = progress {
-                if let Some(ref collector) = config.progress_collector {
-                    let mut guard = collector.lock();
-                    guard.push(p);
-                }
-                let now = Instant::now();
-                let should_emit = now.duration_since(last_emit) >= PROGRESS_EMIT_INTERVAL
-                    || (p - last_progress).abs() >= 0.01
-                    || p >= 1.0;
-                if should_emit {
-                    last_emit = now;
-                    last_progress = p;
-                    if let Some(ref cb) = config.progress_callback {
-                        cb(p);
-                    } else if let Some(handle) = config.app.as_ref() {
-                        let payload = FfmpegProgressPayload {
-                            progress: p,
-                            step: None,
-                        };
-                        let _ = if let Some(ref lbl) = config.window_label {
-                            handle.emit_to(lbl, "ffmpeg-progress", payload)
-                        } else {
-                            handle.emit("ffmpeg-progress", payload)
-                        };
-                    }
-                }
-            }
+            handler.handle_line(line);
             line_buf.clear();
         }
     })
 }
 
 /// Run FFmpeg and block until completion. Used when we need to wait (e.g. preview, transcode).
+/// Binary, thread limit and niceness come from `ctx`; nothing is read from process-global state.
+/// Returns `Aborted` without spawning when `ctx`'s cancellation token is already cancelled, and
+/// when the token (or `terminate_all_ffmpeg`) kills the process mid-run.
 ///
 /// Progress emission:
 /// - If `progress_callback` is Some: calls the callback with 0-1 progress; `app`/`window_label`
@@ -126,6 +247,7 @@ fn read_stream<R: std::io::Read + Send + 'static>(
 ///   immediately from out_time_ms (avoids race with Duration line on stderr).
 /// - `progress_collector`: When provided (e.g. in tests), collects all progress values.
 pub fn run_ffmpeg_blocking(
+    ctx: &FfmpegContext,
     args: Vec<String>,
     app: Option<&tauri::AppHandle>,
     window_label: Option<&str>,
@@ -133,8 +255,58 @@ pub fn run_ffmpeg_blocking(
     progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
     progress_collector: Option<Arc<Mutex<Vec<f64>>>>,
 ) -> Result<(), AppError> {
-    let ffmpeg_path = get_ffmpeg_path()?;
-    let path_str = ffmpeg_path.to_string_lossy();
+    run_blocking(
+        ctx,
+        args,
+        app,
+        window_label,
+        duration_secs,
+        progress_callback,
+        progress_collector,
+        false,
+    )
+    .map(|_| ())
+}
+
+/// Like `run_ffmpeg_blocking` without progress, but returns all of stderr on success. For
+/// analysis runs (`silencedetect`, `blackdetect`, decode checks) that parse FFmpeg's log.
+pub fn run_ffmpeg_capture(ctx: &FfmpegContext, args: Vec<String>) -> Result<String, AppError> {
+    run_blocking(ctx, args, None, None, None, None, None, true)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_blocking(
+    ctx: &FfmpegContext,
+    mut args: Vec<String>,
+    app: Option<&tauri::AppHandle>,
+    window_label: Option<&str>,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    progress_collector: Option<Arc<Mutex<Vec<f64>>>>,
+    full_stderr: bool,
+) -> Result<String, AppError> {
+    let token = ctx.cancellation();
+    if token.is_cancelled() {
+        return Err(AppError::aborted());
+    }
+    let path_str = ctx.ffmpeg_path().to_string_lossy();
+    ctx.apply_thread_limit(&mut args);
+    energy::apply_benchmark(ctx.job_id(), &mut args);
+    if super::fake::enabled() {
+        let (stdout_config, _) = stream_configs(
+            duration_secs,
+            app,
+            window_label,
+            progress_callback,
+            progress_collector,
+            &Arc::new(Mutex::new(Vec::new())),
+        );
+        let mut handler = LineHandler::new(stdout_config);
+        return super::fake::run_blocking(ctx, &args, duration_secs, &mut |line| {
+            handler.handle_line(line)
+        })
+        .map(|()| String::new());
+    }
 
     let input_arg = args
         .iter()
@@ -149,12 +321,10 @@ pub fn run_ffmpeg_blocking(
         output_arg
     );
 
-    let mut cmd = Command::new(&*path_str);
+    let mut cmd = ctx.ffmpeg_command();
     cmd.args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
-    #[cfg(windows)]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     let mut child = cmd
         .spawn()
         .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
@@ -176,56 +346,30 @@ pub fn run_ffmpeg_blocking(
         }
     };
 
-    {
-        let mut guard = ACTIVE_FFMPEG_PROCESS.lock();
-        *guard = Some(child);
-    }
+    let run_id = token.register(child)?;
 
-    let duration = Arc::new(AtomicU64::new(
-        duration_secs
-            .filter(|&d| d > 0.0)
-            .map(f64::to_bits)
-            .unwrap_or(NONE_DURATION_BITS),
-    ));
     let stderr_buffer = Arc::new(Mutex::new(Vec::new()));
-    let (app_stdout, app_stderr, label) = if progress_callback.is_none() {
-        (app.cloned(), app.cloned(), window_label.map(String::from))
+    let (stdout_config, stderr_config) = stream_configs(
+        duration_secs,
+        app,
+        window_label,
+        progress_callback,
+        progress_collector,
+        &stderr_buffer,
+    );
+    let stdout_handle = read_stream(stdout, stdout_config);
+    let stderr_config = stderr_config.with_job(ctx.job_id());
+    let stderr_config = if full_stderr {
+        stderr_config.with_full_stderr()
     } else {
-        (None, None, None)
+        stderr_config
     };
-    let progress_cb_stdout = progress_callback;
-
-    let stdout_handle = read_stream(
-        stdout,
-        ReadStreamConfig {
-            collect_stderr: None,
-            duration: Arc::clone(&duration),
-            app: app_stdout,
-            window_label: label.clone(),
-            progress_collector,
-            progress_callback: progress_cb_stdout,
-        },
-    );
-    let stderr_handle = read_stream(
-        stderr,
-        ReadStreamConfig {
-            collect_stderr: Some(Arc::clone(&stderr_buffer)),
-            duration: Arc::clone(&duration),
-            app: app_stderr,
-            window_label: label,
-            progress_collector: None,
-            progress_callback: None,
-        },
-    );
+    let stderr_handle = read_stream(stderr, stderr_config);
 
     let _ = stdout_handle.join();
     let _ = stderr_handle.join();
 
-    let mut guard = ACTIVE_FFMPEG_PROCESS.lock();
-    let child = guard.take();
-    drop(guard);
-
-    let status = match child {
+    let status = match token.take(run_id) {
         Some(mut c) => c.wait().map_err(|e| e.to_string())?,
         None => {
             log::warn!(
@@ -236,44 +380,14 @@ pub fn run_ffmpeg_blocking(
         }
     };
 
-    let stderr_bytes = stderr_buffer.lock().clone();
-    let stderr_str = String::from_utf8_lossy(&stderr_bytes).to_string();
-
-    if status.success() {
-        log::info!(
-            target: "tiny_vid::ffmpeg::runner",
-            "FFmpeg completed successfully"
-        );
-        Ok(())
-    } else {
-        let code = status.code().unwrap_or(-1);
-        let err_preview = stderr_str
-            .lines()
-            .rev()
-            .take(3)
-            .collect::<Vec<_>>()
-            .join("; ");
-        log::error!(
-            target: "tiny_vid::ffmpeg::runner",
-            "FFmpeg failed (code={}): {}",
-            code,
-            err_preview
-        );
-        Err(AppError::FfmpegFailed {
-            code,
-            stderr: stderr_str,
-        })
-    }
+    finish_run(status, &stderr_buffer)
 }
 
+/// Kills every running FFmpeg process, whatever token it was started with.
 pub fn terminate_all_ffmpeg() {
-    let mut guard = ACTIVE_FFMPEG_PROCESS.lock();
-    if let Some(mut child) = guard.take() {
-        log::info!(
-            target: "tiny_vid::ffmpeg::runner",
-            "Terminating FFmpeg process"
-        );
-        let _ = child.kill();
-        let _ = child.wait();
-    }
+    log::info!(
+        target: "tiny_vid::ffmpeg::runner",
+        "Terminating FFmpeg processes"
+    );
+    kill_all_registered();
 }
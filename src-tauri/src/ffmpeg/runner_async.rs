@@ -0,0 +1,679 @@
+//! Async FFmpeg runner on `tokio::process`.
+//!
+//! Same progress and error semantics as [`run_ffmpeg_blocking`](super::run_ffmpeg_blocking),
+//! but the child is awaited on the async runtime instead of parking a blocking thread per job.
+//! Children are spawned with `kill_on_drop`, so dropping the future (e.g. when a
+//! `tokio::time::timeout` elapses) kills FFmpeg.
+//!
+//! [`transcode_to_writer`] streams the encoded container from FFmpeg's stdout (`pipe:1`) into
+//! any `AsyncWrite`, so integrations can pipe the output into another tool without a temp file.
+
+use std::future::Future;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::cancel::{CancellationToken, terminate_generation};
+use super::context::FfmpegContext;
+use super::runner::{LineHandler, ReadStreamConfig, finish_run, stream_configs};
+use super::{
+    TranscodeOptions, build_ffmpeg_command, build_two_pass_ffmpeg_commands, check_first_pass_log,
+    cleanup_passlog_files, energy, path_to_string,
+};
+use crate::error::AppError;
+
+/// Output target FFmpeg understands as its stdout.
+const STDOUT_PIPE: &str = "pipe:1";
+/// Input FFmpeg understands as its stdin.
+const STDIN_PIPE: &str = "pipe:0";
+/// Conventional CLI path for "read standard input".
+const STDIN_PATH: &str = "-";
+
+/// How often a running job checks its token and `terminate_all_ffmpeg`.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+async fn read_lines<R: AsyncRead + Unpin>(reader: R, config: ReadStreamConfig) {
+    let mut handler = LineHandler::new(config);
+    let mut reader = BufReader::new(reader);
+    let mut line_buf = Vec::with_capacity(256);
+    while reader.read_until(b'\n', &mut line_buf).await.unwrap_or(0) > 0 {
+        let line = std::str::from_utf8(&line_buf)
+            .unwrap_or("")
+            .trim_end_matches(['\n', '\r']);
+        handler.handle_line(line);
+        line_buf.clear();
+    }
+}
+
+/// Like `read_lines`, but each stderr line feeds both handlers: in stream mode stdout carries
+/// the encoded bytes, so `-progress` is written to stderr alongside FFmpeg's log.
+async fn read_stderr_with_progress<R: AsyncRead + Unpin>(
+    reader: R,
+    progress_config: ReadStreamConfig,
+    stderr_config: ReadStreamConfig,
+) {
+    let mut progress = LineHandler::new(progress_config);
+    let mut stderr = LineHandler::new(stderr_config);
+    let mut reader = BufReader::new(reader);
+    let mut line_buf = Vec::with_capacity(256);
+    while reader.read_until(b'\n', &mut line_buf).await.unwrap_or(0) > 0 {
+        let line = std::str::from_utf8(&line_buf)
+            .unwrap_or("")
+            .trim_end_matches(['\n', '\r']);
+        progress.handle_line(line);
+        stderr.handle_line(line);
+        line_buf.clear();
+    }
+}
+
+/// Resolves once `token` is cancelled or `terminate_all_ffmpeg` runs after `generation`.
+async fn cancelled(token: &CancellationToken, generation: u64) {
+    let mut tick = tokio::time::interval(CANCEL_POLL_INTERVAL);
+    loop {
+        tick.tick().await;
+        if token.is_cancelled() || terminate_generation() != generation {
+            return;
+        }
+    }
+}
+
+/// Async counterpart of `run_ffmpeg_blocking` (no progress collector; use a callback).
+pub async fn run_ffmpeg_async(
+    ctx: &FfmpegContext,
+    args: Vec<String>,
+    app: Option<&tauri::AppHandle>,
+    window_label: Option<&str>,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+) -> Result<(), AppError> {
+    run_with_stdin(
+        ctx,
+        args,
+        (app, window_label),
+        duration_secs,
+        progress_callback,
+        None,
+    )
+    .await
+}
+
+type StdinSource<'a> = dyn AsyncRead + Unpin + Send + 'a;
+
+/// Copies `source` into FFmpeg's stdin, closing it at EOF. A broken pipe means FFmpeg stopped
+/// reading (it exited or has all it needs); the exit status reports that, so it is not an error.
+async fn feed_stdin(
+    source: Option<&mut StdinSource<'_>>,
+    stdin: Option<tokio::process::ChildStdin>,
+) -> std::io::Result<()> {
+    let (Some(source), Some(mut stdin)) = (source, stdin) else {
+        return Ok(());
+    };
+    match tokio::io::copy(source, &mut stdin).await {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// `run_ffmpeg_async`, optionally feeding `stdin_source` to FFmpeg's stdin (`-i pipe:0`).
+async fn run_with_stdin(
+    ctx: &FfmpegContext,
+    mut args: Vec<String>,
+    (app, window_label): (Option<&tauri::AppHandle>, Option<&str>),
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    mut stdin_source: Option<&mut StdinSource<'_>>,
+) -> Result<(), AppError> {
+    let token = ctx.cancellation();
+    if token.is_cancelled() {
+        return Err(AppError::aborted());
+    }
+    let generation = terminate_generation();
+    ctx.apply_thread_limit(&mut args);
+    energy::apply_benchmark(ctx.job_id(), &mut args);
+    if super::fake::enabled() {
+        let (stdout_config, _) = stream_configs(
+            duration_secs,
+            app,
+            window_label,
+            progress_callback,
+            None,
+            &Arc::new(Mutex::new(Vec::new())),
+        );
+        let mut handler = LineHandler::new(stdout_config);
+        return super::fake::run_async(ctx, &args, duration_secs, &mut |line| {
+            handler.handle_line(line)
+        })
+        .await;
+    }
+    log::debug!(
+        target: "tiny_vid::ffmpeg::runner_async",
+        "Spawning FFmpeg: path={}, output={:?}",
+        ctx.ffmpeg_path().display(),
+        args.last()
+    );
+
+    let mut cmd = tokio::process::Command::from(ctx.ffmpeg_command());
+    cmd.args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if stdin_source.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+    let stdin = child.stdin.take();
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::from("Failed to capture stdout"))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| AppError::from("Failed to capture stderr"))?;
+
+    let stderr_buffer = Arc::new(Mutex::new(Vec::new()));
+    let (stdout_config, stderr_config) = stream_configs(
+        duration_secs,
+        app,
+        window_label,
+        progress_callback,
+        None,
+        &stderr_buffer,
+    );
+    let run = async {
+        let (fed, _, _, status) = tokio::join!(
+            feed_stdin(stdin_source.as_deref_mut(), stdin),
+            read_lines(stdout, stdout_config),
+            read_lines(stderr, stderr_config.with_job(ctx.job_id())),
+            child.wait()
+        );
+        (fed, status)
+    };
+    let outcome = tokio::select! {
+        outcome = run => Some(outcome),
+        () = cancelled(token, generation) => None,
+    };
+    match outcome {
+        Some((fed, status)) => {
+            // A failed source read truncates the input; report it over whatever FFmpeg made of it.
+            fed?;
+            finish_run(status?, &stderr_buffer).map(|_| ())
+        }
+        None => {
+            log::warn!(
+                target: "tiny_vid::ffmpeg::runner_async",
+                "FFmpeg process was aborted (cancelled)"
+            );
+            let _ = child.kill().await;
+            Err(AppError::aborted())
+        }
+    }
+}
+
+/// Transcodes `input` to `output` with `options`; target-size jobs with a two-pass capable codec
+/// run both passes, each reported as half of `progress_callback`'s 0-1 range. An `input` of `-`
+/// reads the process's stdin (see [`transcode_from_reader`]).
+pub async fn transcode_async(
+    ctx: &FfmpegContext,
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+) -> Result<(), AppError> {
+    if input == Path::new(STDIN_PATH) {
+        let mut stdin = tokio::io::stdin();
+        return transcode_from_reader(
+            ctx,
+            &mut stdin,
+            output,
+            options,
+            duration_secs,
+            progress_callback,
+        )
+        .await;
+    }
+    let input = path_to_string(input);
+    let output = path_to_string(output);
+    if !options.uses_two_pass() {
+        let args = build_ffmpeg_command(&input, &output, options, None, None, None)?;
+        return run_ffmpeg_async(ctx, args, None, None, duration_secs, progress_callback).await;
+    }
+    run_two_pass(ctx, &input, &output, options, |pass_index, args| {
+        let pass_callback = progress_callback.clone().map(|cb| {
+            Arc::new(move |p: f64| cb((pass_index as f64 + p) / 2.0))
+                as Arc<dyn Fn(f64) + Send + Sync>
+        });
+        run_ffmpeg_async(ctx, args, None, None, duration_secs, pass_callback)
+    })
+    .await
+}
+
+/// Runs both passes of a target-size encode over one pass log in `ctx`'s temp dir.
+/// `run_pass(pass_index, args)` runs a single pass. The first pass's log is checked before
+/// the second starts, and the log files are removed whatever the outcome.
+pub(crate) async fn run_two_pass<F, Fut>(
+    ctx: &FfmpegContext,
+    input: &str,
+    output: &str,
+    options: &TranscodeOptions,
+    mut run_pass: F,
+) -> Result<(), AppError>
+where
+    F: FnMut(usize, Vec<String>) -> Fut,
+    Fut: Future<Output = Result<(), AppError>>,
+{
+    let passlog = ctx.create_temp("passlog", None)?;
+    let commands = build_two_pass_ffmpeg_commands(
+        input,
+        output,
+        options,
+        None,
+        None,
+        None,
+        &path_to_string(&passlog),
+    )?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::runner_async",
+        "run_two_pass: passlog={}",
+        passlog.display()
+    );
+    let mut result = Ok(());
+    for (pass_index, args) in [commands.pass1, commands.pass2].into_iter().enumerate() {
+        result = run_pass(pass_index, args).await;
+        if pass_index == 0 && result.is_ok() {
+            result = check_first_pass_log(&passlog);
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+    cleanup_passlog_files(&passlog);
+    result
+}
+
+/// Transcodes data read from `source` (fed to FFmpeg's stdin) to `output`.
+///
+/// A pipe has no duration and cannot be rewound, so the caller supplies `duration_secs` for
+/// progress, and target-size options that would need a second pass over the input are rejected.
+pub async fn transcode_from_reader<R: AsyncRead + Unpin + Send>(
+    ctx: &FfmpegContext,
+    source: &mut R,
+    output: &Path,
+    options: &TranscodeOptions,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+) -> Result<(), AppError> {
+    if options.uses_two_pass() {
+        return Err(AppError::from(
+            "Target-size encoding needs two passes over the input; piped input can be read only once",
+        ));
+    }
+    let args = build_ffmpeg_command(
+        STDIN_PIPE,
+        &path_to_string(output),
+        options,
+        None,
+        None,
+        None,
+    )?;
+    run_with_stdin(
+        ctx,
+        args,
+        (None, None),
+        duration_secs,
+        progress_callback,
+        Some(source),
+    )
+    .await
+}
+
+/// FFmpeg muxer for `format`; a pipe has no extension to infer it from.
+fn pipe_muxer(format: &str) -> &'static str {
+    match format {
+        "webm" => "webm",
+        "mkv" => "matroska",
+        "mov" => "mov",
+        "gif" => "gif",
+        _ => "mp4",
+    }
+}
+
+/// Rewrites file-output args for streaming to stdout: progress moves to stderr, MP4/MOV are
+/// fragmented (a non-seekable pipe cannot be rewritten for `+faststart`) and the muxer is named.
+fn streaming_args(mut args: Vec<String>, format: &str) -> Vec<String> {
+    for i in 0..args.len().saturating_sub(1) {
+        if args[i] == "-progress" {
+            args[i + 1] = "pipe:2".to_string();
+        } else if args[i] == "-movflags" {
+            args[i + 1] = "+frag_keyframe+empty_moov+default_base_moof".to_string();
+        }
+    }
+    let output = args.len().saturating_sub(1);
+    if !args[..output].iter().any(|a| a == "-f") {
+        args.splice(
+            output..output,
+            ["-f".to_string(), pipe_muxer(format).to_string()],
+        );
+    }
+    args
+}
+
+/// Runs FFmpeg with its output on stdout (`pipe:1`) and copies the bytes into `sink`.
+/// Returns the number of bytes written. If `sink` fails, stdout is closed so FFmpeg exits on
+/// the broken pipe and the write error is returned.
+async fn stream_ffmpeg_to_writer<W: AsyncWrite + Unpin>(
+    ctx: &FfmpegContext,
+    mut args: Vec<String>,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    sink: &mut W,
+) -> Result<u64, AppError> {
+    let token = ctx.cancellation();
+    if token.is_cancelled() {
+        return Err(AppError::aborted());
+    }
+    let generation = terminate_generation();
+    ctx.apply_thread_limit(&mut args);
+    energy::apply_benchmark(ctx.job_id(), &mut args);
+    if super::fake::enabled() {
+        let (progress_config, _) = stream_configs(
+            duration_secs,
+            None,
+            None,
+            progress_callback,
+            None,
+            &Arc::new(Mutex::new(Vec::new())),
+        );
+        let mut handler = LineHandler::new(progress_config);
+        super::fake::run_async(ctx, &args, duration_secs, &mut |line| {
+            handler.handle_line(line)
+        })
+        .await?;
+        sink.write_all(super::fake::PLACEHOLDER).await?;
+        return Ok(super::fake::PLACEHOLDER.len() as u64);
+    }
+    log::debug!(
+        target: "tiny_vid::ffmpeg::runner_async",
+        "Spawning FFmpeg (stdout stream): path={}",
+        ctx.ffmpeg_path().display()
+    );
+
+    let mut cmd = tokio::process::Command::from(ctx.ffmpeg_command());
+    cmd.args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::from("Failed to capture stdout"))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| AppError::from("Failed to capture stderr"))?;
+
+    let stderr_buffer = Arc::new(Mutex::new(Vec::new()));
+    let (progress_config, stderr_config) = stream_configs(
+        duration_secs,
+        None,
+        None,
+        progress_callback,
+        None,
+        &stderr_buffer,
+    );
+    let copy = async {
+        let mut stdout = stdout;
+        tokio::io::copy(&mut stdout, sink).await
+    };
+    let run = async {
+        tokio::join!(
+            copy,
+            read_stderr_with_progress(
+                stderr,
+                progress_config,
+                stderr_config.with_job(ctx.job_id())
+            ),
+            child.wait()
+        )
+    };
+    let outcome = tokio::select! {
+        outcome = run => Some(outcome),
+        () = cancelled(token, generation) => None,
+    };
+    match outcome {
+        Some((copied, (), status)) => {
+            let copied = copied?;
+            finish_run(status?, &stderr_buffer)?;
+            Ok(copied)
+        }
+        None => {
+            log::warn!(
+                target: "tiny_vid::ffmpeg::runner_async",
+                "FFmpeg stream was aborted (cancelled)"
+            );
+            let _ = child.kill().await;
+            Err(AppError::aborted())
+        }
+    }
+}
+
+/// Transcodes `input` and streams the container into `sink` instead of writing a file.
+/// MP4/MOV output is fragmented so it can be written front to back. Target-size two-pass jobs
+/// run the analysis pass first; only the second pass is streamed. Returns the bytes written.
+pub async fn transcode_to_writer<W: AsyncWrite + Unpin>(
+    ctx: &FfmpegContext,
+    input: &Path,
+    options: &TranscodeOptions,
+    duration_secs: Option<f64>,
+    progress_callback: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+    sink: &mut W,
+) -> Result<u64, AppError> {
+    let input = path_to_string(input);
+    let format = options.effective_output_format();
+    if !options.uses_two_pass() {
+        let args = build_ffmpeg_command(&input, STDOUT_PIPE, options, None, None, None)?;
+        let args = streaming_args(args, &format);
+        return stream_ffmpeg_to_writer(ctx, args, duration_secs, progress_callback, sink).await;
+    }
+
+    let passlog = ctx.create_temp("passlog", None)?;
+    let commands = build_two_pass_ffmpeg_commands(
+        &input,
+        STDOUT_PIPE,
+        options,
+        None,
+        None,
+        None,
+        &path_to_string(&passlog),
+    )?;
+    let half = |pass_index: usize| {
+        progress_callback.clone().map(|cb| {
+            Arc::new(move |p: f64| cb((pass_index as f64 + p) / 2.0))
+                as Arc<dyn Fn(f64) + Send + Sync>
+        })
+    };
+    let mut result = run_ffmpeg_async(ctx, commands.pass1, None, None, duration_secs, half(0))
+        .await
+        .and_then(|()| check_first_pass_log(&passlog))
+        .map(|()| 0);
+    if result.is_ok() {
+        let args = streaming_args(commands.pass2, &format);
+        result = stream_ffmpeg_to_writer(ctx, args, duration_secs, half(1), sink).await;
+    }
+    cleanup_passlog_files(&passlog);
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::RateControlMode;
+
+    /// Context whose "ffmpeg" is `sh`, so args are `-c <script>`.
+    fn sh_ctx() -> FfmpegContext {
+        FfmpegContext::new("/bin/sh")
+    }
+
+    fn sh_args(script: &str) -> Vec<String> {
+        vec!["-c".to_string(), script.to_string()]
+    }
+
+    #[tokio::test]
+    async fn reports_progress_and_success() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let cb: Arc<dyn Fn(f64) + Send + Sync> = {
+            let seen = Arc::clone(&seen);
+            Arc::new(move |p| seen.lock().push(p))
+        };
+        let result = run_ffmpeg_async(
+            &sh_ctx(),
+            sh_args("printf 'out_time_ms=500000\\nprogress=continue\\n'"),
+            None,
+            None,
+            Some(1.0),
+            Some(cb),
+        )
+        .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(seen.lock().first().copied(), Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn non_zero_exit_is_ffmpeg_failed_with_stderr() {
+        let result = run_ffmpeg_async(
+            &sh_ctx(),
+            sh_args("echo 'Invalid argument' >&2; exit 3"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await;
+        match result {
+            Err(AppError::FfmpegFailed { code, stderr }) => {
+                assert_eq!(code, 3);
+                assert!(stderr.contains("Invalid argument"));
+            }
+            other => panic!("expected FfmpegFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_token_aborts_the_run() {
+        let token = CancellationToken::new();
+        let ctx = sh_ctx().with_cancellation(token.clone());
+        let canceller = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            token.cancel();
+        };
+        let (result, ()) = tokio::join!(
+            run_ffmpeg_async(&ctx, sh_args("sleep 30"), None, None, None, None),
+            canceller
+        );
+        assert!(matches!(result, Err(AppError::Aborted)), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn timeout_drops_the_run() {
+        let result = tokio::time::timeout(
+            Duration::from_millis(100),
+            run_ffmpeg_async(&sh_ctx(), sh_args("sleep 30"), None, None, None, None),
+        )
+        .await;
+        assert!(result.is_err(), "expected timeout to elapse");
+    }
+
+    #[tokio::test]
+    async fn stream_copies_stdout_and_reads_progress_from_stderr() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let cb: Arc<dyn Fn(f64) + Send + Sync> = {
+            let seen = Arc::clone(&seen);
+            Arc::new(move |p| seen.lock().push(p))
+        };
+        let mut sink = Vec::new();
+        let written = stream_ffmpeg_to_writer(
+            &sh_ctx(),
+            sh_args("printf 'encoded'; printf 'out_time_ms=500000\\nprogress=continue\\n' >&2"),
+            Some(1.0),
+            Some(cb),
+            &mut sink,
+        )
+        .await
+        .expect("stream");
+        assert_eq!(written, 7);
+        assert_eq!(sink, b"encoded");
+        assert_eq!(seen.lock().first().copied(), Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn stdin_source_is_fed_to_the_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let script = format!("cat > '{}'", out.display());
+        let mut source: &[u8] = b"piped input";
+        run_with_stdin(
+            &sh_ctx(),
+            sh_args(&script),
+            (None, None),
+            None,
+            None,
+            Some(&mut source),
+        )
+        .await
+        .expect("run");
+        assert_eq!(std::fs::read(&out).unwrap(), b"piped input");
+    }
+
+    #[tokio::test]
+    async fn piped_input_rejects_two_pass_target_size() {
+        let options = TranscodeOptions {
+            codec: Some("libx264".into()),
+            rate_control_mode: Some(RateControlMode::TargetSize),
+            ..Default::default()
+        };
+        let mut source: &[u8] = b"";
+        let result = transcode_from_reader(
+            &sh_ctx(),
+            &mut source,
+            Path::new("out.mp4"),
+            &options,
+            Some(1.0),
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn streaming_args_fragment_mp4_and_name_the_muxer() {
+        let args: Vec<String> = [
+            "-progress",
+            "pipe:1",
+            "-i",
+            "in.mp4",
+            "-movflags",
+            "+faststart",
+            "pipe:1",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let args = streaming_args(args, "mp4");
+        assert_eq!(args[1], "pipe:2");
+        assert_eq!(args[5], "+frag_keyframe+empty_moov+default_base_moof");
+        assert_eq!(args[args.len() - 3..], ["-f", "mp4", "pipe:1"]);
+
+        let args = streaming_args(vec!["-i".into(), "in".into(), "pipe:1".into()], "mkv");
+        assert_eq!(args[args.len() - 2], "matroska");
+    }
+}
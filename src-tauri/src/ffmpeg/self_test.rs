@@ -0,0 +1,326 @@
+//! Installation self-test: checks that ffmpeg/ffprobe spawn, every codec the app advertises
+//! (see `get_build_variant`) can encode a short test source, and the temp dir jobs use is
+//! writable.
+//!
+//! Meant for diagnosing broken installs and LGPL-variant packaging mistakes; each check is
+//! reported independently so one failure does not hide the others.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use serde::Serialize;
+
+use super::builder::hw_encoder_args;
+use super::discovery::get_available_codecs_for;
+use super::hw_probe::filter_working_encoders;
+use super::{FfmpegContext, TempFileManager};
+use crate::codec::{GIF_CODEC, get_build_variant, get_codec_info};
+
+/// Seconds of `testsrc` encoded per codec.
+const TEST_SOURCE_SECONDS: u32 = 1;
+
+/// Result of a single self-test check.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestCheck {
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl SelfTestCheck {
+    fn pass(detail: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(detail: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Encode result for one advertised codec.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CodecSelfTest {
+    pub codec: String,
+    pub ok: bool,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub variant: &'static str,
+    pub passed: bool,
+    pub ffmpeg: SelfTestCheck,
+    pub ffprobe: SelfTestCheck,
+    pub temp_dir: SelfTestCheck,
+    pub codec_detection: SelfTestCheck,
+    pub codecs: Vec<CodecSelfTest>,
+}
+
+impl SelfTestReport {
+    fn all_ok(&self) -> bool {
+        self.ffmpeg.ok
+            && self.ffprobe.ok
+            && self.temp_dir.ok
+            && self.codec_detection.ok
+            && self.codecs.iter().all(|c| c.ok)
+    }
+}
+
+fn build_variant() -> &'static str {
+    #[cfg(feature = "lgpl")]
+    return "lgpl";
+    #[cfg(not(feature = "lgpl"))]
+    return "standalone";
+}
+
+/// Runs `<binary> -version` and returns the first line of stdout.
+fn check_version(binary: &Path) -> SelfTestCheck {
+    let mut cmd = Command::new(binary);
+    cmd.arg("-version");
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let first = stdout.lines().next().unwrap_or("").trim();
+            SelfTestCheck::pass(format!("{} ({})", first, binary.display()))
+        }
+        Ok(output) => SelfTestCheck::fail(format!(
+            "{} -version exited with {}: {}",
+            binary.display(),
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => SelfTestCheck::fail(format!("Failed to spawn {}: {}", binary.display(), e)),
+    }
+}
+
+fn check_temp_dir(dir: &Path) -> SelfTestCheck {
+    let path = match TempFileManager.create_in(dir, "self-test.txt", Some(b"tiny-vid")) {
+        Ok(p) => p,
+        Err(e) => {
+            return SelfTestCheck::fail(format!("Cannot write to {}: {}", dir.display(), e));
+        }
+    };
+    let result = match fs::read(&path) {
+        Ok(data) if data == b"tiny-vid" => SelfTestCheck::pass(dir.display().to_string()),
+        Ok(_) => SelfTestCheck::fail(format!("Read-back mismatch for {}", path.display())),
+        Err(e) => SelfTestCheck::fail(format!("Cannot read {}: {}", path.display(), e)),
+    };
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Args encoding a short `testsrc` with `codec` into `output`. Hardware encoders get the device
+/// and upload the app's encodes use; GIF takes palette pixel formats only, so FFmpeg picks one
+/// for it.
+fn build_codec_test_args(codec: &str, output: &str) -> Vec<String> {
+    let (device_args, upload_filter) = hw_encoder_args(codec);
+    let mut args: Vec<String> = ["-nostdin", "-y", "-v", "error"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.extend(device_args);
+    args.extend([
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("testsrc=duration={TEST_SOURCE_SECONDS}:size=320x240:rate=10"),
+    ]);
+    match upload_filter {
+        Some(filter) => args.extend(["-vf".to_string(), filter.to_string()]),
+        None if codec != GIF_CODEC => args.extend(["-pix_fmt".to_string(), "yuv420p".to_string()]),
+        None => {}
+    }
+    args.extend(["-c:v".to_string(), codec.to_string(), output.to_string()]);
+    args
+}
+
+fn encode_test_source(ctx: &FfmpegContext, codec: &str) -> CodecSelfTest {
+    let start = Instant::now();
+    let format = get_codec_info(codec)
+        .formats
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "mkv".to_string());
+    let result = ctx
+        .create_temp(&format!("self-test-{codec}.{format}"), None)
+        .map_err(|e| format!("Failed to create temp output: {e}"))
+        .and_then(|output_path| {
+            let args = build_codec_test_args(codec, &output_path.to_string_lossy());
+            let mut cmd = Command::new(ctx.ffmpeg_path());
+            cmd.args(&args);
+            #[cfg(windows)]
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            let outcome = match cmd.output() {
+                Ok(output) if output.status.success() => match fs::metadata(&output_path) {
+                    Ok(meta) if meta.len() > 0 => Ok(()),
+                    _ => Err("Encode succeeded but produced no output".to_string()),
+                },
+                Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+                Err(e) => Err(format!("Failed to spawn ffmpeg: {e}")),
+            };
+            let _ = fs::remove_file(&output_path);
+            outcome
+        });
+    CodecSelfTest {
+        codec: codec.to_string(),
+        ok: result.is_ok(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        error: result.err(),
+    }
+}
+
+/// Advertised codecs of `ctx`'s FFmpeg, as `get_build_variant` reports them: hardware encoders
+/// that failed their probe and VA-API without a render node are left out.
+fn advertised_codecs(ctx: &FfmpegContext) -> Result<Vec<String>, String> {
+    let available = get_available_codecs_for(ctx.ffmpeg_path()).map_err(|e| e.to_string())?;
+    let working = filter_working_encoders(ctx.ffmpeg_path(), available);
+    let variant = get_build_variant(working).map_err(|e| e.to_string())?;
+    Ok(variant.codecs.into_iter().map(|c| c.value).collect())
+}
+
+/// Runs all self-test checks against the FFmpeg and temp dir jobs use. Blocking; spawns ffmpeg
+/// once per advertised codec.
+pub fn run_self_test() -> SelfTestReport {
+    log::info!(target: "tiny_vid::ffmpeg::self_test", "run_self_test: starting");
+    let ctx = FfmpegContext::discover();
+    let ffmpeg = match &ctx {
+        Ok(ctx) => check_version(ctx.ffmpeg_path()),
+        Err(e) => SelfTestCheck::fail(e.to_string()),
+    };
+    let ffprobe = match &ctx {
+        Ok(ctx) => match ctx.ffprobe_path() {
+            Ok(path) => check_version(&path),
+            Err(e) => SelfTestCheck::fail(e.to_string()),
+        },
+        Err(e) => SelfTestCheck::fail(e.to_string()),
+    };
+    let temp_dir = check_temp_dir(
+        &ctx.as_ref()
+            .map_or_else(|_| std::env::temp_dir(), FfmpegContext::temp_dir),
+    );
+
+    let (codec_detection, codecs) = match (&ctx, ffmpeg.ok) {
+        (Ok(ctx), true) => match advertised_codecs(ctx) {
+            Ok(advertised) => {
+                let results = advertised
+                    .iter()
+                    .map(|codec| encode_test_source(ctx, codec))
+                    .collect();
+                (SelfTestCheck::pass(advertised.join(", ")), results)
+            }
+            Err(e) => (SelfTestCheck::fail(e), Vec::new()),
+        },
+        _ => (
+            SelfTestCheck::fail("Skipped: ffmpeg is not runnable"),
+            Vec::new(),
+        ),
+    };
+
+    let mut report = SelfTestReport {
+        variant: build_variant(),
+        passed: false,
+        ffmpeg,
+        ffprobe,
+        temp_dir,
+        codec_detection,
+        codecs,
+    };
+    report.passed = report.all_ok();
+    log::info!(
+        target: "tiny_vid::ffmpeg::self_test",
+        "run_self_test: passed={}, codecs={:?}",
+        report.passed,
+        report
+            .codecs
+            .iter()
+            .map(|c| (c.codec.as_str(), c.ok))
+            .collect::<Vec<_>>()
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passing_report() -> SelfTestReport {
+        SelfTestReport {
+            variant: build_variant(),
+            passed: false,
+            ffmpeg: SelfTestCheck::pass("ffmpeg"),
+            ffprobe: SelfTestCheck::pass("ffprobe"),
+            temp_dir: SelfTestCheck::pass("/tmp"),
+            codec_detection: SelfTestCheck::pass("libx264"),
+            codecs: vec![CodecSelfTest {
+                codec: "libx264".to_string(),
+                ok: true,
+                elapsed_ms: 10,
+                error: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn all_ok_requires_every_check() {
+        assert!(passing_report().all_ok());
+
+        let mut report = passing_report();
+        report.ffprobe = SelfTestCheck::fail("missing");
+        assert!(!report.all_ok());
+
+        let mut report = passing_report();
+        report.codecs[0].ok = false;
+        assert!(!report.all_ok());
+    }
+
+    #[test]
+    fn codec_test_args_encode_testsrc_with_codec() {
+        let args = build_codec_test_args("libsvtav1", "/tmp/out.mp4");
+        let c_idx = args.iter().position(|a| a == "-c:v").unwrap();
+        assert_eq!(args[c_idx + 1], "libsvtav1");
+        assert!(args.iter().any(|a| a.starts_with("testsrc=duration=1")));
+        assert_eq!(args.last().unwrap(), "/tmp/out.mp4");
+
+        let args = build_codec_test_args("gif", "/tmp/out.gif");
+        assert!(!args.contains(&"-pix_fmt".to_string()));
+    }
+
+    #[test]
+    fn codec_test_args_set_up_hardware_devices() {
+        let args = build_codec_test_args("hevc_qsv", "/tmp/out.mp4");
+        let init = args.iter().position(|a| a == "-init_hw_device").unwrap();
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert!(init < input);
+        let vf = args.iter().position(|a| a == "-vf").unwrap();
+        assert!(args[vf + 1].contains("hwupload"));
+        assert!(!args.contains(&"-pix_fmt".to_string()));
+
+        let args = build_codec_test_args("h264_vaapi", "/tmp/out.mp4");
+        assert!(args.contains(&"-vaapi_device".to_string()));
+    }
+
+    #[test]
+    fn temp_dir_check_passes_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = check_temp_dir(dir.path());
+        assert!(check.ok, "{}", check.detail);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,196 @@
+//! Silence detection for jump-cut exports of long talks and meeting recordings.
+//!
+//! `silencedetect` runs over the first audio stream; the silent ranges (shrunk by a little
+//! padding so speech is not clipped) are inverted into the ranges to keep. Those go into
+//! `TranscodeOptions::keep_segments`, which the export applies with `select`/`aselect`.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::ffprobe::get_video_metadata_in;
+use super::{FfmpegContext, path_to_string, run_ffmpeg_capture};
+use crate::error::AppError;
+
+const DEFAULT_NOISE_DB: f64 = -35.0;
+const DEFAULT_MIN_SILENCE_SECS: f64 = 0.75;
+const DEFAULT_PADDING_SECS: f64 = 0.15;
+/// Kept ranges shorter than this are dropped (a lone cough between two silences).
+const MIN_KEEP_SECS: f64 = 0.1;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceAnalysis {
+    /// Detected silent ranges `[start, end]` in seconds.
+    pub silences: Vec<(f64, f64)>,
+    /// Ranges to keep; pass as `keepSegments` to the transcode.
+    pub keep_segments: Vec<(f64, f64)>,
+    pub source_seconds: f64,
+    /// Time cut by `keep_segments`.
+    pub removed_seconds: f64,
+}
+
+/// Number after `key` on a silencedetect line, e.g. `silence_end: 3.5 | ...` -> 3.5.
+pub(super) fn value_after(line: &str, key: &str) -> Option<f64> {
+    let rest = &line[line.find(key)? + key.len()..];
+    rest.split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// Pairs `silence_start`/`silence_end` lines; a trailing start without an end runs to `duration`.
+fn parse_silencedetect(stderr: &str, duration: f64) -> Vec<(f64, f64)> {
+    let mut silences = Vec::new();
+    let mut open: Option<f64> = None;
+    for line in stderr.lines().filter(|l| l.contains("silencedetect")) {
+        if let Some(start) = value_after(line, "silence_start:") {
+            open = Some(start.max(0.0));
+        } else if let Some(end) = value_after(line, "silence_end:")
+            && let Some(start) = open.take()
+        {
+            silences.push((start, end.min(duration)));
+        }
+    }
+    if let Some(start) = open.filter(|s| *s < duration) {
+        silences.push((start, duration));
+    }
+    silences
+}
+
+/// Inverts `silences` into the ranges to keep, leaving `padding` of each silence next to speech.
+fn keep_segments(silences: &[(f64, f64)], duration: f64, padding: f64) -> Vec<(f64, f64)> {
+    let mut keep = Vec::new();
+    let mut cursor = 0.0;
+    for &(start, end) in silences {
+        let cut_start = if start <= 0.0 { 0.0 } else { start + padding };
+        let cut_end = if end >= duration {
+            duration
+        } else {
+            end - padding
+        };
+        if cut_end <= cut_start {
+            continue;
+        }
+        if cut_start - cursor >= MIN_KEEP_SECS {
+            keep.push((cursor, cut_start));
+        }
+        cursor = cut_end;
+    }
+    if duration - cursor >= MIN_KEEP_SECS {
+        keep.push((cursor, duration));
+    }
+    keep
+}
+
+fn build_silencedetect_args(path: &str, noise_db: f64, min_silence_secs: f64) -> Vec<String> {
+    [
+        "-nostdin",
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        path,
+        "-map",
+        "0:a:0",
+        "-af",
+        &format!("silencedetect=noise={}dB:d={}", noise_db, min_silence_secs),
+        "-f",
+        "null",
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Detects silences in the first audio stream of `path` and the ranges to keep. Blocking.
+/// Defaults: -35 dB noise floor, 0.75 s minimum silence, 0.15 s padding.
+pub fn analyze_silence(
+    ctx: &FfmpegContext,
+    path: &Path,
+    noise_db: Option<f64>,
+    min_silence_secs: Option<f64>,
+    padding_secs: Option<f64>,
+) -> Result<SilenceAnalysis, AppError> {
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    if meta.audio_stream_count == 0 {
+        return Err(AppError::from(
+            "The video has no audio to detect silence in",
+        ));
+    }
+    if meta.duration <= 0.0 {
+        return Err(AppError::from("Video duration is unknown"));
+    }
+    let noise_db = noise_db
+        .filter(|v| v.is_finite())
+        .unwrap_or(DEFAULT_NOISE_DB)
+        .clamp(-90.0, 0.0);
+    let min_silence_secs = min_silence_secs
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .unwrap_or(DEFAULT_MIN_SILENCE_SECS);
+    let padding = padding_secs
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .unwrap_or(DEFAULT_PADDING_SECS)
+        .min(min_silence_secs / 2.0);
+
+    let args = build_silencedetect_args(&path_to_string(path), noise_db, min_silence_secs);
+    let stderr = run_ffmpeg_capture(ctx, args)?;
+
+    let silences = parse_silencedetect(&stderr, meta.duration);
+    let keep_segments = keep_segments(&silences, meta.duration, padding);
+    let kept: f64 = keep_segments.iter().map(|(s, e)| e - s).sum();
+    log::info!(
+        target: "tiny_vid::ffmpeg::silence",
+        "analyze_silence: {} silence(s), keeping {:.2}s of {:.2}s",
+        silences.len(),
+        kept,
+        meta.duration
+    );
+    Ok(SilenceAnalysis {
+        silences,
+        keep_segments,
+        source_seconds: meta.duration,
+        removed_seconds: (meta.duration - kept).max(0.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SILENCEDETECT_STDERR: &str = "\
+[silencedetect @ 0x600] silence_start: 0
+[silencedetect @ 0x600] silence_end: 1.5 | silence_duration: 1.5
+[silencedetect @ 0x600] silence_start: 10.25
+[silencedetect @ 0x600] silence_end: 14.25 | silence_duration: 4
+[silencedetect @ 0x600] silence_start: 28
+";
+
+    #[test]
+    fn parse_silencedetect_pairs_ranges_and_closes_at_eof() {
+        assert_eq!(
+            parse_silencedetect(SILENCEDETECT_STDERR, 30.0),
+            vec![(0.0, 1.5), (10.25, 14.25), (28.0, 30.0)]
+        );
+    }
+
+    #[test]
+    fn keep_segments_pads_speech_and_skips_edges() {
+        let silences = vec![(0.0, 1.5), (10.25, 14.25), (28.0, 30.0)];
+        assert_eq!(
+            keep_segments(&silences, 30.0, 0.25),
+            vec![(1.25, 10.5), (14.0, 28.25)]
+        );
+    }
+
+    #[test]
+    fn keep_segments_drops_slivers_and_no_silence_keeps_all() {
+        assert_eq!(keep_segments(&[], 12.0, 0.15), vec![(0.0, 12.0)]);
+        let silences = vec![(1.0, 5.0), (5.05, 9.0)];
+        assert_eq!(
+            keep_segments(&silences, 10.0, 0.0),
+            vec![(0.0, 1.0), (9.0, 10.0)]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Storyboard: a sprite sheet of evenly spaced frames plus a JSON index, for hover previews
+//! on the seek bar.
+//!
+//! Unlike the contact sheet, the sprite comes from one decode of the source: `select` keeps a
+//! frame every `interval` seconds and `tile` packs them row by row. Tile `i` shows the source
+//! from `i * interval`, so the frame for a seek position is `floor(t / interval)`. Both files
+//! live in temp and are cached with the previews, keyed by file signature.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::cache::{file_signature, get_cached_storyboard, set_cached_storyboard};
+use super::contact_sheet::tile_height;
+use super::ffprobe::get_video_metadata_in;
+use super::{FfmpegContext, path_to_string, run_ffmpeg_blocking};
+use crate::error::AppError;
+
+const DEFAULT_FRAMES: u32 = 100;
+const MAX_FRAMES: u32 = 400;
+const DEFAULT_TILE_WIDTH: u32 = 160;
+const MIN_TILE_WIDTH: u32 = 64;
+const MAX_TILE_WIDTH: u32 = 480;
+const COLUMNS: u32 = 10;
+
+/// Where one frame sits in the sprite.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryboardTile {
+    /// Source seconds the tile starts showing.
+    pub time: f64,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Contents of the JSON index written next to the sprite.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryboardIndex {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Seconds between tiles.
+    pub interval_secs: f64,
+    pub duration_secs: f64,
+    pub tiles: Vec<StoryboardTile>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Storyboard {
+    /// Temp JPEG holding every tile.
+    pub sprite_path: String,
+    /// Temp JSON file with `index`.
+    pub index_path: String,
+    #[serde(flatten)]
+    pub index: StoryboardIndex,
+}
+
+/// Grid for `frames` tiles of `tile_width` px over `duration` seconds.
+fn layout(duration: f64, frames: u32, tile_width: u32, tile_height: u32) -> StoryboardIndex {
+    let columns = frames.min(COLUMNS);
+    let rows = frames.div_ceil(columns);
+    let interval_secs = duration / frames as f64;
+    let tiles = (0..frames)
+        .map(|i| StoryboardTile {
+            time: i as f64 * interval_secs,
+            x: (i % columns) * tile_width,
+            y: (i / columns) * tile_height,
+        })
+        .collect();
+    StoryboardIndex {
+        columns,
+        rows,
+        tile_width,
+        tile_height,
+        interval_secs,
+        duration_secs: duration,
+        tiles,
+    }
+}
+
+fn build_storyboard_args(
+    input_path: &str,
+    output_path: &str,
+    index: &StoryboardIndex,
+) -> Vec<String> {
+    vec![
+        "-nostdin".to_string(),
+        "-y".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-vf".to_string(),
+        format!(
+            "select='isnan(prev_selected_t)+gte(t-prev_selected_t,{:.3})',\
+             scale={w}:{h}:force_original_aspect_ratio=decrease,\
+             pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,tile={}x{}",
+            index.interval_secs,
+            index.columns,
+            index.rows,
+            w = index.tile_width,
+            h = index.tile_height,
+        ),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-q:v".to_string(),
+        "5".to_string(),
+        "-f".to_string(),
+        "mjpeg".to_string(),
+        output_path.to_string(),
+    ]
+}
+
+/// Renders (or returns the cached) storyboard of `frames` tiles (default 100), each
+/// `tile_width` px wide (default 160). Blocking.
+pub fn generate_storyboard(
+    ctx: &FfmpegContext,
+    path: &Path,
+    frames: Option<u32>,
+    tile_width: Option<u32>,
+) -> Result<Storyboard, AppError> {
+    let frames = frames.unwrap_or(DEFAULT_FRAMES).clamp(1, MAX_FRAMES);
+    let tile_width = tile_width
+        .unwrap_or(DEFAULT_TILE_WIDTH)
+        .clamp(MIN_TILE_WIDTH, MAX_TILE_WIDTH)
+        & !1;
+    let input_str = path_to_string(path);
+    let signature = file_signature(path);
+    if let Some(cached) = get_cached_storyboard(&input_str, frames, tile_width, signature.as_ref())
+    {
+        return Ok(cached);
+    }
+
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    if meta.duration <= 0.0 {
+        return Err(AppError::from("Video duration is unknown"));
+    }
+    let tile_h = tile_height(tile_width, meta.width, meta.height);
+    let index = layout(meta.duration, frames, tile_width, tile_h);
+
+    let sprite = ctx.create_temp("storyboard.jpg", None)?;
+    let args = build_storyboard_args(&input_str, &path_to_string(&sprite), &index);
+    if let Err(e) = run_ffmpeg_blocking(ctx, args, None, None, None, None, None) {
+        let _ = fs::remove_file(&sprite);
+        return Err(e);
+    }
+    let json = serde_json::to_vec(&index).map_err(|e| AppError::from(e.to_string()))?;
+    let index_file = match ctx.create_temp("storyboard.json", Some(&json)) {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = fs::remove_file(&sprite);
+            return Err(e.into());
+        }
+    };
+    log::info!(
+        target: "tiny_vid::ffmpeg::storyboard",
+        "generate_storyboard: {} tiles ({}x{}) -> {}",
+        frames,
+        index.columns,
+        index.rows,
+        sprite.display()
+    );
+    let storyboard = Storyboard {
+        sprite_path: path_to_string(&sprite),
+        index_path: path_to_string(&index_file),
+        index,
+    };
+    set_cached_storyboard(
+        &input_str,
+        frames,
+        tile_width,
+        signature.as_ref(),
+        storyboard.clone(),
+    );
+    Ok(storyboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_fills_rows_and_spaces_tiles_evenly() {
+        let index = layout(50.0, 25, 160, 90);
+        assert_eq!((index.columns, index.rows), (10, 3));
+        assert_eq!(index.interval_secs, 2.0);
+        assert_eq!(
+            index.tiles[12],
+            StoryboardTile {
+                time: 24.0,
+                x: 320,
+                y: 90
+            }
+        );
+        assert_eq!(index.tiles.len(), 25);
+
+        let short = layout(3.0, 4, 160, 90);
+        assert_eq!((short.columns, short.rows), (4, 1));
+    }
+
+    #[test]
+    fn storyboard_args_select_scale_and_tile_in_one_pass() {
+        let index = layout(200.0, 100, 160, 90);
+        let args = build_storyboard_args("/in.mp4", "/sprite.jpg", &index);
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 1);
+        let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+        assert!(vf.starts_with("select='isnan(prev_selected_t)+gte(t-prev_selected_t,2.000)'"));
+        assert!(vf.contains("scale=160:90:force_original_aspect_ratio=decrease"));
+        assert!(vf.ends_with("tile=10x10"));
+        assert_eq!(args.last().unwrap(), "/sprite.jpg");
+    }
+}
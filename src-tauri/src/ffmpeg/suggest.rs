@@ -0,0 +1,439 @@
+//! Content-aware option suggestions: a short, downscaled sample is run through scene-change
+//! scoring (how much moves) and bit-plane noise (how grainy it is), then classified as
+//! screencast, animation or film.
+//!
+//! The thresholds are heuristics; the UI presents the result as a one-click suggestion.
+//!
+//! `suggest_quality` needs no sample: it compares the source's bits per pixel with what the
+//! codec typically spends at a given quality, and lowers the quality for sources that are
+//! already lean so the output does not come out bigger than the input.
+//!
+//! `generation_loss_advisory` uses the same comparison to warn before a lossy-to-lossy
+//! re-encode that would cost quality for little size: the source is far below what its own
+//! codec usually spends, or the target is the same codec and would keep most of the bitrate.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::compression::source_bits_per_pixel;
+use super::ffprobe::{VideoMetadata, get_video_metadata_in};
+use super::{
+    ContentType, FfmpegContext, RoiPriority, TranscodeOptions, path_to_string, run_ffmpeg_capture,
+};
+use crate::codec::is_hardware_codec;
+use crate::error::AppError;
+use crate::i18n::t;
+
+const SAMPLE_SECS: f64 = 15.0;
+/// Mean scene score below which content is treated as mostly static.
+const LOW_MOTION: f64 = 0.02;
+/// Mean bit-plane noise of luma bit 1 below which content is clean (synthetic).
+const CLEAN_NOISE: f64 = 0.35;
+/// Above this, film gets `grain` instead of `film`.
+const GRAINY_NOISE: f64 = 0.6;
+
+/// bpp libx264 typically spends on 1080p at the default quality.
+const REFERENCE_BPP: f64 = 0.1;
+const REFERENCE_PIXELS: f64 = 1920.0 * 1080.0;
+/// Quality points that halve (or double) the output bitrate.
+const QUALITY_POINTS_PER_DOUBLING: f64 = 25.0;
+/// Suggested output stays below this share of the source bpp.
+const MAX_SHARE_OF_SOURCE: f64 = 0.7;
+/// Lowest quality ever suggested; below it artifacts outweigh the savings.
+const MIN_SUGGESTED_QUALITY: u32 = 30;
+/// Source bpp below this share of what its codec typically spends counts as heavily compressed.
+const HEAVILY_COMPRESSED_SHARE: f64 = 0.6;
+/// A same-codec target expected to keep at least this share of the source bpp saves too little.
+const LITTLE_BENEFIT_SHARE: f64 = 0.75;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentKind {
+    Screencast,
+    Animation,
+    Film,
+}
+
+/// Options the UI can apply as-is; unset fields keep the user's values.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedOptions {
+    pub content_kind: ContentKind,
+    pub content_type: ContentType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tune: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roi_priority: Option<RoiPriority>,
+    /// Mean scene-change score (0-1) of the sample.
+    pub motion_score: f64,
+    /// Mean bit-plane noise (0-1) of the sample.
+    pub noise_level: f64,
+}
+
+/// Recommended quality for a source and codec.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QualitySuggestion {
+    pub quality: u32,
+    /// None when the source's bit rate is unknown; `quality` is then the default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_bits_per_pixel: Option<f64>,
+    /// Expected output bpp at `quality`.
+    pub predicted_bits_per_pixel: f64,
+    /// True when even `quality` is predicted to produce a file larger than the source.
+    pub output_may_exceed_input: bool,
+}
+
+/// Bits `codec` needs relative to libx264 for the same look.
+fn codec_efficiency(codec: &str) -> f64 {
+    let hardware = if is_hardware_codec(codec) { 1.3 } else { 1.0 };
+    let family = if codec.contains("av1") {
+        0.5
+    } else if codec.contains("265") || codec.contains("hevc") || codec.contains("vp9") {
+        0.65
+    } else {
+        1.0
+    };
+    family * hardware
+}
+
+/// Typical output bpp of `codec` at `quality` for frames of `pixels`. Smaller frames need more
+/// bits per pixel (size grows about as pixels^0.75).
+fn predicted_bpp(codec: &str, quality: u32, pixels: f64) -> f64 {
+    let default_quality = TranscodeOptions::default().effective_quality() as f64;
+    let resolution = (REFERENCE_PIXELS / pixels.max(1.0)).powf(0.25);
+    REFERENCE_BPP
+        * codec_efficiency(codec)
+        * resolution
+        * 2f64.powf((quality as f64 - default_quality) / QUALITY_POINTS_PER_DOUBLING)
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GenerationLossKind {
+    /// The source is far below the bpp its codec typically spends.
+    HeavilyCompressed,
+    /// The target is the source's codec and would keep most of its bitrate.
+    SameCodecSimilarBitrate,
+}
+
+/// Re-encoding the source is likely to lose quality for little size; a remux keeps the picture.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationLossAdvisory {
+    pub kind: GenerationLossKind,
+    pub source_bits_per_pixel: f64,
+    /// What the source's codec typically spends on its frame size at the default quality.
+    pub typical_bits_per_pixel: f64,
+    /// Expected output bpp of the target codec at the default quality, when one was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_bits_per_pixel: Option<f64>,
+    /// In the current locale.
+    pub message: String,
+}
+
+/// Codec family of an ffprobe codec name or an encoder name (`hevc`, `libx265`, ...).
+fn codec_family(codec: &str) -> &str {
+    if codec.contains("av1") {
+        "av1"
+    } else if codec.contains("265") || codec.contains("hevc") {
+        "hevc"
+    } else if codec.contains("vp9") {
+        "vp9"
+    } else if codec.contains("264") {
+        "h264"
+    } else {
+        codec
+    }
+}
+
+/// Advisory against re-encoding the source described by `meta`, optionally with
+/// `target_codec`. None when the source's bit rate or codec is unknown or re-encoding pays off.
+pub fn generation_loss_advisory(
+    meta: &VideoMetadata,
+    target_codec: Option<&str>,
+) -> Option<GenerationLossAdvisory> {
+    let source_bpp = source_bits_per_pixel(meta)?;
+    let source_codec = meta.codec_name.as_deref()?;
+    let default_quality = TranscodeOptions::default().effective_quality();
+    let pixels = meta.width as f64 * meta.height as f64;
+    let typical = predicted_bpp(source_codec, default_quality, pixels);
+    let target_bpp = target_codec.map(|codec| predicted_bpp(codec, default_quality, pixels));
+    let same_codec_similar = target_codec.zip(target_bpp).is_some_and(|(codec, bpp)| {
+        codec_family(codec) == codec_family(source_codec)
+            && bpp >= source_bpp * LITTLE_BENEFIT_SHARE
+    });
+    let kind = if same_codec_similar {
+        GenerationLossKind::SameCodecSimilarBitrate
+    } else if source_bpp < typical * HEAVILY_COMPRESSED_SHARE {
+        GenerationLossKind::HeavilyCompressed
+    } else {
+        return None;
+    };
+    Some(GenerationLossAdvisory {
+        kind,
+        source_bits_per_pixel: source_bpp,
+        typical_bits_per_pixel: typical,
+        target_bits_per_pixel: target_bpp,
+        message: t("advisory.generation_loss").to_string(),
+    })
+}
+
+fn quality_for_source(meta: &VideoMetadata, codec: &str) -> QualitySuggestion {
+    let default_quality = TranscodeOptions::default().effective_quality();
+    let pixels = meta.width as f64 * meta.height as f64;
+    let source_bpp = source_bits_per_pixel(meta);
+    let quality = match source_bpp {
+        Some(source) => {
+            let budget = source * MAX_SHARE_OF_SOURCE;
+            let at_default = predicted_bpp(codec, default_quality, pixels);
+            let steps = (budget / at_default).log2() * QUALITY_POINTS_PER_DOUBLING;
+            (default_quality as f64 + steps)
+                .floor()
+                .clamp(MIN_SUGGESTED_QUALITY as f64, default_quality as f64) as u32
+        }
+        None => default_quality,
+    };
+    let predicted = predicted_bpp(codec, quality, pixels);
+    QualitySuggestion {
+        quality,
+        source_bits_per_pixel: source_bpp,
+        predicted_bits_per_pixel: predicted,
+        output_may_exceed_input: source_bpp.is_some_and(|source| predicted >= source),
+    }
+}
+
+/// Suggests a quality for encoding `path` with `codec` from its metadata alone. Blocking.
+pub fn suggest_quality(
+    ctx: &FfmpegContext,
+    path: &Path,
+    codec: &str,
+) -> Result<QualitySuggestion, AppError> {
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    let suggestion = quality_for_source(&meta, codec);
+    log::info!(
+        target: "tiny_vid::ffmpeg::suggest",
+        "suggest_quality: {} -> {} (source bpp {:?}, predicted {:.4}, may grow: {})",
+        codec,
+        suggestion.quality,
+        suggestion.source_bits_per_pixel,
+        suggestion.predicted_bits_per_pixel,
+        suggestion.output_may_exceed_input
+    );
+    Ok(suggestion)
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Mean scene score and noise from `metadata=print` lines.
+fn parse_sample_stats(stderr: &str) -> Option<(f64, f64)> {
+    let mut scene = Vec::new();
+    let mut noise = Vec::new();
+    for line in stderr.lines() {
+        let Some((key, value)) = line.rsplit_once('=') else {
+            continue;
+        };
+        let Ok(value) = value.trim().parse::<f64>() else {
+            continue;
+        };
+        if !value.is_finite() {
+            continue;
+        }
+        if key.ends_with("lavfi.scene_score") {
+            scene.push(value);
+        } else if key.ends_with("lavfi.bitplanenoise.0.1") {
+            noise.push(value);
+        }
+    }
+    Some((mean(&scene)?, mean(&noise)?))
+}
+
+fn classify(motion: f64, noise: f64, screen_recorder_tag: bool) -> SuggestedOptions {
+    let content_kind = if screen_recorder_tag || (motion < LOW_MOTION && noise < CLEAN_NOISE) {
+        ContentKind::Screencast
+    } else if noise < CLEAN_NOISE {
+        ContentKind::Animation
+    } else {
+        ContentKind::Film
+    };
+    let (content_type, tune, roi_priority) = match content_kind {
+        ContentKind::Screencast => (ContentType::ScreenRecording, None, Some(RoiPriority::Text)),
+        ContentKind::Animation => (ContentType::General, Some("animation"), None),
+        ContentKind::Film if noise > GRAINY_NOISE => (ContentType::General, Some("grain"), None),
+        ContentKind::Film => (ContentType::General, Some("film"), None),
+    };
+    SuggestedOptions {
+        content_kind,
+        content_type,
+        tune: tune.map(String::from),
+        roi_priority,
+        motion_score: motion,
+        noise_level: noise,
+    }
+}
+
+fn build_sample_args(path: &str, start: f64, len: f64) -> Vec<String> {
+    let mut args = vec!["-nostdin".to_string(), "-hide_banner".to_string()];
+    if start > 0.0 {
+        args.extend(["-ss".to_string(), start.to_string()]);
+    }
+    args.extend([
+        "-t".to_string(),
+        len.to_string(),
+        "-i".to_string(),
+        path.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        "scale=320:-2,select='gte(scene,0)',bitplanenoise,metadata=mode=print".to_string(),
+        "-an".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]);
+    args
+}
+
+/// Samples `path` and suggests content-aware options. Blocking.
+pub fn suggest_options(ctx: &FfmpegContext, path: &Path) -> Result<SuggestedOptions, AppError> {
+    let meta = get_video_metadata_in(ctx, path, false)?;
+    // Skip intros: sample from 10% in, but keep the window inside the video.
+    let start = (meta.duration * 0.1)
+        .min(meta.duration - SAMPLE_SECS)
+        .max(0.0);
+    let args = build_sample_args(&path_to_string(path), start, SAMPLE_SECS);
+    let stderr = run_ffmpeg_capture(ctx, args)?;
+    let (motion, noise) = parse_sample_stats(&stderr)
+        .ok_or_else(|| AppError::from("No frame statistics in ffmpeg output"))?;
+    let suggestion = classify(motion, noise, meta.looks_like_screen_recording());
+    log::info!(
+        target: "tiny_vid::ffmpeg::suggest",
+        "suggest_options: {:?} (motion={:.4}, noise={:.3})",
+        suggestion.content_kind,
+        motion,
+        noise
+    );
+    Ok(suggestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA_STDERR: &str = "\
+[Parsed_metadata_3 @ 0x600] frame:0    pts:0       pts_time:0
+[Parsed_metadata_3 @ 0x600] lavfi.scene_score=0.000000
+[Parsed_metadata_3 @ 0x600] lavfi.bitplanenoise.0.1=0.200000
+[Parsed_metadata_3 @ 0x600] frame:1    pts:512     pts_time:0.04
+[Parsed_metadata_3 @ 0x600] lavfi.scene_score=0.040000
+[Parsed_metadata_3 @ 0x600] lavfi.bitplanenoise.0.1=0.300000
+";
+
+    #[test]
+    fn parse_sample_stats_averages_per_frame_values() {
+        let (motion, noise) = parse_sample_stats(METADATA_STDERR).unwrap();
+        assert!((motion - 0.02).abs() < 1e-9);
+        assert!((noise - 0.25).abs() < 1e-9);
+        assert_eq!(parse_sample_stats("no stats"), None);
+    }
+
+    fn source(video_bit_rate: u64) -> VideoMetadata {
+        VideoMetadata {
+            duration: 60.0,
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            video_bit_rate: Some(video_bit_rate),
+            ..VideoMetadata::default()
+        }
+    }
+
+    #[test]
+    fn suggest_quality_keeps_the_default_for_rich_sources() {
+        // ~0.32 bpp, a typical phone recording.
+        let s = quality_for_source(&source(20_000_000), "libx264");
+        assert_eq!(s.quality, 75);
+        assert!(!s.output_may_exceed_input);
+        assert!(s.predicted_bits_per_pixel < s.source_bits_per_pixel.unwrap());
+    }
+
+    #[test]
+    fn suggest_quality_lowers_quality_for_lean_sources() {
+        // ~0.05 bpp: the default would roughly double it.
+        let lean = source(3_000_000);
+        let x264 = quality_for_source(&lean, "libx264");
+        assert!(x264.quality < 75 && x264.quality >= MIN_SUGGESTED_QUALITY);
+        assert!(x264.predicted_bits_per_pixel <= 0.05 * MAX_SHARE_OF_SOURCE + 1e-9);
+        assert!(!x264.output_may_exceed_input);
+        // AV1 needs fewer bits, so it can keep more quality.
+        assert!(quality_for_source(&lean, "libsvtav1").quality > x264.quality);
+
+        let starved = quality_for_source(&source(300_000), "h264_nvenc");
+        assert_eq!(starved.quality, MIN_SUGGESTED_QUALITY);
+        assert!(starved.output_may_exceed_input);
+    }
+
+    #[test]
+    fn suggest_quality_falls_back_to_the_default_without_a_bit_rate() {
+        let unknown = VideoMetadata {
+            width: 1280,
+            height: 720,
+            fps: 30.0,
+            ..VideoMetadata::default()
+        };
+        let s = quality_for_source(&unknown, "libx265");
+        assert_eq!(s.quality, 75);
+        assert_eq!(s.source_bits_per_pixel, None);
+        assert!(!s.output_may_exceed_input);
+    }
+
+    #[test]
+    fn generation_loss_advisory_flags_lean_or_same_codec_sources() {
+        let h264 = |bit_rate| VideoMetadata {
+            codec_name: Some("h264".to_string()),
+            ..source(bit_rate)
+        };
+        // ~0.05 bpp of H.264 at 1080p: half of what x264 usually spends.
+        let lean = generation_loss_advisory(&h264(3_000_000), None).unwrap();
+        assert_eq!(lean.kind, GenerationLossKind::HeavilyCompressed);
+        assert!(!lean.message.is_empty());
+        // A typical phone recording re-encodes fine, to H.264 as well as to AV1.
+        assert_eq!(generation_loss_advisory(&h264(20_000_000), None), None);
+        assert_eq!(
+            generation_loss_advisory(&h264(20_000_000), Some("libx264")),
+            None
+        );
+
+        // ~0.11 bpp is ordinary for H.264, but x264 at the default would keep almost all of it.
+        let similar = generation_loss_advisory(&h264(7_000_000), Some("h264_nvenc")).unwrap();
+        assert_eq!(similar.kind, GenerationLossKind::SameCodecSimilarBitrate);
+        assert!(similar.target_bits_per_pixel.is_some());
+        assert_eq!(
+            generation_loss_advisory(&h264(7_000_000), Some("libsvtav1")),
+            None
+        );
+        assert_eq!(generation_loss_advisory(&source(3_000_000), None), None);
+    }
+
+    #[test]
+    fn classify_maps_motion_and_noise_to_content_kinds() {
+        let s = classify(0.005, 0.1, false);
+        assert_eq!(s.content_kind, ContentKind::Screencast);
+        assert_eq!(s.content_type, ContentType::ScreenRecording);
+        assert_eq!(s.roi_priority, Some(RoiPriority::Text));
+
+        let s = classify(0.1, 0.2, false);
+        assert_eq!(s.content_kind, ContentKind::Animation);
+        assert_eq!(s.tune.as_deref(), Some("animation"));
+
+        assert_eq!(classify(0.1, 0.5, false).tune.as_deref(), Some("film"));
+        assert_eq!(classify(0.1, 0.8, false).tune.as_deref(), Some("grain"));
+        assert_eq!(
+            classify(0.1, 0.8, true).content_kind,
+            ContentKind::Screencast
+        );
+    }
+}
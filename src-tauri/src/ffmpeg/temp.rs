@@ -2,35 +2,179 @@
 
 use std::fs;
 use std::io;
-use std::path::PathBuf;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use super::cache::get_all_cached_paths;
+use crate::error::AppError;
 use parking_lot::Mutex;
 
 static PREVIOUS_PREVIEW_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
-static TRANSCODE_TEMP_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Uncommitted transcode outputs. An entry leaves on commit (the caller moved the file into
+/// place), on discard or expiry (the file is deleted) or at app exit.
+static TRANSCODE_TEMPS: Mutex<Vec<PendingOutput>> = Mutex::new(Vec::new());
+/// Inputs of every job that registered an output this session. Commits, discards and cleanups
+/// check their targets against these, whether or not the caller names the source.
+static JOB_SOURCES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
 const TEMP_FILE_PREFIX: &str = "tiny-vid-";
+const JOB_TAG: &str = "job";
+/// Uncommitted outputs older than this are deleted and their commit tokens stop working.
+pub const PENDING_OUTPUT_TTL: Duration = Duration::from_secs(60 * 60);
 
-/// Set the current transcode temp path (for cleanup on exit or cancel).
-pub fn set_transcode_temp(path: Option<PathBuf>) {
-    let mut guard = TRANSCODE_TEMP_PATH.lock();
-    *guard = path;
+struct PendingOutput {
+    job_id: u64,
+    token: String,
+    path: PathBuf,
+    created: Instant,
 }
 
-/// Remove the transcode temp file if it exists. Call on app exit or when user cancels save.
-pub fn cleanup_transcode_temp() {
-    let mut guard = TRANSCODE_TEMP_PATH.lock();
-    if let Some(path) = guard.take() {
+/// A job that produces a transcode output. Its temp files carry `job<id>` in the name.
+pub struct TempJob {
+    id: u64,
+}
+
+impl TempJob {
+    pub fn start() -> Self {
+        Self {
+            id: NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// New temp file path in `dir` tagged with this job's id (see [`TempFileManager::create`]).
+    /// Jobs pass their context's [`temp_dir`](super::FfmpegContext::temp_dir).
+    pub fn create(&self, dir: &Path, suffix: &str, content: Option<&[u8]>) -> io::Result<PathBuf> {
+        TempFileManager.create_named(dir, Some(self.id), suffix, content)
+    }
+}
+
+/// Removes and returns the entries registered longer than `ttl` ago.
+fn take_expired(temps: &mut Vec<PendingOutput>, ttl: Duration) -> Vec<PendingOutput> {
+    let (expired, kept) = temps
+        .drain(..)
+        .partition(|p: &PendingOutput| p.created.elapsed() >= ttl);
+    *temps = kept;
+    expired
+}
+
+/// Records `sources` as the inputs of a job, so no later commit, discard or cleanup touches them
+/// (see `ensure_not_job_source`). `register_transcode_temp` records its job's inputs itself.
+pub fn record_job_sources(sources: &[&Path]) {
+    let mut known = JOB_SOURCES.lock();
+    for source in sources {
+        if !known.iter().any(|k| k == source) {
+            known.push(source.to_path_buf());
+        }
+    }
+}
+
+/// Errors when `target` is the input of a job recorded with `record_job_sources`.
+pub fn ensure_not_job_source(target: &Path) -> Result<(), AppError> {
+    let sources = JOB_SOURCES.lock().clone();
+    sources
+        .iter()
+        .try_for_each(|source| crate::source_guard::ensure_not_source(source, target))
+}
+
+/// Deletes a registered output, unless it is a job's input.
+fn remove_output(job_id: u64, path: &Path) {
+    if let Err(e) = ensure_not_job_source(path) {
+        log::error!(
+            target: "tiny_vid::ffmpeg::temp",
+            "remove_output: job {} keeping {}: {}",
+            job_id,
+            path.display(),
+            e
+        );
+        return;
+    }
+    let _ = fs::remove_file(path);
+}
+
+/// Deletes outputs registered longer than `ttl` ago.
+fn expire_transcode_temps(ttl: Duration) {
+    let expired = take_expired(&mut TRANSCODE_TEMPS.lock(), ttl);
+    for pending in expired {
+        log::debug!(
+            target: "tiny_vid::ffmpeg::temp",
+            "expire_transcode_temps: job {} removing {}",
+            pending.job_id,
+            pending.path.display()
+        );
+        remove_output(pending.job_id, &pending.path);
+    }
+}
+
+/// Records `path` as `job`'s uncommitted output (for cleanup on discard, expiry or exit) and
+/// returns the token that commits or discards it. A job has at most one; registering again
+/// replaces the entry without touching other jobs. `sources` are the job's inputs (see
+/// `record_job_sources`).
+pub fn register_transcode_temp(job: &TempJob, path: PathBuf, sources: &[&Path]) -> String {
+    record_job_sources(sources);
+    expire_transcode_temps(PENDING_OUTPUT_TTL);
+    let token = format!("{}-{}", job.id, random_alphanumeric_suffix(16));
+    let mut temps = TRANSCODE_TEMPS.lock();
+    temps.retain(|p| p.job_id != job.id);
+    temps.push(PendingOutput {
+        job_id: job.id,
+        token: token.clone(),
+        path,
+        created: Instant::now(),
+    });
+    token
+}
+
+/// Job whose registered output is `path`.
+pub fn transcode_temp_job(path: &Path) -> Option<u64> {
+    TRANSCODE_TEMPS
+        .lock()
+        .iter()
+        .find(|p| p.path == path)
+        .map(|p| p.job_id)
+}
+
+/// Job id and output path for a commit token. None when the token is unknown, already
+/// committed or discarded, or expired.
+pub fn transcode_temp_for_token(token: &str) -> Option<(u64, PathBuf)> {
+    expire_transcode_temps(PENDING_OUTPUT_TTL);
+    TRANSCODE_TEMPS
+        .lock()
+        .iter()
+        .find(|p| p.token == token)
+        .map(|p| (p.job_id, p.path.clone()))
+}
+
+/// Takes the job's output out of the registry so it is no longer cleaned up. Call once the file
+/// has been moved into place.
+pub fn commit_transcode_temp(job_id: u64) -> Option<PathBuf> {
+    let mut temps = TRANSCODE_TEMPS.lock();
+    let pos = temps.iter().position(|p| p.job_id == job_id)?;
+    Some(temps.remove(pos).path)
+}
+
+/// Deletes the job's output and forgets it. Other jobs' outputs are left alone.
+pub fn discard_transcode_temp(job_id: u64) {
+    if let Some(path) = commit_transcode_temp(job_id) {
         log::debug!(
             target: "tiny_vid::ffmpeg::temp",
-            "cleanup_transcode_temp: removing {}",
+            "discard_transcode_temp: job {} removing {}",
+            job_id,
             path.display()
         );
-        let _ = fs::remove_file(&path);
+        remove_output(job_id, &path);
     }
 }
 
+/// Remove every uncommitted transcode output. Call on app exit.
+pub fn cleanup_transcode_temp() {
+    expire_transcode_temps(Duration::ZERO);
+}
+
 /// Delete temp files from the previous preview. Call at the start of each new preview.
 /// Preserves any paths that are still referenced by the preview cache.
 pub fn cleanup_previous_preview_paths(_new_input_path: &str, _new_preview_duration: u32) {
@@ -70,8 +214,46 @@ pub fn store_preview_paths_for_cleanup(originals: &[PathBuf], compresseds: &[Pat
     guard.extend(compresseds.iter().cloned());
 }
 
+/// Fails unless the first pass left a non-empty stats file for `passlogfile`
+/// (`{prefix}-0.log`, the name both x264/x265 and libvpx-vp9 use for the first video stream).
+/// A second pass without it would fail with a less helpful error, or encode without stats.
+pub fn check_first_pass_log(passlogfile: &Path) -> Result<(), AppError> {
+    let mut stats = passlogfile.as_os_str().to_owned();
+    stats.push("-0.log");
+    match fs::metadata(&stats) {
+        Ok(meta) if meta.len() > 0 => Ok(()),
+        _ => Err(AppError::from(format!(
+            "The first pass wrote no stats to {}",
+            Path::new(&stats).display()
+        ))),
+    }
+}
+
+/// Remove two-pass stats files written for `passlogfile` (e.g. `{prefix}-0.log`, `{prefix}-0.log.mbtree`).
+pub fn cleanup_passlog_files(passlogfile: &Path) {
+    let (Some(dir), Some(prefix)) = (
+        passlogfile.parent(),
+        passlogfile.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(prefix) {
+            log::trace!(
+                target: "tiny_vid::ffmpeg::temp",
+                "cleanup_passlog_files: removing {}",
+                entry.path().display()
+            );
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
 /// Stateless factory for creating temp files. Paths must be handed off to
-/// `set_transcode_temp` or `store_preview_paths_for_cleanup` for cleanup.
+/// `register_transcode_temp` or `store_preview_paths_for_cleanup` for cleanup.
 pub struct TempFileManager;
 
 impl Default for TempFileManager {
@@ -80,30 +262,70 @@ impl Default for TempFileManager {
     }
 }
 
-/// Generates a short random suffix for temp filenames
+/// Generates a short random suffix for temp filenames. Randomly keyed per process (plus a
+/// counter and the clock), so separate app instances do not produce the same names.
 fn random_alphanumeric_suffix(len: usize) -> String {
-    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
     const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
     static STATE: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(STATE.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    let mut bits = hasher.finish();
     let mut s = String::with_capacity(len);
     for _ in 0..len {
-        let idx = STATE.fetch_add(1, Ordering::Relaxed) as usize % CHARS.len();
-        s.push(CHARS[idx] as char);
+        if bits == 0 {
+            hasher.write_u8(0);
+            bits = hasher.finish();
+        }
+        s.push(CHARS[(bits % CHARS.len() as u64) as usize] as char);
+        bits /= CHARS.len() as u64;
     }
     s
 }
 
 impl TempFileManager {
     pub fn create(&self, suffix: &str, content: Option<&[u8]>) -> io::Result<PathBuf> {
-        let tmp = std::env::temp_dir();
+        self.create_in(&std::env::temp_dir(), suffix, content)
+    }
+
+    /// Like [`Self::create`], but in `tmp` instead of the system temp dir.
+    pub fn create_in(
+        &self,
+        tmp: &Path,
+        suffix: &str,
+        content: Option<&[u8]>,
+    ) -> io::Result<PathBuf> {
+        self.create_named(tmp, None, suffix, content)
+    }
+
+    /// Names are `tiny-vid-{timestamp}-[job{id}-]{random}-{suffix}`.
+    fn create_named(
+        &self,
+        tmp: &Path,
+        job_id: Option<u64>,
+        suffix: &str,
+        content: Option<&[u8]>,
+    ) -> io::Result<PathBuf> {
         let timestamp_ms = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("system time before UNIX_EPOCH")
             .as_millis();
+        let job = job_id
+            .map(|id| format!("{}{}-", JOB_TAG, id))
+            .unwrap_or_default();
         let name = format!(
-            "{}{}-{}-{}",
+            "{}{}-{}{}-{}",
             TEMP_FILE_PREFIX,
             timestamp_ms,
+            job,
             random_alphanumeric_suffix(9),
             suffix
         );
@@ -124,26 +346,46 @@ impl TempFileManager {
 /// Best-effort cleanup of old temp files on startup.
 /// Deletes files matching `tiny-vid-{timestamp}-...` older than `max_age`.
 pub fn cleanup_old_temp_files(max_age: Duration) {
-    let tmp = std::env::temp_dir();
+    remove_stale_temp_files(&std::env::temp_dir(), max_age, &[]);
+}
+
+/// Like [`cleanup_old_temp_files`], but safe while the app runs: files the preview cache, the
+/// current preview or an uncommitted output still use are kept. Returns how many were deleted.
+pub fn sweep_stale_temp_files(max_age: Duration) -> usize {
+    let mut keep = get_all_cached_paths();
+    keep.extend(PREVIOUS_PREVIEW_PATHS.lock().iter().cloned());
+    keep.extend(TRANSCODE_TEMPS.lock().iter().map(|p| p.path.clone()));
+    remove_stale_temp_files(&std::env::temp_dir(), max_age, &keep)
+}
+
+/// Deletes uncommitted outputs past [`PENDING_OUTPUT_TTL`]; their commit tokens stop working.
+pub fn expire_pending_outputs() {
+    expire_transcode_temps(PENDING_OUTPUT_TTL);
+}
+
+/// Deletes `tiny-vid-` files in `tmp` other than `keep` that were both created and last
+/// modified more than `max_age` ago. A file a long job is still writing stays.
+fn remove_stale_temp_files(tmp: &Path, max_age: Duration, keep: &[PathBuf]) -> usize {
     let now_ms = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis();
     let max_age_ms = max_age.as_millis();
 
-    let entries = match fs::read_dir(&tmp) {
+    let entries = match fs::read_dir(tmp) {
         Ok(entries) => entries,
         Err(e) => {
             log::debug!(
                 target: "tiny_vid::ffmpeg::temp",
-                "cleanup_old_temp_files: failed to read temp dir {}: {}",
+                "remove_stale_temp_files: failed to read temp dir {}: {}",
                 tmp.display(),
                 e
             );
-            return;
+            return 0;
         }
     };
 
+    let mut removed = 0;
     for entry in entries.flatten() {
         let path = entry.path();
         let file_name = match path.file_name().and_then(|n| n.to_str()) {
@@ -154,16 +396,29 @@ pub fn cleanup_old_temp_files(max_age: Duration) {
             continue;
         };
         let age_ms = now_ms.saturating_sub(ts_ms);
-        if age_ms > max_age_ms {
-            log::trace!(
-                target: "tiny_vid::ffmpeg::temp",
-                "cleanup_old_temp_files: removing stale temp file {} (age_ms={})",
-                path.display(),
-                age_ms
-            );
-            let _ = fs::remove_file(&path);
+        if age_ms <= max_age_ms || keep.contains(&path) {
+            continue;
+        }
+        let recently_modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|elapsed| elapsed <= max_age);
+        if recently_modified {
+            continue;
+        }
+        log::trace!(
+            target: "tiny_vid::ffmpeg::temp",
+            "remove_stale_temp_files: removing stale temp file {} (age_ms={})",
+            path.display(),
+            age_ms
+        );
+        if fs::remove_file(&path).is_ok() {
+            removed += 1;
         }
     }
+    removed
 }
 
 fn parse_timestamp_from_name(name: &str) -> Option<u128> {
@@ -208,6 +463,106 @@ mod tests {
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn cleanup_passlog_files_removes_prefixed_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let prefix = dir.path().join("tiny-vid-1-abc-passlog");
+        let log = dir.path().join("tiny-vid-1-abc-passlog-0.log");
+        let mbtree = dir.path().join("tiny-vid-1-abc-passlog-0.log.mbtree");
+        let other = dir.path().join("other.log");
+        for p in [&log, &mbtree, &other] {
+            fs::write(p, b"x").unwrap();
+        }
+        cleanup_passlog_files(&prefix);
+        assert!(!log.exists());
+        assert!(!mbtree.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn first_pass_log_must_exist_and_hold_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let prefix = dir.path().join("tiny-vid-1-abc-passlog");
+        let log = dir.path().join("tiny-vid-1-abc-passlog-0.log");
+        assert!(check_first_pass_log(&prefix).is_err());
+        fs::write(&log, b"").unwrap();
+        assert!(check_first_pass_log(&prefix).is_err());
+        fs::write(&log, b"stats").unwrap();
+        assert!(check_first_pass_log(&prefix).is_ok());
+    }
+
+    #[test]
+    fn registry_discards_and_commits_per_job() {
+        let first = TempJob::start();
+        let second = TempJob::start();
+        let a = first
+            .create(&std::env::temp_dir(), "transcode-output.mp4", Some(b"a"))
+            .unwrap();
+        let b = second
+            .create(&std::env::temp_dir(), "transcode-output.mp4", Some(b"b"))
+            .unwrap();
+        let name = a.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.contains(&format!("-job{}-", first.id())));
+        assert!(parse_timestamp_from_name(&name).is_some());
+        let token = register_transcode_temp(&first, a.clone(), &[]);
+        register_transcode_temp(&second, b.clone(), &[]);
+        assert_eq!(transcode_temp_job(&b), Some(second.id()));
+        assert_eq!(
+            transcode_temp_for_token(&token),
+            Some((first.id(), a.clone()))
+        );
+        assert_eq!(transcode_temp_for_token("1-unknown"), None);
+
+        discard_transcode_temp(second.id());
+        assert!(!b.exists());
+        assert!(a.exists());
+
+        assert_eq!(commit_transcode_temp(first.id()), Some(a.clone()));
+        discard_transcode_temp(first.id());
+        assert!(a.exists());
+        assert_eq!(transcode_temp_job(&a), None);
+        assert_eq!(transcode_temp_for_token(&token), None);
+        let _ = fs::remove_file(&a);
+    }
+
+    #[test]
+    fn registered_outputs_never_delete_a_job_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("clip.mov");
+        fs::write(&source, b"source").unwrap();
+        let job = TempJob::start();
+        // An output registered under the source's own path (through `..`) is kept on discard.
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        register_transcode_temp(&job, sub.join("../clip.mov"), &[source.as_path()]);
+        assert!(ensure_not_job_source(&source).is_err());
+        assert!(ensure_not_job_source(&dir.path().join("clip.mp4")).is_ok());
+        discard_transcode_temp(job.id());
+        assert_eq!(fs::read(&source).unwrap(), b"source");
+    }
+
+    #[test]
+    fn only_outputs_past_the_ttl_expire() {
+        let pending = |job_id: u64, age: Duration| PendingOutput {
+            job_id,
+            token: job_id.to_string(),
+            path: PathBuf::from(format!("/tmp/{}.mp4", job_id)),
+            created: Instant::now() - age,
+        };
+        let mut temps = vec![
+            pending(1, Duration::from_secs(2 * 60 * 60)),
+            pending(2, Duration::from_secs(60)),
+        ];
+        let expired = take_expired(&mut temps, PENDING_OUTPUT_TTL);
+        assert_eq!(
+            expired.iter().map(|p| p.job_id).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(temps.iter().map(|p| p.job_id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(take_expired(&mut temps, Duration::ZERO).len(), 1);
+        assert!(temps.is_empty());
+    }
+
     #[test]
     fn create_yields_different_paths() {
         let manager = TempFileManager::default();
@@ -218,4 +573,41 @@ mod tests {
             "two create calls should yield different paths"
         );
     }
+
+    #[test]
+    fn stale_sweep_keeps_fresh_modified_and_kept_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let day_ago = SystemTime::now() - Duration::from_secs(24 * 60 * 60);
+        let old_name = |n: u32| {
+            let ts = day_ago.duration_since(UNIX_EPOCH).unwrap().as_millis();
+            dir.path()
+                .join(format!("{}{}-abc{}-x.mp4", TEMP_FILE_PREFIX, ts, n))
+        };
+        let stale = old_name(1);
+        let kept = old_name(2);
+        let written = old_name(3);
+        let fresh = TempFileManager
+            .create_in(dir.path(), "x.mp4", Some(b"x"))
+            .unwrap();
+        let foreign = dir.path().join("other.mp4");
+        for (path, modified) in [(&stale, day_ago), (&kept, day_ago), (&foreign, day_ago)] {
+            fs::write(path, b"x").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+        fs::write(&written, b"x").unwrap();
+
+        let removed = remove_stale_temp_files(
+            dir.path(),
+            Duration::from_secs(60 * 60),
+            std::slice::from_ref(&kept),
+        );
+        assert_eq!(removed, 1);
+        assert!(!stale.exists());
+        assert!(kept.exists() && written.exists() && fresh.exists() && foreign.exists());
+    }
 }
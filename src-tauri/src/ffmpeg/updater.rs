@@ -0,0 +1,688 @@
+//! Opt-in FFmpeg update channel for the standalone variant.
+//!
+//! A channel manifest lists static builds per target with their SHA-256. Manifest URLs are pinned
+//! per channel, and installs name only a channel and version: the build's URL always comes from
+//! the manifest, never from the caller. The manifest is published from
+//! `src-tauri/ffmpeg-channel/manifest.json` (`yarn tv ffmpeg channel`), and the app compiles that
+//! file in: a remote entry is only used when its version, target and SHA-256 match a pinned one,
+//! so a tampered manifest cannot swap in another archive. Installing a build downloads it into
+//! `staging/`, verifies the pinned checksum, extracts it, records the SHA-256 of the extracted binaries
+//! in `build.json`, and checks that the new ffmpeg runs and still provides every codec the
+//! current one does. Only then is it swapped into `current/`, with the previous install kept in
+//! `previous/` for rollback.
+//!
+//! Updates are offered on macOS and Windows only, the platforms whose discovery consults them.
+//! Discovery picks up `current/ffmpeg` on next launch (the resolved path is cached per process)
+//! only while its binaries still match the recorded digests; the integrity check reports them.
+//! Downloads and extraction use the system `curl` and `tar`, which ship with macOS and Windows 10+.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use serde::{Deserialize, Serialize};
+
+use super::integrity::sha256_file;
+use crate::error::AppError;
+
+/// Manifest of the stable channel. Debug builds can override it with TINY_VID_FFMPEG_CHANNEL_URL.
+const STABLE_CHANNEL_URL: &str =
+    "https://github.com/MantasMikal/tiny-vid/releases/download/ffmpeg-channel/manifest.json";
+const BUILD_INFO_FILE: &str = "build.json";
+/// Channel builds this app version trusts; the published manifest is generated from this file.
+const PINNED_CHANNEL: &str = include_str!("../../ffmpeg-channel/manifest.json");
+
+static UPDATES_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the directory holding staged/current/previous FFmpeg updates. Call before discovery.
+pub fn set_updates_dir(dir: PathBuf) {
+    let _ = UPDATES_DIR.set(dir);
+}
+
+fn updates_dir() -> Result<&'static Path, AppError> {
+    UPDATES_DIR
+        .get()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| AppError::from("FFmpeg updates directory is not configured"))
+}
+
+fn ensure_updates_supported() -> Result<(), AppError> {
+    if cfg!(feature = "lgpl") {
+        return Err(AppError::from(
+            "FFmpeg updates are not available in the LGPL build",
+        ));
+    }
+    // Discovery consults the installed update only where it would use a bundled sidecar.
+    if cfg!(not(any(target_os = "macos", target_os = "windows"))) {
+        return Err(AppError::from(
+            "FFmpeg updates are only available on macOS and Windows",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub(super) fn binary_name(base: &str) -> String {
+    format!("{base}.exe")
+}
+#[cfg(not(target_os = "windows"))]
+pub(super) fn binary_name(base: &str) -> String {
+    base.to_string()
+}
+
+/// Directory of the installed update and the binary digests recorded when it was staged.
+/// A missing or unreadable `build.json` yields no digests, so the integrity check fails it.
+pub(crate) fn installed_update() -> Option<(PathBuf, Vec<InstalledBinary>)> {
+    if ensure_updates_supported().is_err() {
+        return None;
+    }
+    let current = UPDATES_DIR.get()?.join("current");
+    if !current.join(binary_name("ffmpeg")).exists() {
+        return None;
+    }
+    let binaries = read_installed_build(&current)
+        .map(|info| info.binaries)
+        .unwrap_or_else(|e| {
+            log::error!(
+                target: "tiny_vid::ffmpeg::updater",
+                "installed_update: no recorded digests in {}: {}",
+                BUILD_INFO_FILE,
+                e
+            );
+            Vec::new()
+        });
+    Some((current, binaries))
+}
+
+fn read_installed_build(dir: &Path) -> Result<InstalledBuild, String> {
+    let data = fs::read(dir.join(BUILD_INFO_FILE)).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&data).map_err(|e| e.to_string())
+}
+
+/// ffmpeg from the installed update, if any and intact. Used by discovery ahead of the bundled
+/// sidecar.
+pub fn installed_ffmpeg_path() -> Option<PathBuf> {
+    let (dir, binaries) = installed_update()?;
+    let checks = super::integrity::check_installed_binaries(&dir, &binaries);
+    if let Some(bad) = checks
+        .iter()
+        .find(|b| b.status != super::integrity::BinaryStatus::Ok)
+    {
+        log::error!(
+            target: "tiny_vid::ffmpeg::updater",
+            "installed_ffmpeg_path: ignoring installed update, {} is {:?}",
+            bad.name,
+            bad.status
+        );
+        return None;
+    }
+    Some(dir.join(binary_name("ffmpeg")))
+}
+
+/// Update channel; each has a pinned manifest URL.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+}
+
+impl UpdateChannel {
+    fn manifest_url(self) -> String {
+        if cfg!(debug_assertions)
+            && let Some(url) = std::env::var("TINY_VID_FFMPEG_CHANNEL_URL")
+                .ok()
+                .filter(|v| !v.is_empty())
+        {
+            return url;
+        }
+        match self {
+            UpdateChannel::Stable => STABLE_CHANNEL_URL.to_string(),
+        }
+    }
+}
+
+/// One build entry in the channel manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelBuild {
+    pub version: String,
+    pub target: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ChannelManifest {
+    builds: Vec<ChannelBuild>,
+}
+
+/// SHA-256 of an extracted binary, recorded at install time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InstalledBinary {
+    pub name: String,
+    pub sha256: String,
+}
+
+/// Contents of `build.json` in an installed update.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct InstalledBuild {
+    #[serde(flatten)]
+    build: ChannelBuild,
+    binaries: Vec<InstalledBinary>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<ChannelBuild>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInstallResult {
+    pub version: String,
+    pub ffmpeg_path: String,
+    /// The running app keeps its cached FFmpeg path until restart.
+    pub restart_required: bool,
+}
+
+fn run_tool(program: &str, args: &[&str]) -> Result<Vec<u8>, AppError> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd
+        .output()
+        .map_err(|e| AppError::from(format!("Failed to run {}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(AppError::from(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Parses "ffmpeg version 7.1.1 ..." (also "n7.1") into "7.1.1". None for git snapshots.
+fn parse_ffmpeg_version(version_output: &str) -> Option<String> {
+    let token = version_output
+        .lines()
+        .next()?
+        .strip_prefix("ffmpeg version ")?
+        .split_whitespace()
+        .next()?;
+    let token = token.strip_prefix('n').unwrap_or(token);
+    let numeric: String = token
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let numeric = numeric.trim_end_matches('.');
+    (!numeric.is_empty()).then(|| numeric.to_string())
+}
+
+fn version_parts(v: &str) -> Vec<u64> {
+    v.split('.').filter_map(|p| p.parse().ok()).collect()
+}
+
+/// True when `candidate` is strictly newer than `current`. Unknown current versions are older.
+fn is_newer(candidate: &str, current: Option<&str>) -> bool {
+    match current {
+        Some(current) => version_parts(candidate) > version_parts(current),
+        None => true,
+    }
+}
+
+/// Fetches and parses the manifest of `channel` (https only).
+fn fetch_manifest(channel: UpdateChannel) -> Result<ChannelManifest, AppError> {
+    let body = run_tool(
+        "curl",
+        &[
+            "-fsSL",
+            "--proto",
+            "=https",
+            "--max-time",
+            "30",
+            channel.manifest_url().as_str(),
+        ],
+    )?;
+    serde_json::from_slice(&body)
+        .map_err(|e| AppError::from(format!("Invalid FFmpeg channel manifest: {}", e)))
+}
+
+fn pinned_builds() -> &'static [ChannelBuild] {
+    static PINNED: OnceLock<Vec<ChannelBuild>> = OnceLock::new();
+    PINNED.get_or_init(|| {
+        serde_json::from_str::<ChannelManifest>(PINNED_CHANNEL)
+            .map(|m| m.builds)
+            .unwrap_or_else(|e| {
+                log::error!(
+                    target: "tiny_vid::ffmpeg::updater",
+                    "pinned_builds: invalid pinned channel manifest: {}",
+                    e
+                );
+                Vec::new()
+            })
+    })
+}
+
+/// True when `pinned` lists `build` with the same SHA-256.
+fn is_pinned(build: &ChannelBuild, pinned: &[ChannelBuild]) -> bool {
+    pinned.iter().any(|p| {
+        p.version == build.version
+            && p.target == build.target
+            && p.sha256.eq_ignore_ascii_case(&build.sha256)
+    })
+}
+
+/// Newest pinned build for `target` in the manifest.
+fn select_build(
+    manifest: &ChannelManifest,
+    pinned: &[ChannelBuild],
+    target: &str,
+) -> Option<ChannelBuild> {
+    manifest
+        .builds
+        .iter()
+        .filter(|b| b.target == target && is_pinned(b, pinned))
+        .max_by(|a, b| version_parts(&a.version).cmp(&version_parts(&b.version)))
+        .cloned()
+}
+
+fn ffmpeg_version_of(ffmpeg: &Path) -> Option<String> {
+    let stdout = run_tool(&ffmpeg.to_string_lossy(), &["-version"]).ok()?;
+    parse_ffmpeg_version(&String::from_utf8_lossy(&stdout))
+}
+
+fn encoders_of(ffmpeg: &Path) -> Result<Vec<String>, AppError> {
+    let stdout = run_tool(&ffmpeg.to_string_lossy(), &["-hide_banner", "-encoders"])?;
+    Ok(super::discovery::parse_encoder_output(
+        &String::from_utf8_lossy(&stdout),
+    ))
+}
+
+/// The manifest's build of `version` for `target`. It must match a pinned build and its URL must
+/// be https.
+fn find_build(
+    manifest: &ChannelManifest,
+    pinned: &[ChannelBuild],
+    target: &str,
+    version: &str,
+) -> Result<ChannelBuild, AppError> {
+    let build = manifest
+        .builds
+        .iter()
+        .find(|b| b.target == target && b.version == version)
+        .cloned()
+        .ok_or_else(|| {
+            AppError::from(format!(
+                "FFmpeg {} is not available for {} in this channel",
+                version, target
+            ))
+        })?;
+    if !is_pinned(&build, pinned) {
+        return Err(AppError::from(format!(
+            "FFmpeg {} does not match a build pinned in this app version",
+            version
+        )));
+    }
+    if !url::Url::parse(&build.url).is_ok_and(|u| u.scheme() == "https") {
+        return Err(AppError::from(format!(
+            "FFmpeg {} has a non-https download URL",
+            version
+        )));
+    }
+    Ok(build)
+}
+
+/// Fetches the channel manifest and reports whether a newer build exists for this target.
+pub fn check_for_update(channel: UpdateChannel) -> Result<UpdateCheck, AppError> {
+    ensure_updates_supported()?;
+    let manifest = fetch_manifest(channel)?;
+    let current_version = super::discovery::get_ffmpeg_path()
+        .ok()
+        .and_then(ffmpeg_version_of);
+    let available = select_build(&manifest, pinned_builds(), env!("TARGET"))
+        .filter(|b| is_newer(&b.version, current_version.as_deref()));
+    log::info!(
+        target: "tiny_vid::ffmpeg::updater",
+        "check_for_update: current={:?}, available={:?}",
+        current_version,
+        available.as_ref().map(|b| b.version.as_str())
+    );
+    Ok(UpdateCheck {
+        current_version,
+        available,
+    })
+}
+
+/// Finds `name` anywhere under `dir` (archives often nest binaries in `bin/`).
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name) {
+                return Some(found);
+            }
+        } else if path.file_name().is_some_and(|n| n == name) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Downloads, verifies, and extracts `build` into `staging/`, leaving ffmpeg/ffprobe at its root.
+fn stage_build(root: &Path, build: &ChannelBuild) -> Result<PathBuf, AppError> {
+    let staging = root.join("staging");
+    let _ = fs::remove_dir_all(&staging);
+    let extract_dir = staging.join("extract");
+    fs::create_dir_all(&extract_dir)?;
+
+    let archive = staging.join("archive");
+    let archive_str = archive.to_string_lossy().into_owned();
+    run_tool(
+        "curl",
+        &[
+            "-fSL",
+            "--proto",
+            "=https",
+            "--max-time",
+            "600",
+            "-o",
+            &archive_str,
+            &build.url,
+        ],
+    )?;
+    let actual = sha256_file(&archive)?;
+    if !actual.eq_ignore_ascii_case(&build.sha256) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(AppError::from(format!(
+            "Checksum mismatch for FFmpeg {}: expected {}, got {}",
+            build.version, build.sha256, actual
+        )));
+    }
+    run_tool(
+        "tar",
+        &[
+            "-xf",
+            &archive_str,
+            "-C",
+            extract_dir.to_string_lossy().as_ref(),
+        ],
+    )?;
+
+    let mut binaries = Vec::new();
+    for base in ["ffmpeg", "ffprobe"] {
+        let name = binary_name(base);
+        let found = find_file(&extract_dir, &name)
+            .ok_or_else(|| AppError::from(format!("{} not found in downloaded archive", name)))?;
+        let staged = staging.join(&name);
+        fs::rename(&found, &staged)?;
+        binaries.push(InstalledBinary {
+            sha256: sha256_file(&staged)?,
+            name,
+        });
+    }
+    let _ = fs::remove_dir_all(&extract_dir);
+    let _ = fs::remove_file(&archive);
+    let info = InstalledBuild {
+        build: build.clone(),
+        binaries,
+    };
+    fs::write(
+        staging.join(BUILD_INFO_FILE),
+        serde_json::to_vec_pretty(&info).map_err(|e| AppError::from(e.to_string()))?,
+    )?;
+    Ok(staging)
+}
+
+/// Rejects builds that don't run or drop a codec the current FFmpeg supports.
+fn validate_staged(staging: &Path, current_ffmpeg: Option<&Path>) -> Result<(), AppError> {
+    let staged_ffmpeg = staging.join(binary_name("ffmpeg"));
+    let staged_codecs = encoders_of(&staged_ffmpeg)?;
+    if let Some(current) = current_ffmpeg
+        && let Ok(current_codecs) = encoders_of(current)
+    {
+        let missing: Vec<&String> = current_codecs
+            .iter()
+            .filter(|c| !staged_codecs.contains(c))
+            .collect();
+        if !missing.is_empty() {
+            return Err(AppError::from(format!(
+                "Downloaded FFmpeg is missing codecs: {:?}",
+                missing
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Moves `staging/` to `current/`, keeping the old `current/` as `previous/`.
+/// Restores the previous install if the swapped-in ffmpeg fails to run.
+fn swap_in(root: &Path, staging: &Path) -> Result<PathBuf, AppError> {
+    let current = root.join("current");
+    let previous = root.join("previous");
+    let _ = fs::remove_dir_all(&previous);
+    if current.exists() {
+        fs::rename(&current, &previous)?;
+    }
+    fs::rename(staging, &current)?;
+
+    let ffmpeg = current.join(binary_name("ffmpeg"));
+    if run_tool(&ffmpeg.to_string_lossy(), &["-version"]).is_err() {
+        let _ = fs::remove_dir_all(&current);
+        if previous.exists() {
+            fs::rename(&previous, &current)?;
+        }
+        return Err(AppError::from(
+            "Installed FFmpeg failed to run; rolled back to the previous version",
+        ));
+    }
+    Ok(ffmpeg)
+}
+
+/// Downloads and installs `version` from `channel`, as listed in the channel's manifest for
+/// this target. The new FFmpeg is used after restart.
+pub fn install_update(
+    channel: UpdateChannel,
+    version: &str,
+) -> Result<UpdateInstallResult, AppError> {
+    ensure_updates_supported()?;
+    let root = updates_dir()?;
+    let build = find_build(
+        &fetch_manifest(channel)?,
+        pinned_builds(),
+        env!("TARGET"),
+        version,
+    )?;
+    log::info!(
+        target: "tiny_vid::ffmpeg::updater",
+        "install_update: channel={:?}, version={}, url={}",
+        channel,
+        build.version,
+        build.url
+    );
+    let staging = stage_build(root, &build)?;
+    let current_ffmpeg = super::discovery::get_ffmpeg_path().ok();
+    if let Err(e) = validate_staged(&staging, current_ffmpeg) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let ffmpeg = swap_in(root, &staging)?;
+    Ok(UpdateInstallResult {
+        version: build.version,
+        ffmpeg_path: ffmpeg.display().to_string(),
+        restart_required: true,
+    })
+}
+
+/// Swaps `previous/` back to `current/`. When no previous update exists, removes the
+/// installed update so discovery falls back to the bundled FFmpeg.
+fn rollback_in(root: &Path) -> Result<(), AppError> {
+    let current = root.join("current");
+    let previous = root.join("previous");
+    if !current.exists() {
+        return Err(AppError::from("No FFmpeg update is installed"));
+    }
+    fs::remove_dir_all(&current)?;
+    if previous.exists() {
+        fs::rename(&previous, &current)?;
+    }
+    Ok(())
+}
+
+/// Rolls back the last installed update. Takes effect after restart.
+pub fn rollback_update() -> Result<(), AppError> {
+    ensure_updates_supported()?;
+    rollback_in(updates_dir()?)?;
+    log::info!(target: "tiny_vid::ffmpeg::updater", "rollback_update: complete");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(version: &str, target: &str) -> ChannelBuild {
+        ChannelBuild {
+            version: version.to_string(),
+            target: target.to_string(),
+            url: format!("https://example.com/ffmpeg-{version}.zip"),
+            sha256: "0".repeat(64),
+        }
+    }
+
+    #[test]
+    fn parse_ffmpeg_version_variants() {
+        assert_eq!(
+            parse_ffmpeg_version("ffmpeg version 7.1.1 Copyright (c) 2000-2025"),
+            Some("7.1.1".to_string())
+        );
+        assert_eq!(
+            parse_ffmpeg_version("ffmpeg version n7.1-12-gabc Copyright"),
+            Some("7.1".to_string())
+        );
+        assert_eq!(
+            parse_ffmpeg_version("ffmpeg version N-118000-g1234 Copyright"),
+            None
+        );
+        assert_eq!(parse_ffmpeg_version("not ffmpeg"), None);
+    }
+
+    #[test]
+    fn is_newer_compares_numerically() {
+        assert!(is_newer("7.10", Some("7.9")));
+        assert!(!is_newer("7.1", Some("7.1")));
+        assert!(!is_newer("6.1", Some("7.0")));
+        assert!(is_newer("7.0", None));
+    }
+
+    #[test]
+    fn select_build_picks_newest_for_target() {
+        let manifest = ChannelManifest {
+            builds: vec![
+                build("7.0", "aarch64-apple-darwin"),
+                build("7.1.1", "aarch64-apple-darwin"),
+                build("8.0", "x86_64-pc-windows-msvc"),
+            ],
+        };
+        let pinned = manifest.builds.clone();
+        let selected = select_build(&manifest, &pinned, "aarch64-apple-darwin").unwrap();
+        assert_eq!(selected.version, "7.1.1");
+        assert!(select_build(&manifest, &pinned, "x86_64-unknown-linux-gnu").is_none());
+
+        let selected = select_build(&manifest, &pinned[..1], "aarch64-apple-darwin").unwrap();
+        assert_eq!(selected.version, "7.0");
+    }
+
+    #[test]
+    fn find_build_takes_url_and_checksum_from_the_manifest() {
+        let mut insecure = build("7.0", "aarch64-apple-darwin");
+        insecure.url = "http://example.com/ffmpeg-7.0.zip".to_string();
+        let manifest = ChannelManifest {
+            builds: vec![build("7.1.1", "aarch64-apple-darwin"), insecure],
+        };
+        let pinned = manifest.builds.clone();
+        let found = find_build(&manifest, &pinned, "aarch64-apple-darwin", "7.1.1").unwrap();
+        assert_eq!(found, manifest.builds[0]);
+        assert!(find_build(&manifest, &pinned, "aarch64-apple-darwin", "7.0").is_err());
+        assert!(find_build(&manifest, &pinned, "x86_64-pc-windows-msvc", "7.1.1").is_err());
+    }
+
+    #[test]
+    fn manifest_builds_must_match_a_pinned_digest() {
+        let mut tampered = build("7.1.1", "aarch64-apple-darwin");
+        tampered.sha256 = "f".repeat(64);
+        let manifest = ChannelManifest {
+            builds: vec![tampered],
+        };
+        let pinned = [build("7.1.1", "aarch64-apple-darwin")];
+        assert!(find_build(&manifest, &pinned, "aarch64-apple-darwin", "7.1.1").is_err());
+        assert!(select_build(&manifest, &pinned, "aarch64-apple-darwin").is_none());
+        assert!(find_build(&manifest, &[], "aarch64-apple-darwin", "7.1.1").is_err());
+    }
+
+    #[test]
+    fn pinned_channel_manifest_parses() {
+        serde_json::from_str::<ChannelManifest>(PINNED_CHANNEL).unwrap();
+    }
+
+    #[test]
+    fn build_info_without_digests_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = serde_json::to_vec(&build("7.1.1", "aarch64-apple-darwin")).unwrap();
+        fs::write(dir.path().join(BUILD_INFO_FILE), legacy).unwrap();
+        assert!(read_installed_build(dir.path()).is_err());
+
+        let info = InstalledBuild {
+            build: build("7.1.1", "aarch64-apple-darwin"),
+            binaries: vec![InstalledBinary {
+                name: binary_name("ffmpeg"),
+                sha256: "0".repeat(64),
+            }],
+        };
+        fs::write(
+            dir.path().join(BUILD_INFO_FILE),
+            serde_json::to_vec(&info).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(read_installed_build(dir.path()).unwrap(), info);
+    }
+
+    #[test]
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    fn updates_are_refused_where_discovery_ignores_them() {
+        assert!(ensure_updates_supported().is_err());
+        assert!(check_for_update(UpdateChannel::Stable).is_err());
+        assert!(install_update(UpdateChannel::Stable, "7.1.1").is_err());
+    }
+
+    #[test]
+    fn rollback_restores_previous_or_removes_current() {
+        let root = tempfile::tempdir().unwrap();
+        let current = root.path().join("current");
+        let previous = root.path().join("previous");
+        fs::create_dir_all(&current).unwrap();
+        fs::write(current.join("marker"), "new").unwrap();
+        fs::create_dir_all(&previous).unwrap();
+        fs::write(previous.join("marker"), "old").unwrap();
+
+        rollback_in(root.path()).unwrap();
+        assert_eq!(fs::read_to_string(current.join("marker")).unwrap(), "old");
+        assert!(!previous.exists());
+
+        rollback_in(root.path()).unwrap();
+        assert!(!current.exists());
+        assert!(rollback_in(root.path()).is_err());
+    }
+}
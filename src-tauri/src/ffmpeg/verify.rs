@@ -2,16 +2,19 @@
 //!
 //! A valid video decodes without errors; corruption produces FFmpeg errors and non-zero exit.
 //! For AV1, uses libdav1d (same as VLC/QuickTime) to catch SVT-AV1 compatibility issues.
+//! After a clean decode, the first audio and video packet timestamps are compared to catch
+//! desync (e.g. `-ss` + stream copy extraction starting audio and video at different points).
 
 use std::path::Path;
-use std::process::Command;
 
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
+use super::ffprobe::probe_packet_span;
+use super::{FfmpegContext, run_ffmpeg_capture};
+use crate::error::AppError;
 
-use super::discovery::get_ffmpeg_path;
+/// Max allowed difference between audio and video start timestamps, in seconds.
+const AV_SYNC_THRESHOLD_SECS: f64 = 0.2;
 
-fn run_verify(ffmpeg: &std::path::Path, path_str: &str, use_dav1d: bool) -> (bool, i32, String) {
+fn run_verify(ctx: &FfmpegContext, path_str: &str, use_dav1d: bool) -> (bool, i32, String) {
     let args: Vec<&str> = if use_dav1d {
         vec![
             "-v", "error", "-c:v", "libdav1d", "-i", path_str, "-f", "null", "-",
@@ -19,18 +22,11 @@ fn run_verify(ffmpeg: &std::path::Path, path_str: &str, use_dav1d: bool) -> (boo
     } else {
         vec!["-v", "error", "-i", path_str, "-f", "null", "-"]
     };
-    let mut cmd = Command::new(ffmpeg);
-    cmd.args(&args);
-    #[cfg(windows)]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    let output = match cmd.output() {
-        Ok(o) => o,
-        Err(e) => return (false, -1, e.to_string()),
-    };
-    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
-    let exit_code = output.status.code().unwrap_or(-1);
-    let success = output.status.success() && !stderr.to_lowercase().contains("error");
-    (success, exit_code, stderr)
+    match run_ffmpeg_capture(ctx, args.iter().map(|s| s.to_string()).collect()) {
+        Ok(stderr) => (!stderr.to_lowercase().contains("error"), 0, stderr),
+        Err(AppError::FfmpegFailed { code, stderr }) => (false, code, stderr),
+        Err(e) => (false, -1, e.to_string()),
+    }
 }
 
 fn is_dav1d_unavailable(stderr: &str) -> bool {
@@ -41,26 +37,52 @@ fn is_dav1d_unavailable(stderr: &str) -> bool {
         || s.contains("no decoder for")
 }
 
+/// Start drift between video and audio packet spans `(first_pts, end)`. Ends are not
+/// compared: audio routinely runs past or stops short of the last frame (encoder padding, a
+/// shorter recording track) without the two being out of sync.
+fn av_sync_drift(video: (f64, f64), audio: (f64, f64)) -> f64 {
+    (video.0 - audio.0).abs()
+}
+
+/// Compares the first packet timestamps of v:0 and a:0. Files without audio pass.
+fn check_av_sync(ctx: &FfmpegContext, path_str: &str) -> Result<(), String> {
+    let ffprobe = ctx.ffprobe_path().map_err(|e| e.to_string())?;
+    let Some(audio) = probe_packet_span(&ffprobe, path_str, "a:0") else {
+        return Ok(());
+    };
+    let Some(video) = probe_packet_span(&ffprobe, path_str, "v:0") else {
+        return Ok(());
+    };
+    let drift = av_sync_drift(video, audio);
+    if drift > AV_SYNC_THRESHOLD_SECS {
+        return Err(format!(
+            "Audio/video out of sync by {:.3}s (video {:.3}-{:.3}s, audio {:.3}-{:.3}s)",
+            drift, video.0, video.1, audio.0, audio.1
+        ));
+    }
+    Ok(())
+}
+
 /// Run FFmpeg decode-to-null. Returns Ok(()) if decode succeeds without errors.
 /// For AV1, uses libdav1d (falls back to default if unavailable). For non-AV1, uses default decoder.
-#[allow(dead_code)] // Used by integration tests; may be used for runtime verification
-pub fn verify_video(path: &Path, codec: Option<&str>) -> Result<(), String> {
-    let ffmpeg = get_ffmpeg_path().map_err(|e| e.to_string())?;
+/// A clean decode must also pass the A/V sync check. Runs under `ctx`, so cancelling its job
+/// stops the decode.
+pub fn verify_video(ctx: &FfmpegContext, path: &Path, codec: Option<&str>) -> Result<(), String> {
     let path_str = path.to_string_lossy();
     let use_dav1d = codec
         .map(|c| c.to_lowercase().contains("svtav1") || c.to_lowercase().contains("av1"))
         .unwrap_or(false);
 
-    let (success, exit_code, stderr) = run_verify(ffmpeg, path_str.as_ref(), use_dav1d);
+    let (success, exit_code, stderr) = run_verify(ctx, path_str.as_ref(), use_dav1d);
 
     if success {
-        return Ok(());
+        return check_av_sync(ctx, path_str.as_ref());
     }
     if use_dav1d && is_dav1d_unavailable(&stderr) {
         let (fallback_success, fallback_code, fallback_stderr) =
-            run_verify(ffmpeg, path_str.as_ref(), false);
+            run_verify(ctx, path_str.as_ref(), false);
         if fallback_success {
-            return Ok(());
+            return check_av_sync(ctx, path_str.as_ref());
         }
         return Err(format!(
             "Video verification failed (exit {}): {}",
@@ -72,3 +94,18 @@ pub fn verify_video(path: &Path, codec: Option<&str>) -> Result<(), String> {
         exit_code, stderr
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_sync_drift_compares_starts_only() {
+        assert!(av_sync_drift((0.0, 10.0), (0.0, 10.02)) <= AV_SYNC_THRESHOLD_SECS);
+        // Copy extraction cut video at the previous keyframe but audio at the exact -ss.
+        let drift = av_sync_drift((0.0, 3.0), (0.5, 3.0));
+        assert!((drift - 0.5).abs() < 1e-9);
+        // Audio ending well before the video is not drift.
+        assert_eq!(av_sync_drift((0.0, 5.0), (0.0, 3.8)), 0.0);
+    }
+}
@@ -0,0 +1,298 @@
+//! Translations for user-facing strings produced in Rust (error summaries, progress step labels,
+//! size change labels).
+//!
+//! The frontend picks the locale with `set_locale`; keys missing from a locale's table fall back
+//! to English. Stderr detail and log messages stay untranslated.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::AppError;
+
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+pub enum Locale {
+    En = 0,
+    De = 1,
+    Es = 2,
+    Fr = 3,
+}
+
+impl Locale {
+    const ALL: [Locale; 4] = [Locale::En, Locale::De, Locale::Es, Locale::Fr];
+
+    fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+        }
+    }
+
+    /// Parses a BCP 47 tag by its language subtag (`de-AT` -> `De`).
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|l| l.code() == language)
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::De => DE,
+            Locale::Es => ES,
+            Locale::Fr => FR,
+        }
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    (
+        "error.ffmpeg_not_started",
+        "FFmpeg not found or failed to start.",
+    ),
+    ("error.ffmpeg_failed", "FFmpeg failed."),
+    ("error.rate_limit", "Encoding rate limit exceeded."),
+    ("error.stopped", "Encoding was stopped."),
+    (
+        "error.ffmpeg_failed_code",
+        "FFmpeg failed (exit code {code}).",
+    ),
+    ("step.transcode", "Compressing video"),
+    ("step.transcode_pass1", "Analyzing video (pass 1 of 2)"),
+    ("step.transcode_pass2", "Compressing video (pass 2 of 2)"),
+    (
+        "step.quality_search",
+        "Finding the quality for the target size",
+    ),
+    (
+        "step.transcode_retry",
+        "Compressing again to hit the target size",
+    ),
+    ("step.generating_preview", "Generating preview"),
+    ("step.preview_extract", "Extracting preview segments"),
+    ("step.preview_transcode", "Compressing preview"),
+    ("step.preview_estimate", "Estimating output size"),
+    ("step.compose", "Composing picture-in-picture"),
+    ("step.batch_analyze", "Estimating batch sizes"),
+    ("size.smaller", "{percent} smaller"),
+    ("size.larger", "{percent} larger"),
+    ("size.same", "Same size"),
+    (
+        "advisory.generation_loss",
+        "This video is already heavily compressed. Re-encoding will lose quality for little size benefit; consider a remux instead.",
+    ),
+];
+
+const DE: &[(&str, &str)] = &[
+    (
+        "error.ffmpeg_not_started",
+        "FFmpeg wurde nicht gefunden oder konnte nicht starten.",
+    ),
+    ("error.ffmpeg_failed", "FFmpeg ist fehlgeschlagen."),
+    ("error.rate_limit", "Kodierungsratenlimit überschritten."),
+    ("error.stopped", "Die Kodierung wurde angehalten."),
+    (
+        "error.ffmpeg_failed_code",
+        "FFmpeg ist fehlgeschlagen (Exit-Code {code}).",
+    ),
+    ("step.transcode", "Video wird komprimiert"),
+    (
+        "step.transcode_pass1",
+        "Video wird analysiert (Durchgang 1 von 2)",
+    ),
+    (
+        "step.transcode_pass2",
+        "Video wird komprimiert (Durchgang 2 von 2)",
+    ),
+    (
+        "step.quality_search",
+        "Qualität für die Zielgröße wird gesucht",
+    ),
+    (
+        "step.transcode_retry",
+        "Erneute Komprimierung für die Zielgröße",
+    ),
+    ("step.generating_preview", "Vorschau wird erstellt"),
+    (
+        "step.preview_extract",
+        "Vorschauabschnitte werden extrahiert",
+    ),
+    ("step.preview_transcode", "Vorschau wird komprimiert"),
+    ("step.preview_estimate", "Ausgabegröße wird geschätzt"),
+    ("step.compose", "Bild-im-Bild wird erstellt"),
+    ("step.batch_analyze", "Stapelgrößen werden geschätzt"),
+    ("size.smaller", "{percent} kleiner"),
+    ("size.larger", "{percent} größer"),
+    ("size.same", "Gleiche Größe"),
+    (
+        "advisory.generation_loss",
+        "Dieses Video ist bereits stark komprimiert. Erneutes Kodieren kostet Qualität bei kaum kleinerer Datei; ein Remux ist die bessere Wahl.",
+    ),
+];
+
+const ES: &[(&str, &str)] = &[
+    (
+        "error.ffmpeg_not_started",
+        "No se encontró FFmpeg o no se pudo iniciar.",
+    ),
+    ("error.ffmpeg_failed", "FFmpeg falló."),
+    (
+        "error.rate_limit",
+        "Se superó el límite de velocidad de codificación.",
+    ),
+    ("error.stopped", "Se detuvo la codificación."),
+    (
+        "error.ffmpeg_failed_code",
+        "FFmpeg falló (código de salida {code}).",
+    ),
+    ("step.transcode", "Comprimiendo vídeo"),
+    ("step.transcode_pass1", "Analizando vídeo (pasada 1 de 2)"),
+    ("step.transcode_pass2", "Comprimiendo vídeo (pasada 2 de 2)"),
+    (
+        "step.quality_search",
+        "Buscando la calidad para el tamaño objetivo",
+    ),
+    (
+        "step.transcode_retry",
+        "Comprimiendo de nuevo para el tamaño objetivo",
+    ),
+    ("step.generating_preview", "Generando vista previa"),
+    (
+        "step.preview_extract",
+        "Extrayendo segmentos de vista previa",
+    ),
+    ("step.preview_transcode", "Comprimiendo vista previa"),
+    ("step.preview_estimate", "Estimando el tamaño de salida"),
+    ("step.compose", "Componiendo imagen en imagen"),
+    ("step.batch_analyze", "Estimando tamaños del lote"),
+    ("size.smaller", "{percent} más pequeño"),
+    ("size.larger", "{percent} más grande"),
+    ("size.same", "Mismo tamaño"),
+    (
+        "advisory.generation_loss",
+        "Este vídeo ya está muy comprimido. Volver a codificarlo perderá calidad con poca reducción de tamaño; considera un remux.",
+    ),
+];
+
+const FR: &[(&str, &str)] = &[
+    (
+        "error.ffmpeg_not_started",
+        "FFmpeg est introuvable ou n'a pas pu démarrer.",
+    ),
+    ("error.ffmpeg_failed", "FFmpeg a échoué."),
+    ("error.rate_limit", "Limite de débit d'encodage dépassée."),
+    ("error.stopped", "L'encodage a été arrêté."),
+    (
+        "error.ffmpeg_failed_code",
+        "FFmpeg a échoué (code de sortie {code}).",
+    ),
+    ("step.transcode", "Compression de la vidéo"),
+    (
+        "step.transcode_pass1",
+        "Analyse de la vidéo (passe 1 sur 2)",
+    ),
+    (
+        "step.transcode_pass2",
+        "Compression de la vidéo (passe 2 sur 2)",
+    ),
+    (
+        "step.quality_search",
+        "Recherche de la qualité pour la taille cible",
+    ),
+    (
+        "step.transcode_retry",
+        "Nouvelle compression pour la taille cible",
+    ),
+    ("step.generating_preview", "Génération de l'aperçu"),
+    ("step.preview_extract", "Extraction des segments d'aperçu"),
+    ("step.preview_transcode", "Compression de l'aperçu"),
+    ("step.preview_estimate", "Estimation de la taille de sortie"),
+    ("step.compose", "Composition de l'incrustation vidéo"),
+    ("step.batch_analyze", "Estimation des tailles du lot"),
+    ("size.smaller", "{percent} plus petit"),
+    ("size.larger", "{percent} plus grand"),
+    ("size.same", "Même taille"),
+    (
+        "advisory.generation_loss",
+        "Cette vidéo est déjà très compressée. La réencoder fera perdre de la qualité pour un faible gain de taille ; envisagez plutôt un remux.",
+    ),
+];
+
+pub fn locale() -> Locale {
+    let current = LOCALE.load(Ordering::Relaxed);
+    Locale::ALL
+        .into_iter()
+        .find(|l| *l as u8 == current)
+        .unwrap_or(Locale::En)
+}
+
+pub fn set_locale(tag: &str) -> Result<Locale, AppError> {
+    let locale =
+        Locale::parse(tag).ok_or_else(|| AppError::from(format!("Unsupported locale: {}", tag)))?;
+    log::info!(
+        target: "tiny_vid::i18n",
+        "set_locale: {}",
+        locale.code()
+    );
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+    Ok(locale)
+}
+
+/// `key` in `locale`, else English, else the key itself.
+pub(crate) fn translate(locale: Locale, key: &str) -> &str {
+    let lookup = |table: &'static [(&'static str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+    lookup(locale.table()).or_else(|| lookup(EN)).unwrap_or(key)
+}
+
+/// `key` in the current locale.
+pub(crate) fn t(key: &str) -> &str {
+    translate(locale(), key)
+}
+
+/// Label for a progress step id (e.g. `preview_extract`); `None` for unknown steps.
+pub(crate) fn step_label(step: &str) -> Option<String> {
+    let key = format!("step.{}", step);
+    let label = t(&key);
+    (label != key).then(|| label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_covers_the_english_keys() {
+        for locale in Locale::ALL {
+            for (key, _) in EN {
+                assert!(
+                    locale.table().iter().any(|(k, _)| k == key),
+                    "{:?} is missing {}",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_uses_the_language_subtag() {
+        assert_eq!(Locale::parse("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::parse("FR_ca"), Some(Locale::Fr));
+        assert_eq!(Locale::parse("ja"), None);
+    }
+
+    #[test]
+    fn translate_falls_back_to_english_then_key() {
+        assert_eq!(
+            translate(Locale::Es, "error.stopped"),
+            "Se detuvo la codificación."
+        );
+        assert_eq!(translate(Locale::De, "missing.key"), "missing.key");
+        assert_eq!(translate(Locale::En, "step.transcode"), "Compressing video");
+    }
+}
@@ -1,11 +1,24 @@
+mod batch;
+mod checksum;
 mod codec;
 mod commands;
 mod error;
+pub mod events;
 pub mod ffmpeg;
+mod i18n;
 mod log_plugin;
+mod presets;
 mod preview;
+mod queue;
+mod quick;
+mod size_format;
+mod source_guard;
 #[cfg(feature = "integration-test-api")]
 pub mod test_support;
+mod watch;
+mod webhook;
+
+pub use preview::{PreviewResult, preview_async};
 
 use std::path::PathBuf;
 
@@ -143,6 +156,39 @@ pub fn run() {
                 #[cfg(target_os = "macos")]
                 setup_menu(app)?;
 
+                if let Ok(dir) = app.path().app_local_data_dir() {
+                    ffmpeg::integrity::set_integrity_dir(dir.join("ffmpeg-integrity"));
+                    ffmpeg::updater::set_updates_dir(dir.join("ffmpeg-updates"));
+                }
+                let handle = app.handle().clone();
+                std::thread::spawn(move || match ffmpeg::integrity::check_bundled_integrity() {
+                    Ok(report) if !report.healthy => {
+                        let _ = handle.emit_to(
+                            "main",
+                            "ffmpeg-integrity-failed",
+                            events::Versioned::new(report),
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!(
+                        target: "tiny_vid::ffmpeg::integrity",
+                        "Integrity check skipped: {}",
+                        e
+                    ),
+                });
+
+                tauri::async_runtime::spawn(ffmpeg::maintenance::maintenance_loop());
+
+                // Warm the hardware encoder probe cache so get_build_variant doesn't wait on it.
+                std::thread::spawn(|| {
+                    if let (Ok(ffmpeg), Ok(available)) = (
+                        ffmpeg::discovery::get_ffmpeg_path(),
+                        ffmpeg::discovery::get_available_codecs(),
+                    ) {
+                        ffmpeg::hw_probe::probe_hardware_encoders(ffmpeg, &available, false);
+                    }
+                });
+
                 if let Some(main_window) = app.get_webview_window("main") {
                     sync_main_window_background(&main_window);
                     let _ = main_window.show();
@@ -159,10 +205,60 @@ pub fn run() {
             commands::preview_media_bytes,
             commands::get_video_metadata,
             commands::get_build_variant,
-            commands::move_compressed_file,
-            commands::cleanup_temp_file,
             commands::get_pending_opened_files,
             commands::extract_first_frame,
+            commands::get_tunes,
+            commands::run_self_test,
+            commands::check_ffmpeg_integrity,
+            commands::repair_bundled_ffmpeg,
+            commands::check_ffmpeg_update,
+            commands::install_ffmpeg_update,
+            commands::rollback_ffmpeg_update,
+            commands::get_encoder_health,
+            commands::repair_file,
+            commands::generate_hover_preview,
+            commands::export_contact_sheet,
+            commands::get_webhook_config,
+            commands::set_webhook_config,
+            commands::get_locale,
+            commands::set_locale,
+            commands::compose_picture_in_picture,
+            commands::analyze_silence,
+            commands::analyze_dead_frames,
+            commands::suggest_options,
+            commands::resolve_batch_jobs,
+            commands::run_batch,
+            commands::analyze_batch,
+            commands::get_last_batch_manifest,
+            commands::compute_output_checksum,
+            commands::queue_add,
+            commands::queue_remove,
+            commands::queue_reorder,
+            commands::queue_list,
+            commands::queue_start,
+            commands::commit_temp_output,
+            commands::discard_temp_output,
+            commands::commit_batch_outputs,
+            commands::suggest_quality,
+            commands::get_video_thumbnail,
+            commands::generate_storyboard,
+            commands::get_progress_throttle,
+            commands::set_progress_throttle,
+            commands::get_live_stderr,
+            commands::get_speed_preset_tunings,
+            commands::quick_compress,
+            commands::list_presets,
+            commands::save_preset,
+            commands::delete_preset,
+            commands::export_presets,
+            commands::import_presets,
+            commands::add_watch_folder,
+            commands::remove_watch_folder,
+            commands::list_watch_folders,
+            commands::get_estimate_sampling,
+            commands::set_estimate_sampling,
+            commands::ffmpeg_cancel_job,
+            commands::ffmpeg_cancel_request,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
@@ -0,0 +1,325 @@
+//! Named option presets ("Discord clip", "Archive AV1", ...) kept in `presets.json` in the app
+//! data dir.
+//!
+//! The file is the only state: every call reads it, so whatever else writes the same file
+//! (another window, a sidecar with the same data dir) is picked up on the next call. Writes go
+//! to a temp file that is renamed over the old one, so a crash never leaves half a file.
+//!
+//! Presets are shared as `.tinyvidpreset` files in the same format. Imports go through the
+//! same deserialization, name and extra FFmpeg argument checks as saving, so a file from
+//! elsewhere cannot carry anything a saved preset could not.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::ffmpeg::{TranscodeOptions, validate_extra_args};
+
+const PRESETS_FILE: &str = "presets.json";
+/// Extension of exported preset files.
+pub const PRESET_FILE_EXTENSION: &str = "tinyvidpreset";
+const FILE_VERSION: u32 = 1;
+const MAX_NAME_CHARS: usize = 64;
+
+/// Serializes read-modify-write cycles within the process.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedPreset {
+    pub name: String,
+    pub options: TranscodeOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetFile {
+    version: u32,
+    presets: Vec<NamedPreset>,
+}
+
+fn presets_path(dir: &Path) -> PathBuf {
+    dir.join(PRESETS_FILE)
+}
+
+fn parse_preset_file(data: &[u8]) -> Result<Vec<NamedPreset>, AppError> {
+    let file: PresetFile = serde_json::from_slice(data)
+        .map_err(|e| AppError::from(format!("Presets file is invalid: {}", e)))?;
+    if file.version > FILE_VERSION {
+        return Err(AppError::from(format!(
+            "Presets file version {} is newer than this app supports",
+            file.version
+        )));
+    }
+    Ok(file.presets)
+}
+
+fn read_presets(dir: &Path) -> Result<Vec<NamedPreset>, AppError> {
+    match fs::read(presets_path(dir)) {
+        Ok(data) => parse_preset_file(&data),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes `presets` to `path` through a temp file next to it.
+fn write_preset_file(path: &Path, presets: &[NamedPreset]) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = PresetFile {
+        version: FILE_VERSION,
+        presets: presets.to_vec(),
+    };
+    let data = serde_json::to_vec_pretty(&file).map_err(|e| AppError::from(e.to_string()))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn write_presets(dir: &Path, presets: &[NamedPreset]) -> Result<(), AppError> {
+    write_preset_file(&presets_path(dir), presets)
+}
+
+/// Adds `preset`, or replaces the one of the same name in place.
+fn upsert(presets: &mut Vec<NamedPreset>, preset: NamedPreset) {
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+}
+
+fn validate_name(name: &str) -> Result<&str, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::from("Preset name cannot be empty"));
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::from(format!(
+            "Preset name is longer than {} characters",
+            MAX_NAME_CHARS
+        )));
+    }
+    Ok(name)
+}
+
+/// Presets saved in `dir`, in the order they were first saved.
+pub fn list_presets(dir: &Path) -> Result<Vec<NamedPreset>, AppError> {
+    let _guard = LOCK.lock();
+    read_presets(dir)
+}
+
+/// The preset called `name`.
+pub fn get_preset(dir: &Path, name: &str) -> Result<NamedPreset, AppError> {
+    list_presets(dir)?
+        .into_iter()
+        .find(|p| p.name == name.trim())
+        .ok_or_else(|| AppError::from(format!("No preset named {:?}", name)))
+}
+
+/// Saves `options` as `name`, replacing a preset of the same name in place. Returns all presets.
+pub fn save_preset(
+    dir: &Path,
+    name: &str,
+    options: TranscodeOptions,
+) -> Result<Vec<NamedPreset>, AppError> {
+    let name = validate_name(name)?;
+    validate_extra_args(&options)?;
+    let _guard = LOCK.lock();
+    let mut presets = read_presets(dir)?;
+    upsert(
+        &mut presets,
+        NamedPreset {
+            name: name.to_string(),
+            options,
+        },
+    );
+    write_presets(dir, &presets)?;
+    log::info!(target: "tiny_vid::presets", "save_preset: saved {:?}", name);
+    Ok(presets)
+}
+
+/// Deletes the preset called `name`. Returns the remaining presets.
+pub fn delete_preset(dir: &Path, name: &str) -> Result<Vec<NamedPreset>, AppError> {
+    let _guard = LOCK.lock();
+    let mut presets = read_presets(dir)?;
+    let before = presets.len();
+    presets.retain(|p| p.name != name.trim());
+    if presets.len() == before {
+        return Err(AppError::from(format!("No preset named {:?}", name)));
+    }
+    write_presets(dir, &presets)?;
+    log::info!(target: "tiny_vid::presets", "delete_preset: deleted {:?}", name);
+    Ok(presets)
+}
+
+/// Writes the presets called `names` (all when None) to `dest` as a `.tinyvidpreset` file,
+/// adding the extension when `dest` lacks it. Returns the written path.
+pub fn export_presets(
+    dir: &Path,
+    names: Option<&[String]>,
+    dest: &Path,
+) -> Result<PathBuf, AppError> {
+    let presets = {
+        let _guard = LOCK.lock();
+        read_presets(dir)?
+    };
+    let presets = match names {
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                presets
+                    .iter()
+                    .find(|p| p.name == name.trim())
+                    .cloned()
+                    .ok_or_else(|| AppError::from(format!("No preset named {:?}", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => presets,
+    };
+    if presets.is_empty() {
+        return Err(AppError::from("There are no presets to export"));
+    }
+    let dest = if dest
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(PRESET_FILE_EXTENSION))
+    {
+        dest.to_path_buf()
+    } else {
+        let mut path = dest.as_os_str().to_owned();
+        path.push(format!(".{}", PRESET_FILE_EXTENSION));
+        PathBuf::from(path)
+    };
+    write_preset_file(&dest, &presets)?;
+    log::info!(
+        target: "tiny_vid::presets",
+        "export_presets: wrote {} preset(s) to {}",
+        presets.len(),
+        dest.display()
+    );
+    Ok(dest)
+}
+
+/// Adds the presets in the `.tinyvidpreset` file at `source`, replacing presets of the same
+/// name. Nothing is saved unless every preset in the file is valid. Returns all presets.
+pub fn import_presets(dir: &Path, source: &Path) -> Result<Vec<NamedPreset>, AppError> {
+    let imported = parse_preset_file(&fs::read(source)?)?;
+    if imported.is_empty() {
+        return Err(AppError::from("The file contains no presets"));
+    }
+    for preset in &imported {
+        validate_name(&preset.name)?;
+        validate_extra_args(&preset.options)
+            .map_err(|e| AppError::from(format!("Preset {:?}: {}", preset.name.trim(), e)))?;
+    }
+    let _guard = LOCK.lock();
+    let mut presets = read_presets(dir)?;
+    for mut preset in imported {
+        preset.name = preset.name.trim().to_string();
+        upsert(&mut presets, preset);
+    }
+    write_presets(dir, &presets)?;
+    log::info!(
+        target: "tiny_vid::presets",
+        "import_presets: imported from {}",
+        source.display()
+    );
+    Ok(presets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn av1() -> TranscodeOptions {
+        TranscodeOptions {
+            codec: Some("libsvtav1".to_string()),
+            quality: Some(60),
+            ..TranscodeOptions::default()
+        }
+    }
+
+    #[test]
+    fn saves_replaces_and_deletes_across_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_presets(dir.path()).unwrap().is_empty());
+
+        save_preset(dir.path(), "Discord clip", TranscodeOptions::default()).unwrap();
+        save_preset(dir.path(), " Archive AV1 ", av1()).unwrap();
+        let presets = save_preset(dir.path(), "Discord clip", av1()).unwrap();
+        let names: Vec<_> = presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Discord clip", "Archive AV1"]);
+
+        let listed = list_presets(dir.path()).unwrap();
+        assert_eq!(listed[0].options.effective_codec(), "libsvtav1");
+
+        let remaining = delete_preset(dir.path(), "Discord clip").unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(delete_preset(dir.path(), "Discord clip").is_err());
+        assert_eq!(list_presets(dir.path()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn exports_and_imports_preset_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        save_preset(&store, "Discord clip", TranscodeOptions::default()).unwrap();
+        save_preset(&store, "Archive AV1", av1()).unwrap();
+
+        let one = vec!["Archive AV1".to_string()];
+        let written = export_presets(&store, Some(&one), &dir.path().join("team")).unwrap();
+        assert_eq!(written, dir.path().join("team.tinyvidpreset"));
+        assert!(export_presets(&store, Some(&["Nope".to_string()]), &written).is_err());
+
+        let other = dir.path().join("other");
+        save_preset(&other, "Archive AV1", TranscodeOptions::default()).unwrap();
+        let presets = import_presets(&other, &written).unwrap();
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].options.effective_codec(), "libsvtav1");
+    }
+
+    #[test]
+    fn imports_are_all_or_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shared.tinyvidpreset");
+        fs::write(
+            &file,
+            br#"{"version": 1, "presets": [
+                {"name": "Fine", "options": {"codec": "libx264"}},
+                {"name": "Sneaky", "options": {"extraOutputArgs": ["-i", "/etc/passwd"]}}
+            ]}"#,
+        )
+        .unwrap();
+        let err = import_presets(dir.path(), &file).unwrap_err().to_string();
+        assert!(err.contains("Sneaky"), "{}", err);
+        assert!(list_presets(dir.path()).unwrap().is_empty());
+
+        fs::write(
+            &file,
+            br#"{"version": 1, "presets": [{"name": "Bad", "options": {"quality": "high"}}]}"#,
+        )
+        .unwrap();
+        assert!(import_presets(dir.path(), &file).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_names_and_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(save_preset(dir.path(), "  ", av1()).is_err());
+        assert!(save_preset(dir.path(), &"x".repeat(MAX_NAME_CHARS + 1), av1()).is_err());
+
+        fs::write(presets_path(dir.path()), b"{ not json").unwrap();
+        assert!(list_presets(dir.path()).is_err());
+        fs::write(
+            presets_path(dir.path()),
+            br#"{"version": 99, "presets": []}"#,
+        )
+        .unwrap();
+        assert!(list_presets(dir.path()).is_err());
+    }
+}
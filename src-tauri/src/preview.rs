@@ -5,16 +5,32 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use futures_util::future::join_all;
+use parking_lot::Mutex;
+use tokio::sync::Semaphore;
+
+use crate::codec::is_hardware_codec;
 use crate::error::AppError;
-use crate::ffmpeg::ffprobe::{VideoMetadata, get_video_metadata_impl};
+use crate::events::Versioned;
+use crate::ffmpeg::compression::{CompressionMetrics, LargerOutputWarning};
+use crate::ffmpeg::discovery::get_available_codecs_for;
+use crate::ffmpeg::ffprobe::{
+    VideoMetadata, VideoTiming, get_video_metadata_with_ffprobe,
+    probe_first_frame_pts_with_ffprobe, probe_video_timing_with_ffprobe,
+};
+use crate::ffmpeg::hw_probe::filter_working_encoders;
+use crate::ffmpeg::loudness::{LoudnessInfo, measure_preview_audio_loudness};
 use crate::ffmpeg::parse_ffmpeg_error;
+use crate::ffmpeg::quality::{QualityMetrics, measure_preview_quality};
 use crate::ffmpeg::{
-    EstimateConfidence, FfmpegProgressPayload, FileSignature, SizeEstimate, TempFileManager,
-    TranscodeOptions, build_extract_args, build_ffmpeg_command, cleanup_previous_preview_paths,
-    file_signature, get_cached_estimate, get_cached_preview, get_cached_segments,
-    is_preview_stream_copy_safe_codec, path_to_string, run_ffmpeg_blocking, set_cached_estimate,
-    set_cached_preview, store_preview_paths_for_cleanup,
+    EstimateConfidence, EstimateSampling, FfmpegContext, FfmpegProgressPayload, FileSignature,
+    ProgressChannel, ProgressSink, ProgressStep, RateControlMode, SizeEstimate, TranscodeOptions,
+    build_extract_args, build_ffmpeg_command, build_video_only_ffmpeg_command,
+    cleanup_previous_preview_paths, estimated_audio_kbps, file_signature, get_cached_estimate,
+    get_cached_preview, get_cached_segments, is_preview_stream_copy_safe_codec, path_to_string,
+    run_ffmpeg_async, set_cached_estimate, set_cached_preview, store_preview_paths_for_cleanup,
 };
+use crate::size_format::SizeSummary;
 use tauri::Emitter;
 
 /// Optional emit context for progress events: (AppHandle, window label).
@@ -24,16 +40,21 @@ pub(crate) type PreviewEmit = Option<(tauri::AppHandle, String)>;
 const PREVIEW_STEPS: usize = 2;
 const ESTIMATE_SHORT_VIDEO_THRESHOLD_SECS: f64 = 12.0;
 const ESTIMATE_ADAPTIVE_MIN_DURATION_SECS: f64 = 30.0;
-const ESTIMATE_BASE_SAMPLE_DURATION_SECS: f64 = 1.5;
-const ESTIMATE_MAX_SAMPLED_SECONDS: f64 = 7.5;
 const ESTIMATE_EXTRA_SAMPLE_CV_THRESHOLD: f64 = 0.35;
 const ESTIMATE_HIGH_CONFIDENCE_MAX_CV: f64 = 0.15;
 const ESTIMATE_MEDIUM_CONFIDENCE_MAX_CV: f64 = 0.35;
 const ESTIMATE_METHOD: &str = "sampled_bitrate";
+/// Most estimate samples encoded at once.
+const ESTIMATE_MAX_PARALLEL_SAMPLES: usize = 5;
+/// Constant-quality output grows slower than the pixel count; size ~ pixels^0.75 is the usual
+/// rule of thumb when correcting downscaled samples.
+const DOWNSCALE_SIZE_EXPONENT: f64 = 0.75;
 
-fn estimate_step_count(video_duration: f64) -> usize {
+type ProgressCallback = Arc<dyn Fn(f64) + Send + Sync>;
+
+fn estimate_step_count(video_duration: f64, sampling: &EstimateSampling) -> usize {
     if video_duration > ESTIMATE_SHORT_VIDEO_THRESHOLD_SECS {
-        5
+        sampling.base_positions().len() + sampling.extra_positions().len()
     } else {
         1
     }
@@ -41,18 +62,16 @@ fn estimate_step_count(video_duration: f64) -> usize {
 
 /// Progress context for multi-step preview (extract + transcode).
 pub(crate) struct PreviewProgressCtx {
-    app: tauri::AppHandle,
-    label: String,
+    sink: ProgressSink,
     step_index: AtomicUsize,
     base_step: usize,
     total_steps: usize,
 }
 
 impl PreviewProgressCtx {
-    fn new(app: tauri::AppHandle, label: String, base_step: usize, total_steps: usize) -> Self {
+    fn new(sink: ProgressSink, base_step: usize, total_steps: usize) -> Self {
         Self {
-            app,
-            label,
+            sink,
             step_index: AtomicUsize::new(0),
             base_step,
             total_steps,
@@ -61,39 +80,67 @@ impl PreviewProgressCtx {
 
     fn make_callback(&self, step: &'static str) -> Arc<dyn Fn(f64) + Send + Sync> {
         let idx = self.step_index.load(Ordering::Relaxed);
-        let app = self.app.clone();
-        let label = self.label.clone();
+        let sink = self.sink.clone();
         let base = self.base_step as f64;
         let total = self.total_steps as f64;
-        let step_owned = step.to_string();
+        let progress_step = ProgressStep::new(step, self.base_step + idx, self.total_steps);
         Arc::new(move |p: f64| {
             let overall = (base + idx as f64 + p) / total;
-            let payload = FfmpegProgressPayload {
-                progress: overall,
-                step: Some(step_owned.clone()),
-            };
-            let _ = app.emit_to(&label, "ffmpeg-progress", payload);
+            sink.send(FfmpegProgressPayload::new(
+                overall,
+                Some(progress_step.clone()),
+            ));
         })
     }
 
+    /// Preview-only progress (no estimate steps) sent to `channel`.
+    pub(crate) fn for_channel(channel: ProgressChannel) -> Self {
+        Self::new(ProgressSink::Channel(channel), 0, PREVIEW_STEPS)
+    }
+
     fn advance(&self) {
         self.step_index.fetch_add(1, Ordering::Relaxed);
     }
+
+    /// Callbacks for `count` steps that run at the same time. Overall progress is the sum of all
+    /// of them, so it only moves forward however the encodes interleave. Advances past the steps.
+    fn make_parallel_callbacks(&self, step: &'static str, count: usize) -> Vec<ProgressCallback> {
+        let idx = self.step_index.fetch_add(count, Ordering::Relaxed);
+        let parts = Arc::new(Mutex::new(vec![0.0_f64; count]));
+        let base = (self.base_step + idx) as f64;
+        let total = self.total_steps as f64;
+        (0..count)
+            .map(|i| {
+                let parts = Arc::clone(&parts);
+                let sink = self.sink.clone();
+                let first_step = self.base_step + idx;
+                let total_steps = self.total_steps;
+                let callback: ProgressCallback = Arc::new(move |p: f64| {
+                    let sum = {
+                        let mut parts = parts.lock();
+                        parts[i] = parts[i].max(p.clamp(0.0, 1.0));
+                        parts.iter().sum::<f64>()
+                    };
+                    let current = first_step + (sum as usize).min(count - 1);
+                    sink.send(FfmpegProgressPayload::new(
+                        (base + sum) / total,
+                        Some(ProgressStep::new(step, current, total_steps)),
+                    ));
+                });
+                callback
+            })
+            .collect()
+    }
 }
 
-/// Creates a callback that emits ffmpeg-progress with a step label.
+/// Creates a callback that sends progress with a step label to `sink`.
 pub(crate) fn make_progress_emitter(
-    app: tauri::AppHandle,
-    label: String,
+    sink: ProgressSink,
     step: &'static str,
 ) -> Arc<dyn Fn(f64) + Send + Sync> {
-    let step_owned = step.to_string();
+    let progress_step = ProgressStep::new(step, 0, 1);
     Arc::new(move |p: f64| {
-        let payload = FfmpegProgressPayload {
-            progress: p,
-            step: Some(step_owned.clone()),
-        };
-        let _ = app.emit_to(&label, "ffmpeg-progress", payload);
+        sink.send(FfmpegProgressPayload::new(p, Some(progress_step.clone())));
     })
 }
 
@@ -101,58 +148,36 @@ pub(crate) fn make_progress_emitter(
 /// `emit`: when Some, used for ffmpeg-error on failure; when `progress_callback` is None, also for ffmpeg-progress.
 /// `progress_callback`: when Some, used for progress instead of direct emit (e.g. preview aggregate); `emit` still used for errors.
 pub(crate) async fn run_ffmpeg_step(
+    ffmpeg: &FfmpegContext,
     args: Vec<String>,
     emit: Option<(&tauri::AppHandle, &str)>,
     duration_secs: Option<f64>,
     progress_callback: Option<std::sync::Arc<dyn Fn(f64) + Send + Sync>>,
 ) -> Result<(), AppError> {
-    let (app_opt, label_opt) = emit
-        .map(|(a, l)| (Some(a.clone()), Some(l.to_string())))
-        .unwrap_or((None, None));
-    let result = tauri::async_runtime::spawn_blocking({
-        let app_for_blocking = app_opt.clone();
-        let label_for_blocking = label_opt.clone();
-        move || {
-            run_ffmpeg_blocking(
-                args,
-                app_for_blocking.as_ref(),
-                label_for_blocking.as_deref(),
-                duration_secs,
-                progress_callback,
-                None,
-            )
-        }
-    })
+    let result = run_ffmpeg_async(
+        ffmpeg,
+        args,
+        emit.map(|(app, _)| app),
+        emit.map(|(_, label)| label),
+        duration_secs,
+        progress_callback,
+    )
     .await;
-
-    match result {
-        Ok(Ok(())) => Ok(()),
-        Ok(Err(e)) => {
-            log::error!(target: "tiny_vid::preview", "ffmpeg-error: {}", e);
-            if let (Some(app), Some(label)) = (app_opt.as_ref(), label_opt.as_ref()) {
-                let payload = match &e {
-                    AppError::FfmpegFailed { code, stderr } => {
-                        parse_ffmpeg_error(stderr, Some(*code))
-                    }
-                    _ => parse_ffmpeg_error(&e.to_string(), None),
-                };
-                let _ = app.emit_to(label, "ffmpeg-error", payload);
-            }
-            Err(e)
-        }
-        Err(join_err) => {
-            let e = AppError::from(join_err.to_string());
-            log::error!(target: "tiny_vid::preview", "ffmpeg-error (join): {}", e);
-            if let (Some(app), Some(label)) = (app_opt.as_ref(), label_opt.as_ref()) {
-                let payload = parse_ffmpeg_error(&e.to_string(), None);
-                let _ = app.emit_to(label, "ffmpeg-error", payload);
-            }
-            Err(e)
+    if let Err(e) = &result {
+        log::error!(target: "tiny_vid::preview", "ffmpeg-error: {}", e);
+        if let Some((app, label)) = emit {
+            let payload = match e {
+                AppError::FfmpegFailed { code, stderr } => parse_ffmpeg_error(stderr, Some(*code)),
+                _ => parse_ffmpeg_error(&e.to_string(), None),
+            };
+            let _ = app.emit_to(label, "ffmpeg-error", Versioned::new(payload));
         }
     }
+    result
 }
 
 async fn run_ffmpeg_with_progress(
+    ffmpeg: &FfmpegContext,
     args: Vec<String>,
     duration_secs: Option<f64>,
     emit: Option<(&tauri::AppHandle, &str)>,
@@ -160,7 +185,7 @@ async fn run_ffmpeg_with_progress(
     step_label: &'static str,
 ) -> Result<(), AppError> {
     let progress_cb = progress_ctx.map(|ctx| ctx.make_callback(step_label));
-    run_ffmpeg_step(args, emit, duration_secs, progress_cb).await?;
+    run_ffmpeg_step(ffmpeg, args, emit, duration_secs, progress_cb).await?;
     if let Some(ctx) = progress_ctx {
         ctx.advance();
     }
@@ -200,6 +225,7 @@ fn preview_start_ms_from_seconds(start_seconds: f64) -> u64 {
     (start_seconds.max(0.0) * 1000.0).round() as u64
 }
 
+/// Fallback when encoder discovery fails.
 fn preview_original_transcode_codec() -> &'static str {
     #[cfg(feature = "lgpl")]
     {
@@ -211,6 +237,78 @@ fn preview_original_transcode_codec() -> &'static str {
     }
 }
 
+/// Encoders for the original preview, fastest first. The original has to play in the webview,
+/// so only H.264 encoders qualify.
+const PREVIEW_ORIGINAL_CODECS: &[&str] = &[
+    "h264_videotoolbox",
+    "h264_nvenc",
+    "h264_qsv",
+    "h264_amf",
+    "h264_vaapi",
+    "libx264",
+];
+
+/// Preview candidates the FFmpeg at the path ships (`ffmpeg -encoders`). Only discovery is
+/// cached: whether a hardware encoder works is asked of `filter_working_encoders` on every
+/// resolve, so its cache decides when to probe again (e.g. after a session limit).
+static PREVIEW_ENCODERS: Mutex<Option<(PathBuf, Vec<String>)>> = Mutex::new(None);
+
+/// `requested` when it is a working H.264 encoder, else the fastest working one.
+fn pick_preview_codec(working: &[String], requested: Option<&str>) -> Option<&'static str> {
+    let is_working = |codec: &str| working.iter().any(|w| w == codec);
+    let mut candidates = PREVIEW_ORIGINAL_CODECS.iter().copied();
+    requested
+        .and_then(|r| candidates.clone().find(|c| *c == r))
+        .filter(|c| is_working(c))
+        .or_else(|| candidates.find(|c| is_working(c)))
+}
+
+/// Preview candidates among the encoders of `ffmpeg_path`, discovered once per FFmpeg. The
+/// cache is not locked while discovery runs.
+fn preview_encoders(ffmpeg_path: &Path) -> Result<Vec<String>, AppError> {
+    if let Some((path, encoders)) = PREVIEW_ENCODERS.lock().as_ref()
+        && path == ffmpeg_path
+    {
+        return Ok(encoders.clone());
+    }
+    let encoders: Vec<String> = get_available_codecs_for(ffmpeg_path)?
+        .into_iter()
+        .filter(|codec| PREVIEW_ORIGINAL_CODECS.contains(&codec.as_str()))
+        .collect();
+    *PREVIEW_ENCODERS.lock() = Some((ffmpeg_path.to_path_buf(), encoders.clone()));
+    Ok(encoders)
+}
+
+/// Encoder for the original preview transcode. Blocking (discovery runs once per FFmpeg,
+/// hardware probes as their cache requires).
+pub(crate) fn resolve_preview_original_codec(
+    ffmpeg_path: &Path,
+    requested: Option<&str>,
+) -> &'static str {
+    let working = match preview_encoders(ffmpeg_path) {
+        Ok(encoders) => filter_working_encoders(ffmpeg_path, encoders),
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::preview",
+                "resolve_preview_original_codec: encoder discovery failed: {}",
+                e
+            );
+            return preview_original_transcode_codec();
+        }
+    };
+    let codec =
+        pick_preview_codec(&working, requested).unwrap_or_else(preview_original_transcode_codec);
+    if requested.is_some_and(|r| r != codec) {
+        log::warn!(
+            target: "tiny_vid::preview",
+            "resolve_preview_original_codec: {:?} unavailable, using {}",
+            requested,
+            codec
+        );
+    }
+    codec
+}
+
 struct TempCleanup {
     paths: Vec<PathBuf>,
     keep: bool,
@@ -263,38 +361,179 @@ struct OriginalPreviewTranscodeCtx<'a> {
     preview_duration: f64,
     source_fps: f64,
     remove_audio: bool,
-    temp: &'a TempFileManager,
+    video_stream: u32,
+    requested_codec: Option<&'a str>,
+    ffmpeg: &'a FfmpegContext,
     file_signature: Option<&'a FileSignature>,
     emit: Option<(&'a tauri::AppHandle, &'a str)>,
     progress_ctx: Option<&'a PreviewProgressCtx>,
 }
 
-async fn get_video_metadata_async(path: &Path) -> Result<VideoMetadata, AppError> {
+pub(crate) async fn get_video_metadata_async(
+    ffmpeg: &FfmpegContext,
+    path: &Path,
+) -> Result<VideoMetadata, AppError> {
+    let ffprobe = ffmpeg.ffprobe_path()?;
     let path = path.to_path_buf();
-    tauri::async_runtime::spawn_blocking(move || get_video_metadata_impl(&path))
-        .await
-        .map_err(|e| AppError::from(e.to_string()))?
+    tauri::async_runtime::spawn_blocking(move || {
+        get_video_metadata_with_ffprobe(&ffprobe, &path, false)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))?
+}
+
+/// Loudness of the preview window's audio (the export's first selected track) as the export
+/// would encode it. `None` when the export keeps no source audio, the source has none, or
+/// measurement fails (logged; never fails the preview).
+async fn measure_preview_loudness(
+    ffmpeg: &FfmpegContext,
+    input_path: &Path,
+    options: &TranscodeOptions,
+    audio_stream_count: u32,
+    start_secs: f64,
+    duration_secs: f64,
+) -> Option<LoudnessInfo> {
+    if options.preview_audio_track().is_none() || audio_stream_count == 0 {
+        return None;
+    }
+    let input = input_path.to_path_buf();
+    let options = options.clone();
+    let ffmpeg = ffmpeg.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        measure_preview_audio_loudness(&ffmpeg, &input, &options, start_secs, duration_secs)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))
+    .and_then(|r| r);
+    match result {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::preview",
+                "measure_preview_loudness: failed: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// SSIM/PSNR of the compressed preview when `preview_quality_metrics` is set. Failures are
+/// logged; the preview is returned without them.
+async fn measure_preview_pair_quality(
+    ffmpeg: &FfmpegContext,
+    original: &Path,
+    compressed: &Path,
+    options: &TranscodeOptions,
+) -> Option<QualityMetrics> {
+    if !options.effective_preview_quality_metrics() {
+        return None;
+    }
+    let original = original.to_path_buf();
+    let compressed = compressed.to_path_buf();
+    let options = options.clone();
+    let ffmpeg = ffmpeg.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        measure_preview_quality(&ffmpeg, &original, &compressed, &options)
+    })
+    .await
+    .map_err(|e| AppError::from(e.to_string()))
+    .and_then(|r| r);
+    match result {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::preview",
+                "measure_preview_pair_quality: failed: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Runs `probe` on the original and compressed preview files concurrently.
+async fn probe_preview_pair<T: Send + 'static>(
+    ffmpeg: &FfmpegContext,
+    original: &Path,
+    compressed: &Path,
+    probe: fn(&Path, &Path) -> Option<T>,
+) -> (Option<T>, Option<T>) {
+    let Ok(ffprobe) = ffmpeg.ffprobe_path() else {
+        return (None, None);
+    };
+    let original = original.to_path_buf();
+    let compressed = compressed.to_path_buf();
+    let original_task = tauri::async_runtime::spawn_blocking({
+        let ffprobe = ffprobe.clone();
+        move || probe(&ffprobe, &original)
+    });
+    let compressed_task =
+        tauri::async_runtime::spawn_blocking(move || probe(&ffprobe, &compressed));
+    (
+        original_task.await.ok().flatten(),
+        compressed_task.await.ok().flatten(),
+    )
+}
+
+/// First-frame PTS and exact timing of both preview files.
+struct PreviewSyncInfo {
+    original_first_frame_pts: Option<f64>,
+    compressed_first_frame_pts: Option<f64>,
+    original_timing: Option<VideoTiming>,
+    compressed_timing: Option<VideoTiming>,
+}
+
+async fn probe_preview_sync_info(
+    ffmpeg: &FfmpegContext,
+    original: &Path,
+    compressed: &Path,
+) -> PreviewSyncInfo {
+    let (
+        (original_first_frame_pts, compressed_first_frame_pts),
+        (original_timing, compressed_timing),
+    ) = futures_util::join!(
+        probe_preview_pair(
+            ffmpeg,
+            original,
+            compressed,
+            probe_first_frame_pts_with_ffprobe
+        ),
+        probe_preview_pair(
+            ffmpeg,
+            original,
+            compressed,
+            probe_video_timing_with_ffprobe
+        ),
+    );
+    PreviewSyncInfo {
+        original_first_frame_pts,
+        compressed_first_frame_pts,
+        original_timing,
+        compressed_timing,
+    }
 }
 
 /// Extracts preview segments from input, or returns cached segment paths if available.
-/// strip_audio: when true, copy only video (-map 0:v -c:v copy -an).
+/// video_stream: which video stream to copy (audio is always stripped).
 /// step_label: when progress_ctx is Some, label for progress ("extract" or "estimate").
 async fn extract_segments_or_use_cache(
+    ffmpeg: &FfmpegContext,
     input_str: &str,
     preview_duration_u32: u32,
     preview_start_ms: u64,
     segments: &[(f64, f64)],
-    temp: &TempFileManager,
     file_signature: Option<&FileSignature>,
     emit: Option<(&tauri::AppHandle, &str)>,
     progress_ctx: Option<&PreviewProgressCtx>,
     step_label: &'static str,
-    strip_audio: bool,
+    video_stream: u32,
 ) -> Result<SegmentSet, AppError> {
     match get_cached_segments(
         input_str,
         preview_duration_u32,
         preview_start_ms,
+        video_stream,
         file_signature,
     ) {
         Some(cached) => {
@@ -320,7 +559,8 @@ async fn extract_segments_or_use_cache(
                 .iter()
                 .enumerate()
                 .map(|(i, _)| {
-                    temp.create(&format!("preview-original-{}.mp4", i), None)
+                    ffmpeg
+                        .create_temp(&format!("preview-original-{}.mp4", i), None)
                         .map_err(AppError::from)
                 })
                 .collect::<Result<Vec<_>, _>>()?;
@@ -331,10 +571,18 @@ async fn extract_segments_or_use_cache(
                     *start,
                     *dur,
                     &path_to_string(path),
-                    strip_audio,
+                    true,
+                    video_stream,
                 );
-                if let Err(err) =
-                    run_ffmpeg_with_progress(args, Some(*dur), emit, progress_ctx, step_label).await
+                if let Err(err) = run_ffmpeg_with_progress(
+                    ffmpeg,
+                    args,
+                    Some(*dur),
+                    emit,
+                    progress_ctx,
+                    step_label,
+                )
+                .await
                 {
                     for created in &paths {
                         let _ = fs::remove_file(created);
@@ -351,6 +599,7 @@ async fn extract_segments_or_use_cache(
 }
 
 async fn transcode_preview_segment(
+    ffmpeg: &FfmpegContext,
     segment_path: &PathBuf,
     output_path: &PathBuf,
     options: &TranscodeOptions,
@@ -358,7 +607,10 @@ async fn transcode_preview_segment(
     emit: Option<(&tauri::AppHandle, &str)>,
     progress_ctx: Option<&PreviewProgressCtx>,
 ) -> Result<(), AppError> {
-    let preview_opts = preview_transcode_options(options);
+    let mut preview_opts = preview_transcode_options(options);
+    // Extracted segments hold only the selected video stream.
+    preview_opts.video_stream_index = None;
+    preview_opts.video_stream_count = None;
     let args = build_ffmpeg_command(
         &path_to_string(segment_path),
         &path_to_string(output_path),
@@ -369,6 +621,7 @@ async fn transcode_preview_segment(
     )?;
 
     run_ffmpeg_with_progress(
+        ffmpeg,
         args,
         output_duration,
         emit,
@@ -385,6 +638,33 @@ fn preview_transcode_options(options: &TranscodeOptions) -> TranscodeOptions {
     preview_opts
 }
 
+/// Extra scale (0-1) that brings the output down to `preview_resolution_cap`, judged on the
+/// shorter side so portrait video is capped like landscape. None when the cap does not apply.
+fn preview_downscale(options: &TranscodeOptions, meta: &VideoMetadata) -> Option<f64> {
+    let cap = options.preview_resolution_cap.filter(|c| *c > 0)? as f64;
+    let (width, height) = options
+        .effective_crop()
+        .map_or((meta.width, meta.height), |(w, h, _, _)| (w, h));
+    let short_side = width.min(height) as f64 * options.effective_scale();
+    (short_side > cap).then(|| cap / short_side)
+}
+
+fn downscaled_options(options: &TranscodeOptions, factor: f64) -> TranscodeOptions {
+    TranscodeOptions {
+        scale: Some(options.effective_scale() * factor),
+        ..options.clone()
+    }
+}
+
+/// Multiplier from a sample rate measured at `factor` scale to the full-resolution rate.
+/// Target-size encodes hit the same bitrate at any resolution, so they need none.
+fn downscale_rate_correction(options: &TranscodeOptions, factor: f64) -> f64 {
+    if options.effective_rate_control_mode() == RateControlMode::TargetSize || factor >= 1.0 {
+        return 1.0;
+    }
+    (factor * factor).powf(-DOWNSCALE_SIZE_EXPONENT)
+}
+
 async fn transcode_original_preview_segment_or_use_cache(
     ctx: OriginalPreviewTranscodeCtx<'_>,
 ) -> Result<SegmentSet, AppError> {
@@ -392,6 +672,7 @@ async fn transcode_original_preview_segment_or_use_cache(
         ctx.input_str,
         ctx.preview_duration_u32,
         ctx.preview_start_ms,
+        ctx.video_stream,
         ctx.file_signature,
     ) {
         Some(cached) => {
@@ -410,16 +691,29 @@ async fn transcode_original_preview_segment_or_use_cache(
             })
         }
         None => {
+            let ffmpeg_path = ctx.ffmpeg.ffmpeg_path().to_path_buf();
+            let requested = ctx.requested_codec.map(str::to_string);
+            let codec = tauri::async_runtime::spawn_blocking(move || {
+                resolve_preview_original_codec(&ffmpeg_path, requested.as_deref())
+            })
+            .await
+            .map_err(|e| AppError::from(e.to_string()))?;
+            log::info!(
+                target: "tiny_vid::preview",
+                "transcode_original_preview_segment_or_use_cache: transcoding original with {}",
+                codec
+            );
             let orig_path = ctx
-                .temp
-                .create("preview-original-transcoded.mp4", None)
+                .ffmpeg
+                .create_temp("preview-original-transcoded.mp4", None)
                 .map_err(AppError::from)?;
             let orig_transcode_opts = preview_transcode_options(&TranscodeOptions {
-                codec: Some(preview_original_transcode_codec().to_string()),
+                codec: Some(codec.to_string()),
                 quality: Some(90),
                 preset: Some("fast".to_string()),
                 output_format: Some("mp4".to_string()),
                 remove_audio: Some(ctx.remove_audio),
+                video_stream_index: Some(ctx.video_stream),
                 scale: None,
                 fps: Some(if ctx.source_fps > 0.0 {
                     ctx.source_fps
@@ -437,6 +731,7 @@ async fn transcode_original_preview_segment_or_use_cache(
                 Some(ctx.preview_start_seconds),
             )?;
             if let Err(err) = run_ffmpeg_with_progress(
+                ctx.ffmpeg,
                 args,
                 Some(ctx.preview_duration),
                 ctx.emit,
@@ -461,8 +756,8 @@ fn clamp_sample_start(center: f64, sample_duration: f64, video_duration: f64) ->
     (center - (sample_duration / 2.0)).clamp(0.0, max_start)
 }
 
-fn sample_duration_for_video(video_duration: f64) -> f64 {
-    ESTIMATE_BASE_SAMPLE_DURATION_SECS.min(video_duration.max(0.1))
+fn sample_duration_for_video(video_duration: f64, sampling: &EstimateSampling) -> f64 {
+    sampling.sample_secs.min(video_duration.max(0.1))
 }
 
 fn sample_at_percent(
@@ -480,7 +775,10 @@ fn sample_at_percent(
     }
 }
 
-fn base_estimate_samples(video_duration: f64) -> Vec<EstimateSampleWindow> {
+fn base_estimate_samples(
+    video_duration: f64,
+    sampling: &EstimateSampling,
+) -> Vec<EstimateSampleWindow> {
     if video_duration <= 0.0 {
         return vec![];
     }
@@ -490,20 +788,76 @@ fn base_estimate_samples(video_duration: f64) -> Vec<EstimateSampleWindow> {
             duration_seconds: video_duration,
         }];
     }
-    let sample_duration = sample_duration_for_video(video_duration);
-    vec![
-        sample_at_percent(video_duration, sample_duration, 0.05),
-        sample_at_percent(video_duration, sample_duration, 0.50),
-        sample_at_percent(video_duration, sample_duration, 0.95),
-    ]
+    let sample_duration = sample_duration_for_video(video_duration, sampling);
+    sampling
+        .base_positions()
+        .into_iter()
+        .map(|p| sample_at_percent(video_duration, sample_duration, p))
+        .collect()
 }
 
-fn extra_estimate_samples(video_duration: f64) -> Vec<EstimateSampleWindow> {
-    let sample_duration = sample_duration_for_video(video_duration);
-    vec![
-        sample_at_percent(video_duration, sample_duration, 0.25),
-        sample_at_percent(video_duration, sample_duration, 0.75),
-    ]
+fn extra_estimate_samples(
+    video_duration: f64,
+    sampling: &EstimateSampling,
+) -> Vec<EstimateSampleWindow> {
+    let sample_duration = sample_duration_for_video(video_duration, sampling);
+    sampling
+        .extra_positions()
+        .into_iter()
+        .map(|p| sample_at_percent(video_duration, sample_duration, p))
+        .collect()
+}
+
+/// The source ranges an export keeps (cut segments or the trim range, else the whole source),
+/// laid end to end. Samples are planned on this timeline so they only cover exported content,
+/// and the sampled rate is scaled by its length rather than the source's.
+struct ExportTimeline {
+    ranges: Vec<(f64, f64)>,
+}
+
+impl ExportTimeline {
+    fn new(options: &TranscodeOptions, source_duration: f64) -> Self {
+        let source_duration = source_duration.max(0.0);
+        let ranges = match (options.effective_keep_segments(), options.effective_trim()) {
+            (Some(segments), _) => segments,
+            (None, Some((start, end))) => vec![(start, end.unwrap_or(source_duration))],
+            (None, None) => vec![(0.0, source_duration)],
+        };
+        let ranges = ranges
+            .into_iter()
+            .map(|(s, e)| (s.min(source_duration), e.min(source_duration)))
+            .filter(|(s, e)| e > s)
+            .collect();
+        Self { ranges }
+    }
+
+    fn duration(&self) -> f64 {
+        self.ranges.iter().map(|(s, e)| e - s).sum()
+    }
+
+    /// Source window for `window` on the export timeline. Samples cannot span a cut, so the
+    /// window stays inside the range holding its midpoint and is shortened to fit it.
+    fn source_window(&self, window: EstimateSampleWindow) -> EstimateSampleWindow {
+        let mut center = window.start_seconds + window.duration_seconds / 2.0;
+        let mut range = self.ranges.last().copied().unwrap_or((0.0, 0.0));
+        for &(start, end) in &self.ranges {
+            if center <= end - start {
+                range = (start, end);
+                break;
+            }
+            center -= end - start;
+        }
+        let (start, end) = range;
+        let duration_seconds = window.duration_seconds.min(end - start);
+        EstimateSampleWindow {
+            start_seconds: start + clamp_sample_start(center, duration_seconds, end - start),
+            duration_seconds,
+        }
+    }
+
+    fn source_windows(&self, windows: Vec<EstimateSampleWindow>) -> Vec<EstimateSampleWindow> {
+        windows.into_iter().map(|w| self.source_window(w)).collect()
+    }
 }
 
 fn coefficient_of_variation(values: &[f64]) -> f64 {
@@ -538,6 +892,16 @@ fn aggregate_bytes_per_sec(values: &[f64]) -> Option<f64> {
     Some(trimmed.iter().sum::<f64>() / trimmed.len() as f64)
 }
 
+/// Floor for the bounds of a partial estimate while samples are still missing: one sample says
+/// nothing about variation, so the range starts wide and narrows as samples come in.
+fn partial_confidence_band(samples_done: usize) -> (EstimateConfidence, f64) {
+    match samples_done {
+        0 | 1 => (EstimateConfidence::Low, 0.30),
+        2 => (EstimateConfidence::Medium, 0.15),
+        _ => (EstimateConfidence::High, 0.0),
+    }
+}
+
 fn confidence_band_for_cv(cv: f64) -> (EstimateConfidence, f64) {
     if cv <= ESTIMATE_HIGH_CONFIDENCE_MAX_CV {
         (EstimateConfidence::High, 0.08)
@@ -548,54 +912,191 @@ fn confidence_band_for_cv(cv: f64) -> (EstimateConfidence, f64) {
     }
 }
 
+/// How many estimate samples to encode at once. Hardware encoders and thread-limited runs go one
+/// at a time (consumer GPUs cap concurrent sessions; the limit is there to keep the machine
+/// responsive). Software encoders are multithreaded themselves, so one sample per two cores.
+fn estimate_sample_concurrency(codec: &str, cores: usize, thread_limited: bool) -> usize {
+    if thread_limited || is_hardware_codec(codec) {
+        return 1;
+    }
+    (cores / 2).clamp(1, ESTIMATE_MAX_PARALLEL_SAMPLES)
+}
+
+/// Temp outputs for `samples`, registered with `cleanup`. Names include the start so concurrent
+/// encodes never share a file.
+fn estimate_sample_outputs(
+    ffmpeg: &FfmpegContext,
+    options: &TranscodeOptions,
+    samples: &[EstimateSampleWindow],
+    cleanup: &mut TempCleanup,
+) -> Result<Vec<(EstimateSampleWindow, PathBuf)>, AppError> {
+    let output_format = options.effective_output_format();
+    samples
+        .iter()
+        .map(|sample| {
+            let output_path = ffmpeg.create_temp(
+                &format!(
+                    "preview-estimate-{}.{}",
+                    (sample.start_seconds * 1000.0) as u64,
+                    output_format
+                ),
+                None,
+            )?;
+            cleanup.add(output_path.clone());
+            Ok((*sample, output_path))
+        })
+        .collect()
+}
+
 async fn encode_estimate_sample(
+    ffmpeg: &FfmpegContext,
     input_path: &Path,
     options: &TranscodeOptions,
     sample: EstimateSampleWindow,
-    sample_index: usize,
-    cleanup: &mut TempCleanup,
+    output_path: &Path,
     emit: Option<(&tauri::AppHandle, &str)>,
-    progress_ctx: Option<&PreviewProgressCtx>,
+    progress_cb: Option<ProgressCallback>,
 ) -> Result<f64, AppError> {
     if sample.duration_seconds <= 0.0 {
         return Err(AppError::from(
             "Estimate sample duration must be greater than zero",
         ));
     }
-    let output_format = options.effective_output_format();
-    let output_path = TempFileManager
-        .create(
-            &format!("preview-estimate-{}.{}", sample_index, output_format),
-            None,
-        )
-        .map_err(AppError::from)?;
-    cleanup.add(output_path.clone());
-
-    let args = build_ffmpeg_command(
+    let args = build_video_only_ffmpeg_command(
         &path_to_string(input_path),
-        &path_to_string(&output_path),
+        &path_to_string(output_path),
         options,
         Some(sample.duration_seconds),
-        None,
         Some(sample.start_seconds),
     )?;
-    run_ffmpeg_with_progress(
+    run_ffmpeg_step(
+        ffmpeg,
         args,
-        Some(sample.duration_seconds),
         emit,
-        progress_ctx,
-        "preview_estimate",
+        Some(sample.duration_seconds),
+        progress_cb,
     )
     .await?;
 
-    let output_size = fs::metadata(&output_path)?.len() as f64;
+    let output_size = fs::metadata(output_path)?.len() as f64;
     Ok(output_size / sample.duration_seconds.max(0.001))
 }
 
+/// Encodes `samples` (each with its temp output), up to `estimate_sample_concurrency` at a time,
+/// and returns their rates in sample order. `on_sample(rate, seconds)` runs as each one finishes.
+async fn encode_estimate_samples(
+    ffmpeg: &FfmpegContext,
+    input_path: &Path,
+    options: &TranscodeOptions,
+    samples: &[(EstimateSampleWindow, PathBuf)],
+    emit: Option<(&tauri::AppHandle, &str)>,
+    progress_ctx: Option<&PreviewProgressCtx>,
+    on_sample: impl Fn(f64, f64),
+) -> Result<Vec<f64>, AppError> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let concurrency = estimate_sample_concurrency(
+        options.effective_codec(),
+        cores,
+        ffmpeg.thread_limit().is_some(),
+    );
+    let permits = Semaphore::new(concurrency);
+    let callbacks: Vec<Option<ProgressCallback>> = match progress_ctx {
+        Some(ctx) => ctx
+            .make_parallel_callbacks("preview_estimate", samples.len())
+            .into_iter()
+            .map(Some)
+            .collect(),
+        None => vec![None; samples.len()],
+    };
+    let permits = &permits;
+    let on_sample = &on_sample;
+    let encodes =
+        samples
+            .iter()
+            .zip(callbacks)
+            .map(|((sample, output_path), progress_cb)| async move {
+                let _permit = permits
+                    .acquire()
+                    .await
+                    .map_err(|e| AppError::from(e.to_string()))?;
+                let rate = encode_estimate_sample(
+                    ffmpeg,
+                    input_path,
+                    options,
+                    *sample,
+                    output_path,
+                    emit,
+                    progress_cb,
+                )
+                .await?;
+                on_sample(rate, sample.duration_seconds);
+                Ok(rate)
+            });
+    log::debug!(
+        target: "tiny_vid::preview",
+        "encode_estimate_samples: {} sample(s), {} at a time",
+        samples.len(),
+        concurrency
+    );
+    join_all(encodes).await.into_iter().collect()
+}
+
+/// `estimate-partial` payload: the estimate from the samples encoded so far, audio included.
+/// The final estimate still arrives with the preview result.
+#[derive(serde::Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EstimatePartialPayload {
+    #[serde(flatten)]
+    pub estimate: SizeEstimate,
+    pub samples_done: u32,
+    /// Grows when extra samples are added for varied content.
+    pub samples_planned: u32,
+}
+
+/// Video-only estimate from per-sample rates. `partial_of` is the planned sample count while
+/// samples are still missing; the bounds are then at least `partial_confidence_band` wide.
+fn estimate_from_rates(
+    sample_rates: &[f64],
+    sample_seconds_total: f64,
+    video_duration: f64,
+    max_reasonable: u64,
+    partial_of: Option<usize>,
+) -> Option<SizeEstimate> {
+    let aggregate_bps = aggregate_bytes_per_sec(sample_rates)?;
+    let best_size = ((aggregate_bps * video_duration).max(0.0) as u64).min(max_reasonable);
+    let cv = coefficient_of_variation(sample_rates);
+    let (mut confidence, mut band) = confidence_band_for_cv(cv);
+    if partial_of.is_some_and(|planned| sample_rates.len() < planned) {
+        let (partial_confidence, partial_band) = partial_confidence_band(sample_rates.len());
+        if partial_band > band {
+            confidence = partial_confidence;
+            band = partial_band;
+        }
+    }
+    let low_size = ((best_size as f64 * (1.0 - band)).max(0.0) as u64).min(best_size);
+    let high_size = ((best_size as f64 * (1.0 + band)) as u64)
+        .max(best_size)
+        .min(max_reasonable);
+    Some(SizeEstimate {
+        best_size,
+        low_size,
+        high_size,
+        confidence,
+        method: ESTIMATE_METHOD.to_string(),
+        sample_count: sample_rates.len() as u32,
+        sample_seconds_total,
+    })
+}
+
+/// Samples the video part of the output size; audio is added by `with_audio`. With `emit`,
+/// sends `estimate-partial` after every sample. Sample rates are multiplied by `rate_correction`
+/// (see `downscale_rate_correction`).
 async fn compute_estimate_size(
+    ffmpeg: &FfmpegContext,
     input_path: &Path,
     video_duration: f64,
     options: &TranscodeOptions,
+    rate_correction: f64,
     emit: Option<(&tauri::AppHandle, &str)>,
     progress_ctx: Option<&PreviewProgressCtx>,
 ) -> Result<SizeEstimate, AppError> {
@@ -603,96 +1104,251 @@ async fn compute_estimate_size(
         return Err(AppError::from("Invalid video duration for size estimation"));
     }
 
+    let timeline = ExportTimeline::new(options, video_duration);
+    let output_duration = timeline.duration();
+    if output_duration <= 0.0 {
+        return Err(AppError::from("Trim leaves nothing to estimate"));
+    }
+
+    let sampling = options.effective_estimate_sampling();
+    sampling.validate()?;
+
     let input_size = fs::metadata(input_path)?.len();
     let max_reasonable = input_size.saturating_mul(2);
 
-    let base_samples = base_estimate_samples(video_duration);
+    let base_samples = timeline.source_windows(base_estimate_samples(output_duration, &sampling));
     if base_samples.is_empty() {
         return Err(AppError::from("No estimate samples were planned"));
     }
-    let mut remaining_extra_steps = if base_samples.len() == 3 {
-        estimate_step_count(video_duration).saturating_sub(base_samples.len())
+    let mut remaining_extra_steps = if output_duration > ESTIMATE_SHORT_VIDEO_THRESHOLD_SECS {
+        estimate_step_count(output_duration, &sampling).saturating_sub(base_samples.len())
     } else {
         0
     };
 
     let mut cleanup = TempCleanup::new();
-    let mut sample_rates = Vec::new();
-    let mut sample_seconds_total = 0.0;
-    let mut sample_index = 0usize;
+    // Rates in the order the samples finished, for the partial estimates.
+    let finished: Mutex<(Vec<f64>, f64)> = Mutex::new((Vec::new(), 0.0));
+    let mut samples_planned = base_samples.len();
+    let emit_partial = |rates: &[f64], seconds: f64, planned: usize| {
+        let Some((app, label)) = emit else {
+            return;
+        };
+        if let Some(video) = estimate_from_rates(
+            rates,
+            seconds,
+            output_duration,
+            max_reasonable,
+            Some(planned),
+        ) {
+            let payload = EstimatePartialPayload {
+                estimate: with_audio(video, options, output_duration),
+                samples_done: rates.len() as u32,
+                samples_planned: planned as u32,
+            };
+            let _ = app.emit_to(label, "estimate-partial", Versioned::new(payload));
+        }
+    };
+
+    let base_outputs = estimate_sample_outputs(ffmpeg, options, &base_samples, &mut cleanup)?;
+    let mut sample_rates = encode_estimate_samples(
+        ffmpeg,
+        input_path,
+        options,
+        &base_outputs,
+        emit,
+        progress_ctx,
+        |rate, seconds| {
+            let mut finished = finished.lock();
+            finished.0.push(rate * rate_correction);
+            finished.1 += seconds;
+            if finished.0.len() < samples_planned {
+                emit_partial(&finished.0, finished.1, samples_planned);
+            }
+        },
+    )
+    .await?;
+    let mut sample_seconds_total: f64 = base_samples.iter().map(|s| s.duration_seconds).sum();
 
-    for sample in &base_samples {
-        let bytes_per_sec = encode_estimate_sample(
+    let base_cv = coefficient_of_variation(&sample_rates);
+    let should_add_extra_samples = output_duration >= ESTIMATE_ADAPTIVE_MIN_DURATION_SECS
+        && base_cv > ESTIMATE_EXTRA_SAMPLE_CV_THRESHOLD
+        && sample_seconds_total < sampling.max_sampled_secs;
+    let extra_samples: Vec<EstimateSampleWindow> = if should_add_extra_samples {
+        let mut budget = sample_seconds_total;
+        timeline
+            .source_windows(extra_estimate_samples(output_duration, &sampling))
+            .into_iter()
+            .take_while(|sample| {
+                budget += sample.duration_seconds;
+                budget <= sampling.max_sampled_secs
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    samples_planned += extra_samples.len();
+    emit_partial(&sample_rates, sample_seconds_total, samples_planned);
+    if !extra_samples.is_empty() {
+        let extra_outputs = estimate_sample_outputs(ffmpeg, options, &extra_samples, &mut cleanup)?;
+        let extra_rates = encode_estimate_samples(
+            ffmpeg,
             input_path,
             options,
-            *sample,
-            sample_index,
-            &mut cleanup,
+            &extra_outputs,
             emit,
             progress_ctx,
+            |rate, seconds| {
+                let mut finished = finished.lock();
+                finished.0.push(rate * rate_correction);
+                finished.1 += seconds;
+                emit_partial(&finished.0, finished.1, samples_planned);
+            },
         )
         .await?;
-        sample_rates.push(bytes_per_sec);
-        sample_seconds_total += sample.duration_seconds;
-        sample_index += 1;
+        sample_rates.extend(extra_rates);
+        sample_seconds_total += extra_samples
+            .iter()
+            .map(|s| s.duration_seconds)
+            .sum::<f64>();
+        remaining_extra_steps = remaining_extra_steps.saturating_sub(extra_samples.len());
+    }
+    complete_progress_steps(progress_ctx, remaining_extra_steps, "preview_estimate");
+    for rate in &mut sample_rates {
+        *rate *= rate_correction;
     }
 
-    let base_cv = coefficient_of_variation(&sample_rates);
-    let should_add_extra_samples = video_duration >= ESTIMATE_ADAPTIVE_MIN_DURATION_SECS
-        && base_cv > ESTIMATE_EXTRA_SAMPLE_CV_THRESHOLD
-        && sample_seconds_total < ESTIMATE_MAX_SAMPLED_SECONDS;
-    if should_add_extra_samples {
-        for sample in extra_estimate_samples(video_duration) {
-            if sample_seconds_total + sample.duration_seconds > ESTIMATE_MAX_SAMPLED_SECONDS {
-                break;
-            }
-            let bytes_per_sec = encode_estimate_sample(
+    estimate_from_rates(
+        &sample_rates,
+        sample_seconds_total,
+        output_duration,
+        max_reasonable,
+        None,
+    )
+    .ok_or_else(|| AppError::from("Unable to aggregate estimate sample bitrates"))
+}
+
+/// Adds the export's audio to a video-only estimate. Audio is close to constant bitrate, so it
+/// shifts the range without widening it.
+fn with_audio(video: SizeEstimate, options: &TranscodeOptions, duration: f64) -> SizeEstimate {
+    let audio_bytes = (estimated_audio_kbps(options) * 1000.0 / 8.0 * duration.max(0.0)) as u64;
+    SizeEstimate {
+        best_size: video.best_size + audio_bytes,
+        low_size: video.low_size + audio_bytes,
+        high_size: video.high_size + audio_bytes,
+        ..video
+    }
+}
+
+/// Size estimate from cached video samples when possible, sampling otherwise. The video part
+/// is cached without audio settings (quality mode), so audio-only changes cost no encode.
+/// `downscale` (from `preview_downscale`) samples at the capped preview resolution.
+async fn estimate_with_cache(
+    ffmpeg: &FfmpegContext,
+    input_path: &Path,
+    video_duration: f64,
+    options: &TranscodeOptions,
+    downscale: Option<f64>,
+    emit: Option<(&tauri::AppHandle, &str)>,
+    progress_ctx: Option<&PreviewProgressCtx>,
+) -> Result<SizeEstimate, AppError> {
+    let input_str = path_to_string(input_path);
+    let preview_duration = options.effective_preview_duration();
+    let output_duration = ExportTimeline::new(options, video_duration).duration();
+    let file_sig = file_signature(input_path);
+    let video = match get_cached_estimate(&input_str, preview_duration, options, file_sig.as_ref())
+    {
+        Some(cached) => {
+            complete_progress_steps(
+                progress_ctx,
+                estimate_step_count(output_duration, &options.effective_estimate_sampling()),
+                "preview_estimate",
+            );
+            cached
+        }
+        None => {
+            let (sample_options, rate_correction) = match downscale {
+                Some(factor) => (
+                    downscaled_options(options, factor),
+                    downscale_rate_correction(options, factor),
+                ),
+                None => (options.clone(), 1.0),
+            };
+            let fresh = compute_estimate_size(
+                ffmpeg,
                 input_path,
-                options,
-                sample,
-                sample_index,
-                &mut cleanup,
+                video_duration,
+                &sample_options,
+                rate_correction,
                 emit,
                 progress_ctx,
             )
             .await?;
-            sample_rates.push(bytes_per_sec);
-            sample_seconds_total += sample.duration_seconds;
-            sample_index += 1;
-            remaining_extra_steps = remaining_extra_steps.saturating_sub(1);
+            set_cached_estimate(
+                &input_str,
+                preview_duration,
+                options,
+                fresh.clone(),
+                file_sig.as_ref(),
+            );
+            fresh
         }
-    }
-    complete_progress_steps(progress_ctx, remaining_extra_steps, "preview_estimate");
-
-    let aggregate_bps = aggregate_bytes_per_sec(&sample_rates)
-        .ok_or_else(|| AppError::from("Unable to aggregate estimate sample bitrates"))?;
-    let best_size = ((aggregate_bps * video_duration).max(0.0) as u64).min(max_reasonable);
-    let cv = coefficient_of_variation(&sample_rates);
-    let (confidence, band) = confidence_band_for_cv(cv);
-    let low_size = ((best_size as f64 * (1.0 - band)).max(0.0) as u64).min(best_size);
-    let high_size = ((best_size as f64 * (1.0 + band)) as u64)
-        .max(best_size)
-        .min(max_reasonable);
+    };
+    Ok(with_audio(video, options, output_duration))
+}
 
-    Ok(SizeEstimate {
-        best_size,
-        low_size,
-        high_size,
-        confidence,
-        method: ESTIMATE_METHOD.to_string(),
-        sample_count: sample_rates.len() as u32,
-        sample_seconds_total,
-    })
+/// Sampled size estimate without progress events, served from the estimate cache when possible.
+pub(crate) async fn estimate_size_quiet(
+    ffmpeg: &FfmpegContext,
+    input_path: &Path,
+    video_duration: f64,
+    options: &TranscodeOptions,
+) -> Result<SizeEstimate, AppError> {
+    let options = TranscodeOptions {
+        preview_resolution_cap: None,
+        ..options.clone()
+    };
+    estimate_with_cache(
+        ffmpeg,
+        input_path,
+        video_duration,
+        &options,
+        None,
+        None,
+        None,
+    )
+    .await
 }
 
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct PreviewResult {
-    pub(crate) original_path: String,
-    pub(crate) compressed_path: String,
+pub struct PreviewResult {
+    pub original_path: String,
+    pub compressed_path: String,
     /// Start offset (seconds) of the original. Compressed typically has 0. Used to delay compressed playback for sync.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub(crate) start_offset_seconds: Option<f64>,
+    pub start_offset_seconds: Option<f64>,
+    /// PTS (seconds) of the first decoded frame of the original preview file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_first_frame_pts: Option<f64>,
+    /// PTS (seconds) of the first decoded frame of the compressed preview file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed_first_frame_pts: Option<f64>,
+    /// Frame count and exact duration of the original preview file, for sample-accurate looping.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_timing: Option<VideoTiming>,
+    /// Frame count and exact duration of the compressed preview file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed_timing: Option<VideoTiming>,
+    /// Loudness of the preview window's audio encoded with the export settings; None when audio is removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<LoudnessInfo>,
+    /// Extra scale (0-1) applied to the compressed preview by `preview_resolution_cap`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_scale: Option<f64>,
+    /// SSIM/PSNR of compressed against original; set with `preview_quality_metrics`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityMetrics>,
 }
 
 /// Result of preview with optional size estimate. Used when include_estimate is true.
@@ -703,107 +1359,134 @@ pub(crate) struct PreviewWithEstimateResult {
     pub(crate) preview: PreviewResult,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) estimate: Option<SizeEstimate>,
+    /// Source size against the estimate's best guess, formatted. Set with `estimate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) size_summary: Option<SizeSummary>,
+    /// Compression ratio and bits per pixel of the source and the estimated output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) compression: Option<CompressionMetrics>,
+    /// Set when the estimate's best guess is not smaller than the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) larger_output: Option<LargerOutputWarning>,
+    /// Id the preview ran under (see ffmpeg-job-started). Set by `ffmpeg_preview`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) job_id: Option<u64>,
 }
 
 /// Unified preview + estimate. Runs both phases with a single progress stream 0-1.
 /// Preview uses steps 0..PREVIEW_STEPS, estimate uses steps PREVIEW_STEPS..total.
 /// Fetches metadata once to compute accurate total steps (avoids progress bar stuck for short videos).
-/// When emit is None, runs silently (e.g. for tests).
+/// When emit is None, runs silently (e.g. for tests). With `channel`, progress goes there instead
+/// of ffmpeg-progress events.
 pub(crate) async fn run_preview_with_estimate_core(
+    ffmpeg: &FfmpegContext,
     input_path: &Path,
     options: &TranscodeOptions,
     preview_start_seconds: Option<f64>,
     emit: PreviewEmit,
+    channel: Option<ProgressChannel>,
 ) -> Result<PreviewWithEstimateResult, AppError> {
-    let meta = get_video_metadata_async(input_path).await?;
-    let estimate_steps = estimate_step_count(meta.duration);
+    let meta = get_video_metadata_async(ffmpeg, input_path).await?;
+    let estimate_steps = estimate_step_count(
+        ExportTimeline::new(options, meta.duration).duration(),
+        &options.effective_estimate_sampling(),
+    );
     let total_steps = PREVIEW_STEPS + estimate_steps;
     let emit_ref = emit.as_ref().map(|(a, l)| (a, l.as_str()));
 
     let (preview_ctx, estimate_ctx) = match emit.as_ref() {
-        Some((app, label)) => (
-            Some(PreviewProgressCtx::new(
-                app.clone(),
-                label.clone(),
-                0,
-                total_steps,
-            )),
-            Some(PreviewProgressCtx::new(
-                app.clone(),
-                label.clone(),
-                PREVIEW_STEPS,
-                total_steps,
-            )),
-        ),
+        Some((app, label)) => {
+            let sink = ProgressSink::new(app, label, channel);
+            (
+                Some(PreviewProgressCtx::new(sink.clone(), 0, total_steps)),
+                Some(PreviewProgressCtx::new(sink, PREVIEW_STEPS, total_steps)),
+            )
+        }
         None => (None, None),
     };
 
     let preview_result = run_preview_core(
+        ffmpeg,
         input_path,
         options,
         preview_start_seconds,
         emit.clone(),
         preview_ctx,
-        Some(meta.duration),
         Some(meta.clone()),
     )
     .await?;
 
-    let input_str = path_to_string(&input_path);
-    let preview_duration_u32 = options.effective_preview_duration();
-    let file_sig = file_signature(input_path);
-
-    let mut estimate =
-        get_cached_estimate(&input_str, preview_duration_u32, options, file_sig.as_ref());
-    if estimate.is_some() {
-        complete_progress_steps(estimate_ctx.as_ref(), estimate_steps, "preview_estimate");
-    } else {
-        match compute_estimate_size(
-            input_path,
-            meta.duration,
-            options,
-            emit_ref,
-            estimate_ctx.as_ref(),
-        )
-        .await
-        {
-            Ok(fresh) => {
-                set_cached_estimate(
-                    &input_str,
-                    preview_duration_u32,
-                    options,
-                    fresh.clone(),
-                    file_sig.as_ref(),
-                );
-                estimate = Some(fresh);
-            }
-            Err(err) => {
-                log::warn!(
-                    target: "tiny_vid::preview",
-                    "run_preview_with_estimate_core: failed to compute estimate: {}",
-                    err
-                );
-                estimate = None;
-            }
+    let estimate = match estimate_with_cache(
+        ffmpeg,
+        input_path,
+        meta.duration,
+        options,
+        preview_downscale(options, &meta),
+        emit_ref,
+        estimate_ctx.as_ref(),
+    )
+    .await
+    {
+        Ok(estimate) => Some(estimate),
+        Err(err) => {
+            log::warn!(
+                target: "tiny_vid::preview",
+                "run_preview_with_estimate_core: failed to compute estimate: {}",
+                err
+            );
+            None
         }
-    }
+    };
 
+    let size_summary = estimate
+        .as_ref()
+        .map(|e| SizeSummary::new(meta.size, e.best_size));
+    let compression = estimate
+        .as_ref()
+        .map(|e| CompressionMetrics::new(&meta, options, e.best_size));
+    let larger_output = estimate
+        .as_ref()
+        .and_then(|e| LargerOutputWarning::check(meta.size, e.best_size, options));
     Ok(PreviewWithEstimateResult {
         preview: preview_result,
         estimate,
+        size_summary,
+        compression,
+        larger_output,
+        job_id: None,
     })
 }
 
+/// Preview for embedders: emits no events, aborts when `ffmpeg`'s token is cancelled, and
+/// dropping the future (e.g. on timeout) kills the running FFmpeg step.
+pub async fn preview_async(
+    ffmpeg: &FfmpegContext,
+    input_path: &Path,
+    options: &TranscodeOptions,
+    preview_start_seconds: Option<f64>,
+) -> Result<PreviewResult, AppError> {
+    run_preview_core(
+        ffmpeg,
+        input_path,
+        options,
+        preview_start_seconds,
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
 /// Core preview logic. When emit is None, runs silently (tests).
 /// `progress_ctx_override`: when Some, uses it for progress (e.g. unified preview+estimate).
-/// `video_duration_override` / `meta_override`: when Some, skip ffprobe when caller already has it.
+/// `meta_override`: when Some, skip ffprobe when caller already has it.
 pub(crate) async fn run_preview_core(
+    ffmpeg: &FfmpegContext,
     input_path: &Path,
     options: &TranscodeOptions,
     preview_start_seconds: Option<f64>,
     emit: PreviewEmit,
     progress_ctx_override: Option<PreviewProgressCtx>,
-    video_duration_override: Option<f64>,
     meta_override: Option<VideoMetadata>,
 ) -> Result<PreviewResult, AppError> {
     let input_str = path_to_string(&input_path);
@@ -814,19 +1497,15 @@ pub(crate) async fn run_preview_core(
     let progress_ctx = match progress_ctx_override {
         Some(ctx) => Some(ctx),
         None => emit_ref.map(|(app, label)| {
-            PreviewProgressCtx::new(app.clone(), label.to_string(), 0, PREVIEW_STEPS)
+            PreviewProgressCtx::new(ProgressSink::new(app, label, None), 0, PREVIEW_STEPS)
         }),
     };
 
-    if let Some((app, label)) = emit.as_ref() {
-        let _ = app.emit_to(
-            label,
-            "ffmpeg-progress",
-            FfmpegProgressPayload {
-                progress: 0.0,
-                step: Some("generating_preview".to_string()),
-            },
-        );
+    if let Some(ctx) = progress_ctx.as_ref() {
+        ctx.sink.send(FfmpegProgressPayload::new(
+            0.0,
+            Some(ProgressStep::new("generating_preview", 0, PREVIEW_STEPS)),
+        ));
     }
 
     log::info!(
@@ -838,10 +1517,22 @@ pub(crate) async fn run_preview_core(
     let meta = if let Some(m) = meta_override {
         m
     } else {
-        get_video_metadata_async(input_path).await?
+        get_video_metadata_async(ffmpeg, input_path).await?
     };
-    let preview_opts = preview_transcode_options(options);
-    let video_duration = video_duration_override.unwrap_or(meta.duration);
+    let preview_scale = preview_downscale(options, &meta);
+    let preview_opts = match preview_scale {
+        Some(factor) => {
+            log::info!(
+                target: "tiny_vid::preview",
+                "run_preview_core: capping preview at {:?} lines (scale x{:.3})",
+                options.preview_resolution_cap,
+                factor
+            );
+            preview_transcode_options(&downscaled_options(options, factor))
+        }
+        None => preview_transcode_options(options),
+    };
+    let video_duration = meta.duration;
     let source_codec = meta.codec_name.as_deref().unwrap_or("unknown");
     let can_stream_copy_video = is_preview_stream_copy_safe_codec(source_codec);
     let can_stream_copy_original_preview = can_stream_copy_video;
@@ -870,14 +1561,34 @@ pub(crate) async fn run_preview_core(
             target: "tiny_vid::preview",
             "run_preview_core: cache hit, reusing output"
         );
-        let start_offset_seconds = get_video_metadata_async(&original_path)
+        let start_offset_seconds = get_video_metadata_async(ffmpeg, &original_path)
             .await
             .ok()
             .and_then(|m| m.start_time);
+        let sync = probe_preview_sync_info(ffmpeg, &original_path, &compressed_path).await;
+        let loudness = measure_preview_loudness(
+            ffmpeg,
+            input_path,
+            options,
+            meta.audio_stream_count,
+            preview_start_seconds,
+            preview_duration,
+        )
+        .await;
+        let quality =
+            measure_preview_pair_quality(ffmpeg, &original_path, &compressed_path, &preview_opts)
+                .await;
         return Ok(PreviewResult {
             original_path: path_to_string(&original_path),
             compressed_path: path_to_string(&compressed_path),
             start_offset_seconds,
+            original_first_frame_pts: sync.original_first_frame_pts,
+            compressed_first_frame_pts: sync.compressed_first_frame_pts,
+            original_timing: sync.original_timing,
+            compressed_timing: sync.compressed_timing,
+            loudness,
+            preview_scale,
+            quality,
         });
     }
 
@@ -885,24 +1596,25 @@ pub(crate) async fn run_preview_core(
 
     let preview_suffix = "preview-output.mp4";
 
-    let temp = TempFileManager;
-    let output_path = temp.create(preview_suffix, None).map_err(AppError::from)?;
+    let output_path = ffmpeg
+        .create_temp(preview_suffix, None)
+        .map_err(AppError::from)?;
     let mut cleanup = TempCleanup::new();
     cleanup.add(output_path.clone());
 
     let preview_segments = vec![(preview_start_seconds, preview_duration)];
     let segment_set = if can_stream_copy_original_preview {
         match extract_segments_or_use_cache(
+            ffmpeg,
             &input_str,
             preview_duration_u32,
             preview_start_ms,
             &preview_segments,
-            &temp,
             file_sig.as_ref(),
             None,
             progress_ctx.as_ref(),
             "preview_extract",
-            true,
+            options.effective_video_stream_index(),
         )
         .await
         {
@@ -926,7 +1638,9 @@ pub(crate) async fn run_preview_core(
                     preview_duration,
                     source_fps: meta.fps,
                     remove_audio: true,
-                    temp: &temp,
+                    video_stream: options.effective_video_stream_index(),
+                    requested_codec: options.preview_codec.as_deref(),
+                    ffmpeg,
                     file_signature: file_sig.as_ref(),
                     emit: emit_ref,
                     progress_ctx: progress_ctx.as_ref(),
@@ -948,7 +1662,9 @@ pub(crate) async fn run_preview_core(
             preview_duration,
             source_fps: meta.fps,
             remove_audio: true,
-            temp: &temp,
+            video_stream: options.effective_video_stream_index(),
+            requested_codec: options.preview_codec.as_deref(),
+            ffmpeg,
             file_signature: file_sig.as_ref(),
             emit: emit_ref,
             progress_ctx: progress_ctx.as_ref(),
@@ -962,6 +1678,7 @@ pub(crate) async fn run_preview_core(
     }
 
     transcode_preview_segment(
+        ffmpeg,
         &segment_set.paths[0],
         &output_path,
         &preview_opts,
@@ -981,34 +1698,62 @@ pub(crate) async fn run_preview_core(
         output_path.clone(),
         file_sig.as_ref(),
     );
-    let start_offset_seconds = get_video_metadata_async(&segment_set.paths[0])
+    let start_offset_seconds = get_video_metadata_async(ffmpeg, &segment_set.paths[0])
         .await
         .ok()
         .and_then(|m| m.start_time);
+    let sync = probe_preview_sync_info(ffmpeg, &segment_set.paths[0], &output_path).await;
+    let loudness = measure_preview_loudness(
+        ffmpeg,
+        input_path,
+        options,
+        meta.audio_stream_count,
+        preview_start_seconds,
+        preview_duration,
+    )
+    .await;
+    let quality =
+        measure_preview_pair_quality(ffmpeg, &segment_set.paths[0], &output_path, &preview_opts)
+            .await;
     log::info!(
         target: "tiny_vid::preview",
-        "run_preview_core: complete, start_offset_seconds={:?}",
-        start_offset_seconds
+        "run_preview_core: complete, start_offset_seconds={:?}, first_frame_pts={:?}/{:?}, timing={:?}/{:?}",
+        start_offset_seconds,
+        sync.original_first_frame_pts,
+        sync.compressed_first_frame_pts,
+        sync.original_timing,
+        sync.compressed_timing
     );
     cleanup.keep();
     Ok(PreviewResult {
         original_path: path_to_string(&segment_set.paths[0]),
         compressed_path: path_to_string(&output_path),
         start_offset_seconds,
+        original_first_frame_pts: sync.original_first_frame_pts,
+        compressed_first_frame_pts: sync.compressed_first_frame_pts,
+        original_timing: sync.original_timing,
+        compressed_timing: sync.compressed_timing,
+        loudness,
+        preview_scale,
+        quality,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        ESTIMATE_BASE_SAMPLE_DURATION_SECS, EstimateConfidence, EstimateSampleWindow,
-        base_estimate_samples, clamp_preview_start_seconds, coefficient_of_variation,
-        confidence_band_for_cv,
+        EstimateConfidence, EstimateSampleWindow, EstimateSampling, ExportTimeline, SizeEstimate,
+        TranscodeOptions, base_estimate_samples, clamp_preview_start_seconds,
+        coefficient_of_variation, confidence_band_for_cv, downscale_rate_correction,
+        estimate_from_rates, estimate_sample_concurrency, estimate_step_count,
+        extra_estimate_samples, pick_preview_codec, preview_downscale, with_audio,
     };
+    use crate::ffmpeg::RateControlMode;
+    use crate::ffmpeg::ffprobe::VideoMetadata;
 
     #[test]
     fn base_estimate_samples_short_video_uses_single_full_sample() {
-        let segs = base_estimate_samples(10.0);
+        let segs = base_estimate_samples(10.0, &EstimateSampling::default());
         assert_eq!(
             segs,
             vec![EstimateSampleWindow {
@@ -1020,13 +1765,132 @@ mod tests {
 
     #[test]
     fn base_estimate_samples_long_video_uses_three_positions() {
-        let segs = base_estimate_samples(60.0);
+        let segs = base_estimate_samples(60.0, &EstimateSampling::default());
         assert_eq!(segs.len(), 3);
-        assert_eq!(segs[0].duration_seconds, ESTIMATE_BASE_SAMPLE_DURATION_SECS);
+        assert_eq!(segs[0].duration_seconds, 1.5);
         assert!(segs[0].start_seconds >= 0.0);
         assert!(segs[2].start_seconds >= segs[1].start_seconds);
     }
 
+    #[test]
+    fn estimate_samples_follow_the_sampling_settings() {
+        let sampling = EstimateSampling {
+            sample_count: 5,
+            sample_secs: 0.5,
+            max_sampled_secs: 4.0,
+            ..EstimateSampling::default()
+        };
+        let segs = base_estimate_samples(100.0, &sampling);
+        assert_eq!(segs.len(), 5);
+        assert!(segs.iter().all(|s| s.duration_seconds == 0.5));
+        assert_eq!(segs[2].start_seconds, 49.75);
+        assert_eq!(extra_estimate_samples(100.0, &sampling).len(), 4);
+        assert_eq!(estimate_step_count(100.0, &sampling), 9);
+        assert_eq!(estimate_step_count(100.0, &EstimateSampling::default()), 5);
+        assert_eq!(estimate_step_count(10.0, &sampling), 1);
+    }
+
+    #[test]
+    fn estimate_samples_stay_inside_the_trim_range() {
+        let options = TranscodeOptions {
+            trim_start_secs: Some(100.0),
+            trim_end_secs: Some(160.0),
+            ..TranscodeOptions::default()
+        };
+        let timeline = ExportTimeline::new(&options, 600.0);
+        assert_eq!(timeline.duration(), 60.0);
+        let samples = base_estimate_samples(timeline.duration(), &EstimateSampling::default());
+        for sample in timeline.source_windows(samples) {
+            assert!(sample.start_seconds >= 100.0);
+            assert!(sample.start_seconds + sample.duration_seconds <= 160.0);
+        }
+        // The end of the trim is capped at the source's end.
+        let open_end = TranscodeOptions {
+            trim_start_secs: Some(590.0),
+            trim_end_secs: Some(700.0),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(ExportTimeline::new(&open_end, 600.0).duration(), 10.0);
+        assert_eq!(
+            ExportTimeline::new(&TranscodeOptions::default(), 42.0).duration(),
+            42.0
+        );
+    }
+
+    #[test]
+    fn estimate_samples_map_onto_kept_segments_without_spanning_cuts() {
+        let options = TranscodeOptions {
+            keep_segments: Some(vec![(0.0, 10.0), (50.0, 51.0), (100.0, 140.0)]),
+            ..TranscodeOptions::default()
+        };
+        let timeline = ExportTimeline::new(&options, 200.0);
+        assert_eq!(timeline.duration(), 51.0);
+        // 10.5s into the export is the middle of the one-second segment.
+        let short = timeline.source_window(EstimateSampleWindow {
+            start_seconds: 9.75,
+            duration_seconds: 1.5,
+        });
+        assert_eq!(
+            short,
+            EstimateSampleWindow {
+                start_seconds: 50.0,
+                duration_seconds: 1.0
+            }
+        );
+        let late = timeline.source_window(EstimateSampleWindow {
+            start_seconds: 30.0,
+            duration_seconds: 1.5,
+        });
+        assert_eq!(late.start_seconds, 119.0);
+    }
+
+    #[test]
+    fn with_audio_shifts_video_estimate_by_audio_bytes() {
+        let video = SizeEstimate {
+            best_size: 1_000_000,
+            low_size: 900_000,
+            high_size: 1_100_000,
+            confidence: EstimateConfidence::High,
+            method: "sampled_bitrate".into(),
+            sample_count: 3,
+            sample_seconds_total: 4.5,
+        };
+        let options = TranscodeOptions {
+            audio_bitrate: Some(128),
+            ..TranscodeOptions::default()
+        };
+        let estimate = with_audio(video.clone(), &options, 10.0);
+        assert_eq!(estimate.best_size, 1_160_000);
+        assert_eq!(estimate.low_size, 1_060_000);
+        assert_eq!(estimate.high_size, 1_260_000);
+
+        let silent = TranscodeOptions {
+            remove_audio: Some(true),
+            ..options
+        };
+        assert_eq!(with_audio(video.clone(), &silent, 10.0), video);
+    }
+
+    #[test]
+    fn partial_estimates_start_wide_and_narrow_with_samples() {
+        let one = estimate_from_rates(&[1000.0], 1.5, 100.0, u64::MAX, Some(3)).unwrap();
+        assert_eq!(one.best_size, 100_000);
+        assert_eq!(one.confidence, EstimateConfidence::Low);
+        assert_eq!((one.low_size, one.high_size), (70_000, 130_000));
+
+        let two = estimate_from_rates(&[1000.0, 1000.0], 3.0, 100.0, u64::MAX, Some(3)).unwrap();
+        assert_eq!(two.confidence, EstimateConfidence::Medium);
+        assert!(two.high_size - two.low_size < one.high_size - one.low_size);
+
+        let rates = [1000.0, 1000.0, 1000.0];
+        let done = estimate_from_rates(&rates, 4.5, 100.0, u64::MAX, Some(3)).unwrap();
+        assert_eq!(
+            done,
+            estimate_from_rates(&rates, 4.5, 100.0, u64::MAX, None).unwrap()
+        );
+        assert_eq!(done.confidence, EstimateConfidence::High);
+    }
+
     #[test]
     fn clamp_preview_start_when_past_end() {
         let clamped = clamp_preview_start_seconds(8.0, 10.0, 3.0);
@@ -1068,4 +1932,79 @@ mod tests {
         assert_eq!(medium, EstimateConfidence::Medium);
         assert_eq!(low, EstimateConfidence::Low);
     }
+
+    #[test]
+    fn estimate_samples_run_in_parallel_only_for_software_encoders() {
+        assert_eq!(estimate_sample_concurrency("libx264", 8, false), 4);
+        assert_eq!(estimate_sample_concurrency("libx265", 32, false), 5);
+        assert_eq!(estimate_sample_concurrency("libsvtav1", 1, false), 1);
+        assert_eq!(estimate_sample_concurrency("libx264", 8, true), 1);
+        assert_eq!(estimate_sample_concurrency("h264_nvenc", 16, false), 1);
+        assert_eq!(
+            estimate_sample_concurrency("hevc_videotoolbox", 10, false),
+            1
+        );
+    }
+
+    #[test]
+    fn preview_cap_applies_to_the_shorter_side_of_the_output() {
+        let uhd = VideoMetadata {
+            width: 3840,
+            height: 2160,
+            ..VideoMetadata::default()
+        };
+        let portrait = VideoMetadata {
+            width: 2160,
+            height: 3840,
+            ..VideoMetadata::default()
+        };
+        let mut options = TranscodeOptions {
+            preview_resolution_cap: Some(1080),
+            ..TranscodeOptions::default()
+        };
+        assert_eq!(preview_downscale(&options, &uhd), Some(0.5));
+        assert_eq!(preview_downscale(&options, &portrait), Some(0.5));
+
+        options.scale = Some(0.5);
+        assert_eq!(preview_downscale(&options, &uhd), None);
+        options.scale = Some(1.0);
+        options.crop_width = Some(1920);
+        options.crop_height = Some(1080);
+        assert_eq!(preview_downscale(&options, &uhd), None);
+        options.preview_resolution_cap = None;
+        assert_eq!(preview_downscale(&options, &uhd), None);
+    }
+
+    #[test]
+    fn downscaled_samples_are_corrected_in_quality_mode_only() {
+        let mut options = TranscodeOptions::default();
+        let correction = downscale_rate_correction(&options, 0.5);
+        assert!((correction - 4f64.powf(0.75)).abs() < 1e-9);
+        assert_eq!(downscale_rate_correction(&options, 1.0), 1.0);
+
+        options.rate_control_mode = Some(RateControlMode::TargetSize);
+        assert_eq!(downscale_rate_correction(&options, 0.5), 1.0);
+    }
+
+    #[test]
+    fn preview_codec_prefers_request_then_fastest_working_h264() {
+        let working: Vec<String> = ["libx264", "libx265", "h264_qsv", "h264_nvenc"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(pick_preview_codec(&working, None), Some("h264_nvenc"));
+        assert_eq!(
+            pick_preview_codec(&working, Some("libx264")),
+            Some("libx264")
+        );
+        assert_eq!(
+            pick_preview_codec(&working, Some("h264_amf")),
+            Some("h264_nvenc")
+        );
+        assert_eq!(
+            pick_preview_codec(&working, Some("libx265")),
+            Some("h264_nvenc")
+        );
+        assert_eq!(pick_preview_codec(&["libx265".to_string()], None), None);
+    }
 }
@@ -0,0 +1,340 @@
+//! Compression queue: files are dropped in at any time and compressed one after another with the
+//! batch job runner, so every item gets `batch-job-started` / `batch-job-complete` and the usual
+//! `ffmpeg-progress` events.
+//!
+//! Items are resolved right before they run, so files added while the queue is running are
+//! picked up by the same run. `queue-updated` carries the whole list after every change.
+
+use std::collections::HashSet;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::Emitter;
+
+use crate::batch::{
+    BatchFile, BatchJobResult, BatchSettings, common_parent, resolve_batch_jobs_avoiding,
+    run_batch_job,
+};
+use crate::error::AppError;
+use crate::events::Versioned;
+use crate::ffmpeg::{FfmpegContext, TranscodeOptions};
+
+static QUEUE: Mutex<QueueState> = Mutex::new(QueueState::new());
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+    Skipped,
+    Cancelled,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub id: u64,
+    #[serde(flatten)]
+    pub file: BatchFile,
+    pub status: QueueStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<BatchJobResult>,
+    /// Set when the item could not be resolved (probe failed, bad overrides).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload of queue-updated: the whole queue, in order.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueUpdated {
+    pub items: Vec<QueueItem>,
+}
+
+/// Emits queue-updated with `items`.
+pub(crate) fn emit_updated(app: &tauri::AppHandle, items: Vec<QueueItem>) {
+    let _ = app.emit("queue-updated", Versioned::new(QueueUpdated { items }));
+}
+
+struct QueueState {
+    items: Vec<QueueItem>,
+    next_id: u64,
+    running: bool,
+}
+
+impl QueueState {
+    const fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            next_id: 1,
+            running: false,
+        }
+    }
+
+    fn add(&mut self, files: Vec<BatchFile>) {
+        for file in files {
+            self.items.push(QueueItem {
+                id: self.next_id,
+                file,
+                status: QueueStatus::Pending,
+                result: None,
+                error: None,
+            });
+            self.next_id += 1;
+        }
+    }
+
+    /// Removes a pending or finished item. The running item has to be cancelled first.
+    fn remove(&mut self, id: u64) -> Result<(), AppError> {
+        let pos = self
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| AppError::from(format!("No queue item {}", id)))?;
+        if self.items[pos].status == QueueStatus::Running {
+            return Err(AppError::from(
+                "Cannot remove the item that is being compressed",
+            ));
+        }
+        self.items.remove(pos);
+        Ok(())
+    }
+
+    /// Puts items in the order of `ids`, which must list every item exactly once.
+    fn reorder(&mut self, ids: &[u64]) -> Result<(), AppError> {
+        let mut sorted = ids.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let mut current: Vec<u64> = self.items.iter().map(|item| item.id).collect();
+        current.sort_unstable();
+        if sorted.len() != ids.len() || sorted != current {
+            return Err(AppError::from(
+                "Queue order must list every item exactly once",
+            ));
+        }
+        self.items
+            .sort_by_key(|item| ids.iter().position(|&id| id == item.id));
+        Ok(())
+    }
+
+    /// Marks the first pending item as running and returns it.
+    fn start_next(&mut self) -> Option<(u64, BatchFile)> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.status == QueueStatus::Pending)?;
+        item.status = QueueStatus::Running;
+        Some((item.id, item.file.clone()))
+    }
+
+    fn finish(
+        &mut self,
+        id: u64,
+        status: QueueStatus,
+        result: Option<BatchJobResult>,
+        error: Option<String>,
+    ) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.status = status;
+            item.result = result;
+            item.error = error;
+        }
+    }
+}
+
+/// Clears the running flag however the run ends (including cancellation).
+struct RunGuard;
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        QUEUE.lock().running = false;
+    }
+}
+
+pub fn list() -> Vec<QueueItem> {
+    QUEUE.lock().items.clone()
+}
+
+pub fn add(files: Vec<BatchFile>) -> Vec<QueueItem> {
+    let mut queue = QUEUE.lock();
+    queue.add(files);
+    queue.items.clone()
+}
+
+pub fn remove(id: u64) -> Result<Vec<QueueItem>, AppError> {
+    let mut queue = QUEUE.lock();
+    queue.remove(id)?;
+    Ok(queue.items.clone())
+}
+
+pub fn reorder(ids: &[u64]) -> Result<Vec<QueueItem>, AppError> {
+    let mut queue = QUEUE.lock();
+    queue.reorder(ids)?;
+    Ok(queue.items.clone())
+}
+
+fn status_of(result: &BatchJobResult, aborted: bool) -> QueueStatus {
+    if aborted {
+        QueueStatus::Cancelled
+    } else if result.job.skipped.is_some() {
+        QueueStatus::Skipped
+    } else if result.error.is_some() {
+        QueueStatus::Failed
+    } else {
+        QueueStatus::Done
+    }
+}
+
+/// Compresses pending items in order until none are left. Returns the results of this run.
+/// Cancelling stops the run; items after the cancelled one stay pending.
+pub(crate) async fn run_queue(
+    base: TranscodeOptions,
+    mut settings: BatchSettings,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<Vec<BatchJobResult>, AppError> {
+    {
+        let mut queue = QUEUE.lock();
+        if queue.running {
+            return Err(AppError::from("The queue is already running"));
+        }
+        queue.running = true;
+        // Items are resolved one at a time, so mirror against the folder shared by everything
+        // queued now rather than each file's own folder.
+        if let Some(layout) = settings.layout.as_mut()
+            && layout.source_root.is_none()
+        {
+            layout.source_root = common_parent(
+                queue
+                    .items
+                    .iter()
+                    .filter(|item| item.status == QueueStatus::Pending)
+                    .map(|item| item.file.input_path.as_path()),
+            );
+        }
+    }
+    let _guard = RunGuard;
+    let ffmpeg = FfmpegContext::discover()?;
+    let checksum = settings.checksum;
+    let mut results = Vec::new();
+    // Destinations planned so far this run. Each item is resolved on its own, so this is what
+    // keeps `a/clip.mov` and `b/clip.mkv` from both landing on `clip.mp4`.
+    let mut taken = HashSet::new();
+    loop {
+        let Some((id, file)) = QUEUE.lock().start_next() else {
+            break;
+        };
+        emit_updated(app, list());
+        let base = base.clone();
+        let item_settings = settings.clone();
+        let probe = ffmpeg.clone();
+        let mut planned = taken.clone();
+        let resolved = tauri::async_runtime::spawn_blocking(move || {
+            let jobs = resolve_batch_jobs_avoiding(
+                &probe,
+                &base,
+                std::slice::from_ref(&file),
+                &item_settings,
+                &mut planned,
+            );
+            (jobs, planned)
+        })
+        .await
+        .map_err(|e| AppError::from(e.to_string()))
+        .and_then(|(jobs, planned)| {
+            taken = planned;
+            jobs
+        })
+        .and_then(|jobs| {
+            jobs.into_iter()
+                .next()
+                .ok_or_else(|| AppError::from("Nothing to resolve"))
+        });
+        let mut job = match resolved {
+            Ok(job) => job,
+            Err(e) => {
+                log::warn!(
+                    target: "tiny_vid::queue",
+                    "run_queue: item {} could not be resolved: {}",
+                    id,
+                    e
+                );
+                QUEUE
+                    .lock()
+                    .finish(id, QueueStatus::Failed, None, Some(e.to_string()));
+                emit_updated(app, list());
+                continue;
+            }
+        };
+        job.index = results.len();
+        let (result, aborted) = run_batch_job(&ffmpeg, job, checksum, app, window_label).await;
+        QUEUE
+            .lock()
+            .finish(id, status_of(&result, aborted), Some(result.clone()), None);
+        emit_updated(app, list());
+        results.push(result);
+        if aborted {
+            return Err(AppError::aborted());
+        }
+    }
+    log::info!(
+        target: "tiny_vid::queue",
+        "run_queue: finished {} item(s)",
+        results.len()
+    );
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(path: &str) -> BatchFile {
+        BatchFile {
+            input_path: PathBuf::from(path),
+            overrides: Default::default(),
+            output_path: None,
+        }
+    }
+
+    fn ids(state: &QueueState) -> Vec<u64> {
+        state.items.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn add_remove_and_reorder() {
+        let mut state = QueueState::new();
+        state.add(vec![file("/a.mov"), file("/b.mov"), file("/c.mov")]);
+        assert_eq!(ids(&state), vec![1, 2, 3]);
+
+        state.reorder(&[3, 1, 2]).unwrap();
+        assert_eq!(ids(&state), vec![3, 1, 2]);
+        assert!(state.reorder(&[3, 1]).is_err());
+        assert!(state.reorder(&[3, 1, 1]).is_err());
+        assert!(state.reorder(&[3, 1, 2, 4]).is_err());
+
+        state.remove(1).unwrap();
+        assert_eq!(ids(&state), vec![3, 2]);
+        assert!(state.remove(1).is_err());
+
+        state.add(vec![file("/d.mov")]);
+        assert_eq!(ids(&state), vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn running_item_cannot_be_removed() {
+        let mut state = QueueState::new();
+        state.add(vec![file("/a.mov"), file("/b.mov")]);
+        let (id, next) = state.start_next().unwrap();
+        assert_eq!((id, next.input_path), (1, PathBuf::from("/a.mov")));
+        assert!(state.remove(1).is_err());
+
+        state.finish(1, QueueStatus::Done, None, None);
+        assert_eq!(state.start_next().map(|(id, _)| id), Some(2));
+        state.remove(1).unwrap();
+        assert_eq!(state.start_next().map(|(id, _)| id), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Quick mode: one call from a source clip to a saved file that fits a platform's upload limit.
+//!
+//! Nothing is previewed or estimated. The source is probed once and encoded in a single pass
+//! with the fastest working H.264 encoder (hardware when its probe passes, else libx264), with
+//! the video bitrate of a two-pass target size encode at the limit used as the peak instead.
+//! Hardware encoders have no two-pass mode, so the cap is what keeps the file under the limit;
+//! H.264 plays everywhere the result is likely to be posted. The output lands next to the
+//! source as `<stem>-<platform>.mp4`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::ffmpeg::ffprobe::VideoMetadata;
+use crate::ffmpeg::{
+    LargerOutputPolicy, RateControlMode, TranscodeOptions, compute_target_video_bitrate_kbps,
+};
+
+/// Share of the limit the encode aims at; hardware rate control overshoots a little.
+const SIZE_HEADROOM: f64 = 0.9;
+const PRESET: &str = "veryfast";
+const MAX_FPS: f64 = 30.0;
+/// Below this video bitrate, sources with a short side above `DOWNSCALE_TO` are scaled to it.
+const DOWNSCALE_BELOW_KBPS: u32 = 2500;
+const DOWNSCALE_TO: u32 = 720;
+
+/// Where the clip is going; decides the size limit.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlatformPreset {
+    Discord,
+    DiscordNitro,
+    Whatsapp,
+    Email,
+}
+
+impl PlatformPreset {
+    /// Upload limit in MB.
+    pub fn size_cap_mb(self) -> f64 {
+        match self {
+            PlatformPreset::Discord => 10.0,
+            PlatformPreset::DiscordNitro => 500.0,
+            PlatformPreset::Whatsapp => 16.0,
+            PlatformPreset::Email => 25.0,
+        }
+    }
+
+    fn file_tag(self) -> &'static str {
+        match self {
+            PlatformPreset::Discord => "discord",
+            PlatformPreset::DiscordNitro => "discord-nitro",
+            PlatformPreset::Whatsapp => "whatsapp",
+            PlatformPreset::Email => "email",
+        }
+    }
+}
+
+/// Options for a quick export of the probed source with `codec`.
+pub(crate) fn quick_options(
+    meta: &VideoMetadata,
+    platform: PlatformPreset,
+    codec: &str,
+) -> Result<TranscodeOptions, AppError> {
+    let source_fps = (meta.fps.is_finite() && meta.fps > 0.0).then_some(meta.fps);
+    let options = TranscodeOptions {
+        codec: Some(codec.to_string()),
+        preset: Some(PRESET.to_string()),
+        output_format: Some("mp4".to_string()),
+        fps: Some(source_fps.map_or(MAX_FPS, |fps| fps.min(MAX_FPS))),
+        source_fps,
+        duration_secs: Some(meta.duration),
+        audio_stream_count: Some(meta.audio_stream_count),
+        audio_codec_name: meta.audio_codec_name.clone(),
+        audio_channels: meta.audio_channels,
+        source_audio_bit_rate: meta.audio_bit_rate,
+        video_stream_count: Some(meta.video_stream_count),
+        video_stream_index: Some(meta.main_video_stream_index),
+        larger_output: Some(LargerOutputPolicy::Remux),
+        ..TranscodeOptions::default()
+    };
+    let budget_kbps = compute_target_video_bitrate_kbps(&TranscodeOptions {
+        codec: Some("libx264".to_string()),
+        rate_control_mode: Some(RateControlMode::TargetSize),
+        target_size_mb: Some(platform.size_cap_mb() * SIZE_HEADROOM),
+        ..options.clone()
+    })?;
+    let short_side = meta.width.min(meta.height);
+    let scale = if budget_kbps < DOWNSCALE_BELOW_KBPS && short_side > DOWNSCALE_TO {
+        DOWNSCALE_TO as f64 / short_side as f64
+    } else {
+        1.0
+    };
+    Ok(TranscodeOptions {
+        rate_control_mode: Some(RateControlMode::Quality),
+        max_bitrate: Some(budget_kbps),
+        scale: Some(scale),
+        ..options
+    })
+}
+
+/// `<stem>-<platform>.mp4` next to `input`, numbered around existing files.
+pub(crate) fn output_path(input: &Path, platform: PlatformPreset) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    let path = input.with_file_name(format!("{}-{}.mp4", stem, platform.file_tag()));
+    crate::batch::unique_path(path, &HashSet::new(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(duration: f64, width: u32, height: u32) -> VideoMetadata {
+        VideoMetadata {
+            duration,
+            audio_stream_count: 1,
+            width,
+            height,
+            fps: 60.0,
+            video_stream_count: 1,
+            ..VideoMetadata::default()
+        }
+    }
+
+    #[test]
+    fn caps_the_bitrate_to_fit_the_platform_limit() {
+        let options = quick_options(
+            &meta(60.0, 1920, 1080),
+            PlatformPreset::Discord,
+            "h264_nvenc",
+        )
+        .unwrap();
+        assert_eq!(options.effective_codec(), "h264_nvenc");
+        assert_eq!(
+            options.effective_rate_control_mode(),
+            RateControlMode::Quality
+        );
+        assert_eq!(options.effective_fps(), 30.0);
+        let budget = options.max_bitrate.unwrap();
+        let video_mb = budget as f64 * 1000.0 * 60.0 / 8.0 / 1024.0 / 1024.0;
+        assert!(video_mb < 10.0 * SIZE_HEADROOM, "{}", video_mb);
+        // A ~1 Mbps budget is too thin for 1080p.
+        assert_eq!(options.effective_scale(), 720.0 / 1080.0);
+
+        let roomy = quick_options(
+            &meta(60.0, 1920, 1080),
+            PlatformPreset::DiscordNitro,
+            "libx264",
+        )
+        .unwrap();
+        assert_eq!(roomy.effective_scale(), 1.0);
+        assert!(roomy.max_bitrate.unwrap() > budget);
+    }
+
+    #[test]
+    fn output_sits_next_to_the_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clip.mov");
+        assert_eq!(
+            output_path(&input, PlatformPreset::Discord),
+            dir.path().join("clip-discord.mp4")
+        );
+        std::fs::write(dir.path().join("clip-email.mp4"), b"").unwrap();
+        assert_eq!(
+            output_path(&input, PlatformPreset::Email),
+            dir.path().join("clip-email (1).mp4")
+        );
+    }
+}
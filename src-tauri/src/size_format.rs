@@ -0,0 +1,123 @@
+//! Human-readable sizes and size changes for results, in the current locale.
+//!
+//! Results carry raw bytes for clients that do their own math and a `SizeSummary` with the
+//! same numbers pre-formatted, so every client shows identical labels.
+
+use crate::i18n::{Locale, locale, translate};
+
+const KIB: f64 = 1024.0;
+const MIB: f64 = KIB * 1024.0;
+const GIB: f64 = MIB * 1024.0;
+/// Changes smaller than this (in percent) read as "Same size".
+const UNCHANGED_PERCENT: f64 = 0.05;
+
+/// Input and output size of one file, raw and formatted.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeSummary {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    /// e.g. `48.20 MB`.
+    pub input_size_label: String,
+    pub output_size_label: String,
+    /// Share of the input saved; negative when the output is larger. 0 for an empty input.
+    pub percent_saved: f64,
+    /// e.g. `62.50% smaller`, `Same size`.
+    pub change_label: String,
+}
+
+impl SizeSummary {
+    pub fn new(input_bytes: u64, output_bytes: u64) -> Self {
+        Self::in_locale(locale(), input_bytes, output_bytes)
+    }
+
+    fn in_locale(locale: Locale, input_bytes: u64, output_bytes: u64) -> Self {
+        let percent_saved = percent_saved(input_bytes, output_bytes);
+        Self {
+            input_bytes,
+            output_bytes,
+            input_size_label: format_size(locale, input_bytes),
+            output_size_label: format_size(locale, output_bytes),
+            percent_saved,
+            change_label: change_label(locale, percent_saved),
+        }
+    }
+}
+
+fn percent_saved(input_bytes: u64, output_bytes: u64) -> f64 {
+    if input_bytes == 0 {
+        return 0.0;
+    }
+    (1.0 - output_bytes as f64 / input_bytes as f64) * 100.0
+}
+
+/// `value` with `decimals` places and the locale's decimal separator.
+fn format_number(locale: Locale, value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    match locale {
+        Locale::En => text,
+        Locale::De | Locale::Es | Locale::Fr => text.replace('.', ","),
+    }
+}
+
+/// Binary units, like the size shown for the source: `512 B`, `340 KB`, `12.34 MB`, `1.50 GB`.
+fn format_size(locale: Locale, bytes: u64) -> String {
+    let b = bytes as f64;
+    if b >= GIB {
+        format!("{} GB", format_number(locale, b / GIB, 2))
+    } else if b >= MIB {
+        format!("{} MB", format_number(locale, b / MIB, 2))
+    } else if b >= KIB {
+        format!("{} KB", format_number(locale, b / KIB, 0))
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+fn change_label(locale: Locale, percent_saved: f64) -> String {
+    if percent_saved.abs() < UNCHANGED_PERCENT {
+        return translate(locale, "size.same").to_string();
+    }
+    let key = if percent_saved > 0.0 {
+        "size.smaller"
+    } else {
+        "size.larger"
+    };
+    let percent = format!("{}%", format_number(locale, percent_saved.abs(), 2));
+    translate(locale, key).replace("{percent}", &percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_use_binary_units_and_the_locale_separator() {
+        assert_eq!(format_size(Locale::En, 512), "512 B");
+        assert_eq!(format_size(Locale::En, 340 * 1024), "340 KB");
+        assert_eq!(format_size(Locale::En, 12_939_428), "12.34 MB");
+        assert_eq!(format_size(Locale::De, 12_939_428), "12,34 MB");
+        assert_eq!(
+            format_size(Locale::Fr, 3 * 1024 * 1024 * 1024 / 2),
+            "1,50 GB"
+        );
+    }
+
+    #[test]
+    fn summary_reports_savings_growth_and_no_change() {
+        let smaller = SizeSummary::in_locale(Locale::En, 8_000_000, 3_000_000);
+        assert_eq!(smaller.percent_saved, 62.5);
+        assert_eq!(smaller.change_label, "62.50% smaller");
+        assert_eq!(smaller.input_bytes, 8_000_000);
+
+        let larger = SizeSummary::in_locale(Locale::En, 1_000_000, 1_100_000);
+        assert!(larger.percent_saved < 0.0);
+        assert_eq!(larger.change_label, "10.00% larger");
+
+        assert_eq!(
+            SizeSummary::in_locale(Locale::De, 1_000_000, 1_000_000).change_label,
+            "Gleiche Größe"
+        );
+        assert_eq!(SizeSummary::in_locale(Locale::En, 0, 10).percent_saved, 0.0);
+    }
+}
@@ -0,0 +1,112 @@
+//! Read-only source guarantee: sources are only ever opened by FFmpeg as `-i` inputs.
+//!
+//! Every path that writes a file (encodes, cover art remux, moving the result into place) checks
+//! that its target is not the source, and batch jobs compare a fingerprint of the source taken
+//! before and after the job so the report can assert the source was left untouched. Jobs also
+//! record their inputs in the temp registry (`ffmpeg::record_job_sources`), so commits, discards
+//! and cleanups refuse them even when the caller does not name the source.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::AppError;
+
+/// Absolute, symlink-resolved form of `path`. Falls back to resolving the parent for targets
+/// that do not exist yet.
+fn resolved(path: &Path) -> PathBuf {
+    if let Ok(p) = fs::canonicalize(path) {
+        return p;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => fs::canonicalize(dir)
+            .map(|d| d.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// True when `a` and `b` name the same file (through `..`, symlinks, etc.).
+pub(crate) fn is_same_file(a: &Path, b: &Path) -> bool {
+    resolved(a) == resolved(b)
+}
+
+/// Errors when `target` would overwrite `source`.
+pub(crate) fn ensure_not_source(source: &Path, target: &Path) -> Result<(), AppError> {
+    if is_same_file(source, target) {
+        log::error!(
+            target: "tiny_vid::source_guard",
+            "ensure_not_source: refusing to write over source {}",
+            source.display()
+        );
+        return Err(AppError::from(format!(
+            "Refusing to overwrite the source file {}",
+            source.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Size and modification time of a source, taken before a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceFingerprint {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl SourceFingerprint {
+    pub(crate) fn capture(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+
+    /// True when `path` still has the size and modification time it had at capture.
+    pub(crate) fn is_unchanged(&self, path: &Path) -> bool {
+        Self::capture(path).as_ref() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_file_sees_through_relative_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("clip.mov");
+        fs::write(&source, b"x").unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        assert!(is_same_file(&source, &sub.join("../clip.mov")));
+        assert!(!is_same_file(&source, &dir.path().join("clip.mp4")));
+        assert!(ensure_not_source(&source, &sub.join("..").join("clip.mov")).is_err());
+        assert!(ensure_not_source(&source, &dir.path().join("out.mp4")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_file_resolves_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("clip.mov");
+        fs::write(&source, b"x").unwrap();
+        let link = dir.path().join("link.mov");
+        std::os::unix::fs::symlink(&source, &link).unwrap();
+        assert!(is_same_file(&source, &link));
+    }
+
+    #[test]
+    fn fingerprint_detects_changed_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("clip.mov");
+        fs::write(&source, b"original").unwrap();
+        let fingerprint = SourceFingerprint::capture(&source).unwrap();
+        assert!(fingerprint.is_unchanged(&source));
+        fs::write(&source, b"rewritten!").unwrap();
+        assert!(!fingerprint.is_unchanged(&source));
+        fs::remove_file(&source).unwrap();
+        assert!(!fingerprint.is_unchanged(&source));
+    }
+}
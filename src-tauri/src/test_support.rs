@@ -7,10 +7,16 @@ use tauri::test::{INVOKE_KEY, mock_builder, mock_context, noop_assets};
 use tauri::webview::InvokeRequest;
 
 use crate::CodecInfo;
+use crate::codec::{GIF_CODEC, filter_codecs_for_display, get_codec_info};
 use crate::commands;
 use crate::error::AppError;
+use crate::ffmpeg::discovery::get_available_codecs;
 use crate::ffmpeg::ffprobe::get_video_metadata_impl;
-use crate::ffmpeg::{SizeEstimate, TranscodeOptions};
+use crate::ffmpeg::quality::measure_psnr;
+use crate::ffmpeg::{
+    FfmpegContext, SizeEstimate, TranscodeOptions, build_ffmpeg_command, path_to_string,
+    run_ffmpeg_blocking,
+};
 use crate::preview::{run_preview_core, run_preview_with_estimate_core};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -55,13 +61,13 @@ pub async fn run_preview_for_test(
     preview_start_seconds: Option<f64>,
 ) -> Result<PreviewResultForTest, AppError> {
     let result = run_preview_core(
+        &FfmpegContext::discover()?,
         input_path,
         options,
         preview_start_seconds,
         None,
         None,
         None,
-        None,
     )
     .await?;
     Ok(PreviewResultForTest {
@@ -81,12 +87,12 @@ pub async fn run_preview_for_test_with_meta_codec_override(
     let mut meta = get_video_metadata_impl(input_path)?;
     meta.codec_name = Some(source_codec_override.to_string());
     let result = run_preview_core(
+        &FfmpegContext::discover()?,
         input_path,
         options,
         preview_start_seconds,
         None,
         None,
-        Some(meta.duration),
         Some(meta),
     )
     .await?;
@@ -103,8 +109,16 @@ pub async fn run_preview_with_estimate_for_test(
     options: &TranscodeOptions,
     preview_start_seconds: Option<f64>,
 ) -> Result<PreviewWithEstimateResultForTest, AppError> {
-    let result =
-        run_preview_with_estimate_core(input_path, options, preview_start_seconds, None).await?;
+    let ffmpeg = FfmpegContext::discover()?;
+    let result = run_preview_with_estimate_core(
+        &ffmpeg,
+        input_path,
+        options,
+        preview_start_seconds,
+        None,
+        None,
+    )
+    .await?;
     Ok(PreviewWithEstimateResultForTest {
         preview: PreviewResultForTest {
             original_path: result.preview.original_path,
@@ -115,6 +129,157 @@ pub async fn run_preview_with_estimate_for_test(
     })
 }
 
+/// Seconds of the golden fixture.
+pub const GOLDEN_FIXTURE_SECONDS: f64 = 2.0;
+/// Lowest mean PSNR (dB) a golden encode may have against the fixture.
+pub const GOLDEN_MIN_PSNR_DB: f64 = 30.0;
+/// Largest a golden encode may be. The fixture is 2 s of 320x240 with a mono tone, so any
+/// codec at default quality lands well below this.
+pub const GOLDEN_MAX_BYTES: u64 = 1_000_000;
+
+/// Outcome of encoding the golden fixture with one codec.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoldenResultForTest {
+    pub codec: String,
+    pub format: String,
+    pub output_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub psnr_db: Option<f64>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+fn build_golden_fixture_args(output: &str) -> Vec<String> {
+    vec![
+        "-nostdin".to_string(),
+        "-y".to_string(),
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("testsrc2=duration={GOLDEN_FIXTURE_SECONDS}:size=320x240:rate=30"),
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("sine=frequency=440:duration={GOLDEN_FIXTURE_SECONDS}"),
+        "-c:v".to_string(),
+        "ffv1".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        "-c:a".to_string(),
+        "pcm_s16le".to_string(),
+        output.to_string(),
+    ]
+}
+
+/// Writes the lossless golden fixture to `dir`. Every build can write it: lavfi sources, FFV1
+/// and PCM are built into ffmpeg itself.
+pub fn write_golden_fixture(ctx: &FfmpegContext, dir: &Path) -> Result<PathBuf, AppError> {
+    let path = dir.join("golden-source.mkv");
+    let args = build_golden_fixture_args(&path_to_string(&path));
+    run_ffmpeg_blocking(ctx, args, None, None, None, None, None)?;
+    Ok(path)
+}
+
+/// Fixed options for the golden encode with `codec`: app defaults, reproducible output.
+pub fn golden_options(codec: &str) -> TranscodeOptions {
+    let format = get_codec_info(codec)
+        .formats
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "mkv".to_string());
+    TranscodeOptions {
+        codec: Some(codec.to_string()),
+        output_format: Some(format),
+        reproducible: Some(true),
+        ..TranscodeOptions::default()
+    }
+}
+
+/// Encodes `fixture` with `codec` into `dir` and checks size and PSNR against the golden bounds.
+pub fn run_golden_case(
+    ctx: &FfmpegContext,
+    fixture: &Path,
+    dir: &Path,
+    codec: &str,
+) -> GoldenResultForTest {
+    let options = golden_options(codec);
+    let format = options.output_format.clone().unwrap_or_default();
+    let output = dir.join(format!("golden-{codec}.{format}"));
+    let mut result = GoldenResultForTest {
+        codec: codec.to_string(),
+        format,
+        output_bytes: 0,
+        psnr_db: None,
+        passed: false,
+        failure: None,
+    };
+    let encoded = build_ffmpeg_command(
+        &path_to_string(fixture),
+        &path_to_string(&output),
+        &options,
+        None,
+        None,
+        None,
+    )
+    .and_then(|args| {
+        run_ffmpeg_blocking(
+            ctx,
+            args,
+            None,
+            None,
+            Some(GOLDEN_FIXTURE_SECONDS),
+            None,
+            None,
+        )
+    })
+    .and_then(|()| Ok(std::fs::metadata(&output)?.len()))
+    .and_then(|bytes| {
+        result.output_bytes = bytes;
+        measure_psnr(ctx, fixture, &output)
+    });
+    result.failure = match encoded {
+        Ok(psnr) => {
+            result.psnr_db = Some(psnr);
+            if result.output_bytes == 0 || result.output_bytes > GOLDEN_MAX_BYTES {
+                Some(format!(
+                    "Output is {} bytes, expected 1..={}",
+                    result.output_bytes, GOLDEN_MAX_BYTES
+                ))
+            } else if psnr < GOLDEN_MIN_PSNR_DB {
+                Some(format!(
+                    "PSNR {:.2} dB is below {:.2} dB",
+                    psnr, GOLDEN_MIN_PSNR_DB
+                ))
+            } else {
+                None
+            }
+        }
+        Err(e) => Some(e.to_string()),
+    };
+    result.passed = result.failure.is_none();
+    let _ = std::fs::remove_file(&output);
+    result
+}
+
+/// Encodes the golden fixture with every advertised codec, working in `dir`. Blocking.
+pub fn run_golden_suite(dir: &Path) -> Result<Vec<GoldenResultForTest>, AppError> {
+    let ctx = FfmpegContext::discover()?;
+    // GIF's palette puts it outside the PSNR bound, and it has no codec settings to pin.
+    let codecs: Vec<String> = filter_codecs_for_display(&get_available_codecs()?)
+        .into_iter()
+        .filter(|codec| codec != GIF_CODEC)
+        .collect();
+    let fixture = write_golden_fixture(&ctx, dir)?;
+    let results = codecs
+        .iter()
+        .map(|codec| run_golden_case(&ctx, &fixture, dir, codec))
+        .collect();
+    let _ = std::fs::remove_file(&fixture);
+    Ok(results)
+}
+
 /// Invokes get_video_metadata through the Tauri command layer.
 pub fn get_video_metadata_via_command_for_test(
     path: PathBuf,
@@ -154,8 +319,6 @@ fn create_test_app_for_commands() -> tauri::App<tauri::test::MockRuntime> {
             commands::get_video_metadata,
             commands::get_build_variant,
             commands::ffmpeg_terminate,
-            commands::move_compressed_file,
-            commands::cleanup_temp_file,
             commands::get_pending_opened_files,
         ])
         .build(mock_context(noop_assets()))
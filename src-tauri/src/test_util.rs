@@ -16,8 +16,9 @@ pub fn create_test_app() -> tauri::App<tauri::test::MockRuntime> {
             commands::get_video_metadata,
             commands::get_build_variant,
             commands::ffmpeg_terminate,
-            commands::move_compressed_file,
-            commands::cleanup_temp_file,
+            commands::commit_temp_output,
+            commands::discard_temp_output,
+            commands::commit_batch_outputs,
         ])
         .build(mock_context(noop_assets()))
         .expect("failed to build test app")
@@ -39,8 +40,8 @@ pub fn create_test_app_with_file_assoc(
             commands::get_video_metadata,
             commands::get_build_variant,
             commands::ffmpeg_terminate,
-            commands::move_compressed_file,
-            commands::cleanup_temp_file,
+            commands::commit_temp_output,
+            commands::discard_temp_output,
             commands::get_pending_opened_files,
         ])
         .build(mock_context(noop_assets()))
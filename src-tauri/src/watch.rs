@@ -0,0 +1,416 @@
+//! Watch folders: videos that appear in a folder are compressed with a saved preset into a
+//! destination folder, without anyone opening them.
+//!
+//! Each watcher keeps a `notify` watcher on its folder and a task fed by it. Only files that
+//! show up after the watcher starts are picked up, and only once their size has stopped changing
+//! for `SETTLE_SECS` (copies and recorders write for a while). Files then run one at a time
+//! through the batch job runner with the preset's options, so the window that added the watcher
+//! gets the usual `batch-job-started` / `batch-job-complete` and progress events, plus
+//! `watch-file-detected` and `watch-job-complete` tagged with the watcher id. Outputs mirror the
+//! watched folder under the destination; anything written under the destination is ignored, so
+//! a destination inside the folder never feeds itself.
+//!
+//! Watchers live as long as the app; removing one lets the file being compressed finish.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::mpsc;
+
+use crate::batch::{
+    BatchFile, BatchJobResult, BatchOutputLayout, BatchSettings, resolve_batch_jobs, run_batch_job,
+};
+use crate::error::AppError;
+use crate::events::Versioned;
+use crate::ffmpeg::{FfmpegContext, TranscodeOptions, path_to_string};
+
+/// Extensions picked up; the same list the open dialog offers.
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mpeg", "webm", "mov", "3gp", "avi", "flv", "mkv", "ogg",
+];
+/// How long a file's size must hold still before it is compressed.
+const SETTLE_SECS: u64 = 3;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static WATCHERS: Mutex<Option<HashMap<u64, ActiveWatch>>> = Mutex::new(None);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderConfig {
+    pub folder: PathBuf,
+    pub destination: PathBuf,
+    /// Name of the saved preset to compress with. Its options are read when the watcher starts.
+    pub preset: String,
+    /// Also watch subfolders. Default false.
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolder {
+    pub id: u64,
+    #[serde(flatten)]
+    pub config: WatchFolderConfig,
+}
+
+/// Payload of watch-file-detected.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFileDetected {
+    pub watcher_id: u64,
+    pub input_path: String,
+}
+
+/// Payload of watch-job-complete. `error` is set when the file could not even be resolved
+/// (probe failed); encode failures are in `result`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchJobComplete {
+    pub watcher_id: u64,
+    pub input_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<BatchJobResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct ActiveWatch {
+    info: WatchFolder,
+    /// Dropping the watcher closes the task's channel, which ends the task.
+    _watcher: RecommendedWatcher,
+}
+
+/// Whether the watcher of `config` should compress `path`.
+fn wants(config: &WatchFolderConfig, path: &Path) -> bool {
+    let visible = path
+        .file_name()
+        .is_some_and(|name| !name.to_string_lossy().starts_with('.'));
+    let video = path
+        .extension()
+        .is_some_and(|ext| VIDEO_EXTENSIONS.iter().any(|v| ext.eq_ignore_ascii_case(v)));
+    visible && video && path.starts_with(&config.folder) && !path.starts_with(&config.destination)
+}
+
+/// Files seen by the watcher, waiting for their size to settle.
+#[derive(Default)]
+struct Settling {
+    /// Size at the last poll and when it last changed; None until the first poll.
+    pending: HashMap<PathBuf, Option<(u64, Instant)>>,
+    /// Files already handed out, so later events for them are ignored.
+    taken: HashSet<PathBuf>,
+}
+
+impl Settling {
+    fn note(&mut self, path: PathBuf) {
+        if !self.taken.contains(&path) {
+            self.pending.entry(path).or_insert(None);
+        }
+    }
+
+    /// Files whose non-zero size has not changed for `settle`. Files that vanished are dropped.
+    fn take_settled(
+        &mut self,
+        now: Instant,
+        settle: Duration,
+        size_of: impl Fn(&Path) -> Option<u64>,
+    ) -> Vec<PathBuf> {
+        let mut settled = Vec::new();
+        self.pending.retain(|path, state| {
+            let Some(size) = size_of(path) else {
+                return false;
+            };
+            match state {
+                Some((last, since)) if *last == size => {
+                    if size > 0 && now.duration_since(*since) >= settle {
+                        settled.push(path.clone());
+                        return false;
+                    }
+                }
+                _ => *state = Some((size, now)),
+            }
+            true
+        });
+        settled.sort();
+        self.taken.extend(settled.iter().cloned());
+        settled
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+}
+
+pub fn list() -> Vec<WatchFolder> {
+    let mut folders: Vec<WatchFolder> = WATCHERS
+        .lock()
+        .as_ref()
+        .map(|watchers| watchers.values().map(|w| w.info.clone()).collect())
+        .unwrap_or_default();
+    folders.sort_by_key(|f| f.id);
+    folders
+}
+
+/// Starts watching `config.folder`, compressing new videos with `options`. Returns all watchers.
+pub(crate) fn add(
+    config: WatchFolderConfig,
+    options: TranscodeOptions,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) -> Result<Vec<WatchFolder>, AppError> {
+    if !config.folder.is_dir() {
+        return Err(AppError::from(format!(
+            "Watch folder {} is not a folder",
+            config.folder.display()
+        )));
+    }
+    if config.destination == config.folder {
+        return Err(AppError::from(
+            "The destination must differ from the watched folder",
+        ));
+    }
+    if list().iter().any(|w| w.config.folder == config.folder) {
+        return Err(AppError::from(format!(
+            "{} is already watched",
+            config.folder.display()
+        )));
+    }
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!(target: "tiny_vid::watch", "watcher error: {}", e),
+        })
+        .map_err(|e| AppError::from(format!("Could not start the folder watcher: {}", e)))?;
+    let mode = if config.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(&config.folder, mode).map_err(|e| {
+        AppError::from(format!(
+            "Could not watch {}: {}",
+            config.folder.display(),
+            e
+        ))
+    })?;
+
+    let info = WatchFolder {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        config,
+    };
+    log::info!(
+        target: "tiny_vid::watch",
+        "add: watcher {} on {} -> {} with preset {:?}",
+        info.id,
+        info.config.folder.display(),
+        info.config.destination.display(),
+        info.config.preset
+    );
+    tauri::async_runtime::spawn(run_watch(
+        info.clone(),
+        options,
+        rx,
+        app.clone(),
+        window_label.to_string(),
+    ));
+    WATCHERS.lock().get_or_insert_with(HashMap::new).insert(
+        info.id,
+        ActiveWatch {
+            info,
+            _watcher: watcher,
+        },
+    );
+    Ok(list())
+}
+
+/// Stops watcher `id`. Returns the remaining watchers.
+pub fn remove(id: u64) -> Result<Vec<WatchFolder>, AppError> {
+    WATCHERS
+        .lock()
+        .as_mut()
+        .and_then(|watchers| watchers.remove(&id))
+        .ok_or_else(|| AppError::from(format!("No watch folder {}", id)))?;
+    log::info!(target: "tiny_vid::watch", "remove: watcher {}", id);
+    Ok(list())
+}
+
+async fn run_watch(
+    info: WatchFolder,
+    options: TranscodeOptions,
+    mut events: mpsc::UnboundedReceiver<PathBuf>,
+    app: tauri::AppHandle,
+    window_label: String,
+) {
+    let mut settling = Settling::default();
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    let settle = Duration::from_secs(SETTLE_SECS);
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(path) if wants(&info.config, &path) => settling.note(path),
+                Some(_) => {}
+                None => break,
+            },
+            _ = poll.tick() => {
+                for path in settling.take_settled(Instant::now(), settle, file_size) {
+                    compress(&info, &options, path, &app, &window_label).await;
+                }
+            }
+        }
+    }
+    log::info!(target: "tiny_vid::watch", "run_watch: watcher {} stopped", info.id);
+}
+
+/// Compresses one settled file and emits `watch-job-complete`.
+async fn compress(
+    info: &WatchFolder,
+    options: &TranscodeOptions,
+    path: PathBuf,
+    app: &tauri::AppHandle,
+    window_label: &str,
+) {
+    let input_path = path_to_string(&path);
+    log::info!(
+        target: "tiny_vid::watch",
+        "compress: watcher {} picked up {}",
+        info.id,
+        input_path
+    );
+    let _ = app.emit_to(
+        window_label,
+        "watch-file-detected",
+        Versioned::new(WatchFileDetected {
+            watcher_id: info.id,
+            input_path: input_path.clone(),
+        }),
+    );
+    let file = BatchFile {
+        input_path: path,
+        overrides: Default::default(),
+        output_path: None,
+    };
+    let settings = BatchSettings {
+        layout: Some(BatchOutputLayout {
+            output_root: info.config.destination.clone(),
+            source_root: Some(info.config.folder.clone()),
+            overwrite: false,
+        }),
+        ..BatchSettings::default()
+    };
+    let base = options.clone();
+    let outcome = match FfmpegContext::discover() {
+        Ok(ffmpeg) => {
+            let probe = ffmpeg.clone();
+            let resolved = tauri::async_runtime::spawn_blocking(move || {
+                resolve_batch_jobs(&probe, &base, std::slice::from_ref(&file), &settings)
+            })
+            .await
+            .map_err(|e| AppError::from(e.to_string()))
+            .and_then(|r| r)
+            .and_then(|jobs| {
+                jobs.into_iter()
+                    .next()
+                    .ok_or_else(|| AppError::from("Nothing to resolve"))
+            });
+            match resolved {
+                Ok(job) => Ok(run_batch_job(&ffmpeg, job, None, app, window_label).await.0),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(e) => {
+            log::warn!(
+                target: "tiny_vid::watch",
+                "compress: watcher {} could not compress {}: {}",
+                info.id,
+                input_path,
+                e
+            );
+            (None, Some(e.to_string()))
+        }
+    };
+    let _ = app.emit_to(
+        window_label,
+        "watch-job-complete",
+        Versioned::new(WatchJobComplete {
+            watcher_id: info.id,
+            input_path,
+            result,
+            error,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(recursive: bool) -> WatchFolderConfig {
+        WatchFolderConfig {
+            folder: PathBuf::from("/in"),
+            destination: PathBuf::from("/in/out"),
+            preset: "Discord clip".to_string(),
+            recursive,
+        }
+    }
+
+    #[test]
+    fn picks_visible_videos_outside_the_destination() {
+        let config = config(true);
+        assert!(wants(&config, Path::new("/in/clip.MOV")));
+        assert!(wants(&config, Path::new("/in/sub/clip.mkv")));
+        assert!(!wants(&config, Path::new("/in/notes.txt")));
+        assert!(!wants(&config, Path::new("/in/.clip.mp4")));
+        assert!(!wants(&config, Path::new("/in/out/clip.mp4")));
+        assert!(!wants(&config, Path::new("/elsewhere/clip.mp4")));
+    }
+
+    #[test]
+    fn files_are_taken_once_their_size_settles() {
+        let settle = Duration::from_secs(SETTLE_SECS);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let sizes: Mutex<HashMap<PathBuf, u64>> = Mutex::new(HashMap::new());
+        let size_of = |p: &Path| sizes.lock().get(p).copied();
+        let clip = PathBuf::from("/in/clip.mp4");
+        let mut settling = Settling::default();
+
+        settling.note(clip.clone());
+        sizes.lock().insert(clip.clone(), 100);
+        assert!(settling.take_settled(at(0), settle, size_of).is_empty());
+        sizes.lock().insert(clip.clone(), 200);
+        assert!(settling.take_settled(at(1), settle, size_of).is_empty());
+        assert!(settling.take_settled(at(3), settle, size_of).is_empty());
+        assert_eq!(
+            settling.take_settled(at(4), settle, size_of),
+            vec![clip.clone()]
+        );
+
+        settling.note(clip.clone());
+        assert!(settling.pending.is_empty());
+
+        let gone = PathBuf::from("/in/gone.mp4");
+        settling.note(gone.clone());
+        assert!(settling.take_settled(at(10), settle, size_of).is_empty());
+        assert!(settling.pending.is_empty());
+    }
+}
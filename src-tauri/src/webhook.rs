@@ -0,0 +1,421 @@
+//! Optional job event webhooks.
+//!
+//! When configured, transcode, batch (including queue and watch) and compose jobs POST
+//! `job-started`, `progress` (at milestone percentages), `complete` and `error` JSON to a user
+//! URL. Jobs can overlap, so each tracks its own milestones under its job id, which every event
+//! carries as `jobId`. With a secret, the body is signed with HMAC-SHA256
+//! in `X-Tiny-Vid-Signature: sha256=<hex>`. Delivery goes through `curl` (like the FFmpeg
+//! updater), runs off the job's thread and never fails the job.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+
+/// Progress percentages reported when no milestones are configured.
+const DEFAULT_MILESTONES: [u8; 3] = [25, 50, 75];
+const SIGNATURE_HEADER: &str = "X-Tiny-Vid-Signature";
+const DELIVERY_TIMEOUT_SECS: &str = "10";
+
+static CONFIG: Mutex<Option<WebhookConfig>> = Mutex::new(None);
+/// Running jobs by job id (`TempJob::id`).
+static JOBS: Mutex<Option<HashMap<u64, JobState>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub url: String,
+    /// HMAC-SHA256 key for the signature header; unsigned when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Progress percentages (1-99) that trigger a `progress` event; defaults to 25/50/75.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub milestones: Option<Vec<u8>>,
+}
+
+impl WebhookConfig {
+    fn milestones(&self) -> Vec<u8> {
+        let mut milestones: Vec<u8> = match &self.milestones {
+            Some(m) => m.iter().copied().filter(|p| (1..100).contains(p)).collect(),
+            None => DEFAULT_MILESTONES.to_vec(),
+        };
+        milestones.sort_unstable();
+        milestones.dedup();
+        milestones
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum WebhookEvent {
+    #[serde(rename_all = "camelCase")]
+    JobStarted { input: String },
+    #[serde(rename_all = "camelCase")]
+    Progress { input: String, percent: u8 },
+    #[serde(rename_all = "camelCase")]
+    Complete {
+        input: String,
+        output: String,
+        output_size: Option<u64>,
+        elapsed_secs: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error {
+        input: String,
+        error: String,
+        elapsed_secs: f64,
+    },
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope<'a> {
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+    job_id: u64,
+    /// See `events::EVENT_VERSION`.
+    event_version: u32,
+    /// Unix time in milliseconds.
+    timestamp: u128,
+}
+
+struct JobState {
+    input: String,
+    started: Instant,
+    pending_milestones: Vec<u8>,
+}
+
+/// Sets or clears (None) the webhook. Only http(s) URLs are accepted.
+pub fn set_config(config: Option<WebhookConfig>) -> Result<(), AppError> {
+    if let Some(config) = &config {
+        let url = url::Url::parse(&config.url)
+            .map_err(|e| AppError::from(format!("Invalid webhook URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::from("Webhook URL must use http or https"));
+        }
+    }
+    log::info!(
+        target: "tiny_vid::webhook",
+        "set_config: {}",
+        if config.is_some() { "enabled" } else { "disabled" }
+    );
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+pub fn config() -> Option<WebhookConfig> {
+    CONFIG.lock().clone()
+}
+
+pub(crate) fn job_started(job_id: u64, input: &str) {
+    let Some(config) = config() else {
+        return;
+    };
+    track_job(job_id, input, config.milestones());
+    send(
+        &config,
+        job_id,
+        WebhookEvent::JobStarted {
+            input: input.to_string(),
+        },
+    );
+}
+
+/// Reports the highest milestone `progress` (0-1) of `job_id` has crossed since its last call,
+/// if any. No-op for jobs without an id or not started with a webhook configured.
+pub(crate) fn job_progress(job_id: Option<u64>, progress: f64) {
+    let Some(job_id) = job_id else {
+        return;
+    };
+    let Some(event) = crossed_milestone(job_id, progress) else {
+        return;
+    };
+    if let Some(config) = config() {
+        send(&config, job_id, event);
+    }
+}
+
+pub(crate) fn job_finished(job_id: u64, result: Result<&str, &AppError>) {
+    let Some(job) = untrack_job(job_id) else {
+        return;
+    };
+    let Some(config) = config() else {
+        return;
+    };
+    let elapsed_secs = job.started.elapsed().as_secs_f64();
+    let event = match result {
+        Ok(output) => WebhookEvent::Complete {
+            input: job.input,
+            output_size: std::fs::metadata(output).ok().map(|m| m.len()),
+            output: output.to_string(),
+            elapsed_secs,
+        },
+        Err(e) => WebhookEvent::Error {
+            input: job.input,
+            error: e.to_string(),
+            elapsed_secs,
+        },
+    };
+    send(&config, job_id, event);
+}
+
+fn track_job(job_id: u64, input: &str, pending_milestones: Vec<u8>) {
+    JOBS.lock().get_or_insert_with(HashMap::new).insert(
+        job_id,
+        JobState {
+            input: input.to_string(),
+            started: Instant::now(),
+            pending_milestones,
+        },
+    );
+}
+
+fn untrack_job(job_id: u64) -> Option<JobState> {
+    JOBS.lock().as_mut()?.remove(&job_id)
+}
+
+/// Consumes the milestones of `job_id` up to `progress`, returning the event for the highest.
+fn crossed_milestone(job_id: u64, progress: f64) -> Option<WebhookEvent> {
+    let percent = (progress * 100.0).clamp(0.0, 100.0);
+    let mut jobs = JOBS.lock();
+    let job = jobs.as_mut()?.get_mut(&job_id)?;
+    let crossed = job
+        .pending_milestones
+        .iter()
+        .take_while(|&&m| f64::from(m) <= percent)
+        .count();
+    if crossed == 0 {
+        return None;
+    }
+    let reached = job.pending_milestones[crossed - 1];
+    job.pending_milestones.drain(..crossed);
+    Some(WebhookEvent::Progress {
+        input: job.input.clone(),
+        percent: reached,
+    })
+}
+
+fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn body_for(job_id: u64, event: &WebhookEvent) -> Vec<u8> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    serde_json::to_vec(&Envelope {
+        event,
+        job_id,
+        event_version: crate::events::EVENT_VERSION,
+        timestamp,
+    })
+    .unwrap_or_default()
+}
+
+/// curl args for the POST; the body is passed on stdin so it never appears in the process list.
+fn curl_args(config: &WebhookConfig, body: &[u8]) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-fsS",
+        "--max-time",
+        DELIVERY_TIMEOUT_SECS,
+        "-X",
+        "POST",
+        "-H",
+        "Content-Type: application/json",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    if let Some(secret) = config.secret.as_deref().filter(|s| !s.is_empty()) {
+        args.extend([
+            "-H".to_string(),
+            format!(
+                "{}: sha256={}",
+                SIGNATURE_HEADER,
+                hmac_sha256_hex(secret.as_bytes(), body)
+            ),
+        ]);
+    }
+    args.extend([
+        "--data-binary".to_string(),
+        "@-".to_string(),
+        config.url.clone(),
+    ]);
+    args
+}
+
+fn send(config: &WebhookConfig, job_id: u64, event: WebhookEvent) {
+    let body = body_for(job_id, &event);
+    let args = curl_args(config, &body);
+    std::thread::spawn(move || {
+        let mut cmd = Command::new("curl");
+        cmd.args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        let delivered = cmd.spawn().and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(&body)?;
+            }
+            child.wait_with_output()
+        });
+        match delivered {
+            Ok(output) if output.status.success() => log::debug!(
+                target: "tiny_vid::webhook",
+                "send: delivered {:?}",
+                event
+            ),
+            Ok(output) => log::warn!(
+                target: "tiny_vid::webhook",
+                "send: delivery failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => log::warn!(
+                target: "tiny_vid::webhook",
+                "send: could not run curl: {}",
+                e
+            ),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(secret: Option<&str>) -> WebhookConfig {
+        WebhookConfig {
+            url: "https://example.com/hook".to_string(),
+            secret: secret.map(String::from),
+            milestones: None,
+        }
+    }
+
+    #[test]
+    fn hmac_matches_rfc4231_vectors() {
+        assert_eq!(
+            hmac_sha256_hex(&[0x0b; 20], b"Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hmac_sha256_hex(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn events_serialize_with_kebab_case_tag() {
+        let body = body_for(
+            7,
+            &WebhookEvent::Progress {
+                input: "/in.mp4".to_string(),
+                percent: 50,
+            },
+        );
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["event"], "progress");
+        assert_eq!(json["jobId"], 7);
+        assert_eq!(json["percent"], 50);
+        assert_eq!(json["eventVersion"], crate::events::EVENT_VERSION);
+        assert!(json["timestamp"].as_u64().unwrap() > 0);
+
+        let body = body_for(
+            7,
+            &WebhookEvent::JobStarted {
+                input: "/in.mp4".to_string(),
+            },
+        );
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["event"], "job-started");
+    }
+
+    #[test]
+    fn curl_args_sign_only_with_a_secret() {
+        let body = br#"{"event":"job-started"}"#;
+        let signed = curl_args(&config(Some("key")), body);
+        let header = format!(
+            "{}: sha256={}",
+            SIGNATURE_HEADER,
+            hmac_sha256_hex(b"key", body)
+        );
+        assert!(signed.contains(&header));
+        assert_eq!(
+            signed.last().map(String::as_str),
+            Some("https://example.com/hook")
+        );
+
+        let unsigned = curl_args(&config(None), body);
+        assert!(!unsigned.iter().any(|a| a.starts_with(SIGNATURE_HEADER)));
+    }
+
+    #[test]
+    fn milestones_are_sorted_and_bounded() {
+        let mut c = config(None);
+        assert_eq!(c.milestones(), DEFAULT_MILESTONES.to_vec());
+        c.milestones = Some(vec![90, 0, 10, 100, 10]);
+        assert_eq!(c.milestones(), vec![10, 90]);
+    }
+
+    #[test]
+    fn overlapping_jobs_track_their_own_milestones() {
+        let (a, b) = (u64::MAX - 1, u64::MAX - 2);
+        let progress = |input: &str, percent| {
+            Some(WebhookEvent::Progress {
+                input: input.to_string(),
+                percent,
+            })
+        };
+        track_job(a, "/a.mp4", DEFAULT_MILESTONES.to_vec());
+        track_job(b, "/b.mp4", DEFAULT_MILESTONES.to_vec());
+
+        assert_eq!(crossed_milestone(a, 0.6), progress("/a.mp4", 50));
+        assert_eq!(crossed_milestone(b, 0.3), progress("/b.mp4", 25));
+        assert_eq!(crossed_milestone(a, 0.6), None);
+
+        assert!(untrack_job(a).is_some());
+        assert_eq!(crossed_milestone(a, 0.9), None);
+        assert_eq!(crossed_milestone(b, 0.8), progress("/b.mp4", 75));
+        assert!(untrack_job(b).is_some());
+    }
+
+    #[test]
+    fn set_config_rejects_non_http_urls() {
+        let mut c = config(None);
+        c.url = "file:///etc/passwd".to_string();
+        assert!(set_config(Some(c)).is_err());
+        assert!(set_config(Some(config(None))).is_ok());
+        assert!(set_config(None).is_ok());
+        assert_eq!(super::config(), None);
+    }
+}
@@ -1,13 +1,16 @@
 //! Integration tests for FFmpeg discovery mechanisms.
-//! Runs in its own process so FFMPEG_PATH and the discovery cache are clean.
+//! Paths are passed explicitly (FfmpegContext, resolve_ffmpeg_path) so no test mutates
+//! FFMPEG_PATH or other process-global env.
 
 use std::env;
 use std::fs;
-#[cfg(feature = "discovery-test-helpers")]
-use tiny_vid_tauri_lib::ffmpeg::discovery::__test_reset_ffmpeg_path_cache;
-use tiny_vid_tauri_lib::ffmpeg::discovery::{
-    get_ffmpeg_path, get_ffprobe_path, resolve_sidecar_path,
-};
+use tiny_vid_tauri_lib::ffmpeg::FfmpegContext;
+use tiny_vid_tauri_lib::ffmpeg::discovery::resolve_sidecar_path;
+#[cfg(all(
+    any(target_os = "macos", target_os = "windows"),
+    feature = "discovery-test-helpers"
+))]
+use tiny_vid_tauri_lib::ffmpeg::discovery::{ffprobe_path_for, resolve_ffmpeg_path};
 
 /// Derive bundled suffix from target at compile time.
 fn bundled_suffix() -> String {
@@ -27,9 +30,9 @@ fn bundled_suffix() -> String {
     }
 }
 
-/// FFMPEG_PATH override with suffixed binary; get_ffprobe_path derives matching ffprobe.
+/// Context with a suffixed ffmpeg derives the matching ffprobe.
 #[test]
-fn env_var_override_with_suffixed_binaries() {
+fn context_with_suffixed_binaries() {
     let dir = env::temp_dir().join("tiny_vid_discovery_test").join(
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -48,24 +51,17 @@ fn env_var_override_with_suffixed_binaries() {
     fs::File::create(&ffmpeg_path).expect("create mock ffmpeg");
     fs::File::create(&ffprobe_path).expect("create mock ffprobe");
 
-    let previous = env::var("FFMPEG_PATH").ok();
-    unsafe { env::set_var("FFMPEG_PATH", &ffmpeg_path) };
-    let _guard = RestoreEnv {
-        key: "FFMPEG_PATH".to_string(),
-        previous,
-    };
-
-    let got_ffmpeg = get_ffmpeg_path().expect("get_ffmpeg_path should succeed");
-    let got_ffprobe = get_ffprobe_path().expect("get_ffprobe_path should succeed");
+    let ctx = FfmpegContext::new(&ffmpeg_path);
+    let got_ffprobe = ctx.ffprobe_path().expect("ffprobe_path should succeed");
 
     assert_eq!(
-        got_ffmpeg,
+        ctx.ffmpeg_path(),
         ffmpeg_path.as_path(),
-        "get_ffmpeg_path should return the bundled ffmpeg path"
+        "context should keep the ffmpeg path it was given"
     );
     assert_eq!(
         got_ffprobe, ffprobe_path,
-        "get_ffprobe_path should return the bundled ffprobe path (same suffix as ffmpeg)"
+        "ffprobe_path should return the bundled ffprobe path (same suffix as ffmpeg)"
     );
 
     // Best-effort cleanup
@@ -82,7 +78,6 @@ fn env_var_override_with_suffixed_binaries() {
     feature = "discovery-test-helpers"
 ))]
 fn prefer_bundled_sidecar_when_present() {
-    __test_reset_ffmpeg_path_cache();
     let exe_path = env::current_exe().expect("get current exe path");
     let exe_dir = exe_path.parent().expect("exe parent dir");
 
@@ -104,15 +99,9 @@ fn prefer_bundled_sidecar_when_present() {
     fs::File::create(&mock_ffmpeg).expect("create mock ffmpeg sidecar");
     fs::File::create(&mock_ffprobe).expect("create mock ffprobe sidecar");
 
-    let previous = env::var("FFMPEG_PATH").ok();
-    unsafe { env::remove_var("FFMPEG_PATH") };
-    let _guard = RestoreEnv {
-        key: "FFMPEG_PATH".to_string(),
-        previous,
-    };
-
-    let got_ffmpeg = get_ffmpeg_path().expect("get_ffmpeg_path should succeed");
-    let got_ffprobe = get_ffprobe_path().expect("get_ffprobe_path should succeed");
+    // resolve_ffmpeg_path skips the FFMPEG_PATH override, so no env mutation is needed.
+    let got_ffmpeg = resolve_ffmpeg_path().expect("resolve_ffmpeg_path should succeed");
+    let got_ffprobe = ffprobe_path_for(&got_ffmpeg).expect("ffprobe_path_for should succeed");
 
     let _ = fs::remove_file(&mock_ffmpeg);
     let _ = fs::remove_file(&mock_ffprobe);
@@ -120,11 +109,11 @@ fn prefer_bundled_sidecar_when_present() {
     assert_eq!(
         got_ffmpeg,
         mock_ffmpeg.as_path(),
-        "get_ffmpeg_path should return the suffixed bundled sidecar when present"
+        "resolve_ffmpeg_path should return the suffixed bundled sidecar when present"
     );
     assert_eq!(
         got_ffprobe, mock_ffprobe,
-        "get_ffprobe_path should return the suffixed bundled ffprobe"
+        "ffprobe_path_for should return the suffixed bundled ffprobe"
     );
 }
 
@@ -180,18 +169,3 @@ fn sidecar_returns_none_on_linux() {
         "resolve_sidecar_path should return None on Linux (no bundle)"
     );
 }
-
-/// Restore an env var to its previous value when dropped.
-struct RestoreEnv {
-    key: String,
-    previous: Option<String>,
-}
-
-impl Drop for RestoreEnv {
-    fn drop(&mut self) {
-        match &self.previous {
-            Some(v) => unsafe { env::set_var(&self.key, v) },
-            None => unsafe { env::remove_var(&self.key) },
-        }
-    }
-}
@@ -0,0 +1,32 @@
+#![cfg(feature = "integration-test-api")]
+
+mod support;
+
+use support::{CodecContract, assert_codec_contract};
+use tiny_vid_tauri_lib::test_support::{
+    GOLDEN_MAX_BYTES, GOLDEN_MIN_PSNR_DB, golden_options, run_golden_suite,
+};
+
+#[test]
+fn golden_fixture_encodes_within_bounds_for_every_advertised_codec() {
+    assert_codec_contract(CodecContract::IntegrationSmoke);
+    let dir = tempfile::tempdir().expect("tempdir");
+    let results = run_golden_suite(dir.path()).expect("run_golden_suite");
+    assert!(!results.is_empty());
+    for result in &results {
+        assert!(
+            result.passed,
+            "{} ({}): {:?}",
+            result.codec, result.format, result.failure
+        );
+        assert!(result.output_bytes > 0 && result.output_bytes <= GOLDEN_MAX_BYTES);
+        assert!(result.psnr_db.unwrap() >= GOLDEN_MIN_PSNR_DB);
+    }
+}
+
+#[test]
+fn golden_options_are_reproducible() {
+    let options = golden_options("libx264");
+    assert_eq!(options.output_format.as_deref(), Some("mp4"));
+    assert_eq!(options.reproducible, Some(true));
+}
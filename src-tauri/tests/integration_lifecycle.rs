@@ -11,8 +11,8 @@ use support::{
     CodecContract, IntegrationEnv, VideoKind, assert_codec_contract, default_codec, opts_with,
 };
 use tiny_vid_tauri_lib::ffmpeg::{
-    TempFileManager, build_ffmpeg_command, cleanup_transcode_temp, run_ffmpeg_blocking,
-    set_transcode_temp, terminate_all_ffmpeg,
+    CancellationToken, FfmpegContext, TempJob, build_ffmpeg_command, discard_transcode_temp,
+    register_transcode_temp, run_ffmpeg_blocking, terminate_all_ffmpeg,
 };
 
 #[test]
@@ -40,7 +40,9 @@ fn transcode_reports_monotonic_progress() {
     .expect("build_ffmpeg_command");
 
     let progress_values: Arc<Mutex<Vec<f64>>> = Arc::new(Mutex::new(Vec::new()));
+    let ctx = FfmpegContext::discover().expect("FFmpeg not found");
     let result = run_ffmpeg_blocking(
+        &ctx,
         args,
         None,
         None,
@@ -87,13 +89,11 @@ fn transcode_cancel_cleans_up_temp_output() {
         o.preset = Some("slow".into());
     });
 
-    cleanup_transcode_temp();
-
-    let temp_manager = TempFileManager::default();
-    let temp_path = temp_manager
-        .create("transcode-output.mp4", None)
+    let job = TempJob::start();
+    let temp_path = job
+        .create(&std::env::temp_dir(), "transcode-output.mp4", None)
         .expect("failed to create temp output");
-    set_transcode_temp(Some(temp_path.clone()));
+    register_transcode_temp(&job, temp_path.clone(), &[]);
 
     let args = build_ffmpeg_command(
         input_path.to_string_lossy().as_ref(),
@@ -110,7 +110,9 @@ fn transcode_cancel_cleans_up_temp_output() {
         terminate_all_ffmpeg();
     });
 
-    let transcode_result = run_ffmpeg_blocking(args, None, None, Some(duration_secs), None, None);
+    let ctx = FfmpegContext::discover().expect("FFmpeg not found");
+    let transcode_result =
+        run_ffmpeg_blocking(&ctx, args, None, None, Some(duration_secs), None, None);
     terminate_handle.join().expect("join");
 
     assert!(
@@ -123,10 +125,82 @@ fn transcode_cancel_cleans_up_temp_output() {
         "expected Aborted error"
     );
 
-    cleanup_transcode_temp();
+    discard_transcode_temp(job.id());
     assert!(
         !temp_path.exists(),
         "temp file should be cleaned up after cancel: {:?}",
         temp_path
     );
 }
+
+#[test]
+fn cancellation_token_aborts_only_its_own_job() {
+    assert_codec_contract(CodecContract::IntegrationSmoke);
+    let env = IntegrationEnv::new();
+    let input_path = env.with_test_video("input.mp4", 10.0, VideoKind::Plain);
+    let duration_secs = 10.0_f64;
+
+    let options = opts_with(|o| {
+        o.codec = Some(default_codec());
+        o.remove_audio = Some(true);
+        o.preset = Some("slow".into());
+    });
+    let build_args = |name: &str| {
+        build_ffmpeg_command(
+            input_path.to_string_lossy().as_ref(),
+            env.path(name).to_string_lossy().as_ref(),
+            &options,
+            None,
+            None,
+            None,
+        )
+        .expect("build_ffmpeg_command")
+    };
+
+    let token = CancellationToken::new();
+    let cancelled_ctx = FfmpegContext::discover()
+        .expect("FFmpeg not found")
+        .with_cancellation(token.clone());
+    let other_ctx = FfmpegContext::discover().expect("FFmpeg not found");
+
+    let cancelled_args = build_args("cancelled.mp4");
+    let cancelled_job = thread::spawn(move || {
+        run_ffmpeg_blocking(
+            &cancelled_ctx,
+            cancelled_args,
+            None,
+            None,
+            Some(duration_secs),
+            None,
+            None,
+        )
+    });
+    let other_args = build_args("other.mp4");
+    let other_job = thread::spawn(move || {
+        run_ffmpeg_blocking(
+            &other_ctx,
+            other_args,
+            None,
+            None,
+            Some(duration_secs),
+            None,
+            None,
+        )
+    });
+
+    thread::sleep(StdDuration::from_millis(100));
+    token.cancel();
+
+    let cancelled_result = cancelled_job.join().expect("join");
+    assert!(
+        format!("{:?}", cancelled_result).contains("Aborted"),
+        "expected Aborted, got {:?}",
+        cancelled_result
+    );
+    let other_result = other_job.join().expect("join");
+    assert!(
+        other_result.is_ok(),
+        "job with another token should finish: {:?}",
+        other_result
+    );
+}
@@ -11,7 +11,7 @@ use support::{
     run_preview_with_meta_codec_override_and_assert_exists, run_transcode_and_verify,
 };
 use tiny_vid_tauri_lib::ffmpeg::ffprobe::get_video_metadata_impl;
-use tiny_vid_tauri_lib::ffmpeg::{cleanup_preview_transcode_cache, verify_video};
+use tiny_vid_tauri_lib::ffmpeg::{FfmpegContext, cleanup_preview_transcode_cache, verify_video};
 
 #[test]
 fn preview_generates_single_segment_output() {
@@ -116,7 +116,7 @@ fn preview_estimate_without_audio_is_smaller_than_input_size() {
         o.remove_audio = Some(true);
         o.preset = Some("ultrafast".into());
         o.preview_duration = Some(3);
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1]);
         o.audio_stream_count = Some(2);
         o.codec = Some(default_codec());
         #[cfg(feature = "lgpl")]
@@ -162,7 +162,7 @@ fn preview_estimate_increases_with_preserved_additional_audio_streams() {
         o.remove_audio = Some(false);
         o.preset = Some("ultrafast".into());
         o.preview_duration = Some(3);
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1]);
         o.audio_stream_count = Some(2);
         o.codec = Some(default_codec());
     });
@@ -207,7 +207,8 @@ fn preview_output_decodes_successfully() {
     let result = run_preview_and_assert_exists(&input_path, &preview_options(3), None);
 
     let compressed_path = Path::new(&result.compressed_path);
-    let verify_result = verify_video(compressed_path, Some(default_codec().as_str()));
+    let ctx = FfmpegContext::discover().expect("FFmpeg not found");
+    let verify_result = verify_video(&ctx, compressed_path, Some(default_codec().as_str()));
     if let Err(err) = verify_result {
         panic!("compressed preview should decode: {}", err);
     }
@@ -8,7 +8,9 @@ use support::{
     CodecContract, IntegrationEnv, VideoKind, assert_codec_contract, metadata, opts_with,
     run_transcode_and_verify,
 };
-use tiny_vid_tauri_lib::ffmpeg::{TranscodeOptions, build_ffmpeg_command, run_ffmpeg_blocking};
+use tiny_vid_tauri_lib::ffmpeg::{
+    FfmpegContext, TranscodeOptions, build_ffmpeg_command, run_ffmpeg_blocking,
+};
 
 fn run_transcode_case(options: TranscodeOptions, duration_secs: f32) {
     let env = IntegrationEnv::new();
@@ -127,7 +129,8 @@ fn transcode_preserves_subtitles_webm() {
     )
     .expect("build_ffmpeg_command");
 
-    let result = run_ffmpeg_blocking(args, None, None, None, None, None);
+    let ctx = FfmpegContext::discover().expect("FFmpeg not found");
+    let result = run_ffmpeg_blocking(&ctx, args, None, None, None, None, None);
     assert!(
         result.is_ok(),
         "run_ffmpeg_blocking failed: {:?}",
@@ -65,7 +65,7 @@ fn transcode_preserves_additional_audio_streams() {
     let options = opts_with(|o| {
         o.remove_audio = Some(false);
         o.preset = Some("ultrafast".into());
-        o.preserve_additional_audio_streams = Some(true);
+        o.audio_stream_indices = Some(vec![0, 1]);
         o.audio_stream_count = Some(2);
         o.codec = Some(default_codec());
     });
@@ -6,7 +6,7 @@ use std::process::{Command, ExitStatus, Stdio};
 
 use tiny_vid_tauri_lib::ffmpeg::ffprobe::get_video_metadata_impl;
 use tiny_vid_tauri_lib::ffmpeg::{
-    TranscodeOptions, build_ffmpeg_command, run_ffmpeg_blocking, verify_video,
+    FfmpegContext, TranscodeOptions, build_ffmpeg_command, run_ffmpeg_blocking, verify_video,
 };
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -184,7 +184,8 @@ pub fn run_transcode_and_verify(
     )
     .map_err(|e| e.to_string())?;
 
-    run_ffmpeg_blocking(args, None, None, duration_secs, None, None)
+    let ctx = FfmpegContext::discover().map_err(|e| format!("FFmpeg not found: {:?}", e))?;
+    run_ffmpeg_blocking(&ctx, args, None, None, duration_secs, None, None)
         .map_err(|e| format!("run_ffmpeg_blocking failed: {:?}", e))?;
 
     if !output_path.exists() {
@@ -194,7 +195,7 @@ pub fn run_transcode_and_verify(
         return Err("output file is empty".into());
     }
 
-    verify_video(output_path, options.codec.as_deref())
+    verify_video(&ctx, output_path, options.codec.as_deref())
         .map_err(|e| format!("Encoded video failed verification: {}", e))
 }
 
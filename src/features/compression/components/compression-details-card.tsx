@@ -40,7 +40,7 @@ export function CompressionDetailsCard() {
         removeAudio: cOptions.removeAudio,
         audioBitrateKbps: cOptions.audioBitrate,
         audioStreamCount: videoMetadata?.audioStreamCount,
-        preserveAdditionalAudioStreams: cOptions.preserveAdditionalAudioStreams,
+        audioStreamIndices: cOptions.audioStreamIndices,
       })
     : null;
   const isTargetSizeInvalid = Boolean(targetSizeStatus && !targetSizeStatus.ok);
@@ -58,6 +58,9 @@ export function VideoPreview() {
     originalSrc,
     compressedSrc,
     startOffsetSeconds,
+    loopEndSeconds,
+    loudness,
+    previewScale,
     videoDuration,
     previewDuration,
     previewStartSeconds,
@@ -69,6 +72,9 @@ export function VideoPreview() {
       originalSrc: s.videoPreview?.originalSrc ?? "",
       compressedSrc: s.videoPreview?.compressedSrc ?? "",
       startOffsetSeconds: s.videoPreview?.startOffsetSeconds,
+      loopEndSeconds: s.videoPreview?.loopEndSeconds,
+      loudness: s.videoPreview?.loudness,
+      previewScale: s.videoPreview?.previewScale,
       videoDuration: s.videoMetadata?.duration,
       previewDuration: s.compressionOptions?.previewDuration,
       previewStartSeconds: s.previewStartSeconds,
@@ -92,7 +98,8 @@ export function VideoPreview() {
     startOffsetSeconds ?? 0,
     [originalSrc, compressedSrc, startOffsetSeconds],
     isPreviewActive,
-    restoreStateRef
+    restoreStateRef,
+    loopEndSeconds
   );
 
   const {
@@ -276,6 +283,29 @@ export function VideoPreview() {
         >
           {isPaused ? <Play className={cn("size-4")} /> : <Pause className={cn("size-4")} />}
         </Button>
+        {loudness && (
+          <>
+            <div className={cn("mx-1 h-5 w-px bg-foreground/10")} />
+            <span
+              className={cn("px-1 text-[10px] font-medium text-foreground/80 tabular-nums")}
+              title="Integrated loudness / true peak of the preview audio with export settings"
+            >
+              {loudness.integratedLufs.toFixed(1)} LUFS
+              {loudness.truePeakDbtp != null && ` · ${loudness.truePeakDbtp.toFixed(1)} dBTP`}
+            </span>
+          </>
+        )}
+        {previewScale != null && (
+          <>
+            <div className={cn("mx-1 h-5 w-px bg-foreground/10")} />
+            <span
+              className={cn("px-1 text-[10px] font-medium text-foreground/80 tabular-nums")}
+              title="Compressed preview is scaled down by the preview resolution cap; the export and size estimate use full resolution"
+            >
+              {Math.round(previewScale * 100)}% res
+            </span>
+          </>
+        )}
       </div>
       {previewDuration != null && videoDuration != null && (
         <div className={cn("absolute inset-x-0 bottom-0 z-20 p-2")}>
@@ -27,12 +27,14 @@ import {
   getCodecInfo,
   getCodecsForFormat,
   getFormatCapabilities,
+  getPreviewCodecOptions,
+  getTuneOptionsForCodec,
   isCodec,
   isFormat,
   isPresetValue,
   presets,
+  previewResolutionCaps,
   supportsDownmixOption,
-  tuneOptions,
 } from "@/features/compression/lib/compression-options";
 import type { VideoMetadata } from "@/features/compression/lib/get-video-metadata";
 import { resolve } from "@/features/compression/lib/options-pipeline";
@@ -201,8 +203,16 @@ export function VideoSettingsAdvanced({
   const ffmpegAccordionRef = useRef<HTMLDivElement>(null);
   const availableFormats = getAvailableFormats(availableCodecs);
   const currentCodec = getCodecInfo(cOptions.codec, availableCodecs);
-  const hasNoAudio = (videoMetadata?.audioStreamCount ?? 0) === 0;
+  const codecTuneOptions = getTuneOptionsForCodec(currentCodec);
+  const previewCodecOptions = getPreviewCodecOptions(availableCodecs);
+  const audioTrackCount = videoMetadata?.audioStreamCount ?? 0;
+  const hasNoAudio = audioTrackCount === 0;
+  const selectedAudioTracks = cOptions.audioStreamIndices ?? [0];
   const isAlreadyStereo = (videoMetadata?.audioChannels ?? 0) <= 2;
+  /** GIF has no video codec, so codec, rate control and preset controls don't apply. */
+  const isGif = cOptions.outputFormat === "gif";
+  const hasEncodingPreset =
+    !isGif && currentCodec?.presetType !== "vt" && currentCodec?.presetType !== "vaapi";
   const isTargetSizeMode = cOptions.rateControlMode === "targetSize";
   const targetSupport = getTargetSizeSupport(cOptions.codec);
   const supportsTargetSize = targetSupport.supported;
@@ -213,7 +223,7 @@ export function VideoSettingsAdvanced({
     removeAudio: cOptions.removeAudio,
     audioBitrateKbps: cOptions.audioBitrate,
     audioStreamCount: videoMetadata?.audioStreamCount,
-    preserveAdditionalAudioStreams: cOptions.preserveAdditionalAudioStreams,
+    audioStreamIndices: cOptions.audioStreamIndices,
   });
   const targetSizeStatus = targetSize.result;
   const targetSizeError = targetSize.error;
@@ -270,51 +280,55 @@ export function VideoSettingsAdvanced({
             </SelectContent>
           </Select>
         </LabeledControl>
-        <LabeledControl
-          label="Codec"
-          tooltip="Video codec: the algorithm that compresses the video. Different codecs offer different compression and compatibility."
-        >
-          <Select
-            value={cOptions.codec}
-            disabled={isDisabled}
-            onValueChange={(v) => {
-              if (!isCodec(v, availableCodecs)) return;
-              setOptions(resolve({ ...cOptions, codec: v }, availableCodecs));
-            }}
+        {!isGif && (
+          <LabeledControl
+            label="Codec"
+            tooltip="Video codec: the algorithm that compresses the video. Different codecs offer different compression and compatibility."
           >
-            <SelectTrigger className={cn("w-full")}>
-              <SelectValue />
-            </SelectTrigger>
-            <SelectContent>
-              {getCodecsForFormat(cOptions.outputFormat, availableCodecs).map((codec) => (
-                <SelectItem key={codec.value} value={codec.value}>
-                  {codec.name}
-                </SelectItem>
-              ))}
-            </SelectContent>
-          </Select>
-        </LabeledControl>
-        <LabeledControl
-          label="Compression mode"
-          tooltip="Choose quality-based compression or a total output size target (two-pass)."
-        >
-          <CompressionModeToggle
-            mode={isTargetSizeMode ? "targetSize" : "quality"}
-            disabled={isDisabled}
-            supportsTargetSize={supportsTargetSize}
-            supportMessage={targetSupport.reason}
-            onChange={(mode) => {
-              setOptions({
-                ...cOptions,
-                rateControlMode: mode,
-                targetSizeMb:
-                  mode === "targetSize"
-                    ? (cOptions.targetSizeMb ?? targetSizeDefault)
-                    : cOptions.targetSizeMb,
-              });
-            }}
-          />
-        </LabeledControl>
+            <Select
+              value={cOptions.codec}
+              disabled={isDisabled}
+              onValueChange={(v) => {
+                if (!isCodec(v, availableCodecs)) return;
+                setOptions(resolve({ ...cOptions, codec: v }, availableCodecs));
+              }}
+            >
+              <SelectTrigger className={cn("w-full")}>
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {getCodecsForFormat(cOptions.outputFormat, availableCodecs).map((codec) => (
+                  <SelectItem key={codec.value} value={codec.value}>
+                    {codec.name}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+          </LabeledControl>
+        )}
+        {!isGif && (
+          <LabeledControl
+            label="Compression mode"
+            tooltip="Choose quality-based compression or a total output size target (two-pass)."
+          >
+            <CompressionModeToggle
+              mode={isTargetSizeMode ? "targetSize" : "quality"}
+              disabled={isDisabled}
+              supportsTargetSize={supportsTargetSize}
+              supportMessage={targetSupport.reason}
+              onChange={(mode) => {
+                setOptions({
+                  ...cOptions,
+                  rateControlMode: mode,
+                  targetSizeMb:
+                    mode === "targetSize"
+                      ? (cOptions.targetSizeMb ?? targetSizeDefault)
+                      : cOptions.targetSizeMb,
+                });
+              }}
+            />
+          </LabeledControl>
+        )}
         {!isTargetSizeMode && (
           <LabeledControl
             label="Quality"
@@ -363,7 +377,7 @@ export function VideoSettingsAdvanced({
             </div>
           </LabeledControl>
         )}
-        {currentCodec?.presetType !== "vt" && (
+        {hasEncodingPreset && (
           <LabeledControl
             label="Encoding Preset"
             tooltip="Encoding speed vs compression. Slower presets produce smaller files at the same quality but take longer to encode."
@@ -389,10 +403,10 @@ export function VideoSettingsAdvanced({
             </Select>
           </LabeledControl>
         )}
-        {currentCodec?.supportsTune && (
+        {codecTuneOptions.length > 0 && (
           <LabeledControl
             label="Tune"
-            tooltip="x264 tune: optimizes for specific content (film, animation, etc.). Only applies to H.264."
+            tooltip="Encoder tune: optimizes for specific content (film, animation, etc.). Options depend on the codec."
           >
             <Select
               value={cOptions.tune ?? "none"}
@@ -408,7 +422,7 @@ export function VideoSettingsAdvanced({
                 <SelectValue />
               </SelectTrigger>
               <SelectContent>
-                {tuneOptions.map((t) => (
+                {codecTuneOptions.map((t) => (
                   <SelectItem key={t.value} value={t.value}>
                     {t.name}
                   </SelectItem>
@@ -417,6 +431,49 @@ export function VideoSettingsAdvanced({
             </Select>
           </LabeledControl>
         )}
+        {(cOptions.codec === "libx264" || cOptions.codec === "libx265") && (
+          <LabeledControl
+            label="Prioritize"
+            tooltip="Where the encoder spends bits at low bitrates. Center favors the middle of the frame; Text keeps edges such as small text in screen recordings sharp."
+          >
+            <Select
+              value={cOptions.roiPriority ?? "none"}
+              disabled={isDisabled}
+              onValueChange={(v) => {
+                setOptions({
+                  ...cOptions,
+                  roiPriority: v === "center" || v === "text" ? v : undefined,
+                });
+              }}
+            >
+              <SelectTrigger className={cn("w-full")}>
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="none">Whole frame</SelectItem>
+                <SelectItem value="center">Center</SelectItem>
+                <SelectItem value="text">Text</SelectItem>
+              </SelectContent>
+            </Select>
+          </LabeledControl>
+        )}
+        <CheckboxWithTooltip
+          id="screenRecording"
+          label="Screen recording"
+          tooltip={
+            videoMetadata?.likelyScreenRecording && cOptions.contentType === undefined
+              ? "Detected from the file's encoder tag. Tunes for still content, longer keyframe intervals and keeps 60fps."
+              : "Tunes for still content, longer keyframe intervals and keeps 60fps sources smooth."
+          }
+          checked={
+            cOptions.contentType === "screenRecording" ||
+            (cOptions.contentType === undefined && (videoMetadata?.likelyScreenRecording ?? false))
+          }
+          onCheckedChange={(c) =>
+            setOptions({ ...cOptions, contentType: c ? "screenRecording" : "general" })
+          }
+          disabled={isDisabled}
+        />
         <LabeledControl
           label="Resolution Scale"
           tooltip="Resize output (scale filter). 1.0 = original size. Lower values shrink resolution and file size; aspect ratio preserved, dimensions kept even for encoders."
@@ -465,22 +522,31 @@ export function VideoSettingsAdvanced({
           onCheckedChange={(c) => setOptions({ ...cOptions, removeAudio: c })}
           disabled={isDisabled || hasNoAudio}
         />
-        {(videoMetadata?.audioStreamCount ?? 0) > 1 && (
-          <CheckboxWithTooltip
-            id="preserveAdditionalAudioStreams"
-            label="Preserve additional audio streams"
-            tooltip={
-              cOptions.removeAudio
-                ? "Enable audio to preserve additional streams"
-                : cOptions.outputFormat === "webm"
-                  ? "WebM supports a single audio stream"
-                  : "Include all audio streams in the output (transcoded to AAC/Opus). Only the first stream is used for preview."
-            }
-            checked={cOptions.preserveAdditionalAudioStreams ?? false}
-            onCheckedChange={(c) => setOptions({ ...cOptions, preserveAdditionalAudioStreams: c })}
-            disabled={isDisabled || cOptions.outputFormat === "webm" || cOptions.removeAudio}
-          />
-        )}
+        {audioTrackCount > 1 &&
+          Array.from({ length: audioTrackCount }, (_, track) => (
+            <CheckboxWithTooltip
+              key={track}
+              id={`audioTrack${track}`}
+              label={`Keep audio track ${track + 1}`}
+              tooltip={
+                cOptions.removeAudio
+                  ? "Enable audio to choose tracks"
+                  : cOptions.outputFormat === "webm"
+                    ? "WebM keeps a single audio track (the first one selected)"
+                    : "Kept tracks are transcoded to AAC/Opus in their original order. Only the first track is used for preview."
+              }
+              checked={selectedAudioTracks.includes(track)}
+              onCheckedChange={(c) =>
+                setOptions({
+                  ...cOptions,
+                  audioStreamIndices: c
+                    ? [...selectedAudioTracks, track].sort((a, b) => a - b)
+                    : selectedAudioTracks.filter((t) => t !== track),
+                })
+              }
+              disabled={isDisabled || cOptions.removeAudio}
+            />
+          ))}
         <LabeledControl
           label="Audio Bitrate"
           tooltip={
@@ -552,6 +618,22 @@ export function VideoSettingsAdvanced({
             disabled={isDisabled}
           />
         )}
+        <CheckboxWithTooltip
+          id="ignoreErrors"
+          label="Ignore decode errors"
+          tooltip="Salvage partially corrupted files (interrupted downloads, bad SD cards) by skipping broken data instead of failing."
+          checked={cOptions.ignoreErrors ?? false}
+          onCheckedChange={(c) => setOptions({ ...cOptions, ignoreErrors: c })}
+          disabled={isDisabled}
+        />
+        <CheckboxWithTooltip
+          id="reproducible"
+          label="Reproducible output"
+          tooltip="Same input and settings give a bit-identical file on every run: fixed encoder threads, no encoder tags or timestamps. Software encoders only."
+          checked={cOptions.reproducible ?? false}
+          onCheckedChange={(c) => setOptions({ ...cOptions, reproducible: c })}
+          disabled={isDisabled}
+        />
       </InputGroup>
       <InputGroup title="Preview" value="preview">
         <CheckboxWithTooltip
@@ -579,6 +661,59 @@ export function VideoSettingsAdvanced({
             }}
           />
         </LabeledControl>
+        <LabeledControl
+          label="Max resolution"
+          tooltip="Caps the compressed preview of 4K/8K sources so it generates quickly on slower machines. The export keeps full resolution and the size estimate is corrected for the difference."
+        >
+          <Select
+            value={String(cOptions.previewResolutionCap ?? "original")}
+            disabled={isDisabled}
+            onValueChange={(v) => {
+              setOptions({
+                ...cOptions,
+                previewResolutionCap: v === "original" ? undefined : Number(v),
+              });
+            }}
+          >
+            <SelectTrigger className={cn("w-full")}>
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              {previewResolutionCaps.map((c) => (
+                <SelectItem key={c.value} value={c.value}>
+                  {c.name}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+        </LabeledControl>
+        <LabeledControl
+          label="Original preview encoder"
+          tooltip="Used when the source can't be played directly (e.g. HEVC or ProRes) and the original preview has to be re-encoded. Auto picks the fastest working H.264 encoder, hardware first."
+        >
+          <Select
+            value={cOptions.previewCodec ?? "auto"}
+            disabled={isDisabled}
+            onValueChange={(v) => {
+              setOptions({
+                ...cOptions,
+                previewCodec: v === "auto" ? undefined : v,
+              });
+            }}
+          >
+            <SelectTrigger className={cn("w-full")}>
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="auto">Auto</SelectItem>
+              {previewCodecOptions.map((c) => (
+                <SelectItem key={c.value} value={c.value}>
+                  {c.name}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+        </LabeledControl>
       </InputGroup>
       <InputGroup title="FFmpeg command" value="ffmpeg-command">
         <div
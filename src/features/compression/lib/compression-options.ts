@@ -1,56 +1,111 @@
-import type { CodecInfo } from "@/types/tauri";
+import type { CodecInfo, ContentType } from "@/types/tauri";
 
 export type LicenseProfile = "standalone" | "lgpl";
 export type RateControlMode = "quality" | "targetSize";
 
 /**
  * Codec metadata. Must stay in sync with backend src-tauri/src/codec.rs CODEC_TABLE.
- * presetType "vt" = VideoToolbox (hardware); others = software encoders.
+ * presetType "vt" = VideoToolbox, "nvenc" = NVIDIA NVENC, "qsv" = Intel Quick Sync,
+ * "vaapi" = VA-API on Linux, "amf" = AMD AMF (hardware); "gif" = the GIF palette encoder, which
+ * has no video codec settings; others = software encoders.
  */
 const CODEC_REGISTRY = {
   libx264: {
     name: "H.264 (Widest support)",
-    supportsTune: true,
     presetType: "x264",
     formats: ["mp4", "mkv"],
   },
   libx265: {
     name: "H.265 (Smaller files)",
-    supportsTune: false,
     presetType: "x265",
     formats: ["mp4", "mkv"],
   },
   libsvtav1: {
     name: "AV1 (Smallest files)",
-    supportsTune: false,
     presetType: "av1",
     formats: ["mp4", "webm", "mkv"],
   },
   "libvpx-vp9": {
     name: "VP9 (Browser-friendly WebM)",
-    supportsTune: false,
     presetType: "vp9",
     formats: ["webm", "mkv"],
   },
   h264_videotoolbox: {
     name: "H.264 (VideoToolbox)",
-    supportsTune: false,
     presetType: "vt",
     formats: ["mp4", "mkv"],
   },
   hevc_videotoolbox: {
     name: "H.265 (VideoToolbox)",
-    supportsTune: false,
     presetType: "vt",
     formats: ["mp4", "mkv"],
   },
+  h264_nvenc: {
+    name: "H.264 (NVIDIA NVENC)",
+    presetType: "nvenc",
+    formats: ["mp4", "mkv"],
+  },
+  hevc_nvenc: {
+    name: "H.265 (NVIDIA NVENC)",
+    presetType: "nvenc",
+    formats: ["mp4", "mkv"],
+  },
+  h264_qsv: {
+    name: "H.264 (Intel Quick Sync)",
+    presetType: "qsv",
+    formats: ["mp4", "mkv"],
+  },
+  hevc_qsv: {
+    name: "H.265 (Intel Quick Sync)",
+    presetType: "qsv",
+    formats: ["mp4", "mkv"],
+  },
+  h264_vaapi: {
+    name: "H.264 (VA-API)",
+    presetType: "vaapi",
+    formats: ["mp4", "mkv"],
+  },
+  hevc_vaapi: {
+    name: "H.265 (VA-API)",
+    presetType: "vaapi",
+    formats: ["mp4", "mkv"],
+  },
+  h264_amf: {
+    name: "H.264 (AMD AMF)",
+    presetType: "amf",
+    formats: ["mp4", "mkv"],
+  },
+  hevc_amf: {
+    name: "H.265 (AMD AMF)",
+    presetType: "amf",
+    formats: ["mp4", "mkv"],
+  },
+  gif: {
+    name: "GIF (Animated)",
+    presetType: "gif",
+    formats: ["gif"],
+  },
 } as const;
 
 const FORMAT_REGISTRY = {
   mp4: {
     name: "MP4",
     extension: "mp4",
-    codecs: ["libx264", "libx265", "libsvtav1", "h264_videotoolbox", "hevc_videotoolbox"],
+    codecs: [
+      "libx264",
+      "libx265",
+      "libsvtav1",
+      "h264_videotoolbox",
+      "hevc_videotoolbox",
+      "h264_nvenc",
+      "hevc_nvenc",
+      "h264_qsv",
+      "hevc_qsv",
+      "h264_vaapi",
+      "hevc_vaapi",
+      "h264_amf",
+      "hevc_amf",
+    ],
     defaultCodec: "libx264",
   },
   webm: {
@@ -69,9 +124,24 @@ const FORMAT_REGISTRY = {
       "libvpx-vp9",
       "h264_videotoolbox",
       "hevc_videotoolbox",
+      "h264_nvenc",
+      "hevc_nvenc",
+      "h264_qsv",
+      "hevc_qsv",
+      "h264_vaapi",
+      "hevc_vaapi",
+      "h264_amf",
+      "hevc_amf",
     ],
     defaultCodec: "libx264",
   },
+  /** Animated GIF: no video codec to pick; frame rate and width are capped by the backend. */
+  gif: {
+    name: "GIF",
+    extension: "gif",
+    codecs: ["gif"],
+    defaultCodec: "gif",
+  },
 } as const;
 
 export type Codec = keyof typeof CODEC_REGISTRY;
@@ -79,7 +149,7 @@ export type Format = keyof typeof FORMAT_REGISTRY;
 
 const CODECS = Object.keys(CODEC_REGISTRY) as Codec[];
 
-const FORMATS: Format[] = ["mp4", "webm", "mkv"];
+const FORMATS: Format[] = ["mp4", "webm", "mkv", "gif"];
 
 /** Validates against backend codecs when provided; otherwise against known codec set. */
 export function isCodec(s: string, availableCodecs?: CodecInfo[]): s is Codec {
@@ -147,8 +217,22 @@ export const tuneOptions = [
   { name: "SSIM", value: "ssim" },
 ] as const;
 
-export function getTuneOptionsForCodec(codec: Codec): readonly { name: string; value: string }[] {
-  return CODEC_REGISTRY[codec].supportsTune ? tuneOptions : [];
+/** Tune options valid for the codec (from backend CodecInfo.tunes), plus "none". Empty when unsupported. */
+export function getTuneOptionsForCodec(
+  codecInfo: CodecInfo | undefined
+): readonly { name: string; value: string }[] {
+  const tunes = codecInfo?.tunes ?? [];
+  if (tunes.length === 0) return [];
+  return tuneOptions.filter((t) => t.value === "none" || tunes.includes(t.value));
+}
+
+/** H.264 encoders that can re-encode the original preview (it has to play in the webview). */
+export function getPreviewCodecOptions(
+  availableCodecs: CodecInfo[]
+): { name: string; value: string }[] {
+  return availableCodecs
+    .filter((c) => c.value === "libx264" || c.value.startsWith("h264_"))
+    .map((c) => ({ name: c.name, value: c.value }));
 }
 
 export const audioBitratePresets = [
@@ -161,6 +245,14 @@ export const audioBitratePresets = [
   { name: "320 kbps", value: 320 },
 ] as const;
 
+export const previewResolutionCaps = [
+  { name: "Original", value: "original" },
+  { name: "2160p", value: "2160" },
+  { name: "1440p", value: "1440" },
+  { name: "1080p", value: "1080" },
+  { name: "720p", value: "720" },
+] as const;
+
 export const maxBitratePresets = [
   { name: "No limit", value: "none" },
   { name: "Low (500 kbps)", value: 500 },
@@ -185,11 +277,39 @@ export interface CompressionOptions {
   generatePreview?: boolean;
   previewDuration?: number;
   tune?: string;
-  preserveAdditionalAudioStreams?: boolean;
+  /** 0-based audio tracks to keep. Unset keeps the first track. */
+  audioStreamIndices?: number[];
   preserveMetadata?: boolean;
   audioBitrate?: number;
   downmixToStereo?: boolean;
   preserveSubtitles?: boolean;
+  ignoreErrors?: boolean;
+  reproducible?: boolean;
+  coverImagePath?: string;
+  coverFrameSeconds?: number;
+  backgroundAudioPath?: string;
+  backgroundAudioMode?: "replace" | "mix";
+  backgroundAudioGainDb?: number;
+  backgroundAudioDucking?: boolean;
+  keepSegments?: [number, number][];
+  cropX?: number;
+  cropY?: number;
+  cropWidth?: number;
+  cropHeight?: number;
+  rotation?: 90 | 180 | 270;
+  flipHorizontal?: boolean;
+  flipVertical?: boolean;
+  trimStartSecs?: number;
+  trimEndSecs?: number;
+  aqMode?: number;
+  aqStrength?: number;
+  roiPriority?: "center" | "text";
+  /** Unset follows metadata detection. */
+  contentType?: ContentType;
+  /** Shorter-side cap for previews of large sources; unset previews at full resolution. */
+  previewResolutionCap?: number;
+  /** H.264 encoder for the original preview when the source can't be stream-copied. */
+  previewCodec?: string;
 }
 
 export function supportsTwoPassCodec(codec: string): boolean {
@@ -235,7 +355,7 @@ export function convertQualityForCodecSwitch(
   oldCodec: string,
   newCodec: string
 ): number {
-  if (oldCodec === newCodec) return oldQuality;
+  if (oldCodec === newCodec || oldCodec === "gif" || newCodec === "gif") return oldQuality;
   const effectiveCrfOld = qualityToCrf(oldQuality, oldCodec);
   const perceptualRef = effectiveCrfOld - getPerceptualOffset(oldCodec);
   const effectiveCrfNew = perceptualRef + getPerceptualOffset(newCodec);
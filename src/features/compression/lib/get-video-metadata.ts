@@ -1,11 +1,13 @@
 import { invoke } from "@tauri-apps/api/core";
 
-import type { GetVideoMetadataResult } from "@/types/tauri";
+import type { GenerationLossAdvisory, GetVideoMetadataResult } from "@/types/tauri";
 
 export interface VideoMetadata {
   duration: number;
   width: number;
   height: number;
+  /** Clockwise rotation of the source; width/height are already upright. */
+  rotation: number;
   size: number;
   sizeMB: number;
   fps: number;
@@ -20,17 +22,32 @@ export interface VideoMetadata {
   subtitleStreamCount?: number;
   audioCodecName?: string;
   audioChannels?: number;
+  audioBitRate?: number;
   encoder?: string;
+  videoStreamCount: number;
+  mainVideoStreamIndex: number;
+  likelyScreenRecording: boolean;
+  generationLoss?: GenerationLossAdvisory;
 }
 
-export async function getVideoMetadataFromPath(filePath: string): Promise<VideoMetadata> {
+/**
+ * `quick` limits ffprobe analysis for faster results on large files; may be less complete.
+ * `targetCodec` lets the generation loss advisory account for the codec about to be used.
+ */
+export async function getVideoMetadataFromPath(
+  filePath: string,
+  options?: { quick?: boolean; targetCodec?: string }
+): Promise<VideoMetadata> {
   const meta = await invoke<GetVideoMetadataResult>("get_video_metadata", {
     path: filePath,
+    quick: options?.quick,
+    targetCodec: options?.targetCodec,
   });
   return {
     duration: meta.duration,
     width: meta.width,
     height: meta.height,
+    rotation: meta.rotation,
     size: meta.size,
     sizeMB: meta.sizeMb,
     fps: meta.fps,
@@ -45,6 +62,11 @@ export async function getVideoMetadataFromPath(filePath: string): Promise<VideoM
     subtitleStreamCount: meta.subtitleStreamCount,
     audioCodecName: meta.audioCodecName,
     audioChannels: meta.audioChannels,
+    audioBitRate: meta.audioBitRate,
     encoder: meta.encoder,
+    videoStreamCount: meta.videoStreamCount,
+    mainVideoStreamIndex: meta.mainVideoStreamIndex,
+    likelyScreenRecording: meta.likelyScreenRecording,
+    generationLoss: meta.generationLoss,
   };
 }
@@ -29,11 +29,13 @@ const DEFAULT_OPTIONS: CompressionOptions = {
   generatePreview: true,
   previewDuration: 3,
   tune: undefined,
-  preserveAdditionalAudioStreams: false,
+  audioStreamIndices: undefined,
   preserveMetadata: false,
   audioBitrate: 128,
   downmixToStereo: false,
   preserveSubtitles: false,
+  ignoreErrors: false,
+  reproducible: false,
 };
 
 const MP4 = "mp4" satisfies Format;
@@ -101,8 +103,8 @@ export function resolve(
   }
 
   const codecInfo = getCodecInfo(codec, codecs);
-  const supportsTune = codecInfo?.supportsTune ?? false;
-  const tune = supportsTune ? (partial.tune ?? undefined) : undefined;
+  const tune =
+    partial.tune && codecInfo?.tunes.includes(partial.tune) ? partial.tune : undefined;
   let rateControlMode: RateControlMode =
     partial.rateControlMode === "targetSize" ? "targetSize" : "quality";
   const targetSizeMb = Number.isFinite(partial.targetSizeMb ?? NaN)
@@ -157,7 +159,7 @@ export function createInitialOptions(
   };
 
   const codecInfo = getCodecInfo(initialCodec, codecs);
-  if (!codecInfo?.supportsTune) {
+  if (!codecInfo?.tunes.length) {
     base.tune = undefined;
   }
 
@@ -3,9 +3,13 @@ const STEP_LABELS: Record<string, string> = {
   transcode: "Compressing…",
   transcode_pass1: "Analyzing (pass 1)…",
   transcode_pass2: "Compressing (pass 2)…",
+  quality_search: "Finding quality for target size…",
+  transcode_retry: "Compressing again for target size…",
   preview_extract: "Extracting segments…",
   preview_transcode: "Compressing…",
   preview_estimate: "Estimating size…",
+  compose: "Composing picture-in-picture…",
+  batch_analyze: "Estimating batch sizes…",
 };
 
 export function getProgressStepLabel(step: string | null): string {
@@ -14,7 +14,7 @@ export interface TargetSizeComputationInput {
   removeAudio?: boolean;
   audioBitrateKbps?: number;
   audioStreamCount?: number;
-  preserveAdditionalAudioStreams?: boolean;
+  audioStreamIndices?: number[];
 }
 
 export interface TargetSizeComputationResult {
@@ -47,13 +47,15 @@ export interface TargetSizeStatus {
 function resolveAudioStreamCount(
   input: Pick<
     TargetSizeComputationInput,
-    "removeAudio" | "audioStreamCount" | "preserveAdditionalAudioStreams"
+    "removeAudio" | "audioStreamCount" | "audioStreamIndices"
   >
 ): number {
   if (input.removeAudio) return 0;
   const count = input.audioStreamCount ?? 1;
   if (count <= 0) return 0;
-  if (input.preserveAdditionalAudioStreams) return count;
+  if (input.audioStreamIndices) {
+    return new Set(input.audioStreamIndices.filter((track) => track < count)).size;
+  }
   return 1;
 }
 
@@ -3,7 +3,18 @@ import { getCurrentWindow } from "@tauri-apps/api/window";
 import { useEffect, useRef } from "react";
 
 import { useCompressionStore, WorkerState } from "@/features/compression/store/compression-store";
-import type { FfmpegErrorPayload, FfmpegProgressPayload } from "@/types/tauri";
+import {
+  isSupportedEvent,
+  upgradeOpenFilesPayload,
+  upgradeProgressPayload,
+  type Versioned,
+} from "@/lib/event-version";
+import type {
+  EstimatePartialPayload,
+  FfmpegErrorPayload,
+  FfmpegProgressPayload,
+  OpenFilesPayload,
+} from "@/types/tauri";
 
 function handleProgressUpdate(payload: FfmpegProgressPayload) {
   const s = useCompressionStore.getState();
@@ -13,11 +24,16 @@ function handleProgressUpdate(payload: FfmpegProgressPayload) {
   ) {
     useCompressionStore.setState({
       progress: payload.progress,
-      progressStep: payload.step ?? null,
+      progressStep: payload.step?.stage ?? null,
     });
   }
 }
 
+function handleEstimatePartial(payload: EstimatePartialPayload) {
+  if (useCompressionStore.getState().workerState !== WorkerState.GeneratingPreview) return;
+  useCompressionStore.setState({ estimate: payload });
+}
+
 export function useCompressionStoreInit() {
   const effectIdRef = useRef(0);
 
@@ -29,62 +45,74 @@ export function useCompressionStoreInit() {
 
     const setup = async () => {
       await useCompressionStore.getState().initBuildVariant();
-      const [unProgress, unError, unComplete, unOpenFile, unMenuOpenFile] = await Promise.all([
-        win.listen<FfmpegProgressPayload>("ffmpeg-progress", (e) => {
-          handleProgressUpdate(e.payload);
-        }),
-        win.listen<FfmpegErrorPayload>("ffmpeg-error", (e) => {
-          const { summary, detail } = e.payload;
-          if (summary === "Aborted") return;
-          const s = useCompressionStore.getState();
-          if (s.workerState === WorkerState.Transcoding) {
-            useCompressionStore.setState({
-              workerState: WorkerState.Idle,
-              error: {
-                type: "Transcode Error",
-                message: summary,
-                detail,
-              },
-            });
-          } else if (s.workerState === WorkerState.GeneratingPreview) {
-            useCompressionStore.setState({
-              workerState: WorkerState.Idle,
-              error: {
-                type: "Preview Error",
-                message: summary,
-                detail,
-              },
-            });
-          }
-        }),
-        win.listen("ffmpeg-complete", () => {
-          const s = useCompressionStore.getState();
-          if (s.workerState === WorkerState.Transcoding) {
-            useCompressionStore.setState({
-              workerState: WorkerState.Idle,
-              progress: 1,
-            });
-          }
-        }),
-        win.listen<string[]>("open-file", (e) => {
-          const paths = e.payload;
-          if (Array.isArray(paths) && paths.length > 0) {
-            void useCompressionStore.getState().selectPath(paths[0]);
-          }
-        }),
-        win.listen("menu-open-file", () => {
-          void useCompressionStore.getState().browseAndSelectFile();
-        }),
-      ]);
+      const [unProgress, unEstimate, unError, unComplete, unOpenFile, unMenuOpenFile] =
+        await Promise.all([
+          win.listen<Versioned<FfmpegProgressPayload>>("ffmpeg-progress", (e) => {
+            if (!isSupportedEvent(e.payload)) return;
+            handleProgressUpdate(upgradeProgressPayload(e.payload));
+          }),
+          win.listen<Versioned<EstimatePartialPayload>>("estimate-partial", (e) => {
+            if (!isSupportedEvent(e.payload)) return;
+            handleEstimatePartial(e.payload);
+          }),
+          win.listen<Versioned<FfmpegErrorPayload>>("ffmpeg-error", (e) => {
+            if (!isSupportedEvent(e.payload)) return;
+            const { summary, detail } = e.payload;
+            if (summary === "Aborted") return;
+            const s = useCompressionStore.getState();
+            if (s.workerState === WorkerState.Transcoding) {
+              useCompressionStore.setState({
+                workerState: WorkerState.Idle,
+                error: {
+                  type: "Transcode Error",
+                  message: summary,
+                  detail,
+                },
+              });
+            } else if (s.workerState === WorkerState.GeneratingPreview) {
+              useCompressionStore.setState({
+                workerState: WorkerState.Idle,
+                error: {
+                  type: "Preview Error",
+                  message: summary,
+                  detail,
+                },
+              });
+            }
+          }),
+          // Version 1 emitted null here, which isSupportedEvent reads as version 1.
+          win.listen<Versioned<object> | null>("ffmpeg-complete", (e) => {
+            if (!isSupportedEvent(e.payload)) return;
+            const s = useCompressionStore.getState();
+            if (s.workerState === WorkerState.Transcoding) {
+              useCompressionStore.setState({
+                workerState: WorkerState.Idle,
+                progress: 1,
+              });
+            }
+          }),
+          win.listen<Versioned<OpenFilesPayload> | string[]>("open-file", (e) => {
+            const payload = upgradeOpenFilesPayload(e.payload);
+            if (!isSupportedEvent(payload)) return;
+            const paths = payload.paths;
+            if (Array.isArray(paths) && paths.length > 0) {
+              void useCompressionStore.getState().selectPath(paths[0]);
+            }
+          }),
+          win.listen("menu-open-file", () => {
+            void useCompressionStore.getState().browseAndSelectFile();
+          }),
+        ]);
       if (cancelled || effectId !== effectIdRef.current) {
         unProgress();
+        unEstimate();
         unError();
         unComplete();
         unOpenFile();
         unMenuOpenFile();
         return;
       }
-      unlisteners.push(unProgress, unError, unComplete, unOpenFile, unMenuOpenFile);
+      unlisteners.push(unProgress, unEstimate, unError, unComplete, unOpenFile, unMenuOpenFile);
       useCompressionStore.setState({ listenersReady: true });
 
       const pendingPaths = await invoke<string[]>("get_pending_opened_files");
@@ -22,6 +22,8 @@ import type {
   CodecInfo,
   FfmpegPreviewResult,
   FfmpegSizeEstimate,
+  LoudnessInfo,
+  TempOutput,
   TranscodeOptions,
 } from "@/types/tauri";
 
@@ -37,6 +39,47 @@ export interface VideoPreview {
   compressedSrc: string;
   /** Start offset (seconds) of original. Delay compressed playback by this to sync. */
   startOffsetSeconds?: number;
+  /** Original playback time at which both videos loop back together; absent when unknown. */
+  loopEndSeconds?: number;
+  /** Loudness of the preview window's audio with export settings; absent when audio is removed. */
+  loudness?: LoudnessInfo;
+  /** Extra scale applied to the compressed preview by the preview resolution cap. */
+  previewScale?: number;
+}
+
+/**
+ * Seconds to delay compressed playback by. Prefers the first-frame PTS difference (accounts for
+ * edit lists and B-frame reordering); falls back to the original's container start offset.
+ */
+function previewSyncOffsetSeconds(result: FfmpegPreviewResult): number | undefined {
+  const { originalFirstFramePts, compressedFirstFramePts } = result;
+  if (originalFirstFramePts != null && compressedFirstFramePts != null) {
+    return originalFirstFramePts - compressedFirstFramePts;
+  }
+  return result.startOffsetSeconds;
+}
+
+/**
+ * Original playback time to loop both previews at: the shorter of the two files, cut to a whole
+ * number of compressed frames so neither video shows a partial or missing last frame.
+ */
+function previewLoopEndSeconds(
+  result: FfmpegPreviewResult,
+  offsetSeconds: number
+): number | undefined {
+  const { originalTiming, compressedTiming } = result;
+  if (originalTiming == null || compressedTiming == null || compressedTiming.frameCount <= 0) {
+    return undefined;
+  }
+  // Playback clamps negative offsets to 0, so the loop bound does too.
+  const offset = Math.max(0, offsetSeconds);
+  const frameSeconds = compressedTiming.durationSeconds / compressedTiming.frameCount;
+  const span = Math.min(
+    originalTiming.durationSeconds - offset,
+    compressedTiming.durationSeconds
+  );
+  const frames = Math.floor(span / frameSeconds + 1e-6);
+  return frames > 0 ? offset + frames * frameSeconds : undefined;
 }
 
 function toRustOptions(
@@ -44,7 +87,15 @@ function toRustOptions(
   durationSecs?: number,
   metadata?: Pick<
     VideoMetadata,
-    "audioStreamCount" | "subtitleStreamCount" | "audioCodecName" | "audioChannels"
+    | "audioStreamCount"
+    | "subtitleStreamCount"
+    | "audioCodecName"
+    | "audioChannels"
+    | "audioBitRate"
+    | "videoStreamCount"
+    | "mainVideoStreamIndex"
+    | "likelyScreenRecording"
+    | "fps"
   >
 ): TranscodeOptions {
   return {
@@ -61,7 +112,7 @@ function toRustOptions(
     targetSizeMb: opts.targetSizeMb,
     previewDuration: opts.previewDuration ?? 3,
     durationSecs,
-    preserveAdditionalAudioStreams: opts.preserveAdditionalAudioStreams ?? false,
+    audioStreamIndices: opts.audioStreamIndices,
     audioStreamCount: metadata?.audioStreamCount,
     preserveMetadata: opts.preserveMetadata ?? false,
     audioBitrate: opts.audioBitrate,
@@ -70,6 +121,35 @@ function toRustOptions(
     subtitleStreamCount: metadata?.subtitleStreamCount,
     audioCodecName: metadata?.audioCodecName,
     audioChannels: metadata?.audioChannels,
+    sourceAudioBitRate: metadata?.audioBitRate,
+    videoStreamCount: metadata?.videoStreamCount,
+    videoStreamIndex: metadata?.mainVideoStreamIndex,
+    ignoreErrors: opts.ignoreErrors ?? false,
+    reproducible: opts.reproducible ?? false,
+    coverImagePath: opts.coverImagePath,
+    coverFrameSeconds: opts.coverFrameSeconds,
+    backgroundAudioPath: opts.backgroundAudioPath,
+    backgroundAudioMode: opts.backgroundAudioMode,
+    backgroundAudioGainDb: opts.backgroundAudioGainDb,
+    backgroundAudioDucking: opts.backgroundAudioDucking,
+    keepSegments: opts.keepSegments,
+    cropX: opts.cropX,
+    cropY: opts.cropY,
+    cropWidth: opts.cropWidth,
+    cropHeight: opts.cropHeight,
+    rotation: opts.rotation,
+    flipHorizontal: opts.flipHorizontal,
+    flipVertical: opts.flipVertical,
+    trimStartSecs: opts.trimStartSecs,
+    trimEndSecs: opts.trimEndSecs,
+    aqMode: opts.aqMode,
+    aqStrength: opts.aqStrength,
+    roiPriority: opts.roiPriority,
+    contentType:
+      opts.contentType ?? (metadata?.likelyScreenRecording ? "screenRecording" : undefined),
+    sourceFps: metadata?.fps,
+    previewResolutionCap: opts.previewResolutionCap,
+    previewCodec: opts.previewCodec,
   };
 }
 
@@ -80,6 +160,17 @@ let commandPreviewRequestId = 0;
 let debounceCommandPreviewTimer: ReturnType<typeof setTimeout> | null = null;
 let selectPathRequestId = 0;
 let activePreviewBlobUrls: string[] = [];
+/** Request id of the preview or transcode this window started last; terminate cancels it. */
+let activeRequestId: string | null = null;
+
+/**
+ * Chooses the request id of a new preview or transcode up front, so terminate can cancel it
+ * even before the backend has started the job.
+ */
+function startCancellableRequest(): string {
+  activeRequestId = crypto.randomUUID();
+  return activeRequestId;
+}
 
 const SCRUB_PREVIEW_DEBOUNCE_MS = 200;
 const OPTIONS_PREVIEW_DEBOUNCE_MS = 300;
@@ -140,11 +231,52 @@ function getTargetSizeError(
     removeAudio: options.removeAudio,
     audioBitrateKbps: options.audioBitrate,
     audioStreamCount: metadata?.audioStreamCount,
-    preserveAdditionalAudioStreams: options.preserveAdditionalAudioStreams,
+    audioStreamIndices: options.audioStreamIndices,
     requireDuration: true,
   });
   return status.error;
 }
+
+/**
+ * Prompts for a destination and commits the temp output there. A failed commit keeps the output
+ * registered, so the dialog is shown again until the user saves or cancels.
+ */
+async function saveTempOutput(
+  output: TempOutput,
+  originalPath: string,
+  dialogOptions: Parameters<typeof save>[0],
+  onError: (error: ResultError) => void
+): Promise<void> {
+  const discard = () =>
+    tryCatch(
+      () => invoke("discard_temp_output", { commitToken: output.commitToken }),
+      "Cleanup Error"
+    );
+  for (;;) {
+    const outputPath = await save(dialogOptions);
+    if (!outputPath) {
+      await discard();
+      return;
+    }
+
+    const commitResult = await tryCatch(
+      () =>
+        invoke<string>("commit_temp_output", {
+          commitToken: output.commitToken,
+          dest: outputPath,
+          originalPath,
+        }),
+      "Save Error"
+    );
+    if (commitResult.ok) return;
+    if (commitResult.aborted) {
+      await discard();
+      return;
+    }
+    onError(commitResult.error);
+  }
+}
+
 export interface CompressionState {
   inputPath: string | null;
   videoPreview: VideoPreview | null;
@@ -368,13 +500,14 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
     set({ workerState: WorkerState.Transcoding, progress: 0, error: null });
     const transcodeResult = await tryCatch(
       () =>
-        invoke<string>("ffmpeg_transcode_to_temp", {
+        invoke<TempOutput>("ffmpeg_transcode_to_temp", {
           inputPath,
           options: toRustOptions(
             compressionOptions,
             videoMetadata?.duration,
             videoMetadata ?? undefined
           ),
+          requestId: startCancellableRequest(),
         }),
       "Transcode Error"
     );
@@ -388,7 +521,7 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
       await get().terminate();
       return;
     }
-    const tempPath = transcodeResult.value;
+    const output = transcodeResult.value;
     set({ workerState: WorkerState.Idle, progress: 1 });
 
     set({ isSaving: true });
@@ -397,35 +530,20 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
         const inputFilename = inputPath.split(/[/\\]/).pop() ?? "output";
         const basename = inputFilename.replace(/\.[^.]+$/, "") || "output";
         const ext = getDefaultExtension(compressionOptions.outputFormat);
-        const outputPath = await save({
-          defaultPath: `compressed-${basename}.${ext}`,
-          filters: [
-            {
-              name: "Video",
-              extensions: [ext],
-            },
-          ],
-        });
-
-        if (!outputPath) {
-          await tryCatch(() => invoke("cleanup_temp_file", { path: tempPath }), "Cleanup Error");
-          return;
-        }
-
-        const moveResult = await tryCatch(
-          () =>
-            invoke("move_compressed_file", {
-              source: tempPath,
-              dest: outputPath,
-            }),
-          "Save Error"
+        await saveTempOutput(
+          output,
+          inputPath,
+          {
+            defaultPath: `compressed-${basename}.${ext}`,
+            filters: [
+              {
+                name: "Video",
+                extensions: [ext],
+              },
+            ],
+          },
+          (error) => set({ error })
         );
-        if (!moveResult.ok) {
-          if (!moveResult.aborted) {
-            set({ error: moveResult.error });
-          }
-          await tryCatch(() => invoke("cleanup_temp_file", { path: tempPath }), "Cleanup Error");
-        }
       },
       "Save Error",
       {
@@ -443,7 +561,7 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
     set({ workerState: WorkerState.ExtractingFrame, error: null });
     const extractResult = await tryCatch(
       () =>
-        invoke<string>("extract_first_frame", {
+        invoke<TempOutput>("extract_first_frame", {
           inputPath,
           quality: compressionOptions.quality,
           scale: compressionOptions.scale,
@@ -459,7 +577,7 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
       }
       return;
     }
-    const tempPath = extractResult.value;
+    const output = extractResult.value;
     set({ workerState: WorkerState.Idle });
 
     set({ isSaving: true });
@@ -467,30 +585,15 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
       async () => {
         const inputFilename = inputPath.split(/[/\\]/).pop() ?? "output";
         const basename = inputFilename.replace(/\.[^.]+$/, "") || "output";
-        const outputPath = await save({
-          defaultPath: `${basename}-poster.jpg`,
-          filters: [{ name: "JPEG Image", extensions: ["jpg"] }],
-        });
-
-        if (!outputPath) {
-          await tryCatch(() => invoke("cleanup_temp_file", { path: tempPath }), "Cleanup Error");
-          return;
-        }
-
-        const moveResult = await tryCatch(
-          () =>
-            invoke("move_compressed_file", {
-              source: tempPath,
-              dest: outputPath,
-            }),
-          "Save Error"
+        await saveTempOutput(
+          output,
+          inputPath,
+          {
+            defaultPath: `${basename}-poster.jpg`,
+            filters: [{ name: "JPEG Image", extensions: ["jpg"] }],
+          },
+          (error) => set({ error })
         );
-        if (!moveResult.ok) {
-          if (!moveResult.aborted) {
-            set({ error: moveResult.error });
-          }
-          await tryCatch(() => invoke("cleanup_temp_file", { path: tempPath }), "Cleanup Error");
-        }
       },
       "Save Error",
       {
@@ -556,6 +659,7 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
           ),
           previewStartSeconds,
           includeEstimate,
+          requestId: startCancellableRequest(),
         }),
       "Preview Error"
     );
@@ -574,12 +678,16 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
       }
       releaseActivePreviewBlobUrls();
       activePreviewBlobUrls = nextBlobUrls;
+      const startOffsetSeconds = previewSyncOffsetSeconds(result.value);
       set({
         previewStartSeconds,
         videoPreview: {
           originalSrc,
           compressedSrc,
-          startOffsetSeconds: result.value.startOffsetSeconds,
+          startOffsetSeconds,
+          loopEndSeconds: previewLoopEndSeconds(result.value, startOffsetSeconds ?? 0),
+          loudness: result.value.loudness,
+          previewScale: result.value.previewScale,
         },
         ...(result.value.estimate != null && { estimate: result.value.estimate }),
         workerState: WorkerState.Idle,
@@ -668,7 +776,11 @@ export const useCompressionStore = create<CompressionState>((set, get) => ({
   },
 
   terminate: async () => {
-    await tryCatch(() => invoke("ffmpeg_terminate"), "Terminate Error");
+    const requestId = activeRequestId;
+    activeRequestId = null;
+    if (requestId !== null) {
+      await tryCatch(() => invoke("ffmpeg_cancel_request", { requestId }), "Terminate Error");
+    }
     set({
       workerState: WorkerState.Idle,
       progress: 0,
@@ -39,7 +39,8 @@ export function useVideoSync(
   startOffsetSeconds = 0,
   deps: DependencyList = [],
   enabled = true,
-  restoreStateRef?: RefObject<VideoSyncRestoreState | null>
+  restoreStateRef?: RefObject<VideoSyncRestoreState | null>,
+  loopEndSeconds?: number
 ): { togglePlayPause: () => void; isPaused: boolean } {
   const [isPaused, setIsPaused] = useState(true);
 
@@ -75,10 +76,15 @@ export function useVideoSync(
 
       const initAt = performance.now();
       let intervalId: ReturnType<typeof setInterval> | null = null;
+      let loopTimeoutId: ReturnType<typeof setTimeout> | null = null;
       let hasStarted = false;
       let pendingSecondaryResume = false;
       let lastResyncAt = 0;
       const offset = Number.isFinite(startOffsetSeconds) ? Math.max(0, startOffsetSeconds) : 0;
+      const loopEnd =
+        loopEndSeconds != null && Number.isFinite(loopEndSeconds) && loopEndSeconds > offset
+          ? loopEndSeconds
+          : null;
 
       const log = (event: string, data?: unknown) => {
         if (!DEBUG) return;
@@ -93,6 +99,13 @@ export function useVideoSync(
         }
       };
 
+      const clearLoopTimeout = () => {
+        if (loopTimeoutId !== null) {
+          clearTimeout(loopTimeoutId);
+          loopTimeoutId = null;
+        }
+      };
+
       const startLoop = () => {
         if (intervalId !== null) return;
         intervalId = setInterval(() => {
@@ -126,16 +139,48 @@ export function useVideoSync(
         safePause(secondary);
       };
 
+      const restartLoop = (reason: string) => {
+        clearLoopTimeout();
+        stopLoop();
+        resetForLoop();
+        safePlay(primary);
+        sync(reason);
+        startLoop();
+      };
+
+      // The sync interval is too coarse to hit the loop end on time, so the last stretch is
+      // timed exactly. Both files stop at the same frame instead of running to their own ends.
+      const scheduleLoopEnd = (primaryTime: number) => {
+        if (loopEnd === null || loopTimeoutId !== null) return;
+        const remainingMs = ((loopEnd - primaryTime) / (primary.playbackRate || 1)) * 1000;
+        if (remainingMs > SYNC_INTERVAL_MS) return;
+        loopTimeoutId = setTimeout(
+          () => {
+            loopTimeoutId = null;
+            if (!primary.paused && !primary.seeking) {
+              restartLoop("loop-end");
+            }
+          },
+          Math.max(0, remainingMs)
+        );
+      };
+
       const sync = (reason: string) => {
         if (primary.readyState < HTMLMediaElement.HAVE_METADATA) return;
         if (secondary.readyState < HTMLMediaElement.HAVE_METADATA) return;
 
         if (primary.paused || primary.seeking || primary.ended) {
+          clearLoopTimeout();
           safePause(secondary);
           return;
         }
 
         const primaryTime = Math.max(0, primary.currentTime);
+        if (loopEnd !== null && primaryTime >= loopEnd) {
+          restartLoop("loop-end");
+          return;
+        }
+        scheduleLoopEnd(primaryTime);
         if (primaryTime < offset) {
           if (Math.abs(secondary.currentTime) > 0.001) {
             secondary.currentTime = 0;
@@ -239,6 +284,7 @@ export function useVideoSync(
       const handlePrimaryPause = () => {
         setIsPaused(true);
         stopLoop();
+        clearLoopTimeout();
         safePause(secondary);
       };
 
@@ -247,6 +293,7 @@ export function useVideoSync(
       };
 
       const handlePrimarySeeking = () => {
+        clearLoopTimeout();
         safePause(secondary);
       };
 
@@ -258,11 +305,7 @@ export function useVideoSync(
       };
 
       const handlePrimaryEnded = () => {
-        stopLoop();
-        resetForLoop();
-        safePlay(primary);
-        sync("primary-ended");
-        startLoop();
+        restartLoop("primary-ended");
       };
 
       const handleSecondarySeeked = () => {
@@ -313,6 +356,7 @@ export function useVideoSync(
 
       innerCleanup = () => {
         stopLoop();
+        clearLoopTimeout();
         for (const { target, event, handler } of listeners) {
           target.removeEventListener(event, handler);
         }
@@ -327,7 +371,15 @@ export function useVideoSync(
       }
       innerCleanup?.();
     };
-  }, [enabled, primaryRef, secondaryRef, startOffsetSeconds, restoreStateRef, ...deps]);
+  }, [
+    enabled,
+    primaryRef,
+    secondaryRef,
+    startOffsetSeconds,
+    restoreStateRef,
+    loopEndSeconds,
+    ...deps,
+  ]);
 
   return { togglePlayPause, isPaused };
 }
@@ -0,0 +1,37 @@
+import type { FfmpegProgressPayload, OpenFilesPayload, ProgressStep } from "@/types/tauri";
+
+/** Event payload shape this frontend is written against (EVENT_VERSION in events.rs). */
+export const EVENT_VERSION = 2;
+
+/** Struct event payloads carry eventVersion; cores from before versioning omit it (version 1). */
+export type Versioned<T> = T & { eventVersion?: number };
+
+/** True for payloads of the current or the previous version. */
+export function isSupportedEvent(payload: { eventVersion?: number } | null | undefined): boolean {
+  const version = payload?.eventVersion ?? 1;
+  if (version >= EVENT_VERSION - 1 && version <= EVENT_VERSION) return true;
+  console.warn(`Ignoring event payload with unsupported eventVersion ${version}`);
+  return false;
+}
+
+/** Version 1 ffmpeg-progress: step was the bare stage id. */
+type ProgressPayloadV1 = Omit<FfmpegProgressPayload, "step"> & { step?: string | ProgressStep };
+
+/** Upgrades a version 1 ffmpeg-progress payload; a bare stage id becomes a single-stage step. */
+export function upgradeProgressPayload(
+  payload: Versioned<ProgressPayloadV1>
+): Versioned<FfmpegProgressPayload> {
+  const { step } = payload;
+  if (typeof step !== "string") return payload as Versioned<FfmpegProgressPayload>;
+  return {
+    ...payload,
+    step: { stage: step, index: 0, total: 1, humanKey: `step.${step}` },
+  };
+}
+
+/** Upgrades a version 1 open-file payload, which was the bare list of paths. */
+export function upgradeOpenFilesPayload(
+  payload: Versioned<OpenFilesPayload> | string[]
+): Versioned<OpenFilesPayload> {
+  return Array.isArray(payload) ? { eventVersion: 1, paths: payload } : payload;
+}
@@ -3,13 +3,116 @@ export interface FfmpegErrorPayload {
   detail: string;
 }
 
+/** EBU R128 loudness of the preview window's audio, encoded with the export settings. */
+export interface LoudnessInfo {
+  integratedLufs: number;
+  truePeakDbtp?: number;
+  loudnessRangeLu?: number;
+}
+
+/** A finished export waiting in temp. Pass commitToken to commit_temp_output or discard_temp_output. */
+export interface TempOutput {
+  path: string;
+  commitToken: string;
+  /** Present when the transcode is not smaller than its input. */
+  largerOutput?: LargerOutputWarning;
+  /** Present with vmafCheck when the output could be scored. */
+  vmaf?: VmafScore;
+  /** CPU time and energy estimate of a transcode. */
+  energy?: JobEnergy;
+}
+
+/**
+ * FFmpeg CPU time of a job. The watts/Wh figures are a proxy (CPU seconds times a typical
+ * per-core power) and are absent for hardware encoders, whose GPU use FFmpeg cannot see.
+ */
+export interface JobEnergy {
+  cpuSeconds: number;
+  averageCores: number;
+  estimatedWatts?: number;
+  estimatedWh?: number;
+}
+
+/** VMAF (0-100) of an export against its source, over a few sampled windows. */
+export interface VmafScore {
+  /** Mean over the windows; ~93+ is hard to tell from the source. */
+  score: number;
+  minWindowScore: number;
+  windows: number;
+}
+
+/** The output (or its estimate) is not smaller than the input. */
+export interface LargerOutputWarning {
+  inputBytes: number;
+  /** Written size, or estimate.bestSize before encoding. */
+  outputBytes: number;
+  /** The options allow largerOutput "remux" (a stream copy of the source). */
+  remuxAvailable: boolean;
+  /** The encode was replaced with a stream copy. */
+  remuxed: boolean;
+}
+
+export interface VideoTiming {
+  frameCount: number;
+  durationSeconds: number;
+}
+
 export interface FfmpegPreviewResult {
   originalPath: string;
   compressedPath: string;
   /** Start offset (seconds) of original. Delay compressed playback by this to sync. */
   startOffsetSeconds?: number;
+  /** PTS (seconds) of the first decoded frame of the original preview file. */
+  originalFirstFramePts?: number;
+  /** PTS (seconds) of the first decoded frame of the compressed preview file. */
+  compressedFirstFramePts?: number;
+  /** Frame count and exact duration of the original preview file. */
+  originalTiming?: VideoTiming;
+  /** Frame count and exact duration of the compressed preview file. */
+  compressedTiming?: VideoTiming;
+  /** Present when audio is kept in the output. */
+  loudness?: LoudnessInfo;
+  /** Extra scale (0-1) applied to the compressed preview by previewResolutionCap. */
+  previewScale?: number;
+  /** Compressed vs original preview; present with previewQualityMetrics. */
+  quality?: QualityMetrics;
   /** Present when includeEstimate was true. */
   estimate?: FfmpegSizeEstimate;
+  /** Source size vs estimate.bestSize, formatted; present with estimate. */
+  sizeSummary?: SizeSummary;
+  /** Ratio and bits per pixel against estimate.bestSize; present with estimate. */
+  compression?: CompressionMetrics;
+  /** Present when estimate.bestSize is not smaller than the source. */
+  largerOutput?: LargerOutputWarning;
+  /** Id the preview ran under (see JobStartedPayload). */
+  jobId?: number;
+}
+
+/** Objective quality of a compressed preview against the original. */
+export interface QualityMetrics {
+  /** 0-1 over all planes; 1 is identical. */
+  ssim: number;
+  /** Mean PSNR in dB, capped at 100 for identical pictures. */
+  psnrDb: number;
+}
+
+/** Codec-independent compression figures. bpp = video bitrate / (width * height * fps). */
+export interface CompressionMetrics {
+  /** Input size / output size. */
+  compressionRatio: number;
+  sourceBitsPerPixel?: number;
+  outputBitsPerPixel?: number;
+}
+
+/** Input and output size, raw and formatted in the app locale. */
+export interface SizeSummary {
+  inputBytes: number;
+  outputBytes: number;
+  inputSizeLabel: string;
+  outputSizeLabel: string;
+  /** Negative when the output is larger. */
+  percentSaved: number;
+  changeLabel: string;
 }
 
 export interface FfmpegSizeEstimate {
@@ -22,15 +125,65 @@ export interface FfmpegSizeEstimate {
   sampleSecondsTotal: number;
 }
 
+/** "estimate-partial" event: estimate from the samples encoded so far; bounds narrow as samples arrive. */
+export interface EstimatePartialPayload extends FfmpegSizeEstimate {
+  samplesDone: number;
+  samplesPlanned: number;
+}
+
+/** Stage of a multi-step job; index is 0-based. humanKey is the backend i18n key. */
+export interface ProgressStep {
+  stage: string;
+  index: number;
+  total: number;
+  humanKey: string;
+}
+
+/**
+ * ffmpeg-progress payload. ffmpeg_transcode_to_temp, ffmpeg_preview and compose_picture_in_picture
+ * also accept an onProgress Channel<Versioned<FfmpegProgressPayload>>, which receives that call's
+ * progress instead of the window event. Emitted payloads carry eventVersion (see event-version.ts).
+ */
 export interface FfmpegProgressPayload {
   progress: number;
-  step?: string;
+  step?: ProgressStep;
+  /** step translated into the locale chosen with set_locale. */
+  stepLabel?: string;
+}
+
+/** Progress event rate limits (get_progress_throttle / set_progress_throttle). */
+export interface ProgressThrottle {
+  /** 0 disables the rate limit. */
+  maxEventsPerSec: number;
+  /** Smallest progress change (0-1) worth an event. */
+  minDelta: number;
+}
+
+/**
+ * Size estimate sampling (get_estimate_sampling / set_estimate_sampling, or per call via
+ * TranscodeOptions.estimateSampling). Omitted fields take the defaults.
+ */
+export interface EstimateSampling {
+  /** Samples spread from 5% to 95% of the export. Default 3; ignored with positions. */
+  sampleCount?: number;
+  /** Seconds per sample (0.25-30). Default 1.5. */
+  sampleSecs?: number;
+  /** Sample centres as fractions (0-1) of the export. */
+  positions?: number[];
+  /** Most seconds sampled, extra samples for uneven footage included. Default 7.5. */
+  maxSampledSecs?: number;
 }
 
+/** Locales for Rust-produced strings (get_locale / set_locale). */
+export type Locale = "en" | "de" | "es" | "fr";
+
 export interface GetVideoMetadataResult {
   duration: number;
+  /** Upright (display) size; rotated sources have width/height swapped from the coded size. */
   width: number;
   height: number;
+  /** Clockwise rotation (0/90/180/270) from the source's display matrix. */
+  rotation: number;
   size: number;
   sizeMb: number;
   fps: number;
@@ -45,22 +198,395 @@ export interface GetVideoMetadataResult {
   subtitleStreamCount?: number;
   audioCodecName?: string;
   audioChannels?: number;
+  /** First audio stream bit rate (bits/s). */
+  audioBitRate?: number;
   encoder?: string;
+  /** Video streams excluding cover art. */
+  videoStreamCount: number;
+  /** `0:v:N` index of the largest non-cover-art video stream. */
+  mainVideoStreamIndex: number;
+  /** Encoder tag names a screen recorder (OBS, ShareX, ...). */
+  likelyScreenRecording: boolean;
+  /** Re-encoding would likely lose quality for little size; judged against targetCodec when given. */
+  generationLoss?: GenerationLossAdvisory;
+}
+
+export type GenerationLossKind = "heavilyCompressed" | "sameCodecSimilarBitrate";
+
+/** From get_video_metadata: the source is already lean, so a remux may serve better than a re-encode. */
+export interface GenerationLossAdvisory {
+  kind: GenerationLossKind;
+  sourceBitsPerPixel: number;
+  /** What the source's codec typically spends on its frame size at the default quality. */
+  typicalBitsPerPixel: number;
+  /** Expected output bpp of the target codec at the default quality. */
+  targetBitsPerPixel?: number;
+  /** Localized (set_locale). */
+  message: string;
 }
 
 export interface CodecInfo {
   value: string;
   name: string;
   formats: string[];
-  supportsTune: boolean;
+  /** Valid tune values; empty when the encoder has no tune option. */
+  tunes: string[];
   presetType: string;
 }
 
+/** From get_speed_preset_tunings: values changed on top of a fast x264/x265 preset. Fields the
+ * preset already sets that way are omitted. */
+export interface SpeedPresetTuning {
+  preset: string;
+  bframes?: number;
+  rcLookahead?: number;
+}
+
 export interface BuildVariantResult {
   variant: "standalone" | "lgpl";
   codecs: CodecInfo[];
 }
 
+export interface SelfTestCheck {
+  ok: boolean;
+  detail: string;
+}
+
+export interface CodecSelfTest {
+  codec: string;
+  ok: boolean;
+  elapsedMs: number;
+  error?: string;
+}
+
+export interface SelfTestReport {
+  variant: "standalone" | "lgpl";
+  passed: boolean;
+  ffmpeg: SelfTestCheck;
+  ffprobe: SelfTestCheck;
+  tempDir: SelfTestCheck;
+  codecDetection: SelfTestCheck;
+  codecs: CodecSelfTest[];
+}
+
+export interface BinaryIntegrity {
+  name: string;
+  path: string;
+  status: "ok" | "corrupted" | "missing" | "unverified";
+  expectedSha256?: string;
+  actualSha256?: string;
+  repairable: boolean;
+}
+
+/** Payload of check_ffmpeg_integrity / repair_bundled_ffmpeg and the ffmpeg-integrity-failed event. */
+export interface IntegrityReport {
+  bundled: boolean;
+  healthy: boolean;
+  binaries: BinaryIntegrity[];
+}
+
+/** Channel for check_ffmpeg_update / install_ffmpeg_update. */
+export type FfmpegUpdateChannel = "stable";
+
+/** A manifest entry. install_ffmpeg_update takes only its channel and version. */
+export interface FfmpegChannelBuild {
+  version: string;
+  target: string;
+  url: string;
+  sha256: string;
+}
+
+export interface FfmpegUpdateCheck {
+  currentVersion?: string;
+  available?: FfmpegChannelBuild;
+}
+
+export interface FfmpegUpdateInstallResult {
+  version: string;
+  ffmpegPath: string;
+  restartRequired: boolean;
+}
+
+export interface EncoderHealth {
+  codec: string;
+  ok: boolean;
+  failure?: "session_limit" | "unavailable";
+  detail?: string;
+}
+
+export type RepairStrategy = "regenerate_pts" | "rebuild_index" | "remux_mkv";
+
+export interface RepairAttempt {
+  strategy: RepairStrategy;
+  ok: boolean;
+  error?: string;
+}
+
+/** Result of repair_file. outputPath is a temp copy; the source is never modified. */
+export interface RepairReport {
+  restored: boolean;
+  neededRepair: boolean;
+  strategy?: RepairStrategy;
+  outputPath?: string;
+  attempts: RepairAttempt[];
+}
+
+/** Result of export_contact_sheet. path is a temp JPEG. */
+export interface ContactSheetResult {
+  path: string;
+  tiles: number;
+  timestampsBurned: boolean;
+}
+
+/** One frame of a storyboard sprite, from time until the next tile's time. */
+export interface StoryboardTile {
+  time: number;
+  x: number;
+  y: number;
+}
+
+/** Result of generate_storyboard. spritePath is a temp JPEG; indexPath holds the other fields as JSON. */
+export interface Storyboard {
+  spritePath: string;
+  indexPath: string;
+  columns: number;
+  rows: number;
+  tileWidth: number;
+  tileHeight: number;
+  intervalSecs: number;
+  durationSecs: number;
+  tiles: StoryboardTile[];
+}
+
+/** Config for set_webhook_config. milestones are progress percentages (default 25/50/75). */
+export interface WebhookConfig {
+  url: string;
+  secret?: string;
+  milestones?: number[];
+}
+
+/** Result of analyze_silence. Pass keepSegments to a transcode for a jump-cut export. */
+export interface SilenceAnalysis {
+  silences: [number, number][];
+  keepSegments: [number, number][];
+  sourceSeconds: number;
+  removedSeconds: number;
+}
+
+/** Result of analyze_dead_frames. keepSegments (when present) trims the dead head/tail. */
+export interface DeadFrameAnalysis {
+  leadingSeconds: number;
+  trailingSeconds: number;
+  sourceSeconds: number;
+  keepSegments?: [number, number][];
+}
+
+export type ContentType = "general" | "screenRecording";
+
+/** Result of suggest_options; apply contentType/tune/roiPriority to the compression options. */
+export interface SuggestedOptions {
+  contentKind: "screencast" | "animation" | "film";
+  contentType: ContentType;
+  tune?: string;
+  roiPriority?: "center" | "text";
+  motionScore: number;
+  noiseLevel: number;
+}
+
+/** Result of suggest_quality(path, codec). */
+export interface QualitySuggestion {
+  quality: number;
+  /** Missing when the source bitrate is unknown; quality is then the default. */
+  sourceBitsPerPixel?: number;
+  predictedBitsPerPixel: number;
+  /** Even the suggested quality is predicted to produce a file larger than the source. */
+  outputMayExceedInput: boolean;
+}
+
+/** Batch-wide settings for resolve_batch_jobs, analyze_batch and run_batch. manifest and checksum apply to run_batch only. */
+export interface BatchSettings {
+  rules?: BatchSkipRules;
+  layout?: BatchOutputLayout;
+  manifest?: BatchManifestConfig;
+  checksum?: ChecksumOptions;
+}
+
+/** A batch input; overrides holds only the TranscodeOptions fields that differ from the base. */
+export interface BatchFile {
+  inputPath: string;
+  overrides?: Partial<TranscodeOptions>;
+  /** Final destination. Takes precedence over the batch output layout. */
+  outputPath?: string;
+}
+
+/** Mirrors the input tree under outputRoot; collisions become "name (1).mp4" in job order. */
+export interface BatchOutputLayout {
+  outputRoot: string;
+  /** Default: the deepest folder containing every input. */
+  sourceRoot?: string;
+  /** Reuse names of files already on disk instead of numbering around them. */
+  overwrite?: boolean;
+}
+
+/** Batch skip rules, checked against ffprobe metadata before encoding. */
+export interface BatchSkipRules {
+  /** Skip inputs already at or under this size. */
+  maxSizeMb?: number;
+  /** Skip inputs already in one of these codecs ("hevc", "h265", "av1", ...). */
+  skipCodecs?: string[];
+  /** Skip files whose destination already exists. */
+  skipExistingOutputs?: boolean;
+}
+
+export type BatchSkipReason =
+  | { kind: "underSize"; sizeMb: number; maxSizeMb: number }
+  | { kind: "codec"; codec: string }
+  | { kind: "outputExists"; path: string };
+
+/** Effective options for one batch file (base, then probed metadata, then overrides). */
+export interface ResolvedBatchJob {
+  index: number;
+  inputPath: string;
+  options: TranscodeOptions;
+  /** Option keys taken from the file's overrides. */
+  overridden: (keyof TranscodeOptions)[];
+  /** Final destination (explicit or from the layout). Missing: the output stays in temp. */
+  outputPath?: string;
+  /** Set when a skip rule matched; the file is not encoded. */
+  skipped?: BatchSkipReason;
+}
+
+/** One row of the analyze_batch dry-run report. */
+export interface BatchReportEntry extends ResolvedBatchJob {
+  inputSize: number;
+  /** Missing for skipped files and when estimation failed (see error). */
+  estimate?: FfmpegSizeEstimate;
+  /** Input size vs estimate.bestSize, formatted. */
+  sizeSummary?: SizeSummary;
+  compression?: CompressionMetrics;
+  largerOutput?: LargerOutputWarning;
+  error?: string;
+}
+
+/** analyze_batch result. Predicted totals cover files that would be encoded and have an estimate. */
+export interface BatchReport {
+  entries: BatchReportEntry[];
+  totalInputBytes: number;
+  encodedInputBytes: number;
+  predictedOutputBytes: number;
+  predictedLowBytes: number;
+  predictedHighBytes: number;
+  skippedCount: number;
+  failedCount: number;
+  /** encodedInputBytes vs predictedOutputBytes, formatted. */
+  sizeSummary?: SizeSummary;
+}
+
+/** Payload of batch-job-complete and one entry of run_batch's result. */
+export interface BatchJobResult extends ResolvedBatchJob {
+  /** outputPath after a successful move, or the temp file when the job had no destination. */
+  writtenPath?: string;
+  /** Present when the output was left in temp; pass to commit_temp_output or discard_temp_output. */
+  commitToken?: string;
+  error?: string;
+  /** Wall time of the encode; missing for skipped jobs. */
+  encodeSeconds?: number;
+  /** Hash of writtenPath when run_batch had a checksum step. */
+  checksum?: OutputChecksum;
+  /** Source size and modification time unchanged by the job; missing for skipped jobs. */
+  sourceUntouched?: boolean;
+  /** Input vs written size, formatted; present for successful jobs. */
+  sizeSummary?: SizeSummary;
+  /** Present for successful jobs whose source could be probed. */
+  compression?: CompressionMetrics;
+  /** Present when the written file is not smaller than the source. */
+  largerOutput?: LargerOutputWarning;
+  /** Present with vmafCheck when the written file could be scored. */
+  vmaf?: VmafScore;
+  /** CPU time and energy estimate of the encode; absent for skipped jobs. */
+  energy?: JobEnergy;
+}
+
+/** One output for commit_batch_outputs, which delivers all items or none. */
+export interface BatchCommitItem {
+  /** writtenPath of a job result that still has its commitToken. */
+  source: string;
+  dest: string;
+  /** Input the output was made from; the commit refuses to overwrite it. */
+  originalPath?: string;
+}
+
+/** compute_output_checksum result; sidecarPath is set when a .sha256 file was written. */
+export interface OutputChecksum {
+  sha256: string;
+  sidecarPath?: string;
+}
+
+/** Checksum step for run_batch: hash each output, optionally with a .sha256 sidecar. */
+export interface ChecksumOptions {
+  sidecar?: boolean;
+}
+
+/** Manifest file settings for run_batch. path defaults to tiny-vid-manifest.<ext> in the output root. */
+export interface BatchManifestConfig {
+  format?: "json" | "csv";
+  path?: string;
+  /** Decode every output to check it before it is listed. */
+  verify?: boolean;
+}
+
+export interface BatchManifestEntry {
+  inputPath: string;
+  outputPath?: string;
+  status: "encoded" | "skipped" | "failed";
+  inputBytes: number;
+  outputBytes?: number;
+  /** Output size / input size. */
+  ratio?: number;
+  codec: string;
+  durationSecs?: number;
+  encodeSeconds?: number;
+  verify: "notRun" | "passed" | "failed";
+  sha256?: string;
+  sourceUntouched?: boolean;
+  /** Encode error, skip reason or verify error. */
+  note?: string;
+}
+
+/** get_last_batch_manifest result. path is set when the manifest was written to disk. */
+export interface BatchManifest {
+  /** Unix seconds. */
+  createdAt: number;
+  entries: BatchManifestEntry[];
+  totalInputBytes: number;
+  totalOutputBytes: number;
+  path?: string;
+}
+
+export type QueueStatus = "pending" | "running" | "done" | "failed" | "skipped" | "cancelled";
+
+/** Compression queue entry; the full list is also emitted as "queue-updated" (QueueUpdatedPayload). */
+export interface QueueItem extends BatchFile {
+  id: number;
+  status: QueueStatus;
+  result?: BatchJobResult;
+  /** Set when the item could not be resolved (probe failed, bad overrides). */
+  error?: string;
+}
+
+/** Payload of queue-updated: the whole queue, in order. */
+export interface QueueUpdatedPayload {
+  items: QueueItem[];
+}
+
+/** Layout for compose_picture_in_picture. size is the overlay width as a fraction of the main width (0.1-0.5). */
+export interface PipLayout {
+  position?: "top-left" | "top-right" | "bottom-left" | "bottom-right";
+  size?: number;
+  margin?: number;
+  audioSource?: "main" | "overlay";
+}
+
 export interface TranscodeOptions {
   codec?: string;
   quality?: number;
@@ -73,9 +599,12 @@ export interface TranscodeOptions {
   outputFormat?: string;
   rateControlMode?: "quality" | "targetSize";
   targetSizeMb?: number;
+  /** Export only, targetSize mode: search for a quality on sampled encodes instead of two-pass. */
+  qualitySearch?: boolean;
   previewDuration?: number;
   durationSecs?: number;
-  preserveAdditionalAudioStreams?: boolean;
+  /** 0-based audio tracks to keep, in output order. Unset keeps the first; empty keeps none. */
+  audioStreamIndices?: number[];
   audioStreamCount?: number;
   preserveMetadata?: boolean;
   audioBitrate?: number;
@@ -84,4 +613,132 @@ export interface TranscodeOptions {
   subtitleStreamCount?: number;
   audioCodecName?: string;
   audioChannels?: number;
+  /** From metadata (bits/s); sizes passthrough audio in estimates. */
+  sourceAudioBitRate?: number;
+  videoStreamCount?: number;
+  /** `0:v:N` index to encode; only the selected video stream is mapped. */
+  videoStreamIndex?: number;
+  ignoreErrors?: boolean;
+  /** Bit-identical output across runs (software encoders): pinned threads, bitexact muxing. */
+  reproducible?: boolean;
+  /** Image embedded as cover art in MP4/MKV exports. Takes precedence over coverFrameSeconds. */
+  coverImagePath?: string;
+  /** Source frame (seconds) embedded as cover art in MP4/MKV exports. */
+  coverFrameSeconds?: number;
+  /** Audio file replacing or mixed into the source audio, looped/cut to the video length. */
+  backgroundAudioPath?: string;
+  backgroundAudioMode?: "replace" | "mix";
+  /** dB; default 0 (replace) or -15 (mix). */
+  backgroundAudioGainDb?: number;
+  /** Mix only: lower the background audio under the source audio. */
+  backgroundAudioDucking?: boolean;
+  /** Source ranges [start, end] in seconds to keep (e.g. SilenceAnalysis.keepSegments). */
+  keepSegments?: [number, number][];
+  /** Crop rectangle in source pixels, applied before scaling; width and height are required. */
+  cropX?: number;
+  cropY?: number;
+  cropWidth?: number;
+  cropHeight?: number;
+  /** Clockwise, on top of the source's own orientation. */
+  rotation?: 90 | 180 | 270;
+  flipHorizontal?: boolean;
+  flipVertical?: boolean;
+  /** Export only: source seconds to start the output at. */
+  trimStartSecs?: number;
+  /** Export only: source seconds to end the output at; unset runs to the end. */
+  trimEndSecs?: number;
+  /** x264 0-3 / x265 0-4. */
+  aqMode?: number;
+  /** 0.0-3.0. */
+  aqStrength?: number;
+  roiPriority?: "center" | "text";
+  contentType?: ContentType;
+  /** Source frame rate from metadata; screen recordings keep up to 60fps. */
+  sourceFps?: number;
+  /** Preview only: shorter-side cap (e.g. 1080) for the compressed preview and estimate samples. */
+  previewResolutionCap?: number;
+  /** Preview only: H.264 encoder for the original preview; unset picks the fastest working one. */
+  previewCodec?: string;
+  /** Preview only: report SSIM/PSNR of the compressed preview (one more decode of both). */
+  previewQualityMetrics?: boolean;
+  /** Estimate only: sampling for this call instead of the app-wide setting. */
+  estimateSampling?: EstimateSampling;
+  /** Export only: what to do when the output is not smaller than the input. Default "warn". */
+  largerOutput?: LargerOutputPolicy;
+  /** Export only: score the output with VMAF (needs FFmpeg with libvmaf). Skipped for crop,
+   * rotation, flips, cut segments and GIF. */
+  vmafCheck?: boolean;
+  /** Extra encoder flag/value pairs after the app's own (e.g. ["-g", "120"]). Allowlisted flags
+   * only; ignored for GIF. */
+  extraVideoArgs?: string[];
+  /** Extra muxer flag/value pairs (e.g. ["-metadata", "title=Clip"]). Allowlisted flags only. */
+  extraOutputArgs?: string[];
+}
+
+/**
+ * From list_presets / save_preset / delete_preset / import_presets: options saved under a name.
+ * export_presets writes them as `.tinyvidpreset` files.
+ */
+export interface NamedPreset {
+  name: string;
+  options: TranscodeOptions;
+}
+
+/** "remux" replaces the encode with a stream copy when the picture is unchanged. */
+export type LargerOutputPolicy = "warn" | "remux" | "refuse";
+
+/** quick_compress target: Discord 10 MB, Discord Nitro 500 MB, WhatsApp 16 MB, email 25 MB. */
+export type PlatformPreset = "discord" | "discordNitro" | "whatsapp" | "email";
+
+export type JobKind = "transcode" | "preview" | "batch" | "compose";
+
+/**
+ * Payload of ffmpeg-job-started, emitted when a transcode, preview, batch job or composition
+ * starts. Pass jobId to get_live_stderr or ffmpeg_cancel_job while the job runs.
+ */
+export interface JobStartedPayload {
+  jobId: number;
+  kind: JobKind;
+}
+
+/** Payload of open-file: files the OS asked the app to open, first one first. */
+export interface OpenFilesPayload {
+  paths: string[];
+}
+
+/** Payload of ffmpeg-salvage-report, emitted after an ignoreErrors transcode. */
+export interface SalvageReport {
+  sourceSeconds: number;
+  salvagedSeconds: number;
+  lostSeconds: number;
+}
+
+/** add_watch_folder input. preset names a saved preset; its options are read when the watcher starts. */
+export interface WatchFolderConfig {
+  folder: string;
+  destination: string;
+  preset: string;
+  recursive?: boolean;
+}
+
+/** From add_watch_folder / remove_watch_folder / list_watch_folders. */
+export interface WatchFolder extends WatchFolderConfig {
+  id: number;
+}
+
+/** Payload of watch-file-detected, emitted when a new file in a watch folder is about to be compressed. */
+export interface WatchFileDetected {
+  watcherId: number;
+  inputPath: string;
+}
+
+/**
+ * Payload of watch-job-complete. error is set when the file could not be resolved (probe
+ * failed); encode failures are in result.
+ */
+export interface WatchJobComplete {
+  watcherId: number;
+  inputPath: string;
+  result?: BatchJobResult;
+  error?: string;
 }